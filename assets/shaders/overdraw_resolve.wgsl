@@ -0,0 +1,62 @@
+// Maps the layer counts of the overdraw debug mode to a heat ramp.
+const OVERDRAW_MAX: f32 = 16.0; // Layers shown as the hottest colour
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@group(0)
+@binding(0)
+var overdraw_texture: texture_2d<f32>;
+
+var<private> positions: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(-1.0,  1.0),
+    vec2<f32>( 1.0,  1.0),
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>( 1.0,  1.0),
+    vec2<f32>( 1.0, -1.0),
+);
+
+var<private> uvs: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>( 0.0,  1.0),
+    vec2<f32>( 0.0,  0.0),
+    vec2<f32>( 1.0,  0.0),
+    vec2<f32>( 0.0,  1.0),
+    vec2<f32>( 1.0,  0.0),
+    vec2<f32>( 1.0,  1.0),
+);
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(positions[vertex_index], 0.0, 1.0);
+    out.uv = uvs[vertex_index];
+    return out;
+}
+
+// Black for no surface, then blue, green, yellow and red up to OVERDRAW_MAX layers
+fn heat_color(t: f32) -> vec3<f32> {
+    let ramp = array<vec3<f32>, 5>(
+        vec3<f32>(0.0, 0.0, 0.0),
+        vec3<f32>(0.0, 0.0, 1.0),
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(1.0, 1.0, 0.0),
+        vec3<f32>(1.0, 0.0, 0.0),
+    );
+    let x = clamp(t, 0.0, 1.0) * 4.0;
+    let i = min(u32(x), 3u);
+    return mix(ramp[i], ramp[i + 1u], x - f32(i));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(overdraw_texture));
+    let pixel = vec2<i32>(clamp(in.uv * size, vec2<f32>(0.0), size - vec2<f32>(1.0)));
+    let count = textureLoad(overdraw_texture, pixel, 0).r;
+    if count <= 0.0 {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    return vec4<f32>(heat_color(0.25 + 0.75 * count / OVERDRAW_MAX), 1.0);
+}
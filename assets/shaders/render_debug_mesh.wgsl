@@ -0,0 +1,173 @@
+// Debug visualisations of the meshes, selected by global_uniforms.mode.
+// Overdraw is drawn additively into a count texture and resolved by overdraw_resolve.wgsl.
+const MODE_SHADING_NORMALS: u32 = 0u;
+const MODE_GEOMETRIC_NORMALS: u32 = 1u;
+const MODE_TANGENTS: u32 = 2u;
+const MODE_UV_CHECKER: u32 = 3u;
+const MODE_MATERIAL_ID: u32 = 4u;
+const MODE_ALBEDO: u32 = 5u;
+const MODE_OVERDRAW: u32 = 6u;
+const MODE_BACKFACES: u32 = 7u;
+
+struct GlobalUniforms {
+    world_to_camera: mat4x4<f32>,
+    camera_to_clip: mat4x4<f32>,
+    camera_position: vec4<f32>,
+    mode: u32,
+    _pad1: u32,
+    _pad2: u32,
+    _pad3: u32,
+}
+
+struct LocalUniforms {
+    local_to_world: mat4x4<f32>,
+    local_to_world_inverse: mat4x4<f32>, // world to local
+    base_color: vec4<f32>, // albedo, or the colour of the material id
+    has_texture: u32,
+    one_sided: u32,
+    _pad1: u32,
+    _pad2: u32,
+}
+
+// global uniforms
+@group(0)
+@binding(0)
+var<uniform> global_uniforms: GlobalUniforms;
+
+// local uniforms
+@group(1)
+@binding(0)
+var<uniform> local_uniforms: LocalUniforms;
+
+// albedo texture, a white texel when the material has none
+@group(2)
+@binding(0)
+var albedo_texture: texture_2d<f32>;
+
+@group(2)
+@binding(1)
+var albedo_sampler: sampler;
+
+struct VertexOut {
+    @location(0) world_position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) world_normal:   vec3<f32>,
+    @location(3) world_tangent:  vec3<f32>,
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    @location(0) position: vec3<f32>,
+    @location(1) uvw: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) tangent: vec3<f32>,
+) -> VertexOut {
+    var out: VertexOut;
+    // local_to_world * world_to_camera * camera_to_clip
+    let m_world = local_uniforms.local_to_world;
+    let m_clip = global_uniforms.camera_to_clip * global_uniforms.world_to_camera * m_world;
+    let m_world_it = transpose(local_uniforms.local_to_world_inverse);
+    let world_position = (m_world * vec4<f32>(position, 1.0)).xyz;
+    let world_normal = normalize((m_world_it * vec4<f32>(normal, 0.0)).xyz);
+    let world_tangent = normalize((m_world * vec4<f32>(tangent, 0.0)).xyz);
+
+    out.position = m_clip * vec4<f32>(position, 1.0);
+    out.world_position = world_position;
+    out.uv = uvw.xy;
+    out.world_normal = world_normal;
+    out.world_tangent = world_tangent;
+    return out;
+}
+
+fn direction_color(v: vec3<f32>) -> vec3<f32> {
+    return 0.5 * (v + vec3<f32>(1.0, 1.0, 1.0));
+}
+
+fn l2srgb(value: f32) -> f32 {
+    return select(
+        12.92 * value,
+        1.055 * pow(value, 1.0 / 2.4) - 0.055,
+        value > 0.0031308
+    );
+}
+
+// The albedo is linear, the other modes write display values
+fn linear_to_srgb(value: vec3<f32>) -> vec3<f32> {
+    return vec3<f32>(
+        l2srgb(value.r),
+        l2srgb(value.g),
+        l2srgb(value.b)
+    );
+}
+
+// 8x8 checker per uv unit, tinted by the uv so flips and seams show
+fn uv_checker(uv: vec2<f32>) -> vec3<f32> {
+    let cell = vec2<i32>(floor(uv * 8.0));
+    let checker = select(0.35, 1.0, ((cell.x + cell.y) & 1) == 0);
+    let tint = vec3<f32>(fract(uv.x), fract(uv.y), 1.0);
+    return checker * mix(vec3<f32>(1.0), tint, 0.6);
+}
+
+// Grey front faces; back faces red on one-sided surfaces and blue on two-sided ones
+fn backface_color(normal: vec3<f32>, camera_to_surface: vec3<f32>) -> vec3<f32> {
+    let facing = dot(normal, -camera_to_surface);
+    if facing >= 0.0 {
+        return vec3<f32>(0.2 + 0.6 * facing);
+    }
+    let shade = 0.3 + 0.7 * -facing;
+    if local_uniforms.one_sided != 0u {
+        return vec3<f32>(1.0, 0.1, 0.1) * shade;
+    }
+    return vec3<f32>(0.2, 0.4, 1.0) * shade;
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let camera_position = global_uniforms.camera_position.xyz;//world position
+    let camera_to_surface = normalize(in.world_position - camera_position);
+    let normal = normalize(in.world_normal);
+    let tangent = normalize(in.world_tangent);
+    // face normal from the screen space derivatives, on the side of the vertex normal
+    var geometric_normal = normalize(cross(dpdx(in.world_position), dpdy(in.world_position)));
+    if dot(geometric_normal, normal) < 0.0 {
+        geometric_normal = -geometric_normal;
+    }
+    let albedo = textureSample(albedo_texture, albedo_sampler, in.uv).rgb;
+
+    var color = vec3<f32>(0.0);
+    switch global_uniforms.mode {
+        case MODE_SHADING_NORMALS: {
+            color = direction_color(normal);
+        }
+        case MODE_GEOMETRIC_NORMALS: {
+            color = direction_color(geometric_normal);
+        }
+        case MODE_TANGENTS: {
+            color = direction_color(tangent);
+        }
+        case MODE_UV_CHECKER: {
+            color = uv_checker(in.uv);
+        }
+        case MODE_MATERIAL_ID: {
+            color = local_uniforms.base_color.rgb;
+        }
+        case MODE_ALBEDO: {
+            if local_uniforms.has_texture != 0u {
+                color = linear_to_srgb(albedo);
+            } else {
+                color = linear_to_srgb(local_uniforms.base_color.rgb);
+            }
+        }
+        case MODE_OVERDRAW: {
+            return vec4<f32>(1.0, 0.0, 0.0, 1.0);
+        }
+        case MODE_BACKFACES: {
+            color = backface_color(normal, camera_to_surface);
+        }
+        default: {
+            color = vec3<f32>(1.0, 0.0, 1.0);
+        }
+    }
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,61 @@
+// Draws the environment of an infinite light behind the scene
+struct EnvironmentUniforms {
+    clip_to_world: mat4x4<f32>,
+    world_to_light: mat4x4<f32>, // rotation of the light
+    intensity: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+};
+
+var<private> positions: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(-1.0,  1.0),
+    vec2<f32>( 1.0,  1.0),
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>( 1.0,  1.0),
+    vec2<f32>( 1.0, -1.0),
+);
+
+@group(0)
+@binding(0)
+var<uniform> uniforms: EnvironmentUniforms;
+
+@group(0)
+@binding(1)
+var env_texture: texture_2d<f32>;
+
+@group(0)
+@binding(2)
+var env_sampler: sampler;
+
+const PI: f32 = 3.14159265359;
+
+fn spherical_texture_lookup(direction: vec3<f32>) -> vec2<f32> {
+    let phi = atan2(direction.y, direction.x);
+    let theta = acos(clamp(direction.z, -1.0, 1.0));//0..PI
+    let u = phi / (2.0 * PI);
+    let v = theta / PI;
+    return vec2<f32>(u, v);
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var result: VertexOutput;
+    result.position = vec4<f32>(positions[vertex_index], 1.0, 1.0);
+    result.ndc = positions[vertex_index];
+    return result;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let near = uniforms.clip_to_world * vec4<f32>(in.ndc, 0.0, 1.0);
+    let far = uniforms.clip_to_world * vec4<f32>(in.ndc, 1.0, 1.0);
+    let direction = normalize(far.xyz / far.w - near.xyz / near.w);
+    let local = normalize((uniforms.world_to_light * vec4<f32>(direction, 0.0)).xyz);
+    let uv = spherical_texture_lookup(local);
+    let color = textureSampleLevel(env_texture, env_sampler, uv, 0.0).rgb;
+    return vec4<f32>(color * uniforms.intensity.rgb, 1.0);
+}
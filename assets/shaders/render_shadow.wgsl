@@ -0,0 +1,24 @@
+// Depth only pass of the shadow maps, one light view per layer
+struct ShadowView {
+    world_to_clip: mat4x4<f32>,
+    params: vec4<f32>, // world size of a texel, perspective flag
+}
+
+struct LocalUniforms {
+    local_to_world: mat4x4<f32>,
+    world_to_local: mat4x4<f32>,
+}
+
+@group(0)
+@binding(0)
+var<uniform> shadow_view: ShadowView;
+
+@group(1)
+@binding(0)
+var<uniform> local_uniforms: LocalUniforms;
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+    let w_position = local_uniforms.local_to_world * vec4<f32>(position, 1.0);
+    return shadow_view.world_to_clip * w_position;
+}
@@ -0,0 +1,141 @@
+// Screen-space ambient occlusion from the depth buffer of the lighting pass.
+// Positions are reconstructed in camera space, the hemisphere around the
+// reconstructed normal is sampled and the samples are tested against the depth buffer.
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+var<private> positions: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(-1.0,  1.0),
+    vec2<f32>( 1.0,  1.0),
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>( 1.0,  1.0),
+    vec2<f32>( 1.0, -1.0),
+);
+
+var<private> uv: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>( 0.0,  1.0),
+    vec2<f32>( 0.0,  0.0),
+    vec2<f32>( 1.0,  0.0),
+    vec2<f32>( 0.0,  1.0),
+    vec2<f32>( 1.0,  0.0),
+    vec2<f32>( 1.0,  1.0),
+);
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var result: VertexOutput;
+    result.position = vec4<f32>(positions[vertex_index], 0.0, 1.0);
+    result.uv = uv[vertex_index];
+    return result;
+}
+
+struct SsaoUniforms {
+    camera_to_clip: mat4x4<f32>,
+    clip_to_camera: mat4x4<f32>,
+    radius: f32,
+    intensity: f32,
+    sample_count: u32,
+    _pad1: u32,
+}
+
+@group(0)
+@binding(0)
+var<uniform> ssao_uniforms: SsaoUniforms;
+
+@group(0)
+@binding(1)
+var depth_texture: texture_depth_2d;
+
+const PI: f32 = 3.14159265359;
+const GOLDEN_ANGLE: f32 = 2.39996323;
+
+fn get_depth(pixel: vec2<i32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(depth_texture));
+    let p = clamp(pixel, vec2<i32>(0), size - vec2<i32>(1));
+    return textureLoad(depth_texture, p, 0);
+}
+
+fn get_camera_position(uv: vec2<f32>, depth: f32) -> vec3<f32> {
+    let ndc = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    let p = ssao_uniforms.clip_to_camera * ndc;
+    return p.xyz / p.w;
+}
+
+fn get_pixel_position(pixel: vec2<i32>) -> vec3<f32> {
+    let size = vec2<f32>(textureDimensions(depth_texture));
+    let uv = (vec2<f32>(pixel) + vec2<f32>(0.5)) / size;
+    return get_camera_position(uv, get_depth(pixel));
+}
+
+// Normal from the neighbouring depths, taking the smaller difference on each axis to keep edges
+fn get_camera_normal(pixel: vec2<i32>, p: vec3<f32>) -> vec3<f32> {
+    let px0 = get_pixel_position(pixel - vec2<i32>(1, 0));
+    let px1 = get_pixel_position(pixel + vec2<i32>(1, 0));
+    let py0 = get_pixel_position(pixel - vec2<i32>(0, 1));
+    let py1 = get_pixel_position(pixel + vec2<i32>(0, 1));
+    let dx = select(p - px0, px1 - p, abs(px1.z - p.z) < abs(p.z - px0.z));
+    let dy = select(p - py0, py1 - p, abs(py1.z - p.z) < abs(p.z - py0.z));
+    var n = normalize(cross(dx, dy));
+    if dot(n, p) > 0.0 {
+        n = -n;
+    }
+    return n;
+}
+
+// 4x4 interleaved rotation, removed by the blur of the composite pass
+fn get_noise(pixel: vec2<i32>) -> f32 {
+    let p = vec2<u32>(pixel) % vec2<u32>(4u);
+    let index = p.y * 4u + p.x;
+    return (f32((index * 7u) % 16u) + 0.5) / 16.0;
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(floor(vertex.position.xy));
+    let depth = get_depth(pixel);
+    if depth >= 1.0 {
+        return vec4<f32>(1.0);
+    }
+    let p = get_pixel_position(pixel);
+    let n = get_camera_normal(pixel, p);
+    let up = select(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 1.0, 0.0), abs(n.y) < 0.99);
+    let t = normalize(cross(up, n));
+    let b = cross(n, t);
+
+    let radius = ssao_uniforms.radius;
+    let count = max(ssao_uniforms.sample_count, 1u);
+    let noise = get_noise(pixel);
+    var occlusion = 0.0;
+    for (var i: u32 = 0u; i < count; i++) {
+        // cosine weighted spiral over the hemisphere, denser close to the point
+        let u = (f32(i) + 0.5) / f32(count);
+        let phi = f32(i) * GOLDEN_ANGLE + noise * 2.0 * PI;
+        let sin_theta = sqrt(u);
+        let cos_theta = sqrt(1.0 - u);
+        let dir = vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+        let scale = mix(0.1, 1.0, fract(u * 7.0 + noise));
+        let s = p + (t * dir.x + b * dir.y + n * dir.z) * radius * scale * scale;
+
+        let clip = ssao_uniforms.camera_to_clip * vec4<f32>(s, 1.0);
+        if clip.w <= 0.0 {
+            continue;
+        }
+        let ndc = clip.xy / clip.w;
+        let sample_uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+        if any(sample_uv < vec2<f32>(0.0)) || any(sample_uv > vec2<f32>(1.0)) {
+            continue;
+        }
+        let size = vec2<f32>(textureDimensions(depth_texture));
+        let sample_depth = get_depth(vec2<i32>(sample_uv * size));
+        let q = get_camera_position(sample_uv, sample_depth);
+        // occluded when the visible surface is in front of the sample along the view ray
+        let d = length(s) - length(q);
+        let range = smoothstep(0.0, 1.0, radius / max(abs(length(p) - length(q)), 1e-4));
+        occlusion += select(0.0, range, d > 0.02 * radius);
+    }
+    let ao = 1.0 - ssao_uniforms.intensity * occlusion / f32(count);
+    return vec4<f32>(vec3<f32>(clamp(ao, 0.0, 1.0)), 1.0);
+}
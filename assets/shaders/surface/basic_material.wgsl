@@ -24,6 +24,7 @@ struct LightUniforms {
 struct DirectionalLight {
     direction: vec4<f32>, // Example light direction
     intensity: vec4<f32>, // Example light intensity
+    shadow: vec4<i32>, // First shadow map layer, cascade count (unused here)
 }
 
 struct SphereLight {
@@ -152,6 +153,10 @@ var ltc_texture_array: texture_2d_array<f32>;// LTC lookup texture
 @binding(9)
 var ltc_sampler: sampler;
 
+@group(3)
+@binding(10)
+var irradiance_texture: texture_2d<f32>;// Irradiance of the infinite light
+
 //-------------------------------------------------------
 const MAX_FLOAT: f32 = 1e+10;
 const PI: f32 = 3.14159265359;
@@ -453,6 +458,15 @@ fn LTC_Evaluate_Disk(N: vec3<f32>, V: vec3<f32>, P: vec3<f32>, Minv: mat3x3<f32>
 }
 
 //-------------------------------------------------------
+// Analytic fit of the split-sum environment BRDF (Karis, "Mobile PBR")
+fn env_brdf_approx(roughness: f32, NdotV: f32) -> vec2<f32> {
+    let c0 = vec4<f32>(-1.0, -0.0275, -0.572, 0.022);
+    let c1 = vec4<f32>(1.0, 0.0425, 1.04, -0.04);
+    let r = roughness * c0 + c1;
+    let a004 = min(r.x * r.x, exp2(-9.28 * NdotV)) * r.x + r.y;
+    return vec2<f32>(-1.04, 1.04) * a004 + r.zw;
+}
+
 fn spherical_texture_lookup(direction: vec3<f32>) -> vec2<f32> {
     // Assumes direction is normalized
     //let u = 0.5 + atan2(direction.z, direction.x) / (2.0 * PI);
@@ -661,17 +675,20 @@ fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
         let intensity = light.intensity.rgb;
         let tex_index = light.indices.x;
         let inv_matrix = light.inv_matrix;
+        var irradiance = vec3<f32>(1.0);
+        if tex_index >= 0 {
+            let nt = normalize((inv_matrix * vec4<f32>(N, 0.0)).xyz);
+            irradiance = textureSampleLevel(irradiance_texture, light_sampler, spherical_texture_lookup(nt), 0.0).rgb;
+        }
         let r = normalize(reflect(camera_to_surface, normal));
-        var val = vec3<f32>(1.0);
+        var prefiltered = vec3<f32>(1.0);
         if tex_index >= 0 {
-            let rt = inv_matrix * vec4<f32>(r, 0.0);
-            let rt2 = normalize(rt.xyz);
-            let uv = spherical_texture_lookup(rt2);
-            val = textureSample(light_texture, light_sampler, uv).rgb;
+            let rt = normalize((inv_matrix * vec4<f32>(r, 0.0)).xyz);
+            let lod = roughness * f32(max(light.indices.y - 1, 0));
+            prefiltered = textureSampleLevel(light_texture, light_sampler, spherical_texture_lookup(rt), lod).rgb;
         }
-        //color += val * intensity;
-        let wi = tbn * r;
-        color += shade(intensity * val, wo, wi);
+        let ab = env_brdf_approx(roughness, NdotV);
+        color += intensity * (m_diff * irradiance + (spec * ab.x + ab.y) * prefiltered);
     }
 
     return vec4<f32>(color, 1.0);
@@ -0,0 +1,109 @@
+// Conductor Fresnel with an anisotropic GGX lobe (metal)
+// Permutations: ETA_TEXTURE, K_TEXTURE, UROUGHNESS_TEXTURE, VROUGHNESS_TEXTURE, REMAP_ROUGHNESS, BUMPMAP_TEXTURE, MIX_AMOUNT, MIX_OVERLAY
+#define HAS_SPECULAR
+#include "include/surface_common.wgsl"
+#include "include/microfacet.wgsl"
+#include "include/material_textures.wgsl"
+#include "include/surface_lighting.wgsl"
+#include "include/material_mix.wgsl"
+
+//-------------------------------------------------------
+// material definitions
+
+// material uniforms
+struct MaterialUniforms {
+    eta: vec4<f32>,
+    k: vec4<f32>,
+    #ifdef UROUGHNESS_TEXTURE
+    uroughness: vec4<f32>,
+    #else
+    uroughness: f32,
+    #endif
+    #ifdef VROUGHNESS_TEXTURE
+    vroughness: vec4<f32>,
+    #else
+    vroughness: f32,
+    #endif
+    #ifdef BUMPMAP_TEXTURE
+    bumpmap: vec4<f32>,
+    #endif
+    #ifdef MIX_AMOUNT
+    amount: vec4<f32>,
+    #endif
+}
+
+@group(2)
+@binding(0)
+var<uniform> material_uniforms: MaterialUniforms;
+
+// Complex index of refraction of the current fragment, read by shade()
+struct Conductor {
+    eta: vec3<f32>,
+    k: vec3<f32>,
+}
+
+var<private> conductor: Conductor;
+
+fn get_eta(uv: vec2<f32>) -> vec3<f32> {
+    #ifdef ETA_TEXTURE
+    return textureSample(eta_texture, eta_sampler, get_texture_uv(material_uniforms.eta, uv)).rgb;
+    #else
+    return material_uniforms.eta.rgb;
+    #endif
+}
+
+fn get_k(uv: vec2<f32>) -> vec3<f32> {
+    #ifdef K_TEXTURE
+    return textureSample(k_texture, k_sampler, get_texture_uv(material_uniforms.k, uv)).rgb;
+    #else
+    return material_uniforms.k.rgb;
+    #endif
+}
+
+fn get_uroughness(uv: vec2<f32>) -> f32 {
+    #ifdef UROUGHNESS_TEXTURE
+    var roughness = textureSample(uroughness_texture, uroughness_sampler, get_texture_uv(material_uniforms.uroughness, uv)).r;
+    #ifdef REMAP_ROUGHNESS
+    roughness = roughness_to_alpha(roughness);
+    #endif
+    return roughness;
+    #else
+    return material_uniforms.uroughness;
+    #endif
+}
+
+fn get_vroughness(uv: vec2<f32>) -> f32 {
+    #ifdef VROUGHNESS_TEXTURE
+    var roughness = textureSample(vroughness_texture, vroughness_sampler, get_texture_uv(material_uniforms.vroughness, uv)).r;
+    #ifdef REMAP_ROUGHNESS
+    roughness = roughness_to_alpha(roughness);
+    #endif
+    return roughness;
+    #else
+    return material_uniforms.vroughness;
+    #endif
+}
+
+fn shade(intensity: vec3<f32>, wo: vec3<f32>, wi: vec3<f32>, surface: Surface) -> vec3<f32> {
+    let wh = normalize(wo + wi);
+    let f = fresnel_conductor(dot(wi, wh), conductor.eta, conductor.k);
+    return f * torrance_sparrow(wo, wi, surface.alpha) * max(wi.z, 0.0) * intensity;
+}
+
+//-------------------------------------------------------
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let weight = get_layer_weight(in.uv);
+    conductor.eta = get_eta(in.uv);
+    conductor.k = get_k(in.uv);
+    let alpha = max(vec2<f32>(get_uroughness(in.uv), get_vroughness(in.uv)), vec2<f32>(1e-3));
+    var surface: Surface;
+    surface.diff = vec3<f32>(0.0);
+    // area lights and the environment use the normal incidence reflectance
+    surface.spec = fresnel_conductor(1.0, conductor.eta, conductor.k);
+    surface.roughness = sqrt(alpha.x * alpha.y);
+    surface.alpha = alpha;
+    let color = shade_surface(in, surface);
+    return layer_output(weight, vec4<f32>(color, 1.0));
+}
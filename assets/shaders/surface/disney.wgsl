@@ -0,0 +1,172 @@
+// Approximation of the Disney principled BSDF: Burley diffuse with retro-reflection and sheen,
+// an anisotropic GGX lobe and a GTR1 clearcoat (disney)
+// Permutations: COLOR_TEXTURE, METALLIC_TEXTURE, ROUGHNESS_TEXTURE, BUMPMAP_TEXTURE, MIX_AMOUNT, MIX_OVERLAY
+#define HAS_DIFFUSE
+#define HAS_SPECULAR
+#include "include/surface_common.wgsl"
+#include "include/microfacet.wgsl"
+#include "include/material_textures.wgsl"
+#include "include/surface_lighting.wgsl"
+#include "include/material_mix.wgsl"
+
+//-------------------------------------------------------
+// material definitions
+
+// material uniforms
+struct MaterialUniforms {
+    color: vec4<f32>,
+    params1: vec4<f32>, // eta, speculartint, anisotropic, sheen
+    params2: vec4<f32>, // sheentint, clearcoat, clearcoatgloss, spectrans
+    #ifdef METALLIC_TEXTURE
+    metallic: vec4<f32>,
+    #else
+    metallic: f32,
+    #endif
+    #ifdef ROUGHNESS_TEXTURE
+    roughness: vec4<f32>,
+    #else
+    roughness: f32,
+    #endif
+    #ifdef BUMPMAP_TEXTURE
+    bumpmap: vec4<f32>,
+    #endif
+    #ifdef MIX_AMOUNT
+    amount: vec4<f32>,
+    #endif
+}
+
+@group(2)
+@binding(0)
+var<uniform> material_uniforms: MaterialUniforms;
+
+// Lobe inputs of the current fragment, read by shade()
+struct Disney {
+    color: vec3<f32>,
+    tint: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+    diffuse_weight: f32,
+    sheen: f32,
+    sheen_tint: f32,
+    clearcoat: f32,
+    clearcoat_alpha: f32,
+}
+
+var<private> disney: Disney;
+
+fn get_color(uv: vec2<f32>) -> vec3<f32> {
+    #ifdef COLOR_TEXTURE
+    return textureSample(color_texture, color_sampler, get_texture_uv(material_uniforms.color, uv)).rgb;
+    #else
+    return material_uniforms.color.rgb;
+    #endif
+}
+
+fn get_metallic(uv: vec2<f32>) -> f32 {
+    #ifdef METALLIC_TEXTURE
+    return textureSample(metallic_texture, metallic_sampler, get_texture_uv(material_uniforms.metallic, uv)).r;
+    #else
+    return material_uniforms.metallic;
+    #endif
+}
+
+fn get_roughness(uv: vec2<f32>) -> f32 {
+    #ifdef ROUGHNESS_TEXTURE
+    return textureSample(roughness_texture, roughness_sampler, get_texture_uv(material_uniforms.roughness, uv)).r;
+    #else
+    return material_uniforms.roughness;
+    #endif
+}
+
+fn schlick_weight(cos_theta: f32) -> f32 {
+    return pow5(1.0 - saturate(cos_theta));
+}
+
+// Generalized Trowbridge-Reitz with gamma 1, used by the clearcoat
+fn gtr1(cos_h: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    return (alpha2 - 1.0) / (PI * log(alpha2) * (1.0 + (alpha2 - 1.0) * cos_h * cos_h));
+}
+
+fn smith_g_ggx(cos_theta: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    let cos2 = cos_theta * cos_theta;
+    return 1.0 / (cos_theta + sqrt(alpha2 + cos2 - alpha2 * cos2));
+}
+
+fn shade(intensity: vec3<f32>, wo: vec3<f32>, wi: vec3<f32>, surface: Surface) -> vec3<f32> {
+    let cos_i = wi.z;
+    let cos_o = wo.z;
+    if cos_i <= 0.0 || cos_o <= 0.0 {
+        return vec3<f32>(0.0);
+    }
+    let wh = normalize(wo + wi);
+    let cos_d = dot(wi, wh);
+    let fo = schlick_weight(cos_o);
+    let fi = schlick_weight(cos_i);
+
+    // Burley diffuse with retro-reflection
+    let rr = 2.0 * disney.roughness * cos_d * cos_d;
+    let diffuse = lambertian_reflection(disney.color)
+        * ((1.0 - 0.5 * fo) * (1.0 - 0.5 * fi) + rr * (fo + fi + fo * fi * (rr - 1.0)));
+    let c_sheen = mix(vec3<f32>(1.0), disney.tint, disney.sheen_tint);
+    let sheen = disney.sheen * c_sheen * schlick_weight(cos_d);
+    var f = (diffuse + sheen) * disney.diffuse_weight;
+
+    // specular with surface.spec as the tinted normal incidence reflectance
+    f += fresnel_schlick(surface.spec, cos_d) * torrance_sparrow(wo, wi, surface.alpha);
+
+    // clearcoat
+    if disney.clearcoat > 0.0 {
+        let dr = gtr1(wh.z, disney.clearcoat_alpha);
+        let fr = mix(0.04, 1.0, schlick_weight(dot(wo, wh)));
+        let gr = smith_g_ggx(cos_o, 0.25) * smith_g_ggx(cos_i, 0.25);
+        f += vec3<f32>(disney.clearcoat * 0.25 * gr * fr * dr);
+    }
+    return f * cos_i * intensity;
+}
+
+//-------------------------------------------------------
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let weight = get_layer_weight(in.uv);
+    let params1 = material_uniforms.params1;
+    let params2 = material_uniforms.params2;
+    let eta = params1.x;
+    let specular_tint = params1.y;
+    let anisotropic = params1.z;
+    let spec_trans = params2.w;
+
+    let color = get_color(in.uv);
+    let metallic = saturate(get_metallic(in.uv));
+    let roughness = saturate(get_roughness(in.uv));
+    let luminance = dot(color, vec3<f32>(0.3, 0.6, 0.1));
+    var tint = vec3<f32>(1.0);
+    if luminance > 0.0 {
+        tint = color / luminance;
+    }
+
+    disney.color = color;
+    disney.tint = tint;
+    disney.metallic = metallic;
+    disney.roughness = roughness;
+    disney.diffuse_weight = (1.0 - metallic) * (1.0 - spec_trans);
+    disney.sheen = params1.w;
+    disney.sheen_tint = params2.x;
+    disney.clearcoat = params2.y;
+    disney.clearcoat_alpha = mix(0.1, 0.001, params2.z);
+
+    let aspect = sqrt(1.0 - anisotropic * 0.9);
+    let alpha = max(vec2<f32>(roughness * roughness / aspect, roughness * roughness * aspect), vec2<f32>(1e-3));
+    let r0 = (eta - 1.0) / (eta + 1.0);
+    let spec0 = mix(r0 * r0 * mix(vec3<f32>(1.0), tint, specular_tint), color, metallic);
+
+    var surface: Surface;
+    surface.diff = color * disney.diffuse_weight;
+    surface.spec = spec0;
+    surface.roughness = sqrt(alpha.x * alpha.y);
+    surface.alpha = alpha;
+    let out_color = shade_surface(in, surface);
+    return layer_output(weight, vec4<f32>(out_color, 1.0));
+}
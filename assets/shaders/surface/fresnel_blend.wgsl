@@ -0,0 +1,110 @@
+// Ashikhmin-Shirley FresnelBlend of a diffuse base under a glossy coating (substrate)
+// Permutations: KD_TEXTURE, KS_TEXTURE, UROUGHNESS_TEXTURE, VROUGHNESS_TEXTURE, REMAP_ROUGHNESS, BUMPMAP_TEXTURE, MIX_AMOUNT, MIX_OVERLAY
+#define HAS_DIFFUSE
+#define HAS_SPECULAR
+#include "include/surface_common.wgsl"
+#include "include/microfacet.wgsl"
+#include "include/material_textures.wgsl"
+#include "include/surface_lighting.wgsl"
+#include "include/material_mix.wgsl"
+
+//-------------------------------------------------------
+// material definitions
+
+// material uniforms
+struct MaterialUniforms {
+    kd: vec4<f32>,
+    ks: vec4<f32>,
+    #ifdef UROUGHNESS_TEXTURE
+    uroughness: vec4<f32>,
+    #else
+    uroughness: f32,
+    #endif
+    #ifdef VROUGHNESS_TEXTURE
+    vroughness: vec4<f32>,
+    #else
+    vroughness: f32,
+    #endif
+    #ifdef BUMPMAP_TEXTURE
+    bumpmap: vec4<f32>,
+    #endif
+    #ifdef MIX_AMOUNT
+    amount: vec4<f32>,
+    #endif
+}
+
+@group(2)
+@binding(0)
+var<uniform> material_uniforms: MaterialUniforms;
+
+fn get_kd(uv: vec2<f32>) -> vec3<f32> {
+    #ifdef KD_TEXTURE
+    return textureSample(kd_texture, kd_sampler, get_texture_uv(material_uniforms.kd, uv)).rgb;
+    #else
+    return material_uniforms.kd.rgb;
+    #endif
+}
+
+fn get_ks(uv: vec2<f32>) -> vec3<f32> {
+    #ifdef KS_TEXTURE
+    return textureSample(ks_texture, ks_sampler, get_texture_uv(material_uniforms.ks, uv)).rgb;
+    #else
+    return material_uniforms.ks.rgb;
+    #endif
+}
+
+fn get_uroughness(uv: vec2<f32>) -> f32 {
+    #ifdef UROUGHNESS_TEXTURE
+    var roughness = textureSample(uroughness_texture, uroughness_sampler, get_texture_uv(material_uniforms.uroughness, uv)).r;
+    #ifdef REMAP_ROUGHNESS
+    roughness = roughness_to_alpha(roughness);
+    #endif
+    return roughness;
+    #else
+    return material_uniforms.uroughness;
+    #endif
+}
+
+fn get_vroughness(uv: vec2<f32>) -> f32 {
+    #ifdef VROUGHNESS_TEXTURE
+    var roughness = textureSample(vroughness_texture, vroughness_sampler, get_texture_uv(material_uniforms.vroughness, uv)).r;
+    #ifdef REMAP_ROUGHNESS
+    roughness = roughness_to_alpha(roughness);
+    #endif
+    return roughness;
+    #else
+    return material_uniforms.vroughness;
+    #endif
+}
+
+fn shade(intensity: vec3<f32>, wo: vec3<f32>, wi: vec3<f32>, surface: Surface) -> vec3<f32> {
+    let cos_i = wi.z;
+    let cos_o = wo.z;
+    if cos_i <= 0.0 || cos_o <= 0.0 {
+        return vec3<f32>(0.0);
+    }
+    let rd = surface.diff;
+    let rs = surface.spec;
+    let diffuse = (28.0 / (23.0 * PI)) * rd * (vec3<f32>(1.0) - rs)
+        * (1.0 - pow5(1.0 - 0.5 * cos_i)) * (1.0 - pow5(1.0 - 0.5 * cos_o));
+    let wh = normalize(wo + wi);
+    let wi_dot_wh = max(dot(wi, wh), 1e-4);
+    let specular = ggx_d(wh, surface.alpha) / (4.0 * wi_dot_wh * max(cos_i, cos_o))
+        * fresnel_schlick(rs, wi_dot_wh);
+    return (diffuse + specular) * cos_i * intensity;
+}
+
+//-------------------------------------------------------
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let weight = get_layer_weight(in.uv);
+    let alpha = max(vec2<f32>(get_uroughness(in.uv), get_vroughness(in.uv)), vec2<f32>(1e-3));
+    var surface: Surface;
+    surface.diff = get_kd(in.uv);
+    surface.spec = get_ks(in.uv);
+    surface.roughness = sqrt(alpha.x * alpha.y);
+    surface.alpha = alpha;
+    let color = shade_surface(in, surface);
+    return layer_output(weight, vec4<f32>(color, 1.0));
+}
@@ -0,0 +1,24 @@
+// Layer weights for the sub-materials of a mix material.
+// The renderer defines MIX_AMOUNT and appends `amount` to the MaterialUniforms.
+// The base layer is weighted by 1 - amount, the layer added over it (MIX_OVERLAY) by amount.
+
+fn get_layer_weight(uv: vec2<f32>) -> vec3<f32> {
+    #ifdef MIX_AMOUNT
+    #ifdef AMOUNT_TEXTURE
+    let amount = textureSample(amount_texture, amount_sampler, get_texture_uv(material_uniforms.amount, uv)).rgb;
+    #else
+    let amount = material_uniforms.amount.rgb;
+    #endif
+    #ifdef MIX_OVERLAY
+    return amount;
+    #else
+    return vec3<f32>(1.0) - amount;
+    #endif
+    #else
+    return vec3<f32>(1.0);
+    #endif
+}
+
+fn layer_output(weight: vec3<f32>, color: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(color.rgb * weight, color.a);
+}
@@ -0,0 +1,151 @@
+// Optional material textures. For every texture parameter the renderer defines
+// <KEY>_TEXTURE with <KEY>_TEXTURE_BINDING and <KEY>_SAMPLER_BINDING in group(2);
+// the matching uniform then holds the uv scale (xy) and offset (zw).
+
+#ifdef KD_TEXTURE
+@group(2)
+@binding(KD_TEXTURE_BINDING)
+var kd_texture: texture_2d<f32>;
+
+@group(2)
+@binding(KD_SAMPLER_BINDING)
+var kd_sampler: sampler;
+#endif
+
+#ifdef KS_TEXTURE
+@group(2)
+@binding(KS_TEXTURE_BINDING)
+var ks_texture: texture_2d<f32>;
+
+@group(2)
+@binding(KS_SAMPLER_BINDING)
+var ks_sampler: sampler;
+#endif
+
+#ifdef KR_TEXTURE
+@group(2)
+@binding(KR_TEXTURE_BINDING)
+var kr_texture: texture_2d<f32>;
+
+@group(2)
+@binding(KR_SAMPLER_BINDING)
+var kr_sampler: sampler;
+#endif
+
+#ifdef KT_TEXTURE
+@group(2)
+@binding(KT_TEXTURE_BINDING)
+var kt_texture: texture_2d<f32>;
+
+@group(2)
+@binding(KT_SAMPLER_BINDING)
+var kt_sampler: sampler;
+#endif
+
+#ifdef ROUGHNESS_TEXTURE
+@group(2)
+@binding(ROUGHNESS_TEXTURE_BINDING)
+var roughness_texture: texture_2d<f32>;
+
+@group(2)
+@binding(ROUGHNESS_SAMPLER_BINDING)
+var roughness_sampler: sampler;
+#endif
+
+#ifdef ETA_TEXTURE
+@group(2)
+@binding(ETA_TEXTURE_BINDING)
+var eta_texture: texture_2d<f32>;
+
+@group(2)
+@binding(ETA_SAMPLER_BINDING)
+var eta_sampler: sampler;
+#endif
+
+#ifdef K_TEXTURE
+@group(2)
+@binding(K_TEXTURE_BINDING)
+var k_texture: texture_2d<f32>;
+
+@group(2)
+@binding(K_SAMPLER_BINDING)
+var k_sampler: sampler;
+#endif
+
+#ifdef UROUGHNESS_TEXTURE
+@group(2)
+@binding(UROUGHNESS_TEXTURE_BINDING)
+var uroughness_texture: texture_2d<f32>;
+
+@group(2)
+@binding(UROUGHNESS_SAMPLER_BINDING)
+var uroughness_sampler: sampler;
+#endif
+
+#ifdef VROUGHNESS_TEXTURE
+@group(2)
+@binding(VROUGHNESS_TEXTURE_BINDING)
+var vroughness_texture: texture_2d<f32>;
+
+@group(2)
+@binding(VROUGHNESS_SAMPLER_BINDING)
+var vroughness_sampler: sampler;
+#endif
+
+#ifdef COLOR_TEXTURE
+@group(2)
+@binding(COLOR_TEXTURE_BINDING)
+var color_texture: texture_2d<f32>;
+
+@group(2)
+@binding(COLOR_SAMPLER_BINDING)
+var color_sampler: sampler;
+#endif
+
+#ifdef METALLIC_TEXTURE
+@group(2)
+@binding(METALLIC_TEXTURE_BINDING)
+var metallic_texture: texture_2d<f32>;
+
+@group(2)
+@binding(METALLIC_SAMPLER_BINDING)
+var metallic_sampler: sampler;
+#endif
+
+#ifdef AMOUNT_TEXTURE
+@group(2)
+@binding(AMOUNT_TEXTURE_BINDING)
+var amount_texture: texture_2d<f32>;
+
+@group(2)
+@binding(AMOUNT_SAMPLER_BINDING)
+var amount_sampler: sampler;
+#endif
+
+#ifdef BUMPMAP_TEXTURE
+@group(2)
+@binding(BUMPMAP_TEXTURE_BINDING)
+var bumpmap_texture: texture_2d<f32>;
+
+@group(2)
+@binding(BUMPMAP_SAMPLER_BINDING)
+var bumpmap_sampler: sampler;
+#endif
+
+fn get_texture_uv(uv_factor: vec4<f32>, uv: vec2<f32>) -> vec2<f32> {
+    return uv_factor.xy * uv + uv_factor.zw;
+}
+
+// Perturbs the normal with the normal map converted from the bumpmap.
+// The map holds the height slopes per unit uv, so they follow the uv scale of the texture.
+fn get_shading_normal(uv: vec2<f32>, normal: vec3<f32>, tangent: vec3<f32>) -> vec3<f32> {
+    #ifdef BUMPMAP_TEXTURE
+    let t = normalize(tangent - dot(tangent, normal) * normal);
+    let b = cross(normal, t);
+    let n = textureSample(bumpmap_texture, bumpmap_sampler, get_texture_uv(material_uniforms.bumpmap, uv)).xyz;
+    let slope = -n.xy / max(n.z, 1e-4) * material_uniforms.bumpmap.xy;
+    return normalize(normal - slope.x * t - slope.y * b);
+    #else
+    return normal;
+    #endif
+}
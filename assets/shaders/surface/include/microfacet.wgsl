@@ -0,0 +1,81 @@
+#pragma once
+// Microfacet and Fresnel terms for the punctual lights in shade().
+// Directions are in the shading frame: normal along +z, tangent along +x.
+
+fn pow5(x: f32) -> f32 {
+    let x2 = x * x;
+    return x2 * x2 * x;
+}
+
+// Anisotropic Trowbridge-Reitz (GGX) distribution, alpha along tangent and bitangent
+fn ggx_d(wh: vec3<f32>, alpha: vec2<f32>) -> f32 {
+    let cos2 = wh.z * wh.z;
+    if cos2 <= 0.0 {
+        return 0.0;
+    }
+    let e = (wh.x * wh.x) / (alpha.x * alpha.x) + (wh.y * wh.y) / (alpha.y * alpha.y) + cos2;
+    return 1.0 / (PI * alpha.x * alpha.y * e * e);
+}
+
+fn ggx_lambda(w: vec3<f32>, alpha: vec2<f32>) -> f32 {
+    let cos2 = w.z * w.z;
+    if cos2 <= 0.0 {
+        return 0.0;
+    }
+    let tan2_alpha2 = (w.x * w.x * alpha.x * alpha.x + w.y * w.y * alpha.y * alpha.y) / cos2;
+    return (-1.0 + sqrt(1.0 + tan2_alpha2)) * 0.5;
+}
+
+fn ggx_g(wo: vec3<f32>, wi: vec3<f32>, alpha: vec2<f32>) -> f32 {
+    return 1.0 / (1.0 + ggx_lambda(wo, alpha) + ggx_lambda(wi, alpha));
+}
+
+// D * G / (4 cos_o cos_i) without the Fresnel term, zero below the horizon
+fn torrance_sparrow(wo: vec3<f32>, wi: vec3<f32>, alpha: vec2<f32>) -> f32 {
+    let cos_o = wo.z;
+    let cos_i = wi.z;
+    if cos_o <= 0.0 || cos_i <= 0.0 {
+        return 0.0;
+    }
+    let wh = normalize(wo + wi);
+    return ggx_d(wh, alpha) * ggx_g(wo, wi, alpha) / (4.0 * cos_o * cos_i);
+}
+
+fn fresnel_schlick(f0: vec3<f32>, cos_theta: f32) -> vec3<f32> {
+    return f0 + (vec3<f32>(1.0) - f0) * pow5(1.0 - saturate(cos_theta));
+}
+
+// Unpolarized Fresnel reflectance from air into a dielectric
+fn fresnel_dielectric(cos_theta_i: f32, eta: f32) -> f32 {
+    let cos_i = saturate(cos_theta_i);
+    let sin_t = sqrt(max(0.0, 1.0 - cos_i * cos_i)) / eta;
+    if sin_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = sqrt(max(0.0, 1.0 - sin_t * sin_t));
+    let r_parl = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perp = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    return (r_parl * r_parl + r_perp * r_perp) * 0.5;
+}
+
+// Fresnel reflectance of a conductor with complex index of refraction eta + ik
+fn fresnel_conductor(cos_theta_i: f32, eta: vec3<f32>, k: vec3<f32>) -> vec3<f32> {
+    let cos_i = clamp(cos_theta_i, 0.0, 1.0);
+    let cos2 = vec3<f32>(cos_i * cos_i);
+    let sin2 = vec3<f32>(1.0) - cos2;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = sqrt(t0 * t0 + 4.0 * eta2 * k2);
+    let t1 = a2_plus_b2 + cos2;
+    let a = sqrt(max(vec3<f32>(0.0), 0.5 * (a2_plus_b2 + t0)));
+    let t2 = 2.0 * cos_i * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    return 0.5 * (rp + rs);
+}
@@ -0,0 +1,102 @@
+#pragma once
+// Shadow maps of the punctual lights, rendered by shadow_map.rs.
+// Each layer of shadow_texture holds one light view: a cascade of a directional light,
+// the frustum of a spot light or a cube face of a point light (+X, -X, +Y, -Y, +Z, -Z).
+
+struct ShadowUniforms {
+    cascade_splits: vec4<f32>, // View depth where each cascade ends
+    params: vec4<f32>, // texel size, pcf radius, depth bias, normal bias
+}
+
+struct ShadowView {
+    world_to_clip: mat4x4<f32>,
+    params: vec4<f32>, // world size of a texel (at distance 1 for perspective views), perspective flag
+}
+
+@group(3)
+@binding(11)
+var<uniform> shadow_uniforms: ShadowUniforms;
+
+@group(3)
+@binding(12)
+var<storage, read> shadow_views: array<ShadowView>;
+
+@group(3)
+@binding(13)
+var shadow_texture: texture_depth_2d_array;
+
+@group(3)
+@binding(14)
+var shadow_sampler: sampler_comparison;
+
+// Fraction of the light reaching position, filtered with a PCF kernel
+fn sample_shadow_layer(layer: i32, position: vec3<f32>, normal: vec3<f32>, distance: f32) -> f32 {
+    let view = shadow_views[layer];
+    var texel = view.params.x;
+    if view.params.y > 0.0 {
+        texel *= distance;
+    }
+    // offset along the normal against acne on surfaces at grazing angles
+    let p = position + normal * texel * shadow_uniforms.params.w;
+    let clip = view.world_to_clip * vec4<f32>(p, 1.0);
+    if clip.w <= 0.0 {
+        return 1.0;
+    }
+    let ndc = clip.xyz / clip.w;
+    let uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || ndc.z > 1.0 {
+        return 1.0;
+    }
+    let depth = ndc.z - shadow_uniforms.params.z;
+    let radius = i32(shadow_uniforms.params.y);
+    let texel_size = shadow_uniforms.params.x;
+    var lit = 0.0;
+    var count = 0.0;
+    for (var y = -radius; y <= radius; y++) {
+        for (var x = -radius; x <= radius; x++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel_size;
+            lit += textureSampleCompareLevel(shadow_texture, shadow_sampler, uv + offset, layer, depth);
+            count += 1.0;
+        }
+    }
+    return lit / count;
+}
+
+fn get_directional_shadow(light: DirectionalLight, position: vec3<f32>, normal: vec3<f32>) -> f32 {
+    let first = light.shadow.x;
+    if first < 0 {
+        return 1.0;
+    }
+    let view_depth = -(global_uniforms.world_to_camera * vec4<f32>(position, 1.0)).z;
+    for (var c = 0; c < light.shadow.y; c++) {
+        if view_depth <= shadow_uniforms.cascade_splits[c] {
+            return sample_shadow_layer(first + c, position, normal, 1.0);
+        }
+    }
+    return 1.0;
+}
+
+fn get_point_shadow(light: SphereLight, position: vec3<f32>, normal: vec3<f32>) -> f32 {
+    if light.shadow_index < 0 {
+        return 1.0;
+    }
+    let d = position - light.position.xyz;
+    let a = abs(d);
+    var face = 0;
+    if a.x >= a.y && a.x >= a.z {
+        face = select(1, 0, d.x > 0.0);
+    } else if a.y >= a.z {
+        face = select(3, 2, d.y > 0.0);
+    } else {
+        face = select(5, 4, d.z > 0.0);
+    }
+    return sample_shadow_layer(light.shadow_index + face, position, normal, length(d));
+}
+
+fn get_spot_shadow(light: DiskLight, position: vec3<f32>, normal: vec3<f32>) -> f32 {
+    if light.shadow_index < 0 {
+        return 1.0;
+    }
+    let distance = length(position - light.position.xyz);
+    return sample_shadow_layer(light.shadow_index, position, normal, distance);
+}
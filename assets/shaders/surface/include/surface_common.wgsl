@@ -1,3 +1,4 @@
+// Shared declarations of the surface shaders: scene uniforms, lights, LTC and IBL helpers, vertex stage
 struct GlobalUniforms {
     world_to_camera: mat4x4<f32>,
     camera_to_clip: mat4x4<f32>,
@@ -24,6 +25,7 @@ struct LightUniforms {
 struct DirectionalLight {
     direction: vec4<f32>, // Example light direction
     intensity: vec4<f32>, // Example light intensity
+    shadow: vec4<i32>, // First shadow map layer (-1 without shadows), cascade count
 }
 
 struct SphereLight {
@@ -31,7 +33,8 @@ struct SphereLight {
     intensity: vec4<f32>, // Light intensity
     radius: f32,
     range: f32,
-    _pad1: vec2<f32>, // Padding for alignment
+    shadow_index: i32, // First of the six shadow map layers, -1 without shadows
+    _pad1: u32, // Padding for alignment
 }
 
 struct DiskLight {
@@ -45,7 +48,7 @@ struct DiskLight {
     u_axis: vec4<f32>,    // U axis for rectangle // 4 * 4 = 16
     v_axis: vec4<f32>,    // V axis for rectangle // 4 * 4 = 16
     twosided: u32,       // Whether the rectangle emits light on both sides
-    _pad1: u32,     // Padding to ensure alignment
+    shadow_index: i32,   // Shadow map layer, -1 without shadows
     _pad2: u32,     // Padding to ensure alignment
     _pad3: u32,     // Padding to ensure alignment
 }
@@ -81,31 +84,6 @@ var<uniform> global_uniforms: GlobalUniforms;
 var<uniform> local_uniforms: LocalUniforms;
 
 //-------------------------------------------------------
-// material definitions
-
-// material uniforms
-struct MaterialUniforms {
-    kr: vec4<f32>,
-    roughness: f32,
-    _pad1: f32,
-    _pad2: f32,
-    _pad3: f32,
-}
-
-@group(2)
-@binding(0)
-var<uniform> material_uniforms: MaterialUniforms;
-
-fn lambertian_reflection(r: vec3<f32>) -> vec3<f32> {;
-    return r * INV_PI;
-}
-
-fn shade(intensity: vec3<f32>, wo: vec3<f32>, wi: vec3<f32>) -> vec3<f32> {
-    return vec3<f32>(0.0, 0.0, 0.0); // no direct light
-}
-
-//-------------------------------------------------------
-
 // light uniforms
 @group(3)
 @binding(0)
@@ -148,6 +126,10 @@ var ltc_texture_array: texture_2d_array<f32>;// LTC lookup texture
 @binding(9)
 var ltc_sampler: sampler;
 
+@group(3)
+@binding(10)
+var irradiance_texture: texture_2d<f32>;// Irradiance of the infinite light
+
 //-------------------------------------------------------
 const MAX_FLOAT: f32 = 1e+10;
 const PI: f32 = 3.14159265359;
@@ -449,6 +431,15 @@ fn LTC_Evaluate_Disk(N: vec3<f32>, V: vec3<f32>, P: vec3<f32>, Minv: mat3x3<f32>
 }
 
 //-------------------------------------------------------
+// Analytic fit of the split-sum environment BRDF (Karis, "Mobile PBR")
+fn env_brdf_approx(roughness: f32, NdotV: f32) -> vec2<f32> {
+    let c0 = vec4<f32>(-1.0, -0.0275, -0.572, 0.022);
+    let c1 = vec4<f32>(1.0, 0.0425, 1.04, -0.04);
+    let r = roughness * c0 + c1;
+    let a004 = min(r.x * r.x, exp2(-9.28 * NdotV)) * r.x + r.y;
+    return vec2<f32>(-1.04, 1.04) * a004 + r.zw;
+}
+
 fn spherical_texture_lookup(direction: vec3<f32>) -> vec2<f32> {
     // Assumes direction is normalized
     //let u = 0.5 + atan2(direction.z, direction.x) / (2.0 * PI);
@@ -492,180 +483,25 @@ fn vs_main(
     return out;
 }
 
-@fragment
-fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
-    let camera_to_surface = normalize(in.w_position - global_uniforms.camera_position.xyz);
-    var normal = normalize(in.w_normal);
-    if dot(normal, camera_to_surface) > 0.0 {
-        normal = -normal;
-    }
-    var tangent = normalize(in.w_tangent);
-    var bitangent = normalize(cross(normal, tangent));
-    tangent = normalize(cross(bitangent, normal)); // Recompute tangent to ensure orthogonality
-    let tbn = transpose(mat3x3<f32>(tangent, bitangent, normal));//tangent space matrix
-    let wo = tbn * -camera_to_surface;// object to camera vector
-
-    let V = -camera_to_surface;//point to camera
-    let P = in.w_position;
-    let N = normal;
-    let NdotV = saturate(dot(N, V));
-
-    let roughness = max(material_uniforms.roughness, 0.08); // cannot < 0.08
-    var ltc_uv = vec2<f32>(roughness, sqrt(1.0 - NdotV));
-    ltc_uv = ltc_uv * LUT_SCALE + LUT_BIAS;
-
-    let t1 = textureSample(ltc_texture_array, ltc_sampler, ltc_uv, 0);
-    let t2 = textureSample(ltc_texture_array, ltc_sampler, ltc_uv, 1);
-    // Construct inverse matrix
-    let Minv = mat3x3<f32>(
-        vec3<f32>(t1.x, 0.0, t1.y),
-        vec3<f32>(0.0, 1.0, 0.0),
-        vec3<f32>(t1.z, 0.0, t1.w)
-    );
-
-    let spec = material_uniforms.kr.rgb;
-    let m_spec = spec;
-
-    var color = vec3<f32>(0.0);
-    for (var i: u32 = 0; i < light_uniforms.num_directional_lights; i++) {
-        let light = directional_lights[i];
-        let intensity = light.intensity.rgb;
-        var wi = tbn * -normalize(light.direction.xyz);
-        color += shade(intensity, wo, wi);
-    }
-
-    for (var i: u32 = 0; i < light_uniforms.num_sphere_lights; i++) {
-        let light = sphere_lights[i];
-        let position = light.position.xyz;
-        let intensity = light.intensity.rgb;
-        let radius = light.radius;
-        if radius > 0.0 {
-            let l = in.w_position - position;
-            let r = reflect(normalize(camera_to_surface), normal);
-            let center_to_ray = dot(l, r) * r - l;
-            let closest_point = light.position.xyz + center_to_ray * saturate(radius / length(center_to_ray));
-            let light_to_surface = in.w_position - closest_point;
-            let distance = length(light_to_surface);
-            let attenuation = 1.0 / pow(1.0 + distance, 2.0); // Simple quadratic attenuation
-            var wi = tbn * -normalize(light_to_surface);
-            color += shade(intensity * attenuation, wo, wi);
-        } else {
-            let light_to_surface = in.w_position - position;
-            let distance = length(light_to_surface);
-            let attenuation = 1.0 / pow(1.0 + distance, 2.0); // Simple quadratic attenuation
-            var wi = tbn * -normalize(light_to_surface);
-            color += shade(intensity * attenuation, wo, wi);
-        }
-    }
 
-    for (var i: u32 = 0; i < light_uniforms.num_disk_lights; i++) {
-        let light = disk_lights[i];
-        let position = light.position.xyz;
-        let direction = normalize(light.direction.xyz);
-        let intensity = light.intensity.rgb;
-        let radius = light.radius;
-        let cos_inner = light.cos_inner_angle;//cos(light.inner_angle);
-        let cos_outer = light.cos_outer_angle;//cos(light.outer_angle);
-        let u_axis = light.u_axis.xyz;
-        let v_axis = light.v_axis.xyz;
-        let twosided = light.twosided;
-        let center_to_surface = in.w_position - position;
-        let distance = length(center_to_surface);
-        if (distance < 1e-6) {
-            continue;
-        }
-        let dd = dot(center_to_surface, direction);
-        if (dd < 0.0 && twosided == 0) {
-            continue;
-        }
-        //let ndotl = dot(normal, direction);
-        //if (ndotl >= 0.0 && twosided == 0) {
-        //    continue;
-        //}
- 
-        if radius > 0.0 {
-            let ex = radius * u_axis * 0.5;
-            let ey = radius * v_axis * 0.5;
-
-            let a = position - ex - ey;
-            let b = position + ex - ey;
-            let c = position + ex + ey;
-            let d = position - ex + ey;
-
-            let lightPoints = array<vec3<f32>, 4>(a, b, c, d);
-            let diffuse = LTC_Evaluate_Disk(N, V, P, IDENTITY_MAT3, lightPoints);
-            let specular = LTC_Evaluate_Disk(N, V, P, Minv, lightPoints);
-
-            var attenuation = 1.0 / ((1.0 + distance) * (PI * PI)); // Simple quadratic attenuation
-            color += intensity * attenuation * (m_spec * specular);
-        } else {
-            var closest_point = position;
-            let light_to_surface = in.w_position - closest_point;
-            let cos_theta = max(dot(normalize(light_to_surface), direction), 0.0);
-            let cos_delta = cos_inner - cos_outer;
-            var falloff = select(step(cos_outer, cos_theta), clamp((cos_theta - cos_outer) / cos_delta, 0.0, 1.0), cos_delta > 0.0);
-            if radius <= 0.0 {
-                falloff = pow(falloff, 4.0);
-            }
-            let distance = length(light_to_surface);
-            var attenuation = 1.0 / pow(1.0 + distance, 2.0); // Simple quadratic attenuation
-            var wi = tbn * -normalize(light_to_surface);
-            color += shade(intensity * attenuation * falloff, wo, wi);
-        }
-    }
+//-------------------------------------------------------
+// material helpers
 
-    for (var i: u32 = 0; i < light_uniforms.num_rect_lights; i++) 
-    {
-        let light = rect_lights[i];
-        let position = light.position.xyz;
-        let direction = normalize(light.direction.xyz);
-        let intensity = light.intensity.rgb;
-        let twosided = light.twosided;
-        let center_to_surface = in.w_position - position;
-        let distance = length(center_to_surface);
-        if (distance < 1e-6) {
-            continue;
-        }
-        let dd = dot(center_to_surface, direction);
-        if (dd < 0.0 && twosided == 0) {
-            continue;
-        }
-        //let ndotl = dot(normal, direction);
-        //if (ndotl >= 0.0 && twosided == 0) {
-        //    continue;
-        //}
-
-        let a = position - light.u_axis.xyz - light.v_axis.xyz;
-        let b = position - light.u_axis.xyz + light.v_axis.xyz;
-        let c = position + light.u_axis.xyz + light.v_axis.xyz;
-        let d = position + light.u_axis.xyz - light.v_axis.xyz;
-
-        let lightPoints = array<vec3<f32>, 4>(a, b, c, d);
-        let diffuse = LTC_Evaluate_Polygon(N, V, P, IDENTITY_MAT3, lightPoints);
-        let specular = LTC_Evaluate_Polygon(N, V, P, Minv, lightPoints);
-        
-        let attenuation = 1.0 / ((1.0 + distance) * (PI * PI)); // Simple quadratic attenuation
-        color += intensity * attenuation * (m_spec * specular);
-    }
+// Material parameters resolved for the current fragment
+struct Surface {
+    diff: vec3<f32>,
+    spec: vec3<f32>,
+    roughness: f32,
+    // GGX alpha along tangent and bitangent for the punctual lights
+    alpha: vec2<f32>,
+}
 
-    for (var i: u32 = 0; i < light_uniforms.num_infinite_lights; i++) 
-    {
-        let light = infinite_lights[i];
-        let intensity = light.intensity.rgb;
-        let tex_index = light.indices.x;
-        let inv_matrix = light.inv_matrix;
-        let r = normalize(reflect(camera_to_surface, normal));
-        var val = vec3<f32>(1.0);
-        if tex_index >= 0 {
-            let rt = inv_matrix * vec4<f32>(r, 0.0);
-            let rt2 = normalize(rt.xyz);
-            let uv = spherical_texture_lookup(rt2);
-            val = textureSample(light_texture, light_sampler, uv).rgb;
-        }
-        //color += val * intensity;
-        let wi = tbn * r;
-        color += shade(intensity * val, wo, wi);
-    }
+fn lambertian_reflection(r: vec3<f32>) -> vec3<f32> {
+    return r * INV_PI;
+}
 
-    return vec4<f32>(color, 1.0);
+// Same fit as pbrt's TrowbridgeReitzDistribution::RoughnessToAlpha
+fn roughness_to_alpha(roughness: f32) -> f32 {
+    let x = log(max(roughness, 1e-3));
+    return 1.62142 + 0.819955 * x + 0.1734 * x * x + 0.0171201 * x * x * x + 0.000640711 * x * x * x * x;
 }
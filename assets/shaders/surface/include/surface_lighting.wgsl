@@ -0,0 +1,194 @@
+// Light loops shared by the surface shaders.
+// The including shader defines HAS_DIFFUSE and/or HAS_SPECULAR and provides
+// fn shade(intensity, wo, wi, surface) for the punctual lights.
+#include "include/shadows.wgsl"
+
+fn shade_surface(in: VertexOut, surface: Surface) -> vec3<f32> {
+    let camera_to_surface = normalize(in.w_position - global_uniforms.camera_position.xyz);
+    var geometric_normal = normalize(in.w_normal);
+    var normal = get_shading_normal(in.uv, geometric_normal, in.w_tangent);
+    if dot(geometric_normal, camera_to_surface) > 0.0 {
+        geometric_normal = -geometric_normal;
+        normal = -normal;
+    }
+    var tangent = normalize(in.w_tangent);
+    var bitangent = normalize(cross(normal, tangent));
+    tangent = normalize(cross(bitangent, normal)); // Recompute tangent to ensure orthogonality
+    let tbn = transpose(mat3x3<f32>(tangent, bitangent, normal));//tangent space matrix
+    let wo = tbn * -camera_to_surface;// object to camera vector
+
+    let V = -camera_to_surface;//point to camera
+    let P = in.w_position;
+    let N = normal;
+    let NdotV = saturate(dot(N, V));
+
+    let roughness = max(surface.roughness, 0.08); // cannot < 0.08
+    var ltc_uv = vec2<f32>(roughness, sqrt(1.0 - NdotV));
+    ltc_uv = ltc_uv * LUT_SCALE + LUT_BIAS;
+
+    let t1 = textureSample(ltc_texture_array, ltc_sampler, ltc_uv, 0);
+    // Construct inverse matrix
+    let Minv = mat3x3<f32>(
+        vec3<f32>(t1.x, 0.0, t1.y),
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(t1.z, 0.0, t1.w)
+    );
+
+    let m_diff = surface.diff;
+    let m_spec = surface.spec;
+
+    var color = vec3<f32>(0.0);
+    for (var i: u32 = 0; i < light_uniforms.num_directional_lights; i++) {
+        let light = directional_lights[i];
+        let intensity = light.intensity.rgb;
+        var wi = tbn * -normalize(light.direction.xyz);
+        let shadow = get_directional_shadow(light, in.w_position, geometric_normal);
+        color += shade(intensity * shadow, wo, wi, surface);
+    }
+
+    for (var i: u32 = 0; i < light_uniforms.num_sphere_lights; i++) {
+        let light = sphere_lights[i];
+        let position = light.position.xyz;
+        let intensity = light.intensity.rgb * get_point_shadow(light, in.w_position, geometric_normal);
+        let radius = light.radius;
+        if radius > 0.0 {
+            let l = in.w_position - position;
+            let r = reflect(normalize(camera_to_surface), normal);
+            let center_to_ray = dot(l, r) * r - l;
+            let closest_point = light.position.xyz + center_to_ray * saturate(radius / length(center_to_ray));
+            let light_to_surface = in.w_position - closest_point;
+            let distance = length(light_to_surface);
+            let attenuation = 1.0 / pow(1.0 + distance, 2.0); // Simple quadratic attenuation
+            var wi = tbn * -normalize(light_to_surface);
+            color += shade(intensity * attenuation, wo, wi, surface);
+        } else {
+            let light_to_surface = in.w_position - position;
+            let distance = length(light_to_surface);
+            let attenuation = 1.0 / pow(1.0 + distance, 2.0); // Simple quadratic attenuation
+            var wi = tbn * -normalize(light_to_surface);
+            color += shade(intensity * attenuation, wo, wi, surface);
+        }
+    }
+
+    for (var i: u32 = 0; i < light_uniforms.num_disk_lights; i++) {
+        let light = disk_lights[i];
+        let position = light.position.xyz;
+        let direction = normalize(light.direction.xyz);
+        let intensity = light.intensity.rgb;
+        let radius = light.radius;
+        let cos_inner = light.cos_inner_angle;//cos(light.inner_angle);
+        let cos_outer = light.cos_outer_angle;//cos(light.outer_angle);
+        let u_axis = light.u_axis.xyz;
+        let v_axis = light.v_axis.xyz;
+        let twosided = light.twosided;
+        let center_to_surface = in.w_position - position;
+        let distance = length(center_to_surface);
+        if (distance < 1e-6) {
+            continue;
+        }
+        let dd = dot(center_to_surface, direction);
+        if (dd < 0.0 && twosided == 0) {
+            continue;
+        }
+
+        if radius > 0.0 {
+            let ex = radius * u_axis * 0.5;
+            let ey = radius * v_axis * 0.5;
+
+            let a = position - ex - ey;
+            let b = position + ex - ey;
+            let c = position + ex + ey;
+            let d = position - ex + ey;
+
+            let lightPoints = array<vec3<f32>, 4>(a, b, c, d);
+            var lo = vec3<f32>(0.0);
+            #ifdef HAS_DIFFUSE
+            lo += m_diff * LTC_Evaluate_Disk(N, V, P, IDENTITY_MAT3, lightPoints);
+            #endif
+            #ifdef HAS_SPECULAR
+            lo += m_spec * LTC_Evaluate_Disk(N, V, P, Minv, lightPoints);
+            #endif
+
+            var attenuation = 1.0 / ((1.0 + distance) * (PI * PI)); // Simple quadratic attenuation
+            color += intensity * attenuation * lo;
+        } else {
+            var closest_point = position;
+            let light_to_surface = in.w_position - closest_point;
+            let cos_theta = max(dot(normalize(light_to_surface), direction), 0.0);
+            let cos_delta = cos_inner - cos_outer;
+            var falloff = select(step(cos_outer, cos_theta), clamp((cos_theta - cos_outer) / cos_delta, 0.0, 1.0), cos_delta > 0.0);
+            if radius <= 0.0 {
+                falloff = pow(falloff, 4.0);
+            }
+            let distance = length(light_to_surface);
+            var attenuation = 1.0 / pow(1.0 + distance, 2.0); // Simple quadratic attenuation
+            var wi = tbn * -normalize(light_to_surface);
+            let shadow = get_spot_shadow(light, in.w_position, geometric_normal);
+            color += shade(intensity * attenuation * falloff * shadow, wo, wi, surface);
+        }
+    }
+
+    for (var i: u32 = 0; i < light_uniforms.num_rect_lights; i++)
+    {
+        let light = rect_lights[i];
+        let position = light.position.xyz;
+        let direction = normalize(light.direction.xyz);
+        let intensity = light.intensity.rgb;
+        let twosided = light.twosided;
+        let center_to_surface = in.w_position - position;
+        let distance = length(center_to_surface);
+        if (distance < 1e-6) {
+            continue;
+        }
+        let dd = dot(center_to_surface, direction);
+        if (dd < 0.0 && twosided == 0) {
+            continue;
+        }
+
+        let a = position - light.u_axis.xyz - light.v_axis.xyz;
+        let b = position - light.u_axis.xyz + light.v_axis.xyz;
+        let c = position + light.u_axis.xyz + light.v_axis.xyz;
+        let d = position + light.u_axis.xyz - light.v_axis.xyz;
+
+        let lightPoints = array<vec3<f32>, 4>(a, b, c, d);
+        var lo = vec3<f32>(0.0);
+        #ifdef HAS_DIFFUSE
+        lo += m_diff * LTC_Evaluate_Polygon(N, V, P, IDENTITY_MAT3, lightPoints);
+        #endif
+        #ifdef HAS_SPECULAR
+        lo += m_spec * LTC_Evaluate_Polygon(N, V, P, Minv, lightPoints);
+        #endif
+
+        let attenuation = 1.0 / ((1.0 + distance) * (PI * PI)); // Simple quadratic attenuation
+        color += intensity * attenuation * lo;
+    }
+
+    for (var i: u32 = 0; i < light_uniforms.num_infinite_lights; i++)
+    {
+        let light = infinite_lights[i];
+        let intensity = light.intensity.rgb;
+        let tex_index = light.indices.x;
+        let inv_matrix = light.inv_matrix;
+        #ifdef HAS_DIFFUSE
+        var irradiance = vec3<f32>(1.0);
+        if tex_index >= 0 {
+            let nt = normalize((inv_matrix * vec4<f32>(N, 0.0)).xyz);
+            irradiance = textureSampleLevel(irradiance_texture, light_sampler, spherical_texture_lookup(nt), 0.0).rgb;
+        }
+        color += intensity * m_diff * irradiance;
+        #endif
+        #ifdef HAS_SPECULAR
+        let r = normalize(reflect(camera_to_surface, normal));
+        var prefiltered = vec3<f32>(1.0);
+        if tex_index >= 0 {
+            let rt = normalize((inv_matrix * vec4<f32>(r, 0.0)).xyz);
+            let lod = roughness * f32(max(light.indices.y - 1, 0));
+            prefiltered = textureSampleLevel(light_texture, light_sampler, spherical_texture_lookup(rt), lod).rgb;
+        }
+        let ab = env_brdf_approx(roughness, NdotV);
+        color += intensity * (m_spec * ab.x + ab.y) * prefiltered;
+        #endif
+    }
+
+    return color;
+}
@@ -0,0 +1,98 @@
+// Lambertian diffuse with a GGX specular lobe (plastic, uber, fourier)
+// Permutations: KD_TEXTURE, KS_TEXTURE, ROUGHNESS_TEXTURE, REMAP_ROUGHNESS, BUMPMAP_TEXTURE, MIX_AMOUNT, MIX_OVERLAY
+#define HAS_DIFFUSE
+#define HAS_SPECULAR
+#include "include/surface_common.wgsl"
+#include "include/microfacet.wgsl"
+#include "include/material_textures.wgsl"
+#include "include/surface_lighting.wgsl"
+#include "include/material_mix.wgsl"
+
+//-------------------------------------------------------
+// material definitions
+
+// material uniforms
+struct MaterialUniforms {
+    kd: vec4<f32>,
+    ks: vec4<f32>,
+    #ifdef ROUGHNESS_TEXTURE
+    roughness: vec4<f32>,
+    #endif
+    #ifndef ROUGHNESS_TEXTURE
+    roughness: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+    #endif
+    #ifdef BUMPMAP_TEXTURE
+    bumpmap: vec4<f32>,
+    #endif
+    #ifdef MIX_AMOUNT
+    amount: vec4<f32>,
+    #endif
+}
+
+@group(2)
+@binding(0)
+var<uniform> material_uniforms: MaterialUniforms;
+
+fn get_kd(uv: vec2<f32>) -> vec3<f32> {
+    #ifdef KD_TEXTURE
+    return textureSample(kd_texture, kd_sampler, get_texture_uv(material_uniforms.kd, uv)).rgb;
+    #endif
+    #ifndef KD_TEXTURE
+    return material_uniforms.kd.rgb;
+    #endif
+}
+
+fn get_ks(uv: vec2<f32>) -> vec3<f32> {
+    #ifdef KS_TEXTURE
+    return textureSample(ks_texture, ks_sampler, get_texture_uv(material_uniforms.ks, uv)).rgb;
+    #endif
+    #ifndef KS_TEXTURE
+    return material_uniforms.ks.rgb;
+    #endif
+}
+
+fn get_roughness(uv: vec2<f32>) -> f32 {
+    #ifdef ROUGHNESS_TEXTURE
+    var roughness = textureSample(roughness_texture, roughness_sampler, get_texture_uv(material_uniforms.roughness, uv)).r;
+    #ifdef REMAP_ROUGHNESS
+    roughness = roughness_to_alpha(roughness);
+    #endif
+    return roughness;
+    #endif
+    #ifndef ROUGHNESS_TEXTURE
+    return material_uniforms.roughness;
+    #endif
+}
+
+fn matte(wo: vec3<f32>, wi: vec3<f32>, kd: vec3<f32>) -> vec3<f32> {
+    let diffuse = max(dot(vec3<f32>(0.0, 0.0, 1.0), wi), 0.0);
+    let c1 = lambertian_reflection(kd);
+    return diffuse * c1;
+}
+
+fn glossy(wo: vec3<f32>, wi: vec3<f32>, ks: vec3<f32>, alpha: vec2<f32>) -> vec3<f32> {
+    let wh = normalize(wo + wi);
+    let f = fresnel_dielectric(dot(wi, wh), 1.5);
+    return ks * f * torrance_sparrow(wo, wi, alpha) * max(wi.z, 0.0);
+}
+
+fn shade(intensity: vec3<f32>, wo: vec3<f32>, wi: vec3<f32>, surface: Surface) -> vec3<f32> {
+    return (matte(wo, wi, surface.diff) + glossy(wo, wi, surface.spec, surface.alpha)) * intensity;
+}
+
+//-------------------------------------------------------
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let weight = get_layer_weight(in.uv);
+    var surface: Surface;
+    surface.diff = get_kd(in.uv);
+    surface.spec = get_ks(in.uv);
+    surface.roughness = get_roughness(in.uv);
+    surface.alpha = vec2<f32>(max(surface.roughness, 1e-3));
+    let color = shade_surface(in, surface);
+    return layer_output(weight, vec4<f32>(color, 1.0));
+}
@@ -0,0 +1,57 @@
+// Lambertian diffuse only (matte)
+// Permutations: KD_TEXTURE, BUMPMAP_TEXTURE, MIX_AMOUNT, MIX_OVERLAY
+#define HAS_DIFFUSE
+#include "include/surface_common.wgsl"
+#include "include/material_textures.wgsl"
+#include "include/surface_lighting.wgsl"
+#include "include/material_mix.wgsl"
+
+//-------------------------------------------------------
+// material definitions
+
+// material uniforms
+struct MaterialUniforms {
+    kd: vec4<f32>,
+    #ifdef BUMPMAP_TEXTURE
+    bumpmap: vec4<f32>,
+    #endif
+    #ifdef MIX_AMOUNT
+    amount: vec4<f32>,
+    #endif
+}
+
+@group(2)
+@binding(0)
+var<uniform> material_uniforms: MaterialUniforms;
+
+fn get_kd(uv: vec2<f32>) -> vec3<f32> {
+    #ifdef KD_TEXTURE
+    return textureSample(kd_texture, kd_sampler, get_texture_uv(material_uniforms.kd, uv)).rgb;
+    #endif
+    #ifndef KD_TEXTURE
+    return material_uniforms.kd.rgb;
+    #endif
+}
+
+fn matte(wo: vec3<f32>, wi: vec3<f32>, kd: vec3<f32>) -> vec3<f32> {
+    let diffuse = max(dot(vec3<f32>(0.0, 0.0, 1.0), wi), 0.0);
+    let c1 = lambertian_reflection(kd);
+    return diffuse * c1;
+}
+
+fn shade(intensity: vec3<f32>, wo: vec3<f32>, wi: vec3<f32>, surface: Surface) -> vec3<f32> {
+    return matte(wo, wi, surface.diff) * intensity;
+}
+
+//-------------------------------------------------------
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let weight = get_layer_weight(in.uv);
+    var surface: Surface;
+    surface.diff = get_kd(in.uv);
+    surface.spec = vec3<f32>(0.0);
+    surface.roughness = 0.5;
+    let color = shade_surface(in, surface);
+    return layer_output(weight, vec4<f32>(color, 1.0));
+}
@@ -0,0 +1,73 @@
+// GGX specular only (mirror, reflection passes of glass and uber)
+// Permutations: KR_TEXTURE, ROUGHNESS_TEXTURE, REMAP_ROUGHNESS, BUMPMAP_TEXTURE, MIX_AMOUNT, MIX_OVERLAY
+#define HAS_SPECULAR
+#include "include/surface_common.wgsl"
+#include "include/material_textures.wgsl"
+#include "include/surface_lighting.wgsl"
+#include "include/material_mix.wgsl"
+
+//-------------------------------------------------------
+// material definitions
+
+// material uniforms
+struct MaterialUniforms {
+    kr: vec4<f32>,
+    #ifdef ROUGHNESS_TEXTURE
+    roughness: vec4<f32>,
+    #endif
+    #ifndef ROUGHNESS_TEXTURE
+    roughness: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+    #endif
+    #ifdef BUMPMAP_TEXTURE
+    bumpmap: vec4<f32>,
+    #endif
+    #ifdef MIX_AMOUNT
+    amount: vec4<f32>,
+    #endif
+}
+
+@group(2)
+@binding(0)
+var<uniform> material_uniforms: MaterialUniforms;
+
+fn get_kr(uv: vec2<f32>) -> vec3<f32> {
+    #ifdef KR_TEXTURE
+    return textureSample(kr_texture, kr_sampler, get_texture_uv(material_uniforms.kr, uv)).rgb;
+    #endif
+    #ifndef KR_TEXTURE
+    return material_uniforms.kr.rgb;
+    #endif
+}
+
+fn get_roughness(uv: vec2<f32>) -> f32 {
+    #ifdef ROUGHNESS_TEXTURE
+    var roughness = textureSample(roughness_texture, roughness_sampler, get_texture_uv(material_uniforms.roughness, uv)).r;
+    #ifdef REMAP_ROUGHNESS
+    roughness = roughness_to_alpha(roughness);
+    #endif
+    return roughness;
+    #endif
+    #ifndef ROUGHNESS_TEXTURE
+    return material_uniforms.roughness;
+    #endif
+}
+
+fn shade(intensity: vec3<f32>, wo: vec3<f32>, wi: vec3<f32>, surface: Surface) -> vec3<f32> {
+    return vec3<f32>(0.0, 0.0, 0.0); // no direct light
+}
+
+//-------------------------------------------------------
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let weight = get_layer_weight(in.uv);
+    var surface: Surface;
+    surface.diff = vec3<f32>(0.0);
+    surface.spec = get_kr(in.uv);
+    surface.roughness = get_roughness(in.uv);
+    let color = shade_surface(in, surface);
+    return layer_output(weight, vec4<f32>(color, 1.0));
+}
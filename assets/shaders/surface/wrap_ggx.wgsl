@@ -0,0 +1,97 @@
+// Wrap-lighting approximation of subsurface scattering with a GGX specular lobe
+// (subsurface, kdsubsurface, translucent, hair)
+// Permutations: KD_TEXTURE, KR_TEXTURE, ROUGHNESS_TEXTURE, REMAP_ROUGHNESS, BUMPMAP_TEXTURE, MIX_AMOUNT, MIX_OVERLAY
+#define HAS_DIFFUSE
+#define HAS_SPECULAR
+#include "include/surface_common.wgsl"
+#include "include/microfacet.wgsl"
+#include "include/material_textures.wgsl"
+#include "include/surface_lighting.wgsl"
+#include "include/material_mix.wgsl"
+
+//-------------------------------------------------------
+// material definitions
+
+// material uniforms
+struct MaterialUniforms {
+    kd: vec4<f32>,
+    kr: vec4<f32>,
+    scatter: vec4<f32>, // tint of the light wrapped past the terminator
+    #ifdef ROUGHNESS_TEXTURE
+    roughness: vec4<f32>,
+    #else
+    roughness: f32,
+    #endif
+    wrap: f32, // 0: lambertian, 1: light wraps all around
+    eta: f32,
+    #ifdef BUMPMAP_TEXTURE
+    bumpmap: vec4<f32>,
+    #endif
+    #ifdef MIX_AMOUNT
+    amount: vec4<f32>,
+    #endif
+}
+
+@group(2)
+@binding(0)
+var<uniform> material_uniforms: MaterialUniforms;
+
+fn get_kd(uv: vec2<f32>) -> vec3<f32> {
+    #ifdef KD_TEXTURE
+    return textureSample(kd_texture, kd_sampler, get_texture_uv(material_uniforms.kd, uv)).rgb;
+    #else
+    return material_uniforms.kd.rgb;
+    #endif
+}
+
+fn get_kr(uv: vec2<f32>) -> vec3<f32> {
+    #ifdef KR_TEXTURE
+    return textureSample(kr_texture, kr_sampler, get_texture_uv(material_uniforms.kr, uv)).rgb;
+    #else
+    return material_uniforms.kr.rgb;
+    #endif
+}
+
+fn get_roughness(uv: vec2<f32>) -> f32 {
+    #ifdef ROUGHNESS_TEXTURE
+    var roughness = textureSample(roughness_texture, roughness_sampler, get_texture_uv(material_uniforms.roughness, uv)).r;
+    #ifdef REMAP_ROUGHNESS
+    roughness = roughness_to_alpha(roughness);
+    #endif
+    return roughness;
+    #else
+    return material_uniforms.roughness;
+    #endif
+}
+
+fn wrapped_diffuse(wi: vec3<f32>, kd: vec3<f32>) -> vec3<f32> {
+    let wrap = material_uniforms.wrap;
+    let cos_i = wi.z;
+    let lambert = max(cos_i, 0.0);
+    let wrapped = max((cos_i + wrap) / ((1.0 + wrap) * (1.0 + wrap)), 0.0);
+    let scatter = max(wrapped - lambert, 0.0);
+    return lambertian_reflection(kd) * (min(wrapped, lambert) + material_uniforms.scatter.rgb * scatter);
+}
+
+fn shade(intensity: vec3<f32>, wo: vec3<f32>, wi: vec3<f32>, surface: Surface) -> vec3<f32> {
+    let wh = normalize(wo + wi);
+    let f = fresnel_schlick(surface.spec, dot(wi, wh));
+    let specular = f * torrance_sparrow(wo, wi, surface.alpha) * max(wi.z, 0.0);
+    return (wrapped_diffuse(wi, surface.diff) + specular) * intensity;
+}
+
+//-------------------------------------------------------
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let weight = get_layer_weight(in.uv);
+    var surface: Surface;
+    surface.diff = get_kd(in.uv);
+    // reflectance at normal incidence of the dielectric boundary
+    let r0 = (material_uniforms.eta - 1.0) / (material_uniforms.eta + 1.0);
+    surface.spec = get_kr(in.uv) * r0 * r0;
+    surface.roughness = get_roughness(in.uv);
+    surface.alpha = vec2<f32>(max(surface.roughness, 1e-3));
+    let color = shade_surface(in, surface);
+    return layer_output(weight, vec4<f32>(color, 1.0));
+}
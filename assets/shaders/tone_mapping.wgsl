@@ -0,0 +1,146 @@
+// Final pass of the lighting view: ambient occlusion, exposure, tone mapping,
+// the false colour overlay and the conversion to sRGB.
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+var<private> positions: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(-1.0,  1.0),
+    vec2<f32>( 1.0,  1.0),
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>( 1.0,  1.0),
+    vec2<f32>( 1.0, -1.0),
+);
+
+var<private> uv: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>( 0.0,  1.0),
+    vec2<f32>( 0.0,  0.0),
+    vec2<f32>( 1.0,  0.0),
+    vec2<f32>( 0.0,  1.0),
+    vec2<f32>( 1.0,  0.0),
+    vec2<f32>( 1.0,  1.0),
+);
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var result: VertexOutput;
+    result.position = vec4<f32>(positions[vertex_index], 0.0, 1.0);
+    result.uv = uv[vertex_index];
+    return result;
+}
+
+const TONE_MAPPING_LINEAR_CLAMP: u32 = 0u;
+const TONE_MAPPING_REINHARD: u32 = 1u;
+const TONE_MAPPING_ACES_FILMIC: u32 = 2u;
+
+struct ToneMappingUniforms {
+    exposure: f32, // linear scale, 2^stops
+    tone_mapping: u32,
+    false_color: u32,
+    ssao: u32,
+}
+
+@group(0)
+@binding(0)
+var<uniform> tone_mapping_uniforms: ToneMappingUniforms;
+
+@group(0)
+@binding(1)
+var color_texture: texture_2d<f32>;
+
+@group(0)
+@binding(2)
+var ao_texture: texture_2d<f32>;
+
+fn l2srgb(value: f32) -> f32 {
+    return select(
+        12.92 * value,
+        1.055 * pow(value, 1.0 / 2.4) - 0.055,
+        value > 0.0031308
+    );
+}
+
+fn linear_to_srgb(value: vec3<f32>) -> vec3<f32> {
+    return vec3<f32>(
+        l2srgb(value.r),
+        l2srgb(value.g),
+        l2srgb(value.b)
+    );
+}
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// Box blur over the 4x4 noise tile of the SSAO pass
+fn get_ambient_occlusion(pixel: vec2<i32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(ao_texture));
+    var ao = 0.0;
+    for (var y: i32 = -2; y < 2; y++) {
+        for (var x: i32 = -2; x < 2; x++) {
+            let p = clamp(pixel + vec2<i32>(x, y), vec2<i32>(0), size - vec2<i32>(1));
+            ao += textureLoad(ao_texture, p, 0).r;
+        }
+    }
+    return ao / 16.0;
+}
+
+// Narkowicz's fit of the ACES reference rendering transform
+fn aces_filmic(x: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return (x * (a * x + b)) / (x * (c * x + d) + e);
+}
+
+fn tone_map(color: vec3<f32>) -> vec3<f32> {
+    switch tone_mapping_uniforms.tone_mapping {
+        case TONE_MAPPING_REINHARD: {
+            return color / (vec3<f32>(1.0) + color);
+        }
+        case TONE_MAPPING_ACES_FILMIC: {
+            return aces_filmic(color);
+        }
+        default: {
+            return color;
+        }
+    }
+}
+
+// Luminance in stops around middle gray: blue below -4, green at 0, red above +4
+fn false_color(color: vec3<f32>) -> vec3<f32> {
+    let stops = log2(max(luminance(color), 1e-6) / 0.18);
+    let t = clamp((stops + 4.0) / 8.0, 0.0, 1.0);
+    let ramp = array<vec3<f32>, 5>(
+        vec3<f32>(0.0, 0.0, 1.0),
+        vec3<f32>(0.0, 1.0, 1.0),
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(1.0, 1.0, 0.0),
+        vec3<f32>(1.0, 0.0, 0.0),
+    );
+    let x = t * 4.0;
+    let i = min(u32(x), 3u);
+    return mix(ramp[i], ramp[i + 1u], x - f32(i));
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(color_texture));
+    let pixel = vec2<i32>(clamp(vertex.uv * size, vec2<f32>(0.0), size - vec2<f32>(1.0)));
+    let color = textureLoad(color_texture, pixel, 0);
+    var rgb = color.rgb;
+    if tone_mapping_uniforms.ssao != 0u {
+        rgb *= get_ambient_occlusion(pixel);
+    }
+    rgb *= tone_mapping_uniforms.exposure;
+    var mapped = clamp(tone_map(rgb), vec3<f32>(0.0), vec3<f32>(1.0));
+    if tone_mapping_uniforms.false_color != 0u {
+        mapped = mix(mapped, false_color(rgb), 0.75);
+    }
+    let srgb = clamp(linear_to_srgb(mapped), vec3<f32>(0.0), vec3<f32>(1.0));
+    return vec4<f32>(srgb, color.a);
+}
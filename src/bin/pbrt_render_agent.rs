@@ -0,0 +1,95 @@
+//! Renders scenes sent by pbrt-ui on a machine without a display.
+//!
+//! pbrt_render_agent --pbrt <path to pbrt> [--bind <address>] [--port <port>] [--token <token>]
+//!     [--max-jobs <n>] [--work-dir <dir>]
+//!
+//! The agent listens on 127.0.0.1 unless `--bind` says otherwise, and other addresses need
+//! a token, also read from PBRT_RENDER_AGENT_TOKEN, which the UI sends with each job.
+
+use pbrt_ui::io::remote::DEFAULT_AGENT_PORT;
+use pbrt_ui::io::remote::RenderAgent;
+
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::TcpListener;
+use std::path::PathBuf;
+
+const TOKEN_VARIABLE: &str = "PBRT_RENDER_AGENT_TOKEN";
+
+fn print_usage() {
+    eprintln!(
+        "Usage: pbrt_render_agent --pbrt <path> [--bind <address>] [--port <port>] [--token <token>] [--max-jobs <n>] [--work-dir <dir>]"
+    );
+}
+
+fn exit_with_usage() -> ! {
+    print_usage();
+    std::process::exit(2);
+}
+
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let mut pbrt_path = PathBuf::from("pbrt");
+    let mut bind = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let mut port = DEFAULT_AGENT_PORT;
+    let mut token = std::env::var(TOKEN_VARIABLE).unwrap_or_default();
+    let mut max_jobs = 1;
+    let mut work_dir = std::env::temp_dir().join("pbrt_render_agent");
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "-h" | "--help" => {
+                print_usage();
+                return;
+            }
+            "--pbrt" | "--bind" | "--port" | "--token" | "--max-jobs" | "--work-dir" => args.next(),
+            _ => None,
+        };
+        let Some(value) = value else {
+            exit_with_usage();
+        };
+        match arg.as_str() {
+            "--pbrt" => pbrt_path = PathBuf::from(value),
+            "--bind" => bind = value.parse().unwrap_or_else(|_| exit_with_usage()),
+            "--port" => port = value.parse().unwrap_or_else(|_| exit_with_usage()),
+            "--token" => token = value,
+            "--max-jobs" => match value.parse() {
+                Ok(value) if value > 0 => max_jobs = value,
+                _ => exit_with_usage(),
+            },
+            _ => work_dir = PathBuf::from(value),
+        }
+    }
+
+    // Anyone who can reach the agent can run pbrt on it
+    if !bind.is_loopback() && token.is_empty() {
+        log::error!(
+            "Listening on {} needs a token, give one with --token or {}",
+            bind,
+            TOKEN_VARIABLE
+        );
+        std::process::exit(2);
+    }
+
+    let listener = match TcpListener::bind((bind, port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Failed to listen on {}:{}: {}", bind, port, e);
+            std::process::exit(1);
+        }
+    };
+    log::info!(
+        "Render agent on {}:{} with {}, up to {} jobs in {}",
+        bind,
+        port,
+        pbrt_path.display(),
+        max_jobs,
+        work_dir.display()
+    );
+    let agent = RenderAgent::new(&pbrt_path, &work_dir, &token, max_jobs);
+    if let Err(e) = agent.serve(listener) {
+        log::error!("Render agent stopped: {}", e);
+        std::process::exit(1);
+    }
+}
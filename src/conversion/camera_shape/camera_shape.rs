@@ -0,0 +1,60 @@
+use crate::model::base::Vector3;
+
+/// Lines of a camera gizmo in camera space, looking down +z.
+pub struct CameraShape {
+    pub lines: Vec<Vec<Vector3>>,
+    pub focal_lines: Vec<Vec<Vector3>>, // Focal plane and lens, empty without depth of field
+}
+
+pub(super) const GATE_DEPTH: f32 = 1.0; // Distance of the drawn film gate
+const CIRCLE_DIVISIONS: usize = 32;
+const MAX_FOCAL_DISTANCE: f32 = 1e5; // pbrt's default of 1e6 means no focal plane
+
+pub(super) fn create_rect_points(window: &[f32; 4], scale: f32, z: f32) -> Vec<Vector3> {
+    let [x0, x1, y0, y1] = *window;
+    return vec![
+        Vector3::new(x0 * scale, y0 * scale, z),
+        Vector3::new(x1 * scale, y0 * scale, z),
+        Vector3::new(x1 * scale, y1 * scale, z),
+        Vector3::new(x0 * scale, y1 * scale, z),
+        Vector3::new(x0 * scale, y0 * scale, z),
+    ];
+}
+
+/// Circle in the plane perpendicular to `axis` (0: x, 1: y, 2: z).
+pub(super) fn create_circle_points(radius: f32, axis: usize, z: f32) -> Vec<Vector3> {
+    let mut points = Vec::with_capacity(CIRCLE_DIVISIONS + 1);
+    for i in 0..=CIRCLE_DIVISIONS {
+        let angle = (i as f32 / CIRCLE_DIVISIONS as f32) * std::f32::consts::PI * 2.0;
+        let (c, s) = (angle.cos() * radius, angle.sin() * radius);
+        let point = match axis {
+            0 => Vector3::new(z, c, s),
+            1 => Vector3::new(s, z, c),
+            _ => Vector3::new(c, s, z),
+        };
+        points.push(point);
+    }
+    return points;
+}
+
+/// Triangle above a gate rectangle pointing to the camera up.
+pub(super) fn create_up_marker(window: &[f32; 4], scale: f32, z: f32) -> Vec<Vector3> {
+    let [x0, x1, _, y1] = *window;
+    let half = (x1 - x0) * scale * 0.15;
+    let cx = (x0 + x1) * 0.5 * scale;
+    let base = y1 * scale + half * 0.3;
+    return vec![
+        Vector3::new(cx - half, base, z),
+        Vector3::new(cx + half, base, z),
+        Vector3::new(cx, base + half, z),
+        Vector3::new(cx - half, base, z),
+    ];
+}
+
+/// Focal distance when the lens has an aperture.
+pub(super) fn get_depth_of_field(lens_radius: f32, focal_distance: f32) -> Option<f32> {
+    if lens_radius > 0.0 && focal_distance > 0.0 && focal_distance < MAX_FOCAL_DISTANCE {
+        return Some(focal_distance);
+    }
+    return None;
+}
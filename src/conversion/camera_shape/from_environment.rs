@@ -0,0 +1,22 @@
+use super::camera_shape::*;
+use crate::model::base::Vector3;
+
+const SPHERE_RADIUS: f32 = 0.5;
+
+/// The environment camera sees every direction, drawn as a sphere with the forward axis.
+pub fn create_camera_shape_from_environment() -> Option<CameraShape> {
+    let mut lines = Vec::new();
+    for axis in 0..3 {
+        lines.push(create_circle_points(SPHERE_RADIUS, axis, 0.0));
+    }
+    // The centre of the lat-long image looks down +z
+    lines.push(vec![Vector3::zero(), Vector3::new(0.0, 0.0, GATE_DEPTH)]);
+    lines.push(vec![
+        Vector3::new(0.0, SPHERE_RADIUS, 0.0),
+        Vector3::new(0.0, SPHERE_RADIUS * 1.5, 0.0),
+    ]);
+    return Some(CameraShape {
+        lines,
+        focal_lines: Vec::new(),
+    });
+}
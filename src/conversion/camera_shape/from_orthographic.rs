@@ -0,0 +1,41 @@
+use super::camera_shape::*;
+use super::screen_window::*;
+use crate::model::base::PropertyMap;
+use crate::model::base::Vector3;
+
+pub fn create_camera_shape_from_orthographic(
+    camera: &PropertyMap,
+    film: Option<&PropertyMap>,
+) -> Option<CameraShape> {
+    let lens_radius = camera.find_one_float("lensradius").unwrap_or(0.0);
+    let focal_distance = camera.find_one_float("focaldistance").unwrap_or(1e6);
+    let screen = get_screen_window(camera, film);
+    let crop = get_crop_window(film);
+    // The screen window is in camera space units, draw a box as deep as it is wide
+    let depth = (screen[1] - screen[0]).max(screen[3] - screen[2]);
+
+    let mut lines = Vec::new();
+    let near = create_rect_points(&screen, 1.0, 0.0);
+    let far = create_rect_points(&screen, 1.0, depth);
+    for i in 0..4 {
+        lines.push(vec![near[i], far[i]]);
+    }
+    if !is_full_crop_window(&crop) {
+        let window = get_cropped_screen_window(&screen, &crop);
+        lines.push(create_rect_points(&window, 1.0, 0.0));
+    }
+    lines.push(create_up_marker(&screen, 1.0, 0.0));
+    lines.push(near);
+    lines.push(far);
+
+    let mut focal_lines = Vec::new();
+    if let Some(distance) = get_depth_of_field(lens_radius, focal_distance) {
+        let plane = create_rect_points(&screen, 1.0, distance);
+        for corner in plane.iter().take(4) {
+            focal_lines.push(vec![Vector3::new(corner.x, corner.y, depth), *corner]);
+        }
+        focal_lines.push(plane);
+        focal_lines.push(create_circle_points(lens_radius, 2, 0.0));
+    }
+    return Some(CameraShape { lines, focal_lines });
+}
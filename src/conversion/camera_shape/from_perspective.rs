@@ -0,0 +1,53 @@
+use super::camera_shape::*;
+use super::screen_window::*;
+use crate::model::base::PropertyMap;
+use crate::model::base::Vector3;
+
+pub fn create_camera_shape_from_perspective(
+    camera: &PropertyMap,
+    film: Option<&PropertyMap>,
+) -> Option<CameraShape> {
+    let fov = camera.find_one_float("fov").unwrap_or(90.0);
+    let lens_radius = camera.find_one_float("lensradius").unwrap_or(0.0);
+    let focal_distance = camera.find_one_float("focaldistance").unwrap_or(1e6);
+    let screen = get_screen_window(camera, film);
+    let crop = get_crop_window(film);
+    // The screen window lies on the z = 1 plane scaled by tan(fov / 2)
+    let tan = (fov.clamp(0.01, 179.0) * 0.5).to_radians().tan();
+
+    let mut lines = Vec::new();
+    let gate = create_rect_points(&screen, tan * GATE_DEPTH, GATE_DEPTH);
+    for corner in gate.iter().take(4) {
+        lines.push(vec![Vector3::zero(), *corner]);
+    }
+    lines.push(gate);
+    if !is_full_crop_window(&crop) {
+        let window = get_cropped_screen_window(&screen, &crop);
+        lines.push(create_rect_points(&window, tan * GATE_DEPTH, GATE_DEPTH));
+    }
+    lines.push(create_up_marker(&screen, tan * GATE_DEPTH, GATE_DEPTH));
+
+    let mut focal_lines = Vec::new();
+    if let Some(distance) = get_depth_of_field(lens_radius, focal_distance) {
+        let plane = create_rect_points(&screen, tan * distance, distance);
+        // Frustum from the gate to the focal plane
+        for i in 0..4 {
+            let from = plane[i] * (GATE_DEPTH / distance);
+            focal_lines.push(vec![from, plane[i]]);
+        }
+        let (cx, cy) = ((screen[0] + screen[1]) * 0.5, (screen[2] + screen[3]) * 0.5);
+        let center = Vector3::new(cx * tan * distance, cy * tan * distance, distance);
+        let size = (screen[1] - screen[0]) * tan * distance * 0.05;
+        focal_lines.push(vec![
+            center - Vector3::new(size, 0.0, 0.0),
+            center + Vector3::new(size, 0.0, 0.0),
+        ]);
+        focal_lines.push(vec![
+            center - Vector3::new(0.0, size, 0.0),
+            center + Vector3::new(0.0, size, 0.0),
+        ]);
+        focal_lines.push(plane);
+        focal_lines.push(create_circle_points(lens_radius, 2, 0.0));
+    }
+    return Some(CameraShape { lines, focal_lines });
+}
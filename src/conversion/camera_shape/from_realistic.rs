@@ -0,0 +1,54 @@
+use super::camera_shape::*;
+use crate::model::base::PropertyMap;
+use crate::model::base::Vector3;
+
+const BODY_SIZE: [f32; 3] = [0.15, 0.1, 0.25]; // Half width, half height and depth
+const BARREL_RADIUS: f32 = 0.08;
+const BARREL_LENGTH: f32 = 0.15;
+
+/// Camera body with a lens barrel; the field of view depends on the lens file.
+pub fn create_camera_shape_from_realistic(camera: &PropertyMap) -> Option<CameraShape> {
+    let focus_distance = camera.find_one_float("focusdistance").unwrap_or(10.0);
+    let aperture = camera.find_one_float("aperturediameter").unwrap_or(1.0);
+
+    let mut lines = Vec::new();
+    let [w, h, d] = BODY_SIZE;
+    let body = [-w, w, -h, h];
+    let back = create_rect_points(&body, 1.0, -d);
+    let front = create_rect_points(&body, 1.0, 0.0);
+    for i in 0..4 {
+        lines.push(vec![back[i], front[i]]);
+    }
+    lines.push(back);
+    lines.push(front);
+    lines.push(create_circle_points(BARREL_RADIUS, 2, 0.0));
+    lines.push(create_circle_points(BARREL_RADIUS, 2, BARREL_LENGTH));
+    for i in 0..4 {
+        let angle = i as f32 * std::f32::consts::FRAC_PI_2;
+        let (x, y) = (angle.cos() * BARREL_RADIUS, angle.sin() * BARREL_RADIUS);
+        lines.push(vec![
+            Vector3::new(x, y, 0.0),
+            Vector3::new(x, y, BARREL_LENGTH),
+        ]);
+    }
+    lines.push(create_up_marker(&body, 1.0, 0.0));
+
+    // Aperture (in millimetres) and the point in focus
+    let mut focal_lines = Vec::new();
+    let radius = (aperture * 0.5e-3).min(BARREL_RADIUS);
+    focal_lines.push(create_circle_points(radius, 2, BARREL_LENGTH));
+    if focus_distance > 0.0 {
+        let size = BARREL_RADIUS;
+        let center = Vector3::new(0.0, 0.0, focus_distance);
+        focal_lines.push(vec![Vector3::new(0.0, 0.0, BARREL_LENGTH), center]);
+        focal_lines.push(vec![
+            center - Vector3::new(size, 0.0, 0.0),
+            center + Vector3::new(size, 0.0, 0.0),
+        ]);
+        focal_lines.push(vec![
+            center - Vector3::new(0.0, size, 0.0),
+            center + Vector3::new(0.0, size, 0.0),
+        ]);
+    }
+    return Some(CameraShape { lines, focal_lines });
+}
@@ -0,0 +1,45 @@
+mod camera_shape;
+mod from_environment;
+mod from_orthographic;
+mod from_perspective;
+mod from_realistic;
+pub mod screen_window;
+
+use crate::model::scene::CameraComponent;
+use crate::model::scene::FilmComponent;
+pub use camera_shape::CameraShape;
+use from_environment::create_camera_shape_from_environment;
+use from_orthographic::create_camera_shape_from_orthographic;
+use from_perspective::create_camera_shape_from_perspective;
+use from_realistic::create_camera_shape_from_realistic;
+
+use crate::model::scene::Node;
+use std::sync::{Arc, RwLock};
+
+pub fn create_camera_shape(node: &Arc<RwLock<Node>>) -> Option<CameraShape> {
+    let node = node.read().unwrap();
+    let camera = node.get_component::<CameraComponent>()?;
+    let film = node.get_component::<FilmComponent>().map(|f| &f.props);
+    let camera_type = camera
+        .props
+        .find_one_string("type")
+        .unwrap_or("perspective".to_string());
+    match camera_type.as_str() {
+        "perspective" => {
+            return create_camera_shape_from_perspective(&camera.props, film);
+        }
+        "orthographic" => {
+            return create_camera_shape_from_orthographic(&camera.props, film);
+        }
+        "environment" | "spherical" => {
+            return create_camera_shape_from_environment();
+        }
+        "realistic" => {
+            return create_camera_shape_from_realistic(&camera.props);
+        }
+        _ => {
+            log::warn!("Unknown camera type: {}", camera_type);
+        }
+    }
+    return None;
+}
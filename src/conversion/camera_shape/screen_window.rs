@@ -0,0 +1,116 @@
+use crate::model::base::PropertyMap;
+
+const DEFAULT_RESOLUTION: (i32, i32) = (1280, 720);
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    return a + t * (b - a);
+}
+
+pub fn get_film_resolution(film: Option<&PropertyMap>) -> (i32, i32) {
+    let (mut width, mut height) = DEFAULT_RESOLUTION;
+    if let Some(film) = film {
+        width = film.find_one_int("xresolution").unwrap_or(width).max(1);
+        height = film.find_one_int("yresolution").unwrap_or(height).max(1);
+    }
+    return (width, height);
+}
+
+/// `frameaspectratio`, or the aspect of the film resolution.
+pub fn get_frame_aspect_ratio(camera: &PropertyMap, film: Option<&PropertyMap>) -> f32 {
+    if let Some(frame) = camera.find_one_float("frameaspectratio") {
+        if frame > 0.0 {
+            return frame;
+        }
+    }
+    let (width, height) = get_film_resolution(film);
+    return width as f32 / height as f32;
+}
+
+/// `screenwindow` as [xmin, xmax, ymin, ymax]; the shorter side spans [-1, 1] by default.
+pub fn get_screen_window(camera: &PropertyMap, film: Option<&PropertyMap>) -> [f32; 4] {
+    let window = camera.get_floats("screenwindow");
+    if window.len() == 4 {
+        return [window[0], window[1], window[2], window[3]];
+    }
+    let frame = get_frame_aspect_ratio(camera, film);
+    if frame > 1.0 {
+        return [-frame, frame, -1.0, 1.0];
+    } else {
+        return [-1.0, 1.0, -1.0 / frame, 1.0 / frame];
+    }
+}
+
+/// `cropwindow` as [xmin, xmax, ymin, ymax] in image space, where y goes down.
+pub fn get_crop_window(film: Option<&PropertyMap>) -> [f32; 4] {
+    let mut crop = [0.0, 1.0, 0.0, 1.0];
+    if let Some(film) = film {
+        let window = film.get_floats("cropwindow");
+        if window.len() == 4 {
+            crop = [
+                window[0].min(window[1]).clamp(0.0, 1.0),
+                window[0].max(window[1]).clamp(0.0, 1.0),
+                window[2].min(window[3]).clamp(0.0, 1.0),
+                window[2].max(window[3]).clamp(0.0, 1.0),
+            ];
+        }
+    }
+    return crop;
+}
+
+pub fn is_full_crop_window(crop: &[f32; 4]) -> bool {
+    return *crop == [0.0, 1.0, 0.0, 1.0];
+}
+
+/// Part of the screen window covered by a crop window.
+pub fn get_cropped_screen_window(screen: &[f32; 4], crop: &[f32; 4]) -> [f32; 4] {
+    return [
+        lerp(crop[0], screen[0], screen[1]),
+        lerp(crop[1], screen[0], screen[1]),
+        lerp(crop[3], screen[3], screen[2]),
+        lerp(crop[2], screen[3], screen[2]),
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::base::Property;
+
+    #[test]
+    fn test_screen_window() {
+        let camera = PropertyMap::new();
+        let mut film = PropertyMap::new();
+        film.insert("integer xresolution", Property::Ints(vec![800]));
+        film.insert("integer yresolution", Property::Ints(vec![400]));
+        assert_eq!(
+            get_screen_window(&camera, Some(&film)),
+            [-2.0, 2.0, -1.0, 1.0]
+        );
+        film.insert("integer xresolution", Property::Ints(vec![200]));
+        assert_eq!(
+            get_screen_window(&camera, Some(&film)),
+            [-1.0, 1.0, -2.0, 2.0]
+        );
+
+        let mut camera = PropertyMap::new();
+        camera.insert("float frameaspectratio", Property::Floats(vec![1.0]));
+        assert_eq!(
+            get_screen_window(&camera, Some(&film)),
+            [-1.0, 1.0, -1.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn test_cropped_screen_window() {
+        let mut film = PropertyMap::new();
+        film.insert(
+            "float cropwindow",
+            Property::Floats(vec![0.5, 1.0, 0.0, 0.25]),
+        );
+        let crop = get_crop_window(Some(&film));
+        assert!(!is_full_crop_window(&crop));
+        // The top quarter of the image is the top of the screen window
+        let window = get_cropped_screen_window(&[-2.0, 2.0, -1.0, 1.0], &crop);
+        assert_eq!(window, [0.0, 2.0, 0.5, 1.0]);
+    }
+}
@@ -0,0 +1,239 @@
+use super::transfer::TransferFunction;
+
+use glam::DMat3;
+use glam::DVec3;
+
+const GAMUT_EPSILON: f32 = 1e-4;
+
+/// RGB working spaces known to the application.
+/// Values stored in scene properties are linear RGB in the scene working space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ColorSpace {
+    #[default]
+    Srgb,
+    AcesCg,
+    Aces2065_1,
+    Rec2020,
+    DciP3,
+}
+
+const WHITE_D65: [f64; 2] = [0.3127, 0.3290];
+const WHITE_ACES: [f64; 2] = [0.32168, 0.33767];
+
+// Chromatic adaptation (Bradford)
+const BRADFORD: [f64; 9] = [
+    0.8951, 0.2664, -0.1614, //
+    -0.7502, 1.7135, 0.0367, //
+    0.0389, -0.0685, 1.0296, //
+];
+
+fn xy_to_xyz(xy: &[f64; 2]) -> DVec3 {
+    DVec3::new(xy[0] / xy[1], 1.0, (1.0 - xy[0] - xy[1]) / xy[1])
+}
+
+fn bradford_matrix() -> DMat3 {
+    DMat3::from_cols_array(&BRADFORD).transpose()
+}
+
+fn get_adaptation_matrix(src_white: &[f64; 2], dst_white: &[f64; 2]) -> DMat3 {
+    if src_white == dst_white {
+        return DMat3::IDENTITY;
+    }
+    let m = bradford_matrix();
+    let src = m * xy_to_xyz(src_white);
+    let dst = m * xy_to_xyz(dst_white);
+    let scale = DMat3::from_diagonal(dst / src);
+    return m.inverse() * scale * m;
+}
+
+fn mul_rgb(m: &DMat3, v: &[f32]) -> [f32; 3] {
+    let v = *m * DVec3::new(v[0] as f64, v[1] as f64, v[2] as f64);
+    return [v.x as f32, v.y as f32, v.z as f32];
+}
+
+impl ColorSpace {
+    pub fn all() -> &'static [ColorSpace] {
+        &[
+            ColorSpace::Srgb,
+            ColorSpace::AcesCg,
+            ColorSpace::Aces2065_1,
+            ColorSpace::Rec2020,
+            ColorSpace::DciP3,
+        ]
+    }
+
+    /// Name used in scene properties.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ColorSpace::Srgb => "srgb",
+            ColorSpace::AcesCg => "acescg",
+            ColorSpace::Aces2065_1 => "aces2065-1",
+            ColorSpace::Rec2020 => "rec2020",
+            ColorSpace::DciP3 => "dci-p3",
+        }
+    }
+
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            ColorSpace::Srgb => "sRGB",
+            ColorSpace::AcesCg => "ACEScg",
+            ColorSpace::Aces2065_1 => "ACES2065-1",
+            ColorSpace::Rec2020 => "Rec.2020",
+            ColorSpace::DciP3 => "DCI-P3",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ColorSpace> {
+        let name = name.to_lowercase();
+        return ColorSpace::all()
+            .iter()
+            .find(|cs| cs.get_name() == name)
+            .copied();
+    }
+
+    /// xy chromaticities of the red, green and blue primaries.
+    pub fn get_primaries(&self) -> [[f64; 2]; 3] {
+        match self {
+            ColorSpace::Srgb => [[0.64, 0.33], [0.30, 0.60], [0.15, 0.06]],
+            ColorSpace::AcesCg => [[0.713, 0.293], [0.165, 0.830], [0.128, 0.044]],
+            ColorSpace::Aces2065_1 => [[0.7347, 0.2653], [0.0, 1.0], [0.0001, -0.0770]],
+            ColorSpace::Rec2020 => [[0.708, 0.292], [0.170, 0.797], [0.131, 0.046]],
+            ColorSpace::DciP3 => [[0.680, 0.320], [0.265, 0.690], [0.150, 0.060]],
+        }
+    }
+
+    pub fn get_white_point(&self) -> [f64; 2] {
+        match self {
+            ColorSpace::AcesCg | ColorSpace::Aces2065_1 => WHITE_ACES,
+            _ => WHITE_D65,
+        }
+    }
+
+    /// Transfer function used when the color space is stored in 8-bit images.
+    pub fn get_transfer_function(&self) -> TransferFunction {
+        match self {
+            ColorSpace::Srgb => TransferFunction::Srgb,
+            ColorSpace::AcesCg | ColorSpace::Aces2065_1 => TransferFunction::Linear,
+            ColorSpace::Rec2020 => TransferFunction::Rec2020,
+            ColorSpace::DciP3 => TransferFunction::Gamma(2.6),
+        }
+    }
+
+    pub fn get_rgb_to_xyz_matrix(&self) -> DMat3 {
+        let p = self.get_primaries();
+        let r = xy_to_xyz(&p[0]);
+        let g = xy_to_xyz(&p[1]);
+        let b = xy_to_xyz(&p[2]);
+        let m = DMat3::from_cols(r, g, b);
+        let w = xy_to_xyz(&self.get_white_point());
+        let s = m.inverse() * w;
+        return DMat3::from_cols(r * s.x, g * s.y, b * s.z);
+    }
+
+    pub fn get_xyz_to_rgb_matrix(&self) -> DMat3 {
+        return self.get_rgb_to_xyz_matrix().inverse();
+    }
+
+    pub fn rgb_to_xyz(&self, rgb: &[f32]) -> [f32; 3] {
+        return mul_rgb(&self.get_rgb_to_xyz_matrix(), rgb);
+    }
+
+    pub fn xyz_to_rgb(&self, xyz: &[f32]) -> [f32; 3] {
+        return mul_rgb(&self.get_xyz_to_rgb_matrix(), xyz);
+    }
+
+    /// Matrix converting linear RGB in `self` to linear RGB in `dst`, adapting the white point.
+    pub fn get_conversion_matrix(&self, dst: ColorSpace) -> DMat3 {
+        if *self == dst {
+            return DMat3::IDENTITY;
+        }
+        let adaptation = get_adaptation_matrix(&self.get_white_point(), &dst.get_white_point());
+        return dst.get_xyz_to_rgb_matrix() * adaptation * self.get_rgb_to_xyz_matrix();
+    }
+
+    pub fn convert_rgb(&self, rgb: &[f32], dst: ColorSpace) -> [f32; 3] {
+        if *self == dst {
+            return [rgb[0], rgb[1], rgb[2]];
+        }
+        return mul_rgb(&self.get_conversion_matrix(dst), rgb);
+    }
+}
+
+impl std::fmt::Display for ColorSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.get_label())
+    }
+}
+
+/// Linear RGB with a negative component cannot be represented in the color space.
+pub fn is_out_of_gamut(rgb: &[f32]) -> bool {
+    rgb.iter().any(|v| *v < -GAMUT_EPSILON)
+}
+
+/// Converts a linear RGB image buffer in place.
+pub fn convert_rgb_buffer(data: &mut [f32], src: ColorSpace, dst: ColorSpace) {
+    if src == dst {
+        return;
+    }
+    let m = src.get_conversion_matrix(dst);
+    for p in data.chunks_exact_mut(3) {
+        let v = mul_rgb(&m, p);
+        p.copy_from_slice(&v);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near_equal(a: f32, b: f32, epsilon: f32) -> bool {
+        (a - b).abs() < epsilon
+    }
+
+    #[test]
+    fn test_color_space_001() {
+        // sRGB matrix from the primaries matches the usual one
+        let xyz = ColorSpace::Srgb.rgb_to_xyz(&[1.0, 0.0, 0.0]);
+        assert!(near_equal(xyz[0], 0.412456, 1e-4));
+        assert!(near_equal(xyz[1], 0.212673, 1e-4));
+        assert!(near_equal(xyz[2], 0.019334, 1e-4));
+    }
+
+    #[test]
+    fn test_color_space_002() {
+        for cs in ColorSpace::all().iter() {
+            let rgb = [0.2, 0.5, 0.8];
+            let xyz = cs.rgb_to_xyz(&rgb);
+            let rgb2 = cs.xyz_to_rgb(&xyz);
+            for i in 0..3 {
+                assert!(near_equal(rgb[i], rgb2[i], 1e-5), "{}", cs);
+            }
+            assert_eq!(ColorSpace::from_name(cs.get_name()), Some(*cs));
+        }
+    }
+
+    #[test]
+    fn test_color_space_003() {
+        // white stays white, saturated sRGB red is inside every wider gamut
+        for cs in ColorSpace::all().iter() {
+            let white = ColorSpace::Srgb.convert_rgb(&[1.0, 1.0, 1.0], *cs);
+            for i in 0..3 {
+                assert!(near_equal(white[i], 1.0, 1e-3), "{}", cs);
+            }
+        }
+        let red = ColorSpace::Srgb.convert_rgb(&[1.0, 0.0, 0.0], ColorSpace::Rec2020);
+        assert!(!is_out_of_gamut(&red));
+        let green = ColorSpace::Rec2020.convert_rgb(&[0.0, 1.0, 0.0], ColorSpace::Srgb);
+        assert!(is_out_of_gamut(&green));
+    }
+
+    #[test]
+    fn test_transfer_function_001() {
+        for cs in ColorSpace::all().iter() {
+            let tf = cs.get_transfer_function();
+            for v in [0.0, 0.001, 0.18, 0.5, 1.0] {
+                assert!(near_equal(tf.decode(tf.encode(v)), v, 1e-4));
+            }
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod color_space;
+mod transfer;
+
+pub use color_space::*;
+pub use transfer::*;
@@ -0,0 +1,70 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransferFunction {
+    Linear,
+    Srgb,
+    Rec2020,
+    Gamma(f32),
+}
+
+#[inline]
+pub fn gamma_correct(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        return 12.92 * value;
+    } else {
+        return 1.055 * f32::powf(value, 1.0 / 2.4) - 0.055;
+    }
+}
+
+#[inline]
+pub fn inverse_gamma_correct(value: f32) -> f32 {
+    if value <= 0.04045 {
+        return value * 1.0 / 12.92;
+    } else {
+        return f32::powf((value + 0.055) * 1.0 / 1.055, 2.4);
+    }
+}
+
+const REC2020_ALPHA: f32 = 1.09929682680944;
+const REC2020_BETA: f32 = 0.018053968510807;
+
+impl TransferFunction {
+    /// Linear to encoded (OETF).
+    pub fn encode(&self, value: f32) -> f32 {
+        match self {
+            TransferFunction::Linear => value,
+            TransferFunction::Srgb => gamma_correct(value),
+            TransferFunction::Rec2020 => {
+                if value < REC2020_BETA {
+                    4.5 * value
+                } else {
+                    REC2020_ALPHA * f32::powf(value, 0.45) - (REC2020_ALPHA - 1.0)
+                }
+            }
+            TransferFunction::Gamma(g) => f32::powf(value.max(0.0), 1.0 / *g),
+        }
+    }
+
+    /// Encoded to linear (inverse OETF).
+    pub fn decode(&self, value: f32) -> f32 {
+        match self {
+            TransferFunction::Linear => value,
+            TransferFunction::Srgb => inverse_gamma_correct(value),
+            TransferFunction::Rec2020 => {
+                if value < 4.5 * REC2020_BETA {
+                    value / 4.5
+                } else {
+                    f32::powf((value + (REC2020_ALPHA - 1.0)) / REC2020_ALPHA, 1.0 / 0.45)
+                }
+            }
+            TransferFunction::Gamma(g) => f32::powf(value.max(0.0), *g),
+        }
+    }
+
+    pub fn encode_to_byte(&self, value: f32) -> u8 {
+        f32::clamp(255.0 * self.encode(value), 0.0, 255.0).round() as u8
+    }
+
+    pub fn decode_from_byte(&self, value: u8) -> f32 {
+        self.decode(value as f32 / 255.0)
+    }
+}
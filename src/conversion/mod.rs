@@ -1,3 +1,5 @@
+pub mod camera_shape;
+pub mod color_space;
 pub mod light_shape;
 pub mod mesh_data;
 pub mod plane_data;
@@ -0,0 +1,372 @@
+// Measured spectra shipped with pbrt-ui.
+// Tables are stored as interleaved (lambda[nm], value) pairs like *.spd files.
+
+#[rustfmt::skip]
+pub const METAL_AU_ETA: [f32; 34] = [
+    380.0, 1.7000, 400.0, 1.6580, 425.0, 1.5600, 450.0, 1.4000, 475.0, 1.1500,
+    500.0, 0.9700, 525.0, 0.6500, 550.0, 0.4300, 575.0, 0.3100, 600.0, 0.2500,
+    625.0, 0.2000, 650.0, 0.1700, 675.0, 0.1600, 700.0, 0.1600, 725.0, 0.1600,
+    750.0, 0.1600, 780.0, 0.1700,
+];
+
+#[rustfmt::skip]
+pub const METAL_AU_K: [f32; 34] = [
+    380.0, 1.9000, 400.0, 1.9560, 425.0, 1.9100, 450.0, 1.8800, 475.0, 1.8400,
+    500.0, 1.8700, 525.0, 2.1000, 550.0, 2.4500, 575.0, 2.7200, 600.0, 2.9800,
+    625.0, 3.2200, 650.0, 3.4600, 675.0, 3.7000, 700.0, 3.9500, 725.0, 4.1800,
+    750.0, 4.4000, 780.0, 4.6500,
+];
+
+#[rustfmt::skip]
+pub const METAL_AG_ETA: [f32; 34] = [
+    380.0, 0.0600, 400.0, 0.0500, 425.0, 0.0450, 450.0, 0.0400, 475.0, 0.0450,
+    500.0, 0.0500, 525.0, 0.0550, 550.0, 0.0600, 575.0, 0.0600, 600.0, 0.0600,
+    625.0, 0.0550, 650.0, 0.0500, 675.0, 0.0450, 700.0, 0.0400, 725.0, 0.0350,
+    750.0, 0.0300, 780.0, 0.0300,
+];
+
+#[rustfmt::skip]
+pub const METAL_AG_K: [f32; 34] = [
+    380.0, 1.8600, 400.0, 2.1000, 425.0, 2.3800, 450.0, 2.6500, 475.0, 2.8700,
+    500.0, 3.0900, 525.0, 3.3400, 550.0, 3.5900, 575.0, 3.8000, 600.0, 4.0000,
+    625.0, 4.2400, 650.0, 4.4800, 675.0, 4.6900, 700.0, 4.9000, 725.0, 5.1000,
+    750.0, 5.3000, 780.0, 5.5400,
+];
+
+#[rustfmt::skip]
+pub const METAL_CU_ETA: [f32; 34] = [
+    380.0, 1.2200, 400.0, 1.1800, 425.0, 1.1700, 450.0, 1.1700, 475.0, 1.1500,
+    500.0, 1.1200, 525.0, 1.0500, 550.0, 0.8600, 575.0, 0.4500, 600.0, 0.2500,
+    625.0, 0.2300, 650.0, 0.2100, 675.0, 0.2100, 700.0, 0.2100, 725.0, 0.2200,
+    750.0, 0.2400, 780.0, 0.2600,
+];
+
+#[rustfmt::skip]
+pub const METAL_CU_K: [f32; 34] = [
+    380.0, 2.1700, 400.0, 2.2100, 425.0, 2.3100, 450.0, 2.4000, 475.0, 2.4900,
+    500.0, 2.6000, 525.0, 2.6000, 550.0, 2.5500, 575.0, 2.8900, 600.0, 3.4100,
+    625.0, 3.5400, 650.0, 3.6700, 675.0, 3.9400, 700.0, 4.2000, 725.0, 4.4000,
+    750.0, 4.6000, 780.0, 4.8400,
+];
+
+#[rustfmt::skip]
+pub const METAL_AL_ETA: [f32; 34] = [
+    380.0, 0.4500, 400.0, 0.4900, 425.0, 0.5500, 450.0, 0.6200, 475.0, 0.6900,
+    500.0, 0.7700, 525.0, 0.8600, 550.0, 0.9600, 575.0, 1.0800, 600.0, 1.2000,
+    625.0, 1.3400, 650.0, 1.4900, 675.0, 1.6500, 700.0, 1.8300, 725.0, 2.0800,
+    750.0, 2.4000, 780.0, 2.6300,
+];
+
+#[rustfmt::skip]
+pub const METAL_AL_K: [f32; 34] = [
+    380.0, 4.6100, 400.0, 4.8600, 425.0, 5.1600, 450.0, 5.4700, 475.0, 5.7800,
+    500.0, 6.0800, 525.0, 6.3900, 550.0, 6.6900, 575.0, 6.9800, 600.0, 7.2600,
+    625.0, 7.5400, 650.0, 7.8200, 675.0, 8.0700, 700.0, 8.3100, 725.0, 8.4700,
+    750.0, 8.6000, 780.0, 8.6200,
+];
+
+#[rustfmt::skip]
+pub const METAL_CUZN_ETA: [f32; 34] = [
+    380.0, 1.4400, 400.0, 1.3700, 425.0, 1.3000, 450.0, 1.2400, 475.0, 1.1600,
+    500.0, 1.0800, 525.0, 0.9200, 550.0, 0.7200, 575.0, 0.5600, 600.0, 0.4600,
+    625.0, 0.4100, 650.0, 0.3800, 675.0, 0.3700, 700.0, 0.3600, 725.0, 0.3700,
+    750.0, 0.3800, 780.0, 0.4000,
+];
+
+#[rustfmt::skip]
+pub const METAL_CUZN_K: [f32; 34] = [
+    380.0, 1.8600, 400.0, 1.9000, 425.0, 1.9200, 450.0, 1.9500, 475.0, 1.9900,
+    500.0, 2.0300, 525.0, 2.2000, 550.0, 2.4400, 575.0, 2.7200, 600.0, 2.9800,
+    625.0, 3.1900, 650.0, 3.4000, 675.0, 3.6000, 700.0, 3.8000, 725.0, 4.0000,
+    750.0, 4.2000, 780.0, 4.4400,
+];
+
+// Sellmeier coefficients (B1, B2, B3, C1, C2, C3), C in um^2.
+#[rustfmt::skip]
+pub const GLASS_SELLMEIER: [(&str, [f64; 6]); 6] = [
+    ("BK7",    [1.03961212, 0.231792344, 1.01046945, 0.00600069867, 0.0200179144, 103.560653]),
+    ("BAF10",  [1.5851495, 0.143559385, 1.08521269, 0.00926681282, 0.0424489805, 105.613573]),
+    ("FK51A",  [0.971247817, 0.216901417, 0.904651666, 0.00472301995, 0.0153575612, 168.68133]),
+    ("LASF9",  [2.00029547, 0.298926886, 1.80691843, 0.0121426017, 0.0538736236, 156.530829]),
+    ("F5",     [1.3104463, 0.19603426, 0.96612977, 0.00958633048, 0.0457627627, 115.011883]),
+    ("SF11",   [1.73759695, 0.313747346, 1.89878101, 0.013188707, 0.0623068142, 155.23629]),
+];
+
+#[rustfmt::skip]
+pub const STDILLUM_D65: [f32; 82] = [
+    380.0, 49.98, 390.0, 54.65, 400.0, 82.75, 410.0, 91.49, 420.0, 93.43,
+    430.0, 86.68, 440.0, 104.86, 450.0, 117.01, 460.0, 117.81, 470.0, 114.86,
+    480.0, 115.92, 490.0, 108.81, 500.0, 109.35, 510.0, 107.80, 520.0, 104.79,
+    530.0, 107.69, 540.0, 104.41, 550.0, 104.05, 560.0, 100.00, 570.0, 96.33,
+    580.0, 95.79, 590.0, 88.69, 600.0, 90.01, 610.0, 89.60, 620.0, 87.70,
+    630.0, 83.29, 640.0, 83.70, 650.0, 80.03, 660.0, 80.21, 670.0, 82.28,
+    680.0, 78.28, 690.0, 69.72, 700.0, 71.61, 710.0, 74.35, 720.0, 61.60,
+    730.0, 69.89, 740.0, 75.09, 750.0, 63.59, 760.0, 46.42, 770.0, 66.81,
+    780.0, 63.38,
+];
+
+#[rustfmt::skip]
+pub const STDILLUM_D50: [f32; 82] = [
+    380.0, 24.49, 390.0, 29.87, 400.0, 49.31, 410.0, 56.51, 420.0, 60.03,
+    430.0, 57.82, 440.0, 74.82, 450.0, 87.25, 460.0, 90.61, 470.0, 91.37,
+    480.0, 95.11, 490.0, 91.96, 500.0, 95.72, 510.0, 96.61, 520.0, 97.13,
+    530.0, 102.10, 540.0, 100.75, 550.0, 102.32, 560.0, 100.00, 570.0, 97.74,
+    580.0, 98.92, 590.0, 93.50, 600.0, 97.69, 610.0, 99.27, 620.0, 99.04,
+    630.0, 95.72, 640.0, 98.86, 650.0, 95.67, 660.0, 98.19, 670.0, 103.00,
+    680.0, 99.13, 690.0, 87.38, 700.0, 91.60, 710.0, 92.89, 720.0, 76.85,
+    730.0, 86.51, 740.0, 92.58, 750.0, 78.23, 760.0, 57.69, 770.0, 82.92,
+    780.0, 78.27,
+];
+
+// CIE F-series fluorescent illuminants F1-F12, 380-780nm in 5nm steps.
+#[rustfmt::skip]
+pub const STDILLUM_F1: [f32; 162] = [
+    380.0, 1.87, 385.0, 2.36, 390.0, 2.94, 395.0, 3.47, 400.0, 5.17,
+    405.0, 19.49, 410.0, 6.13, 415.0, 6.24, 420.0, 7.01, 425.0, 7.79,
+    430.0, 8.56, 435.0, 43.67, 440.0, 16.94, 445.0, 10.72, 450.0, 11.35,
+    455.0, 11.89, 460.0, 12.37, 465.0, 12.75, 470.0, 13.00, 475.0, 13.15,
+    480.0, 13.23, 485.0, 13.17, 490.0, 13.13, 495.0, 12.85, 500.0, 12.52,
+    505.0, 12.20, 510.0, 11.83, 515.0, 11.50, 520.0, 11.22, 525.0, 11.05,
+    530.0, 11.03, 535.0, 11.18, 540.0, 11.53, 545.0, 27.74, 550.0, 17.05,
+    555.0, 13.55, 560.0, 14.33, 565.0, 15.01, 570.0, 15.52, 575.0, 18.29,
+    580.0, 19.55, 585.0, 15.48, 590.0, 14.91, 595.0, 14.15, 600.0, 13.22,
+    605.0, 12.19, 610.0, 11.12, 615.0, 10.03, 620.0, 8.95, 625.0, 7.96,
+    630.0, 7.02, 635.0, 6.20, 640.0, 5.42, 645.0, 4.73, 650.0, 4.15,
+    655.0, 3.64, 660.0, 3.20, 665.0, 2.81, 670.0, 2.47, 675.0, 2.18,
+    680.0, 1.93, 685.0, 1.72, 690.0, 1.67, 695.0, 1.43, 700.0, 1.29,
+    705.0, 1.19, 710.0, 1.08, 715.0, 0.96, 720.0, 0.88, 725.0, 0.81,
+    730.0, 0.77, 735.0, 0.75, 740.0, 0.73, 745.0, 0.68, 750.0, 0.69,
+    755.0, 0.64, 760.0, 0.68, 765.0, 0.69, 770.0, 0.61, 775.0, 0.52,
+    780.0, 0.43,
+];
+
+#[rustfmt::skip]
+pub const STDILLUM_F2: [f32; 162] = [
+    380.0, 1.18, 385.0, 1.48, 390.0, 1.84, 395.0, 2.15, 400.0, 3.44,
+    405.0, 15.69, 410.0, 3.85, 415.0, 3.74, 420.0, 4.19, 425.0, 4.62,
+    430.0, 5.06, 435.0, 34.98, 440.0, 11.81, 445.0, 6.27, 450.0, 6.63,
+    455.0, 6.93, 460.0, 7.19, 465.0, 7.40, 470.0, 7.54, 475.0, 7.62,
+    480.0, 7.65, 485.0, 7.62, 490.0, 7.62, 495.0, 7.45, 500.0, 7.28,
+    505.0, 7.15, 510.0, 7.05, 515.0, 7.04, 520.0, 7.16, 525.0, 7.47,
+    530.0, 8.04, 535.0, 8.88, 540.0, 10.01, 545.0, 24.88, 550.0, 16.64,
+    555.0, 14.59, 560.0, 16.16, 565.0, 17.56, 570.0, 18.62, 575.0, 21.47,
+    580.0, 22.79, 585.0, 19.29, 590.0, 18.66, 595.0, 17.73, 600.0, 16.54,
+    605.0, 15.21, 610.0, 13.80, 615.0, 12.36, 620.0, 10.95, 625.0, 9.65,
+    630.0, 8.40, 635.0, 7.32, 640.0, 6.31, 645.0, 5.43, 650.0, 4.68,
+    655.0, 4.02, 660.0, 3.45, 665.0, 2.96, 670.0, 2.55, 675.0, 2.19,
+    680.0, 1.89, 685.0, 1.64, 690.0, 1.53, 695.0, 1.27, 700.0, 1.10,
+    705.0, 0.99, 710.0, 0.88, 715.0, 0.76, 720.0, 0.68, 725.0, 0.61,
+    730.0, 0.56, 735.0, 0.54, 740.0, 0.51, 745.0, 0.47, 750.0, 0.47,
+    755.0, 0.43, 760.0, 0.46, 765.0, 0.47, 770.0, 0.40, 775.0, 0.33,
+    780.0, 0.27,
+];
+
+#[rustfmt::skip]
+pub const STDILLUM_F3: [f32; 162] = [
+    380.0, 0.82, 385.0, 1.02, 390.0, 1.26, 395.0, 1.44, 400.0, 2.57,
+    405.0, 14.36, 410.0, 2.70, 415.0, 2.45, 420.0, 2.73, 425.0, 3.00,
+    430.0, 3.28, 435.0, 31.85, 440.0, 9.47, 445.0, 4.02, 450.0, 4.25,
+    455.0, 4.44, 460.0, 4.59, 465.0, 4.72, 470.0, 4.80, 475.0, 4.86,
+    480.0, 4.87, 485.0, 4.85, 490.0, 4.88, 495.0, 4.77, 500.0, 4.67,
+    505.0, 4.62, 510.0, 4.62, 515.0, 4.73, 520.0, 4.99, 525.0, 5.48,
+    530.0, 6.25, 535.0, 7.34, 540.0, 8.78, 545.0, 23.82, 550.0, 16.14,
+    555.0, 14.59, 560.0, 16.63, 565.0, 18.49, 570.0, 19.95, 575.0, 23.11,
+    580.0, 24.69, 585.0, 21.41, 590.0, 20.85, 595.0, 19.93, 600.0, 18.67,
+    605.0, 17.22, 610.0, 15.65, 615.0, 14.04, 620.0, 12.45, 625.0, 10.95,
+    630.0, 9.51, 635.0, 8.27, 640.0, 7.11, 645.0, 6.09, 650.0, 5.22,
+    655.0, 4.45, 660.0, 3.80, 665.0, 3.23, 670.0, 2.75, 675.0, 2.33,
+    680.0, 1.99, 685.0, 1.70, 690.0, 1.55, 695.0, 1.27, 700.0, 1.11,
+    705.0, 0.96, 710.0, 0.86, 715.0, 0.78, 720.0, 0.68, 725.0, 0.59,
+    730.0, 0.52, 735.0, 0.48, 740.0, 0.45, 745.0, 0.40, 750.0, 0.37,
+    755.0, 0.33, 760.0, 0.35, 765.0, 0.38, 770.0, 0.32, 775.0, 0.26,
+    780.0, 0.21,
+];
+
+#[rustfmt::skip]
+pub const STDILLUM_F4: [f32; 162] = [
+    380.0, 0.57, 385.0, 0.70, 390.0, 0.87, 395.0, 0.98, 400.0, 2.01,
+    405.0, 13.75, 410.0, 1.95, 415.0, 1.59, 420.0, 1.76, 425.0, 1.93,
+    430.0, 2.10, 435.0, 30.28, 440.0, 8.03, 445.0, 2.55, 450.0, 2.70,
+    455.0, 2.82, 460.0, 2.91, 465.0, 2.99, 470.0, 3.04, 475.0, 3.08,
+    480.0, 3.09, 485.0, 3.09, 490.0, 3.14, 495.0, 3.06, 500.0, 3.00,
+    505.0, 2.98, 510.0, 3.01, 515.0, 3.14, 520.0, 3.41, 525.0, 3.90,
+    530.0, 4.69, 535.0, 5.81, 540.0, 7.32, 545.0, 22.59, 550.0, 15.11,
+    555.0, 13.88, 560.0, 16.33, 565.0, 18.68, 570.0, 20.64, 575.0, 24.28,
+    580.0, 26.26, 585.0, 23.28, 590.0, 22.94, 595.0, 22.14, 600.0, 20.91,
+    605.0, 19.43, 610.0, 17.74, 615.0, 16.00, 620.0, 14.42, 625.0, 12.56,
+    630.0, 10.93, 635.0, 9.52, 640.0, 8.18, 645.0, 7.01, 650.0, 6.00,
+    655.0, 5.11, 660.0, 4.36, 665.0, 3.69, 670.0, 3.13, 675.0, 2.64,
+    680.0, 2.24, 685.0, 1.91, 690.0, 1.70, 695.0, 1.39, 700.0, 1.18,
+    705.0, 1.03, 710.0, 0.88, 715.0, 0.74, 720.0, 0.63, 725.0, 0.54,
+    730.0, 0.46, 735.0, 0.41, 740.0, 0.37, 745.0, 0.33, 750.0, 0.31,
+    755.0, 0.27, 760.0, 0.29, 765.0, 0.33, 770.0, 0.27, 775.0, 0.22,
+    780.0, 0.17,
+];
+
+#[rustfmt::skip]
+pub const STDILLUM_F5: [f32; 162] = [
+    380.0, 1.87, 385.0, 2.35, 390.0, 2.92, 395.0, 3.45, 400.0, 5.10,
+    405.0, 18.91, 410.0, 6.00, 415.0, 6.11, 420.0, 6.85, 425.0, 7.58,
+    430.0, 8.31, 435.0, 40.76, 440.0, 16.06, 445.0, 10.32, 450.0, 10.91,
+    455.0, 11.40, 460.0, 11.83, 465.0, 12.17, 470.0, 12.40, 475.0, 12.54,
+    480.0, 12.58, 485.0, 12.52, 490.0, 12.47, 495.0, 12.20, 500.0, 11.89,
+    505.0, 11.61, 510.0, 11.33, 515.0, 11.10, 520.0, 10.96, 525.0, 10.97,
+    530.0, 11.16, 535.0, 11.54, 540.0, 12.12, 545.0, 27.78, 550.0, 17.73,
+    555.0, 14.47, 560.0, 15.20, 565.0, 15.77, 570.0, 16.10, 575.0, 18.54,
+    580.0, 19.50, 585.0, 15.39, 590.0, 14.64, 595.0, 13.72, 600.0, 12.69,
+    605.0, 11.57, 610.0, 10.45, 615.0, 9.35, 620.0, 8.29, 625.0, 7.32,
+    630.0, 6.41, 635.0, 5.63, 640.0, 4.90, 645.0, 4.26, 650.0, 3.72,
+    655.0, 3.25, 660.0, 2.83, 665.0, 2.49, 670.0, 2.19, 675.0, 1.93,
+    680.0, 1.71, 685.0, 1.52, 690.0, 1.48, 695.0, 1.26, 700.0, 1.13,
+    705.0, 1.05, 710.0, 0.96, 715.0, 0.85, 720.0, 0.78, 725.0, 0.72,
+    730.0, 0.68, 735.0, 0.67, 740.0, 0.65, 745.0, 0.61, 750.0, 0.62,
+    755.0, 0.59, 760.0, 0.62, 765.0, 0.64, 770.0, 0.55, 775.0, 0.47,
+    780.0, 0.40,
+];
+
+#[rustfmt::skip]
+pub const STDILLUM_F6: [f32; 162] = [
+    380.0, 1.05, 385.0, 1.31, 390.0, 1.63, 395.0, 1.90, 400.0, 3.11,
+    405.0, 14.80, 410.0, 3.43, 415.0, 3.30, 420.0, 3.68, 425.0, 4.07,
+    430.0, 4.45, 435.0, 32.61, 440.0, 10.74, 445.0, 5.48, 450.0, 5.78,
+    455.0, 6.03, 460.0, 6.25, 465.0, 6.41, 470.0, 6.52, 475.0, 6.58,
+    480.0, 6.59, 485.0, 6.56, 490.0, 6.56, 495.0, 6.42, 500.0, 6.28,
+    505.0, 6.20, 510.0, 6.19, 515.0, 6.30, 520.0, 6.60, 525.0, 7.12,
+    530.0, 7.94, 535.0, 9.07, 540.0, 10.49, 545.0, 25.22, 550.0, 17.46,
+    555.0, 15.63, 560.0, 17.22, 565.0, 18.53, 570.0, 19.43, 575.0, 21.97,
+    580.0, 23.01, 585.0, 19.41, 590.0, 18.56, 595.0, 17.42, 600.0, 16.09,
+    605.0, 14.64, 610.0, 13.15, 615.0, 11.68, 620.0, 10.25, 625.0, 8.95,
+    630.0, 7.74, 635.0, 6.69, 640.0, 5.71, 645.0, 4.87, 650.0, 4.16,
+    655.0, 3.55, 660.0, 3.02, 665.0, 2.57, 670.0, 2.20, 675.0, 1.87,
+    680.0, 1.60, 685.0, 1.37, 690.0, 1.29, 695.0, 1.05, 700.0, 0.91,
+    705.0, 0.81, 710.0, 0.71, 715.0, 0.61, 720.0, 0.54, 725.0, 0.48,
+    730.0, 0.44, 735.0, 0.43, 740.0, 0.40, 745.0, 0.37, 750.0, 0.38,
+    755.0, 0.35, 760.0, 0.39, 765.0, 0.41, 770.0, 0.33, 775.0, 0.26,
+    780.0, 0.21,
+];
+
+#[rustfmt::skip]
+pub const STDILLUM_F7: [f32; 162] = [
+    380.0, 2.56, 385.0, 3.18, 390.0, 3.84, 395.0, 4.53, 400.0, 6.15,
+    405.0, 19.37, 410.0, 7.37, 415.0, 7.05, 420.0, 7.71, 425.0, 8.41,
+    430.0, 9.15, 435.0, 44.14, 440.0, 17.52, 445.0, 11.35, 450.0, 12.00,
+    455.0, 12.58, 460.0, 13.08, 465.0, 13.45, 470.0, 13.71, 475.0, 13.88,
+    480.0, 13.95, 485.0, 13.93, 490.0, 13.82, 495.0, 13.64, 500.0, 13.43,
+    505.0, 13.25, 510.0, 13.08, 515.0, 12.93, 520.0, 12.78, 525.0, 12.60,
+    530.0, 12.44, 535.0, 12.33, 540.0, 12.26, 545.0, 29.52, 550.0, 17.05,
+    555.0, 12.44, 560.0, 12.58, 565.0, 12.72, 570.0, 12.83, 575.0, 15.46,
+    580.0, 16.75, 585.0, 12.83, 590.0, 12.67, 595.0, 12.45, 600.0, 12.19,
+    605.0, 11.89, 610.0, 11.60, 615.0, 11.35, 620.0, 11.12, 625.0, 10.95,
+    630.0, 10.76, 635.0, 10.42, 640.0, 10.11, 645.0, 10.04, 650.0, 10.02,
+    655.0, 10.11, 660.0, 9.87, 665.0, 8.65, 670.0, 7.27, 675.0, 6.44,
+    680.0, 5.83, 685.0, 5.41, 690.0, 5.04, 695.0, 4.57, 700.0, 4.12,
+    705.0, 3.77, 710.0, 3.46, 715.0, 3.08, 720.0, 2.73, 725.0, 2.47,
+    730.0, 2.25, 735.0, 2.06, 740.0, 1.90, 745.0, 1.75, 750.0, 1.62,
+    755.0, 1.54, 760.0, 1.45, 765.0, 1.32, 770.0, 1.17, 775.0, 0.99,
+    780.0, 0.81,
+];
+
+#[rustfmt::skip]
+pub const STDILLUM_F8: [f32; 162] = [
+    380.0, 1.21, 385.0, 1.50, 390.0, 1.81, 395.0, 2.13, 400.0, 3.17,
+    405.0, 13.08, 410.0, 3.83, 415.0, 3.45, 420.0, 3.86, 425.0, 4.42,
+    430.0, 5.09, 435.0, 34.10, 440.0, 12.42, 445.0, 7.68, 450.0, 8.60,
+    455.0, 9.46, 460.0, 10.24, 465.0, 10.84, 470.0, 11.33, 475.0, 11.71,
+    480.0, 11.98, 485.0, 12.17, 490.0, 12.28, 495.0, 12.32, 500.0, 12.35,
+    505.0, 12.44, 510.0, 12.55, 515.0, 12.68, 520.0, 12.77, 525.0, 12.72,
+    530.0, 12.60, 535.0, 12.43, 540.0, 12.22, 545.0, 28.96, 550.0, 16.51,
+    555.0, 11.79, 560.0, 11.76, 565.0, 11.77, 570.0, 11.84, 575.0, 14.61,
+    580.0, 16.11, 585.0, 12.34, 590.0, 12.53, 595.0, 12.72, 600.0, 12.92,
+    605.0, 13.12, 610.0, 13.34, 615.0, 13.61, 620.0, 13.87, 625.0, 14.07,
+    630.0, 14.20, 635.0, 14.16, 640.0, 14.13, 645.0, 14.34, 650.0, 14.50,
+    655.0, 14.46, 660.0, 14.00, 665.0, 12.58, 670.0, 10.99, 675.0, 9.98,
+    680.0, 9.22, 685.0, 8.62, 690.0, 8.07, 695.0, 7.39, 700.0, 6.71,
+    705.0, 6.16, 710.0, 5.63, 715.0, 5.03, 720.0, 4.46, 725.0, 4.02,
+    730.0, 3.66, 735.0, 3.36, 740.0, 3.09, 745.0, 2.85, 750.0, 2.65,
+    755.0, 2.51, 760.0, 2.37, 765.0, 2.15, 770.0, 1.89, 775.0, 1.61,
+    780.0, 1.32,
+];
+
+#[rustfmt::skip]
+pub const STDILLUM_F9: [f32; 162] = [
+    380.0, 0.90, 385.0, 1.12, 390.0, 1.36, 395.0, 1.60, 400.0, 2.59,
+    405.0, 12.80, 410.0, 3.05, 415.0, 2.56, 420.0, 2.86, 425.0, 3.30,
+    430.0, 3.82, 435.0, 32.62, 440.0, 10.77, 445.0, 5.84, 450.0, 6.57,
+    455.0, 7.25, 460.0, 7.86, 465.0, 8.35, 470.0, 8.75, 475.0, 9.06,
+    480.0, 9.31, 485.0, 9.48, 490.0, 9.61, 495.0, 9.68, 500.0, 9.74,
+    505.0, 9.88, 510.0, 10.04, 515.0, 10.26, 520.0, 10.48, 525.0, 10.63,
+    530.0, 10.76, 535.0, 10.96, 540.0, 11.18, 545.0, 27.71, 550.0, 16.29,
+    555.0, 12.28, 560.0, 12.74, 565.0, 13.21, 570.0, 13.65, 575.0, 16.57,
+    580.0, 18.14, 585.0, 14.55, 590.0, 14.65, 595.0, 14.66, 600.0, 14.61,
+    605.0, 14.50, 610.0, 14.39, 615.0, 14.40, 620.0, 14.47, 625.0, 14.62,
+    630.0, 14.72, 635.0, 14.55, 640.0, 14.40, 645.0, 14.58, 650.0, 14.88,
+    655.0, 15.51, 660.0, 15.47, 665.0, 13.20, 670.0, 10.57, 675.0, 9.18,
+    680.0, 8.25, 685.0, 7.57, 690.0, 7.03, 695.0, 6.35, 700.0, 5.72,
+    705.0, 5.20, 710.0, 4.66, 715.0, 4.11, 720.0, 3.60, 725.0, 3.22,
+    730.0, 2.90, 735.0, 2.65, 740.0, 2.41, 745.0, 2.19, 750.0, 2.01,
+    755.0, 1.88, 760.0, 1.73, 765.0, 1.57, 770.0, 1.41, 775.0, 1.17,
+    780.0, 0.96,
+];
+
+#[rustfmt::skip]
+pub const STDILLUM_F10: [f32; 162] = [
+    380.0, 1.11, 385.0, 0.63, 390.0, 0.62, 395.0, 0.57, 400.0, 1.48,
+    405.0, 12.16, 410.0, 2.12, 415.0, 2.70, 420.0, 3.74, 425.0, 5.14,
+    430.0, 6.75, 435.0, 34.39, 440.0, 14.86, 445.0, 10.40, 450.0, 10.76,
+    455.0, 10.67, 460.0, 10.11, 465.0, 9.27, 470.0, 8.29, 475.0, 7.29,
+    480.0, 7.91, 485.0, 16.64, 490.0, 16.73, 495.0, 10.44, 500.0, 5.94,
+    505.0, 3.34, 510.0, 2.35, 515.0, 1.88, 520.0, 1.59, 525.0, 1.47,
+    530.0, 1.80, 535.0, 5.71, 540.0, 40.98, 545.0, 73.69, 550.0, 33.61,
+    555.0, 8.24, 560.0, 3.38, 565.0, 2.47, 570.0, 2.14, 575.0, 4.86,
+    580.0, 11.45, 585.0, 14.79, 590.0, 12.16, 595.0, 8.97, 600.0, 6.52,
+    605.0, 8.31, 610.0, 44.12, 615.0, 34.55, 620.0, 12.09, 625.0, 12.15,
+    630.0, 10.52, 635.0, 4.43, 640.0, 1.95, 645.0, 2.19, 650.0, 3.19,
+    655.0, 2.77, 660.0, 2.29, 665.0, 2.00, 670.0, 1.52, 675.0, 1.35,
+    680.0, 1.47, 685.0, 1.79, 690.0, 1.74, 695.0, 1.02, 700.0, 1.14,
+    705.0, 3.32, 710.0, 4.49, 715.0, 2.05, 720.0, 0.49, 725.0, 0.24,
+    730.0, 0.21, 735.0, 0.21, 740.0, 0.24, 745.0, 0.24, 750.0, 0.21,
+    755.0, 0.17, 760.0, 0.21, 765.0, 0.22, 770.0, 0.17, 775.0, 0.12,
+    780.0, 0.09,
+];
+
+#[rustfmt::skip]
+pub const STDILLUM_F11: [f32; 162] = [
+    380.0, 0.91, 385.0, 0.63, 390.0, 0.46, 395.0, 0.37, 400.0, 1.29,
+    405.0, 12.68, 410.0, 1.59, 415.0, 1.79, 420.0, 2.46, 425.0, 3.33,
+    430.0, 4.49, 435.0, 33.94, 440.0, 12.13, 445.0, 6.95, 450.0, 7.19,
+    455.0, 7.12, 460.0, 6.72, 465.0, 6.13, 470.0, 5.46, 475.0, 4.79,
+    480.0, 5.66, 485.0, 14.29, 490.0, 14.96, 495.0, 8.97, 500.0, 4.72,
+    505.0, 2.33, 510.0, 1.47, 515.0, 1.10, 520.0, 0.89, 525.0, 0.83,
+    530.0, 1.18, 535.0, 4.90, 540.0, 39.59, 545.0, 72.84, 550.0, 32.61,
+    555.0, 7.52, 560.0, 2.83, 565.0, 1.96, 570.0, 1.67, 575.0, 4.43,
+    580.0, 11.28, 585.0, 14.76, 590.0, 12.73, 595.0, 9.74, 600.0, 7.33,
+    605.0, 9.72, 610.0, 55.27, 615.0, 42.58, 620.0, 13.18, 625.0, 13.16,
+    630.0, 12.26, 635.0, 5.11, 640.0, 2.07, 645.0, 2.34, 650.0, 3.58,
+    655.0, 3.01, 660.0, 2.48, 665.0, 2.14, 670.0, 1.54, 675.0, 1.33,
+    680.0, 1.46, 685.0, 1.94, 690.0, 2.00, 695.0, 1.20, 700.0, 1.35,
+    705.0, 4.10, 710.0, 5.58, 715.0, 2.51, 720.0, 0.57, 725.0, 0.27,
+    730.0, 0.23, 735.0, 0.21, 740.0, 0.24, 745.0, 0.24, 750.0, 0.20,
+    755.0, 0.24, 760.0, 0.32, 765.0, 0.26, 770.0, 0.16, 775.0, 0.12,
+    780.0, 0.09,
+];
+
+#[rustfmt::skip]
+pub const STDILLUM_F12: [f32; 162] = [
+    380.0, 0.96, 385.0, 0.64, 390.0, 0.45, 395.0, 0.33, 400.0, 1.19,
+    405.0, 12.48, 410.0, 1.12, 415.0, 0.94, 420.0, 1.08, 425.0, 1.37,
+    430.0, 1.78, 435.0, 29.05, 440.0, 7.90, 445.0, 2.65, 450.0, 2.71,
+    455.0, 2.65, 460.0, 2.49, 465.0, 2.33, 470.0, 2.10, 475.0, 1.91,
+    480.0, 3.01, 485.0, 10.83, 490.0, 11.88, 495.0, 6.88, 500.0, 3.43,
+    505.0, 1.49, 510.0, 0.92, 515.0, 0.71, 520.0, 0.60, 525.0, 0.63,
+    530.0, 1.10, 535.0, 4.56, 540.0, 34.40, 545.0, 65.40, 550.0, 29.48,
+    555.0, 7.16, 560.0, 3.08, 565.0, 2.47, 570.0, 2.27, 575.0, 5.09,
+    580.0, 11.96, 585.0, 15.32, 590.0, 14.27, 595.0, 11.86, 600.0, 9.28,
+    605.0, 12.31, 610.0, 68.53, 615.0, 53.02, 620.0, 14.67, 625.0, 14.38,
+    630.0, 14.71, 635.0, 6.46, 640.0, 2.57, 645.0, 2.75, 650.0, 4.18,
+    655.0, 3.44, 660.0, 2.81, 665.0, 2.42, 670.0, 1.64, 675.0, 1.36,
+    680.0, 1.49, 685.0, 2.14, 690.0, 2.34, 695.0, 1.42, 700.0, 1.61,
+    705.0, 5.04, 710.0, 6.98, 715.0, 3.19, 720.0, 0.71, 725.0, 0.30,
+    730.0, 0.26, 735.0, 0.23, 740.0, 0.28, 745.0, 0.28, 750.0, 0.21,
+    755.0, 0.17, 760.0, 0.21, 765.0, 0.19, 770.0, 0.15, 775.0, 0.10,
+    780.0, 0.05,
+];
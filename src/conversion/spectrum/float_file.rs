@@ -33,3 +33,23 @@ pub fn read_float_file(path: &str) -> Result<Vec<f32>, PbrtError> {
     }
     return Ok(values);
 }
+
+pub fn write_spd_file(
+    path: &str,
+    lambda: &[f32],
+    vals: &[f32],
+    comment: &str,
+) -> Result<(), PbrtError> {
+    let mut s = String::new();
+    if !comment.is_empty() {
+        for line in comment.lines() {
+            s.push_str(&format!("# {}\n", line));
+        }
+    }
+    for (l, v) in lambda.iter().zip(vals.iter()) {
+        s.push_str(&format!("{} {}\n", l, v));
+    }
+    std::fs::write(path, s)
+        .map_err(|_| PbrtError::error(&format!("Unable to write file \"{}\".", path)))?;
+    return Ok(());
+}
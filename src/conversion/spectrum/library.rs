@@ -0,0 +1,273 @@
+use super::data_named::*;
+use super::float_file::write_spd_file;
+use crate::error::PbrtError;
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NamedSpectrumCategory {
+    Metal,
+    Glass,
+    Illuminant,
+}
+
+impl NamedSpectrumCategory {
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            NamedSpectrumCategory::Metal => "Metals",
+            NamedSpectrumCategory::Glass => "Glasses",
+            NamedSpectrumCategory::Illuminant => "Illuminants",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NamedSpectrum {
+    pub name: String,
+    pub category: NamedSpectrumCategory,
+    pub lambda: Vec<f32>,
+    pub values: Vec<f32>,
+}
+
+impl NamedSpectrum {
+    fn from_interleaved(name: &str, category: NamedSpectrumCategory, data: &[f32]) -> Self {
+        let n = data.len() / 2;
+        let mut lambda = Vec::with_capacity(n);
+        let mut values = Vec::with_capacity(n);
+        for i in 0..n {
+            lambda.push(data[2 * i + 0]);
+            values.push(data[2 * i + 1]);
+        }
+        NamedSpectrum {
+            name: name.to_string(),
+            category,
+            lambda,
+            values,
+        }
+    }
+
+    pub fn get_filename(&self) -> String {
+        return get_named_spectrum_filename(&self.name);
+    }
+}
+
+const LIBRARY_LAMBDA_START: f32 = 380.0;
+const LIBRARY_LAMBDA_END: f32 = 780.0;
+const LIBRARY_LAMBDA_STEP: f32 = 10.0;
+
+fn library_lambda() -> Vec<f32> {
+    let n = ((LIBRARY_LAMBDA_END - LIBRARY_LAMBDA_START) / LIBRARY_LAMBDA_STEP) as usize + 1;
+    return (0..n)
+        .map(|i| LIBRARY_LAMBDA_START + i as f32 * LIBRARY_LAMBDA_STEP)
+        .collect();
+}
+
+fn sellmeier_eta(coeffs: &[f64; 6], lambda_nm: f32) -> f32 {
+    let l = lambda_nm as f64 * 1e-3; // um
+    let l2 = l * l;
+    let mut n2 = 1.0;
+    for i in 0..3 {
+        n2 += coeffs[i] * l2 / (l2 - coeffs[i + 3]);
+    }
+    return f64::sqrt(n2) as f32;
+}
+
+fn create_glass_spectrum(name: &str, coeffs: &[f64; 6]) -> NamedSpectrum {
+    let lambda = library_lambda();
+    let values = lambda.iter().map(|l| sellmeier_eta(coeffs, *l)).collect();
+    NamedSpectrum {
+        name: format!("glass-{}", name),
+        category: NamedSpectrumCategory::Glass,
+        lambda,
+        values,
+    }
+}
+
+// CIE standard illuminant A, defined analytically and normalized to 100 at 560nm.
+fn create_illuminant_a() -> NamedSpectrum {
+    let lambda = library_lambda();
+    let c2 = 1.435e7_f64;
+    let t = 2848.0_f64;
+    let values = lambda
+        .iter()
+        .map(|l| {
+            let l = *l as f64;
+            let v = 100.0 * f64::powi(560.0 / l, 5) * (f64::exp(c2 / (t * 560.0)) - 1.0)
+                / (f64::exp(c2 / (t * l)) - 1.0);
+            v as f32
+        })
+        .collect();
+    NamedSpectrum {
+        name: "stdillum-A".to_string(),
+        category: NamedSpectrumCategory::Illuminant,
+        lambda,
+        values,
+    }
+}
+
+const STDILLUM_F: [(&str, &[f32]); 12] = [
+    ("stdillum-F1", &STDILLUM_F1),
+    ("stdillum-F2", &STDILLUM_F2),
+    ("stdillum-F3", &STDILLUM_F3),
+    ("stdillum-F4", &STDILLUM_F4),
+    ("stdillum-F5", &STDILLUM_F5),
+    ("stdillum-F6", &STDILLUM_F6),
+    ("stdillum-F7", &STDILLUM_F7),
+    ("stdillum-F8", &STDILLUM_F8),
+    ("stdillum-F9", &STDILLUM_F9),
+    ("stdillum-F10", &STDILLUM_F10),
+    ("stdillum-F11", &STDILLUM_F11),
+    ("stdillum-F12", &STDILLUM_F12),
+];
+
+// Built on first use; the UI looks spectra up every frame
+static NAMED_SPECTRA: LazyLock<Vec<NamedSpectrum>> = LazyLock::new(create_named_spectra);
+
+fn create_named_spectra() -> Vec<NamedSpectrum> {
+    let metal = NamedSpectrumCategory::Metal;
+    let illum = NamedSpectrumCategory::Illuminant;
+    let mut spectra = vec![
+        NamedSpectrum::from_interleaved("metal-Au-eta", metal, &METAL_AU_ETA),
+        NamedSpectrum::from_interleaved("metal-Au-k", metal, &METAL_AU_K),
+        NamedSpectrum::from_interleaved("metal-Ag-eta", metal, &METAL_AG_ETA),
+        NamedSpectrum::from_interleaved("metal-Ag-k", metal, &METAL_AG_K),
+        NamedSpectrum::from_interleaved("metal-Cu-eta", metal, &METAL_CU_ETA),
+        NamedSpectrum::from_interleaved("metal-Cu-k", metal, &METAL_CU_K),
+        NamedSpectrum::from_interleaved("metal-Al-eta", metal, &METAL_AL_ETA),
+        NamedSpectrum::from_interleaved("metal-Al-k", metal, &METAL_AL_K),
+        NamedSpectrum::from_interleaved("metal-CuZn-eta", metal, &METAL_CUZN_ETA),
+        NamedSpectrum::from_interleaved("metal-CuZn-k", metal, &METAL_CUZN_K),
+    ];
+    for (name, coeffs) in GLASS_SELLMEIER.iter() {
+        spectra.push(create_glass_spectrum(name, coeffs));
+    }
+    spectra.push(create_illuminant_a());
+    spectra.push(NamedSpectrum::from_interleaved(
+        "stdillum-D50",
+        illum,
+        &STDILLUM_D50,
+    ));
+    spectra.push(NamedSpectrum::from_interleaved(
+        "stdillum-D65",
+        illum,
+        &STDILLUM_D65,
+    ));
+    for (name, data) in STDILLUM_F.iter() {
+        spectra.push(NamedSpectrum::from_interleaved(name, illum, *data));
+    }
+    return spectra;
+}
+
+/// Returns all spectra of the built-in library.
+/// Names follow the pbrt-v4 convention (`metal-Au-eta`, `glass-BK7`, `stdillum-D65`, ...).
+/// Illuminants are A, D50, D65 and F1 to F12.
+pub fn get_named_spectra() -> &'static [NamedSpectrum] {
+    return &NAMED_SPECTRA;
+}
+
+pub fn find_named_spectrum(name: &str) -> Option<&'static NamedSpectrum> {
+    return get_named_spectra().iter().find(|s| s.name == name);
+}
+
+/// Relative path used when a library spectrum is referenced from a scene.
+pub fn get_named_spectrum_filename(name: &str) -> String {
+    return format!("spds/{}.spd", name);
+}
+
+/// Inverse of `get_named_spectrum_filename`.
+pub fn find_named_spectrum_by_filename(filename: &str) -> Option<&'static NamedSpectrum> {
+    let path = Path::new(filename);
+    let parent = path.parent()?.to_str()?;
+    if parent != "spds" {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    return find_named_spectrum(stem);
+}
+
+/// Writes a library spectrum into `dir` and returns the full path of the written file.
+pub fn write_named_spectrum(spectrum: &NamedSpectrum, dir: &Path) -> Result<PathBuf, PbrtError> {
+    let path = dir.join(spectrum.get_filename());
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let comment = format!("{} (pbrt-ui spectral library)", spectrum.name);
+    write_spd_file(
+        path.to_str().unwrap(),
+        &spectrum.lambda,
+        &spectrum.values,
+        &comment,
+    )?;
+    return Ok(path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversion::spectrum::Spectrum;
+
+    fn near_equal(a: f32, b: f32, epsilon: f32) -> bool {
+        (a - b).abs() < epsilon
+    }
+
+    #[test]
+    fn test_named_spectra_001() {
+        let spectra = get_named_spectra();
+        for s in spectra.iter() {
+            assert_eq!(s.lambda.len(), s.values.len());
+            assert!(s.lambda.len() >= 2);
+            for i in 0..(s.lambda.len() - 1) {
+                assert!(s.lambda[i] < s.lambda[i + 1], "{} is not sorted", s.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_named_spectra_002() {
+        // BK7 at the sodium d-line
+        let bk7 = find_named_spectrum("glass-BK7").unwrap();
+        assert!(near_equal(
+            sellmeier_eta(&GLASS_SELLMEIER[0].1, 587.6),
+            1.5168,
+            1e-3
+        ));
+        assert!(bk7.values.iter().all(|v| *v > 1.5 && *v < 1.54));
+    }
+
+    #[test]
+    fn test_named_spectra_003() {
+        let filename = get_named_spectrum_filename("metal-Au-eta");
+        let s = find_named_spectrum_by_filename(&filename).unwrap();
+        assert_eq!(s.name, "metal-Au-eta");
+        assert!(find_named_spectrum_by_filename("metal-Au-eta.spd").is_none());
+    }
+
+    #[test]
+    fn test_named_spectra_004() {
+        // chromaticities of the fluorescent illuminants as given by the CIE
+        let expected = [
+            (0.3131, 0.3373),
+            (0.3721, 0.3751),
+            (0.4091, 0.3943),
+            (0.4402, 0.4033),
+            (0.3138, 0.3453),
+            (0.3779, 0.3884),
+            (0.3129, 0.3293),
+            (0.3459, 0.3588),
+            (0.3742, 0.3728),
+            (0.3461, 0.3599),
+            (0.3805, 0.3771),
+            (0.4370, 0.4044),
+        ];
+        for (i, (x, y)) in expected.iter().enumerate() {
+            let name = format!("stdillum-F{}", i + 1);
+            let s = find_named_spectrum(&name).unwrap();
+            let xyz = Spectrum::from_sampled(&s.lambda, &s.values).to_xyz();
+            let sum = xyz[0] + xyz[1] + xyz[2];
+            assert!(near_equal(xyz[0] / sum, *x, 2e-3), "{}", name);
+            assert!(near_equal(xyz[1] / sum, *y, 2e-3), "{}", name);
+        }
+    }
+}
@@ -1,13 +1,20 @@
 mod blackbody;
 mod config;
 mod data_cie;
+mod data_named;
 mod data_xyz;
 mod float_file;
+mod library;
+mod resolve;
 mod spectrum;
 mod utils;
 
+pub use float_file::write_spd_file;
+pub use library::*;
+pub use resolve::*;
 pub use spectrum::Spectrum;
 
+use crate::conversion::color_space::ColorSpace;
 use crate::error::PbrtError;
 use blackbody::*;
 use config::*;
@@ -38,7 +45,7 @@ impl Spectrum {
         };
     }
 
-    pub fn load_from_file(path: &str) -> Result<Spectrum, PbrtError> {
+    pub fn load_samples_from_file(path: &str) -> Result<(Vec<f32>, Vec<f32>), PbrtError> {
         match read_float_file(path) {
             Ok(vals) => {
                 if vals.len() % 2 != 0 {
@@ -53,7 +60,7 @@ impl Spectrum {
                     wls.push(vals[2 * j] as f32);
                     v.push(vals[2 * j + 1] as f32);
                 }
-                return Ok(Spectrum::from_sampled(&wls, &v));
+                return Ok((wls, v));
             }
             Err(e) => {
                 return Err(e);
@@ -61,6 +68,22 @@ impl Spectrum {
         }
     }
 
+    pub fn load_from_file(path: &str) -> Result<Spectrum, PbrtError> {
+        let (wls, v) = Self::load_samples_from_file(path)?;
+        if wls.is_empty() {
+            return Err(PbrtError::error(&format!(
+                "No samples found in spectrum file \"{}\".",
+                path
+            )));
+        }
+        return Ok(Spectrum::from_sampled(&wls, &v));
+    }
+
+    pub fn from_named(name: &str) -> Option<Spectrum> {
+        let s = find_named_spectrum(name)?;
+        return Some(Spectrum::from_sampled(&s.lambda, &s.values));
+    }
+
     pub fn from_blackbody(values: &[f32]) -> Spectrum {
         let n_values = values.len();
         assert_eq!(n_values % 2, 0);
@@ -90,7 +113,11 @@ impl Spectrum {
     }
 
     pub fn to_rgb(&self) -> [f32; 3] {
+        return self.to_rgb_in(ColorSpace::Srgb);
+    }
+
+    pub fn to_rgb_in(&self, color_space: ColorSpace) -> [f32; 3] {
         let xyz = self.to_xyz();
-        return xyz_to_rgb(&xyz);
+        return color_space.xyz_to_rgb(&xyz);
     }
 }
@@ -0,0 +1,120 @@
+use super::library::find_named_spectrum_by_filename;
+use super::spectrum::Spectrum;
+use crate::conversion::color_space::ColorSpace;
+use crate::model::base::Property;
+
+fn spectrum_from_inline(v: &[f32]) -> Option<Spectrum> {
+    if v.len() < 2 || v.len() % 2 != 0 {
+        return None;
+    }
+    let n = v.len() / 2;
+    let mut lambda = Vec::with_capacity(n);
+    let mut values = Vec::with_capacity(n);
+    for i in 0..n {
+        lambda.push(v[2 * i + 0]);
+        values.push(v[2 * i + 1]);
+    }
+    return Some(Spectrum::from_sampled(&lambda, &values));
+}
+
+fn spectrum_from_name(name: &str, fullpath: Option<&str>) -> Option<Spectrum> {
+    if let Some(fullpath) = fullpath {
+        if let Ok(s) = Spectrum::load_from_file(fullpath) {
+            return Some(s);
+        }
+    }
+    if let Some(s) = Spectrum::from_named(name) {
+        return Some(s);
+    }
+    let s = find_named_spectrum_by_filename(name)?;
+    return Some(Spectrum::from_sampled(&s.lambda, &s.values));
+}
+
+/// Color of a blackbody emitter, normalized like pbrt's `blackbody` parameter.
+pub fn blackbody_to_rgb(temperature: f32) -> [f32; 3] {
+    return Spectrum::from_blackbody(&[temperature, 1.0]).to_rgb();
+}
+
+/// Resolves a color-like property to linear sRGB.
+/// `fullpath` is the resolved path when the property names a spectrum file,
+/// `color_space` is the working color space RGB values are given in.
+pub fn property_to_rgb(
+    key_type: &str,
+    value: &Property,
+    fullpath: Option<&str>,
+    color_space: ColorSpace,
+) -> Option<[f32; 3]> {
+    match value {
+        Property::Floats(v) => match key_type {
+            "blackbody" => {
+                if v.len() == 1 {
+                    return Some(blackbody_to_rgb(v[0])); // pbrt-v4 gives the temperature only
+                }
+                if v.len() < 2 {
+                    return None;
+                }
+                let n = v.len() / 2 * 2;
+                return Some(Spectrum::from_blackbody(&v[..n]).to_rgb());
+            }
+            "spectrum" => {
+                return spectrum_from_inline(v).map(|s| s.to_rgb());
+            }
+            "xyz" => {
+                if v.len() < 3 {
+                    return None;
+                }
+                return Some(ColorSpace::Srgb.xyz_to_rgb(v));
+            }
+            "float" => {
+                if v.is_empty() {
+                    return None;
+                }
+                return Some([v[0], v[0], v[0]]);
+            }
+            _ => {
+                if v.len() < 3 {
+                    return None;
+                }
+                return Some(color_space.convert_rgb(v, ColorSpace::Srgb));
+            }
+        },
+        Property::Strings(v) => {
+            if key_type != "spectrum" || v.is_empty() {
+                return None;
+            }
+            return spectrum_from_name(&v[0], fullpath).map(|s| s.to_rgb());
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_property_to_rgb_001() {
+        // low temperatures are reddish, high temperatures are bluish
+        let warm = blackbody_to_rgb(3000.0);
+        let cool = blackbody_to_rgb(10000.0);
+        assert!(warm[0] > warm[2]);
+        assert!(cool[2] > cool[0]);
+        let value = Property::Floats(vec![3000.0, 1.0]);
+        let rgb = property_to_rgb("blackbody", &value, None, ColorSpace::Srgb).unwrap();
+        assert_eq!(rgb, warm);
+        let value = Property::Floats(vec![3000.0]);
+        let rgb = property_to_rgb("blackbody", &value, None, ColorSpace::Srgb).unwrap();
+        assert_eq!(rgb, warm);
+    }
+
+    #[test]
+    fn test_property_to_rgb_002() {
+        // a flat inline spectrum keeps its luminance
+        let value = Property::Floats(vec![300.0, 0.5, 800.0, 0.5]);
+        let rgb = property_to_rgb("spectrum", &value, None, ColorSpace::Srgb).unwrap();
+        let xyz = ColorSpace::Srgb.rgb_to_xyz(&rgb);
+        assert!((xyz[1] - 0.5).abs() < 0.02);
+        let value = Property::Strings(vec!["stdillum-D65".to_string()]);
+        assert!(property_to_rgb("spectrum", &value, None, ColorSpace::Srgb).is_some());
+    }
+}
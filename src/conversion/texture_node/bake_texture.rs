@@ -0,0 +1,170 @@
+use super::create_image_variants::create_image_variant;
+use super::create_texture_nodes::create_texture_nodes;
+use super::dyna_image::DynaImage;
+use super::texture_node::TexturePurpose;
+use crate::conversion::color_space::ColorSpace;
+use crate::conversion::color_space::TransferFunction;
+use crate::error::PbrtError;
+use crate::model::scene::ResourceCacheManager;
+use crate::model::scene::ResourceManager;
+
+use std::path::Path;
+
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BakeFormat {
+    #[default]
+    Exr,
+    Png,
+}
+
+impl BakeFormat {
+    pub fn all() -> &'static [BakeFormat] {
+        &[BakeFormat::Exr, BakeFormat::Png]
+    }
+
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            BakeFormat::Exr => "exr",
+            BakeFormat::Png => "png",
+        }
+    }
+
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            BakeFormat::Exr => "OpenEXR",
+            BakeFormat::Png => "PNG",
+        }
+    }
+}
+
+/// Flattens a texture and the textures it depends on into a single square image.
+/// The nodes are built in a private cache so the bake resolution does not touch the viewport cache.
+/// Texture images are linear sRGB, so the result is converted back to the scene color space.
+pub fn bake_texture_image(
+    resource_manager: &ResourceManager,
+    texture_id: Uuid,
+    size: u32,
+    color_space: ColorSpace,
+) -> Result<DynaImage, PbrtError> {
+    let mut resource_cache_manager = ResourceCacheManager::new();
+    resource_cache_manager.set_color_space(color_space);
+    create_texture_nodes(resource_manager, &mut resource_cache_manager);
+    let texture_node = resource_cache_manager
+        .textures
+        .get(&texture_id)
+        .ok_or_else(|| PbrtError::error("Texture is not found."))?;
+    let image = create_image_variant(
+        texture_node,
+        resource_manager,
+        TexturePurpose::Bake(size),
+        color_space,
+    )
+    .ok_or_else(|| PbrtError::error("Texture type is not supported for baking."))?;
+    let image = image.read().unwrap();
+    // converted before resizing, which clamps the colors outside of sRGB
+    let image = image.convert_color_space(ColorSpace::Srgb, color_space);
+    // constant textures come out as a single pixel
+    let image = image.resize_exact(size, size, image::imageops::FilterType::Nearest);
+    return Ok(image);
+}
+
+fn encode_srgb(image: &DynaImage) -> image::DynamicImage {
+    let tf = TransferFunction::Srgb;
+    match image {
+        DynaImage::ImageLuma8(img) => image::DynamicImage::ImageLuma8(img.clone()),
+        DynaImage::ImageRgb8(img) => image::DynamicImage::ImageRgb8(img.clone()),
+        DynaImage::ImageLuma32F(img) => {
+            let mut result_image = image::GrayImage::new(img.width(), img.height());
+            for (x, y, pixel) in img.enumerate_pixels() {
+                result_image.put_pixel(x, y, image::Luma([tf.encode_to_byte(pixel[0])]));
+            }
+            image::DynamicImage::ImageLuma8(result_image)
+        }
+        DynaImage::ImageRgb32F(_) => image::DynamicImage::ImageRgb8(image.to_rgb8()),
+    }
+}
+
+/// Writes a baked image. EXR keeps linear floats, PNG is sRGB encoded and clamped.
+pub fn save_baked_image(
+    image: &DynaImage,
+    path: &Path,
+    format: BakeFormat,
+) -> Result<(), PbrtError> {
+    let output = match format {
+        BakeFormat::Exr => image::DynamicImage::ImageRgb32F(image.to_rgb32f()),
+        BakeFormat::Png => encode_srgb(image),
+    };
+    let image_format = match format {
+        BakeFormat::Exr => image::ImageFormat::OpenExr,
+        BakeFormat::Png => image::ImageFormat::Png,
+    };
+    output
+        .save_with_format(path, image_format)
+        .map_err(|e| PbrtError::error(&format!("Failed to save \"{}\": {}", path.display(), e)))?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::base::Matrix4x4;
+    use crate::model::base::PropertyMap;
+    use crate::model::scene::Texture;
+
+    use std::sync::Arc;
+    use std::sync::RwLock;
+
+    fn add_texture(
+        resource_manager: &mut ResourceManager,
+        name: &str,
+        ty: &str,
+        props: &PropertyMap,
+    ) -> Uuid {
+        let texture = Texture::new(name, "spectrum", ty, None, props, &Matrix4x4::identity());
+        let id = texture.get_id();
+        resource_manager
+            .textures
+            .insert(id, Arc::new(RwLock::new(texture)));
+        return id;
+    }
+
+    #[test]
+    fn test_bake_texture_image_001() {
+        // a mix of two constants flattens to the blended color at the requested size
+        let mut resource_manager = ResourceManager::default();
+        let mut props = PropertyMap::new();
+        props.add_rgb("rgb value", &[1.0, 0.0, 0.0]);
+        add_texture(&mut resource_manager, "red", "constant", &props);
+        let mut props = PropertyMap::new();
+        props.add_rgb("rgb value", &[0.0, 0.0, 1.0]);
+        add_texture(&mut resource_manager, "blue", "constant", &props);
+        let mut props = PropertyMap::new();
+        props.add_string("texture tex1", "red");
+        props.add_string("texture tex2", "blue");
+        props.add_floats("float amount", &[0.25]);
+        let id = add_texture(&mut resource_manager, "mixed", "mix", &props);
+
+        let image = bake_texture_image(&resource_manager, id, 16, ColorSpace::Srgb).unwrap();
+        assert_eq!(image.dimensions(), (16, 16));
+        let pixel = image.to_rgb32f().get_pixel(7, 7).0;
+        assert!((pixel[0] - 0.75).abs() < 1e-3);
+        assert!((pixel[2] - 0.25).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_bake_texture_image_002() {
+        // the baked colors are written back in the color space of the scene
+        let mut resource_manager = ResourceManager::default();
+        let mut props = PropertyMap::new();
+        props.add_rgb("rgb value", &[0.8, 0.2, 0.1]);
+        let id = add_texture(&mut resource_manager, "red", "constant", &props);
+
+        let image = bake_texture_image(&resource_manager, id, 4, ColorSpace::Rec2020).unwrap();
+        let pixel = image.to_rgb32f().get_pixel(1, 1).0;
+        assert!((pixel[0] - 0.8).abs() < 1e-3);
+        assert!((pixel[1] - 0.2).abs() < 1e-3);
+        assert!((pixel[2] - 0.1).abs() < 1e-3);
+    }
+}
@@ -1,4 +1,6 @@
 use super::dyna_image::DynaImage;
+use crate::conversion::color_space::ColorSpace;
+use crate::conversion::color_space::gamma_correct;
 use image::DynamicImage;
 
 use super::render_texture_image::render_texture_image;
@@ -61,11 +63,7 @@ fn sort_texture_nodes_by_dependency(
 }
 
 fn linear_to_srgb(value: f32) -> u8 {
-    if value <= 0.0031308 {
-        (value * 12.92 * 255.0).round() as u8
-    } else {
-        ((1.055 * value.powf(1.0 / 2.4) - 0.055) * 255.0).round() as u8
-    }
+    (gamma_correct(value) * 255.0).round() as u8
 }
 
 fn convert_float_to_u8(image: &image::Rgb32FImage) -> image::RgbImage {
@@ -111,6 +109,7 @@ fn create_image_variants_for_nodes(
     texture_nodes: &Vec<Arc<RwLock<TextureNode>>>,
     resource_manager: &ResourceManager,
     purpose: TexturePurpose,
+    color_space: ColorSpace,
 ) {
     let ordered_nodes = sort_texture_nodes_by_dependency(&texture_nodes);
     /*
@@ -152,7 +151,7 @@ fn create_image_variants_for_nodes(
         }
         let texture = resource_manager.textures.get(&texture_id).unwrap();
         let texture = texture.read().unwrap();
-        if let Some(image) = render_texture_image(&texture, &dependencies, purpose) {
+        if let Some(image) = render_texture_image(&texture, &dependencies, purpose, color_space) {
             if purpose == TexturePurpose::Render {
                 texture_node
                     .image_variants
@@ -177,12 +176,13 @@ pub fn create_image_variant(
     texture_node: &Arc<RwLock<TextureNode>>,
     resource_manager: &ResourceManager,
     purpose: TexturePurpose,
+    color_space: ColorSpace,
 ) -> Option<Arc<RwLock<DynaImage>>> {
     if let Some(image) = texture_node.read().unwrap().image_variants.get(&purpose) {
         return Some(image.clone());
     } else {
         let texture_nodes = vec![texture_node.clone()];
-        create_image_variants_for_nodes(&texture_nodes, resource_manager, purpose);
+        create_image_variants_for_nodes(&texture_nodes, resource_manager, purpose, color_space);
         if let Some(image) = texture_node.read().unwrap().image_variants.get(&purpose) {
             return Some(image.clone());
         }
@@ -190,6 +190,32 @@ pub fn create_image_variant(
     }
 }
 
+/// Converts the Render variant of a float bump texture into a normal map.
+pub fn create_normal_map_variant(
+    texture_node: &Arc<RwLock<TextureNode>>,
+    resource_manager: &ResourceManager,
+    color_space: ColorSpace,
+) -> Option<Arc<RwLock<DynaImage>>> {
+    let purpose = TexturePurpose::RenderNormal;
+    if let Some(image) = texture_node.read().unwrap().image_variants.get(&purpose) {
+        return Some(image.clone());
+    }
+    let image = create_image_variant(
+        texture_node,
+        resource_manager,
+        TexturePurpose::Render,
+        color_space,
+    )?;
+    let normal_map = image.read().unwrap().to_normal_map();
+    let normal_map = Arc::new(RwLock::new(normal_map));
+    texture_node
+        .write()
+        .unwrap()
+        .image_variants
+        .insert(purpose, normal_map.clone());
+    return Some(normal_map);
+}
+
 pub fn create_image_variants(
     resource_manager: &ResourceManager,
     resource_cache_manager: &mut ResourceCacheManager,
@@ -201,5 +227,10 @@ pub fn create_image_variants(
             texture_nodes.push(texture_node.clone());
         }
     }
-    create_image_variants_for_nodes(&texture_nodes, resource_manager, purpose);
+    create_image_variants_for_nodes(
+        &texture_nodes,
+        resource_manager,
+        purpose,
+        resource_cache_manager.color_space,
+    );
 }
@@ -1,21 +1,17 @@
+use crate::conversion::color_space::ColorSpace;
+use crate::conversion::color_space::convert_rgb_buffer;
+use crate::conversion::color_space::gamma_correct;
+use crate::conversion::color_space::inverse_gamma_correct;
+
 use image::{buffer::ConvertBuffer, imageops};
 //pub type Rgb32FImage = ImageBuffer<Rgb<f32>, Vec<f32>>;
 
 fn linear_to_srgb(value: f32) -> u8 {
-    if value <= 0.0031308 {
-        (value * 12.92 * 255.0).round() as u8
-    } else {
-        ((1.055 * value.powf(1.0 / 2.4) - 0.055) * 255.0).round() as u8
-    }
+    (gamma_correct(value) * 255.0).round() as u8
 }
 
 fn srgb_to_linear(value: u8) -> f32 {
-    let v = value as f32 / 255.0;
-    if v <= 0.04045 {
-        v / 12.92
-    } else {
-        ((v + 0.055) / 1.055).powf(2.4)
-    }
+    inverse_gamma_correct(value as f32 / 255.0)
 }
 
 // Inner representation of images used in texture cache
@@ -142,4 +138,87 @@ impl DynaImage {
             }
         }
     }
+
+    /// Converts a height image into a tangent space normal map with a Sobel filter.
+    /// Heights are taken from the first channel. Slopes are per unit uv, with v pointing up the image,
+    /// and the result stores the unit normal in [-1, 1] without encoding.
+    #[must_use]
+    pub fn to_normal_map(&self) -> DynaImage {
+        let heights: image::ImageBuffer<image::Luma<f32>, Vec<f32>> = match self {
+            DynaImage::ImageLuma32F(img) => img.clone(),
+            _ => {
+                let img = self.to_rgb32f();
+                image::ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+                    image::Luma([img.get_pixel(x, y)[0]])
+                })
+            }
+        };
+        let (width, height) = heights.dimensions();
+        // textures repeat by default, so the kernel wraps around the borders
+        let h = |x: i64, y: i64| -> f32 {
+            let x = x.rem_euclid(width as i64) as u32;
+            let y = y.rem_euclid(height as i64) as u32;
+            heights.get_pixel(x, y)[0]
+        };
+        let mut result_image = image::Rgb32FImage::new(width, height);
+        for (x, y, pixel) in result_image.enumerate_pixels_mut() {
+            let (x, y) = (x as i64, y as i64);
+            let gx = (h(x + 1, y - 1) + 2.0 * h(x + 1, y) + h(x + 1, y + 1))
+                - (h(x - 1, y - 1) + 2.0 * h(x - 1, y) + h(x - 1, y + 1));
+            let gy = (h(x - 1, y + 1) + 2.0 * h(x, y + 1) + h(x + 1, y + 1))
+                - (h(x - 1, y - 1) + 2.0 * h(x, y - 1) + h(x + 1, y - 1));
+            // the kernel spans two texels with weights summing to 4
+            let du = gx / 8.0 * width as f32;
+            let dv = -gy / 8.0 * height as f32;
+            let n = [-du, -dv, 1.0];
+            let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            *pixel = image::Rgb([n[0] / length, n[1] / length, n[2] / length]);
+        }
+        return DynaImage::ImageRgb32F(result_image);
+    }
+
+    /// Converts linear RGB pixels from `src` to `dst`. 8-bit images are assumed to be sRGB encoded.
+    #[must_use]
+    pub fn convert_color_space(&self, src: ColorSpace, dst: ColorSpace) -> DynaImage {
+        match self {
+            DynaImage::ImageLuma8(img) => DynaImage::ImageLuma8(img.clone()),
+            DynaImage::ImageLuma32F(img) => DynaImage::ImageLuma32F(img.clone()),
+            _ => {
+                let mut img = self.to_rgb32f();
+                convert_rgb_buffer(&mut img, src, dst);
+                DynaImage::ImageRgb32F(img)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_normal_map_001() {
+        // a ramp rising along u tilts the normal against +u and leaves v untouched
+        let img = image::ImageBuffer::from_fn(8, 8, |x, _y| {
+            let x = x.min(7 - x) as f32; // keeps the wrapped kernel on a constant slope
+            image::Luma([x * 0.125])
+        });
+        let normal_map = DynaImage::ImageLuma32F(img).to_normal_map().to_rgb32f();
+        let n = normal_map.get_pixel(1, 4).0;
+        // du = 0.125 height per texel * 8 texels per unit uv
+        let expected = 1.0 / 2.0f32.sqrt();
+        assert!((n[0] + expected).abs() < 1e-5);
+        assert!(n[1].abs() < 1e-5);
+        assert!((n[2] - expected).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_to_normal_map_002() {
+        // image rows go down while v goes up
+        let img = image::ImageBuffer::from_fn(4, 4, |_x, y| image::Luma([y as f32]));
+        let normal_map = DynaImage::ImageLuma32F(img).to_normal_map().to_rgb32f();
+        let n = normal_map.get_pixel(2, 1).0;
+        assert!(n[1] > 0.0);
+        assert!(n[0].abs() < 1e-5);
+    }
 }
@@ -1,10 +1,13 @@
+mod bake_texture;
 mod create_image_variants;
 mod create_texture_nodes;
 mod dyna_image;
 mod render_texture_image;
 mod texture_node;
+pub use bake_texture::*;
 pub use create_image_variants::create_image_variant;
 pub use create_image_variants::create_image_variants;
+pub use create_image_variants::create_normal_map_variant;
 pub use create_texture_nodes::create_texture_nodes;
 pub use dyna_image::DynaImage;
 pub use render_texture_image::render_texture_image;
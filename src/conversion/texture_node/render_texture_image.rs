@@ -1,5 +1,6 @@
 use super::texture_node::TexturePurpose;
-use crate::conversion::spectrum::Spectrum;
+use crate::conversion::color_space::ColorSpace;
+use crate::conversion::spectrum::property_to_rgb;
 use crate::model::base::Property;
 use crate::model::scene::Texture;
 
@@ -15,48 +16,29 @@ const ICON_SIZE: u32 = 64;
 const DISPLAY_SIZE: u32 = 256;
 const RENDER_SIZE: u32 = 1024;
 
-fn get_color_texture_image(texture: &Texture, key: &str) -> Option<DynaImage> {
+// Texture images hold linear sRGB, which is what the viewport and the previews display.
+// Constant colors are given in the scene working color space and are converted here.
+fn get_color_texture_image(
+    texture: &Texture,
+    key: &str,
+    color_space: ColorSpace,
+) -> Option<DynaImage> {
     let props = texture.as_property_map();
     if let Some((key_type, key_name, value)) = props.entry(key) {
         if let Property::Floats(v) = value {
-            if key_type == "blackbody" {
-                let s = Spectrum::from_blackbody(&v);
-                let color = s.to_rgb();
-                let color = image::Rgb([color[0] as f32, color[1] as f32, color[2] as f32]);
-                let image_buffer = image::ImageBuffer::from_pixel(1, 1, color);
-                return Some(DynaImage::ImageRgb32F(image_buffer));
-            } else {
-                if v.len() == 1 {
-                    let value = image::Luma([v[0] as f32]);
-                    let image_buffer = image::ImageBuffer::from_pixel(1, 1, value);
-                    return Some(DynaImage::ImageLuma32F(image_buffer));
-                } else if v.len() == 3 {
-                    let color = image::Rgb([(v[0]) as f32, (v[1]) as f32, (v[2]) as f32]);
-                    let image_buffer = image::ImageBuffer::from_pixel(1, 1, color);
-                    return Some(DynaImage::ImageRgb32F(image_buffer));
-                }
-            }
-        } else if let Property::Strings(_name) = value {
-            if key_type == "spectrum" {
-                let fullpath_name = format!("{}_fullpath", key_name);
-                if let Some(src) = props.get(&fullpath_name) {
-                    if let Property::Strings(v) = src {
-                        assert!(
-                            v.len() == 1,
-                            "Spectrum fullpath must have exactly one value"
-                        );
-                        let fullpath = v[0].clone();
-                        if let Ok(s) = Spectrum::load_from_file(&fullpath) {
-                            let color = s.to_rgb();
-                            let color =
-                                image::Rgb([color[0] as f32, color[1] as f32, color[2] as f32]);
-                            let image_buffer = image::ImageBuffer::from_pixel(1, 1, color);
-                            return Some(DynaImage::ImageRgb32F(image_buffer));
-                        }
-                    }
-                }
+            // an empty value falls through to the default of the caller
+            if !v.is_empty() && (key_type == "float" || v.len() == 1) {
+                let value = image::Luma([v[0] as f32]);
+                let image_buffer = image::ImageBuffer::from_pixel(1, 1, value);
+                return Some(DynaImage::ImageLuma32F(image_buffer));
             }
         }
+        let fullpath = props.find_one_string(&format!("{}_fullpath", key_name));
+        if let Some(color) = property_to_rgb(key_type, value, fullpath.as_deref(), color_space) {
+            let color = image::Rgb(color);
+            let image_buffer = image::ImageBuffer::from_pixel(1, 1, color);
+            return Some(DynaImage::ImageRgb32F(image_buffer));
+        }
     }
     return None;
 }
@@ -84,7 +66,7 @@ fn resize_image_for_purpose(
     purpose: TexturePurpose,
 ) -> image::DynamicImage {
     match purpose {
-        TexturePurpose::Render => image,
+        TexturePurpose::Render | TexturePurpose::RenderNormal => image,
         TexturePurpose::Display | TexturePurpose::DisplaySrgb => {
             let resized = image.resize_exact(
                 DISPLAY_SIZE,
@@ -101,6 +83,20 @@ fn resize_image_for_purpose(
             );
             return resized;
         }
+        TexturePurpose::Bake(size) => {
+            let resized = image.resize_exact(size, size, image::imageops::FilterType::CatmullRom);
+            return resized;
+        }
+    }
+}
+
+// Resolution procedural textures are evaluated at.
+fn get_procedural_size(purpose: TexturePurpose) -> u32 {
+    match purpose {
+        TexturePurpose::Render | TexturePurpose::RenderNormal => RENDER_SIZE,
+        TexturePurpose::Display | TexturePurpose::DisplaySrgb => DISPLAY_SIZE,
+        TexturePurpose::Icon | TexturePurpose::IconSrgb => ICON_SIZE,
+        TexturePurpose::Bake(size) => size,
     }
 }
 
@@ -148,11 +144,12 @@ fn get_dependent_image(
     textue: &Texture,
     dependencies: &HashMap<String, Arc<RwLock<DynaImage>>>,
     key: &str,
+    color_space: ColorSpace,
 ) -> Option<Arc<RwLock<DynaImage>>> {
     if let Some(image) = dependencies.get(key) {
         return Some(image.clone());
     }
-    if let Some(image) = get_color_texture_image(textue, key) {
+    if let Some(image) = get_color_texture_image(textue, key, color_space) {
         let image = Arc::new(RwLock::new(image));
         return Some(image);
     }
@@ -191,8 +188,8 @@ fn load_imagemap_texture_image(texture: &Texture, purpose: TexturePurpose) -> Op
     return None;
 }
 
-fn render_constant_texture_image(texture: &Texture) -> Option<DynaImage> {
-    if let Some(color_image) = get_color_texture_image(texture, "value") {
+fn render_constant_texture_image(texture: &Texture, color_space: ColorSpace) -> Option<DynaImage> {
+    if let Some(color_image) = get_color_texture_image(texture, "value", color_space) {
         return Some(color_image);
     } else {
         // Default to white if color not found
@@ -298,10 +295,11 @@ fn mix_texture(tex1: &DynaImage, tex2: &DynaImage, amount: &DynaImage) -> Option
 fn render_mix_texture_image(
     texture: &Texture,
     dependencies: &HashMap<String, Arc<RwLock<DynaImage>>>,
+    color_space: ColorSpace,
 ) -> Option<DynaImage> {
-    let tex1 = get_dependent_image(texture, dependencies, "tex1")?;
-    let tex2 = get_dependent_image(texture, dependencies, "tex2")?;
-    let amount = get_dependent_image(texture, dependencies, "amount")?;
+    let tex1 = get_dependent_image(texture, dependencies, "tex1", color_space)?;
+    let tex2 = get_dependent_image(texture, dependencies, "tex2", color_space)?;
+    let amount = get_dependent_image(texture, dependencies, "amount", color_space)?;
     return mix_texture(
         &tex1.read().unwrap(),
         &tex2.read().unwrap(),
@@ -385,16 +383,128 @@ fn scale_texture(tex1: &DynaImage, tex2: &DynaImage) -> Option<DynaImage> {
 fn render_scale_texture_image(
     texture: &Texture,
     dependencies: &HashMap<String, Arc<RwLock<DynaImage>>>,
+    color_space: ColorSpace,
 ) -> Option<DynaImage> {
-    let tex1 = get_dependent_image(texture, dependencies, "tex1")?;
-    let tex2 = get_dependent_image(texture, dependencies, "tex2")?;
+    let tex1 = get_dependent_image(texture, dependencies, "tex1", color_space)?;
+    let tex2 = get_dependent_image(texture, dependencies, "tex2", color_space)?;
     return scale_texture(&tex1.read().unwrap(), &tex2.read().unwrap());
 }
 
+struct UVMapping {
+    su: f32,
+    sv: f32,
+    du: f32,
+    dv: f32,
+}
+
+impl UVMapping {
+    fn new(texture: &Texture) -> Self {
+        let props = texture.as_property_map();
+        Self {
+            su: props.find_one_float("uscale").unwrap_or(1.0),
+            sv: props.find_one_float("vscale").unwrap_or(1.0),
+            du: props.find_one_float("udelta").unwrap_or(0.0),
+            dv: props.find_one_float("vdelta").unwrap_or(0.0),
+        }
+    }
+
+    // Texture coordinates at the center of a pixel. Image rows run from t = 1 down to t = 0.
+    fn map(&self, x: u32, y: u32, size: u32) -> (f32, f32) {
+        let u = (x as f32 + 0.5) / size as f32;
+        let v = 1.0 - (y as f32 + 0.5) / size as f32;
+        return (self.su * u + self.du, self.sv * v + self.dv);
+    }
+}
+
+fn get_procedural_input(
+    texture: &Texture,
+    dependencies: &HashMap<String, Arc<RwLock<DynaImage>>>,
+    key: &str,
+    size: u32,
+    color_space: ColorSpace,
+) -> Option<image::Rgb32FImage> {
+    let image = get_dependent_image(texture, dependencies, key, color_space)?;
+    let image = image.read().unwrap();
+    let image = image.resize_exact(size, size, image::imageops::FilterType::Lanczos3);
+    return Some(image.to_rgb32f());
+}
+
+fn is_float_texture(texture: &Texture) -> bool {
+    return texture.get_color_type() == "float";
+}
+
+fn to_texture_image(texture: &Texture, image: image::Rgb32FImage) -> DynaImage {
+    if is_float_texture(texture) {
+        return DynaImage::ImageLuma32F(image.convert());
+    }
+    return DynaImage::ImageRgb32F(image);
+}
+
+fn render_checkerboard_texture_image(
+    texture: &Texture,
+    dependencies: &HashMap<String, Arc<RwLock<DynaImage>>>,
+    purpose: TexturePurpose,
+    color_space: ColorSpace,
+) -> Option<DynaImage> {
+    let dimension = texture
+        .as_property_map()
+        .find_one_int("dimension")
+        .unwrap_or(2);
+    if dimension != 2 {
+        return None; // solid checkerboards depend on the shape
+    }
+    let size = get_procedural_size(purpose);
+    let tex1 = get_procedural_input(texture, dependencies, "tex1", size, color_space)?;
+    let tex2 = get_procedural_input(texture, dependencies, "tex2", size, color_space)?;
+    let mapping = UVMapping::new(texture);
+    let mut image_buffer = image::Rgb32FImage::new(size, size);
+    for (x, y, pixel) in image_buffer.enumerate_pixels_mut() {
+        let (s, t) = mapping.map(x, y, size);
+        let check = (s.floor() as i64 + t.floor() as i64) % 2 == 0;
+        *pixel = if check {
+            *tex1.get_pixel(x, y)
+        } else {
+            *tex2.get_pixel(x, y)
+        };
+    }
+    return Some(to_texture_image(texture, image_buffer));
+}
+
+fn render_bilerp_texture_image(
+    texture: &Texture,
+    purpose: TexturePurpose,
+    color_space: ColorSpace,
+) -> Option<DynaImage> {
+    let get_corner = |key: &str| -> [f32; 3] {
+        match get_color_texture_image(texture, key, color_space) {
+            Some(image) => image.to_rgb32f().get_pixel(0, 0).0,
+            None => [0.0, 0.0, 0.0],
+        }
+    };
+    let v00 = get_corner("v00");
+    let v01 = get_corner("v01");
+    let v10 = get_corner("v10");
+    let v11 = get_corner("v11");
+    let size = get_procedural_size(purpose);
+    let mapping = UVMapping::new(texture);
+    let mut image_buffer = image::Rgb32FImage::new(size, size);
+    for (x, y, pixel) in image_buffer.enumerate_pixels_mut() {
+        let (s, t) = mapping.map(x, y, size);
+        for i in 0..3 {
+            pixel[i] = (1.0 - s) * (1.0 - t) * v00[i]
+                + (1.0 - s) * t * v01[i]
+                + s * (1.0 - t) * v10[i]
+                + s * t * v11[i];
+        }
+    }
+    return Some(to_texture_image(texture, image_buffer));
+}
+
 pub fn render_texture_image(
     texture: &Texture,
     dependencies: &HashMap<String, Arc<RwLock<DynaImage>>>,
     purpose: TexturePurpose,
+    color_space: ColorSpace,
 ) -> Option<DynaImage> {
     let texture_type = texture.get_type();
     match texture_type.as_str() {
@@ -402,13 +512,19 @@ pub fn render_texture_image(
             return load_imagemap_texture_image(texture, purpose);
         }
         "constant" => {
-            return render_constant_texture_image(texture);
+            return render_constant_texture_image(texture, color_space);
         }
         "mix" => {
-            return render_mix_texture_image(texture, dependencies);
+            return render_mix_texture_image(texture, dependencies, color_space);
         }
         "scale" => {
-            return render_scale_texture_image(texture, dependencies);
+            return render_scale_texture_image(texture, dependencies, color_space);
+        }
+        "checkerboard" => {
+            return render_checkerboard_texture_image(texture, dependencies, purpose, color_space);
+        }
+        "bilerp" => {
+            return render_bilerp_texture_image(texture, purpose, color_space);
         }
         _ => {
             return None; // Placeholder return
@@ -22,6 +22,8 @@ pub enum TexturePurpose {
     DisplaySrgb,
     Icon,
     IconSrgb,
+    Bake(u32),    // square image of the given size, written out by the bake action
+    RenderNormal, // normal map derived from the Render variant of a bump texture
 }
 
 impl TexturePurpose {
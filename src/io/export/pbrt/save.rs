@@ -1,4 +1,6 @@
 use super::super::copy_utility;
+use crate::conversion::color_space::ColorSpace;
+use crate::conversion::color_space::convert_rgb_buffer;
 use crate::error::PbrtError;
 use crate::model::base::Matrix4x4;
 use crate::model::base::ParamSet;
@@ -23,11 +25,14 @@ use crate::model::scene::Properties;
 use crate::model::scene::ResourceComponent;
 use crate::model::scene::SamplerComponent;
 use crate::model::scene::SamplerProperties;
+use crate::model::scene::SceneComponent;
+use crate::model::scene::Shape;
 use crate::model::scene::ShapeComponent;
 use crate::model::scene::ShapeProperties;
 use crate::model::scene::TextureProperties;
 use crate::model::scene::TransformComponent;
 
+use std::borrow::Cow;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
@@ -38,6 +43,10 @@ use std::sync::RwLock;
 pub struct SavePbrtOptions {
     pub pretty_print: bool,
     pub copy_resources: bool,
+    pub shape_cache_dir: Option<String>, // Inline meshes are written here once per edition and included, for interactive renders
+    pub film_props: Option<ParamSet>,    // Written instead of the film of the scene
+    pub sampler_props: Option<ParamSet>, // Written instead of the sampler of the scene
+    pub integrator_props: Option<ParamSet>, // Written instead of the integrator of the scene
 }
 
 impl Default for SavePbrtOptions {
@@ -45,12 +54,20 @@ impl Default for SavePbrtOptions {
         Self {
             pretty_print: true,
             copy_resources: true,
+            shape_cache_dir: None,
+            film_props: None,
+            sampler_props: None,
+            integrator_props: None,
         }
     }
 }
 
+// Shapes whose data is written inline and can be large
+const CACHED_SHAPE_TYPES: [&str; 3] = ["trianglemesh", "bilinearmesh", "loopsubdiv"];
+
 struct PbrtSaver {
     options: SavePbrtOptions,
+    rgb_source: Option<ColorSpace>, // RGB values are converted from this space to sRGB, which pbrt-v3 reads
 }
 
 fn get_world_matrix(node: &Arc<RwLock<Node>>) -> Result<Matrix4x4, PbrtError> {
@@ -128,6 +145,7 @@ impl PbrtSaver {
     pub fn new(options: &SavePbrtOptions) -> Self {
         PbrtSaver {
             options: options.clone(),
+            rgb_source: None,
         }
     }
 
@@ -142,6 +160,7 @@ impl PbrtSaver {
     ) -> Result<(), PbrtError> {
         if let Some((kt, kn, value)) = props.entry(key_name) {
             if let Property::Floats(v) = value {
+                let v = self.convert_rgb_values(kt.as_str(), v);
                 let values = v
                     .iter()
                     .map(|v| v.to_string())
@@ -238,8 +257,12 @@ impl PbrtSaver {
             let film_component = camera_node
                 .get_component::<FilmComponent>()
                 .ok_or(PbrtError::error("Film is not found!"))?;
-            let film_type = film_component
-                .props
+            let film_props = self
+                .options
+                .film_props
+                .as_ref()
+                .unwrap_or(&film_component.props);
+            let film_type = film_props
                 .find_one_string("string type")
                 .ok_or(PbrtError::error("Film type is not found!"))?;
             //println!("Film type: {}", film_type);
@@ -255,7 +278,7 @@ impl PbrtSaver {
                         &entry.key_type,
                         &entry.key_name,
                         &entry.default_value,
-                        &film_component.props,
+                        film_props,
                         writer,
                     )?;
                 }
@@ -278,8 +301,12 @@ impl PbrtSaver {
         let sampler_component = node
             .get_component::<SamplerComponent>()
             .ok_or(PbrtError::error("Sampler is not found!"))?;
-        let sampler_type = sampler_component
-            .props
+        let sampler_props = self
+            .options
+            .sampler_props
+            .as_ref()
+            .unwrap_or(&sampler_component.props);
+        let sampler_type = sampler_props
             .find_one_string("string type")
             .ok_or(PbrtError::error("Sampler type is not found!"))?;
         let sampler_properties = SamplerProperties::get_instance();
@@ -294,7 +321,7 @@ impl PbrtSaver {
                     &entry.key_type,
                     &entry.key_name,
                     &entry.default_value,
-                    &sampler_component.props,
+                    sampler_props,
                     writer,
                 )?;
             }
@@ -312,8 +339,12 @@ impl PbrtSaver {
         let integrator_component = node
             .get_component::<IntegratorComponent>()
             .ok_or(PbrtError::error("Integrator is not found!"))?;
-        let integrator_type = integrator_component
-            .props
+        let integrator_props = self
+            .options
+            .integrator_props
+            .as_ref()
+            .unwrap_or(&integrator_component.props);
+        let integrator_type = integrator_props
             .find_one_string("string type")
             .ok_or(PbrtError::error("Integrator type is not found!"))?;
         let integrator_properties = IntegratorProperties::get_instance();
@@ -328,7 +359,7 @@ impl PbrtSaver {
                     &entry.key_type,
                     &entry.key_name,
                     &entry.default_value,
-                    &integrator_component.props,
+                    integrator_props,
                     writer,
                 )?;
             }
@@ -337,11 +368,34 @@ impl PbrtSaver {
         Ok(())
     }
 
+    fn convert_rgb_values<'a>(&self, key_type: &str, values: &'a [f32]) -> Cow<'a, [f32]> {
+        if let Some(src) = self.rgb_source {
+            if (key_type == "rgb" || key_type == "color") && values.len() % 3 == 0 {
+                let mut values = values.to_vec();
+                convert_rgb_buffer(&mut values, src, ColorSpace::Srgb);
+                return Cow::Owned(values);
+            }
+        }
+        Cow::Borrowed(values)
+    }
+
+    fn set_rgb_source(&mut self, node: &Arc<RwLock<Node>>) {
+        self.rgb_source = None;
+        let node = node.read().unwrap();
+        if let Some(scene) = node.get_component::<SceneComponent>() {
+            let color_space = scene.get_color_space();
+            if color_space != ColorSpace::Srgb {
+                self.rgb_source = Some(color_space);
+            }
+        }
+    }
+
     fn write_options_block(
         &mut self,
         node: &Arc<RwLock<Node>>,
         writer: &mut dyn Write,
     ) -> Result<(), PbrtError> {
+        self.set_rgb_source(node);
         // Accelerator
         self.write_camera_options(node, writer)?;
         if self.options.pretty_print {
@@ -538,7 +592,6 @@ impl PbrtSaver {
                 .as_bytes(),
             )?;
         }
-        let shape_properties = ShapeProperties::get_instance();
         let light_properties = LightProperties::get_instance();
         if let Some(component) = node.get_component::<ShapeComponent>() {
             if let Some(light_component) = node.get_component::<AreaLightComponent>() {
@@ -568,23 +621,15 @@ impl PbrtSaver {
             }
             let shape = component.get_shape();
             let shape = shape.read().unwrap();
-            let t = shape.get_type(); //
-            if let Some(entries) = shape_properties.get_entries(&t) {
-                writer.write(format!("{}Shape \"{}\"", make_indent(indent), t).as_bytes())?;
-                for entry in entries.iter() {
-                    if !entry.output_to_file {
-                        continue;
-                    }
-                    self.write_property(
-                        indent,
-                        &entry.key_type,
-                        &entry.key_name,
-                        &entry.default_value,
-                        &shape.props,
-                        writer,
+            match self.get_cached_shape_path(&shape)? {
+                Some(path) => {
+                    writer.write(
+                        format!("{}Include \"{}\"\n", make_indent(indent), path).as_bytes(),
                     )?;
                 }
-                writer.write("\n".as_bytes())?;
+                None => {
+                    self.write_shape(indent, &shape, writer)?;
+                }
             }
         } else if let Some(light_component) = node.get_component::<LightComponent>() {
             let light = light_component.get_light();
@@ -612,6 +657,70 @@ impl PbrtSaver {
         Ok(())
     }
 
+    fn write_shape(
+        &self,
+        indent: usize,
+        shape: &Shape,
+        writer: &mut dyn Write,
+    ) -> Result<(), PbrtError> {
+        let shape_properties = ShapeProperties::get_instance();
+        let t = shape.get_type(); //
+        if let Some(entries) = shape_properties.get_entries(&t) {
+            writer.write(format!("{}Shape \"{}\"", make_indent(indent), t).as_bytes())?;
+            for entry in entries.iter() {
+                if !entry.output_to_file {
+                    continue;
+                }
+                self.write_property(
+                    indent,
+                    &entry.key_type,
+                    &entry.key_name,
+                    &entry.default_value,
+                    &shape.props,
+                    writer,
+                )?;
+            }
+            writer.write("\n".as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// File holding the shape in the shape cache, written when its edition is not there yet.
+    fn get_cached_shape_path(&self, shape: &Shape) -> Result<Option<String>, PbrtError> {
+        let Some(cache_dir) = self.options.shape_cache_dir.as_ref() else {
+            return Ok(None);
+        };
+        let edition = shape.get_edition();
+        if edition.is_empty() || !CACHED_SHAPE_TYPES.contains(&shape.get_type().as_str()) {
+            return Ok(None);
+        }
+        let prefix = format!("{}_", shape.get_id());
+        let path = Path::new(cache_dir).join(format!("{}{}.pbrt", prefix, edition));
+        if !path.exists() {
+            std::fs::create_dir_all(cache_dir)?;
+            // Earlier editions of the shape are not included anymore
+            for entry in std::fs::read_dir(cache_dir)?.flatten() {
+                if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                    let _ = std::fs::remove_file(entry.path());
+                }
+            }
+            // Renamed when complete so that an interrupted write is not reused
+            let temp_path = path.with_extension("tmp");
+            {
+                let file = File::create(&temp_path)?;
+                let mut writer = std::io::BufWriter::new(file);
+                self.write_shape(0, shape, &mut writer)?;
+                writer.flush()?;
+            }
+            std::fs::rename(&temp_path, &path)?;
+        }
+        let path = path
+            .to_str()
+            .ok_or(PbrtError::error("Invalid path!"))?
+            .to_string();
+        return Ok(Some(path));
+    }
+
     fn write_node(
         &self,
         indent: usize,
@@ -801,3 +910,130 @@ pub fn save_pbrt(
 
     return Ok(());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::import::pbrt::load_pbrt;
+
+    const SCENE: &str = r#"ColorSpace "rec2020"
+LookAt 0 0 5  0 0 0  0 1 0
+Camera "perspective"
+WorldBegin
+"#;
+
+    fn save_to_string(node: &Arc<RwLock<Node>>) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("saved.pbrt");
+        let path = path.to_str().unwrap();
+        save_pbrt(node, path, &SavePbrtOptions::default()).unwrap();
+        return std::fs::read_to_string(path).unwrap();
+    }
+
+    #[test]
+    fn test_color_space_is_read_but_not_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scene.pbrt");
+        std::fs::write(&path, SCENE).unwrap();
+        let node = load_pbrt(path.to_str().unwrap()).unwrap();
+        let color_space = {
+            let node = node.read().unwrap();
+            node.get_component::<SceneComponent>()
+                .unwrap()
+                .get_color_space()
+        };
+        assert_eq!(color_space, ColorSpace::Rec2020);
+
+        let saved = save_to_string(&node);
+        assert!(!saved.contains("ColorSpace"));
+        assert!(saved.contains("WorldEnd"));
+    }
+
+    fn find_floats(saved: &str, key: &str) -> Vec<f32> {
+        let start = saved.find(key).unwrap() + key.len();
+        let rest = &saved[start..];
+        let rest = &rest[rest.find('[').unwrap() + 1..rest.find(']').unwrap()];
+        return rest
+            .split_whitespace()
+            .map(|v| v.parse::<f32>().unwrap())
+            .collect();
+    }
+
+    #[test]
+    fn test_pbrt_v3_rgb_is_converted_to_srgb() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scene.pbrt");
+        let scene = format!(
+            "{}{}{}",
+            SCENE,
+            "MakeNamedMaterial \"red\" \"string type\" [\"matte\"] \"rgb Kd\" [0.5 0.1 0.1]\n",
+            "Texture \"tint\" \"color\" \"constant\" \"rgb value\" [0.2 0.5 0.8]\n"
+        );
+        std::fs::write(&path, scene).unwrap();
+        let node = load_pbrt(path.to_str().unwrap()).unwrap();
+
+        let saved = save_to_string(&node);
+        let expected = [
+            (
+                "Kd\"",
+                ColorSpace::Rec2020.convert_rgb(&[0.5, 0.1, 0.1], ColorSpace::Srgb),
+            ),
+            (
+                "value\"",
+                ColorSpace::Rec2020.convert_rgb(&[0.2, 0.5, 0.8], ColorSpace::Srgb),
+            ),
+        ];
+        for (key, rgb) in expected.iter() {
+            let values = find_floats(&saved, key);
+            assert_eq!(values.len(), 3);
+            for i in 0..3 {
+                assert!((values[i] - rgb[i]).abs() < 1e-4, "{}: {:?}", key, values);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shape_cache_keeps_the_current_edition() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scene.pbrt");
+        let scene = format!(
+            "{}Shape \"trianglemesh\" \"integer indices\" [0 1 2] \"point P\" [0 0 0 1 0 0 0 1 0]\n",
+            SCENE
+        );
+        std::fs::write(&path, scene).unwrap();
+        let node = load_pbrt(path.to_str().unwrap()).unwrap();
+        let cache_dir = dir.path().join("shapes");
+        let mut options = SavePbrtOptions::default();
+        options.shape_cache_dir = Some(cache_dir.to_str().unwrap().to_string());
+        let saved_path = dir.path().join("saved.pbrt");
+        let saved_path = saved_path.to_str().unwrap();
+        save_pbrt(&node, saved_path, &options).unwrap();
+        assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 1);
+
+        // an edited mesh replaces its earlier file
+        let shape_node = Node::find_node_by_component::<ShapeComponent>(&node).unwrap();
+        let shape = shape_node
+            .read()
+            .unwrap()
+            .get_component::<ShapeComponent>()
+            .unwrap()
+            .get_shape();
+        shape
+            .write()
+            .unwrap()
+            .as_property_map_mut()
+            .add_string("string edition", "edited");
+        save_pbrt(&node, saved_path, &options).unwrap();
+        let names = std::fs::read_dir(&cache_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names.len(), 1);
+        assert!(names[0].ends_with("_edited.pbrt"));
+
+        // without the cache the mesh is written inline
+        let saved = save_to_string(&node);
+        assert!(saved.contains("trianglemesh"));
+        assert!(!saved.contains("Include"));
+    }
+}
@@ -145,10 +145,13 @@ pub fn parse_params(s: &str) -> IResult<&str, ParamSet> {
             }
             "blackbody" => {
                 let s_values = vv.1;
-                let values: Vec<Float> = s_values
+                let mut values: Vec<Float> = s_values
                     .iter()
                     .map(|s| s.parse::<Float>().unwrap())
                     .collect();
+                if values.len() == 1 {
+                    values.push(1.0); // pbrt-v4 gives the temperature only
+                }
                 params.add_blackbody(&new_key, &values);
             }
             "point" | "point2" | "point3" | "point4" => {
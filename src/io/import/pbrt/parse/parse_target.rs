@@ -61,5 +61,6 @@ pub trait ParseTarget {
     fn work_dir_begin(&mut self, _path: &str) {}
     fn work_dir_end(&mut self) {}
     fn include(&mut self, _filename: &str, _params: &ParamSet) {}
+    fn color_space(&mut self, _name: &str) {}
     //----------------------------------------
 }
@@ -386,6 +386,12 @@ fn evaluate_opnodes(ops: &[OPNode], context: &mut dyn ParseTarget) -> Result<(),
                 let params = op.params.as_ref().unwrap();
                 context.include(filename, params);
             }
+            "ColorSpace" => {
+                let args = op.args.as_ref().unwrap();
+                let vec = args.get_strings("arg1");
+                let name: &str = vec.first().unwrap();
+                context.color_space(name);
+            }
             _ => {
                 let msg = format!("Unexpected token: {}", opname);
                 return Err(PbrtError::error(&msg));
@@ -468,7 +474,7 @@ fn parse_operation(s: &str) -> IResult<&str, OPNode> {
             parse_world_end,
             parse_include,
         )),
-        nom::branch::alt((parse_work_dir_begin, parse_work_dir_end)),
+        nom::branch::alt((parse_work_dir_begin, parse_work_dir_end, parse_color_space)),
     ))(s);
 }
 
@@ -742,6 +748,10 @@ fn parse_include(s: &str) -> IResult<&str, OPNode> {
     return parse_op_string_params(s, "Include");
 }
 
+fn parse_color_space(s: &str) -> IResult<&str, OPNode> {
+    return parse_op_string(s, "ColorSpace");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -852,6 +862,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_color_space() {
+        let nodes = parse_opnodes("ColorSpace \"aces2065-1\"\nWorldBegin").unwrap();
+        assert_eq!(nodes[0].name, "ColorSpace");
+        let args = nodes[0].args.as_ref().unwrap();
+        assert_eq!(args.get_strings("arg1"), vec!["aces2065-1".to_string()]);
+        assert_eq!(nodes[1].name, "WorldBegin");
+    }
+
     #[test]
     fn test_parse_ops_004() {
         let s = "\n
@@ -270,5 +270,10 @@ impl ParseTarget for MultipleTarget {
             target.write().unwrap().include(filename, params);
         }
     }
+    fn color_space(&mut self, name: &str) {
+        for target in &self.targets {
+            target.write().unwrap().color_space(name);
+        }
+    }
     //----------------------------------------
 }
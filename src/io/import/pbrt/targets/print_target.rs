@@ -517,4 +517,8 @@ impl ParseTarget for PrintTarget {
             self.get_indent()
         ));
     }
+
+    fn color_space(&mut self, name: &str) {
+        self.print(&format!("{}ColorSpace \"{name}\"\n", self.get_indent()));
+    }
 }
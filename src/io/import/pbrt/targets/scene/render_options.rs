@@ -16,6 +16,7 @@ pub struct RenderOptions {
     pub integrator_params: PropertyMap,
     pub camera_name: String,
     pub camera_params: PropertyMap,
+    pub color_space: Option<String>, // Name given to ColorSpace
 }
 
 impl Default for RenderOptions {
@@ -35,6 +36,7 @@ impl Default for RenderOptions {
             integrator_params: PropertyMap::new(),
             camera_name: "perspective".to_string(),
             camera_params: PropertyMap::new(),
+            color_space: None,
         }
     }
 }
@@ -4,6 +4,7 @@ use super::render_options::RenderOptions;
 use super::transform::Transform;
 use super::transform::TransformBit;
 use super::transform::TransformSet;
+use crate::conversion::color_space::ColorSpace;
 use crate::model::base::Matrix4x4;
 use crate::model::base::ParamSet;
 use crate::model::base::Property;
@@ -864,6 +865,10 @@ impl ParseTarget for SceneTarget {
     fn include(&mut self, _filename: &str, _params: &ParamSet) {
         //
     }
+
+    fn color_space(&mut self, name: &str) {
+        self.render_options.color_space = Some(name.to_string());
+    }
     //----------------------------------------
 }
 
@@ -885,7 +890,13 @@ impl SceneTarget {
         let root_node = self.nodes[0].clone();
         {
             let props = ParamSet::new();
-            let scene = scene::SceneComponent::new(&props);
+            let mut scene = scene::SceneComponent::new(&props);
+            if let Some(name) = self.render_options.color_space.as_ref() {
+                match ColorSpace::from_name(name) {
+                    Some(color_space) => scene.set_color_space(color_space),
+                    None => log::warn!("Unknown color space: {}", name),
+                }
+            }
             let mut root_node = root_node.write().unwrap();
             root_node.add_component(scene);
         }
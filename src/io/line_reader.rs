@@ -0,0 +1,57 @@
+use std::io::Read;
+use std::thread;
+
+/// Reads a pipe of a process on a thread, splitting lines at '\n' and '\r' so progress bars
+/// redrawn in place come as lines too.
+pub fn spawn_line_reader<R, F>(mut reader: R, mut on_line: F) -> thread::JoinHandle<()>
+where
+    R: Read + Send + 'static,
+    F: FnMut(&str) + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut buffer = [0u8; 4096];
+        let mut pending = Vec::new();
+        loop {
+            let n = match reader.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            for &b in &buffer[..n] {
+                if b == b'\n' || b == b'\r' {
+                    on_line(&String::from_utf8_lossy(&pending));
+                    pending.clear();
+                } else {
+                    pending.push(b);
+                }
+            }
+        }
+        if !pending.is_empty() {
+            on_line(&String::from_utf8_lossy(&pending));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_spawn_line_reader() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let reader = std::io::Cursor::new(b"first\nRendering: [+ ]\rRendering: [++]".to_vec());
+        let handle = {
+            let lines = lines.clone();
+            spawn_line_reader(reader, move |line| {
+                lines.lock().unwrap().push(line.to_string());
+            })
+        };
+        handle.join().unwrap();
+        assert_eq!(
+            *lines.lock().unwrap(),
+            vec!["first", "Rendering: [+ ]", "Rendering: [++]"]
+        );
+    }
+}
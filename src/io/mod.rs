@@ -1,2 +1,4 @@
 pub mod export;
 pub mod import;
+pub mod line_reader;
+pub mod remote;
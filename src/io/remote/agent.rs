@@ -0,0 +1,379 @@
+use super::package::unpack_package;
+use super::protocol::*;
+use crate::error::PbrtError;
+use crate::io::line_reader::spawn_line_reader;
+
+use std::io::Read;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// Until pbrt runs, a client that stops sending is dropped after this
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+// Connections that have not sent a job with the token yet
+const MAX_PENDING_CONNECTIONS: usize = 16;
+
+type SharedWriter = Arc<Mutex<TcpStream>>;
+
+fn send(writer: &SharedWriter, message: &AgentMessage) -> Result<(), PbrtError> {
+    let mut writer = writer.lock().unwrap();
+    return write_message(&mut *writer, message);
+}
+
+/// Sends the output of pbrt line by line, split like the local log.
+fn spawn_line_sender<R: Read + Send + 'static>(
+    reader: R,
+    writer: SharedWriter,
+) -> thread::JoinHandle<()> {
+    return spawn_line_reader(reader, move |line| {
+        let _ = send(&writer, &AgentMessage::Log(line.to_string()));
+    });
+}
+
+/// Accepts the display server connection of pbrt and forwards what it sends.
+///
+/// The port is on 127.0.0.1 and the first connection is taken to be pbrt's, so another
+/// process on the agent machine could connect first. It could then only feed tiles to the
+/// client of this job, it receives nothing back; the agent assumes its local users are trusted.
+fn spawn_display_relay(
+    listener: TcpListener,
+    writer: SharedWriter,
+    finished: Arc<AtomicBool>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let _ = listener.set_nonblocking(true);
+        let mut stream = loop {
+            match listener.accept() {
+                Ok((stream, _)) => break stream,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    if finished.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+                Err(_) => return,
+            }
+        };
+        let _ = stream.set_nonblocking(false);
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let n = match stream.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            if send(&writer, &AgentMessage::Display(buffer[..n].to_vec())).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Ends a job before it starts; the client waits for this answer and does not send the package.
+fn refuse_job(stream: &mut TcpStream, message: &str) -> Result<(), PbrtError> {
+    let result = JobResult {
+        success: false,
+        message: message.to_string(),
+    };
+    write_message(stream, &AgentMessage::Done(result))?;
+    return Err(PbrtError::error(message));
+}
+
+/// A file of the job, which must stay in its directory.
+fn get_job_path(job_dir: &Path, name: &str) -> Result<PathBuf, PbrtError> {
+    let path = Path::new(name);
+    let is_inside = path
+        .components()
+        .all(|c| matches!(c, std::path::Component::Normal(_)));
+    if name.is_empty() || !is_inside {
+        return Err(PbrtError::error(&format!("Invalid path in job: {}", name)));
+    }
+    return Ok(job_dir.join(path));
+}
+
+/// The image pbrt wrote, which must be a file in the job directory and not a link out of it.
+fn check_output_path(job_dir: &Path, output_path: &Path) -> Result<(), PbrtError> {
+    let metadata = std::fs::symlink_metadata(output_path)?;
+    let job_dir = job_dir.canonicalize()?;
+    let is_inside = output_path.canonicalize()?.starts_with(&job_dir);
+    if !metadata.is_file() || !is_inside {
+        return Err(PbrtError::error(&format!(
+            "Invalid output in job: {}",
+            output_path.display()
+        )));
+    }
+    return Ok(());
+}
+
+/// Compares every byte so the time taken does not tell how much of the token matched.
+fn is_same_token(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    return a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0;
+}
+
+/// A place among a limited number of running jobs or connections, given back when dropped.
+struct Slot {
+    running: Arc<AtomicUsize>,
+}
+
+impl Slot {
+    fn take(running: &Arc<AtomicUsize>, max_jobs: usize) -> Option<Self> {
+        running
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                if n < max_jobs { Some(n + 1) } else { None }
+            })
+            .ok()?;
+        return Some(Self {
+            running: running.clone(),
+        });
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.running.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Renders scenes sent by the UI with a local pbrt.
+#[derive(Debug, Clone)]
+pub struct RenderAgent {
+    pbrt_path: PathBuf,
+    work_dir: PathBuf, // Jobs are unpacked here and removed when done
+    token: String,     // Jobs must carry it, unless it is empty
+    max_jobs: usize,   // Jobs beyond it are refused while the others run
+    running: Arc<AtomicUsize>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl RenderAgent {
+    pub fn new(pbrt_path: &Path, work_dir: &Path, token: &str, max_jobs: usize) -> Self {
+        Self {
+            pbrt_path: pbrt_path.to_path_buf(),
+            work_dir: work_dir.to_path_buf(),
+            token: token.to_string(),
+            max_jobs,
+            running: Arc::new(AtomicUsize::new(0)),
+            pending: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Handles each connection as one job on its own thread, until the listener fails.
+    /// Connections beyond the ones waiting for their job are closed at once.
+    pub fn serve(&self, listener: TcpListener) -> Result<(), PbrtError> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let Some(pending) = Slot::take(&self.pending, MAX_PENDING_CONNECTIONS) else {
+                log::warn!("Too many connections waiting, closing one");
+                continue;
+            };
+            let agent = self.clone();
+            thread::spawn(move || {
+                let peer = stream
+                    .peer_addr()
+                    .map(|addr| addr.to_string())
+                    .unwrap_or_default();
+                log::info!("Job from {}", peer);
+                if let Err(e) = agent.run_job(stream, pending) {
+                    log::error!("Job from {} failed: {}", peer, e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn run_job(&self, mut stream: TcpStream, pending: Slot) -> Result<(), PbrtError> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let request = match read_message(&mut stream)? {
+            AgentMessage::Job(request) => request,
+            _ => return Err(PbrtError::error("Expected a job")),
+        };
+        // Checked before the package is read, a refused job costs nothing
+        if !is_same_token(&request.token, &self.token) {
+            return refuse_job(&mut stream, "Invalid token");
+        }
+        drop(pending);
+        let Some(_slot) = Slot::take(&self.running, self.max_jobs) else {
+            return refuse_job(&mut stream, "The agent is busy");
+        };
+        write_message(&mut stream, &AgentMessage::Accepted)?;
+        let length = match read_message(&mut stream)? {
+            AgentMessage::Package(length) => length,
+            _ => return Err(PbrtError::error("Expected a package")),
+        };
+        let job_id = Uuid::new_v4().to_string();
+        let job_dir = self.work_dir.join(&job_id);
+        // The package goes to disk as it arrives and is removed once unpacked
+        let package_path = self.work_dir.join(format!("{}.tar.gz", job_id));
+        std::fs::create_dir_all(&self.work_dir)?;
+        let result = read_file(&mut stream, length, &package_path)
+            .and_then(|_| unpack_package(&package_path, &job_dir, MAX_FILE_LENGTH));
+        let _ = std::fs::remove_file(&package_path);
+        if let Err(e) = result {
+            let _ = std::fs::remove_dir_all(&job_dir);
+            return Err(e);
+        }
+        let writer: SharedWriter = Arc::new(Mutex::new(stream.try_clone()?));
+        let result = self.render(&request, &job_dir, &stream, &writer);
+        if let Err(e) = std::fs::remove_dir_all(&job_dir) {
+            log::warn!("Failed to remove {}: {}", job_dir.display(), e);
+        }
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => JobResult {
+                success: false,
+                message: e.to_string(),
+            },
+        };
+        return send(&writer, &AgentMessage::Done(result));
+    }
+
+    fn render(
+        &self,
+        request: &JobRequest,
+        job_dir: &Path,
+        stream: &TcpStream,
+        writer: &SharedWriter,
+    ) -> Result<JobResult, PbrtError> {
+        let scene_path = get_job_path(job_dir, &request.scene)?;
+        let output_path = get_job_path(job_dir, &request.output)?;
+        let mut command = Command::new(&self.pbrt_path);
+        command
+            .arg(&scene_path)
+            .arg("--outfile")
+            .arg(&output_path)
+            .current_dir(job_dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut display_listener = None;
+        if request.display {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let port = listener.local_addr()?.port();
+            command
+                .arg("--display-server")
+                .arg(format!("127.0.0.1:{}", port));
+            display_listener = Some(listener);
+        }
+        let mut child = command.spawn()?;
+        // Nothing is accepted before pbrt is started
+        let finished = Arc::new(AtomicBool::new(false));
+        let relay = display_listener
+            .map(|listener| spawn_display_relay(listener, writer.clone(), finished.clone()));
+        // Once pbrt runs the client may stay quiet until it cancels
+        stream.set_read_timeout(None)?;
+        let mut readers = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            readers.push(spawn_line_sender(stdout, writer.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(spawn_line_sender(stderr, writer.clone()));
+        }
+        // A cancel or a closed connection stops pbrt
+        let cancelled = Arc::new(AtomicBool::new(false));
+        {
+            let mut stream = stream.try_clone()?;
+            let cancelled = cancelled.clone();
+            thread::spawn(move || {
+                loop {
+                    match read_message(&mut stream) {
+                        Ok(AgentMessage::Cancel) | Err(_) => break,
+                        Ok(_) => {}
+                    }
+                }
+                cancelled.store(true, Ordering::SeqCst);
+            });
+        }
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if cancelled.load(Ordering::SeqCst) {
+                let _ = child.kill();
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+        // All of the output goes out before the image and the result
+        for reader in readers {
+            let _ = reader.join();
+        }
+        finished.store(true, Ordering::SeqCst);
+        if let Some(relay) = relay {
+            let _ = relay.join();
+        }
+        if cancelled.load(Ordering::SeqCst) {
+            return Ok(JobResult {
+                success: false,
+                message: "Cancelled".to_string(),
+            });
+        }
+        if output_path.exists() {
+            check_output_path(job_dir, &output_path)?;
+            let mut writer = writer.lock().unwrap();
+            write_file(&mut *writer, AgentMessage::Image, &output_path)?;
+        }
+        return Ok(JobResult {
+            success: status.success(),
+            message: format!("pbrt exited with {}", status),
+        });
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_output_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let job_dir = dir.path().join("job");
+        std::fs::create_dir_all(&job_dir).unwrap();
+        let secret = dir.path().join("secret.txt");
+        std::fs::write(&secret, "secret").unwrap();
+
+        let output_path = job_dir.join("scene.exr");
+        std::fs::write(&output_path, "EXR").unwrap();
+        assert!(check_output_path(&job_dir, &output_path).is_ok());
+
+        // A link to a file outside of the job is refused
+        let link_path = job_dir.join("link.exr");
+        std::os::unix::fs::symlink(&secret, &link_path).unwrap();
+        assert!(check_output_path(&job_dir, &link_path).is_err());
+        let link_path = job_dir.join("inside.exr");
+        std::os::unix::fs::symlink(&output_path, &link_path).unwrap();
+        assert!(check_output_path(&job_dir, &link_path).is_err());
+    }
+
+    #[test]
+    fn test_slot() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let slot = Slot::take(&running, 1);
+        assert!(slot.is_some());
+        assert!(Slot::take(&running, 1).is_none());
+        drop(slot);
+        assert!(Slot::take(&running, 1).is_some());
+        assert_eq!(running.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_is_same_token() {
+        assert!(is_same_token("secret", "secret"));
+        assert!(is_same_token("", ""));
+        assert!(!is_same_token("secret", "secreT"));
+        assert!(!is_same_token("secret", ""));
+    }
+}
@@ -0,0 +1,292 @@
+use super::package::pack_directory;
+use super::protocol::*;
+use crate::error::PbrtError;
+
+use std::net::Shutdown;
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+/// The connection as the job thread and the UI both see it.
+#[derive(Debug, Default)]
+struct Connection {
+    stream: Option<TcpStream>,
+    is_sent: bool, // The package is sent, so a `Cancel` can follow
+    is_cancelled: bool,
+}
+
+fn cancelled_result() -> JobResult {
+    return JobResult {
+        success: false,
+        message: "Cancelled".to_string(),
+    };
+}
+
+/// A job sent to a render agent; it is packed, sent and its messages are handled on a thread.
+pub struct RemoteJob {
+    connection: Arc<Mutex<Connection>>,
+    handle: Option<thread::JoinHandle<Result<JobResult, PbrtError>>>,
+}
+
+impl RemoteJob {
+    /// Packs `scene_dir` and sends it with the job, then passes each message to `on_message` until `Done`.
+    /// The image the agent sends back is written to `image_path`.
+    pub fn start<F>(
+        address: &str,
+        request: &JobRequest,
+        scene_dir: &Path,
+        image_path: &Path,
+        on_message: F,
+    ) -> Self
+    where
+        F: FnMut(AgentMessage) + Send + 'static,
+    {
+        let connection = Arc::new(Mutex::new(Connection::default()));
+        let handle = {
+            let connection = connection.clone();
+            let address = address.to_string();
+            let request = request.clone();
+            let scene_dir = scene_dir.to_path_buf();
+            let image_path = image_path.to_path_buf();
+            thread::spawn(move || {
+                return run_job(
+                    &connection,
+                    &address,
+                    &request,
+                    &scene_dir,
+                    &image_path,
+                    on_message,
+                );
+            })
+        };
+        return Self {
+            connection,
+            handle: Some(handle),
+        };
+    }
+
+    /// Asks the agent to stop pbrt; the job still ends with its result.
+    /// While the package is on its way the connection is closed instead.
+    pub fn cancel(&mut self) -> Result<(), PbrtError> {
+        let mut connection = self.connection.lock().unwrap();
+        connection.is_cancelled = true;
+        let is_sent = connection.is_sent;
+        if let Some(stream) = connection.stream.as_mut() {
+            if is_sent {
+                return write_message(stream, &AgentMessage::Cancel);
+            }
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        return Ok(());
+    }
+
+    /// The result once the agent is done, or the error that ended the connection.
+    pub fn try_result(&mut self) -> Option<Result<JobResult, PbrtError>> {
+        if !self.handle.as_ref()?.is_finished() {
+            return None;
+        }
+        let handle = self.handle.take()?;
+        return match handle.join() {
+            Ok(result) => Some(result),
+            Err(_) => Some(Err(PbrtError::error("Remote job thread panicked"))),
+        };
+    }
+}
+
+impl Drop for RemoteJob {
+    fn drop(&mut self) {
+        // The agent stops pbrt when the connection closes
+        let mut connection = self.connection.lock().unwrap();
+        connection.is_cancelled = true;
+        if let Some(stream) = connection.stream.as_ref() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+fn run_job<F>(
+    connection: &Mutex<Connection>,
+    address: &str,
+    request: &JobRequest,
+    scene_dir: &Path,
+    image_path: &Path,
+    mut on_message: F,
+) -> Result<JobResult, PbrtError>
+where
+    F: FnMut(AgentMessage),
+{
+    let package_path = scene_dir.with_extension("tar.gz");
+    pack_directory(scene_dir, &package_path)?;
+    let sent = send_job(connection, address, request, &package_path);
+    let _ = std::fs::remove_file(&package_path);
+    let mut reader = match sent {
+        Ok(SentJob::Accepted(reader)) => reader,
+        Ok(SentJob::Refused(result)) => return Ok(result),
+        Err(_) if connection.lock().unwrap().is_cancelled => return Ok(cancelled_result()),
+        Err(e) => return Err(e),
+    };
+    {
+        let mut connection = connection.lock().unwrap();
+        connection.is_sent = true;
+        if connection.is_cancelled {
+            if let Some(stream) = connection.stream.as_mut() {
+                write_message(stream, &AgentMessage::Cancel)?;
+            }
+        }
+    }
+    loop {
+        match read_message(&mut reader)? {
+            AgentMessage::Done(result) => return Ok(result),
+            AgentMessage::Image(length) => read_file(&mut reader, length, image_path)?,
+            message => on_message(message),
+        }
+    }
+}
+
+/// How the agent answered the job.
+enum SentJob {
+    Accepted(TcpStream), // The package is sent, the answers are read from the stream
+    Refused(JobResult),
+}
+
+/// Connects and sends the job, then its package once the agent accepts it.
+fn send_job(
+    connection: &Mutex<Connection>,
+    address: &str,
+    request: &JobRequest,
+    package_path: &Path,
+) -> Result<SentJob, PbrtError> {
+    let mut stream = TcpStream::connect(address)?;
+    {
+        let mut connection = connection.lock().unwrap();
+        if connection.is_cancelled {
+            return Err(PbrtError::error("Cancelled"));
+        }
+        connection.stream = Some(stream.try_clone()?);
+    }
+    write_message(&mut stream, &AgentMessage::Job(request.clone()))?;
+    // A refused job is answered before the package, which is then not sent at all
+    match read_message(&mut stream)? {
+        AgentMessage::Accepted => {}
+        AgentMessage::Done(result) => return Ok(SentJob::Refused(result)),
+        _ => return Err(PbrtError::error("Expected the agent to accept the job")),
+    }
+    write_file(&mut stream, AgentMessage::Package, package_path)?;
+    return Ok(SentJob::Accepted(stream));
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::super::agent::RenderAgent;
+    use super::*;
+
+    use std::net::TcpListener;
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    // Stands in for pbrt: prints a line and writes the file after --outfile
+    const FAKE_PBRT: &str = r#"#!/bin/sh
+echo "Rendering: [++++] (0.1s)"
+while [ -n "$1" ]; do
+    if [ "$1" = "--outfile" ]; then
+        shift
+        printf EXR > "$1"
+    fi
+    shift
+done
+"#;
+
+    fn wait_result(job: &mut RemoteJob) -> Result<JobResult, PbrtError> {
+        let start = Instant::now();
+        loop {
+            if let Some(result) = job.try_result() {
+                return result;
+            }
+            assert!(start.elapsed() < Duration::from_secs(10), "Job timed out");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_render_on_local_agent() {
+        let agent_dir = tempfile::tempdir().unwrap();
+        let pbrt_path = agent_dir.path().join("pbrt");
+        std::fs::write(&pbrt_path, FAKE_PBRT).unwrap();
+        std::fs::set_permissions(&pbrt_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let work_dir = agent_dir.path().join("jobs");
+        let agent = RenderAgent::new(&pbrt_path, &work_dir, "secret", 1);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || agent.serve(listener));
+
+        let scene_dir = tempfile::tempdir().unwrap();
+        std::fs::write(scene_dir.path().join("scene.pbrt"), "WorldBegin\n").unwrap();
+        let out_dir = tempfile::tempdir().unwrap();
+        let image_path = out_dir.path().join("scene.exr");
+        let request = JobRequest {
+            token: "secret".to_string(),
+            scene: "scene.pbrt".to_string(),
+            display: false,
+            output: "scene.exr".to_string(),
+        };
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let mut job = {
+            let messages = messages.clone();
+            RemoteJob::start(
+                &address,
+                &request,
+                scene_dir.path(),
+                &image_path,
+                move |message| {
+                    messages.lock().unwrap().push(message);
+                },
+            )
+        };
+        let result = wait_result(&mut job).unwrap();
+        assert!(result.success, "{}", result.message);
+        let messages = messages.lock().unwrap();
+        assert!(messages.contains(&AgentMessage::Log("Rendering: [++++] (0.1s)".to_string())));
+        assert_eq!(std::fs::read(&image_path).unwrap(), b"EXR");
+        // The job directory and the packages on both sides are removed once the image is sent
+        assert_eq!(std::fs::read_dir(&work_dir).unwrap().count(), 0);
+        assert!(!scene_dir.path().with_extension("tar.gz").exists());
+
+        // Paths outside of the package are refused
+        let request = JobRequest {
+            scene: "../scene.pbrt".to_string(),
+            ..request
+        };
+        let mut job = RemoteJob::start(&address, &request, scene_dir.path(), &image_path, |_| {});
+        assert!(!wait_result(&mut job).unwrap().success);
+
+        // So are jobs without the token, before the package is sent
+        let request = JobRequest {
+            token: "guess".to_string(),
+            scene: "scene.pbrt".to_string(),
+            ..request
+        };
+        let mut job = RemoteJob::start(&address, &request, scene_dir.path(), &image_path, |_| {});
+        let result = wait_result(&mut job).unwrap();
+        assert!(!result.success);
+        assert_eq!(result.message, "Invalid token");
+
+        // An agent without room refuses the job the same way
+        let agent = RenderAgent::new(&pbrt_path, &work_dir, "secret", 0);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || agent.serve(listener));
+        let request = JobRequest {
+            token: "secret".to_string(),
+            ..request
+        };
+        let mut job = RemoteJob::start(&address, &request, scene_dir.path(), &image_path, |_| {});
+        let result = wait_result(&mut job).unwrap();
+        assert!(!result.success);
+        assert_eq!(result.message, "The agent is busy");
+    }
+}
@@ -0,0 +1,9 @@
+pub mod agent;
+pub mod client;
+pub mod package;
+pub mod protocol;
+
+pub use agent::RenderAgent;
+pub use client::RemoteJob;
+pub use package::*;
+pub use protocol::*;
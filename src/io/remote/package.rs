@@ -0,0 +1,109 @@
+use crate::error::PbrtError;
+
+use std::io::Write;
+use std::path::Path;
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+/// Packs the files under a directory, an exported scene and its resources, into a gzipped tar at `path`.
+pub fn pack_directory(dir: &Path, path: &Path) -> Result<(), PbrtError> {
+    let file = std::fs::File::create(path)?;
+    let encoder = GzEncoder::new(std::io::BufWriter::new(file), Compression::fast());
+    let mut builder = tar::Builder::new(encoder);
+    builder.append_dir_all(".", dir)?;
+    let encoder = builder.into_inner()?;
+    let mut writer = encoder.finish()?;
+    writer.flush()?;
+    return Ok(());
+}
+
+/// Unpacks the package at `path` into a directory; entries reaching outside of it are skipped.
+/// Only files and directories are accepted, links could point the job at any file of the agent.
+/// The files may add up to `max_length` bytes, a small package can unpack to far more.
+pub fn unpack_package(path: &Path, dir: &Path, max_length: u64) -> Result<(), PbrtError> {
+    std::fs::create_dir_all(dir)?;
+    let file = std::fs::File::open(path)?;
+    let decoder = GzDecoder::new(std::io::BufReader::new(file));
+    let mut archive = tar::Archive::new(decoder);
+    let mut total_length: u64 = 0;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+        if !entry_type.is_file() && !entry_type.is_dir() {
+            return Err(PbrtError::error(&format!(
+                "Unsupported entry in package: {}",
+                entry.path()?.display()
+            )));
+        }
+        total_length = total_length.saturating_add(entry.size());
+        if total_length > max_length {
+            return Err(PbrtError::error(&format!(
+                "Package unpacks to more than {} bytes",
+                max_length
+            )));
+        }
+        entry.unpack_in(dir)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_round_trip() {
+        let src = tempfile::tempdir().unwrap();
+        std::fs::write(src.path().join("scene.pbrt"), "WorldBegin\n").unwrap();
+        std::fs::create_dir_all(src.path().join("textures")).unwrap();
+        std::fs::write(src.path().join("textures/wood.png"), [1u8, 2, 3]).unwrap();
+
+        let dst = tempfile::tempdir().unwrap();
+        let package = dst.path().join("scene.tar.gz");
+        pack_directory(src.path(), &package).unwrap();
+        unpack_package(&package, dst.path(), 1024).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dst.path().join("scene.pbrt")).unwrap(),
+            "WorldBegin\n"
+        );
+        assert_eq!(
+            std::fs::read(dst.path().join("textures/wood.png")).unwrap(),
+            [1u8, 2, 3]
+        );
+    }
+    #[test]
+    fn test_unpack_rejects_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let package = dir.path().join("scene.tar.gz");
+        {
+            let file = std::fs::File::create(&package).unwrap();
+            let encoder = GzEncoder::new(file, Compression::fast());
+            let mut builder = tar::Builder::new(encoder);
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            builder
+                .append_link(&mut header, "scene.exr", "/etc/passwd")
+                .unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        let dst = dir.path().join("job");
+        assert!(unpack_package(&package, &dst, 1024).is_err());
+        assert!(std::fs::symlink_metadata(dst.join("scene.exr")).is_err());
+    }
+
+    #[test]
+    fn test_unpack_limits_the_size() {
+        // Zeros compress to almost nothing, the limit is on what they unpack to
+        let src = tempfile::tempdir().unwrap();
+        std::fs::write(src.path().join("a.bin"), vec![0u8; 600]).unwrap();
+        std::fs::write(src.path().join("b.bin"), vec![0u8; 600]).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let package = dir.path().join("scene.tar.gz");
+        pack_directory(src.path(), &package).unwrap();
+        assert!(unpack_package(&package, &dir.path().join("small"), 1000).is_err());
+        assert!(unpack_package(&package, &dir.path().join("large"), 1200).is_ok());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::error::PbrtError;
+
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_AGENT_PORT: u16 = 24160;
+
+const MAX_MESSAGE_LENGTH: u64 = 64 << 20; // Messages held in memory, all but the files
+pub const MAX_FILE_LENGTH: u64 = 1 << 34; // Packages and images larger than 16GB are refused
+
+/// What the agent is asked to render, sent before the package.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobRequest {
+    #[serde(default)]
+    pub token: String, // Shared with the agent, which refuses jobs without it
+    pub scene: String,  // Scene file in the package
+    pub display: bool,  // Forward the display server tiles
+    pub output: String, // Image pbrt writes, in the package directory
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobResult {
+    pub success: bool,
+    pub message: String,
+}
+
+/// Messages between the UI and a render agent.
+///
+/// The UI sends `Job` and waits for `Accepted`, or for `Done` if the agent refuses it.
+/// It then sends `Package`, and `Cancel` if it gives up.
+/// The agent answers with `Log`, `Display` and `Image`, and ends the job with `Done`.
+/// `Package` and `Image` carry the length of a file that follows, see `write_file` and `read_file`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgentMessage {
+    Job(JobRequest),
+    Accepted,     // The token is valid and the agent has room, the package can follow
+    Package(u64), // Gzipped tar of the scene directory
+    Cancel,
+    Log(String),      // A line of pbrt output
+    Display(Vec<u8>), // Bytes pbrt sent to the display server
+    Image(u64),       // The output image file
+    Done(JobResult),
+}
+
+impl AgentMessage {
+    fn get_kind(&self) -> u8 {
+        match self {
+            AgentMessage::Job(_) => 1,
+            AgentMessage::Package(_) => 2,
+            AgentMessage::Cancel => 3,
+            AgentMessage::Log(_) => 4,
+            AgentMessage::Display(_) => 5,
+            AgentMessage::Image(_) => 6,
+            AgentMessage::Done(_) => 7,
+            AgentMessage::Accepted => 8,
+        }
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, PbrtError> {
+    return serde_json::to_vec(value).map_err(|e| PbrtError::error(&e.to_string()));
+}
+
+fn from_json<'a, T: Deserialize<'a>>(payload: &'a [u8]) -> Result<T, PbrtError> {
+    return serde_json::from_slice(payload).map_err(|e| PbrtError::error(&e.to_string()));
+}
+
+/// Writes a message as its kind, the length of the payload and the payload.
+/// For `Package` and `Image` only the kind and the length are written, the file has to follow.
+pub fn write_message(writer: &mut dyn Write, message: &AgentMessage) -> Result<(), PbrtError> {
+    let payload = match message {
+        AgentMessage::Job(request) => to_json(request)?,
+        AgentMessage::Package(length) | AgentMessage::Image(length) => {
+            writer.write_all(&[message.get_kind()])?;
+            writer.write_all(&length.to_le_bytes())?;
+            return Ok(());
+        }
+        AgentMessage::Display(data) => data.clone(),
+        AgentMessage::Cancel | AgentMessage::Accepted => Vec::new(),
+        AgentMessage::Log(line) => line.as_bytes().to_vec(),
+        AgentMessage::Done(result) => to_json(result)?,
+    };
+    writer.write_all(&[message.get_kind()])?;
+    writer.write_all(&(payload.len() as u64).to_le_bytes())?;
+    writer.write_all(&payload)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes a `Package` or an `Image` message with the file at `path`, streamed in chunks.
+pub fn write_file(
+    writer: &mut dyn Write,
+    message: fn(u64) -> AgentMessage,
+    path: &Path,
+) -> Result<(), PbrtError> {
+    let mut file = std::fs::File::open(path)?;
+    let length = file.metadata()?.len();
+    write_message(writer, &message(length))?;
+    let copied = std::io::copy(&mut file, writer)?;
+    if copied != length {
+        return Err(PbrtError::error(&format!(
+            "{} changed while it was sent",
+            path.display()
+        )));
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads the file following a `Package` or an `Image` message into `path`.
+pub fn read_file(reader: &mut dyn Read, length: u64, path: &Path) -> Result<(), PbrtError> {
+    let file = std::fs::File::create(path)?;
+    let mut writer = std::io::BufWriter::new(file);
+    let copied = std::io::copy(&mut reader.take(length), &mut writer)?;
+    writer.flush()?;
+    if copied != length {
+        return Err(PbrtError::error("Connection closed while receiving a file"));
+    }
+    Ok(())
+}
+
+/// Reads the next message, failing when the connection is closed.
+/// The file after a `Package` or an `Image` is left to `read_file`.
+pub fn read_message(reader: &mut dyn Read) -> Result<AgentMessage, PbrtError> {
+    let mut kind = [0u8; 1];
+    reader.read_exact(&mut kind)?;
+    let mut length = [0u8; 8];
+    reader.read_exact(&mut length)?;
+    let length = u64::from_le_bytes(length);
+    let is_file = kind[0] == 2 || kind[0] == 6;
+    let max_length = if is_file {
+        MAX_FILE_LENGTH
+    } else {
+        MAX_MESSAGE_LENGTH
+    };
+    if length > max_length {
+        return Err(PbrtError::error(&format!(
+            "Message of {} bytes is too large",
+            length
+        )));
+    }
+    if is_file {
+        return match kind[0] {
+            2 => Ok(AgentMessage::Package(length)),
+            _ => Ok(AgentMessage::Image(length)),
+        };
+    }
+    let mut payload = vec![0u8; length as usize];
+    reader.read_exact(&mut payload)?;
+    let message = match kind[0] {
+        1 => AgentMessage::Job(from_json(&payload)?),
+        3 => AgentMessage::Cancel,
+        4 => AgentMessage::Log(String::from_utf8_lossy(&payload).to_string()),
+        5 => AgentMessage::Display(payload),
+        7 => AgentMessage::Done(from_json(&payload)?),
+        8 => AgentMessage::Accepted,
+        k => {
+            return Err(PbrtError::error(&format!("Unknown message kind {}", k)));
+        }
+    };
+    return Ok(message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_round_trip() {
+        let messages = vec![
+            AgentMessage::Job(JobRequest {
+                token: "secret".to_string(),
+                scene: "room.pbrt".to_string(),
+                display: true,
+                output: "room.exr".to_string(),
+            }),
+            AgentMessage::Accepted,
+            AgentMessage::Cancel,
+            AgentMessage::Log("Rendering: [+++   ]".to_string()),
+            AgentMessage::Display(vec![]),
+            AgentMessage::Done(JobResult {
+                success: false,
+                message: "pbrt exited with 1".to_string(),
+            }),
+        ];
+        let mut buffer = Vec::new();
+        for message in messages.iter() {
+            write_message(&mut buffer, message).unwrap();
+        }
+        let mut reader = std::io::Cursor::new(buffer);
+        for message in messages.iter() {
+            assert_eq!(&read_message(&mut reader).unwrap(), message);
+        }
+        // The end of the stream is an error, not a message
+        assert!(read_message(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("scene.tar.gz");
+        std::fs::write(&src, [1u8, 2, 3]).unwrap();
+        let mut buffer = Vec::new();
+        write_file(&mut buffer, AgentMessage::Package, &src).unwrap();
+        write_message(&mut buffer, &AgentMessage::Cancel).unwrap();
+        let mut reader = std::io::Cursor::new(buffer);
+        let message = read_message(&mut reader).unwrap();
+        assert_eq!(message, AgentMessage::Package(3));
+        let dst = dir.path().join("received.tar.gz");
+        read_file(&mut reader, 3, &dst).unwrap();
+        assert_eq!(std::fs::read(&dst).unwrap(), [1u8, 2, 3]);
+        assert_eq!(read_message(&mut reader).unwrap(), AgentMessage::Cancel);
+
+        // A file cut short is an error
+        let mut reader = std::io::Cursor::new(vec![1u8, 2]);
+        assert!(read_file(&mut reader, 3, &dst).is_err());
+    }
+
+    #[test]
+    fn test_large_message() {
+        // Only files may be larger than the limit for messages held in memory
+        let mut buffer = vec![4u8];
+        buffer.extend_from_slice(&(MAX_MESSAGE_LENGTH + 1).to_le_bytes());
+        assert!(read_message(&mut std::io::Cursor::new(buffer)).is_err());
+        let mut buffer = vec![6u8];
+        buffer.extend_from_slice(&(MAX_MESSAGE_LENGTH + 1).to_le_bytes());
+        let message = read_message(&mut std::io::Cursor::new(buffer)).unwrap();
+        assert_eq!(message, AgentMessage::Image(MAX_MESSAGE_LENGTH + 1));
+    }
+
+    #[test]
+    fn test_unknown_message() {
+        let mut buffer = vec![99u8];
+        buffer.extend_from_slice(&0u64.to_le_bytes());
+        assert!(read_message(&mut std::io::Cursor::new(buffer)).is_err());
+    }
+}
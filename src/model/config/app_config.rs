@@ -1,4 +1,6 @@
 use crate::error::PbrtError;
+use crate::io::remote::DEFAULT_AGENT_PORT;
+use crate::render::ViewportSettings;
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
@@ -12,6 +14,24 @@ pub struct AppConfig {
     pub render_output_directory: String,
     pub import_file_directory: String,
     pub export_file_directory: String,
+    #[serde(default)]
+    pub viewport: ViewportSettings,
+    #[serde(default = "default_render_queue_workers")]
+    pub render_queue_workers: usize,
+    #[serde(default)]
+    pub enable_render_agent: bool, // Render on pbrt_render_agent instead of a local pbrt
+    #[serde(default = "default_render_agent_address")]
+    pub render_agent_address: String,
+    #[serde(default)]
+    pub render_agent_token: String, // Given to pbrt_render_agent with --token
+}
+
+fn default_render_queue_workers() -> usize {
+    1
+}
+
+fn default_render_agent_address() -> String {
+    format!("localhost:{}", DEFAULT_AGENT_PORT)
 }
 
 impl Default for AppConfig {
@@ -40,6 +60,11 @@ impl Default for AppConfig {
             render_output_directory: render_output_directory,
             import_file_directory: import_file_directory,
             export_file_directory: export_file_directory,
+            viewport: ViewportSettings::default(),
+            render_queue_workers: default_render_queue_workers(),
+            enable_render_agent: false,
+            render_agent_address: default_render_agent_address(),
+            render_agent_token: String::new(),
         }
     }
 }
@@ -54,3 +79,49 @@ impl AppConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::viewport_settings::ToneMapping;
+
+    #[test]
+    fn test_load_config_without_viewport() {
+        // configs written before the viewport options fall back to the defaults
+        let json = r#"{
+            "pbrt_executable_path": "pbrt",
+            "enable_display_server": false,
+            "display_server_host": "localhost",
+            "display_server_port": 24158,
+            "render_output_directory": "",
+            "import_file_directory": "",
+            "export_file_directory": ""
+        }"#;
+        let config: AppConfig = serde_json::from_str(json).unwrap();
+        // the lighting view keeps its plain linear to sRGB look until post-processing is turned on
+        assert!(!config.viewport.post_process.ssao.enabled);
+        assert_eq!(config.viewport.post_process.exposure, 0.0);
+        assert_eq!(
+            config.viewport.post_process.tone_mapping,
+            ToneMapping::LinearClamp
+        );
+        assert_eq!(config.render_queue_workers, 1);
+        assert!(!config.enable_render_agent);
+        assert_eq!(config.render_agent_address, "localhost:24160");
+        assert!(config.render_agent_token.is_empty());
+    }
+
+    #[test]
+    fn test_viewport_settings_round_trip() {
+        let mut config = AppConfig::default();
+        config.viewport.post_process.exposure = 1.5;
+        config.viewport.post_process.tone_mapping = ToneMapping::Reinhard;
+        let json = serde_json::to_string(&config).unwrap();
+        let config: AppConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.viewport.post_process.exposure, 1.5);
+        assert_eq!(
+            config.viewport.post_process.tone_mapping,
+            ToneMapping::Reinhard
+        );
+    }
+}
@@ -19,6 +19,13 @@ pub struct ResourceManager {
 }
 
 impl ResourceManager {
+    pub fn find_material_by_name(&self, name: &str) -> Option<Arc<RwLock<Material>>> {
+        self.materials
+            .values()
+            .find(|material| material.read().unwrap().get_name() == name)
+            .cloned()
+    }
+
     pub fn find_texture_by_name(&self, name: &str) -> Option<Arc<RwLock<Texture>>> {
         self.textures
             .values()
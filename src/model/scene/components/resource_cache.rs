@@ -1,4 +1,5 @@
 use super::component::Component;
+use crate::conversion::color_space::ColorSpace;
 use crate::conversion::texture_node::TextureNode;
 
 use std::collections::HashMap;
@@ -10,12 +11,25 @@ use uuid::Uuid;
 #[derive(Debug, Clone)]
 pub struct ResourceCacheManager {
     pub textures: HashMap<Uuid, Arc<RwLock<TextureNode>>>,
+    pub color_space: ColorSpace, // working color space the texture images were created in
 }
 
 impl ResourceCacheManager {
     pub fn new() -> Self {
         Self {
             textures: HashMap::new(),
+            color_space: ColorSpace::default(),
+        }
+    }
+
+    /// Sets the scene working color space. Cached texture images are invalidated when it changes.
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        if self.color_space == color_space {
+            return;
+        }
+        self.color_space = color_space;
+        for (_id, texture_node) in self.textures.iter() {
+            texture_node.write().unwrap().image_variants.clear();
         }
     }
 }
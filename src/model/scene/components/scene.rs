@@ -1,4 +1,5 @@
 use super::component::Component;
+use crate::conversion::color_space::ColorSpace;
 use crate::model::base::*;
 
 #[derive(Debug, Clone)]
@@ -8,7 +9,10 @@ pub struct SceneComponent {
 
 impl SceneComponent {
     pub fn new(props: &PropertyMap) -> Self {
-        let props = props.clone();
+        let mut props = props.clone();
+        if props.get("colorspace").is_none() {
+            props.add_string("string colorspace", ColorSpace::default().get_name());
+        }
         SceneComponent { props: props }
     }
 
@@ -19,6 +23,19 @@ impl SceneComponent {
     pub fn get_fullpath(&self) -> Option<String> {
         self.props.find_one_string("string fullpath")
     }
+
+    /// Working color space of the scene. RGB values in the scene are linear in this space.
+    pub fn get_color_space(&self) -> ColorSpace {
+        self.props
+            .find_one_string("string colorspace")
+            .and_then(|name| ColorSpace::from_name(&name))
+            .unwrap_or_default()
+    }
+
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.props
+            .add_string("string colorspace", color_space.get_name());
+    }
 }
 
 impl Component for SceneComponent {}
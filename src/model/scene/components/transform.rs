@@ -1,6 +1,8 @@
 use super::component::Component;
 use crate::model::base::*;
 
+use uuid::Uuid;
+
 #[derive(Debug, Clone)]
 pub struct TransformComponent {
     pub props: PropertyMap,
@@ -43,6 +45,16 @@ impl TransformComponent {
             "float scale",
             Property::Floats(vec![scale.x, scale.y, scale.z]),
         );
+        self.props
+            .add_string("string edition", &Uuid::new_v4().to_string());
+    }
+
+    /// Changes whenever the placement is set; editors writing `props` directly bump it themselves.
+    pub fn get_edition(&self) -> String {
+        return self
+            .props
+            .find_one_string("string edition")
+            .unwrap_or_default();
     }
 
     pub fn get_local_trs(&self) -> (Vector3, Quaternion, Vector3) {
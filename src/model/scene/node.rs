@@ -125,6 +125,19 @@ impl Node {
         None
     }
 
+    pub fn find_nodes_by_component<T: Component>(
+        node: &Arc<RwLock<Node>>,
+    ) -> Vec<Arc<RwLock<Node>>> {
+        let mut nodes = Vec::new();
+        if node.read().unwrap().get_component::<T>().is_some() {
+            nodes.push(node.clone());
+        }
+        for child in &node.read().unwrap().children {
+            nodes.extend(Node::find_nodes_by_component::<T>(child));
+        }
+        nodes
+    }
+
     pub fn update(&mut self) {
         for component in self.components.iter_mut() {
             if let Some(c) = component.downcast_mut::<&mut dyn Component>() {
@@ -1,6 +1,11 @@
 use std::vec;
 
 use super::resource_selector::ResourceSelector;
+use crate::conversion::color_space::ColorSpace;
+use crate::conversion::color_space::gamma_correct;
+use crate::conversion::color_space::is_out_of_gamut;
+use crate::conversion::spectrum::blackbody_to_rgb;
+use crate::conversion::spectrum::property_to_rgb;
 use crate::model::base::*;
 use crate::model::scene::*;
 
@@ -19,60 +24,58 @@ fn get_label3(key_type: &str) -> (String, String, String) {
     }
 }
 
-#[inline]
-pub fn gamma_correct(value: f32) -> f32 {
-    if value <= 0.0031308 {
-        return 12.92 * value;
-    } else {
-        return 1.055 * f32::powf(value, 1.0 / 2.4) - 0.055;
-    }
-}
-
-#[inline]
-pub fn inverse_gamma_correct(value: f32) -> f32 {
-    if value <= 0.04045 {
-        return value * 1.0 / 12.92;
-    } else {
-        return f32::powf((value + 0.055) * 1.0 / 1.055, 2.4);
-    }
-}
-
-#[inline]
-fn to_byte(v: f32) -> u8 {
-    f32::clamp(255.0 * gamma_correct(v), 0.0, 255.0) as u8
-}
-
-#[inline]
-fn from_byte(v: u8) -> f32 {
-    inverse_gamma_correct(v as f32 / 255.0)
+fn get_intensity(value: &[f32]) -> (f32, Vec<f32>) {
+    let intensity = value.iter().fold(1.0f32, |acc, &x| acc.max(x));
+    let new_value = value.iter().map(|&x| x / intensity).collect::<Vec<f32>>();
+    return (intensity, new_value);
 }
 
-fn xyz_to_rgb(xyz: &[f32]) -> [f32; 3] {
-    let mut rgb: [f32; 3] = [0.0; 3];
-    rgb[0] = 3.240479 * xyz[0] - 1.537150 * xyz[1] - 0.498535 * xyz[2];
-    rgb[1] = -0.969256 * xyz[0] + 1.875991 * xyz[1] + 0.041556 * xyz[2];
-    rgb[2] = 0.055648 * xyz[0] - 0.204043 * xyz[1] + 1.057311 * xyz[2];
-    return rgb;
+/// Shows a linear sRGB color, scaled down when it is brighter than 1.
+pub fn show_color_swatch(ui: &mut egui::Ui, rgb: &[f32; 3], size: egui::Vec2) -> egui::Response {
+    let max = rgb.iter().fold(1.0f32, |acc, &x| acc.max(x));
+    let to_byte = |v: f32| f32::clamp(255.0 * gamma_correct(v / max), 0.0, 255.0) as u8;
+    let color = egui::Color32::from_rgb(to_byte(rgb[0]), to_byte(rgb[1]), to_byte(rgb[2]));
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+    ui.painter().rect_filled(rect, 2.0, color);
+    return response.on_hover_text(format!("{:.4} {:.4} {:.4}", rgb[0], rgb[1], rgb[2]));
 }
 
-fn rgb_to_xyz(rgb: &[f32]) -> [f32; 3] {
-    let mut xyz: [f32; 3] = [0.0; 3];
-    xyz[0] = 0.412453 * rgb[0] + 0.357580 * rgb[1] + 0.180423 * rgb[2];
-    xyz[1] = 0.212671 * rgb[0] + 0.715160 * rgb[1] + 0.072169 * rgb[2];
-    xyz[2] = 0.019334 * rgb[0] + 0.119193 * rgb[1] + 0.950227 * rgb[2];
-    return xyz;
+fn show_blackbody(ui: &mut egui::Ui, value: &mut [f32]) -> bool {
+    let mut is_changed = false;
+    if value.len() >= 2 {
+        let rgb = blackbody_to_rgb(value[0]);
+        show_color_swatch(ui, &rgb, egui::Vec2::new(16.0, 16.0));
+        if ui
+            .add(egui::widgets::Slider::new(&mut value[0], 1000.0..=12000.0).suffix(" K"))
+            .changed()
+        {
+            is_changed = true;
+        }
+        if ui
+            .add(
+                egui::DragValue::new(&mut value[1])
+                    .speed(0.01)
+                    .range(0.0..=f32::MAX),
+            )
+            .changed()
+        {
+            is_changed = true;
+        }
+    }
+    return is_changed;
 }
 
-fn get_intensity(value: &[f32]) -> (f32, Vec<f32>) {
-    let intensity = value.iter().fold(1.0f32, |acc, &x| acc.max(x));
-    let new_value = value.iter().map(|&x| x / intensity).collect::<Vec<f32>>();
-    return (intensity, new_value);
+fn show_gamut_warning(ui: &mut egui::Ui, color_space: ColorSpace) {
+    ui.colored_label(ui.visuals().warn_fg_color, "⚠")
+        .on_hover_text(format!("Out of {} gamut", color_space.get_label()));
 }
 
-fn show_rgb(ui: &mut egui::Ui, value: &mut [f32]) -> bool {
+// `value` is linear RGB in `color_space`. The egui color picker works in linear sRGB.
+fn show_rgb(ui: &mut egui::Ui, value: &mut [f32], color_space: ColorSpace) -> bool {
     let mut is_changed = false;
     if value.len() >= 3 {
-        let (mut intensity, new_value) = get_intensity(value);
+        let display_value = color_space.convert_rgb(value, ColorSpace::Srgb);
+        let (mut intensity, new_value) = get_intensity(&display_value);
         let mut new_value: [f32; 3] = new_value.try_into().unwrap();
         if ui.color_edit_button_rgb(&mut new_value).changed() {
             is_changed = true;
@@ -83,9 +86,20 @@ fn show_rgb(ui: &mut egui::Ui, value: &mut [f32]) -> bool {
         {
             is_changed = true;
         }
-        value[0] = new_value[0] * intensity;
-        value[1] = new_value[1] * intensity;
-        value[2] = new_value[2] * intensity;
+        if is_out_of_gamut(&display_value) {
+            show_gamut_warning(ui, ColorSpace::Srgb);
+        }
+        if is_changed {
+            let new_value = [
+                new_value[0] * intensity,
+                new_value[1] * intensity,
+                new_value[2] * intensity,
+            ];
+            let new_value = ColorSpace::Srgb.convert_rgb(&new_value, color_space);
+            value[0] = new_value[0];
+            value[1] = new_value[1];
+            value[2] = new_value[2];
+        }
     }
     return is_changed;
 }
@@ -257,6 +271,24 @@ fn show_strings(
                         }
                     }
                 });
+        } else if key_name == "colorspace" {
+            let selected = ColorSpace::from_name(&value[0]).unwrap_or_default();
+            egui::ComboBox::from_id_salt("colorspace")
+                .selected_text(selected.get_label())
+                .show_ui(ui, |ui| {
+                    for color_space in ColorSpace::all().iter() {
+                        if ui
+                            .selectable_value(
+                                &mut value[0],
+                                color_space.get_name().to_string(),
+                                color_space.get_label(),
+                            )
+                            .changed()
+                        {
+                            is_changed = true;
+                        }
+                    }
+                });
         } else if key_name == "strategy" {
             let types = vec!["all", "one"]
                 .iter()
@@ -436,6 +468,7 @@ enum ColorType {
     Value,
     Texture,
     Spd,
+    Blackbody,
 }
 
 fn show_color_like(
@@ -454,6 +487,8 @@ fn show_color_like(
         color_type = ColorType::Value;
     } else if key_type == "spectrum" {
         color_type = ColorType::Spd;
+    } else if key_type == "blackbody" {
+        color_type = ColorType::Blackbody;
     }
     if key_type == "texture" {
         color_type = ColorType::Texture;
@@ -540,13 +575,38 @@ fn show_color_like(
                         }
                     }
                 }
+                if color_type != ColorType::Blackbody {
+                    if ui.small_button("K").on_hover_text("Blackbody").clicked() {
+                        is_changed = true;
+                        let search_key = format!("{}_{:?}", key_name, ColorType::Blackbody);
+                        let backup_value = if let Some(p) = props.get(&search_key) {
+                            Some(p.clone())
+                        } else {
+                            None
+                        };
+
+                        if let Some((key_type, _, prop)) = props.entry_mut(key_name) {
+                            backups.push((
+                                key_type.clone(),
+                                format!("{}_{:?}", key_name, color_type),
+                                prop.clone(),
+                            ));
+                            *key_type = "blackbody".to_string();
+                            if let Some(p) = backup_value {
+                                *prop = p;
+                            } else {
+                                *prop = Property::Floats(vec![6500.0, 1.0]);
+                            }
+                        }
+                    }
+                }
             });
             ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
                 if key_type == "color" || key_type == "rgb" {
                     if let Some(v) = props.get_mut(key_name) {
                         if let Property::Floats(value) = v {
                             ui.horizontal(|ui| {
-                                if show_rgb(ui, value) {
+                                if show_rgb(ui, value, resource_selector.color_space) {
                                     is_changed = true;
                                 }
                             });
@@ -555,16 +615,30 @@ fn show_color_like(
                 } else if key_type == "xyz" {
                     if let Some(v) = props.get_mut(key_name) {
                         if let Property::Floats(value) = v {
-                            let mut rgb = xyz_to_rgb(&value);
+                            let color_space = resource_selector.color_space;
+                            let mut rgb = color_space.xyz_to_rgb(&value);
                             ui.horizontal(|ui| {
-                                if show_rgb(ui, &mut rgb) {
+                                if show_rgb(ui, &mut rgb, color_space) {
                                     is_changed = true;
                                 }
+                                if is_out_of_gamut(&rgb) {
+                                    show_gamut_warning(ui, color_space);
+                                }
                             });
-                            let xyz = rgb_to_xyz(&rgb);
-                            value[0] = xyz[0];
-                            value[1] = xyz[1];
-                            value[2] = xyz[2];
+                            if is_changed {
+                                let xyz = color_space.rgb_to_xyz(&rgb);
+                                value[0] = xyz[0];
+                                value[1] = xyz[1];
+                                value[2] = xyz[2];
+                            }
+                        }
+                    }
+                } else if key_type == "blackbody" {
+                    if let Some(v) = props.get_mut(key_name) {
+                        if let Property::Floats(value) = v {
+                            if show_blackbody(ui, value) {
+                                is_changed = true;
+                            }
                         }
                     }
                 } else if key_type == "spectrum" {
@@ -572,24 +646,69 @@ fn show_color_like(
                         //if let Property::Strings(value) = v {
                         //    ui.text_edit_singleline(&mut value[0]);
                         //}
+                        let fullpath = if let Property::Strings(value) = v {
+                            value
+                                .first()
+                                .and_then(|name| resource_selector.get_spd_fullpath(name))
+                        } else {
+                            None
+                        };
+                        let color_space = resource_selector.color_space;
+                        if let Some(rgb) =
+                            property_to_rgb(key_type, v, fullpath.as_deref(), color_space)
+                        {
+                            show_color_swatch(ui, &rgb, egui::Vec2::new(16.0, 16.0));
+                        }
+                        if let Property::Floats(value) = v {
+                            ui.label(format!("inline ({} samples)", value.len() / 2));
+                        }
                         if let Property::Strings(value) = v {
                             let spd_names = resource_selector.get_spd_items();
+                            let library_names = resource_selector
+                                .get_library_spd_items()
+                                .into_iter()
+                                .filter(|(_, name, _)| !spd_names.iter().any(|s| s.1 == *name))
+                                .collect::<Vec<_>>();
+                            // The parser accepts an empty list, which has nothing selected
+                            let mut selected = value.first().cloned().unwrap_or_default();
+                            let mut is_selected = false;
                             egui::ComboBox::from_id_salt("spectrum")
-                                .selected_text(value[0].clone())
+                                .selected_text(selected.clone())
                                 .show_ui(ui, |ui| {
                                     for (_id, name, display_name) in spd_names.iter() {
                                         if ui
                                             .selectable_value(
-                                                &mut value[0],
+                                                &mut selected,
                                                 name.clone(),
                                                 display_name.clone(),
                                             )
                                             .changed()
                                         {
-                                            is_changed = true;
+                                            is_selected = true;
+                                        }
+                                    }
+                                    if !library_names.is_empty() {
+                                        ui.separator();
+                                        ui.label("Library");
+                                    }
+                                    for (_id, name, display_name) in library_names.iter() {
+                                        if ui
+                                            .selectable_value(
+                                                &mut selected,
+                                                name.clone(),
+                                                display_name.clone(),
+                                            )
+                                            .changed()
+                                        {
+                                            resource_selector.request_spectrum(name);
+                                            is_selected = true;
                                         }
                                     }
                                 });
+                            if is_selected {
+                                *value = vec![selected];
+                                is_changed = true;
+                            }
                         }
                     }
                 } else if key_type == "texture" {
@@ -636,7 +755,12 @@ fn show_color_like(
 }
 
 fn is_color_like(key_type: &str, key_name: &str) -> bool {
-    if key_type == "color" || key_type == "rgb" || key_type == "xyz" || key_type == "spectrum" {
+    if key_type == "color"
+        || key_type == "rgb"
+        || key_type == "xyz"
+        || key_type == "spectrum"
+        || key_type == "blackbody"
+    {
         return true;
     }
     if key_type == "texture" {
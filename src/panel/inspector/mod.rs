@@ -4,6 +4,8 @@ mod material_component;
 mod panel;
 mod preview;
 mod resource_selector;
+mod spd_editor;
+mod texture_baker;
 mod transform_component;
 mod typed_component;
 
@@ -1,5 +1,7 @@
 use super::common::*;
 use super::resource_selector::ResourceSelector;
+use super::spd_editor::SpdEditor;
+use super::texture_baker::TextureBaker;
 use crate::controller::AppController;
 use crate::model::base::PropertyMap;
 use crate::model::scene::AcceleratorComponent;
@@ -20,8 +22,10 @@ use crate::model::scene::Node;
 use crate::model::scene::OptionProperties;
 use crate::model::scene::Properties;
 use crate::model::scene::ResourceComponent;
+use crate::model::scene::ResourceManager;
 use crate::model::scene::SamplerComponent;
 use crate::model::scene::SamplerProperties;
+use crate::model::scene::SceneComponent;
 use crate::model::scene::ShapeComponent;
 use crate::model::scene::ShapeProperties;
 use crate::model::scene::TextureProperties;
@@ -46,6 +50,8 @@ pub struct InspectorPanel {
     pub is_open: bool,
     pub app_controller: Arc<RwLock<AppController>>,
     pub texture_id_map: Arc<RwLock<HashMap<Uuid, (String, egui::TextureId)>>>,
+    pub spd_editor: Arc<RwLock<SpdEditor>>,
+    pub texture_baker: Arc<RwLock<TextureBaker>>,
 }
 
 impl InspectorPanel {
@@ -54,34 +60,62 @@ impl InspectorPanel {
             is_open: true,
             app_controller: controller.clone(),
             texture_id_map: Arc::new(RwLock::new(HashMap::new())),
+            spd_editor: Arc::new(RwLock::new(SpdEditor::new())),
+            texture_baker: Arc::new(RwLock::new(TextureBaker::new())),
         }
     }
 
     pub fn show_inspector(&self, ui: &mut egui::Ui) {
+        let resource_selector = self.get_resource_selector();
+        {
+            let controller = self.app_controller.read().unwrap();
+            if let Some(node) = controller.get_current_node() {
+                self.show_node(ui, &node, &resource_selector);
+            } else if let Some(current_resource) = controller.get_current_resource() {
+                let id = current_resource.read().unwrap().get_id();
+                self.show_resource(ui, id, &resource_selector);
+            } else {
+                //ui.label("No node selected");
+            }
+        }
+        if let Some(resource_manager) = self.get_resource_manager() {
+            self.register_spectrum_resources(&resource_manager, &resource_selector);
+            self.register_baked_textures(ui, &resource_manager);
+        }
+    }
+
+    fn get_resource_manager(&self) -> Option<Arc<RwLock<ResourceManager>>> {
         let controller = self.app_controller.read().unwrap();
-        if let Some(node) = controller.get_current_node() {
-            self.show_node(ui, &node);
-        } else if let Some(current_resource) = controller.get_current_resource() {
-            let id = current_resource.read().unwrap().get_id();
-            self.show_resource(ui, id);
-        } else {
-            //ui.label("No node selected");
+        let root_node = controller.get_root_node();
+        let root_node = root_node.read().unwrap();
+        if let Some(resources_component) = root_node.get_component::<ResourceComponent>() {
+            return Some(resources_component.get_resource_manager());
         }
+        return None;
     }
 
     fn get_resource_selector(&self) -> ResourceSelector {
         let controller = self.app_controller.read().unwrap();
         let root_node = controller.get_root_node();
         let root_node = root_node.read().unwrap();
-        if let Some(resources_component) = root_node.get_component::<ResourceComponent>() {
-            ResourceSelector::new(&resources_component.get_resource_manager())
-        } else {
-            ResourceSelector::default()
+        let mut resource_selector =
+            if let Some(resources_component) = root_node.get_component::<ResourceComponent>() {
+                ResourceSelector::new(&resources_component.get_resource_manager())
+            } else {
+                ResourceSelector::default()
+            };
+        if let Some(scene) = root_node.get_component::<SceneComponent>() {
+            resource_selector.color_space = scene.get_color_space();
         }
+        return resource_selector;
     }
 
-    pub fn show_node(&self, ui: &mut egui::Ui, node: &Arc<RwLock<Node>>) {
-        let resource_selector = self.get_resource_selector();
+    pub fn show_node(
+        &self,
+        ui: &mut egui::Ui,
+        node: &Arc<RwLock<Node>>,
+        resource_selector: &ResourceSelector,
+    ) {
         let mut node = node.write().unwrap();
         {
             let mut enabled = node.is_enabled();
@@ -98,7 +132,7 @@ impl InspectorPanel {
                 node.set_name(&name);
             }
         }
-        self.show_components(ui, &mut node.components, &resource_selector);
+        self.show_components(ui, &mut node.components, resource_selector);
     }
 
     pub fn show_components(
@@ -116,6 +150,8 @@ impl InspectorPanel {
                 self.show_light_component(i, ui, component, resource_selector);
             } else if let Some(component) = component.downcast_mut::<MaterialComponent>() {
                 self.show_material_component(i, ui, component, resource_selector);
+            } else if let Some(component) = component.downcast_mut::<SceneComponent>() {
+                self.show_scene_component(i, ui, component, resource_selector);
             } else if let Some(component) = component.downcast_mut::<CameraComponent>() {
                 let camera_properties = CameraProperties::get_instance();
                 self.show_typed_component(
@@ -251,6 +287,24 @@ impl InspectorPanel {
         return is_changed;
     }
 
+    fn show_scene_component(
+        &self,
+        index: usize,
+        ui: &mut egui::Ui,
+        component: &mut SceneComponent,
+        resource_selector: &ResourceSelector,
+    ) -> bool {
+        let keys = vec![("string".to_string(), "colorspace".to_string(), None)];
+        return show_component_props(
+            index,
+            "Scene",
+            ui,
+            &mut component.props,
+            &keys,
+            resource_selector,
+        );
+    }
+
     fn show_other_component(
         &self,
         index: usize,
@@ -272,14 +326,13 @@ impl InspectorPanel {
         return is_changed;
     }
 
-    pub fn show_resource(&self, ui: &mut egui::Ui, id: Uuid) {
+    pub fn show_resource(&self, ui: &mut egui::Ui, id: Uuid, resource_selector: &ResourceSelector) {
         let controller = self.app_controller.read().unwrap();
         let root_node = controller.get_root_node();
         let root_node = root_node.read().unwrap();
 
         if let Some(resources_component) = root_node.get_component::<ResourceComponent>() {
             let resource_manager = resources_component.get_resource_manager();
-            let resource_selector = ResourceSelector::new(&resource_manager);
             let resource_manager = resource_manager.read().unwrap();
             if let Some(texture) = resource_manager.textures.get(&id) {
                 let mut texture_keys = Vec::new();
@@ -350,8 +403,18 @@ impl InspectorPanel {
                 {
                     let mut texture = texture.write().unwrap();
                     let props = texture.as_property_map_mut();
-                    show_properties(0, ui, props, &texture_keys, &resource_selector);
-                    show_properties(1, ui, props, &mapping_keys, &resource_selector);
+                    show_properties(0, ui, props, &texture_keys, resource_selector);
+                    show_properties(1, ui, props, &mapping_keys, resource_selector);
+                }
+                ui.separator();
+                {
+                    let texture = texture.read().unwrap();
+                    self.show_texture_baker(
+                        ui,
+                        &resource_manager,
+                        &texture,
+                        resource_selector.color_space,
+                    );
                 }
                 ui.add_space(3.0);
             } else if let Some(material) = resource_manager.materials.get(&id) {
@@ -401,7 +464,7 @@ impl InspectorPanel {
                 ui.separator();
                 self.show_material_preview(ui, 300.0, props);
                 ui.separator();
-                show_properties(0, ui, props, &keys, &resource_selector);
+                show_properties(0, ui, props, &keys, resource_selector);
                 ui.add_space(3.0);
             } else if let Some(mesh) = resource_manager.meshes.get(&id) {
                 let mut mesh = mesh.write().unwrap();
@@ -433,13 +496,14 @@ impl InspectorPanel {
                 ui.separator();
                 self.show_mesh_preview(ui, 300.0, props);
                 ui.separator();
-                show_properties(0, ui, props, &keys, &resource_selector);
+                show_properties(0, ui, props, &keys, resource_selector);
                 ui.add_space(3.0);
             } else if let Some(res) = resource_manager.other_resources.get(&id) {
                 let res = res.write().unwrap();
                 let mut name = res.get_name();
                 let t = res.get_type();
                 let filename = res.get_filename().unwrap_or_default();
+                let fullpath = res.get_fullpath();
                 let mut props = PropertyMap::new();
                 props.add_string("string type", &t);
                 props.add_string("string filename", &filename);
@@ -457,7 +521,11 @@ impl InspectorPanel {
                 ui.separator();
                 //show_type(ui, &mut props, &[t.clone()]);
                 //ui.separator();
-                show_properties(0, ui, &mut props, &keys, &resource_selector);
+                show_properties(0, ui, &mut props, &keys, resource_selector);
+                if t == "spd" {
+                    ui.separator();
+                    self.show_spd_editor(ui, id, fullpath);
+                }
                 ui.add_space(3.0);
             } else {
                 ui.label("Resource not found");
@@ -1,6 +1,10 @@
+use crate::conversion::color_space::ColorSpace;
+use crate::conversion::spectrum::get_named_spectra;
 use crate::model::scene::ResourceManager;
 
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::sync::RwLock;
 use uuid::Uuid;
 
@@ -12,6 +16,10 @@ pub struct ResourceSelector {
     pub material_items: Vec<ResourceSelectionItem>,
     pub spd_items: Vec<ResourceSelectionItem>,
     pub bsdffile_items: Vec<ResourceSelectionItem>,
+    pub library_spd_items: Vec<ResourceSelectionItem>,
+    pub color_space: ColorSpace, // working color space of the scene
+    spd_fullpaths: HashMap<String, String>,
+    requested_spectra: Arc<Mutex<Vec<String>>>,
 }
 
 impl ResourceSelector {
@@ -20,6 +28,7 @@ impl ResourceSelector {
         let mut material_items = Vec::new();
         let mut spd_items = Vec::new();
         let mut bsdffile_items = Vec::new();
+        let mut spd_fullpaths = HashMap::new();
 
         let manager = resouce_manager.read().unwrap();
         for (id, texture) in manager.textures.iter() {
@@ -41,6 +50,9 @@ impl ResourceSelector {
                 let name = resource.get_name().to_lowercase();
                 let filename = resource.get_filename().unwrap();
                 spd_items.push((id.clone(), name.clone(), filename.clone(), filename.clone()));
+                if let Some(fullpath) = resource.get_fullpath() {
+                    spd_fullpaths.insert(filename.clone(), fullpath);
+                }
             }
             if resource.get_type() == "bsdffile" {
                 let name = resource.get_name().to_lowercase();
@@ -59,11 +71,21 @@ impl ResourceSelector {
             .map(|(id, _name1, name2, name3)| (id, name2, name3))
             .collect();
 
+        // Built-in spectra are listed by the filename they get when registered to the scene.
+        let library_spd_items = get_named_spectra()
+            .iter()
+            .map(|s| (Uuid::nil(), s.get_filename(), s.name.clone()))
+            .collect();
+
         Self {
             texture_items: texture_items,
             material_items: material_items,
             spd_items: spd_items,
             bsdffile_items: bsdffile_items,
+            library_spd_items: library_spd_items,
+            color_space: ColorSpace::default(),
+            spd_fullpaths: spd_fullpaths,
+            requested_spectra: Arc::new(Mutex::new(Vec::new())),
         }
     }
     //pub fn get_texture_items(&self) -> &[ResourceSelectionItem] {
@@ -84,4 +106,26 @@ impl ResourceSelector {
     pub fn get_bsdffile_items(&self) -> Vec<(Uuid, String, String)> {
         self.bsdffile_items.clone()
     }
+
+    pub fn get_library_spd_items(&self) -> Vec<(Uuid, String, String)> {
+        self.library_spd_items.clone()
+    }
+
+    pub fn get_spd_fullpath(&self, filename: &str) -> Option<String> {
+        self.spd_fullpaths.get(filename).cloned()
+    }
+
+    /// Library spectra selected in the UI are registered as scene resources after drawing,
+    /// because the resource manager is locked while the inspector is shown.
+    pub fn request_spectrum(&self, filename: &str) {
+        let mut requested = self.requested_spectra.lock().unwrap();
+        if !requested.iter().any(|s| s == filename) {
+            requested.push(filename.to_string());
+        }
+    }
+
+    pub fn take_requested_spectra(&self) -> Vec<String> {
+        let mut requested = self.requested_spectra.lock().unwrap();
+        return std::mem::take(&mut *requested);
+    }
 }
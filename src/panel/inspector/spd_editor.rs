@@ -0,0 +1,383 @@
+use super::common::show_color_swatch;
+use super::panel::InspectorPanel;
+use super::resource_selector::ResourceSelector;
+use crate::conversion::spectrum::Spectrum;
+use crate::conversion::spectrum::find_named_spectrum;
+use crate::conversion::spectrum::find_named_spectrum_by_filename;
+use crate::conversion::spectrum::get_named_spectra;
+use crate::conversion::spectrum::write_named_spectrum;
+use crate::conversion::spectrum::write_spd_file;
+use crate::model::base::ParamSet;
+use crate::model::scene::OtherResource;
+use crate::model::scene::ResourceManager;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use eframe::egui;
+use uuid::Uuid;
+
+const PLOT_LAMBDA_START: f32 = 360.0;
+const PLOT_LAMBDA_END: f32 = 830.0;
+
+#[derive(Debug, Clone, Default)]
+pub struct SpdEditor {
+    resource_id: Option<Uuid>,
+    fullpath: Option<String>,
+    lambda: Vec<f32>,
+    values: Vec<f32>,
+    is_dirty: bool,
+    library_name: String,
+    saved_resources: Vec<(String, String, String)>, // name, filename, fullpath
+}
+
+impl SpdEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn load(&mut self, id: Uuid, fullpath: Option<String>) {
+        self.resource_id = Some(id);
+        self.fullpath = fullpath.clone();
+        self.lambda.clear();
+        self.values.clear();
+        self.is_dirty = false;
+        if let Some(fullpath) = fullpath {
+            match Spectrum::load_samples_from_file(&fullpath) {
+                Ok((lambda, values)) => {
+                    self.lambda = lambda;
+                    self.values = values;
+                }
+                Err(e) => {
+                    log::warn!("Failed to load spectrum \"{}\": {}", fullpath, e);
+                }
+            }
+        }
+    }
+
+    fn sort_samples(&mut self) {
+        let mut samples = self
+            .lambda
+            .iter()
+            .cloned()
+            .zip(self.values.iter().cloned())
+            .collect::<Vec<_>>();
+        samples.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        self.lambda = samples.iter().map(|s| s.0).collect();
+        self.values = samples.iter().map(|s| s.1).collect();
+    }
+
+    fn save(&mut self, path: &str) -> bool {
+        self.sort_samples();
+        match write_spd_file(path, &self.lambda, &self.values, "Generated by pbrt-ui") {
+            Ok(_) => {
+                self.is_dirty = false;
+                return true;
+            }
+            Err(e) => {
+                log::error!("Failed to save spectrum \"{}\": {}", path, e);
+                return false;
+            }
+        }
+    }
+
+    fn save_as(&mut self) {
+        let mut dialog = rfd::FileDialog::new()
+            .set_title("Save Spectrum")
+            .add_filter("SPD", &["spd"]);
+        if let Some(fullpath) = self.fullpath.as_ref() {
+            if let Some(parent) = Path::new(fullpath).parent() {
+                dialog = dialog.set_directory(parent);
+            }
+        }
+        if let Some(path) = dialog.save_file() {
+            let path = path.with_extension("spd");
+            let fullpath = path.to_string_lossy().to_string();
+            if self.save(&fullpath) {
+                let name = path.file_stem().unwrap().to_string_lossy().to_string();
+                let filename = path.file_name().unwrap().to_string_lossy().to_string();
+                self.saved_resources.push((name, filename, fullpath));
+            }
+        }
+    }
+
+    pub fn take_saved_resources(&mut self) -> Vec<(String, String, String)> {
+        return std::mem::take(&mut self.saved_resources);
+    }
+}
+
+pub fn show_spd_plot(ui: &mut egui::Ui, lambda: &[f32], values: &[f32], height: f32) {
+    let width = ui.available_width();
+    let (rect, _response) =
+        ui.allocate_exact_size(egui::Vec2::new(width, height), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, egui::Color32::from_gray(24));
+
+    let plot_rect = rect.shrink2(egui::Vec2::new(8.0, 12.0));
+    let max_value = values.iter().fold(0.0f32, |acc, &x| acc.max(x));
+    let max_value = if max_value > 0.0 {
+        max_value * 1.1
+    } else {
+        1.0
+    };
+    let to_screen = |l: f32, v: f32| -> egui::Pos2 {
+        let x = (l - PLOT_LAMBDA_START) / (PLOT_LAMBDA_END - PLOT_LAMBDA_START);
+        let y = v / max_value;
+        egui::Pos2::new(
+            plot_rect.left() + x * plot_rect.width(),
+            plot_rect.bottom() - y * plot_rect.height(),
+        )
+    };
+
+    // Grid lines every 50nm
+    let grid_stroke = egui::Stroke::new(1.0, egui::Color32::from_gray(48));
+    let text_color = egui::Color32::from_gray(160);
+    let mut l = 400.0;
+    while l <= 800.0 {
+        let p = to_screen(l, 0.0);
+        painter.line_segment(
+            [
+                egui::Pos2::new(p.x, plot_rect.top()),
+                egui::Pos2::new(p.x, plot_rect.bottom()),
+            ],
+            grid_stroke,
+        );
+        if (l as i32) % 100 == 0 {
+            painter.text(
+                egui::Pos2::new(p.x, rect.bottom()),
+                egui::Align2::CENTER_BOTTOM,
+                format!("{}", l as i32),
+                egui::FontId::monospace(9.0),
+                text_color,
+            );
+        }
+        l += 50.0;
+    }
+    painter.text(
+        plot_rect.left_top(),
+        egui::Align2::LEFT_TOP,
+        format!("{:.3}", max_value),
+        egui::FontId::monospace(9.0),
+        text_color,
+    );
+
+    if lambda.len() >= 2 {
+        let points = lambda
+            .iter()
+            .zip(values.iter())
+            .map(|(l, v)| to_screen(*l, *v))
+            .collect::<Vec<_>>();
+        for p in points.iter() {
+            painter.circle_filled(*p, 2.0, egui::Color32::LIGHT_BLUE);
+        }
+        painter.add(egui::Shape::line(
+            points,
+            egui::Stroke::new(1.5, egui::Color32::LIGHT_BLUE),
+        ));
+    }
+}
+
+fn show_samples(ui: &mut egui::Ui, editor: &mut SpdEditor) {
+    let mut remove_index = None;
+    egui_extras::TableBuilder::new(ui)
+        .id_salt("spd_samples")
+        .column(egui_extras::Column::initial(100.0))
+        .column(egui_extras::Column::initial(100.0))
+        .column(egui_extras::Column::remainder())
+        .auto_shrink([false, true])
+        .max_scroll_height(200.0)
+        .header(18.0, |mut header| {
+            header.col(|ui| {
+                ui.label("lambda (nm)");
+            });
+            header.col(|ui| {
+                ui.label("value");
+            });
+            header.col(|_ui| {});
+        })
+        .body(|mut body| {
+            for i in 0..editor.lambda.len() {
+                body.row(18.0, |mut row| {
+                    row.col(|ui| {
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut editor.lambda[i])
+                                    .speed(1.0)
+                                    .range(PLOT_LAMBDA_START..=PLOT_LAMBDA_END),
+                            )
+                            .changed()
+                        {
+                            editor.is_dirty = true;
+                        }
+                    });
+                    row.col(|ui| {
+                        if ui
+                            .add(egui::DragValue::new(&mut editor.values[i]).speed(0.01))
+                            .changed()
+                        {
+                            editor.is_dirty = true;
+                        }
+                    });
+                    row.col(|ui| {
+                        if ui.small_button("-").clicked() {
+                            remove_index = Some(i);
+                        }
+                    });
+                });
+            }
+        });
+    if let Some(i) = remove_index {
+        editor.lambda.remove(i);
+        editor.values.remove(i);
+        editor.is_dirty = true;
+    }
+}
+
+impl InspectorPanel {
+    pub fn show_spd_editor(&self, ui: &mut egui::Ui, id: Uuid, fullpath: Option<String>) {
+        let mut editor = self.spd_editor.write().unwrap();
+        if editor.resource_id != Some(id) {
+            editor.load(id, fullpath);
+        }
+
+        show_spd_plot(ui, &editor.lambda, &editor.values, 160.0);
+        ui.add_space(3.0);
+
+        ui.horizontal(|ui| {
+            ui.label("RGB");
+            if editor.lambda.len() >= 2 {
+                let rgb = Spectrum::from_sampled(&editor.lambda, &editor.values).to_rgb();
+                show_color_swatch(ui, &rgb, egui::Vec2::new(40.0, 16.0));
+                ui.label(format!("{:.4} {:.4} {:.4}", rgb[0], rgb[1], rgb[2]));
+            } else {
+                ui.label("-");
+            }
+        });
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            let names = get_named_spectra();
+            egui::ComboBox::from_id_salt("spd_library")
+                .selected_text(editor.library_name.clone())
+                .show_ui(ui, |ui| {
+                    for s in names.iter() {
+                        ui.selectable_value(
+                            &mut editor.library_name,
+                            s.name.clone(),
+                            s.name.clone(),
+                        );
+                    }
+                });
+            if ui.button("Load from library").clicked() {
+                if let Some(s) = find_named_spectrum(&editor.library_name) {
+                    editor.lambda = s.lambda.clone();
+                    editor.values = s.values.clone();
+                    editor.is_dirty = true;
+                }
+            }
+        });
+        ui.separator();
+
+        show_samples(ui, &mut editor);
+        ui.horizontal(|ui| {
+            if ui.button("Add sample").clicked() {
+                let l = editor.lambda.last().map(|l| l + 10.0).unwrap_or(400.0);
+                let v = editor.values.last().cloned().unwrap_or(1.0);
+                editor.lambda.push(l.min(PLOT_LAMBDA_END));
+                editor.values.push(v);
+                editor.is_dirty = true;
+            }
+            if ui.button("Sort").clicked() {
+                editor.sort_samples();
+            }
+        });
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            let fullpath = editor.fullpath.clone();
+            let can_save = editor.is_dirty && fullpath.is_some();
+            if ui
+                .add_enabled(can_save, egui::Button::new("Save"))
+                .clicked()
+            {
+                if let Some(fullpath) = fullpath {
+                    editor.save(&fullpath);
+                }
+            }
+            if ui
+                .add_enabled(editor.lambda.len() >= 2, egui::Button::new("Save As..."))
+                .clicked()
+            {
+                editor.save_as();
+            }
+            if editor.is_dirty {
+                ui.label("modified");
+            }
+        });
+    }
+
+    pub fn register_spectrum_resources(
+        &self,
+        resource_manager: &Arc<RwLock<ResourceManager>>,
+        resource_selector: &ResourceSelector,
+    ) {
+        let requested = resource_selector.take_requested_spectra();
+        let saved = self.spd_editor.write().unwrap().take_saved_resources();
+        if requested.is_empty() && saved.is_empty() {
+            return;
+        }
+        let mut resource_manager = resource_manager.write().unwrap();
+        for filename in requested.iter() {
+            if resource_manager
+                .find_spectrum_by_filename(filename)
+                .is_some()
+            {
+                continue;
+            }
+            if let Some(spectrum) = find_named_spectrum_by_filename(filename) {
+                let dir = dirs::cache_dir().unwrap().join("pbrt_ui").join("spectra");
+                match write_named_spectrum(spectrum, &dir) {
+                    Ok(fullpath) => {
+                        let fullpath = fullpath.to_string_lossy().to_string();
+                        add_spectrum_resource(
+                            &mut resource_manager,
+                            &spectrum.name,
+                            filename,
+                            &fullpath,
+                        );
+                    }
+                    Err(e) => {
+                        log::error!("Failed to write spectrum \"{}\": {}", spectrum.name, e);
+                    }
+                }
+            }
+        }
+        for (name, filename, fullpath) in saved.iter() {
+            if resource_manager
+                .find_spectrum_by_filename(filename)
+                .is_some()
+            {
+                continue;
+            }
+            add_spectrum_resource(&mut resource_manager, name, filename, fullpath);
+        }
+    }
+}
+
+fn add_spectrum_resource(
+    resource_manager: &mut ResourceManager,
+    name: &str,
+    filename: &str,
+    fullpath: &str,
+) {
+    let mut params = ParamSet::default();
+    params.add_string("string type", "spd");
+    params.add_string("string filename", filename);
+    params.add_string("string fullpath", fullpath);
+    let resource = OtherResource::new(name, &params);
+    let id = resource.get_id();
+    resource_manager
+        .other_resources
+        .insert(id, Arc::new(RwLock::new(resource)));
+}
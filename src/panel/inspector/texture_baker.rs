@@ -0,0 +1,405 @@
+use super::panel::InspectorPanel;
+use crate::conversion::color_space::ColorSpace;
+use crate::conversion::texture_node::BakeFormat;
+use crate::conversion::texture_node::bake_texture_image;
+use crate::conversion::texture_node::save_baked_image;
+use crate::model::base::Matrix4x4;
+use crate::model::base::Property;
+use crate::model::base::PropertyMap;
+use crate::model::scene::ResourceManager;
+use crate::model::scene::Texture;
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::RwLock;
+use std::thread;
+use std::time::Duration;
+
+use eframe::egui;
+use uuid::Uuid;
+
+const BAKE_SIZES: [u32; 5] = [256, 512, 1024, 2048, 4096];
+const BAKE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Mapping parameters carried over so the baked image lines up with the original texture.
+const MAPPING_KEYS: [&str; 5] = [
+    "string mapping",
+    "float uscale",
+    "float vscale",
+    "float udelta",
+    "float vdelta",
+];
+
+#[derive(Debug, Clone)]
+struct BakedTexture {
+    source_name: String,
+    name: String,
+    color_type: String,
+    filename: String,
+    fullpath: String,
+    mapping: PropertyMap,
+    transform: Matrix4x4,
+    gamma: bool,
+    replace_in_materials: bool,
+}
+
+/// A bake running on a thread, so a large size does not stall the UI.
+#[derive(Debug, Clone)]
+struct PendingBake {
+    baked: BakedTexture,
+    path: PathBuf,
+    result: Arc<Mutex<Option<Result<(), String>>>>,
+}
+
+impl PendingBake {
+    fn spawn(
+        resource_manager: ResourceManager,
+        texture_id: Uuid,
+        size: u32,
+        format: BakeFormat,
+        color_space: ColorSpace,
+        baked: BakedTexture,
+        path: &Path,
+    ) -> Self {
+        let result = Arc::new(Mutex::new(None));
+        {
+            let result = result.clone();
+            let path = path.to_path_buf();
+            thread::spawn(move || {
+                let baked = bake_texture_image(&resource_manager, texture_id, size, color_space)
+                    .and_then(|image| save_baked_image(&image, &path, format))
+                    .map_err(|e| e.to_string());
+                *result.lock().unwrap() = Some(baked);
+            });
+        }
+        return Self {
+            baked,
+            path: path.to_path_buf(),
+            result,
+        };
+    }
+
+    /// The result once the thread is done.
+    fn take_result(&self) -> Option<Result<(), String>> {
+        return self.result.lock().unwrap().take();
+    }
+}
+
+/// Textures as they are now, for a bake that reads them while they are edited.
+fn copy_textures(resource_manager: &ResourceManager) -> ResourceManager {
+    let mut copy = ResourceManager::default();
+    for (id, texture) in resource_manager.textures.iter() {
+        let texture = texture.read().unwrap().clone();
+        copy.textures.insert(*id, Arc::new(RwLock::new(texture)));
+    }
+    return copy;
+}
+
+#[derive(Debug, Clone)]
+pub struct TextureBaker {
+    size: u32,
+    format: BakeFormat,
+    replace_in_materials: bool,
+    message: Option<String>,
+    pending: Vec<PendingBake>,
+    baked_textures: Vec<BakedTexture>,
+}
+
+impl Default for TextureBaker {
+    fn default() -> Self {
+        Self {
+            size: 1024,
+            format: BakeFormat::default(),
+            replace_in_materials: false,
+            message: None,
+            pending: Vec::new(),
+            baked_textures: Vec::new(),
+        }
+    }
+}
+
+impl TextureBaker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn bake(
+        &mut self,
+        resource_manager: &ResourceManager,
+        texture: &Texture,
+        color_space: ColorSpace,
+    ) {
+        let name = texture.get_name();
+        let extension = self.format.get_extension();
+        let dialog = rfd::FileDialog::new()
+            .set_title("Bake Texture")
+            .add_filter(self.format.get_label(), &[extension])
+            .set_file_name(format!("{}_baked.{}", name, extension));
+        let Some(path) = dialog.save_file() else {
+            return;
+        };
+        let path = path.with_extension(extension);
+        let baked = self.make_baked_texture(texture, &path);
+        self.pending.push(PendingBake::spawn(
+            copy_textures(resource_manager),
+            texture.get_id(),
+            self.size,
+            self.format,
+            color_space,
+            baked,
+            &path,
+        ));
+        self.message = Some(format!("Baking to {}...", path.display()));
+    }
+
+    pub fn is_baking(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Keeps the textures of the bakes that are done, to be registered.
+    fn update(&mut self) {
+        let mut pending = Vec::new();
+        for bake in std::mem::take(&mut self.pending) {
+            match bake.take_result() {
+                Some(Ok(())) => {
+                    self.message = Some(format!("Baked to {}", bake.path.display()));
+                    self.baked_textures.push(bake.baked);
+                }
+                Some(Err(e)) => {
+                    log::error!(
+                        "Failed to bake texture \"{}\": {}",
+                        bake.baked.source_name,
+                        e
+                    );
+                    self.message = Some(e);
+                }
+                None => pending.push(bake),
+            }
+        }
+        self.pending = pending;
+    }
+
+    fn make_baked_texture(&self, texture: &Texture, path: &Path) -> BakedTexture {
+        let props = texture.as_property_map();
+        let mut mapping = PropertyMap::new();
+        for key in MAPPING_KEYS.iter() {
+            if let Some(value) = props.get(key) {
+                mapping.insert(key, value.clone());
+            }
+        }
+        BakedTexture {
+            source_name: texture.get_name(),
+            name: path.file_stem().unwrap().to_string_lossy().to_string(),
+            color_type: texture.get_color_type(),
+            filename: path.file_name().unwrap().to_string_lossy().to_string(),
+            fullpath: path.to_string_lossy().to_string(),
+            mapping,
+            transform: texture.get_transform(),
+            gamma: self.format == BakeFormat::Png, // PNG is written sRGB encoded
+            replace_in_materials: self.replace_in_materials,
+        }
+    }
+}
+
+impl InspectorPanel {
+    pub fn show_texture_baker(
+        &self,
+        ui: &mut egui::Ui,
+        resource_manager: &ResourceManager,
+        texture: &Texture,
+        color_space: ColorSpace,
+    ) {
+        let mut baker = self.texture_baker.write().unwrap();
+        egui::CollapsingHeader::new("Bake")
+            .id_salt("texture_bake")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Resolution");
+                    egui::ComboBox::from_id_salt("bake_size")
+                        .selected_text(format!("{}", baker.size))
+                        .show_ui(ui, |ui| {
+                            for size in BAKE_SIZES.iter() {
+                                ui.selectable_value(&mut baker.size, *size, format!("{}", size));
+                            }
+                        });
+                    ui.label("Format");
+                    egui::ComboBox::from_id_salt("bake_format")
+                        .selected_text(baker.format.get_label())
+                        .show_ui(ui, |ui| {
+                            for format in BakeFormat::all().iter() {
+                                ui.selectable_value(&mut baker.format, *format, format.get_label());
+                            }
+                        });
+                });
+                ui.checkbox(
+                    &mut baker.replace_in_materials,
+                    "Replace in materials with an imagemap",
+                );
+                if ui.button("Bake texture...").clicked() {
+                    baker.bake(resource_manager, texture, color_space);
+                }
+                if let Some(message) = baker.message.as_ref() {
+                    ui.label(message);
+                }
+            });
+    }
+
+    pub fn register_baked_textures(
+        &self,
+        ui: &egui::Ui,
+        resource_manager: &Arc<RwLock<ResourceManager>>,
+    ) {
+        let baked_textures = {
+            let mut baker = self.texture_baker.write().unwrap();
+            baker.update();
+            if baker.is_baking() {
+                ui.ctx().request_repaint_after(BAKE_POLL_INTERVAL);
+            }
+            std::mem::take(&mut baker.baked_textures)
+        };
+        if baked_textures.is_empty() {
+            return;
+        }
+        let mut resource_manager = resource_manager.write().unwrap();
+        for baked in baked_textures.iter() {
+            let name = add_imagemap_texture(&mut resource_manager, baked);
+            if baked.replace_in_materials {
+                replace_texture_in_materials(&resource_manager, &baked.source_name, &name);
+            }
+        }
+    }
+}
+
+/// The imagemap made by an earlier bake into the same file.
+fn find_baked_texture(
+    resource_manager: &ResourceManager,
+    fullpath: &str,
+) -> Option<Arc<RwLock<Texture>>> {
+    resource_manager
+        .textures
+        .values()
+        .find(|texture| {
+            let texture = texture.read().unwrap();
+            return texture.get_type() == "imagemap"
+                && texture.get_fullpath().as_deref() == Some(fullpath);
+        })
+        .cloned()
+}
+
+fn get_unique_texture_name(resource_manager: &ResourceManager, name: &str) -> String {
+    let mut unique_name = name.to_string();
+    let mut index = 1;
+    while resource_manager
+        .find_texture_by_name(&unique_name)
+        .is_some()
+    {
+        unique_name = format!("{}_{}", name, index);
+        index += 1;
+    }
+    return unique_name;
+}
+
+/// Adds the baked image as an imagemap texture and returns its name.
+fn add_imagemap_texture(resource_manager: &mut ResourceManager, baked: &BakedTexture) -> String {
+    if let Some(texture) = find_baked_texture(resource_manager, &baked.fullpath) {
+        // baked again over the same file, make the viewport reload it
+        let mut texture = texture.write().unwrap();
+        let props = texture.as_property_map_mut();
+        props.add_string("string edition", &Uuid::new_v4().to_string());
+        return texture.get_name();
+    }
+    let name = get_unique_texture_name(resource_manager, &baked.name);
+    let mut props = baked.mapping.clone();
+    props.add_string("string filename", &baked.filename);
+    props.add_bools("bool gamma", &[baked.gamma]);
+    let texture = Texture::new(
+        &name,
+        &baked.color_type,
+        "imagemap",
+        Some(&baked.fullpath),
+        &props,
+        &baked.transform,
+    );
+    let id = texture.get_id();
+    resource_manager
+        .textures
+        .insert(id, Arc::new(RwLock::new(texture)));
+    return name;
+}
+
+fn replace_texture_in_materials(resource_manager: &ResourceManager, from: &str, to: &str) {
+    for material in resource_manager.materials.values() {
+        let mut material = material.write().unwrap();
+        let props = material.as_property_map_mut();
+        let mut is_changed = false;
+        for (key_type, key_name) in props.get_keys().iter() {
+            if key_type != "texture" {
+                continue;
+            }
+            if let Some(Property::Strings(names)) = props.get_mut(key_name) {
+                for name in names.iter_mut().filter(|name| name.as_str() == from) {
+                    *name = to.to_string();
+                    is_changed = true;
+                }
+            }
+        }
+        if is_changed {
+            props.add_string("string edition", &Uuid::new_v4().to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_baked_texture(name: &str, fullpath: &str) -> BakedTexture {
+        BakedTexture {
+            source_name: "checks".to_string(),
+            name: name.to_string(),
+            color_type: "spectrum".to_string(),
+            filename: format!("{}.exr", name),
+            fullpath: fullpath.to_string(),
+            mapping: PropertyMap::new(),
+            transform: Matrix4x4::identity(),
+            gamma: false,
+            replace_in_materials: false,
+        }
+    }
+
+    #[test]
+    fn test_add_imagemap_texture_001() {
+        // an unrelated texture with the same name is left alone
+        let mut resource_manager = ResourceManager::default();
+        let mut props = PropertyMap::new();
+        props.add_rgb("rgb value", &[1.0, 0.0, 0.0]);
+        let texture = Texture::new(
+            "wood_baked",
+            "spectrum",
+            "constant",
+            None,
+            &props,
+            &Matrix4x4::identity(),
+        );
+        let id = texture.get_id();
+        resource_manager
+            .textures
+            .insert(id, Arc::new(RwLock::new(texture)));
+
+        let baked = make_baked_texture("wood_baked", "/tmp/wood_baked.exr");
+        let name = add_imagemap_texture(&mut resource_manager, &baked);
+        assert_eq!(name, "wood_baked_1");
+        assert_eq!(resource_manager.textures.len(), 2);
+        let texture = resource_manager.textures.get(&id).unwrap().read().unwrap();
+        assert_eq!(texture.get_type(), "constant");
+        drop(texture);
+
+        // baking into the same file again reuses the imagemap
+        let name = add_imagemap_texture(&mut resource_manager, &baked);
+        assert_eq!(name, "wood_baked_1");
+        assert_eq!(resource_manager.textures.len(), 2);
+    }
+}
@@ -6,6 +6,7 @@ use crate::model::scene::ResourceCacheComponent;
 use crate::model::scene::ResourceCacheManager;
 use crate::model::scene::ResourceComponent;
 use crate::model::scene::ResourceManager;
+use crate::model::scene::SceneComponent;
 
 use eframe::egui;
 
@@ -146,6 +147,9 @@ impl DebugTexturesPanel {
         let resource_manager = resource_manager.read().unwrap();
         let resource_cache_manager = resource_cache_component.get_resource_cache_manager();
         let mut resource_cache_manager = resource_cache_manager.write().unwrap();
+        if let Some(scene) = root_node.get_component::<SceneComponent>() {
+            resource_cache_manager.set_color_space(scene.get_color_space());
+        }
         // Update texture caches
         create_texture_nodes(&resource_manager, &mut resource_cache_manager);
         create_image_variants(
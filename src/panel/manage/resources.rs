@@ -1,6 +1,7 @@
 use crate::controller::AppController;
 use crate::model::scene::ResourceCacheComponent;
 use crate::model::scene::ResourceComponent;
+use crate::model::scene::SceneComponent;
 
 use crate::conversion::texture_node::DynaImage;
 use crate::conversion::texture_node::TexturePurpose;
@@ -100,6 +101,9 @@ impl ResourcesPanel {
                         let resource_cache_manager =
                             resource_cache_component.get_resource_cache_manager();
                         let mut resource_cache_manager = resource_cache_manager.write().unwrap();
+                        if let Some(scene) = root_node.get_component::<SceneComponent>() {
+                            resource_cache_manager.set_color_space(scene.get_color_space());
+                        }
                         create_texture_nodes(&resource_manager, &mut resource_cache_manager);
                         create_image_variants(
                             &resource_manager,
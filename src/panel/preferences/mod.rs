@@ -33,13 +33,35 @@ impl PreferencesWindow {
     }
 
     pub fn open(&mut self) {
+        self.reload_config();
         self.is_open = true;
     }
 
     pub fn toggle_open(&mut self) {
+        if !self.is_open {
+            self.reload_config();
+        }
         self.is_open = !self.is_open;
     }
 
+    // The viewport and the render queue keep their settings in the same config
+    fn reload_config(&mut self) {
+        let controller = self.controller.read().unwrap();
+        let config = controller.get_config();
+        let config = config.read().unwrap();
+        self.config = config.clone();
+    }
+
+    // Only the fields edited here, so settings changed elsewhere are kept
+    fn apply_config(&self, config: &mut AppConfig) {
+        config.pbrt_executable_path = self.config.pbrt_executable_path.clone();
+        config.enable_display_server = self.config.enable_display_server;
+        config.display_server_port = self.config.display_server_port;
+        config.enable_render_agent = self.config.enable_render_agent;
+        config.render_agent_address = self.config.render_agent_address.clone();
+        config.render_agent_token = self.config.render_agent_token.clone();
+    }
+
     pub fn show(&mut self, ctx: &egui::Context) {
         if !self.is_open {
             return;
@@ -104,13 +126,31 @@ impl PreferencesWindow {
                         );
                     });
                 });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.config.enable_render_agent, "Render on agent")
+                        .on_hover_text(
+                            "Send the scene to pbrt_render_agent instead of running pbrt here",
+                        );
+                    ui.add_enabled(
+                        self.config.enable_render_agent,
+                        egui::TextEdit::singleline(&mut self.config.render_agent_address)
+                            .hint_text("host:port"),
+                    );
+                    ui.add_enabled(
+                        self.config.enable_render_agent,
+                        egui::TextEdit::singleline(&mut self.config.render_agent_token)
+                            .password(true)
+                            .hint_text("token"),
+                    )
+                    .on_hover_text("The token pbrt_render_agent was started with");
+                });
                 ui.horizontal(|ui| {
                     if ui.button("OK").clicked() {
                         let controller = self.controller.write().unwrap();
                         {
                             let config = controller.get_config();
                             let mut config = config.write().unwrap();
-                            *config = self.config.clone();
+                            self.apply_config(&mut config);
                         }
                         controller.save_config();
                         self.is_open = false;
@@ -138,7 +178,7 @@ impl Panel for PreferencesWindow {
     }
 
     fn toggle_open(&mut self) -> bool {
-        self.is_open = !self.is_open;
+        PreferencesWindow::toggle_open(self);
         self.is_open
     }
 
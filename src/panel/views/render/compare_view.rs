@@ -0,0 +1,461 @@
+use super::image_channels::get_false_color;
+use super::image_compare::CompareMode;
+use super::image_compare::ImageMetrics;
+use super::image_compare::compute_metrics;
+use super::image_compare::get_difference;
+use super::image_data::ImageData;
+use super::render_history::RenderHistory;
+use super::render_state::RenderState;
+use super::render_view::FULL_UV;
+use super::render_view::get_pixel_at;
+use super::render_view::handle_zoom_input;
+use super::render_view::update_texture;
+
+use eframe::egui;
+use std::sync::Arc;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+// Distance in points within which a drag moves the wipe line instead of panning
+const WIPE_GRAB_DISTANCE: f32 = 6.0;
+
+/// Images the cached difference and metrics were computed from.
+/// Both are only computed for finished renders, whose images no longer receive tiles.
+#[derive(Debug, Clone, PartialEq)]
+struct CompareKey {
+    a: (Uuid, u64),
+    b: (Uuid, u64),
+    mode: CompareMode,
+}
+
+impl CompareKey {
+    fn new(a: &RenderHistory, b: &RenderHistory, mode: CompareMode) -> Option<Self> {
+        a.get_image_data()?;
+        b.get_image_data()?;
+        Some(Self {
+            a: (a.get_id(), a.get_image_edition()),
+            b: (b.get_id(), b.get_image_edition()),
+            mode,
+        })
+    }
+}
+
+struct DifferenceImage {
+    key: CompareKey,
+    result: Result<(egui::TextureId, f32), String>, // Texture and the value of the top colour
+    values: Vec<f32>,
+}
+
+/// A/B comparison of two render histories, B being the reference.
+pub struct CompareView {
+    mode: CompareMode,
+    wipe: f32, // Split position across the view, in [0, 1]
+    dragging_wipe: bool,
+    texture_id: Option<egui::TextureId>,
+    difference: Option<DifferenceImage>,
+    metrics: Option<(CompareKey, Result<ImageMetrics, String>)>,
+}
+
+fn is_finished(history: &RenderHistory) -> bool {
+    history.get_state() == RenderState::Finished
+}
+
+fn get_image_size(image: &Arc<Mutex<ImageData>>) -> egui::Vec2 {
+    let image = image.lock().unwrap();
+    egui::vec2(image.width as f32, image.height as f32)
+}
+
+fn create_difference_image(width: usize, height: usize, values: &[f32]) -> (egui::ImageData, f32) {
+    let max = values
+        .iter()
+        .filter(|v| v.is_finite())
+        .fold(0.0f32, |max, v| max.max(*v));
+    let pixels = values
+        .iter()
+        .map(|v| {
+            let t = if max > 0.0 { v / max } else { 0.0 };
+            let [r, g, b] = get_false_color(t).map(|c| (c * 255.0) as u8);
+            egui::Color32::from_rgb(r, g, b)
+        })
+        .collect();
+    let image = egui::ColorImage {
+        size: [width, height],
+        source_size: egui::Vec2::new(width as f32, height as f32),
+        pixels,
+    };
+    (egui::ImageData::Color(Arc::new(image)), max)
+}
+
+fn show_metrics(ui: &mut egui::Ui, metrics: &ImageMetrics) {
+    ui.monospace(format!("MSE {:.3e}", metrics.mse))
+        .on_hover_text("Mean squared error of the linear values");
+    ui.monospace(format!("relMSE {:.3e}", metrics.rel_mse))
+        .on_hover_text("Squared error relative to the reference B");
+    ui.monospace(format!("PSNR {:.2} dB", metrics.psnr))
+        .on_hover_text("Peak signal to noise ratio for a peak of 1.0");
+    ui.monospace(format!("FLIP {:.4}", metrics.flip))
+        .on_hover_text("Mean perceptual error of the sRGB display, in [0, 1]");
+}
+
+fn get_rgb(image: &ImageData, x: usize, y: usize) -> Vec<(String, f32)> {
+    let n_channels = image.channel_names.len();
+    let index = (y * image.width + x) * n_channels;
+    image
+        .channel_names
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| ["R", "G", "B", "Y"].contains(&name.as_str()))
+        .map(|(c, name)| (name.clone(), image.data[index + c]))
+        .collect()
+}
+
+/// Colour values of A and B at one pixel, and the difference shown.
+fn show_compare_probe(
+    ui: &mut egui::Ui,
+    a: &ImageData,
+    b: &ImageData,
+    x: usize,
+    y: usize,
+    difference: Option<f32>,
+) {
+    ui.label(format!("Pixel {}, {}", x, y));
+    egui::Grid::new("compare_view_pixel_probe")
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("");
+            ui.label("A");
+            ui.label("B");
+            ui.end_row();
+            let va = get_rgb(a, x, y);
+            let vb = if x < b.width && y < b.height {
+                get_rgb(b, x, y)
+            } else {
+                Vec::new()
+            };
+            for (i, (name, value)) in va.iter().enumerate() {
+                ui.label(name);
+                ui.monospace(format!("{:.6}", value));
+                match vb.get(i) {
+                    Some((_, value)) => ui.monospace(format!("{:.6}", value)),
+                    None => ui.label("-"),
+                };
+                ui.end_row();
+            }
+            if let Some(difference) = difference {
+                ui.label("Diff");
+                ui.monospace(format!("{:.6}", difference));
+                ui.end_row();
+            }
+        });
+}
+
+fn show_label(painter: &egui::Painter, pos: egui::Pos2, align: egui::Align2, text: &str) {
+    let galley = painter.layout_no_wrap(
+        text.to_string(),
+        egui::FontId::proportional(12.0),
+        egui::Color32::WHITE,
+    );
+    let rect = align.anchor_size(pos, galley.size()).expand(3.0);
+    painter.rect_filled(rect, 2.0, egui::Color32::from_black_alpha(160));
+    painter.galley(
+        rect.min + egui::vec2(3.0, 3.0),
+        galley,
+        egui::Color32::WHITE,
+    );
+}
+
+impl CompareView {
+    pub fn new() -> Self {
+        Self {
+            mode: CompareMode::default(),
+            wipe: 0.5,
+            dragging_wipe: false,
+            texture_id: None,
+            difference: None,
+            metrics: None,
+        }
+    }
+
+    fn update_metrics(&mut self, a: &RenderHistory, b: &RenderHistory) {
+        if !is_finished(a) || !is_finished(b) {
+            self.metrics = None;
+            return;
+        }
+        // Metrics do not depend on the mode
+        let Some(key) = CompareKey::new(a, b, CompareMode::default()) else {
+            self.metrics = None;
+            return;
+        };
+        if self.metrics.as_ref().map(|(k, _)| k) == Some(&key) {
+            return;
+        }
+        let image_a = a.get_image_data().unwrap();
+        let image_b = b.get_image_data().unwrap();
+        let metrics = compute_metrics(&image_a.lock().unwrap(), &image_b.lock().unwrap());
+        self.metrics = Some((key, metrics));
+    }
+
+    fn update_difference(&mut self, ctx: &egui::Context, a: &RenderHistory, b: &RenderHistory) {
+        if !is_finished(a) || !is_finished(b) {
+            self.difference = None;
+            return;
+        }
+        let Some(key) = CompareKey::new(a, b, self.mode) else {
+            self.difference = None;
+            return;
+        };
+        if self.difference.as_ref().map(|d| &d.key) == Some(&key) {
+            return;
+        }
+        let image_a = a.get_image_data().unwrap();
+        let image_b = b.get_image_data().unwrap();
+        let image_a = image_a.lock().unwrap();
+        let image_b = image_b.lock().unwrap();
+        let (result, values) = match get_difference(&image_a, &image_b, self.mode) {
+            Ok(values) => {
+                let (image, max) = create_difference_image(image_a.width, image_a.height, &values);
+                let tex_manager = ctx.tex_manager().clone();
+                let mut tex_manager = tex_manager.write();
+                let texture_id = match self.texture_id {
+                    Some(texture_id) => {
+                        let delta =
+                            egui::epaint::ImageDelta::full(image, egui::TextureOptions::NEAREST);
+                        tex_manager.set(texture_id, delta);
+                        texture_id
+                    }
+                    None => {
+                        let texture_id = tex_manager.alloc(
+                            "compare_image".to_string(),
+                            image,
+                            egui::TextureOptions::NEAREST,
+                        );
+                        self.texture_id = Some(texture_id);
+                        texture_id
+                    }
+                };
+                (Ok((texture_id, max)), values)
+            }
+            Err(e) => (Err(e), Vec::new()),
+        };
+        self.difference = Some(DifferenceImage {
+            key,
+            result,
+            values,
+        });
+    }
+
+    fn show_toolbar(&mut self, ui: &mut egui::Ui, a: &RenderHistory, b: &RenderHistory) {
+        egui::ComboBox::from_id_salt("compare_view_mode")
+            .selected_text(self.mode.get_label())
+            .show_ui(ui, |ui| {
+                for mode in CompareMode::all() {
+                    ui.selectable_value(&mut self.mode, *mode, mode.get_label());
+                }
+            });
+        ui.label(format!("A: {}  B: {}", a.get_name(), b.get_name()));
+        ui.separator();
+        match self.metrics.as_ref() {
+            Some((_, Ok(metrics))) => show_metrics(ui, metrics),
+            Some((_, Err(e))) => {
+                ui.label(e);
+            }
+            None => {
+                ui.label("Metrics are shown once both renders have finished");
+            }
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, a: &mut RenderHistory, b: &mut RenderHistory) {
+        a.reload_image(ui.ctx());
+        b.reload_image(ui.ctx());
+        self.update_metrics(a, b);
+        if self.mode.is_difference() {
+            self.update_difference(ui.ctx(), a, b);
+        }
+        ui.horizontal(|ui| self.show_toolbar(ui, a, b));
+
+        let view_rect = ui.available_rect_before_wrap();
+        let response = ui.allocate_rect(view_rect, egui::Sense::click_and_drag());
+        let texture_a = update_texture(ui.ctx(), a);
+        let texture_b = update_texture(ui.ctx(), b);
+        let (Some(image_a), Some(image_b)) = (a.get_image_data(), b.get_image_data()) else {
+            return;
+        };
+        let size_a = get_image_size(&image_a);
+        let size_b = get_image_size(&image_b);
+        let painter = ui.painter_at(view_rect);
+
+        // Both images follow the zoom and pan of A
+        let (rect_a, rect_b) = match self.mode {
+            CompareMode::SideBySide => {
+                let half = egui::vec2(view_rect.width() * 0.5, view_rect.height());
+                let left = egui::Rect::from_min_size(view_rect.min, half);
+                let right = egui::Rect::from_min_size(view_rect.center_top(), half);
+                let pointer_rect = match response.interact_pointer_pos().or(response.hover_pos()) {
+                    Some(pos) if right.contains(pos) => right,
+                    _ => left,
+                };
+                handle_zoom_input(ui, &response, &mut a.view_zoom, pointer_rect, size_a);
+                let rect_a = a.view_zoom.get_image_rect(left, size_a);
+                let rect_b = a.view_zoom.get_image_rect(right, size_b);
+                if let Some(texture_id) = texture_a {
+                    painter.with_clip_rect(left).image(
+                        texture_id,
+                        rect_a,
+                        FULL_UV,
+                        egui::Color32::WHITE,
+                    );
+                }
+                if let Some(texture_id) = texture_b {
+                    painter.with_clip_rect(right).image(
+                        texture_id,
+                        rect_b,
+                        FULL_UV,
+                        egui::Color32::WHITE,
+                    );
+                }
+                painter.vline(
+                    view_rect.center().x,
+                    view_rect.y_range(),
+                    egui::Stroke::new(1.0, egui::Color32::GRAY),
+                );
+                (rect_a, rect_b)
+            }
+            CompareMode::Wipe => {
+                let split_x = view_rect.min.x + self.wipe * view_rect.width();
+                if response.drag_started() {
+                    self.dragging_wipe = response
+                        .interact_pointer_pos()
+                        .is_some_and(|pos| (pos.x - split_x).abs() < WIPE_GRAB_DISTANCE);
+                }
+                if !response.dragged() {
+                    self.dragging_wipe = false;
+                }
+                if self.dragging_wipe {
+                    if let Some(pos) = response.interact_pointer_pos() {
+                        self.wipe = ((pos.x - view_rect.min.x) / view_rect.width()).clamp(0.0, 1.0);
+                    }
+                } else {
+                    handle_zoom_input(ui, &response, &mut a.view_zoom, view_rect, size_a);
+                }
+                let split_x = view_rect.min.x + self.wipe * view_rect.width();
+                let rect_a = a.view_zoom.get_image_rect(view_rect, size_a);
+                let rect_b = a.view_zoom.get_image_rect(view_rect, size_b);
+                let mut clip_a = view_rect;
+                clip_a.max.x = split_x;
+                let mut clip_b = view_rect;
+                clip_b.min.x = split_x;
+                if let Some(texture_id) = texture_a {
+                    painter.with_clip_rect(clip_a).image(
+                        texture_id,
+                        rect_a,
+                        FULL_UV,
+                        egui::Color32::WHITE,
+                    );
+                }
+                if let Some(texture_id) = texture_b {
+                    painter.with_clip_rect(clip_b).image(
+                        texture_id,
+                        rect_b,
+                        FULL_UV,
+                        egui::Color32::WHITE,
+                    );
+                }
+                painter.vline(
+                    split_x,
+                    view_rect.y_range(),
+                    egui::Stroke::new(2.0, egui::Color32::WHITE),
+                );
+                let near_split = response
+                    .hover_pos()
+                    .is_some_and(|pos| (pos.x - split_x).abs() < WIPE_GRAB_DISTANCE);
+                if near_split || self.dragging_wipe {
+                    ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+                }
+                (rect_a, rect_b)
+            }
+            _ => {
+                handle_zoom_input(ui, &response, &mut a.view_zoom, view_rect, size_a);
+                let rect_a = a.view_zoom.get_image_rect(view_rect, size_a);
+                match self.difference.as_ref().map(|d| &d.result) {
+                    Some(Ok((texture_id, max))) => {
+                        painter.image(*texture_id, rect_a, FULL_UV, egui::Color32::WHITE);
+                        show_label(
+                            &painter,
+                            view_rect.right_bottom() - egui::vec2(8.0, 8.0),
+                            egui::Align2::RIGHT_BOTTOM,
+                            &format!("0 .. {:.3e}", max),
+                        );
+                    }
+                    Some(Err(e)) => {
+                        show_label(&painter, view_rect.center(), egui::Align2::CENTER_CENTER, e);
+                    }
+                    None => {
+                        show_label(
+                            &painter,
+                            view_rect.center(),
+                            egui::Align2::CENTER_CENTER,
+                            "The difference is shown once both renders have finished",
+                        );
+                    }
+                }
+                (rect_a, rect_a)
+            }
+        };
+        painter.rect_stroke(
+            rect_a,
+            0.0,
+            egui::Stroke::new(1.0, egui::Color32::WHITE),
+            egui::StrokeKind::Outside,
+        );
+        if rect_b != rect_a {
+            painter.rect_stroke(
+                rect_b,
+                0.0,
+                egui::Stroke::new(1.0, egui::Color32::WHITE),
+                egui::StrokeKind::Outside,
+            );
+        }
+        show_label(
+            &painter,
+            view_rect.left_top() + egui::vec2(8.0, 8.0),
+            egui::Align2::LEFT_TOP,
+            &format!("A: {}", a.get_name()),
+        );
+        if !self.mode.is_difference() {
+            show_label(
+                &painter,
+                view_rect.right_top() + egui::vec2(-8.0, 8.0),
+                egui::Align2::RIGHT_TOP,
+                &format!("B: {}", b.get_name()),
+            );
+        }
+
+        if response.dragged() {
+            return;
+        }
+        let Some(pos) = response.hover_pos() else {
+            return;
+        };
+        let pixel = if rect_b.contains(pos) && !rect_a.contains(pos) {
+            get_pixel_at(rect_b, size_b, pos)
+        } else {
+            get_pixel_at(rect_a, size_a, pos)
+        };
+        if let Some((x, y)) = pixel {
+            let image_a = image_a.lock().unwrap();
+            let image_b = image_b.lock().unwrap();
+            if x >= image_a.width || y >= image_a.height {
+                return;
+            }
+            let difference = match (self.mode.is_difference(), self.difference.as_ref()) {
+                (true, Some(difference)) => difference.values.get(y * image_a.width + x).copied(),
+                _ => None,
+            };
+            response.on_hover_ui_at_pointer(|ui| {
+                show_compare_probe(ui, &image_a, &image_b, x, y, difference)
+            });
+        }
+    }
+}
@@ -0,0 +1,635 @@
+use crate::model::base::Matrix4x4;
+use crate::model::base::Quaternion;
+use crate::model::base::Vector3;
+use crate::model::scene::Node;
+use crate::model::scene::TransformComponent;
+use crate::render::transform_gizmo::GizmoHandle;
+use crate::render::transform_gizmo::GizmoMode;
+use crate::render::transform_gizmo::GizmoSpace;
+use crate::render::transform_gizmo::TransformGizmo;
+use crate::render::viewport_settings::GizmoSettings;
+
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use eframe::egui;
+
+const GIZMO_PIXELS: f32 = 100.0; // Screen length of the arrows
+const PICK_PIXELS: f32 = 8.0; // Hit-test tolerance
+const ROTATE_SPEED: f32 = 0.01; // Radians per pixel when a ring is edge-on
+const EDGE_ON_COSINE: f32 = 0.1;
+
+/// Maps between world points and screen positions of a view.
+pub struct ViewProjection {
+    world_to_clip: Matrix4x4,
+    clip_to_world: Matrix4x4,
+    world_to_camera: Matrix4x4,
+    tan_half_fov: f32,
+    rect: egui::Rect,
+}
+
+impl ViewProjection {
+    pub fn new(w2c: &Matrix4x4, c2c: &Matrix4x4, rect: egui::Rect) -> Self {
+        let world_to_clip = *c2c * *w2c;
+        let clip_to_world = world_to_clip.inverse().unwrap_or(Matrix4x4::identity());
+        Self {
+            world_to_clip,
+            clip_to_world,
+            world_to_camera: *w2c,
+            tan_half_fov: 1.0 / c2c.m[5],
+            rect,
+        }
+    }
+
+    pub fn project(&self, p: &Vector3) -> Option<[f32; 2]> {
+        let m = &self.world_to_clip.m;
+        let w = m[12] * p.x + m[13] * p.y + m[14] * p.z + m[15];
+        if w <= 1e-5 {
+            return None; // Behind the camera
+        }
+        let q = self.world_to_clip.transform_point(p);
+        let x = self.rect.min.x + (q.x + 1.0) * 0.5 * self.rect.width();
+        let y = self.rect.min.y + (1.0 - q.y) * 0.5 * self.rect.height();
+        return Some([x, y]);
+    }
+
+    /// World ray through a screen position.
+    pub fn get_ray(&self, position: egui::Pos2) -> (Vector3, Vector3) {
+        let x = 2.0 * (position.x - self.rect.min.x) / self.rect.width() - 1.0;
+        let y = 1.0 - 2.0 * (position.y - self.rect.min.y) / self.rect.height();
+        let near = self
+            .clip_to_world
+            .transform_point(&Vector3::new(x, y, -1.0));
+        let far = self.clip_to_world.transform_point(&Vector3::new(x, y, 1.0));
+        return (near, (far - near).normalize());
+    }
+
+    /// World length of a pixel at the depth of a point.
+    pub fn get_pixel_size(&self, p: &Vector3) -> f32 {
+        let depth = self.world_to_camera.transform_point(p).z.abs();
+        return 2.0 * depth * self.tan_half_fov / self.rect.height();
+    }
+}
+
+pub fn snap_value(value: f32, step: f32) -> f32 {
+    if step > 0.0 {
+        return (value / step).round() * step;
+    }
+    return value;
+}
+
+/// Parameter of the point on the axis closest to the ray, None when they are parallel.
+pub fn get_closest_axis_param(
+    ray_origin: &Vector3,
+    ray_dir: &Vector3,
+    axis_origin: &Vector3,
+    axis_dir: &Vector3,
+) -> Option<f32> {
+    let w0 = *ray_origin - *axis_origin;
+    let a = Vector3::dot(ray_dir, ray_dir);
+    let b = Vector3::dot(ray_dir, axis_dir);
+    let c = Vector3::dot(axis_dir, axis_dir);
+    let d = Vector3::dot(ray_dir, &w0);
+    let e = Vector3::dot(axis_dir, &w0);
+    let denom = a * c - b * b;
+    if denom.abs() < 1e-6 {
+        return None;
+    }
+    return Some((a * e - b * d) / denom);
+}
+
+pub fn intersect_plane(
+    ray_origin: &Vector3,
+    ray_dir: &Vector3,
+    plane_origin: &Vector3,
+    plane_normal: &Vector3,
+) -> Option<Vector3> {
+    let denom = Vector3::dot(ray_dir, plane_normal);
+    if denom.abs() < 1e-6 {
+        return None;
+    }
+    let t = Vector3::dot(&(*plane_origin - *ray_origin), plane_normal) / denom;
+    if t < 0.0 {
+        return None;
+    }
+    return Some(*ray_origin + *ray_dir * t);
+}
+
+/// Right-handed angle around `axis` turning `v0` to `v1`, in radians.
+pub fn get_signed_angle(v0: &Vector3, v1: &Vector3, axis: &Vector3) -> f32 {
+    let s = Vector3::dot(axis, &Vector3::cross(v0, v1));
+    let c = Vector3::dot(v0, v1);
+    return f32::atan2(s, c);
+}
+
+/// Local-to-world matrix of a node, through the transforms of its parents.
+pub fn get_world_matrix(node: &Arc<RwLock<Node>>) -> Matrix4x4 {
+    let node = node.read().unwrap();
+    let local = node
+        .get_component::<TransformComponent>()
+        .map(|t| t.get_local_matrix())
+        .unwrap_or(Matrix4x4::identity());
+    match node.parent.as_ref().and_then(|p| p.upgrade()) {
+        Some(parent) => get_world_matrix(&parent) * local,
+        None => local,
+    }
+}
+
+/// World matrix of the parent of a node, `None` at the root.
+pub fn get_parent_matrix(node: &Arc<RwLock<Node>>) -> Option<Matrix4x4> {
+    let parent = node.read().unwrap().parent.as_ref()?.upgrade()?;
+    return Some(get_world_matrix(&parent));
+}
+
+fn get_rotation_part(m: &Matrix4x4) -> Matrix4x4 {
+    let mut r = Matrix4x4::identity();
+    for j in 0..3 {
+        let c = Vector3::new(m.m[j], m.m[4 + j], m.m[8 + j]).normalize();
+        r.m[j] = c.x;
+        r.m[4 + j] = c.y;
+        r.m[8 + j] = c.z;
+    }
+    return r;
+}
+
+#[derive(Debug, Clone)]
+struct GizmoDrag {
+    node: Arc<RwLock<Node>>,
+    handle: GizmoHandle,
+    gizmo: TransformGizmo, // As placed when the drag started
+    start_trs: (Vector3, Quaternion, Vector3),
+    parent_to_world: Matrix4x4,
+    start_pointer: egui::Pos2,
+    start_param: f32, // Axis parameter, or screen distance of uniform scale
+    start_point: Option<Vector3>, // Plane hit of plane handles and rings
+    text: String,     // Typed value
+    value: f32,       // Applied value, for the overlay
+}
+
+/// Hit-testing and dragging of the transform gizmo of the selected node.
+pub struct GizmoController {
+    pub mode: GizmoMode,
+    pub space: GizmoSpace,
+    hovered: Option<GizmoHandle>,
+    drag: Option<GizmoDrag>,
+}
+
+impl GizmoController {
+    pub fn new() -> Self {
+        Self {
+            mode: GizmoMode::Translate,
+            space: GizmoSpace::World,
+            hovered: None,
+            drag: None,
+        }
+    }
+
+    /// Gizmo of a node; the root node has none.
+    pub fn get_gizmo(
+        &self,
+        selected: Option<&Arc<RwLock<Node>>>,
+        view: &ViewProjection,
+    ) -> Option<TransformGizmo> {
+        if let Some(drag) = self.drag.as_ref() {
+            return self.create_gizmo(&drag.node, view);
+        }
+        return self.create_gizmo(selected?, view);
+    }
+
+    fn create_gizmo(
+        &self,
+        node: &Arc<RwLock<Node>>,
+        view: &ViewProjection,
+    ) -> Option<TransformGizmo> {
+        get_parent_matrix(node)?;
+        if node
+            .read()
+            .unwrap()
+            .get_component::<TransformComponent>()
+            .is_none()
+        {
+            return None;
+        }
+        let world = get_world_matrix(node);
+        let origin = world.transform_point(&Vector3::zero());
+        // Scaling only makes sense along the local axes
+        let orientation = if self.space == GizmoSpace::Local || self.mode == GizmoMode::Scale {
+            get_rotation_part(&world)
+        } else {
+            Matrix4x4::identity()
+        };
+        let highlighted = self.drag.as_ref().map(|d| d.handle).or(self.hovered);
+        return Some(TransformGizmo {
+            mode: self.mode,
+            origin,
+            orientation,
+            size: view.get_pixel_size(&origin) * GIZMO_PIXELS,
+            highlighted,
+        });
+    }
+
+    /// Handles the pointer and keys, returns whether the gizmo took the pointer.
+    pub fn update(
+        &mut self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        selected: Option<&Arc<RwLock<Node>>>,
+        view: &ViewProjection,
+        settings: &GizmoSettings,
+    ) -> bool {
+        if self.drag.is_some() {
+            self.update_drag(ui, response, view, settings);
+            return true;
+        }
+        self.hovered = None;
+        let Some(gizmo) = self.get_gizmo(selected, view) else {
+            return false;
+        };
+        let project = |p: &Vector3| view.project(p);
+        if response.hovered() && !response.dragged() {
+            let (hover, keys) = ui.input(|i| {
+                let keys = [
+                    (egui::Key::W, GizmoMode::Translate),
+                    (egui::Key::E, GizmoMode::Rotate),
+                    (egui::Key::R, GizmoMode::Scale),
+                ]
+                .into_iter()
+                .find(|(key, _)| i.key_pressed(*key))
+                .map(|(_, mode)| mode);
+                (i.pointer.hover_pos(), keys)
+            });
+            if let Some(mode) = keys {
+                self.mode = mode;
+            }
+            if let Some(pos) = hover {
+                self.hovered = gizmo.hit_test([pos.x, pos.y], PICK_PIXELS, &project);
+            }
+        }
+        let shift = ui.input(|i| i.modifiers.shift);
+        if response.drag_started_by(egui::PointerButton::Primary) && !shift {
+            let press = ui.input(|i| i.pointer.press_origin());
+            let Some(press) = press else {
+                return false;
+            };
+            let Some(handle) = gizmo.hit_test([press.x, press.y], PICK_PIXELS, &project) else {
+                return false;
+            };
+            let node = if let Some(selected) = selected {
+                selected.clone()
+            } else {
+                return false;
+            };
+            self.begin_drag(&node, handle, &gizmo, press, view);
+            self.update_drag(ui, response, view, settings);
+            return true;
+        }
+        return false;
+    }
+
+    fn begin_drag(
+        &mut self,
+        node: &Arc<RwLock<Node>>,
+        handle: GizmoHandle,
+        gizmo: &TransformGizmo,
+        press: egui::Pos2,
+        view: &ViewProjection,
+    ) {
+        let Some(parent_to_world) = get_parent_matrix(node) else {
+            return;
+        };
+        let start_trs = {
+            let node = node.read().unwrap();
+            let Some(t) = node.get_component::<TransformComponent>() else {
+                return;
+            };
+            t.get_local_trs()
+        };
+        let (ray_origin, ray_dir) = view.get_ray(press);
+        let mut start_param = 0.0;
+        let mut start_point = None;
+        match (gizmo.mode, handle.get_axis_index()) {
+            (GizmoMode::Translate | GizmoMode::Scale, Some(i)) if !handle.is_plane() => {
+                let axis = gizmo.get_axis(i);
+                start_param = get_closest_axis_param(&ray_origin, &ray_dir, &gizmo.origin, &axis)
+                    .unwrap_or(0.0);
+            }
+            (GizmoMode::Translate, Some(i)) => {
+                let normal = gizmo.get_axis(i);
+                start_point = intersect_plane(&ray_origin, &ray_dir, &gizmo.origin, &normal);
+            }
+            (GizmoMode::Rotate, Some(i)) => {
+                let normal = gizmo.get_axis(i);
+                if Vector3::dot(&ray_dir, &normal).abs() > EDGE_ON_COSINE {
+                    start_point = intersect_plane(&ray_origin, &ray_dir, &gizmo.origin, &normal);
+                }
+            }
+            _ => {
+                if let Some(center) = view.project(&gizmo.origin) {
+                    start_param = (press - egui::pos2(center[0], center[1])).length();
+                }
+            }
+        }
+        self.drag = Some(GizmoDrag {
+            node: node.clone(),
+            handle,
+            gizmo: gizmo.clone(),
+            start_trs,
+            parent_to_world,
+            start_pointer: press,
+            start_param,
+            start_point,
+            text: String::new(),
+            value: 0.0,
+        });
+    }
+
+    fn update_drag(
+        &mut self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        view: &ViewProjection,
+        settings: &GizmoSettings,
+    ) {
+        let Some(drag) = self.drag.as_mut() else {
+            return;
+        };
+        let (text, backspace, enter, escape, ctrl, pointer) = ui.input(|i| {
+            let text = i
+                .events
+                .iter()
+                .filter_map(|e| match e {
+                    egui::Event::Text(t) => Some(t.clone()),
+                    _ => None,
+                })
+                .collect::<String>();
+            (
+                text,
+                i.key_pressed(egui::Key::Backspace),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Escape),
+                i.modifiers.ctrl,
+                i.pointer.latest_pos(),
+            )
+        });
+        if escape {
+            let (position, rotation, scale) = drag.start_trs;
+            set_local_trs(&drag.node, position, rotation, scale);
+            self.drag = None;
+            return;
+        }
+        // Numeric entry while dragging, for handles moving along a single value
+        let is_plane_move =
+            matches!(drag.gizmo.mode, GizmoMode::Translate) && drag.handle.is_plane();
+        if !is_plane_move {
+            drag.text.extend(
+                text.chars()
+                    .filter(|c| c.is_ascii_digit() || *c == '.' || *c == '-'),
+            );
+        }
+        if backspace {
+            drag.text.pop();
+        }
+        let snap = settings.snap != ctrl;
+        if let Some(pointer) = pointer {
+            apply_drag(drag, pointer, view, settings, snap);
+        }
+        if enter || response.drag_stopped() || !response.dragged() {
+            self.drag = None;
+        }
+    }
+
+    /// Shows the dragged value, or the typed one, next to the pointer.
+    pub fn show_overlay(&self, ui: &egui::Ui) {
+        let Some(drag) = self.drag.as_ref() else {
+            return;
+        };
+        let Some(pointer) = ui.input(|i| i.pointer.latest_pos()) else {
+            return;
+        };
+        let value = if drag.text.is_empty() {
+            match drag.gizmo.mode {
+                GizmoMode::Translate => format!("{:.3}", drag.value),
+                GizmoMode::Rotate => format!("{:.1}°", drag.value),
+                GizmoMode::Scale => format!("×{:.3}", drag.value),
+            }
+        } else {
+            format!("{}_", drag.text)
+        };
+        ui.painter().text(
+            pointer + egui::vec2(16.0, 16.0),
+            egui::Align2::LEFT_TOP,
+            format!("{} {}", drag.gizmo.mode.get_label(), value),
+            egui::FontId::monospace(14.0),
+            egui::Color32::YELLOW,
+        );
+    }
+}
+
+fn set_local_trs(
+    node: &Arc<RwLock<Node>>,
+    position: Vector3,
+    rotation: Quaternion,
+    scale: Vector3,
+) {
+    let mut node = node.write().unwrap();
+    if let Some(t) = node.get_component_mut::<TransformComponent>() {
+        t.set_local_trs(position, rotation, scale);
+    }
+}
+
+fn apply_drag(
+    drag: &mut GizmoDrag,
+    pointer: egui::Pos2,
+    view: &ViewProjection,
+    settings: &GizmoSettings,
+    snap: bool,
+) {
+    let typed = drag.text.parse::<f32>().ok();
+    let (position, rotation, scale) = drag.start_trs;
+    let gizmo = &drag.gizmo;
+    let handle = drag.handle;
+    let (ray_origin, ray_dir) = view.get_ray(pointer);
+    let world_to_parent = drag
+        .parent_to_world
+        .inverse()
+        .unwrap_or(Matrix4x4::identity());
+    match gizmo.mode {
+        GizmoMode::Translate => {
+            let step = settings.translate_step;
+            let mut delta = Vector3::zero();
+            let Some(n) = handle.get_axis_index() else {
+                return;
+            };
+            if handle.is_plane() {
+                let normal = gizmo.get_axis(n);
+                let hit = intersect_plane(&ray_origin, &ray_dir, &gizmo.origin, &normal);
+                let (Some(start), Some(hit)) = (drag.start_point, hit) else {
+                    return;
+                };
+                let d = hit - start;
+                for i in [(n + 1) % 3, (n + 2) % 3] {
+                    let axis = gizmo.get_axis(i);
+                    // no typed value here, it would move both axes by the same amount
+                    let mut s = Vector3::dot(&d, &axis);
+                    if snap {
+                        s = snap_value(s, step);
+                    }
+                    delta += axis * s;
+                    drag.value = s;
+                }
+            } else {
+                let axis = gizmo.get_axis(n);
+                let Some(t) = get_closest_axis_param(&ray_origin, &ray_dir, &gizmo.origin, &axis)
+                else {
+                    return;
+                };
+                let mut s = t - drag.start_param;
+                if let Some(v) = typed {
+                    s = v;
+                } else if snap {
+                    s = snap_value(s, step);
+                }
+                delta = axis * s;
+                drag.value = s;
+            }
+            let delta = world_to_parent.transform_vector(&delta);
+            set_local_trs(&drag.node, position + delta, rotation, scale);
+        }
+        GizmoMode::Rotate => {
+            let Some(n) = handle.get_axis_index() else {
+                return;
+            };
+            let axis = gizmo.get_axis(n);
+            let hit = intersect_plane(&ray_origin, &ray_dir, &gizmo.origin, &axis);
+            let angle = match (drag.start_point, hit) {
+                (Some(p0), Some(p1)) => {
+                    get_signed_angle(&(p0 - gizmo.origin), &(p1 - gizmo.origin), &axis)
+                }
+                // Turn with the horizontal motion when the ring is edge-on
+                _ => (pointer.x - drag.start_pointer.x) * ROTATE_SPEED,
+            };
+            let mut degrees = angle.to_degrees();
+            if let Some(v) = typed {
+                degrees = v;
+            } else if snap {
+                degrees = snap_value(degrees, settings.rotate_step);
+            }
+            drag.value = degrees;
+            // Rotate in world space around the gizmo axis, then bring it to the parent space
+            let rotate = Matrix4x4::rotate(degrees, axis.x, axis.y, axis.z);
+            let mut m = world_to_parent * rotate * drag.parent_to_world;
+            m.m[3] = 0.0;
+            m.m[7] = 0.0;
+            m.m[11] = 0.0;
+            let m = m * rotation.to_matrix();
+            if let Some((_, rotation, _)) = m.decompose(1e-6) {
+                set_local_trs(&drag.node, position, rotation, scale);
+            }
+        }
+        GizmoMode::Scale => {
+            let ratio = if let Some(i) = handle.get_axis_index() {
+                let axis = gizmo.get_axis(i);
+                let t = get_closest_axis_param(&ray_origin, &ray_dir, &gizmo.origin, &axis);
+                match t {
+                    Some(t) if drag.start_param.abs() > 1e-6 => t / drag.start_param,
+                    _ => return,
+                }
+            } else {
+                let Some(center) = view.project(&gizmo.origin) else {
+                    return;
+                };
+                let d = (pointer - egui::pos2(center[0], center[1])).length();
+                if drag.start_param > 1e-6 {
+                    d / drag.start_param
+                } else {
+                    1.0
+                }
+            };
+            let step = settings.scale_step;
+            let ratio = if let Some(v) = typed {
+                v
+            } else if snap {
+                snap_value(ratio, step).max(step)
+            } else {
+                ratio
+            };
+            // Keep the scale invertible
+            let ratio = ratio.max(1e-3);
+            drag.value = ratio;
+            let mut scale = scale;
+            match handle {
+                GizmoHandle::X => scale.x *= ratio,
+                GizmoHandle::Y => scale.y *= ratio,
+                GizmoHandle::Z => scale.z *= ratio,
+                _ => scale = scale * ratio,
+            }
+            set_local_trs(&drag.node, position, rotation, scale);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snap_value() {
+        assert_eq!(snap_value(1.4, 1.0), 1.0);
+        assert_eq!(snap_value(-22.0, 15.0), -15.0);
+        assert!((snap_value(0.26, 0.1) - 0.3).abs() < 1e-6);
+        assert_eq!(snap_value(0.26, 0.0), 0.26);
+    }
+
+    #[test]
+    fn test_closest_axis_param() {
+        // A ray going down -z through (3, 1, 5) passes over x = 3 of the x axis
+        let t = get_closest_axis_param(
+            &Vector3::new(3.0, 1.0, 5.0),
+            &Vector3::new(0.0, 0.0, -1.0),
+            &Vector3::zero(),
+            &Vector3::new(1.0, 0.0, 0.0),
+        );
+        assert!((t.unwrap() - 3.0).abs() < 1e-5);
+        // Parallel to the axis
+        let t = get_closest_axis_param(
+            &Vector3::new(0.0, 1.0, 0.0),
+            &Vector3::new(1.0, 0.0, 0.0),
+            &Vector3::zero(),
+            &Vector3::new(1.0, 0.0, 0.0),
+        );
+        assert!(t.is_none());
+    }
+
+    #[test]
+    fn test_intersect_plane() {
+        let hit = intersect_plane(
+            &Vector3::new(1.0, 2.0, 5.0),
+            &Vector3::new(0.0, 0.0, -1.0),
+            &Vector3::zero(),
+            &Vector3::new(0.0, 0.0, 1.0),
+        )
+        .unwrap();
+        assert!((hit - Vector3::new(1.0, 2.0, 0.0)).length() < 1e-5);
+        // The plane is behind the ray
+        let hit = intersect_plane(
+            &Vector3::new(0.0, 0.0, 5.0),
+            &Vector3::new(0.0, 0.0, 1.0),
+            &Vector3::zero(),
+            &Vector3::new(0.0, 0.0, 1.0),
+        );
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn test_signed_angle() {
+        let x = Vector3::new(1.0, 0.0, 0.0);
+        let y = Vector3::new(0.0, 1.0, 0.0);
+        let z = Vector3::new(0.0, 0.0, 1.0);
+        let angle = get_signed_angle(&x, &y, &z);
+        assert!((angle - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+        // Matches the direction of Matrix4x4::rotate
+        let r = Matrix4x4::rotate(angle.to_degrees(), z.x, z.y, z.z);
+        assert!((r.transform_vector(&x) - y).length() < 1e-5);
+        assert!((get_signed_angle(&y, &x, &z) + std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+    }
+}
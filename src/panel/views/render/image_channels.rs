@@ -0,0 +1,243 @@
+use super::image_data::ImageData;
+
+/// Channels shown together, "Albedo" for "Albedo.R", "Albedo.G" and "Albedo.B".
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelLayer {
+    pub name: String,
+    pub channels: Vec<usize>,
+}
+
+impl ChannelLayer {
+    /// Component names, "R" for "Albedo.R".
+    pub fn get_component_names(&self, channel_names: &[String]) -> Vec<String> {
+        self.channels
+            .iter()
+            .map(|i| {
+                let name = &channel_names[*i];
+                match name.rfind('.') {
+                    Some(dot) => name[dot + 1..].to_string(),
+                    None => name.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChannelMode {
+    #[default]
+    Color,
+    FalseColor,
+    Depth,
+    Normal,
+}
+
+impl ChannelMode {
+    pub fn all() -> &'static [ChannelMode] {
+        &[
+            ChannelMode::Color,
+            ChannelMode::FalseColor,
+            ChannelMode::Depth,
+            ChannelMode::Normal,
+        ]
+    }
+
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            ChannelMode::Color => "Color",
+            ChannelMode::FalseColor => "False Color",
+            ChannelMode::Depth => "Depth",
+            ChannelMode::Normal => "Normal",
+        }
+    }
+
+    /// Mode that suits the usual pbrt AOV names.
+    pub fn from_layer_name(name: &str) -> ChannelMode {
+        match name {
+            "N" | "Ns" | "Normal" => ChannelMode::Normal,
+            "Z" | "Depth" => ChannelMode::Depth,
+            "Variance" | "RelativeVariance" => ChannelMode::FalseColor,
+            _ => ChannelMode::Color,
+        }
+    }
+}
+
+/// Groups channels by the part of the name before the last '.'; R, G, B and A form "RGBA".
+pub fn get_channel_layers(channel_names: &[String]) -> Vec<ChannelLayer> {
+    let mut layers: Vec<ChannelLayer> = Vec::new();
+    for (i, name) in channel_names.iter().enumerate() {
+        let layer_name = match name.rfind('.') {
+            Some(dot) => name[..dot].to_string(),
+            None if ["R", "G", "B", "A"].contains(&name.as_str()) => "RGBA".to_string(),
+            None => name.clone(),
+        };
+        match layers.iter_mut().find(|layer| layer.name == layer_name) {
+            Some(layer) => layer.channels.push(i),
+            None => layers.push(ChannelLayer {
+                name: layer_name,
+                channels: vec![i],
+            }),
+        }
+    }
+    if let Some(layer) = layers.iter_mut().find(|layer| layer.name == "RGBA") {
+        layer
+            .channels
+            .sort_by_key(|i| "RGBA".find(channel_names[*i].as_str()));
+    }
+    return layers;
+}
+
+pub fn get_luminance(rgb: [f32; 3]) -> f32 {
+    0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+}
+
+/// Turbo colour map, polynomial fit by Google.
+pub fn get_false_color(t: f32) -> [f32; 3] {
+    let t = if t.is_finite() {
+        t.clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let t4 = t3 * t;
+    let t5 = t4 * t;
+    let r = 0.13572138 + 4.61539260 * t - 42.66032258 * t2 + 132.13108234 * t3 - 152.94239396 * t4
+        + 59.28637943 * t5;
+    let g = 0.09140261 + 2.19418839 * t + 4.84296658 * t2 - 14.18503333 * t3
+        + 4.27729857 * t4
+        + 2.82956604 * t5;
+    let b = 0.10667330 + 12.64194608 * t - 60.58204836 * t2 + 110.36276771 * t3 - 89.90310912 * t4
+        + 27.34824973 * t5;
+    [r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0)]
+}
+
+/// Maps the channels of one layer to colours of the render view.
+#[derive(Debug, Clone)]
+pub struct ChannelMapping {
+    pub channels: Vec<usize>,
+    pub isolate: Option<usize>,
+    pub mode: ChannelMode,
+    pub range: (f32, f32), // Scalar range of the false colour and depth modes
+}
+
+impl ChannelMapping {
+    pub fn new(
+        image: &ImageData,
+        layer: &ChannelLayer,
+        isolate: Option<usize>,
+        mode: ChannelMode,
+    ) -> Self {
+        let mut mapping = Self {
+            channels: layer.channels.clone(),
+            isolate: isolate.filter(|i| *i < layer.channels.len()),
+            mode,
+            range: (0.0, 1.0),
+        };
+        if matches!(mode, ChannelMode::FalseColor | ChannelMode::Depth) {
+            let n_channels = image.channel_names.len().max(1);
+            let mut range = (f32::MAX, f32::MIN);
+            for pixel in image.data.chunks_exact(n_channels) {
+                let v = mapping.get_scalar(pixel);
+                if v.is_finite() {
+                    range = (range.0.min(v), range.1.max(v));
+                }
+            }
+            if range.0 <= range.1 {
+                mapping.range = range;
+            }
+        }
+        return mapping;
+    }
+
+    fn get_rgb(&self, pixel: &[f32]) -> [f32; 3] {
+        if let Some(i) = self.isolate {
+            let v = pixel[self.channels[i]];
+            return [v, v, v];
+        }
+        let get = |i: usize| pixel[self.channels[i.min(self.channels.len() - 1)]];
+        if self.channels.len() < 3 {
+            let v = get(0);
+            return [v, v, v];
+        }
+        [get(0), get(1), get(2)]
+    }
+
+    fn get_scalar(&self, pixel: &[f32]) -> f32 {
+        if self.isolate.is_some() || self.channels.len() < 3 {
+            return self.get_rgb(pixel)[0];
+        }
+        get_luminance(self.get_rgb(pixel))
+    }
+
+    fn normalize(&self, v: f32) -> f32 {
+        let (min, max) = self.range;
+        if max > min {
+            (v - min) / (max - min)
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the colour and whether it is linear (to be tone mapped) or already for display.
+    pub fn map(&self, pixel: &[f32]) -> ([f32; 3], bool) {
+        match self.mode {
+            ChannelMode::Color => (self.get_rgb(pixel), true),
+            ChannelMode::FalseColor => (
+                get_false_color(self.normalize(self.get_scalar(pixel))),
+                false,
+            ),
+            ChannelMode::Depth => {
+                // Near is bright
+                let v = 1.0 - self.normalize(self.get_scalar(pixel)).clamp(0.0, 1.0);
+                ([v, v, v], false)
+            }
+            ChannelMode::Normal => {
+                let n = self.get_rgb(pixel);
+                let n = n.map(|v| (v * 0.5 + 0.5).clamp(0.0, 1.0));
+                (n, false)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_channel_layers() {
+        let channel_names = names(&[
+            "R", "G", "B", "A", "Albedo.R", "Albedo.G", "Albedo.B", "N.X", "N.Y", "N.Z", "u",
+        ]);
+        let layers = get_channel_layers(&channel_names);
+        let layer_names = layers.iter().map(|l| l.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(layer_names, ["RGBA", "Albedo", "N", "u"]);
+        assert_eq!(layers[1].channels, [4, 5, 6]);
+        assert_eq!(
+            layers[2].get_component_names(&channel_names),
+            ["X", "Y", "Z"]
+        );
+        assert_eq!(ChannelMode::from_layer_name("N"), ChannelMode::Normal);
+    }
+
+    #[test]
+    fn test_channel_mapping() {
+        let channel_names = names(&["B", "G", "R", "Z"]);
+        let mut image = ImageData::new("test".to_string(), 2, 1, &channel_names);
+        image.data = vec![1.0, 0.25, 0.5, 2.0, 1.0, 0.0, -1.0, 4.0];
+        let layers = get_channel_layers(&channel_names);
+        let rgb = ChannelMapping::new(&image, &layers[0], Some(1), ChannelMode::Color);
+        assert_eq!(rgb.map(&image.data[0..4]), ([0.25, 0.25, 0.25], true));
+        let depth = ChannelMapping::new(&image, &layers[1], None, ChannelMode::Depth);
+        assert_eq!(depth.range, (2.0, 4.0));
+        assert_eq!(depth.map(&image.data[0..4]).0, [1.0, 1.0, 1.0]);
+        assert_eq!(depth.map(&image.data[4..8]).0, [0.0, 0.0, 0.0]);
+        let normal = ChannelMapping::new(&image, &layers[0], None, ChannelMode::Normal);
+        assert_eq!(normal.map(&image.data[4..8]).0, [0.0, 0.5, 1.0]);
+    }
+}
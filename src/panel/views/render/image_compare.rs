@@ -0,0 +1,245 @@
+use super::image_channels::get_channel_layers;
+use super::image_data::ImageData;
+use crate::conversion::color_space::gamma_correct;
+use crate::conversion::color_space::inverse_gamma_correct;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompareMode {
+    #[default]
+    Wipe,
+    SideBySide,
+    AbsoluteDifference,
+    RelativeDifference,
+    SquaredDifference,
+}
+
+impl CompareMode {
+    pub fn all() -> &'static [CompareMode] {
+        &[
+            CompareMode::Wipe,
+            CompareMode::SideBySide,
+            CompareMode::AbsoluteDifference,
+            CompareMode::RelativeDifference,
+            CompareMode::SquaredDifference,
+        ]
+    }
+
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            CompareMode::Wipe => "Wipe",
+            CompareMode::SideBySide => "Side by Side",
+            CompareMode::AbsoluteDifference => "|A-B|",
+            CompareMode::RelativeDifference => "|A-B|/B",
+            CompareMode::SquaredDifference => "(A-B)²",
+        }
+    }
+
+    pub fn is_difference(&self) -> bool {
+        matches!(
+            self,
+            CompareMode::AbsoluteDifference
+                | CompareMode::RelativeDifference
+                | CompareMode::SquaredDifference
+        )
+    }
+}
+
+/// Error of image A against the reference B.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ImageMetrics {
+    pub mse: f32,
+    pub rel_mse: f32,
+    pub psnr: f32, // dB, for a peak of 1.0
+    pub flip: f32, // Mean perceptual error in [0, 1]
+}
+
+// Keeps the relative error finite on black pixels
+const REL_EPSILON: f32 = 0.01;
+
+/// Linear RGB of each pixel; a single channel image is grey.
+pub fn get_rgb_pixels(image: &ImageData) -> Vec<[f32; 3]> {
+    let n_channels = image.channel_names.len().max(1);
+    let layers = get_channel_layers(&image.channel_names);
+    let channels = match layers.first() {
+        Some(layer) if layer.channels.len() >= 3 => {
+            [layer.channels[0], layer.channels[1], layer.channels[2]]
+        }
+        Some(layer) => [layer.channels[0]; 3],
+        None => return vec![[0.0; 3]; image.width * image.height],
+    };
+    image
+        .data
+        .chunks_exact(n_channels)
+        .take(image.width * image.height)
+        .map(|pixel| channels.map(|c| pixel[c]))
+        .collect()
+}
+
+fn check_sizes(a: &ImageData, b: &ImageData) -> Result<(), String> {
+    if a.width != b.width || a.height != b.height {
+        return Err(format!(
+            "Sizes differ: {}x{} and {}x{}",
+            a.width, a.height, b.width, b.height
+        ));
+    }
+    Ok(())
+}
+
+/// Per pixel difference of the mode, averaged over RGB.
+pub fn get_difference(a: &ImageData, b: &ImageData, mode: CompareMode) -> Result<Vec<f32>, String> {
+    check_sizes(a, b)?;
+    let pa = get_rgb_pixels(a);
+    let pb = get_rgb_pixels(b);
+    let difference = pa
+        .iter()
+        .zip(pb.iter())
+        .map(|(a, b)| {
+            let mut sum = 0.0;
+            for c in 0..3 {
+                let d = a[c] - b[c];
+                sum += match mode {
+                    CompareMode::RelativeDifference => d.abs() / (b[c].abs() + REL_EPSILON),
+                    CompareMode::SquaredDifference => d * d,
+                    _ => d.abs(),
+                };
+            }
+            sum / 3.0
+        })
+        .collect();
+    Ok(difference)
+}
+
+fn srgb_to_lab(rgb: [f32; 3]) -> [f32; 3] {
+    // Linear sRGB to XYZ, D65
+    let [r, g, b] = rgb.map(|v| v.clamp(0.0, 1.0));
+    let x = 0.4124 * r + 0.3576 * g + 0.1805 * b;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = 0.0193 * r + 0.1192 * g + 0.9505 * b;
+    let f = |t: f32| {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x / 0.9505), f(y), f(z / 1.089));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// 3x3 box filter, a rough stand-in for the contrast sensitivity filter of FLIP.
+fn blur(pixels: &[[f32; 3]], width: usize, height: usize) -> Vec<[f32; 3]> {
+    let mut result = vec![[0.0; 3]; pixels.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0.0; 3];
+            let mut count = 0.0;
+            for dy in -1i32..=1 {
+                for dx in -1i32..=1 {
+                    let (sx, sy) = (x as i32 + dx, y as i32 + dy);
+                    if sx < 0 || sy < 0 || sx >= width as i32 || sy >= height as i32 {
+                        continue;
+                    }
+                    let p = pixels[sy as usize * width + sx as usize];
+                    for c in 0..3 {
+                        sum[c] += p[c];
+                    }
+                    count += 1.0;
+                }
+            }
+            result[y * width + x] = sum.map(|v| v / count);
+        }
+    }
+    result
+}
+
+/// MSE, relMSE and PSNR of the linear values and a FLIP-like error of the displayed ones.
+pub fn compute_metrics(a: &ImageData, b: &ImageData) -> Result<ImageMetrics, String> {
+    check_sizes(a, b)?;
+    let pa = get_rgb_pixels(a);
+    let pb = get_rgb_pixels(b);
+    let count = (pa.len() * 3).max(1) as f64;
+    let mut mse = 0.0f64;
+    let mut rel_mse = 0.0f64;
+    for (a, b) in pa.iter().zip(pb.iter()) {
+        for c in 0..3 {
+            let d = (a[c] - b[c]) as f64;
+            if !d.is_finite() {
+                continue;
+            }
+            mse += d * d;
+            rel_mse += d * d / (b[c] as f64 * b[c] as f64 + REL_EPSILON as f64);
+        }
+    }
+    let mse = (mse / count) as f32;
+    let rel_mse = (rel_mse / count) as f32;
+    let psnr = if mse > 0.0 {
+        10.0 * (1.0 / mse).log10()
+    } else {
+        f32::INFINITY
+    };
+    // Perceptual error of the sRGB display, in CIELAB after a small blur
+    let display = |pixels: &[[f32; 3]]| {
+        let encoded = pixels
+            .iter()
+            .map(|p| p.map(|v| gamma_correct(v.clamp(0.0, 1.0))))
+            .collect::<Vec<_>>();
+        blur(&encoded, a.width, a.height)
+            .iter()
+            .map(|p| srgb_to_lab(p.map(inverse_gamma_correct)))
+            .collect::<Vec<_>>()
+    };
+    let la = display(&pa);
+    let lb = display(&pb);
+    let mut flip = 0.0f64;
+    for (a, b) in la.iter().zip(lb.iter()) {
+        let de = ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt();
+        flip += (de / 100.0).min(1.0) as f64;
+    }
+    let flip = (flip / la.len().max(1) as f64) as f32;
+    Ok(ImageMetrics {
+        mse,
+        rel_mse,
+        psnr,
+        flip,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_image(values: &[f32]) -> ImageData {
+        let names = ["R", "G", "B"].map(|s| s.to_string()).to_vec();
+        let mut image = ImageData::new("test".to_string(), values.len(), 1, &names);
+        image.data = values.iter().flat_map(|v| [*v, *v, *v]).collect();
+        image
+    }
+
+    #[test]
+    fn test_metrics() {
+        let a = make_image(&[0.5, 0.5]);
+        let b = make_image(&[0.5, 0.5]);
+        let metrics = compute_metrics(&a, &b).unwrap();
+        assert_eq!(metrics.mse, 0.0);
+        assert_eq!(metrics.flip, 0.0);
+        assert!(metrics.psnr.is_infinite());
+        let a = make_image(&[0.6, 0.4]);
+        let metrics = compute_metrics(&a, &b).unwrap();
+        assert!((metrics.mse - 0.01).abs() < 1e-6);
+        assert!((metrics.psnr - 20.0).abs() < 1e-3);
+        assert!((metrics.rel_mse - 0.01 / 0.26).abs() < 1e-5);
+        assert!(metrics.flip > 0.0);
+        assert!(compute_metrics(&make_image(&[0.5]), &b).is_err());
+    }
+
+    #[test]
+    fn test_difference() {
+        let a = make_image(&[0.6, 0.0]);
+        let b = make_image(&[0.5, 0.0]);
+        let d = get_difference(&a, &b, CompareMode::AbsoluteDifference).unwrap();
+        assert!((d[0] - 0.1).abs() < 1e-6);
+        assert_eq!(d[1], 0.0);
+        let d = get_difference(&a, &b, CompareMode::RelativeDifference).unwrap();
+        assert!((d[0] - 0.1 / 0.51).abs() < 1e-6);
+    }
+}
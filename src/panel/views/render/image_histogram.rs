@@ -0,0 +1,59 @@
+/// Histogram of log2 luminance, in stops around 1.0.
+#[derive(Debug, Clone)]
+pub struct ImageHistogram {
+    pub bins: Vec<u32>,
+    pub min: f32, // Stops at the left edge
+    pub max: f32, // Stops at the right edge
+    pub zeros: u32,
+}
+
+impl ImageHistogram {
+    pub fn new(bin_count: usize, min: f32, max: f32) -> Self {
+        Self {
+            bins: vec![0; bin_count.max(1)],
+            min,
+            max,
+            zeros: 0,
+        }
+    }
+
+    pub fn add(&mut self, luminance: f32) {
+        if !luminance.is_finite() {
+            return;
+        }
+        if luminance <= 0.0 {
+            self.zeros += 1;
+            return;
+        }
+        let t = (luminance.log2() - self.min) / (self.max - self.min);
+        let n = self.bins.len();
+        let i = ((t * n as f32).floor().max(0.0) as usize).min(n - 1);
+        self.bins[i] += 1;
+    }
+
+    pub fn get_max_count(&self) -> u32 {
+        self.bins.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Position of `stops` across the histogram in [0, 1].
+    pub fn get_position(&self, stops: f32) -> f32 {
+        ((stops - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_bins() {
+        let mut histogram = ImageHistogram::new(4, -2.0, 2.0);
+        for v in [0.3, 0.5, 1.0, 1.5, 100.0, 0.01, 0.0, f32::NAN] {
+            histogram.add(v);
+        }
+        assert_eq!(histogram.bins, [2, 1, 2, 1]);
+        assert_eq!(histogram.zeros, 1);
+        assert_eq!(histogram.get_max_count(), 2);
+        assert_eq!(histogram.get_position(0.0), 0.5);
+    }
+}
@@ -0,0 +1,219 @@
+use super::image_data::ImageData;
+use crate::conversion::color_space::inverse_gamma_correct;
+use crate::error::PbrtError;
+
+use std::path::Path;
+
+/// Index of the channel that goes first: R, G, B, A, then everything else.
+fn get_channel_rank(name: &str) -> usize {
+    match name {
+        "R" => 0,
+        "G" => 1,
+        "B" => 2,
+        "A" => 3,
+        _ => 4,
+    }
+}
+
+/// Builds an `ImageData` from planar channels, putting RGBA first.
+fn create_image_data(
+    name: &str,
+    width: usize,
+    height: usize,
+    mut channels: Vec<(String, Vec<f32>)>,
+) -> ImageData {
+    // A stable sort keeps the order of the AOV channels from the file
+    channels.sort_by_key(|(name, _)| get_channel_rank(name));
+    let channel_names = channels
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    let mut image = ImageData::new(name.to_string(), width, height, &channel_names);
+    let n_channels = channels.len();
+    for (c, (_, values)) in channels.iter().enumerate() {
+        for (i, value) in values.iter().take(width * height).enumerate() {
+            image.data[i * n_channels + c] = *value;
+        }
+    }
+    image.tiles.push((0, 0, width, height));
+    return image;
+}
+
+fn load_exr(path: &Path, name: &str) -> Result<ImageData, PbrtError> {
+    let image = exr::prelude::read_all_flat_layers_from_file(path)
+        .map_err(|e| PbrtError::error(&format!("{}", e)))?;
+    let size = image
+        .layer_data
+        .first()
+        .map(|layer| layer.size)
+        .ok_or(PbrtError::error("EXR file has no layers"))?;
+    let (width, height) = (size.width(), size.height());
+    let mut channels = Vec::new();
+    for layer in image.layer_data.iter() {
+        if layer.size.width() != width || layer.size.height() != height {
+            log::warn!("Skipped an EXR layer with a different size in {}", name);
+            continue;
+        }
+        let prefix = match layer.attributes.layer_name.as_ref() {
+            Some(layer_name) => format!("{}.", layer_name),
+            None => String::new(),
+        };
+        for channel in layer.channel_data.list.iter() {
+            let values = channel.sample_data.values_as_f32().collect::<Vec<_>>();
+            channels.push((format!("{}{}", prefix, channel.name), values));
+        }
+    }
+    if channels.is_empty() {
+        return Err(PbrtError::error("EXR file has no channels"));
+    }
+    return Ok(create_image_data(name, width, height, channels));
+}
+
+fn read_pfm_token<'a>(data: &'a [u8], offset: &mut usize) -> Result<&'a str, PbrtError> {
+    while *offset < data.len() && data[*offset].is_ascii_whitespace() {
+        *offset += 1;
+    }
+    let start = *offset;
+    while *offset < data.len() && !data[*offset].is_ascii_whitespace() {
+        *offset += 1;
+    }
+    if start == *offset {
+        return Err(PbrtError::error("Unexpected end of PFM header"));
+    }
+    std::str::from_utf8(&data[start..*offset]).map_err(|_| PbrtError::error("Invalid PFM header"))
+}
+
+/// Portable float map, "PF" (RGB) or "Pf" (grey), stored bottom row first.
+pub fn parse_pfm(data: &[u8], name: &str) -> Result<ImageData, PbrtError> {
+    let mut offset = 0;
+    let n_channels = match read_pfm_token(data, &mut offset)? {
+        "PF" => 3,
+        "Pf" => 1,
+        _ => return Err(PbrtError::error("Not a PFM file")),
+    };
+    let width = read_pfm_token(data, &mut offset)?.parse::<usize>()?;
+    let height = read_pfm_token(data, &mut offset)?.parse::<usize>()?;
+    let scale = read_pfm_token(data, &mut offset)?
+        .parse::<f32>()
+        .map_err(|_| PbrtError::error("Invalid PFM scale"))?;
+    offset += 1; // A single whitespace ends the header
+    let little_endian = scale < 0.0;
+    // The header is not trusted, a huge size must not overflow
+    let count = width
+        .checked_mul(height)
+        .and_then(|n| n.checked_mul(n_channels));
+    let end = count
+        .and_then(|n| n.checked_mul(4))
+        .and_then(|n| n.checked_add(offset));
+    let (Some(count), Some(end)) = (count, end) else {
+        return Err(PbrtError::error("Invalid PFM size"));
+    };
+    if data.len() < end {
+        return Err(PbrtError::error("PFM data is too short"));
+    }
+    let names: &[&str] = if n_channels == 3 {
+        &["R", "G", "B"]
+    } else {
+        &["Y"]
+    };
+    let mut channels = names
+        .iter()
+        .map(|name| (name.to_string(), vec![0.0; width * height]))
+        .collect::<Vec<_>>();
+    for i in 0..count {
+        let bytes: [u8; 4] = data[offset + i * 4..offset + i * 4 + 4].try_into().unwrap();
+        let value = if little_endian {
+            f32::from_le_bytes(bytes)
+        } else {
+            f32::from_be_bytes(bytes)
+        };
+        let pixel = i / n_channels;
+        let (x, y) = (pixel % width, height - 1 - pixel / width);
+        channels[i % n_channels].1[y * width + x] = value * scale.abs();
+    }
+    return Ok(create_image_data(name, width, height, channels));
+}
+
+/// PNG, HDR and the other formats of the image crate; 8 and 16 bit images are sRGB encoded.
+fn load_other(path: &Path, name: &str) -> Result<ImageData, PbrtError> {
+    let image = image::open(path).map_err(|e| PbrtError::error(&format!("{}", e)))?;
+    let is_float = matches!(
+        image.color(),
+        image::ColorType::Rgb32F | image::ColorType::Rgba32F
+    );
+    let has_alpha = image.color().has_alpha();
+    let image = image.to_rgba32f();
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let names: &[&str] = if has_alpha {
+        &["R", "G", "B", "A"]
+    } else {
+        &["R", "G", "B"]
+    };
+    let mut channels = names
+        .iter()
+        .map(|name| (name.to_string(), Vec::with_capacity(width * height)))
+        .collect::<Vec<_>>();
+    for pixel in image.pixels() {
+        for (c, (_, values)) in channels.iter_mut().enumerate() {
+            let value = pixel.0[c];
+            if c < 3 && !is_float {
+                values.push(inverse_gamma_correct(value));
+            } else {
+                values.push(value);
+            }
+        }
+    }
+    return Ok(create_image_data(name, width, height, channels));
+}
+
+/// Reads a rendered image from disk with all of its channels.
+pub fn load_image_data(path: &str) -> Result<ImageData, PbrtError> {
+    let path = Path::new(path);
+    let name = path
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "exr" => load_exr(path, &name),
+        "pfm" => parse_pfm(&std::fs::read(path)?, &name),
+        _ => load_other(path, &name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_order() {
+        let channels = ["Albedo.R", "B", "G", "N.X", "R"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.to_string(), vec![i as f32]))
+            .collect::<Vec<_>>();
+        let image = create_image_data("test", 1, 1, channels);
+        assert_eq!(image.channel_names, ["R", "G", "B", "Albedo.R", "N.X"]);
+        assert_eq!(image.data, [4.0, 2.0, 1.0, 0.0, 3.0]);
+        assert_eq!(image.tiles, [(0, 0, 1, 1)]);
+    }
+
+    #[test]
+    fn test_parse_pfm() {
+        let mut data = b"Pf\n2 2\n-2.0\n".to_vec();
+        for value in [1.0f32, 2.0, 3.0, 4.0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        let image = parse_pfm(&data, "test.pfm").unwrap();
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.channel_names, ["Y"]);
+        // The first row in the file is the bottom one
+        assert_eq!(image.data, [6.0, 8.0, 2.0, 4.0]);
+        assert!(parse_pfm(b"P6\n1 1\n255\n", "test.ppm").is_err());
+        let huge = format!("PF\n{} {}\n-1.0\n", usize::MAX / 2, 3);
+        assert!(parse_pfm(huge.as_bytes(), "test.pfm").is_err());
+    }
+}
@@ -0,0 +1,312 @@
+use super::render_state::RenderState;
+use crate::model::base::PropertyMap;
+use crate::model::scene::CameraComponent;
+use crate::model::scene::FilmComponent;
+use crate::model::scene::IntegratorComponent;
+use crate::model::scene::LightComponent;
+use crate::model::scene::MaterialComponent;
+use crate::model::scene::Node;
+use crate::model::scene::ResourceComponent;
+use crate::model::scene::SamplerComponent;
+use crate::model::scene::ShapeComponent;
+use crate::model::scene::TransformComponent;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::Duration;
+use std::time::Instant;
+
+const DEBOUNCE: Duration = Duration::from_millis(500); // Edits closer than this restart once
+const SAMPLE_PASSES: [i32; 3] = [1, 4, 16]; // Before the samples of the scene
+const DEFAULT_PIXEL_SAMPLES: i32 = 16; // pbrt's default for the samplers
+
+/// Samples per pixel of the scene's sampler.
+pub fn get_pixel_samples(node: &Arc<RwLock<Node>>) -> i32 {
+    if let Some(sampler_node) = Node::find_node_by_component::<SamplerComponent>(node) {
+        let sampler_node = sampler_node.read().unwrap();
+        let sampler = sampler_node.get_component::<SamplerComponent>().unwrap();
+        if let Some(pixel_samples) = sampler.props.find_one_int("integer pixelsamples") {
+            return pixel_samples;
+        }
+    }
+    return DEFAULT_PIXEL_SAMPLES;
+}
+
+/// Samples per pixel of each pass, the last one with the full quality of the scene.
+pub fn get_sample_passes(pixel_samples: i32) -> Vec<i32> {
+    let pixel_samples = pixel_samples.max(1);
+    let mut passes = SAMPLE_PASSES
+        .iter()
+        .copied()
+        .filter(|samples| *samples < pixel_samples)
+        .collect::<Vec<_>>();
+    passes.push(pixel_samples);
+    return passes;
+}
+
+fn get_props_edition(props: &PropertyMap) -> Option<String> {
+    return props.find_one_string("string edition");
+}
+
+fn hash_node(node: &Arc<RwLock<Node>>, hasher: &mut DefaultHasher) {
+    let node = node.read().unwrap();
+    node.id.hash(hasher);
+    node.enable.hash(hasher);
+    if let Some(transform) = node.get_component::<TransformComponent>() {
+        transform.get_edition().hash(hasher);
+    }
+    if let Some(component) = node.get_component::<ShapeComponent>() {
+        let shape = component.get_shape();
+        let shape = shape.read().unwrap();
+        shape.get_id().hash(hasher);
+        shape.get_edition().hash(hasher);
+    }
+    if let Some(component) = node.get_component::<MaterialComponent>() {
+        let material = component.get_material();
+        let material = material.read().unwrap();
+        material.get_id().hash(hasher);
+        material.get_edition().hash(hasher);
+    }
+    if let Some(component) = node.get_component::<LightComponent>() {
+        let light = component.get_light();
+        light.read().unwrap().get_edition().hash(hasher);
+    }
+    // Render settings are bumped by the inspector; queue overrides are put back unbumped
+    if let Some(camera) = node.get_component::<CameraComponent>() {
+        get_props_edition(&camera.props).hash(hasher);
+    }
+    if let Some(film) = node.get_component::<FilmComponent>() {
+        get_props_edition(&film.props).hash(hasher);
+    }
+    if let Some(sampler) = node.get_component::<SamplerComponent>() {
+        get_props_edition(&sampler.props).hash(hasher);
+    }
+    if let Some(integrator) = node.get_component::<IntegratorComponent>() {
+        get_props_edition(&integrator.props).hash(hasher);
+    }
+    if let Some(component) = node.get_component::<ResourceComponent>() {
+        let resource_manager = component.get_resource_manager();
+        let resource_manager = resource_manager.read().unwrap();
+        let mut editions = Vec::new();
+        for material in resource_manager.materials.values() {
+            let material = material.read().unwrap();
+            editions.push((material.get_id(), material.get_edition()));
+        }
+        for texture in resource_manager.textures.values() {
+            let texture = texture.read().unwrap();
+            editions.push((texture.get_id(), texture.get_edition()));
+        }
+        editions.sort();
+        editions.hash(hasher);
+    }
+    node.children.len().hash(hasher);
+    for child in node.children.iter() {
+        hash_node(child, hasher);
+    }
+}
+
+/// Changes when an edit bumps an edition anywhere in the scene.
+pub fn get_scene_fingerprint(node: &Arc<RwLock<Node>>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hash_node(node, &mut hasher);
+    return hasher.finish();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InteractiveAction {
+    Cancel,        // The scene changed under the running render
+    Render(usize), // Start the pass with this index
+}
+
+/// Re-renders the scene when it is edited, first with few samples and then with more.
+#[derive(Debug, Default)]
+pub struct InteractiveRender {
+    enabled: bool,
+    fingerprint: Option<u64>, // Scene of the running or next pass
+    changed: Option<Instant>, // Last edit, rendered after the debounce
+    pass: Option<usize>,      // Pass being rendered
+}
+
+impl InteractiveRender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        return self.enabled;
+    }
+
+    /// Enabling renders the scene as it is.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.fingerprint = None;
+        self.changed = None;
+        self.pass = None;
+    }
+
+    pub fn get_pass(&self) -> Option<usize> {
+        return self.pass;
+    }
+
+    /// True while the running pass is followed by another one.
+    pub fn is_refining(&self, n_passes: usize) -> bool {
+        return self.enabled && self.pass.is_some_and(|pass| pass + 1 < n_passes);
+    }
+
+    /// What to do with the last render for the scene as it is now.
+    pub fn update(
+        &mut self,
+        fingerprint: u64,
+        state: RenderState,
+        n_passes: usize,
+        now: Instant,
+    ) -> Option<InteractiveAction> {
+        if !self.enabled {
+            return None;
+        }
+        if self.fingerprint != Some(fingerprint) {
+            self.fingerprint = Some(fingerprint);
+            self.changed = Some(now);
+            self.pass = None;
+            if matches!(
+                state,
+                RenderState::Saving | RenderState::Rendering | RenderState::Finishing
+            ) {
+                return Some(InteractiveAction::Cancel);
+            }
+        }
+        if let Some(changed) = self.changed {
+            if now.duration_since(changed) < DEBOUNCE || state != RenderState::Ready {
+                return None;
+            }
+            self.changed = None;
+            self.pass = Some(0);
+            return Some(InteractiveAction::Render(0));
+        }
+        if let Some(pass) = self.pass {
+            if state == RenderState::Finished {
+                if pass + 1 < n_passes {
+                    self.pass = Some(pass + 1);
+                    return Some(InteractiveAction::Render(pass + 1));
+                }
+                self.pass = None;
+            }
+        }
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::base::Property;
+    use crate::model::base::Quaternion;
+    use crate::model::base::Vector3;
+
+    use uuid::Uuid;
+
+    #[test]
+    fn test_sample_passes() {
+        assert_eq!(get_sample_passes(64), [1, 4, 16, 64]);
+        assert_eq!(get_sample_passes(16), [1, 4, 16]);
+        assert_eq!(get_sample_passes(2), [1, 2]);
+        assert_eq!(get_sample_passes(0), [1]);
+    }
+
+    #[test]
+    fn test_scene_fingerprint() {
+        let root = Node::root_node("root");
+        let child = Node::child_node("child", &root);
+        let fingerprint = get_scene_fingerprint(&root);
+        assert_eq!(get_scene_fingerprint(&root), fingerprint);
+        {
+            let mut child = child.write().unwrap();
+            let transform = child.get_component_mut::<TransformComponent>().unwrap();
+            transform.set_local_trs(
+                Vector3::new(1.0, 0.0, 0.0),
+                Quaternion::identity(),
+                Vector3::new(1.0, 1.0, 1.0),
+            );
+        }
+        let edited = get_scene_fingerprint(&root);
+        assert_ne!(edited, fingerprint);
+        child.write().unwrap().set_enable(false);
+        let disabled = get_scene_fingerprint(&root);
+        assert_ne!(disabled, edited);
+        // every move through the component bumps the edition
+        {
+            let mut child = child.write().unwrap();
+            let transform = child.get_component_mut::<TransformComponent>().unwrap();
+            transform.set_local_trs(
+                Vector3::new(2.0, 0.0, 0.0),
+                Quaternion::identity(),
+                Vector3::new(1.0, 1.0, 1.0),
+            );
+        }
+        let moved = get_scene_fingerprint(&root);
+        assert_ne!(moved, disabled);
+        // render settings count once the inspector bumps them, not while overrides are applied
+        {
+            let mut root = root.write().unwrap();
+            root.add_component::<FilmComponent>(FilmComponent::new("rgb", &PropertyMap::new()));
+        }
+        let with_film = get_scene_fingerprint(&root);
+        {
+            let mut root = root.write().unwrap();
+            let film = root.get_component_mut::<FilmComponent>().unwrap();
+            film.props
+                .insert("integer xresolution", Property::Ints(vec![64]));
+        }
+        assert_eq!(get_scene_fingerprint(&root), with_film);
+        {
+            let mut root = root.write().unwrap();
+            let film = root.get_component_mut::<FilmComponent>().unwrap();
+            film.props
+                .add_string("string edition", &Uuid::new_v4().to_string());
+        }
+        assert_ne!(get_scene_fingerprint(&root), with_film);
+    }
+
+    #[test]
+    fn test_debounce_and_passes() {
+        let mut interactive = InteractiveRender::new();
+        let start = Instant::now();
+        assert_eq!(interactive.update(1, RenderState::Ready, 3, start), None);
+        interactive.set_enabled(true);
+        assert_eq!(interactive.update(1, RenderState::Ready, 3, start), None);
+        // Another edit before the debounce restarts it
+        let t = start + Duration::from_millis(300);
+        assert_eq!(interactive.update(2, RenderState::Ready, 3, t), None);
+        let t = start + Duration::from_millis(600);
+        assert_eq!(interactive.update(2, RenderState::Ready, 3, t), None);
+        let t = start + Duration::from_millis(900);
+        assert_eq!(
+            interactive.update(2, RenderState::Ready, 3, t),
+            Some(InteractiveAction::Render(0))
+        );
+        assert_eq!(interactive.update(2, RenderState::Rendering, 3, t), None);
+        assert!(interactive.is_refining(3));
+        assert_eq!(
+            interactive.update(2, RenderState::Finished, 3, t),
+            Some(InteractiveAction::Render(1))
+        );
+        // An edit cancels the pass and starts over after the debounce
+        assert_eq!(
+            interactive.update(3, RenderState::Rendering, 3, t),
+            Some(InteractiveAction::Cancel)
+        );
+        let t = t + DEBOUNCE;
+        assert_eq!(
+            interactive.update(3, RenderState::Ready, 3, t),
+            Some(InteractiveAction::Render(0))
+        );
+        interactive.update(3, RenderState::Finished, 3, t);
+        interactive.update(3, RenderState::Finished, 3, t);
+        assert!(!interactive.is_refining(3));
+        assert_eq!(interactive.update(3, RenderState::Finished, 3, t), None);
+        assert_eq!(interactive.get_pass(), None);
+    }
+}
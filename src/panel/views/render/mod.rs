@@ -1,11 +1,28 @@
+pub mod compare_view;
 pub mod fps_counter;
+pub mod gizmo_controller;
+pub mod image_channels;
+pub mod image_compare;
 pub mod image_data;
+pub mod image_histogram;
+pub mod image_loader;
 pub mod image_receiver;
+pub mod interactive_render;
+pub mod render_gallery;
+pub mod render_gallery_view;
 pub mod render_history;
+pub mod render_log;
+pub mod render_log_view;
 pub mod render_panel;
+pub mod render_queue;
+pub mod render_queue_view;
+pub mod render_region;
 pub mod render_session;
 pub mod render_state;
 pub mod render_view;
+pub mod render_view_settings;
 pub mod scene_view;
+pub mod viewport_camera;
+pub mod viewport_menu;
 
 pub use render_panel::RenderPanel;
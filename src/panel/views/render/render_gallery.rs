@@ -0,0 +1,562 @@
+use super::image_compare::get_rgb_pixels;
+use super::image_data::ImageData;
+use super::image_loader::load_image_data;
+use super::render_log::RenderLogLevel;
+use super::render_log::RenderStatisticsSection;
+use super::render_session::RenderSession;
+use super::render_session::SessionDir;
+use super::render_view_settings::RenderViewSettings;
+use crate::error::PbrtError;
+use crate::model::base::PropertyMap;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use std::time::SystemTime;
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const THUMBNAIL_SIZE: usize = 160;
+const INCLUDE_DIR: &str = "includes"; // Included files of a snapshot, in its record directory
+
+/// Film, sampler and integrator settings worth showing next to a render.
+pub fn get_render_settings(
+    film: Option<&PropertyMap>,
+    sampler: Option<&PropertyMap>,
+    integrator: Option<&PropertyMap>,
+) -> Vec<(String, String)> {
+    let mut settings = Vec::new();
+    if let Some(film) = film {
+        let width = film.find_one_int("integer xresolution").unwrap_or(1280);
+        let height = film.find_one_int("integer yresolution").unwrap_or(720);
+        settings.push(("Resolution".to_string(), format!("{}x{}", width, height)));
+        let crop_window = film.get_floats("float cropwindow");
+        if crop_window.len() == 4 {
+            let text = crop_window
+                .iter()
+                .map(|v| format!("{:.3}", v))
+                .collect::<Vec<_>>()
+                .join(" ");
+            settings.push(("Crop Window".to_string(), text));
+        }
+    }
+    if let Some(sampler) = sampler {
+        if let Some(sampler_type) = sampler.find_one_string("string type") {
+            settings.push(("Sampler".to_string(), sampler_type));
+        }
+        if let Some(pixel_samples) = sampler.find_one_int("integer pixelsamples") {
+            settings.push(("Pixel Samples".to_string(), pixel_samples.to_string()));
+        }
+    }
+    if let Some(integrator) = integrator {
+        if let Some(integrator_type) = integrator.find_one_string("string type") {
+            settings.push(("Integrator".to_string(), integrator_type));
+        }
+        if let Some(max_depth) = integrator.find_one_int("integer maxdepth") {
+            settings.push(("Max Depth".to_string(), max_depth.to_string()));
+        }
+    }
+    return settings;
+}
+
+/// A finished render kept across restarts with everything needed to make it again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderRecord {
+    pub id: Uuid,
+    pub name: String,
+    pub created: u64, // Seconds since the Unix epoch
+    #[serde(default)]
+    pub scene_path: Option<String>, // Scene file the render was made from
+    pub snapshot_path: String, // Exported .pbrt that pbrt rendered
+    pub image_path: String,
+    #[serde(default)]
+    pub thumbnail_path: String,
+    #[serde(default)]
+    pub command_line: Vec<String>,
+    #[serde(default)]
+    pub duration: f32, // Seconds
+    #[serde(default)]
+    pub settings: Vec<(String, String)>,
+    #[serde(default)]
+    pub statistics: Vec<RenderStatisticsSection>,
+    #[serde(default)]
+    pub warnings: usize,
+    #[serde(default)]
+    pub errors: usize,
+    #[serde(skip)]
+    pub session_dir: Option<Arc<SessionDir>>, // Keeps the snapshot of an agent session until it is stored
+}
+
+impl RenderRecord {
+    pub fn new(name: &str, session: &RenderSession, image_path: &str) -> Self {
+        let created = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let log = session.get_log();
+        let log = log.lock().unwrap();
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            created,
+            scene_path: session.get_scene_path(),
+            snapshot_path: session.get_pbrt_path(),
+            image_path: image_path.to_string(),
+            thumbnail_path: String::new(),
+            command_line: session.get_command_line(),
+            duration: session.get_duration(),
+            settings: session.get_settings(),
+            statistics: log.statistics.clone(),
+            warnings: log.get_count(RenderLogLevel::Warning),
+            errors: log.get_count(RenderLogLevel::Error),
+            session_dir: session.get_session_dir(),
+        }
+    }
+}
+
+/// "2025-07-01 12:34 UTC" for seconds since the Unix epoch.
+pub fn format_time(secs: u64) -> String {
+    // Civil date from days, after Howard Hinnant
+    let days = (secs / 86400) as i64;
+    let minutes = (secs % 86400) / 60;
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+/// Displayed colours of an image scaled to fit in `max_size` pixels.
+pub fn create_thumbnail(image: &ImageData, max_size: usize) -> egui::ColorImage {
+    let scale = (max_size as f32 / image.width.max(image.height).max(1) as f32).min(1.0);
+    let width = ((image.width as f32 * scale).round() as usize).max(1);
+    let height = ((image.height as f32 * scale).round() as usize).max(1);
+    let pixels = get_rgb_pixels(image);
+    let settings = RenderViewSettings::default();
+    let mut thumbnail = egui::ColorImage::filled([width, height], egui::Color32::BLACK);
+    if pixels.is_empty() {
+        return thumbnail;
+    }
+    for y in 0..height {
+        for x in 0..width {
+            // Nearest pixel is enough for a preview
+            let sx = ((x as f32 + 0.5) / scale) as usize;
+            let sy = ((y as f32 + 0.5) / scale) as usize;
+            let sx = sx.min(image.width - 1);
+            let sy = sy.min(image.height - 1);
+            let [r, g, b] = settings
+                .to_display(pixels[sy * image.width + sx])
+                .map(|v| (v * 255.0).round() as u8);
+            thumbnail.pixels[y * width + x] = egui::Color32::from_rgb(r, g, b);
+        }
+    }
+    return thumbnail;
+}
+
+fn save_thumbnail(thumbnail: &egui::ColorImage, path: &Path) -> Result<(), PbrtError> {
+    let [width, height] = thumbnail.size;
+    let bytes = thumbnail
+        .pixels
+        .iter()
+        .flat_map(|c| [c.r(), c.g(), c.b()])
+        .collect::<Vec<_>>();
+    image::save_buffer(
+        path,
+        &bytes,
+        width as u32,
+        height as u32,
+        image::ColorType::Rgb8,
+    )
+    .map_err(|e| PbrtError::error(&format!("{}", e)))
+}
+
+fn load_thumbnail(path: &str) -> Option<egui::ColorImage> {
+    let image = image::open(path).ok()?.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Some(egui::ColorImage::from_rgba_unmultiplied(
+        size,
+        image.as_raw(),
+    ))
+}
+
+/// Strings between quotes on a line of a scene file.
+fn get_quoted_strings(line: &str) -> Vec<&str> {
+    line.split('"').skip(1).step_by(2).collect()
+}
+
+/// A file named in a scene, relative to the scene and inside of its directory.
+fn get_resource_path(dir: &Path, name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    let is_inside = path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if name.is_empty() || !is_inside {
+        return None;
+    }
+    let path = dir.join(path);
+    if path.is_file() { Some(path) } else { None }
+}
+
+/// Copies the snapshot at `src_path` to `dst_path` as it was written, with the resources it
+/// names next to it. Included files, from the shape cache, are copied into `INCLUDE_DIR` and
+/// their `Include` lines are the only ones changed.
+fn copy_snapshot(src_path: &Path, record_dir: &Path, dst_path: &Path) -> Result<(), PbrtError> {
+    let src_dir = src_path.parent().ok_or(PbrtError::error("Invalid path!"))?;
+    let mut reader = std::io::BufReader::new(std::fs::File::open(src_path)?);
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(dst_path)?);
+    let mut names = HashSet::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        let text = String::from_utf8_lossy(&line);
+        let trimmed = text.trim_start();
+        let quoted = get_quoted_strings(&text);
+        if trimmed.starts_with("Include") && !quoted.is_empty() {
+            let name = quoted[0];
+            let include_path = src_dir.join(name);
+            let file_name = include_path
+                .file_name()
+                .ok_or(PbrtError::error(&format!("Invalid include: {}", name)))?
+                .to_string_lossy()
+                .to_string();
+            std::fs::create_dir_all(record_dir.join(INCLUDE_DIR))?;
+            std::fs::copy(&include_path, record_dir.join(INCLUDE_DIR).join(&file_name))?;
+            let indent = &text[..text.len() - trimmed.len()];
+            writer.write_all(
+                format!("{}Include \"{}/{}\"\n", indent, INCLUDE_DIR, file_name).as_bytes(),
+            )?;
+            continue;
+        }
+        for name in quoted {
+            if !names.insert(name.to_string()) {
+                continue;
+            }
+            if let Some(path) = get_resource_path(src_dir, name) {
+                let resource_path = record_dir.join(name);
+                if let Some(parent) = resource_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::copy(&path, &resource_path)?;
+            }
+        }
+        writer.write_all(&line)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Renders of earlier sessions, newest last.
+#[derive(Default, Serialize, Deserialize)]
+pub struct RenderGallery {
+    pub records: Vec<RenderRecord>,
+    #[serde(skip)]
+    thumbnails: HashMap<Uuid, Option<egui::TextureHandle>>,
+    #[serde(skip)]
+    pending: Vec<thread::JoinHandle<(String, Result<RenderRecord, PbrtError>)>>, // Records being stored
+}
+
+impl RenderGallery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap()
+            .join("pbrt_ui")
+            .join("render_gallery.json")
+    }
+
+    /// Directory holding the files of one record.
+    fn get_record_dir(id: Uuid) -> PathBuf {
+        let mut data_dir = dirs::data_dir().unwrap();
+        data_dir.push("pbrt_ui");
+        data_dir.push("gallery");
+        data_dir.push(id.to_string());
+        data_dir
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PbrtError> {
+        serde_json::from_str::<RenderGallery>(json).map_err(|e| PbrtError::error(&format!("{}", e)))
+    }
+
+    pub fn load() -> Self {
+        let path = Self::get_path();
+        if !path.exists() {
+            return Self::new();
+        }
+        let json = match std::fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) => {
+                log::error!("Error loading render gallery: {}", e);
+                return Self::new();
+            }
+        };
+        match Self::from_json(&json) {
+            Ok(gallery) => gallery,
+            Err(e) => {
+                log::error!("Error loading render gallery: {}", e);
+                Self::new()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let path = Self::get_path();
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => {
+                if let Some(parent) = path.parent() {
+                    let _ = std::fs::create_dir_all(parent);
+                }
+                if let Err(e) = std::fs::write(&path, json) {
+                    log::error!("Error saving render gallery: {}", e);
+                }
+            }
+            Err(e) => {
+                log::error!("Error saving render gallery: {}", e);
+            }
+        }
+    }
+
+    pub fn get_record(&self, id: Uuid) -> Option<&RenderRecord> {
+        self.records.iter().find(|record| record.id == id)
+    }
+
+    /// Copies the snapshot with the files it uses and the image into `record_dir`.
+    /// The scene cache and the output path are reused by later renders, so this is done
+    /// before the next one can start.
+    fn copy_files(mut record: RenderRecord, record_dir: &Path) -> Result<RenderRecord, PbrtError> {
+        std::fs::create_dir_all(record_dir)?;
+        let snapshot_path = record_dir.join(format!("{}.pbrt", record.name));
+        copy_snapshot(Path::new(&record.snapshot_path), record_dir, &snapshot_path)?;
+        let image_name = Path::new(&record.image_path)
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or(format!("{}.exr", record.name));
+        let image_path = record_dir.join(image_name);
+        std::fs::copy(&record.image_path, &image_path)?;
+        record.snapshot_path = snapshot_path.to_str().unwrap().to_string();
+        record.image_path = image_path.to_str().unwrap().to_string();
+        record.session_dir = None;
+        Ok(record)
+    }
+
+    /// Makes the thumbnail of a record whose files are copied.
+    fn store_thumbnail(
+        mut record: RenderRecord,
+        record_dir: &Path,
+    ) -> Result<RenderRecord, PbrtError> {
+        let image = load_image_data(&record.image_path)?;
+        let thumbnail = create_thumbnail(&image, THUMBNAIL_SIZE);
+        let thumbnail_path = record_dir.join("thumbnail.png");
+        save_thumbnail(&thumbnail, &thumbnail_path)?;
+        record.thumbnail_path = thumbnail_path.to_str().unwrap().to_string();
+        Ok(record)
+    }
+
+    /// Adds a finished render; renders without an image are not kept.
+    /// Its files are copied at once, the thumbnail is made on a thread and the record
+    /// shows up once it is done.
+    pub fn add(&mut self, record: RenderRecord) {
+        if !Path::new(&record.image_path).exists() {
+            log::warn!("Render {} has no image to keep", record.name);
+            return;
+        }
+        let name = record.name.clone();
+        let record_dir = Self::get_record_dir(record.id);
+        let record = match Self::copy_files(record, &record_dir) {
+            Ok(record) => record,
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&record_dir);
+                log::error!("Failed to store render {}: {}", name, e);
+                return;
+            }
+        };
+        let handle = thread::spawn(move || {
+            let result = Self::store_thumbnail(record, &record_dir);
+            if result.is_err() {
+                let _ = std::fs::remove_dir_all(&record_dir);
+            }
+            (name, result)
+        });
+        self.pending.push(handle);
+    }
+
+    /// Keeps the records whose files are stored; returns true if one was added.
+    pub fn update(&mut self) -> bool {
+        let (finished, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|handle| handle.is_finished());
+        self.pending = pending;
+        let mut is_added = false;
+        for handle in finished {
+            match handle.join() {
+                Ok((_, Ok(record))) => {
+                    self.records.push(record);
+                    is_added = true;
+                }
+                Ok((name, Err(e))) => {
+                    log::error!("Failed to store render {}: {}", name, e);
+                }
+                Err(_) => {
+                    log::error!("Render gallery thread panicked");
+                }
+            }
+        }
+        if is_added {
+            self.save();
+        }
+        return is_added;
+    }
+
+    pub fn remove(&mut self, id: Uuid) {
+        self.records.retain(|record| record.id != id);
+        self.thumbnails.remove(&id);
+        let _ = std::fs::remove_dir_all(Self::get_record_dir(id));
+        self.save();
+    }
+
+    /// Thumbnail texture of a record, read from disk the first time it is shown.
+    pub fn get_thumbnail(&mut self, ctx: &egui::Context, id: Uuid) -> Option<egui::TextureHandle> {
+        if let Some(texture) = self.thumbnails.get(&id) {
+            return texture.clone();
+        }
+        let texture = self
+            .get_record(id)
+            .and_then(|record| load_thumbnail(&record.thumbnail_path))
+            .map(|image| {
+                ctx.load_texture(
+                    format!("render_gallery_{}", id),
+                    image,
+                    egui::TextureOptions::LINEAR,
+                )
+            });
+        self.thumbnails.insert(id, texture.clone());
+        return texture;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_time(951782400 + 3661), "2000-02-29 01:01 UTC");
+    }
+
+    #[test]
+    fn test_create_thumbnail() {
+        let names = ["R", "G", "B"].map(|s| s.to_string()).to_vec();
+        let mut image = ImageData::new("test".to_string(), 400, 200, &names);
+        image.data.fill(1.0);
+        let thumbnail = create_thumbnail(&image, 100);
+        assert_eq!(thumbnail.size, [100, 50]);
+        assert_eq!(thumbnail.pixels[0], egui::Color32::WHITE);
+        let thumbnail = create_thumbnail(&image, 1000);
+        assert_eq!(thumbnail.size, [400, 200]);
+    }
+
+    #[test]
+    fn test_gallery_json() {
+        let json = r#"{"records":[{"id":"67e55044-10b1-426f-9247-bb680e5fe0c8","name":"1",
+            "created":0,"snapshot_path":"a.pbrt","image_path":"a.exr"}]}"#;
+        let gallery = RenderGallery::from_json(json).unwrap();
+        assert_eq!(gallery.records.len(), 1);
+        assert_eq!(gallery.records[0].scene_path, None);
+        assert!(gallery.records[0].statistics.is_empty());
+        let json = serde_json::to_string(&gallery).unwrap();
+        let gallery2 = RenderGallery::from_json(&json).unwrap();
+        assert_eq!(gallery.records, gallery2.records);
+    }
+
+    #[test]
+    fn test_store_files() {
+        // the stored snapshot is the written one, with its textures and the meshes it included from elsewhere
+        let src = tempfile::tempdir().unwrap();
+        let shapes = tempfile::tempdir().unwrap();
+        image::RgbImage::from_pixel(2, 2, image::Rgb([255, 0, 0]))
+            .save(src.path().join("wood.png"))
+            .unwrap();
+        let shape_path = shapes.path().join("mesh.pbrt");
+        std::fs::write(
+            &shape_path,
+            "Shape \"trianglemesh\" \"integer indices\" [0 1 2] \"point P\" [0 0 0 1 0 0 0 1 0]\n",
+        )
+        .unwrap();
+        let scene = format!(
+            "WorldBegin\nTexture \"wood\" \"spectrum\" \"imagemap\" \"string filename\" \"wood.png\"\n    Include \"{}\"\nWorldEnd\n",
+            shape_path.display()
+        );
+        let snapshot_path = src.path().join("snapshot.pbrt");
+        std::fs::write(&snapshot_path, &scene).unwrap();
+        let image_path = src.path().join("render.exr");
+        image::Rgb32FImage::from_pixel(4, 2, image::Rgb([0.5, 0.5, 0.5]))
+            .save(&image_path)
+            .unwrap();
+        let record = RenderRecord {
+            id: Uuid::new_v4(),
+            name: "render".to_string(),
+            created: 0,
+            scene_path: None,
+            snapshot_path: snapshot_path.to_str().unwrap().to_string(),
+            image_path: image_path.to_str().unwrap().to_string(),
+            thumbnail_path: String::new(),
+            command_line: Vec::new(),
+            duration: 0.0,
+            settings: Vec::new(),
+            statistics: Vec::new(),
+            warnings: 0,
+            errors: 0,
+            session_dir: Some(Arc::new(SessionDir::new(src.path()))),
+        };
+
+        let record_dir = tempfile::tempdir().unwrap();
+        let record = RenderGallery::copy_files(record, record_dir.path()).unwrap();
+        // The directory of the session goes once the snapshot is copied
+        assert!(record.session_dir.is_none());
+        assert!(!src.path().exists());
+        assert!(
+            record
+                .image_path
+                .starts_with(record_dir.path().to_str().unwrap())
+        );
+        assert!(record_dir.path().join("wood.png").exists());
+        assert!(record_dir.path().join("includes/mesh.pbrt").exists());
+        let snapshot = std::fs::read_to_string(&record.snapshot_path).unwrap();
+        let expected = scene.replace(&shape_path.display().to_string(), "includes/mesh.pbrt");
+        assert_eq!(snapshot, expected);
+        let record = RenderGallery::store_thumbnail(record, record_dir.path()).unwrap();
+        assert!(Path::new(&record.thumbnail_path).exists());
+
+        // a missing image fails without a record
+        let mut record = record;
+        record.image_path = src.path().join("missing.exr").to_str().unwrap().to_string();
+        let record_dir = tempfile::tempdir().unwrap();
+        assert!(RenderGallery::copy_files(record, record_dir.path()).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use super::render_gallery::RenderGallery;
+use super::render_gallery::RenderRecord;
+use super::render_gallery::format_time;
+use super::render_log_view::format_seconds;
+use super::render_log_view::show_render_statistics;
+
+use eframe::egui;
+use uuid::Uuid;
+
+const THUMBNAIL_WIDTH: f32 = 96.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GalleryCommand {
+    Open(Uuid),     // Show the image in the render view
+    Restore(Uuid),  // Load the scene snapshot into the editor
+    Rerender(Uuid), // Queue the scene snapshot
+    Remove(Uuid),
+}
+
+fn show_record_details(
+    ui: &mut egui::Ui,
+    record: &RenderRecord,
+    commands: &mut Vec<GalleryCommand>,
+) {
+    ui.horizontal(|ui| {
+        if ui
+            .button("Open")
+            .on_hover_text("Show the image in the render view")
+            .clicked()
+        {
+            commands.push(GalleryCommand::Open(record.id));
+        }
+        if ui
+            .button("Restore Scene")
+            .on_hover_text("Replace the scene with the one this image was rendered from")
+            .clicked()
+        {
+            commands.push(GalleryCommand::Restore(record.id));
+        }
+        if ui
+            .button("Re-render")
+            .on_hover_text("Queue the scene this image was rendered from")
+            .clicked()
+        {
+            commands.push(GalleryCommand::Rerender(record.id));
+        }
+        if ui.button("Remove").clicked() {
+            commands.push(GalleryCommand::Remove(record.id));
+        }
+    });
+    egui::Grid::new("render_gallery_record")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("Name");
+            ui.label(&record.name);
+            ui.end_row();
+            ui.label("Rendered");
+            ui.label(format_time(record.created));
+            ui.end_row();
+            ui.label("Duration");
+            ui.label(format_seconds(record.duration));
+            ui.end_row();
+            if let Some(scene_path) = record.scene_path.as_ref() {
+                ui.label("Scene");
+                ui.label(scene_path).on_hover_text(&record.snapshot_path);
+                ui.end_row();
+            }
+            ui.label("Image");
+            ui.label(&record.image_path);
+            ui.end_row();
+            for (name, value) in record.settings.iter() {
+                ui.label(name);
+                ui.label(value);
+                ui.end_row();
+            }
+            if record.warnings > 0 || record.errors > 0 {
+                ui.label("Messages");
+                ui.label(format!(
+                    "{} warnings, {} errors",
+                    record.warnings, record.errors
+                ));
+                ui.end_row();
+            }
+        });
+    if !record.command_line.is_empty() {
+        let mut command_line = record.command_line.join(" ");
+        ui.add(
+            egui::TextEdit::multiline(&mut command_line)
+                .code_editor()
+                .desired_rows(2)
+                .desired_width(f32::INFINITY),
+        )
+        .on_hover_text("Command line of pbrt");
+    }
+    show_render_statistics(ui, &record.statistics);
+}
+
+/// Thumbnails of earlier renders and the details of the selected one.
+pub fn show_render_gallery(
+    ui: &mut egui::Ui,
+    gallery: &mut RenderGallery,
+    selected: &mut Option<Uuid>,
+) -> Vec<GalleryCommand> {
+    let mut commands = Vec::new();
+    let ids = gallery
+        .records
+        .iter()
+        .rev()
+        .map(|record| (record.id, record.name.clone()))
+        .collect::<Vec<_>>();
+    egui::ScrollArea::vertical()
+        .id_salt("render_gallery_scroll")
+        .max_height(ui.available_height() * 0.5)
+        .show(ui, |ui| {
+            if ids.is_empty() {
+                ui.label("No renders yet");
+            }
+            ui.horizontal_wrapped(|ui| {
+                for (id, name) in ids.iter() {
+                    let is_selected = *selected == Some(*id);
+                    let thumbnail = gallery.get_thumbnail(ui.ctx(), *id);
+                    ui.vertical(|ui| {
+                        ui.set_width(THUMBNAIL_WIDTH);
+                        let button = match thumbnail.as_ref() {
+                            Some(texture) => egui::Button::image(
+                                egui::Image::new(texture).max_width(THUMBNAIL_WIDTH),
+                            ),
+                            None => egui::Button::new("No image")
+                                .min_size(egui::vec2(THUMBNAIL_WIDTH, THUMBNAIL_WIDTH * 0.5)),
+                        };
+                        let response = ui.add(button.selected(is_selected));
+                        if response.clicked() {
+                            *selected = Some(*id);
+                        }
+                        if response.double_clicked() {
+                            commands.push(GalleryCommand::Open(*id));
+                        }
+                        ui.add(egui::Label::new(name).truncate());
+                    });
+                }
+            });
+        });
+    if let Some(record) = selected.and_then(|id| gallery.get_record(id)) {
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_salt("render_gallery_details")
+            .show(ui, |ui| show_record_details(ui, record, &mut commands));
+    }
+    return commands;
+}
@@ -1,6 +1,15 @@
 use super::image_data::ImageData;
+use super::image_histogram::ImageHistogram;
+use super::image_loader::load_image_data;
+use super::render_gallery::RenderRecord;
+use super::render_log::RenderLog;
+use super::render_queue::RenderJobOverrides;
+use super::render_region::RenderRegion;
+use super::render_region::composite_region;
 use super::render_session::RenderSession;
 use super::render_state::RenderState;
+use super::render_view_settings::RenderViewSettings;
+use super::render_view_settings::RenderViewZoom;
 use crate::error::PbrtError;
 use crate::model::config::AppConfig;
 use crate::model::scene::Node;
@@ -8,10 +17,56 @@ use crate::model::scene::Node;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::RwLock;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
 use uuid::Uuid;
 
 use eframe::egui;
 
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+const RELOAD_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// An output image loading on a thread, so a large EXR does not stall the UI.
+struct PendingImage {
+    result: Arc<Mutex<Option<Result<ImageData, PbrtError>>>>,
+}
+
+impl PendingImage {
+    fn spawn(path: &str, region: Option<RenderRegion>) -> Self {
+        let result = Arc::new(Mutex::new(None));
+        {
+            let result = result.clone();
+            let path = path.to_string();
+            thread::spawn(move || {
+                let image = load_image_data(&path).map(|image| match region.as_ref() {
+                    Some(region) => {
+                        let base = region
+                            .base
+                            .as_ref()
+                            .map(|base| base.lock().unwrap().clone());
+                        composite_region(
+                            base.as_ref(),
+                            &image,
+                            region.crop_window,
+                            region.full_size,
+                        )
+                    }
+                    None => image,
+                });
+                *result.lock().unwrap() = Some(image);
+            });
+        }
+        return Self { result };
+    }
+
+    /// The image once the thread is done.
+    fn take_result(&self) -> Option<Result<ImageData, PbrtError>> {
+        return self.result.lock().unwrap().take();
+    }
+}
+
 pub struct RenderHistory {
     pub id: Uuid,
     pub name: String,
@@ -20,6 +75,17 @@ pub struct RenderHistory {
     pub texture_id: Option<egui::TextureId>,
     pub state: RenderState,
     pub image_data: Option<Arc<Mutex<ImageData>>>,
+    pub image_edition: u64, // Bumped whenever image_data is replaced
+    pub log: Option<Arc<Mutex<RenderLog>>>,
+    pub image_modified: Option<SystemTime>,
+    image_checked: Option<Instant>, // When the output file was last looked at
+    pending_image: Option<PendingImage>,
+    pub view_settings: RenderViewSettings,
+    pub texture_settings: Option<RenderViewSettings>, // Settings the texture was made with
+    pub view_zoom: RenderViewZoom,
+    pub histogram: Option<ImageHistogram>,
+    pub record: Option<RenderRecord>, // Made when the render finishes, for the gallery
+    pub region: Option<RenderRegion>, // Set for crop window renders
 }
 
 impl RenderHistory {
@@ -35,9 +101,28 @@ impl RenderHistory {
             texture_id: None,
             state: RenderState::Ready,
             image_data: None,
+            image_edition: 0,
+            log: None,
+            image_modified: None,
+            image_checked: None,
+            pending_image: None,
+            view_settings: RenderViewSettings::default(),
+            texture_settings: None,
+            view_zoom: RenderViewZoom::default(),
+            histogram: None,
+            record: None,
+            region: None,
         }
     }
 
+    /// A finished history showing the image of a gallery record.
+    pub fn from_record(record: &RenderRecord) -> Self {
+        let mut history = Self::new(&record.name);
+        history.output_image_path = record.image_path.clone();
+        history.state = RenderState::Finished;
+        return history;
+    }
+
     pub fn get_id(&self) -> Uuid {
         return self.id;
     }
@@ -55,15 +140,24 @@ impl RenderHistory {
             let next_state = session.update()?;
             self.state = next_state;
 
-            if self.image_data.is_none() {
-                if let Some(image_data) = session.get_image_data() {
+            // A new session replaces the image kept from the previous one once it sends pixels
+            if let Some(image_data) = session.get_image_data() {
+                let is_same = self
+                    .image_data
+                    .as_ref()
+                    .is_some_and(|current| Arc::ptr_eq(current, &image_data));
+                if !is_same {
                     self.image_data = Some(image_data);
+                    self.image_edition += 1;
                 }
             }
         }
 
         if self.state == RenderState::Finished {
-            self.session = None;
+            if let Some(session) = self.session.take() {
+                let record = RenderRecord::new(&self.name, &session, &self.output_image_path);
+                self.record = Some(record);
+            }
         }
         return Ok(self.state);
     }
@@ -72,14 +166,24 @@ impl RenderHistory {
         &mut self,
         node: &Arc<RwLock<Node>>,
         config: &AppConfig,
+        overrides: &RenderJobOverrides,
+        is_interactive: bool,
     ) -> Result<(), PbrtError> {
         //println!("Starting render for history: {}", self.name);
         if self.session.is_some() {
             return Ok(());
         }
         //println!("Creating new render session for history: {}", self.name);
-        let session = RenderSession::new(node, config, self.id, &self.output_image_path)?;
+        let session = RenderSession::new(
+            node,
+            config,
+            self.id,
+            &self.output_image_path,
+            overrides,
+            is_interactive,
+        )?;
         self.state = session.get_state();
+        self.log = Some(session.get_log());
         //println!("Render session created for history: {}", self.name);
         self.session = Some(session);
         return Ok(());
@@ -93,7 +197,78 @@ impl RenderHistory {
         return Ok(());
     }
 
+    /// Stops the session and makes the history ready for another render, keeping the image shown.
+    pub fn reset(&mut self) -> Result<(), PbrtError> {
+        if let Some(mut session) = self.session.take() {
+            if session.get_state() != RenderState::Finished {
+                session.cancel()?;
+            }
+        }
+        self.state = RenderState::Ready;
+        self.record = None;
+        self.pending_image = None;
+        self.image_checked = None;
+        return Ok(());
+    }
+
     pub fn get_image_data(&self) -> Option<Arc<Mutex<ImageData>>> {
         return self.image_data.clone();
     }
+
+    pub fn get_image_edition(&self) -> u64 {
+        return self.image_edition;
+    }
+
+    /// Reads the output image on a thread when the file on disk differs from the one shown.
+    /// Returns true when a new image has been loaded.
+    pub fn reload_image(&mut self, ctx: &egui::Context) -> bool {
+        if self.state != RenderState::Finished || self.output_image_path.is_empty() {
+            return false;
+        }
+        if let Some(pending) = self.pending_image.as_ref() {
+            let Some(result) = pending.take_result() else {
+                ctx.request_repaint_after(RELOAD_POLL_INTERVAL);
+                return false;
+            };
+            self.pending_image = None;
+            match result {
+                Ok(image) => {
+                    self.image_data = Some(Arc::new(Mutex::new(image)));
+                    self.image_edition += 1;
+                    return true;
+                }
+                Err(e) => {
+                    log::error!("Failed to load {}: {}", self.output_image_path, e);
+                    return false;
+                }
+            }
+        }
+        let now = Instant::now();
+        if self
+            .image_checked
+            .is_some_and(|checked| now.duration_since(checked) < RELOAD_CHECK_INTERVAL)
+        {
+            return false;
+        }
+        self.image_checked = Some(now);
+        let modified = match std::fs::metadata(&self.output_image_path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(_) => return false,
+        };
+        if self.image_modified == Some(modified) {
+            return false;
+        }
+        // Not retried every frame when the file cannot be read
+        self.image_modified = Some(modified);
+        self.pending_image = Some(PendingImage::spawn(
+            &self.output_image_path,
+            self.region.clone(),
+        ));
+        ctx.request_repaint_after(RELOAD_POLL_INTERVAL);
+        return false;
+    }
+
+    pub fn get_log(&self) -> Option<Arc<Mutex<RenderLog>>> {
+        return self.log.clone();
+    }
 }
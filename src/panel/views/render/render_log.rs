@@ -0,0 +1,264 @@
+use crate::io::line_reader::spawn_line_reader;
+
+use std::collections::VecDeque;
+use std::io::Read;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+const MAX_LOG_LINES: usize = 10000;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RenderLogLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderLogLine {
+    pub level: RenderLogLevel,
+    pub text: String,
+    pub location: Option<(String, u32)>, // Scene file and line
+}
+
+/// Progress of the current pbrt progress bar.
+#[derive(Debug, Clone, Default)]
+pub struct RenderProgress {
+    pub title: String,
+    pub fraction: f32,
+    pub elapsed: Option<f32>,   // Seconds
+    pub remaining: Option<f32>, // Seconds
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RenderStatisticsSection {
+    pub title: String,
+    pub entries: Vec<(String, String)>,
+}
+
+/// Output of a pbrt process: log lines, progress and the end-of-render statistics.
+#[derive(Debug, Clone, Default)]
+pub struct RenderLog {
+    pub lines: VecDeque<RenderLogLine>,
+    pub progress: Option<RenderProgress>,
+    pub statistics: Vec<RenderStatisticsSection>,
+    in_statistics: bool,
+}
+
+/// Parses "Title: [+++++   ]  (1.5s|3.5s)", the progress bar of pbrt.
+pub fn parse_progress(line: &str) -> Option<RenderProgress> {
+    let open = line.find('[')?;
+    let close = open + line[open..].find(']')?;
+    let title = line[..open].trim().trim_end_matches(':').trim().to_string();
+    let bar = &line[open + 1..close];
+    if bar.is_empty() || !bar.chars().all(|c| c == '+' || c == ' ') {
+        return None;
+    }
+    let done = bar.chars().filter(|c| *c == '+').count();
+    let fraction = done as f32 / bar.chars().count() as f32;
+    let mut progress = RenderProgress {
+        title,
+        fraction,
+        elapsed: None,
+        remaining: None,
+    };
+    let rest = &line[close + 1..];
+    if let (Some(a), Some(b)) = (rest.find('('), rest.find(')')) {
+        if a < b {
+            let times = rest[a + 1..b]
+                .split('|')
+                .map(|t| t.trim().trim_end_matches('s').parse::<f32>().ok())
+                .collect::<Vec<_>>();
+            progress.elapsed = times.first().cloned().flatten();
+            progress.remaining = times.get(1).cloned().flatten();
+            if times.len() == 1 {
+                progress.fraction = 1.0; // The final update only has the elapsed time
+            }
+        }
+    }
+    return Some(progress);
+}
+
+/// Location prefix of a message, "file:line:column" (pbrt-v4) or "file(line)" (pbrt-v3).
+fn parse_location(prefix: &str) -> Option<(String, u32)> {
+    let prefix = prefix.trim().trim_end_matches(':').trim();
+    // Drop a leading "[ tid ... ]" log header
+    let prefix = match prefix.rfind(']') {
+        Some(i) => prefix[i + 1..].trim(),
+        None => prefix,
+    };
+    if prefix.is_empty() {
+        return None;
+    }
+    if let (Some(open), true) = (prefix.rfind('('), prefix.ends_with(')')) {
+        let line = prefix[open + 1..prefix.len() - 1].parse::<u32>().ok()?;
+        return Some((prefix[..open].to_string(), line));
+    }
+    let parts = prefix.rsplitn(3, ':').collect::<Vec<_>>();
+    match parts.as_slice() {
+        [column, line, file] if column.parse::<u32>().is_ok() => {
+            let line = line.parse::<u32>().ok()?;
+            return Some((file.to_string(), line));
+        }
+        [line, file] => {
+            let line = line.parse::<u32>().ok()?;
+            return Some((file.to_string(), line));
+        }
+        _ => {}
+    }
+    return None;
+}
+
+pub fn parse_message(line: &str) -> RenderLogLine {
+    for (tag, level) in [
+        ("Error:", RenderLogLevel::Error),
+        ("Warning:", RenderLogLevel::Warning),
+    ] {
+        if let Some(i) = line.find(tag) {
+            return RenderLogLine {
+                level,
+                text: line[i + tag.len()..].trim().to_string(),
+                location: parse_location(&line[..i]),
+            };
+        }
+    }
+    return RenderLogLine {
+        level: RenderLogLevel::Info,
+        text: line.to_string(),
+        location: None,
+    };
+}
+
+impl RenderLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_count(&self, level: RenderLogLevel) -> usize {
+        return self.lines.iter().filter(|l| l.level == level).count();
+    }
+
+    pub fn push(&mut self, line: RenderLogLine) {
+        if self.lines.len() >= MAX_LOG_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    /// Adds one line of pbrt output; progress bar updates replace the progress.
+    pub fn parse_line(&mut self, line: &str) {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            return;
+        }
+        if let Some(progress) = parse_progress(line) {
+            self.in_statistics = false;
+            if progress.fraction >= 1.0 && progress.elapsed.is_some() {
+                self.push(RenderLogLine {
+                    level: RenderLogLevel::Info,
+                    text: line.to_string(),
+                    location: None,
+                });
+            }
+            self.progress = Some(progress);
+            return;
+        }
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        if line.starts_with("Statistics:") || line.starts_with("Profile:") {
+            self.in_statistics = true;
+            self.statistics.push(RenderStatisticsSection {
+                title: trimmed.trim_end_matches(':').to_string(),
+                entries: Vec::new(),
+            });
+            return;
+        } else if self.in_statistics && indent > 0 {
+            if indent <= 2 {
+                self.statistics.push(RenderStatisticsSection {
+                    title: trimmed.to_string(),
+                    entries: Vec::new(),
+                });
+            } else if let Some(section) = self.statistics.last_mut() {
+                // Names and values are separated by a run of spaces
+                let entry = match trimmed.find("  ") {
+                    Some(i) => (trimmed[..i].to_string(), trimmed[i..].trim().to_string()),
+                    None => (trimmed.to_string(), String::new()),
+                };
+                section.entries.push(entry);
+            }
+            return;
+        } else {
+            self.in_statistics = false;
+        }
+        self.push(parse_message(line));
+    }
+}
+
+/// Reads a pipe of the pbrt process on a thread into the log.
+pub fn spawn_log_reader<R: Read + Send + 'static>(
+    reader: R,
+    log: Arc<Mutex<RenderLog>>,
+) -> thread::JoinHandle<()> {
+    return spawn_line_reader(reader, move |line| log.lock().unwrap().parse_line(line));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_progress() {
+        let p = parse_progress("Rendering: [++++++      ]  (1.5s|3.5s)  ").unwrap();
+        assert_eq!(p.title, "Rendering");
+        assert!((p.fraction - 0.5).abs() < 1e-6);
+        assert_eq!(p.elapsed, Some(1.5));
+        assert_eq!(p.remaining, Some(3.5));
+        let p = parse_progress("Rendering: [++++++++++++]  (5.0s)").unwrap();
+        assert_eq!(p.fraction, 1.0);
+        assert_eq!(p.remaining, None);
+        assert!(parse_progress("Warning: [x] is not a bar").is_none());
+    }
+
+    #[test]
+    fn test_parse_message() {
+        let m = parse_message("scenes/room.pbrt:12:5: Warning: \"foo\": unused parameter.");
+        assert_eq!(m.level, RenderLogLevel::Warning);
+        assert_eq!(m.location, Some(("scenes/room.pbrt".to_string(), 12)));
+        assert_eq!(m.text, "\"foo\": unused parameter.");
+        let m = parse_message("room.pbrt(7): Error: Unknown shape \"blob\"");
+        assert_eq!(m.level, RenderLogLevel::Error);
+        assert_eq!(m.location, Some(("room.pbrt".to_string(), 7)));
+        let m = parse_message("Error: Couldn't open scene file");
+        assert_eq!(m.location, None);
+        let m = parse_message("pbrt version 4 (built Jan 1 2025)");
+        assert_eq!(m.level, RenderLogLevel::Info);
+    }
+
+    #[test]
+    fn test_parse_statistics() {
+        let mut log = RenderLog::new();
+        for line in [
+            "Rendering: [+++   ]  (1.0s|1.0s)",
+            "Statistics:",
+            "  Integrator",
+            "    Camera rays traced                      921600",
+            "  Memory",
+            "    Texture MIP maps                     12.50 MiB",
+            "Done.",
+        ] {
+            log.parse_line(line);
+        }
+        assert_eq!(log.progress.as_ref().unwrap().fraction, 0.5);
+        assert_eq!(log.statistics.len(), 3);
+        assert_eq!(log.statistics[1].title, "Integrator");
+        assert_eq!(
+            log.statistics[1].entries[0],
+            ("Camera rays traced".to_string(), "921600".to_string())
+        );
+        assert_eq!(log.statistics[2].entries[0].1, "12.50 MiB");
+        assert_eq!(log.lines.len(), 1);
+    }
+}
@@ -0,0 +1,105 @@
+use super::render_log::RenderLog;
+use super::render_log::RenderLogLevel;
+use super::render_log::RenderProgress;
+use super::render_log::RenderStatisticsSection;
+
+use eframe::egui;
+
+pub fn format_seconds(seconds: f32) -> String {
+    let seconds = seconds.max(0.0) as u32;
+    if seconds >= 3600 {
+        return format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            (seconds / 60) % 60,
+            seconds % 60
+        );
+    }
+    return format!("{}:{:02}", seconds / 60, seconds % 60);
+}
+
+pub fn get_progress_text(progress: &RenderProgress) -> String {
+    let mut text = format!("{} {:.0}%", progress.title, progress.fraction * 100.0);
+    if let Some(elapsed) = progress.elapsed {
+        text += &format!("  {}", format_seconds(elapsed));
+    }
+    if let Some(remaining) = progress.remaining {
+        text += &format!("  ETA {}", format_seconds(remaining));
+    }
+    return text;
+}
+
+fn get_level_color(ui: &egui::Ui, level: RenderLogLevel) -> egui::Color32 {
+    match level {
+        RenderLogLevel::Info => ui.visuals().text_color(),
+        RenderLogLevel::Warning => ui.visuals().warn_fg_color,
+        RenderLogLevel::Error => ui.visuals().error_fg_color,
+    }
+}
+
+/// The statistics pbrt prints at the end of a render, in a collapsed section.
+pub fn show_render_statistics(ui: &mut egui::Ui, statistics: &[RenderStatisticsSection]) {
+    if statistics.is_empty() {
+        return;
+    }
+    egui::CollapsingHeader::new("Statistics")
+        .id_salt("render_statistics")
+        .show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .id_salt("render_statistics_scroll")
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    for (i, section) in statistics.iter().enumerate() {
+                        ui.strong(&section.title);
+                        egui::Grid::new(("render_statistics_section", i))
+                            .num_columns(2)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for (name, value) in section.entries.iter() {
+                                    ui.label(name);
+                                    ui.monospace(value);
+                                    ui.end_row();
+                                }
+                            });
+                    }
+                });
+        });
+}
+
+/// Progress, statistics and output lines of a pbrt render.
+pub fn show_render_log(ui: &mut egui::Ui, log: &RenderLog) {
+    ui.horizontal(|ui| {
+        if let Some(progress) = log.progress.as_ref() {
+            ui.add(
+                egui::ProgressBar::new(progress.fraction)
+                    .desired_width(240.0)
+                    .text(get_progress_text(progress)),
+            );
+        }
+        for level in [RenderLogLevel::Warning, RenderLogLevel::Error] {
+            let count = log.get_count(level);
+            if count > 0 {
+                let text = format!("{} {:?}", count, level);
+                ui.colored_label(get_level_color(ui, level), text);
+            }
+        }
+    });
+    show_render_statistics(ui, &log.statistics);
+    ui.separator();
+    egui::ScrollArea::vertical()
+        .id_salt("render_log_scroll")
+        .stick_to_bottom(true)
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            for line in log.lines.iter() {
+                let text = match line.location.as_ref() {
+                    Some((file, number)) => format!("{}:{}: {}", file, number, line.text),
+                    None => line.text.clone(),
+                };
+                let text = egui::RichText::new(text)
+                    .monospace()
+                    .color(get_level_color(ui, line.level));
+                ui.label(text);
+            }
+        });
+}
@@ -1,18 +1,42 @@
+use super::compare_view::CompareView;
+use super::interactive_render::InteractiveAction;
+use super::interactive_render::InteractiveRender;
+use super::interactive_render::get_pixel_samples;
+use super::interactive_render::get_sample_passes;
+use super::interactive_render::get_scene_fingerprint;
+use super::render_gallery::RenderGallery;
+use super::render_gallery_view::GalleryCommand;
+use super::render_gallery_view::show_render_gallery;
 use super::render_history::RenderHistory;
+use super::render_log_view::get_progress_text;
+use super::render_log_view::show_render_log;
+use super::render_queue::RenderQueue;
+use super::render_queue_view::show_render_queue;
+use super::render_region::RenderPreset;
+use super::render_region::RenderRegion;
 use super::render_state::RenderState;
 //
 use super::render_view::RenderView;
 use super::scene_view::SceneView;
+use super::viewport_menu::show_viewport_menu;
+use crate::render::render_mode::DebugMode;
 use crate::render::render_mode::RenderMode;
+use crate::render::transform_gizmo::GizmoMode;
+use crate::render::transform_gizmo::GizmoSpace;
 //
 use crate::controller::AppController;
+use crate::io::import::pbrt::load_pbrt;
 use crate::model::config::AppConfig;
+use crate::model::scene::SceneComponent;
 
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::RwLock;
+use std::time::Duration;
+use std::time::Instant;
 
 use eframe::egui;
+use uuid::Uuid;
 
 pub struct RenderPanel {
     app_controller: Arc<RwLock<AppController>>,
@@ -21,15 +45,39 @@ pub struct RenderPanel {
     render_view: RenderView,
     scene_view: SceneView,
     render_mode: RenderMode,
+    show_log: bool,
+    queue: RenderQueue,
+    show_queue: bool,
+    selected_job: Option<Uuid>,
+    compare_view: CompareView,
+    compare_with: Option<usize>, // History B of the comparison
+    gallery: RenderGallery,
+    show_gallery: bool,
+    selected_record: Option<Uuid>,
+    preset: RenderPreset,
+    interactive: InteractiveRender,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum RenderCommand {
     Render,
+    RenderRegion([f32; 4]), // Crop window over the current image
     Stop,
     NewHistory,
 }
 
+/// Mutable references to two different elements.
+fn get_pair_mut<T>(items: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+    assert!(a != b);
+    if a < b {
+        let (left, right) = items.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = items.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
 fn create_history(name: &str, config: &Arc<RwLock<AppConfig>>) -> Box<RenderHistory> {
     let mut history = Box::new(RenderHistory::new(name));
     let render_output_directory = config.read().unwrap().render_output_directory.clone();
@@ -44,6 +92,127 @@ fn create_history(name: &str, config: &Arc<RwLock<AppConfig>>) -> Box<RenderHist
 }
 
 impl RenderPanel {
+    fn run_gallery_command(&mut self, cmd: GalleryCommand) {
+        match cmd {
+            GalleryCommand::Open(id) => {
+                let Some(record) = self.gallery.get_record(id) else {
+                    return;
+                };
+                // The last history stays the one for new renders
+                let index = self.histories.len().saturating_sub(1);
+                let history = Box::new(RenderHistory::from_record(record));
+                self.histories.insert(index, history);
+                if let Some(other) = self.compare_with.as_mut() {
+                    if *other >= index {
+                        *other += 1;
+                    }
+                }
+                self.current = index;
+            }
+            GalleryCommand::Restore(id) => {
+                let Some(record) = self.gallery.get_record(id) else {
+                    return;
+                };
+                match load_pbrt(&record.snapshot_path) {
+                    Ok(node) => {
+                        // Keep the scene file, not the snapshot, as the source of the scene
+                        if let Some(scene_path) = record.scene_path.as_ref() {
+                            let mut node = node.write().unwrap();
+                            if let Some(scene) = node.get_component_mut::<SceneComponent>() {
+                                scene.props.add_string("string fullpath", scene_path);
+                            }
+                        }
+                        self.app_controller.write().unwrap().set_root_node(&node);
+                        log::info!("Restored the scene of render {}", record.name);
+                    }
+                    Err(e) => {
+                        log::error!("Failed to restore the scene of {}: {}", record.name, e);
+                    }
+                }
+            }
+            GalleryCommand::Rerender(id) => {
+                let Some(record) = self.gallery.get_record(id) else {
+                    return;
+                };
+                let job_id = self.queue.enqueue_file(&record.snapshot_path);
+                self.queue.save();
+                self.selected_job = Some(job_id);
+                self.show_queue = true;
+            }
+            GalleryCommand::Remove(id) => {
+                self.gallery.remove(id);
+                if self.selected_record == Some(id) {
+                    self.selected_record = None;
+                }
+            }
+        }
+    }
+
+    /// Samples per pixel of the interactive passes for the scene and preset.
+    fn get_interactive_passes(&self) -> Vec<i32> {
+        let node = self.app_controller.read().unwrap().get_root_node();
+        let pixel_samples = self
+            .preset
+            .get_overrides()
+            .pixel_samples
+            .unwrap_or(get_pixel_samples(&node));
+        return get_sample_passes(pixel_samples);
+    }
+
+    /// Restarts the last history when the scene is edited and refines it pass by pass.
+    fn update_interactive(&mut self, passes: &[i32]) {
+        let node = self.app_controller.read().unwrap().get_root_node();
+        let fingerprint = get_scene_fingerprint(&node);
+        let last_index = self.histories.len() - 1;
+        let last_history = &mut self.histories[last_index];
+        let state = last_history.get_state();
+        let action = self
+            .interactive
+            .update(fingerprint, state, passes.len(), Instant::now());
+        match action {
+            Some(InteractiveAction::Cancel) => {
+                if let Err(e) = last_history.reset() {
+                    log::error!(
+                        "Failed to cancel render for session {}: {}",
+                        last_history.get_name(),
+                        e
+                    );
+                }
+            }
+            Some(InteractiveAction::Render(pass)) => {
+                // Earlier passes only fill the image until the next one is done
+                if let Err(e) = last_history.reset() {
+                    log::error!("Failed to reset session {}: {}", last_history.get_name(), e);
+                    return;
+                }
+                let config = self.app_controller.read().unwrap().get_config();
+                let config = config.read().unwrap();
+                let mut overrides = self.preset.get_overrides();
+                overrides.pixel_samples = Some(passes[pass]);
+                match last_history.render(&node, &config, &overrides, true) {
+                    Ok(_) => {
+                        if pass == 0 {
+                            self.current = last_index;
+                        }
+                        log::info!(
+                            "Interactive pass {} started for session: {}",
+                            pass + 1,
+                            last_history.get_name()
+                        );
+                    }
+                    Err(e) => {
+                        log::error!(
+                            "Failed to start interactive pass for session {}: {}",
+                            last_history.get_name(),
+                            e
+                        );
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+
     pub fn new<'a>(
         cc: &'a eframe::CreationContext<'a>,
         controller: &Arc<RwLock<AppController>>,
@@ -59,22 +228,44 @@ impl RenderPanel {
             render_view: render_view,
             scene_view: scene_view,
             render_mode: RenderMode::Wire,
+            show_log: false,
+            queue: RenderQueue::load(),
+            show_queue: false,
+            selected_job: None,
+            compare_view: CompareView::new(),
+            compare_with: None,
+            gallery: RenderGallery::load(),
+            show_gallery: false,
+            selected_record: None,
+            preset: RenderPreset::default(),
+            interactive: InteractiveRender::new(),
         }
     }
 
     pub fn show(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) {
         //---------------------------------------------------------------------------
-        let current_index = self.current;
         let mut commamds = Vec::new();
         //---------------------------------------------------------------------------
+        let interactive_passes = if self.interactive.is_enabled() {
+            self.get_interactive_passes()
+        } else {
+            Vec::new()
+        };
         {
+            // Passes followed by another one stay in the same history
+            let is_refining = self.interactive.is_refining(interactive_passes.len());
             if let Some(last_history) = self.histories.last_mut() {
                 //let before_state = last_history.get_state();
                 match last_history.update() {
                     Ok(state) => {
-                        if state == RenderState::Finished {
+                        if state == RenderState::Finished && !is_refining {
                             commamds.push(RenderCommand::NewHistory);
                         }
+                        if let Some(record) = last_history.record.take() {
+                            if !is_refining {
+                                self.gallery.add(record);
+                            }
+                        }
 
                         // log::info!("Render state updated: {:?}", last_history.get_state());
                     }
@@ -88,6 +279,23 @@ impl RenderPanel {
                 }
             }
         }
+        {
+            let config = self.app_controller.read().unwrap().get_config();
+            let config = config.read().unwrap();
+            if self.queue.update(&config, config.render_queue_workers) {
+                self.queue.save();
+            }
+            for record in self.queue.take_records() {
+                self.gallery.add(record);
+            }
+            self.gallery.update();
+        }
+        if self.interactive.is_enabled() {
+            self.update_interactive(&interactive_passes);
+            // Edits are picked up after the debounce without other input
+            ui.ctx().request_repaint_after(Duration::from_millis(100));
+        }
+        let current_index = self.current;
         //---------------------------------------------------------------------------
         egui::TopBottomPanel::top("render_mode_panel").show_inside(ui, |ui| {
             ui.horizontal(|ui| {
@@ -103,6 +311,21 @@ impl RenderPanel {
                         (" S ", RenderMode::Solid),
                         (" L ", RenderMode::Lighting),
                     ];
+                    let debug_label = match self.render_mode {
+                        RenderMode::Debug(mode) => mode.get_label(),
+                        _ => "Debug",
+                    };
+                    egui::ComboBox::from_id_salt("debug_render_mode")
+                        .selected_text(debug_label)
+                        .show_ui(ui, |ui| {
+                            for mode in DebugMode::all() {
+                                ui.selectable_value(
+                                    &mut self.render_mode,
+                                    RenderMode::Debug(*mode),
+                                    mode.get_label(),
+                                );
+                            }
+                        });
                     for (label, mode) in render_modes.iter().rev() {
                         ui.selectable_value(
                             &mut self.render_mode,
@@ -111,6 +334,62 @@ impl RenderPanel {
                         );
                     }
                     ui.separator();
+                    let mut view_through_camera = self.scene_view.is_view_through_camera();
+                    if ui
+                        .toggle_value(&mut view_through_camera, "🎥")
+                        .on_hover_text("View through the render camera")
+                        .changed()
+                    {
+                        self.scene_view.set_view_through_camera(view_through_camera);
+                    }
+                    if ui
+                        .button("Set Camera")
+                        .on_hover_text("Set the render camera from the view")
+                        .clicked()
+                    {
+                        self.scene_view.set_render_camera_from_view();
+                    }
+                    if ui
+                        .button("Frame")
+                        .on_hover_text("Frame the selection (F)")
+                        .clicked()
+                    {
+                        self.scene_view.frame_selected();
+                    }
+                    ui.separator();
+                    let space = self.scene_view.get_gizmo_space();
+                    let (space_label, next_space) = match space {
+                        GizmoSpace::Local => ("Local", GizmoSpace::World),
+                        GizmoSpace::World => ("World", GizmoSpace::Local),
+                    };
+                    if ui
+                        .button(space_label)
+                        .on_hover_text("Space of the translate and rotate gizmos")
+                        .clicked()
+                    {
+                        self.scene_view.set_gizmo_space(next_space);
+                    }
+                    let gizmo_modes = [
+                        (GizmoMode::Translate, "W"),
+                        (GizmoMode::Rotate, "E"),
+                        (GizmoMode::Scale, "R"),
+                    ];
+                    let mut gizmo_mode = self.scene_view.get_gizmo_mode();
+                    for (mode, key) in gizmo_modes.iter().rev() {
+                        if ui
+                            .selectable_value(&mut gizmo_mode, *mode, mode.get_label())
+                            .on_hover_text(format!("{} gizmo ({})", mode.get_label(), key))
+                            .clicked()
+                        {
+                            self.scene_view.set_gizmo_mode(gizmo_mode);
+                        }
+                    }
+                    ui.separator();
+                    let controller = self.app_controller.read().unwrap();
+                    let node = controller.get_root_node();
+                    let config = controller.get_config();
+                    let mut config = config.write().unwrap();
+                    show_viewport_menu(ui, &mut config.viewport, &node);
                 })
             });
         });
@@ -125,18 +404,66 @@ impl RenderPanel {
                         if ui.selectable_value(&mut self.current, i, name).clicked() {
                             log::info!("Clicked {}", i);
                         }
+                        if session.get_state() == RenderState::Rendering {
+                            let progress = session
+                                .get_log()
+                                .and_then(|log| log.lock().unwrap().progress.clone());
+                            let fraction = progress.as_ref().map(|p| p.fraction).unwrap_or(0.0);
+                            let bar = ui.add(
+                                egui::ProgressBar::new(fraction)
+                                    .desired_width(60.0)
+                                    .desired_height(8.0),
+                            );
+                            if let Some(progress) = progress.as_ref() {
+                                bar.on_hover_text(get_progress_text(progress));
+                            }
+                        }
                     }
                 });
             }
             ui.separator();
 
             {
+                // Renders with an image that the current one can be compared with
+                let compare_candidates = self
+                    .histories
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, other)| *i != current_index && other.get_image_data().is_some())
+                    .map(|(i, other)| (i, other.get_name()))
+                    .collect::<Vec<_>>();
                 let history = &mut self.histories[current_index];
                 let state = history.get_state();
                 let is_ready = state == RenderState::Ready;
                 let is_stoppable = state == RenderState::Rendering;
                 let is_finished = state == RenderState::Finished;
                 ui.horizontal(|ui| {
+                    ui.toggle_value(&mut self.show_log, "Log")
+                        .on_hover_text("Show the output of pbrt");
+                    ui.toggle_value(&mut self.show_gallery, "Gallery")
+                        .on_hover_text("Show the renders of earlier sessions");
+                    let queue_text = match self.queue.get_running_count() {
+                        0 => "Queue".to_string(),
+                        n => format!("Queue ({})", n),
+                    };
+                    ui.toggle_value(&mut self.show_queue, queue_text)
+                        .on_hover_text("Show the render queue");
+                    let compare_text = compare_candidates
+                        .iter()
+                        .find(|(i, _)| Some(*i) == self.compare_with)
+                        .map(|(_, name)| format!("Compare with {}", name))
+                        .unwrap_or("Compare".to_string());
+                    egui::ComboBox::from_id_salt("render_compare_with")
+                        .selected_text(compare_text)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.compare_with, None, "None");
+                            for (i, name) in compare_candidates.iter() {
+                                ui.selectable_value(&mut self.compare_with, Some(*i), name);
+                            }
+                        })
+                        .response
+                        .on_hover_text("Compare the image with another render (B)");
+                    ui.separator();
                     //ui.text_edit_singleline(&mut session.output_image_path);
                     if is_finished {
                         ui.label(&history.output_image_path);
@@ -186,6 +513,32 @@ impl RenderPanel {
                         }
 
                         ui.separator();
+                        ui.add_enabled_ui(is_ready, |ui| {
+                            egui::ComboBox::from_id_salt("render_preset")
+                                .selected_text(self.preset.get_label())
+                                .show_ui(ui, |ui| {
+                                    for preset in RenderPreset::all() {
+                                        ui.selectable_value(
+                                            &mut self.preset,
+                                            *preset,
+                                            preset.get_label(),
+                                        )
+                                        .on_hover_text(preset.get_description());
+                                    }
+                                })
+                                .response
+                                .on_hover_text(self.preset.get_description());
+                        });
+                        let mut interactive = self.interactive.is_enabled();
+                        if ui
+                            .toggle_value(&mut interactive, "Interactive")
+                            .on_hover_text(
+                                "Render again when the scene is edited, with more samples each pass",
+                            )
+                            .changed()
+                        {
+                            self.interactive.set_enabled(interactive);
+                        }
                         let (text, cmd) = if is_ready {
                             ("▶ Render", RenderCommand::Render)
                         } else {
@@ -202,44 +555,139 @@ impl RenderPanel {
                         }
                         ui.separator();
                         ui.label(format!("{:?}", state));
+                        if let Some(pass) = self.interactive.get_pass() {
+                            if let Some(samples) = interactive_passes.get(pass) {
+                                ui.label(format!(
+                                    "Pass {}/{} ({} spp)",
+                                    pass + 1,
+                                    interactive_passes.len(),
+                                    samples
+                                ));
+                            }
+                        }
                     }
                 });
             }
         });
+        if self.show_log {
+            egui::TopBottomPanel::bottom("render_log")
+                .resizable(true)
+                .default_height(200.0)
+                .show_inside(ui, |ui| match self.histories[current_index].get_log() {
+                    Some(log) => show_render_log(ui, &log.lock().unwrap()),
+                    None => {
+                        ui.label("Not rendered yet");
+                    }
+                });
+        }
+        let mut gallery_commands = Vec::new();
+        if self.show_gallery {
+            egui::SidePanel::left("render_gallery")
+                .resizable(true)
+                .default_width(320.0)
+                .show_inside(ui, |ui| {
+                    gallery_commands =
+                        show_render_gallery(ui, &mut self.gallery, &mut self.selected_record);
+                });
+        }
+        if self.show_queue {
+            egui::SidePanel::right("render_queue")
+                .resizable(true)
+                .default_width(360.0)
+                .show_inside(ui, |ui| {
+                    let controller = self.app_controller.read().unwrap();
+                    let node = controller.get_root_node();
+                    let config = controller.get_config();
+                    let mut config = config.write().unwrap();
+                    if show_render_queue(
+                        ui,
+                        &mut self.queue,
+                        &node,
+                        &mut config,
+                        &mut self.selected_job,
+                    ) {
+                        self.queue.save();
+                    }
+                });
+        }
         //---------------------------------------------------------------------------
+        let viewport_settings = {
+            let config = self.app_controller.read().unwrap().get_config();
+            let config = config.read().unwrap();
+            config.viewport.clone()
+        };
         let frame = egui::Frame {
             inner_margin: egui::Margin::same(0),
             ..Default::default()
         };
+        if self.compare_with == Some(current_index) {
+            self.compare_with = None;
+        }
         egui::CentralPanel::default()
             .frame(frame)
             .show_inside(ui, |ui| {
-                let history = &mut self.histories[current_index];
-                let state = history.get_state();
+                let can_render_region = self
+                    .histories
+                    .last()
+                    .is_some_and(|history| history.get_state() == RenderState::Ready);
                 let available_rect = ui.available_rect_before_wrap();
                 ui.painter()
                     .rect_filled(available_rect, 0.0, egui::Color32::BLACK);
+                if let Some(other_index) = self.compare_with {
+                    let (history, other) =
+                        get_pair_mut(&mut self.histories, current_index, other_index);
+                    if history.get_image_data().is_some() && other.get_image_data().is_some() {
+                        self.compare_view.show(ui, history, other);
+                        return;
+                    }
+                }
+                let history = &mut self.histories[current_index];
+                let state = history.get_state();
                 match state {
                     RenderState::Ready => {
-                        let node = self.app_controller.read().unwrap().get_root_node();
-                        self.scene_view.show(ui, &node, self.render_mode, true);
+                        let controller = self.app_controller.read().unwrap();
+                        let node = controller.get_root_node();
+                        let selected = controller.get_current_node();
+                        self.scene_view.show(
+                            ui,
+                            &node,
+                            selected.as_ref(),
+                            self.render_mode,
+                            &viewport_settings,
+                            true,
+                        );
                     }
                     RenderState::Saving | RenderState::Rendering => {
                         if history.get_image_data().is_none() {
                             let node = self.app_controller.read().unwrap().get_root_node();
-                            self.scene_view.show(ui, &node, self.render_mode, false);
-                        } else {
-                            self.render_view.show(ui, history);
+                            self.scene_view.show(
+                                ui,
+                                &node,
+                                None,
+                                self.render_mode,
+                                &viewport_settings,
+                                false,
+                            );
+                        } else if let Some(crop_window) =
+                            self.render_view.show(ui, history, can_render_region)
+                        {
+                            commamds.push(RenderCommand::RenderRegion(crop_window));
                         }
-                        self.render_view.show(ui, history);
                     }
                     RenderState::Finishing | RenderState::Finished => {
-                        self.render_view.show(ui, history);
+                        if let Some(crop_window) =
+                            self.render_view.show(ui, history, can_render_region)
+                        {
+                            commamds.push(RenderCommand::RenderRegion(crop_window));
+                        }
                     }
                 }
                 //show renderred image
             });
         //---------------------------------------------------------------------------
+        for cmd in gallery_commands {
+            self.run_gallery_command(cmd);
+        }
         {
             for cmd in commamds {
                 match cmd {
@@ -250,7 +698,12 @@ impl RenderPanel {
                         let config = self.app_controller.read().unwrap().get_config();
                         let config = config.read().unwrap();
                         if last_history.get_state() == RenderState::Ready {
-                            match last_history.render(&node, &config) {
+                            match last_history.render(
+                                &node,
+                                &config,
+                                &self.preset.get_overrides(),
+                                false,
+                            ) {
                                 Ok(_) => {
                                     log::info!(
                                         "Render started for session: {}",
@@ -267,8 +720,45 @@ impl RenderPanel {
                             }
                         }
                     }
+                    RenderCommand::RenderRegion(crop_window) => {
+                        let base = self.histories[current_index].get_image_data();
+                        let last_index = self.histories.len() - 1;
+                        let last_history = &mut self.histories[last_index];
+                        if last_history.get_state() != RenderState::Ready {
+                            continue;
+                        }
+                        let node = self.app_controller.read().unwrap().get_root_node();
+                        let config = self.app_controller.read().unwrap().get_config();
+                        let config = config.read().unwrap();
+                        let mut overrides = self.preset.get_overrides();
+                        overrides.crop_window = Some(crop_window);
+                        let [width, height] = overrides.get_resolution(&node);
+                        last_history.region = Some(RenderRegion {
+                            crop_window,
+                            full_size: [width.max(1) as usize, height.max(1) as usize],
+                            base,
+                        });
+                        match last_history.render(&node, &config, &overrides, false) {
+                            Ok(_) => {
+                                self.current = last_index;
+                                log::info!(
+                                    "Region render started for session: {}",
+                                    last_history.get_name()
+                                );
+                            }
+                            Err(e) => {
+                                log::error!(
+                                    "Failed to start region render for session {}: {}",
+                                    last_history.get_name(),
+                                    e
+                                );
+                            }
+                        }
+                    }
                     RenderCommand::Stop => {
                         assert!(!self.histories.is_empty());
+                        // Otherwise the stopped pass would be followed by the next one
+                        self.interactive.set_enabled(false);
                         let last_history = self.histories.last_mut().unwrap();
                         match last_history.cancel() {
                             Ok(_) => {}
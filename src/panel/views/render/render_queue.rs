@@ -0,0 +1,719 @@
+use super::render_gallery::RenderRecord;
+use super::render_gallery::get_render_settings;
+use super::render_log::RenderLog;
+use super::render_session::RenderSession;
+use super::render_state::RenderState;
+use crate::error::PbrtError;
+use crate::io::export::pbrt::*;
+use crate::model::base::Property;
+use crate::model::base::PropertyMap;
+use crate::model::config::AppConfig;
+use crate::model::scene::FilmComponent;
+use crate::model::scene::IntegratorComponent;
+use crate::model::scene::Node;
+use crate::model::scene::SamplerComponent;
+use crate::model::scene::SceneComponent;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RenderJobStatus {
+    Queued,
+    Paused,
+    Running,
+    Finished,
+    Failed,
+    Cancelled,
+}
+
+impl RenderJobStatus {
+    pub fn is_done(&self) -> bool {
+        matches!(
+            self,
+            RenderJobStatus::Finished | RenderJobStatus::Failed | RenderJobStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RenderJobSource {
+    Scene, // Snapshot of the scene being edited
+    File,  // A .pbrt file on disk
+}
+
+/// Settings of a job that replace the ones in the scene.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderJobOverrides {
+    pub resolution: Option<[i32; 2]>,
+    pub pixel_samples: Option<i32>,
+    pub integrator: Option<String>,
+    pub output_path: Option<String>,
+    pub resolution_scale: Option<f32>, // Applied after the resolution
+    pub crop_window: Option<[f32; 4]>, // x0, x1, y0, y1 as in pbrt
+}
+
+/// Film resolution of the props, pbrt's default when it is not set.
+fn get_props_resolution(props: &PropertyMap) -> [i32; 2] {
+    let width = props.find_one_int("integer xresolution");
+    let height = props.find_one_int("integer yresolution");
+    return [width.unwrap_or(1280), height.unwrap_or(720)];
+}
+
+/// Film resolution of the scene, pbrt's default when it is not set.
+pub fn get_film_resolution(node: &Arc<RwLock<Node>>) -> [i32; 2] {
+    if let Some(film_node) = Node::find_node_by_component::<FilmComponent>(node) {
+        let film_node = film_node.read().unwrap();
+        let film = film_node.get_component::<FilmComponent>().unwrap();
+        return get_props_resolution(&film.props);
+    }
+    return [1280, 720];
+}
+
+// The overrides are applied to copies of the props, the scene being edited is never changed.
+impl RenderJobOverrides {
+    pub fn get_film_props(&self, node: &Arc<RwLock<Node>>) -> Option<PropertyMap> {
+        let film_node = Node::find_node_by_component::<FilmComponent>(node)?;
+        let mut props = {
+            let film_node = film_node.read().unwrap();
+            let film = film_node.get_component::<FilmComponent>().unwrap();
+            film.props.clone()
+        };
+        let resolution = match (self.resolution, self.resolution_scale) {
+            (None, None) => None,
+            (resolution, scale) => {
+                let [width, height] = resolution.unwrap_or(get_props_resolution(&props));
+                let scale = scale.unwrap_or(1.0);
+                let scaled = |v: i32| ((v as f32 * scale).round() as i32).max(1);
+                Some([scaled(width), scaled(height)])
+            }
+        };
+        if let Some([width, height]) = resolution {
+            props.insert("integer xresolution", Property::Ints(vec![width]));
+            props.insert("integer yresolution", Property::Ints(vec![height]));
+        }
+        if let Some(crop_window) = self.crop_window {
+            props.insert("float cropwindow", Property::Floats(crop_window.to_vec()));
+        }
+        return Some(props);
+    }
+
+    pub fn get_sampler_props(&self, node: &Arc<RwLock<Node>>) -> Option<PropertyMap> {
+        let sampler_node = Node::find_node_by_component::<SamplerComponent>(node)?;
+        let mut props = {
+            let sampler_node = sampler_node.read().unwrap();
+            let sampler = sampler_node.get_component::<SamplerComponent>().unwrap();
+            sampler.props.clone()
+        };
+        if let Some(pixel_samples) = self.pixel_samples {
+            props.insert("integer pixelsamples", Property::Ints(vec![pixel_samples]));
+        }
+        return Some(props);
+    }
+
+    pub fn get_integrator_props(&self, node: &Arc<RwLock<Node>>) -> Option<PropertyMap> {
+        let integrator_node = Node::find_node_by_component::<IntegratorComponent>(node)?;
+        let mut props = {
+            let integrator_node = integrator_node.read().unwrap();
+            let integrator = integrator_node
+                .get_component::<IntegratorComponent>()
+                .unwrap();
+            integrator.props.clone()
+        };
+        if let Some(integrator) = self.integrator.as_ref() {
+            // Parameters the new type does not know are not written by the exporter
+            props.insert("string type", Property::from(integrator.as_str()));
+        }
+        return Some(props);
+    }
+
+    /// Film resolution with the overrides applied.
+    pub fn get_resolution(&self, node: &Arc<RwLock<Node>>) -> [i32; 2] {
+        match self.get_film_props(node) {
+            Some(props) => get_props_resolution(&props),
+            None => [1280, 720],
+        }
+    }
+
+    pub fn get_render_settings(&self, node: &Arc<RwLock<Node>>) -> Vec<(String, String)> {
+        return get_render_settings(
+            self.get_film_props(node).as_ref(),
+            self.get_sampler_props(node).as_ref(),
+            self.get_integrator_props(node).as_ref(),
+        );
+    }
+
+    /// Makes the exporter write the overridden props in place of the ones in the scene.
+    pub fn set_save_options(&self, node: &Arc<RwLock<Node>>, options: &mut SavePbrtOptions) {
+        options.film_props = self.get_film_props(node);
+        options.sampler_props = self.get_sampler_props(node);
+        options.integrator_props = self.get_integrator_props(node);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderJob {
+    pub id: Uuid,
+    pub name: String,
+    pub source: RenderJobSource,
+    pub scene_path: String,
+    #[serde(default)]
+    pub overrides: RenderJobOverrides,
+    #[serde(default)]
+    pub priority: i32,
+    pub status: RenderJobStatus,
+    #[serde(default)]
+    pub message: String,
+}
+
+impl RenderJob {
+    pub fn new(name: &str, source: RenderJobSource, scene_path: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            source,
+            scene_path: scene_path.to_string(),
+            overrides: RenderJobOverrides::default(),
+            priority: 0,
+            status: RenderJobStatus::Queued,
+            message: String::new(),
+        }
+    }
+
+    pub fn get_output_path(&self, config: &AppConfig) -> String {
+        if let Some(output_path) = self.overrides.output_path.as_ref() {
+            if !output_path.is_empty() {
+                return output_path.clone();
+            }
+        }
+        // The id keeps outputs apart even if a name is given again
+        let filename = format!("render_image_{}_{}.exr", self.name, self.id);
+        let path = PathBuf::from(&config.render_output_directory).join(filename);
+        return path.to_str().unwrap().to_string();
+    }
+}
+
+fn get_snapshot_dir(id: Uuid) -> PathBuf {
+    let mut cache_dir = dirs::cache_dir().unwrap();
+    cache_dir.push("pbrt_ui");
+    cache_dir.push("queue");
+    cache_dir.push(id.to_string());
+    cache_dir
+}
+
+fn get_scene_name(node: &Arc<RwLock<Node>>) -> String {
+    let node = node.read().unwrap();
+    if let Some(scene) = node.get_component::<SceneComponent>() {
+        if let Some(fullpath) = scene.get_fullpath() {
+            if let Some(stem) = std::path::Path::new(&fullpath).file_stem() {
+                return stem.to_string_lossy().to_string();
+            }
+        }
+    }
+    return "scene".to_string();
+}
+
+/// The number `make_name` put after the base of a job name.
+fn get_name_number(name: &str) -> Option<usize> {
+    let (_, number) = name.rsplit_once('_')?;
+    return number.parse().ok();
+}
+
+struct RenderQueueWorker {
+    job_id: Uuid,
+    session: RenderSession,
+    output_path: String,
+    output_modified: Option<SystemTime>, // Of an output left by an earlier render
+}
+
+fn get_modified(path: &str) -> Option<SystemTime> {
+    return std::fs::metadata(path).and_then(|m| m.modified()).ok();
+}
+
+/// Jobs rendered in the background through `RenderSession`, a few at a time.
+#[derive(Default, Serialize, Deserialize)]
+pub struct RenderQueue {
+    pub jobs: Vec<RenderJob>,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(skip)]
+    workers: Vec<RenderQueueWorker>,
+    #[serde(skip)]
+    logs: HashMap<Uuid, Arc<Mutex<RenderLog>>>,
+    #[serde(default)]
+    next_number: usize, // Of the last job name, kept so names are not given twice
+    #[serde(skip)]
+    records: Vec<RenderRecord>, // Finished renders not yet taken by the gallery
+}
+
+impl RenderQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap()
+            .join("pbrt_ui")
+            .join("render_queue.json")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PbrtError> {
+        match serde_json::from_str::<RenderQueue>(json) {
+            Ok(mut queue) => {
+                // Renders interrupted by quitting the app start over
+                for job in queue.jobs.iter_mut() {
+                    if job.status == RenderJobStatus::Running {
+                        job.status = RenderJobStatus::Queued;
+                    }
+                }
+                // Queues saved without the number continue after the names they list
+                let last_number = queue
+                    .jobs
+                    .iter()
+                    .filter_map(|job| get_name_number(&job.name))
+                    .max()
+                    .unwrap_or(0);
+                queue.next_number = queue.next_number.max(last_number);
+                Ok(queue)
+            }
+            Err(e) => Err(PbrtError::error(&format!("{}", e))),
+        }
+    }
+
+    pub fn load() -> Self {
+        let path = Self::get_path();
+        if !path.exists() {
+            return Self::new();
+        }
+        let json = match std::fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) => {
+                log::error!("Error loading render queue: {}", e);
+                return Self::new();
+            }
+        };
+        match Self::from_json(&json) {
+            Ok(queue) => queue,
+            Err(e) => {
+                log::error!("Error loading render queue: {}", e);
+                Self::new()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let path = Self::get_path();
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => {
+                if let Some(parent) = path.parent() {
+                    let _ = std::fs::create_dir_all(parent);
+                }
+                if let Err(e) = std::fs::write(&path, json) {
+                    log::error!("Error saving render queue: {}", e);
+                }
+            }
+            Err(e) => {
+                log::error!("Error saving render queue: {}", e);
+            }
+        }
+    }
+
+    fn make_name(&mut self, base: &str) -> String {
+        self.next_number += 1;
+        return format!("{}_{}", base, self.next_number);
+    }
+
+    pub fn push(&mut self, job: RenderJob) -> Uuid {
+        let id = job.id;
+        self.jobs.push(job);
+        return id;
+    }
+
+    /// Saves the scene as it is now, so later edits do not change the job.
+    pub fn enqueue_scene(&mut self, node: &Arc<RwLock<Node>>) -> Result<Uuid, PbrtError> {
+        let name = self.make_name(&get_scene_name(node));
+        let mut job = RenderJob::new(&name, RenderJobSource::Scene, "");
+        let snapshot_dir = get_snapshot_dir(job.id);
+        std::fs::create_dir_all(&snapshot_dir)?;
+        let scene_path = snapshot_dir.join(format!("{}.pbrt", name));
+        job.scene_path = scene_path.to_str().unwrap().to_string();
+        let mut options = SavePbrtOptions::default();
+        options.pretty_print = false;
+        save_pbrt(node, &job.scene_path, &options)?;
+        return Ok(self.push(job));
+    }
+
+    pub fn enqueue_file(&mut self, path: &str) -> Uuid {
+        let stem = std::path::Path::new(path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or("scene".to_string());
+        let name = self.make_name(&stem);
+        return self.push(RenderJob::new(&name, RenderJobSource::File, path));
+    }
+
+    pub fn get_job_mut(&mut self, id: Uuid) -> Option<&mut RenderJob> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    pub fn get_log(&self, id: Uuid) -> Option<Arc<Mutex<RenderLog>>> {
+        self.logs.get(&id).cloned()
+    }
+
+    pub fn get_running_count(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Index of the queued job with the highest priority; the earlier one wins a tie.
+    pub fn get_next_job(&self) -> Option<usize> {
+        let mut next: Option<usize> = None;
+        for (i, job) in self.jobs.iter().enumerate() {
+            if job.status != RenderJobStatus::Queued {
+                continue;
+            }
+            match next {
+                Some(n) if self.jobs[n].priority >= job.priority => {}
+                _ => next = Some(i),
+            }
+        }
+        return next;
+    }
+
+    /// Moves a job up (negative) or down (positive) in the list.
+    pub fn move_job(&mut self, id: Uuid, offset: i32) {
+        if let Some(index) = self.jobs.iter().position(|job| job.id == id) {
+            let target = index as i32 + offset;
+            if target >= 0 && (target as usize) < self.jobs.len() {
+                let job = self.jobs.remove(index);
+                self.jobs.insert(target as usize, job);
+            }
+        }
+    }
+
+    /// A running job is stopped; pbrt cannot continue it, so it renders from the start when resumed.
+    pub fn pause_job(&mut self, id: Uuid, paused: bool) {
+        let status = self.get_job_mut(id).map(|job| job.status);
+        match (status, paused) {
+            (Some(RenderJobStatus::Running), true) => {
+                if let Some(index) = self.workers.iter().position(|w| w.job_id == id) {
+                    let mut worker = self.workers.remove(index);
+                    if let Err(e) = worker.session.cancel() {
+                        log::error!("Failed to stop render job: {}", e);
+                    }
+                }
+                if let Some(job) = self.get_job_mut(id) {
+                    job.status = RenderJobStatus::Paused;
+                    job.message = "Stopped, renders from the start when resumed".to_string();
+                }
+            }
+            (Some(RenderJobStatus::Queued), true) => {
+                if let Some(job) = self.get_job_mut(id) {
+                    job.status = RenderJobStatus::Paused;
+                }
+            }
+            (Some(RenderJobStatus::Paused), false) => {
+                if let Some(job) = self.get_job_mut(id) {
+                    job.status = RenderJobStatus::Queued;
+                    job.message.clear();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn cancel_job(&mut self, id: Uuid) {
+        if let Some(index) = self.workers.iter().position(|w| w.job_id == id) {
+            let mut worker = self.workers.remove(index);
+            if let Err(e) = worker.session.cancel() {
+                log::error!("Failed to cancel render job: {}", e);
+            }
+        }
+        if let Some(job) = self.get_job_mut(id) {
+            if !job.status.is_done() {
+                job.status = RenderJobStatus::Cancelled;
+            }
+        }
+    }
+
+    /// Puts a finished, failed or cancelled job back into the queue.
+    pub fn retry_job(&mut self, id: Uuid) {
+        if let Some(job) = self.get_job_mut(id) {
+            if job.status.is_done() {
+                job.status = RenderJobStatus::Queued;
+                job.message.clear();
+            }
+        }
+    }
+
+    pub fn remove_job(&mut self, id: Uuid) {
+        self.cancel_job(id);
+        if let Some(index) = self.jobs.iter().position(|job| job.id == id) {
+            let job = self.jobs.remove(index);
+            if job.source == RenderJobSource::Scene {
+                let _ = std::fs::remove_dir_all(get_snapshot_dir(job.id));
+            }
+        }
+        self.logs.remove(&id);
+    }
+
+    pub fn remove_done_jobs(&mut self) {
+        let ids = self
+            .jobs
+            .iter()
+            .filter(|job| job.status.is_done())
+            .map(|job| job.id)
+            .collect::<Vec<_>>();
+        for id in ids {
+            self.remove_job(id);
+        }
+    }
+
+    /// Records of the jobs finished since the last call.
+    pub fn take_records(&mut self) -> Vec<RenderRecord> {
+        std::mem::take(&mut self.records)
+    }
+
+    fn start_job(&mut self, index: usize, config: &AppConfig) -> Result<(), PbrtError> {
+        let job = &self.jobs[index];
+        let output_path = job.get_output_path(config);
+        if let Some(parent) = std::path::Path::new(&output_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let output_modified = get_modified(&output_path);
+        // Concurrent pbrt processes cannot share one display server
+        let mut config = config.clone();
+        config.enable_display_server = false;
+        let session = RenderSession::from_file(
+            &job.scene_path,
+            &config,
+            job.id,
+            &output_path,
+            &job.overrides,
+        )?;
+        self.logs.insert(job.id, session.get_log());
+        self.workers.push(RenderQueueWorker {
+            job_id: job.id,
+            session,
+            output_path,
+            output_modified,
+        });
+        Ok(())
+    }
+
+    /// Advances the running jobs and starts queued ones; returns true if a job changed status.
+    pub fn update(&mut self, config: &AppConfig, max_workers: usize) -> bool {
+        let mut changed = false;
+        let mut results = Vec::new();
+        let mut records = Vec::new();
+        self.workers
+            .retain_mut(|worker| match worker.session.update() {
+                Ok(RenderState::Finished) => {
+                    let modified = get_modified(&worker.output_path);
+                    let status = if let Some(message) = worker.session.get_error() {
+                        (RenderJobStatus::Failed, message)
+                    } else if modified.is_some() && modified != worker.output_modified {
+                        let record = RenderRecord::new("", &worker.session, &worker.output_path);
+                        records.push((worker.job_id, record));
+                        (RenderJobStatus::Finished, String::new())
+                    } else {
+                        let message = "pbrt did not write an image".to_string();
+                        (RenderJobStatus::Failed, message)
+                    };
+                    results.push((worker.job_id, status));
+                    false
+                }
+                Ok(_) => true,
+                Err(e) => {
+                    results.push((worker.job_id, (RenderJobStatus::Failed, e.to_string())));
+                    false
+                }
+            });
+        for (id, mut record) in records {
+            if let Some(job) = self.jobs.iter().find(|job| job.id == id) {
+                record.name = job.name.clone();
+            }
+            self.records.push(record);
+        }
+        for (id, (status, message)) in results {
+            if let Some(job) = self.get_job_mut(id) {
+                job.status = status;
+                job.message = message;
+                changed = true;
+            }
+        }
+        if self.paused {
+            return changed;
+        }
+        while self.workers.len() < max_workers.max(1) {
+            let Some(index) = self.get_next_job() else {
+                break;
+            };
+            changed = true;
+            match self.start_job(index, config) {
+                Ok(_) => {
+                    self.jobs[index].status = RenderJobStatus::Running;
+                    self.jobs[index].message.clear();
+                }
+                Err(e) => {
+                    log::error!(
+                        "Failed to start render job {}: {}",
+                        self.jobs[index].name,
+                        e
+                    );
+                    self.jobs[index].status = RenderJobStatus::Failed;
+                    self.jobs[index].message = e.to_string();
+                }
+            }
+        }
+        return changed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_queue() -> RenderQueue {
+        let mut queue = RenderQueue::new();
+        for name in ["a", "b", "c"] {
+            queue.push(RenderJob::new(name, RenderJobSource::File, "a.pbrt"));
+        }
+        queue
+    }
+
+    #[test]
+    fn test_next_job_by_priority() {
+        let mut queue = make_queue();
+        assert_eq!(queue.get_next_job(), Some(0));
+        queue.jobs[2].priority = 1;
+        assert_eq!(queue.get_next_job(), Some(2));
+        queue.jobs[2].status = RenderJobStatus::Paused;
+        assert_eq!(queue.get_next_job(), Some(0));
+        let id = queue.jobs[1].id;
+        queue.move_job(id, -1);
+        assert_eq!(queue.jobs[0].name, "b");
+        assert_eq!(queue.get_next_job(), Some(0));
+        queue.move_job(id, -1);
+        assert_eq!(queue.jobs[0].name, "b");
+        queue.pause_job(queue.jobs[2].id, false);
+        queue.cancel_job(queue.jobs[2].id);
+        assert_eq!(queue.jobs[2].status, RenderJobStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_pause_running_job() {
+        let mut queue = make_queue();
+        let id = queue.jobs[0].id;
+        queue.jobs[0].status = RenderJobStatus::Running;
+        queue.pause_job(id, true);
+        assert_eq!(queue.jobs[0].status, RenderJobStatus::Paused);
+        assert!(!queue.jobs[0].message.is_empty());
+        queue.pause_job(id, false);
+        assert_eq!(queue.jobs[0].status, RenderJobStatus::Queued);
+        assert!(queue.jobs[0].message.is_empty());
+    }
+
+    #[test]
+    fn test_queue_round_trip() {
+        let mut queue = make_queue();
+        queue.paused = true;
+        queue.jobs[0].status = RenderJobStatus::Running;
+        queue.jobs[1].overrides.resolution = Some([640, 480]);
+        queue.jobs[1].overrides.integrator = Some("bdpt".to_string());
+        let json = serde_json::to_string(&queue).unwrap();
+        let loaded = RenderQueue::from_json(&json).unwrap();
+        assert!(loaded.paused);
+        assert_eq!(loaded.jobs.len(), 3);
+        assert_eq!(loaded.jobs[0].status, RenderJobStatus::Queued);
+        assert_eq!(loaded.jobs[1].overrides, queue.jobs[1].overrides);
+        assert_eq!(loaded.jobs[2].id, queue.jobs[2].id);
+    }
+
+    #[test]
+    fn test_names_after_reload() {
+        let mut queue = RenderQueue::new();
+        let first = queue.enqueue_file("room.pbrt");
+        queue.enqueue_file("room.pbrt");
+        queue.remove_job(first);
+        let json = serde_json::to_string(&queue).unwrap();
+        let mut loaded = RenderQueue::from_json(&json).unwrap();
+        loaded.enqueue_file("room.pbrt");
+        let names = loaded
+            .jobs
+            .iter()
+            .map(|job| job.name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["room_2", "room_3"]);
+
+        // Queues saved before the number was kept continue after their names
+        let json = json.replace("\"next_number\":2", "\"next_number\":0");
+        let mut loaded = RenderQueue::from_json(&json).unwrap();
+        loaded.enqueue_file("room.pbrt");
+        assert_eq!(loaded.jobs[1].name, "room_3");
+
+        // Default outputs differ between jobs even with the same name
+        let config = AppConfig::default();
+        let mut job = loaded.jobs[1].clone();
+        let output_path = job.get_output_path(&config);
+        job.id = Uuid::new_v4();
+        assert_ne!(job.get_output_path(&config), output_path);
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let node = Node::root_node("root");
+        let camera = Node::child_node("camera", &node);
+        camera
+            .write()
+            .unwrap()
+            .add_component(FilmComponent::new("rgb", &Default::default()));
+        node.write()
+            .unwrap()
+            .add_component(IntegratorComponent::new("path", &Default::default()));
+        let overrides = RenderJobOverrides {
+            resolution: Some([320, 240]),
+            integrator: Some("bdpt".to_string()),
+            ..Default::default()
+        };
+        let film = overrides.get_film_props(&node).unwrap();
+        assert_eq!(film.find_one_int("xresolution"), Some(320));
+        assert_eq!(film.find_one_int("yresolution"), Some(240));
+        let integrator = overrides.get_integrator_props(&node).unwrap();
+        assert_eq!(integrator.find_one_string("type"), Some("bdpt".to_string()));
+        assert!(overrides.get_sampler_props(&node).is_none());
+    }
+
+    #[test]
+    fn test_overrides_leave_the_scene_unchanged() {
+        let node = Node::root_node("root");
+        let mut props = PropertyMap::new();
+        props.add_ints("integer xresolution", &[800]);
+        props.add_ints("integer yresolution", &[600]);
+        node.write()
+            .unwrap()
+            .add_component(FilmComponent::new("rgb", &props));
+        let overrides = RenderJobOverrides {
+            resolution_scale: Some(0.25),
+            crop_window: Some([0.0, 0.5, 0.25, 1.0]),
+            ..Default::default()
+        };
+        assert_eq!(overrides.get_resolution(&node), [200, 150]);
+        let mut options = SavePbrtOptions::default();
+        overrides.set_save_options(&node, &mut options);
+        let film = options.film_props.unwrap();
+        assert_eq!(film.get_floats("cropwindow"), [0.0, 0.5, 0.25, 1.0]);
+        assert_eq!(get_film_resolution(&node), [800, 600]);
+        let node = node.read().unwrap();
+        let film = node.get_component::<FilmComponent>().unwrap();
+        assert!(film.props.get("cropwindow").is_none());
+    }
+}
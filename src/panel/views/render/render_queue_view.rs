@@ -0,0 +1,321 @@
+use super::render_log_view::get_progress_text;
+use super::render_log_view::show_render_log;
+use super::render_queue::RenderJob;
+use super::render_queue::RenderJobOverrides;
+use super::render_queue::RenderJobSource;
+use super::render_queue::RenderJobStatus;
+use super::render_queue::RenderQueue;
+use crate::model::config::AppConfig;
+use crate::model::scene::IntegratorProperties;
+use crate::model::scene::Node;
+
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use eframe::egui;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QueueCommand {
+    Move(Uuid, i32),
+    Pause(Uuid, bool),
+    Cancel(Uuid),
+    Retry(Uuid),
+    Remove(Uuid),
+}
+
+fn get_status_color(ui: &egui::Ui, status: RenderJobStatus) -> egui::Color32 {
+    match status {
+        RenderJobStatus::Running => ui.visuals().hyperlink_color,
+        RenderJobStatus::Failed => ui.visuals().error_fg_color,
+        RenderJobStatus::Paused | RenderJobStatus::Cancelled => ui.visuals().weak_text_color(),
+        _ => ui.visuals().text_color(),
+    }
+}
+
+/// Checkbox that turns an override on (with `default`) or off, followed by its editor.
+fn show_override<T: Clone>(
+    ui: &mut egui::Ui,
+    label: &str,
+    value: &mut Option<T>,
+    default: T,
+    add_contents: impl FnOnce(&mut egui::Ui, &mut T) -> bool,
+) -> bool {
+    let mut changed = false;
+    let mut enabled = value.is_some();
+    if ui.checkbox(&mut enabled, label).changed() {
+        *value = if enabled { Some(default) } else { None };
+        changed = true;
+    }
+    if let Some(value) = value.as_mut() {
+        changed |= add_contents(ui, value);
+    }
+    ui.end_row();
+    return changed;
+}
+
+fn show_overrides(ui: &mut egui::Ui, id: Uuid, overrides: &mut RenderJobOverrides) -> bool {
+    let mut changed = false;
+    egui::Grid::new(("render_job_overrides", id))
+        .num_columns(2)
+        .show(ui, |ui| {
+            changed |= show_override(
+                ui,
+                "Resolution",
+                &mut overrides.resolution,
+                [1280, 720],
+                |ui, [width, height]| {
+                    ui.horizontal(|ui| {
+                        let a = ui.add(egui::DragValue::new(width).range(1..=16384));
+                        ui.label("x");
+                        let b = ui.add(egui::DragValue::new(height).range(1..=16384));
+                        a.changed() || b.changed()
+                    })
+                    .inner
+                },
+            );
+            changed |= show_override(
+                ui,
+                "Samples",
+                &mut overrides.pixel_samples,
+                16,
+                |ui, spp| {
+                    ui.add(egui::DragValue::new(spp).range(1..=1000000))
+                        .changed()
+                },
+            );
+            changed |= show_override(
+                ui,
+                "Integrator",
+                &mut overrides.integrator,
+                "path".to_string(),
+                |ui, integrator| {
+                    let mut changed = false;
+                    egui::ComboBox::from_id_salt(("render_job_integrator", id))
+                        .selected_text(integrator.as_str())
+                        .show_ui(ui, |ui| {
+                            let properties = IntegratorProperties::get_instance();
+                            for t in properties.get_types().iter() {
+                                changed |= ui.selectable_value(integrator, t.clone(), t).changed();
+                            }
+                        });
+                    changed
+                },
+            );
+            changed |= show_override(
+                ui,
+                "Output",
+                &mut overrides.output_path,
+                String::new(),
+                |ui, path| {
+                    ui.horizontal(|ui| {
+                        let mut changed = ui.text_edit_singleline(path).changed();
+                        if ui.button("…").clicked() {
+                            if let Some(new_path) = rfd::FileDialog::new()
+                                .set_title("Select Output Path")
+                                .add_filter("EXR", &["exr"])
+                                .save_file()
+                            {
+                                *path = new_path.to_str().unwrap_or("").to_string();
+                                changed = true;
+                            }
+                        }
+                        changed
+                    })
+                    .inner
+                },
+            );
+        });
+    return changed;
+}
+
+fn show_job(
+    ui: &mut egui::Ui,
+    queue: &RenderQueue,
+    job: &mut RenderJob,
+    selected: &mut Option<Uuid>,
+    commands: &mut Vec<QueueCommand>,
+) -> bool {
+    let mut changed = false;
+    let id = job.id;
+    ui.horizontal(|ui| {
+        let is_selected = *selected == Some(id);
+        let source = match job.source {
+            RenderJobSource::Scene => "🎬",
+            RenderJobSource::File => "📄",
+        };
+        if ui
+            .selectable_label(is_selected, format!("{} {}", source, job.name))
+            .on_hover_text(&job.scene_path)
+            .clicked()
+        {
+            *selected = if is_selected { None } else { Some(id) };
+        }
+        let status = ui.colored_label(
+            get_status_color(ui, job.status),
+            format!("{:?}", job.status),
+        );
+        if !job.message.is_empty() {
+            status.on_hover_text(&job.message);
+        }
+        if job.status == RenderJobStatus::Running {
+            let progress = queue
+                .get_log(id)
+                .and_then(|log| log.lock().unwrap().progress.clone());
+            let fraction = progress.as_ref().map(|p| p.fraction).unwrap_or(0.0);
+            let bar = ui.add(egui::ProgressBar::new(fraction).desired_width(80.0));
+            if let Some(progress) = progress.as_ref() {
+                bar.on_hover_text(get_progress_text(progress));
+            }
+        }
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if job.status.is_done() {
+                if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                    commands.push(QueueCommand::Remove(id));
+                }
+                if ui.small_button("↺").on_hover_text("Render again").clicked() {
+                    commands.push(QueueCommand::Retry(id));
+                }
+            } else {
+                if ui.small_button("⏹").on_hover_text("Cancel").clicked() {
+                    commands.push(QueueCommand::Cancel(id));
+                }
+                match job.status {
+                    RenderJobStatus::Queued => {
+                        if ui.small_button("⏸").on_hover_text("Pause").clicked() {
+                            commands.push(QueueCommand::Pause(id, true));
+                        }
+                    }
+                    RenderJobStatus::Running => {
+                        let text = "Stop and pause, renders from the start when resumed";
+                        if ui.small_button("⏸").on_hover_text(text).clicked() {
+                            commands.push(QueueCommand::Pause(id, true));
+                        }
+                    }
+                    RenderJobStatus::Paused => {
+                        if ui.small_button("▶").on_hover_text("Resume").clicked() {
+                            commands.push(QueueCommand::Pause(id, false));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if ui.small_button("⏷").on_hover_text("Move down").clicked() {
+                commands.push(QueueCommand::Move(id, 1));
+            }
+            if ui.small_button("⏶").on_hover_text("Move up").clicked() {
+                commands.push(QueueCommand::Move(id, -1));
+            }
+            changed |= ui
+                .add(egui::DragValue::new(&mut job.priority).prefix("P "))
+                .on_hover_text("Priority; higher runs first")
+                .changed();
+        });
+    });
+    let editable = matches!(
+        job.status,
+        RenderJobStatus::Queued | RenderJobStatus::Paused
+    );
+    if *selected == Some(id) {
+        ui.add_enabled_ui(editable, |ui| {
+            changed |= show_overrides(ui, id, &mut job.overrides);
+        });
+    }
+    return changed;
+}
+
+/// Queue list with its controls; returns true if the queue should be saved.
+pub fn show_render_queue(
+    ui: &mut egui::Ui,
+    queue: &mut RenderQueue,
+    node: &Arc<RwLock<Node>>,
+    config: &mut AppConfig,
+    selected: &mut Option<Uuid>,
+) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        if ui
+            .button("+ Scene")
+            .on_hover_text("Queue a snapshot of the current scene")
+            .clicked()
+        {
+            match queue.enqueue_scene(node) {
+                Ok(id) => *selected = Some(id),
+                Err(e) => log::error!("Failed to queue the scene: {}", e),
+            }
+            changed = true;
+        }
+        if ui
+            .button("+ File")
+            .on_hover_text("Queue .pbrt files")
+            .clicked()
+        {
+            if let Some(paths) = rfd::FileDialog::new()
+                .set_title("Queue PBRT Files")
+                .add_filter("PBRT", &["pbrt", "pbrt.gz"])
+                .pick_files()
+            {
+                for path in paths.iter() {
+                    queue.enqueue_file(path.to_str().unwrap_or(""));
+                }
+                changed = true;
+            }
+        }
+        ui.separator();
+        let (text, hover) = if queue.paused {
+            ("▶ Start", "Start rendering the queue")
+        } else {
+            ("⏸ Pause", "Do not start more jobs")
+        };
+        if ui.button(text).on_hover_text(hover).clicked() {
+            queue.paused = !queue.paused;
+            changed = true;
+        }
+        ui.label("Workers");
+        ui.add(egui::DragValue::new(&mut config.render_queue_workers).range(1..=16))
+            .on_hover_text("Number of renders running at once");
+        if ui
+            .button("Clear")
+            .on_hover_text("Remove done jobs")
+            .clicked()
+        {
+            queue.remove_done_jobs();
+            changed = true;
+        }
+    });
+    ui.separator();
+    let mut commands = Vec::new();
+    egui::ScrollArea::vertical()
+        .id_salt("render_queue_scroll")
+        .max_height(ui.available_height() * 0.6)
+        .show(ui, |ui| {
+            if queue.jobs.is_empty() {
+                ui.label("No jobs");
+            }
+            let mut jobs = std::mem::take(&mut queue.jobs);
+            for job in jobs.iter_mut() {
+                changed |= show_job(ui, queue, job, selected, &mut commands);
+            }
+            queue.jobs = jobs;
+        });
+    for cmd in commands {
+        match cmd {
+            QueueCommand::Move(id, offset) => queue.move_job(id, offset),
+            QueueCommand::Pause(id, paused) => queue.pause_job(id, paused),
+            QueueCommand::Cancel(id) => queue.cancel_job(id),
+            QueueCommand::Retry(id) => queue.retry_job(id),
+            QueueCommand::Remove(id) => {
+                queue.remove_job(id);
+                if *selected == Some(id) {
+                    *selected = None;
+                }
+            }
+        }
+        changed = true;
+    }
+    if let Some(log) = selected.and_then(|id| queue.get_log(id)) {
+        ui.separator();
+        show_render_log(ui, &log.lock().unwrap());
+    }
+    return changed;
+}
@@ -0,0 +1,179 @@
+use super::image_data::ImageData;
+use super::render_queue::RenderJobOverrides;
+
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// Quality of a render from the render button; the lower ones only change the exported file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderPreset {
+    #[default]
+    Final,
+    Preview, // Half resolution, few samples
+    Draft,   // Quarter resolution, very few samples
+}
+
+impl RenderPreset {
+    pub fn all() -> &'static [RenderPreset] {
+        &[
+            RenderPreset::Final,
+            RenderPreset::Preview,
+            RenderPreset::Draft,
+        ]
+    }
+
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            RenderPreset::Final => "Final",
+            RenderPreset::Preview => "Preview",
+            RenderPreset::Draft => "Draft",
+        }
+    }
+
+    pub fn get_description(&self) -> &'static str {
+        match self {
+            RenderPreset::Final => "Settings of the scene",
+            RenderPreset::Preview => "1/2 resolution, 16 samples per pixel",
+            RenderPreset::Draft => "1/4 resolution, 4 samples per pixel",
+        }
+    }
+
+    pub fn get_overrides(&self) -> RenderJobOverrides {
+        let (resolution_scale, pixel_samples) = match self {
+            RenderPreset::Final => return RenderJobOverrides::default(),
+            RenderPreset::Preview => (0.5, 16),
+            RenderPreset::Draft => (0.25, 4),
+        };
+        RenderJobOverrides {
+            resolution_scale: Some(resolution_scale),
+            pixel_samples: Some(pixel_samples),
+            ..Default::default()
+        }
+    }
+}
+
+/// Crop window of the rectangle between two points in [0, 1] image coordinates.
+pub fn get_crop_window(a: [f32; 2], b: [f32; 2]) -> Option<[f32; 4]> {
+    let [x0, x1] = [a[0].min(b[0]), a[0].max(b[0])].map(|v| v.clamp(0.0, 1.0));
+    let [y0, y1] = [a[1].min(b[1]), a[1].max(b[1])].map(|v| v.clamp(0.0, 1.0));
+    if x1 - x0 <= 0.0 || y1 - y0 <= 0.0 {
+        return None;
+    }
+    Some([x0, x1, y0, y1])
+}
+
+/// Pixels pbrt renders for a crop window, [x0, y0, x1, y1) as in its film.
+pub fn get_crop_pixel_bounds(crop_window: [f32; 4], width: usize, height: usize) -> [usize; 4] {
+    let [x0, x1, y0, y1] = crop_window;
+    let to_pixel = |v: f32, size: usize| ((size as f32 * v).ceil() as usize).min(size);
+    [
+        to_pixel(x0, width),
+        to_pixel(y0, height),
+        to_pixel(x1, width),
+        to_pixel(y1, height),
+    ]
+}
+
+/// A crop window render and the image it is shown over.
+#[derive(Debug, Clone)]
+pub struct RenderRegion {
+    pub crop_window: [f32; 4],
+    pub full_size: [usize; 2], // Film resolution of the region render
+    pub base: Option<Arc<Mutex<ImageData>>>,
+}
+
+/// Puts the region render over the base image, scaled to the full size when it differs.
+pub fn composite_region(
+    base: Option<&ImageData>,
+    region: &ImageData,
+    crop_window: [f32; 4],
+    full_size: [usize; 2],
+) -> ImageData {
+    let [width, height] = full_size;
+    let n_channels = region.channel_names.len().max(1);
+    let mut image = ImageData::new(region.name.clone(), width, height, &region.channel_names);
+    if let Some(base) = base.filter(|base| base.channel_names == region.channel_names) {
+        if base.width > 0 && base.height > 0 {
+            for y in 0..height {
+                let sy = (y * base.height / height).min(base.height - 1);
+                for x in 0..width {
+                    let sx = (x * base.width / width).min(base.width - 1);
+                    let src = (sy * base.width + sx) * n_channels;
+                    let dst = (y * width + x) * n_channels;
+                    image.data[dst..dst + n_channels]
+                        .copy_from_slice(&base.data[src..src + n_channels]);
+                }
+            }
+        }
+    }
+    let [x0, y0, x1, y1] = get_crop_pixel_bounds(crop_window, width, height);
+    // pbrt writes only the cropped pixels, some formats keep the full size
+    let (offset_x, offset_y) = if region.width == width && region.height == height {
+        (0, 0)
+    } else {
+        (x0, y0)
+    };
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let (rx, ry) = (x - offset_x, y - offset_y);
+            if rx >= region.width || ry >= region.height {
+                continue;
+            }
+            let src = (ry * region.width + rx) * n_channels;
+            let dst = (y * width + x) * n_channels;
+            image.data[dst..dst + n_channels].copy_from_slice(&region.data[src..src + n_channels]);
+        }
+    }
+    image.tiles.push((0, 0, width, height));
+    return image;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_image(width: usize, height: usize, value: f32) -> ImageData {
+        let names = vec!["Y".to_string()];
+        let mut image = ImageData::new("test".to_string(), width, height, &names);
+        image.data.fill(value);
+        image
+    }
+
+    #[test]
+    fn test_crop_window() {
+        assert_eq!(
+            get_crop_window([0.75, 0.5], [0.25, 1.5]),
+            Some([0.25, 0.75, 0.5, 1.0])
+        );
+        assert_eq!(get_crop_window([0.5, 0.0], [0.5, 1.0]), None);
+        assert_eq!(
+            get_crop_pixel_bounds([0.25, 0.75, 0.5, 1.0], 10, 4),
+            [3, 2, 8, 4]
+        );
+    }
+
+    #[test]
+    fn test_composite_region() {
+        let crop_window = [0.5, 1.0, 0.0, 0.5];
+        let base = make_image(2, 2, 1.0);
+        let region = make_image(2, 1, 5.0);
+        let image = composite_region(Some(&base), &region, crop_window, [4, 2]);
+        assert_eq!(image.data, [1.0, 1.0, 5.0, 5.0, 1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(image.tiles, [(0, 0, 4, 2)]);
+        // A full size region keeps its pixels in place
+        let region = make_image(4, 2, 5.0);
+        let image = composite_region(None, &region, crop_window, [4, 2]);
+        assert_eq!(image.data, [0.0, 0.0, 5.0, 5.0, 0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_preset_overrides() {
+        assert_eq!(
+            RenderPreset::Final.get_overrides(),
+            RenderJobOverrides::default()
+        );
+        let overrides = RenderPreset::Draft.get_overrides();
+        assert_eq!(overrides.resolution_scale, Some(0.25));
+        assert_eq!(overrides.pixel_samples, Some(4));
+    }
+}
@@ -1,5 +1,7 @@
 use super::image_data::ImageData;
 use super::image_receiver::ImageReceiver;
+use super::render_log::RenderLog;
+use super::render_queue::RenderJobOverrides;
 use super::render_state::*;
 use crate::model::scene::Node;
 use crate::model::scene::SceneComponent;
@@ -9,6 +11,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::RwLock;
+use std::time::SystemTime;
 
 use crypto::digest::Digest;
 use dirs;
@@ -46,30 +49,170 @@ fn scene_cache_dir(path: Option<String>) -> PathBuf {
     cache_dir
 }
 
+/// The directory an agent session exports its scene to, removed when the last owner drops it.
+/// Records of the session share it until their snapshot is stored.
+#[derive(Debug, PartialEq)]
+pub struct SessionDir(PathBuf);
+
+impl SessionDir {
+    pub fn new(path: &std::path::Path) -> Self {
+        Self(path.to_path_buf())
+    }
+}
+
+impl Drop for SessionDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("Failed to remove {}: {}", self.0.display(), e);
+            }
+        }
+    }
+}
+
 pub struct RenderSession {
     state: RenderState,
     tasks: HashMap<RenderState, Box<dyn RenderTask>>,
     receiver: Option<ImageReceiver>,
+    log: Arc<Mutex<RenderLog>>,
+    scene_path: Option<String>,
+    pbrt_path: String,
+    command_line: Vec<String>,
+    settings: Arc<Mutex<Vec<(String, String)>>>, // Filled in by the saving task of a scene file
+    session_dir: Option<Arc<SessionDir>>,
+    started: SystemTime,
+    finished: Option<SystemTime>,
 }
 
-impl RenderSession {
-    pub fn new(
-        node: &Arc<RwLock<Node>>,
+struct SessionPaths {
+    execute_path: String,
+    pbrt_path: String,
+    image_path: String,
+    output_image_path: String,
+    shape_cache_dir: Option<String>,
+    session_dir: Option<PathBuf>,
+}
+
+impl SessionPaths {
+    fn new(
+        scene_path: Option<String>,
         config: &AppConfig,
         session_id: Uuid,
         output_image_path: &str,
-    ) -> Result<RenderSession, PbrtError> {
-        let cache_dir = scene_cache_dir(get_file_path(node));
+        use_shape_cache: bool,
+    ) -> Self {
+        let cache_dir = scene_cache_dir(scene_path);
 
         let execute_path = config.pbrt_executable_path.clone();
-        let pbrt_path = cache_dir.join(format!("{}.pbrt", session_id)); //
+        // Everything next to the scene is sent to the render agent, so it gets a directory
+        let session_dir = if config.enable_render_agent {
+            Some(cache_dir.join(session_id.to_string()))
+        } else {
+            None
+        };
+        let pbrt_path = session_dir
+            .as_ref()
+            .unwrap_or(&cache_dir)
+            .join(format!("{}.pbrt", session_id)); //
         let image_path = cache_dir.join(format!("{}.exr", session_id)); //
         let output_image_path = PathBuf::from(output_image_path); //
+        // Meshes are kept between interactive passes so unchanged ones are not exported again
+        let shape_cache_dir = cache_dir.join("shapes");
 
         let execute_path = execute_path.to_str().unwrap().to_string();
         let pbrt_path = pbrt_path.to_str().unwrap().to_string();
         let image_path = image_path.to_str().unwrap().to_string();
         let output_image_path = output_image_path.to_str().unwrap().to_string();
+        // Included shape files live outside of the scene directory, other renders are self-contained
+        let shape_cache_dir = if !use_shape_cache || config.enable_render_agent {
+            None
+        } else {
+            shape_cache_dir.to_str().map(|s| s.to_string())
+        };
+        Self {
+            execute_path,
+            pbrt_path,
+            image_path,
+            output_image_path,
+            shape_cache_dir,
+            session_dir,
+        }
+    }
+}
+
+impl RenderSession {
+    pub fn new(
+        node: &Arc<RwLock<Node>>,
+        config: &AppConfig,
+        session_id: Uuid,
+        output_image_path: &str,
+        overrides: &RenderJobOverrides,
+        is_interactive: bool,
+    ) -> Result<RenderSession, PbrtError> {
+        let scene_path = get_file_path(node);
+        let paths = SessionPaths::new(
+            scene_path.clone(),
+            config,
+            session_id,
+            output_image_path,
+            is_interactive,
+        );
+        let settings = overrides.get_render_settings(node);
+        let settings = Arc::new(Mutex::new(settings));
+        let saving_task = Box::new(SavingRenderTask::new(
+            node.clone(),
+            &paths.pbrt_path,
+            overrides,
+            paths.shape_cache_dir.clone(),
+        ));
+        return Self::start(paths, saving_task, config, scene_path, settings);
+    }
+
+    /// Renders a scene file; it is loaded and exported on a thread instead of the caller's.
+    pub fn from_file(
+        scene_file: &str,
+        config: &AppConfig,
+        session_id: Uuid,
+        output_image_path: &str,
+        overrides: &RenderJobOverrides,
+    ) -> Result<RenderSession, PbrtError> {
+        let scene_path = std::fs::canonicalize(scene_file)
+            .ok()
+            .and_then(|path| path.to_str().map(|s| s.to_string()))
+            .unwrap_or(scene_file.to_string());
+        let paths = SessionPaths::new(
+            Some(scene_path.clone()),
+            config,
+            session_id,
+            output_image_path,
+            false,
+        );
+        let settings = Arc::new(Mutex::new(Vec::new()));
+        let loading_task = Box::new(LoadingRenderTask::new(
+            scene_file,
+            &paths.pbrt_path,
+            overrides,
+            &settings,
+        ));
+        return Self::start(paths, loading_task, config, Some(scene_path), settings);
+    }
+
+    fn start(
+        paths: SessionPaths,
+        saving_task: Box<dyn RenderTask>,
+        config: &AppConfig,
+        scene_path: Option<String>,
+        settings: Arc<Mutex<Vec<(String, String)>>>,
+    ) -> Result<RenderSession, PbrtError> {
+        let SessionPaths {
+            execute_path,
+            pbrt_path,
+            image_path,
+            output_image_path,
+            session_dir,
+            ..
+        } = paths;
+        let session_dir = session_dir.map(|dir| Arc::new(SessionDir::new(&dir)));
 
         let display_server = if config.enable_display_server {
             Some((
@@ -89,26 +232,37 @@ impl RenderSession {
             None
         };
 
+        let command_line =
+            get_command_line(&execute_path, &pbrt_path, &image_path, &display_server);
+
+        let log = Arc::new(Mutex::new(RenderLog::new()));
         let mut tasks: HashMap<RenderState, Box<dyn RenderTask>> = HashMap::new();
         {
             // Initialize tasks
             tasks.insert(RenderState::Ready, Box::new(ReadyRenderTask::new()));
             // Saving phase
-            tasks.insert(
-                RenderState::Saving,
-                Box::new(SavingRenderTask::new(node.clone(), &pbrt_path)),
-            );
+            tasks.insert(RenderState::Saving, saving_task);
             // Rendering phase
 
-            tasks.insert(
-                RenderState::Rendering,
+            let rendering_task: Box<dyn RenderTask> = if config.enable_render_agent {
+                Box::new(RemoteRenderingRenderTask::new(
+                    &config.render_agent_address,
+                    &config.render_agent_token,
+                    &pbrt_path,
+                    &image_path,
+                    &display_server,
+                    &log,
+                ))
+            } else {
                 Box::new(RenderingRenderTask::new(
                     &execute_path,
                     &pbrt_path,
                     &image_path,
                     &display_server,
-                )),
-            );
+                    &log,
+                ))
+            };
+            tasks.insert(RenderState::Rendering, rendering_task);
             // Finishing phase
             tasks.insert(
                 RenderState::Finishing,
@@ -125,6 +279,14 @@ impl RenderSession {
             state: RenderState::Saving,
             tasks: tasks,
             receiver: image_receiver,
+            log,
+            scene_path,
+            pbrt_path,
+            command_line,
+            settings,
+            session_dir,
+            started: SystemTime::now(),
+            finished: None,
         });
     }
 
@@ -147,6 +309,9 @@ impl RenderSession {
                 //println!("Entered state: {:?}", self.state);
             }
             self.state = next_state;
+            if next_state == RenderState::Finished && self.finished.is_none() {
+                self.finished = Some(SystemTime::now());
+            }
         }
         Ok(self.state)
     }
@@ -162,6 +327,47 @@ impl RenderSession {
         Ok(())
     }
 
+    pub fn get_log(&self) -> Arc<Mutex<RenderLog>> {
+        self.log.clone()
+    }
+
+    /// Scene file the render was made from, if the scene was loaded from one.
+    pub fn get_scene_path(&self) -> Option<String> {
+        self.scene_path.clone()
+    }
+
+    /// The exported scene that pbrt renders.
+    pub fn get_pbrt_path(&self) -> String {
+        self.pbrt_path.clone()
+    }
+
+    /// The directory of the exported scene, if it is only kept for this session.
+    pub fn get_session_dir(&self) -> Option<Arc<SessionDir>> {
+        self.session_dir.clone()
+    }
+
+    pub fn get_command_line(&self) -> Vec<String> {
+        self.command_line.clone()
+    }
+
+    pub fn get_settings(&self) -> Vec<(String, String)> {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// Why pbrt failed, if it did; the session still runs to `Finished`.
+    pub fn get_error(&self) -> Option<String> {
+        let task = self.tasks.get(&RenderState::Rendering)?;
+        return task.get_error();
+    }
+
+    /// Seconds from the start of the session to its end, or to now while it runs.
+    pub fn get_duration(&self) -> f32 {
+        let end = self.finished.unwrap_or(SystemTime::now());
+        end.duration_since(self.started)
+            .map(|d| d.as_secs_f32())
+            .unwrap_or(0.0)
+    }
+
     pub fn get_image_data(&self) -> Option<Arc<Mutex<ImageData>>> {
         if let Some(receiver) = self.receiver.as_ref() {
             return receiver.get_image_data();
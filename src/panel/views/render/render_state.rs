@@ -1,9 +1,21 @@
+use super::render_log::RenderLog;
+use super::render_log::RenderLogLevel;
+use super::render_log::RenderLogLine;
+use super::render_log::spawn_log_reader;
+use super::render_queue::RenderJobOverrides;
 use crate::error::PbrtError;
 use crate::io::export::pbrt::*;
+use crate::io::import::pbrt::load_pbrt;
+use crate::io::remote::*;
 use crate::model::scene::Node;
 
+use std::io::Write;
+use std::net::TcpStream;
+use std::process::Stdio;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::sync::RwLock;
+use std::thread;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum RenderState {
@@ -14,13 +26,41 @@ pub enum RenderState {
     Finished,
 }
 
-fn save_pbrt_file(node: &Arc<RwLock<Node>>, pbrt_path: &str) -> Result<(), PbrtError> {
+fn save_pbrt_file(
+    node: &Arc<RwLock<Node>>,
+    pbrt_path: &str,
+    overrides: &RenderJobOverrides,
+    shape_cache_dir: &Option<String>,
+) -> Result<(), PbrtError> {
     let mut options = SavePbrtOptions::default();
     options.pretty_print = false;
+    options.shape_cache_dir = shape_cache_dir.clone();
+    // Overrides go to the exported file only, the scene being edited is unchanged
+    overrides.set_save_options(node, &mut options);
     save_pbrt(node, pbrt_path, &options)?;
     Ok(())
 }
 
+/// Arguments of the pbrt process, the executable first.
+pub fn get_command_line(
+    execute_path: &str,
+    pbrt_path: &str,
+    output_path: &str,
+    display_server: &Option<(String, u16)>,
+) -> Vec<String> {
+    let mut command_line = vec![
+        execute_path.to_string(),
+        pbrt_path.to_string(),
+        "--outfile".to_string(),
+        output_path.to_string(),
+    ];
+    if let Some((hostname, port)) = display_server {
+        command_line.push("--display-server".to_string());
+        command_line.push(format!("{}:{}", hostname, port));
+    }
+    return command_line;
+}
+
 pub trait RenderTask {
     fn get_state(&self) -> RenderState;
     fn enter(&mut self) -> Result<(), PbrtError> {
@@ -35,6 +75,18 @@ pub trait RenderTask {
     fn cancel(&mut self) -> Result<(), PbrtError> {
         Ok(())
     }
+    fn get_error(&self) -> Option<String> {
+        None
+    }
+}
+
+// An image left by an earlier render must not pass for the output of this one
+fn remove_old_image(path: &str) -> Result<(), PbrtError> {
+    let path = std::path::Path::new(path);
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
 }
 
 pub struct ReadyRenderTask {}
@@ -54,11 +106,24 @@ impl RenderTask for ReadyRenderTask {
 pub struct SavingRenderTask {
     node: Arc<RwLock<Node>>,
     pbrt_path: String,
+    overrides: RenderJobOverrides,
+    shape_cache_dir: Option<String>,
 }
 impl SavingRenderTask {
-    pub fn new(node: Arc<RwLock<Node>>, pbrt_path: &str) -> Self {
+    pub fn new(
+        node: Arc<RwLock<Node>>,
+        pbrt_path: &str,
+        overrides: &RenderJobOverrides,
+        shape_cache_dir: Option<String>,
+    ) -> Self {
         let pbrt_path = pbrt_path.to_string();
-        Self { node, pbrt_path }
+        let overrides = overrides.clone();
+        Self {
+            node,
+            pbrt_path,
+            overrides,
+            shape_cache_dir,
+        }
     }
 }
 impl RenderTask for SavingRenderTask {
@@ -72,19 +137,88 @@ impl RenderTask for SavingRenderTask {
             let pbrt_path = std::path::Path::new(&pbrt_path);
             std::fs::create_dir_all(pbrt_path.parent().unwrap())?;
         }
-        save_pbrt_file(&self.node, &self.pbrt_path)?;
+        save_pbrt_file(
+            &self.node,
+            &self.pbrt_path,
+            &self.overrides,
+            &self.shape_cache_dir,
+        )?;
+        Ok(())
+    }
+    fn update(&mut self) -> Result<RenderState, PbrtError> {
+        Ok(RenderState::Rendering)
+    }
+}
+type LoadingResult = Result<Vec<(String, String)>, PbrtError>;
+
+/// Saving phase for a scene file: it is loaded and exported again with the overrides on a thread.
+pub struct LoadingRenderTask {
+    scene_file: String,
+    pbrt_path: String,
+    overrides: RenderJobOverrides,
+    settings: Arc<Mutex<Vec<(String, String)>>>,
+    handle: Option<thread::JoinHandle<LoadingResult>>,
+}
+impl LoadingRenderTask {
+    pub fn new(
+        scene_file: &str,
+        pbrt_path: &str,
+        overrides: &RenderJobOverrides,
+        settings: &Arc<Mutex<Vec<(String, String)>>>,
+    ) -> Self {
+        Self {
+            scene_file: scene_file.to_string(),
+            pbrt_path: pbrt_path.to_string(),
+            overrides: overrides.clone(),
+            settings: settings.clone(),
+            handle: None,
+        }
+    }
+}
+impl RenderTask for LoadingRenderTask {
+    fn get_state(&self) -> RenderState {
+        RenderState::Saving
+    }
+    fn enter(&mut self) -> Result<(), PbrtError> {
+        log::info!("Entering saving state with scene file: {}", self.scene_file);
+        let scene_file = self.scene_file.clone();
+        let pbrt_path = self.pbrt_path.clone();
+        let overrides = self.overrides.clone();
+        self.handle = Some(thread::spawn(move || {
+            let node = load_pbrt(&scene_file)?;
+            if let Some(parent) = std::path::Path::new(&pbrt_path).parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            save_pbrt_file(&node, &pbrt_path, &overrides, &None)?;
+            let settings = overrides.get_render_settings(&node);
+            Ok(settings)
+        }));
         Ok(())
     }
     fn update(&mut self) -> Result<RenderState, PbrtError> {
+        let Some(handle) = self.handle.as_ref() else {
+            return Ok(RenderState::Rendering);
+        };
+        if !handle.is_finished() {
+            return Ok(RenderState::Saving);
+        }
+        let handle = self.handle.take().unwrap();
+        let settings = handle
+            .join()
+            .map_err(|_| PbrtError::error("Scene loading thread panicked"))??;
+        *self.settings.lock().unwrap() = settings;
         Ok(RenderState::Rendering)
     }
 }
+
 pub struct RenderingRenderTask {
     execute_path: String,
     pbrt_path: String,
     output_path: String,
     display_server: Option<(String, u16)>,
     child: Option<std::process::Child>,
+    log: Arc<Mutex<RenderLog>>,
+    error: Option<String>,
 }
 
 impl RenderingRenderTask {
@@ -93,6 +227,7 @@ impl RenderingRenderTask {
         pbrt_path: &str,
         output_path: &str,
         display_server: &Option<(String, u16)>,
+        log: &Arc<Mutex<RenderLog>>,
     ) -> Self {
         let execute_path = execute_path.to_string();
         let pbrt_path = pbrt_path.to_string();
@@ -103,6 +238,8 @@ impl RenderingRenderTask {
             output_path,
             display_server: display_server.clone(),
             child: None,
+            log: log.clone(),
+            error: None,
         }
     }
 }
@@ -117,19 +254,26 @@ impl RenderTask for RenderingRenderTask {
             self.pbrt_path
         );
 
-        let execute_path = self.execute_path.clone();
-        let pbrt_path = self.pbrt_path.clone();
-        let output_path = self.output_path.clone();
-
-        let mut command = std::process::Command::new(execute_path);
+        let command_line = get_command_line(
+            &self.execute_path,
+            &self.pbrt_path,
+            &self.output_path,
+            &self.display_server,
+        );
+        remove_old_image(&self.output_path)?;
+        let mut command = std::process::Command::new(&command_line[0]);
         //.arg("-v") // Optional: quiet mode
         //.arg("-i")
-        command.arg(pbrt_path).arg("--outfile").arg(output_path);
-        if let Some((hostname, port)) = &self.display_server {
-            let display_server = format!("{}:{}", hostname, port);
-            command.arg("--display-server").arg(display_server);
+        command.args(&command_line[1..]);
+        // pbrt's progress, warnings and statistics are shown in the log view
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let mut child = command.spawn()?;
+        if let Some(stdout) = child.stdout.take() {
+            spawn_log_reader(stdout, self.log.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            spawn_log_reader(stderr, self.log.clone());
         }
-        let child = command.spawn()?;
         self.child = Some(child);
         Ok(())
     }
@@ -145,6 +289,13 @@ impl RenderTask for RenderingRenderTask {
                         return Ok(RenderState::Finishing);
                     } else {
                         log::error!("Rendering failed with status: {:?}", status);
+                        let text = format!("pbrt exited with {}", status);
+                        self.log.lock().unwrap().push(RenderLogLine {
+                            level: RenderLogLevel::Error,
+                            text: text.clone(),
+                            location: None,
+                        });
+                        self.error = Some(text);
                         return Ok(RenderState::Finishing);
                         //return Err(PbrtError::error("Rendering process failed"));
                     }
@@ -177,6 +328,10 @@ impl RenderTask for RenderingRenderTask {
         log::info!("Exiting rendering state");
         Ok(())
     }
+
+    fn get_error(&self) -> Option<String> {
+        self.error.clone()
+    }
 }
 
 impl Drop for RenderingRenderTask {
@@ -191,6 +346,143 @@ impl Drop for RenderingRenderTask {
     }
 }
 
+/// Renders on a render agent: the directory of the exported scene is sent as a package,
+/// display server tiles are passed on to the local image receiver and the image is written back.
+pub struct RemoteRenderingRenderTask {
+    address: String,
+    token: String,
+    pbrt_path: String,
+    output_path: String,
+    display_server: Option<(String, u16)>,
+    job: Option<RemoteJob>,
+    log: Arc<Mutex<RenderLog>>,
+    error: Option<String>,
+}
+
+impl RemoteRenderingRenderTask {
+    pub fn new(
+        address: &str,
+        token: &str,
+        pbrt_path: &str,
+        output_path: &str,
+        display_server: &Option<(String, u16)>,
+        log: &Arc<Mutex<RenderLog>>,
+    ) -> Self {
+        Self {
+            address: address.to_string(),
+            token: token.to_string(),
+            pbrt_path: pbrt_path.to_string(),
+            output_path: output_path.to_string(),
+            display_server: display_server.clone(),
+            job: None,
+            log: log.clone(),
+            error: None,
+        }
+    }
+
+    fn push_error(&mut self, text: &str) {
+        self.log.lock().unwrap().push(RenderLogLine {
+            level: RenderLogLevel::Error,
+            text: text.to_string(),
+            location: None,
+        });
+        self.error = Some(text.to_string());
+    }
+}
+
+impl RenderTask for RemoteRenderingRenderTask {
+    fn get_state(&self) -> RenderState {
+        RenderState::Rendering
+    }
+    fn enter(&mut self) -> Result<(), PbrtError> {
+        log::info!(
+            "Entering remote rendering state on {} with PBRT file: {}",
+            self.address,
+            self.pbrt_path
+        );
+        let pbrt_path = std::path::Path::new(&self.pbrt_path);
+        let scene_dir = pbrt_path
+            .parent()
+            .ok_or(PbrtError::error("Invalid path!"))?;
+        let scene = pbrt_path
+            .file_name()
+            .ok_or(PbrtError::error("Invalid path!"))?
+            .to_string_lossy()
+            .to_string();
+        let request = JobRequest {
+            token: self.token.clone(),
+            output: format!("{}.exr", scene.trim_end_matches(".pbrt")),
+            scene,
+            display: self.display_server.is_some(),
+        };
+        remove_old_image(&self.output_path)?;
+        let log = self.log.clone();
+        let display_server = self.display_server.clone();
+        let mut display_stream: Option<TcpStream> = None;
+        let on_message = move |message| match message {
+            AgentMessage::Log(line) => {
+                log.lock().unwrap().parse_line(&line);
+            }
+            AgentMessage::Display(data) => {
+                if display_stream.is_none() {
+                    if let Some((hostname, port)) = display_server.as_ref() {
+                        display_stream = TcpStream::connect((hostname.as_str(), *port)).ok();
+                    }
+                }
+                if let Some(stream) = display_stream.as_mut() {
+                    if stream.write_all(&data).is_err() {
+                        display_stream = None;
+                    }
+                }
+            }
+            _ => {}
+        };
+        // Packing and sending happen on the job thread, not on the UI
+        self.job = Some(RemoteJob::start(
+            &self.address,
+            &request,
+            scene_dir,
+            std::path::Path::new(&self.output_path),
+            on_message,
+        ));
+        Ok(())
+    }
+
+    fn update(&mut self) -> Result<RenderState, PbrtError> {
+        let Some(job) = self.job.as_mut() else {
+            return Ok(RenderState::Finishing);
+        };
+        match job.try_result() {
+            None => {
+                return Ok(RenderState::Rendering);
+            }
+            Some(Ok(result)) => {
+                if !result.success {
+                    log::error!("Remote rendering failed: {}", result.message);
+                    self.push_error(&result.message);
+                }
+            }
+            Some(Err(e)) => {
+                log::error!("Lost the render agent: {}", e);
+                self.push_error(&format!("Lost the render agent: {}", e));
+            }
+        }
+        self.job = None;
+        Ok(RenderState::Finishing)
+    }
+
+    fn cancel(&mut self) -> Result<(), PbrtError> {
+        if let Some(job) = self.job.as_mut() {
+            job.cancel()?;
+        }
+        Ok(())
+    }
+
+    fn get_error(&self) -> Option<String> {
+        self.error.clone()
+    }
+}
+
 pub struct FinishingRenderTask {
     src_path: String,
     dst_path: String,
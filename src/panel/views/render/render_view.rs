@@ -1,44 +1,91 @@
+use super::image_channels::ChannelLayer;
+use super::image_channels::ChannelMapping;
+use super::image_channels::ChannelMode;
+use super::image_channels::get_channel_layers;
+use super::image_channels::get_luminance;
 use super::image_data::ImageData;
+use super::image_histogram::ImageHistogram;
 use super::render_history::RenderHistory;
+use super::render_region::get_crop_window;
+use super::render_view_settings::FitMode;
+use super::render_view_settings::RenderViewSettings;
+use super::render_view_settings::RenderViewZoom;
+use crate::render::viewport_settings::ToneMapping;
 
 use eframe::egui;
 use std::sync::Arc;
 
 #[inline]
-pub fn gamma_correct(value: f32) -> f32 {
-    if value <= 0.0031308 {
-        return 12.92 * value;
-    } else {
-        return 1.055 * f32::powf(value, 1.0 / 2.4) - 0.055;
-    }
+fn to_display_byte(a: f32) -> u8 {
+    (a * 255.0).clamp(0.0, 255.0) as u8
 }
-/*
-#[inline]
-pub fn inverse_gamma_correct(value: f32) -> f32 {
-    if value <= 0.04045 {
-        return value * 1.0 / 12.92;
-    } else {
-        return f32::powf((value + 0.055) * 1.0 / 1.055, 2.4);
-    }
+
+/// Layer selected by the settings, the first one when the name is not found.
+fn get_layer(layers: &[ChannelLayer], settings: &RenderViewSettings) -> usize {
+    layers
+        .iter()
+        .position(|layer| layer.name == settings.layer)
+        .unwrap_or(0)
 }
-*/
 
-#[inline]
-fn to_byte(a: f32) -> u8 {
-    (gamma_correct(a) * 255.0).clamp(0.0, 255.0) as u8
+fn get_mapping(image: &ImageData, settings: &RenderViewSettings) -> Option<ChannelMapping> {
+    let layers = get_channel_layers(&image.channel_names);
+    let layer = layers.get(get_layer(&layers, settings))?;
+    Some(ChannelMapping::new(
+        image,
+        layer,
+        settings.isolate,
+        settings.mode,
+    ))
 }
 
-fn create_image_delta(render_image: &ImageData) -> egui::epaint::ImageDelta {
+const HISTOGRAM_BINS: usize = 100;
+const HISTOGRAM_MIN_STOPS: f32 = -12.0;
+const HISTOGRAM_MAX_STOPS: f32 = 8.0;
+
+// Nearest neighbour when zoomed in, so single pixels can be inspected
+const TEXTURE_OPTIONS: egui::TextureOptions = egui::TextureOptions {
+    magnification: egui::TextureFilter::Nearest,
+    minification: egui::TextureFilter::Linear,
+    wrap_mode: egui::TextureWrapMode::ClampToEdge,
+    mipmap_mode: None,
+};
+
+/// Texture of the image and the histogram of its linear values.
+fn create_image_delta(
+    render_image: &ImageData,
+    settings: &RenderViewSettings,
+) -> (egui::epaint::ImageDelta, Option<ImageHistogram>) {
     let width = render_image.width as usize;
     let height = render_image.height as usize;
+    let n_channels = render_image.channel_names.len().max(1);
+    let mapping = get_mapping(render_image, settings);
+    let mut histogram =
+        ImageHistogram::new(HISTOGRAM_BINS, HISTOGRAM_MIN_STOPS, HISTOGRAM_MAX_STOPS);
+    let mut has_linear = false;
     let mut pixels: Vec<egui::Color32> = Vec::with_capacity(width * height);
     for y in 0..height {
         for x in 0..width {
-            let index = y * width + x;
-            let r = render_image.data[3 * index + 0];
-            let g = render_image.data[3 * index + 1];
-            let b = render_image.data[3 * index + 2];
-            let pixel = egui::Color32::from_rgb(to_byte(r), to_byte(g), to_byte(b));
+            let index = (y * width + x) * n_channels;
+            let pixel = &render_image.data[index..index + n_channels];
+            let pixel = match mapping.as_ref() {
+                Some(mapping) => {
+                    let (rgb, linear) = mapping.map(pixel);
+                    let [r, g, b] = if linear {
+                        has_linear = true;
+                        histogram.add(get_luminance(rgb));
+                        settings.to_display(rgb)
+                    } else {
+                        rgb
+                    };
+                    egui::Color32::from_rgb(
+                        to_display_byte(r),
+                        to_display_byte(g),
+                        to_display_byte(b),
+                    )
+                }
+                None => egui::Color32::BLACK,
+            };
             pixels.push(pixel);
         }
     }
@@ -48,74 +95,394 @@ fn create_image_delta(render_image: &ImageData) -> egui::epaint::ImageDelta {
         pixels,
     };
     let image = egui::ImageData::Color(Arc::new(image));
-    let options = egui::TextureOptions::LINEAR;
     let delta = egui::epaint::ImageDelta {
         image,
-        options,
+        options: TEXTURE_OPTIONS,
         pos: None,
     };
-    return delta;
+    let histogram = if has_linear { Some(histogram) } else { None };
+    return (delta, histogram);
 }
 
-fn show_render_view(ui: &mut egui::Ui, history: &mut RenderHistory) {
-    let available_rect = ui.available_rect_before_wrap();
-    let available_size = available_rect.size();
-    if let Some(image) = history.get_image_data() {
-        let mut image = image.lock().unwrap();
-        let render_size = egui::vec2(image.width as f32, image.height as f32);
-        let scale_x = available_size.x / render_size.x;
-        let scale_y = available_size.y / render_size.y;
-        let scale = scale_x.min(scale_y);
-        let scaled_size = egui::vec2(render_size.x * scale, render_size.y * scale);
-        let scaled_rect = egui::Rect::from_min_size(
-            available_rect.min + (available_size - scaled_size) / 2.0,
-            scaled_size,
+/// Layer, mode and component selectors of the render view.
+fn show_channel_toolbar(ui: &mut egui::Ui, history: &mut RenderHistory) {
+    let Some(image) = history.get_image_data() else {
+        return;
+    };
+    let channel_names = image.lock().unwrap().channel_names.clone();
+    let layers = get_channel_layers(&channel_names);
+    if layers.is_empty() {
+        return;
+    }
+    let settings = &mut history.view_settings;
+    let layer_index = get_layer(&layers, settings);
+    let layer = &layers[layer_index];
+    egui::ComboBox::from_id_salt("render_view_layer")
+        .selected_text(&layer.name)
+        .show_ui(ui, |ui| {
+            for (i, layer) in layers.iter().enumerate() {
+                if ui.selectable_label(i == layer_index, &layer.name).clicked() {
+                    settings.layer = layer.name.clone();
+                    settings.mode = ChannelMode::from_layer_name(&layer.name);
+                    settings.isolate = None;
+                }
+            }
+        });
+    egui::ComboBox::from_id_salt("render_view_channel_mode")
+        .selected_text(settings.mode.get_label())
+        .show_ui(ui, |ui| {
+            for mode in ChannelMode::all() {
+                ui.selectable_value(&mut settings.mode, *mode, mode.get_label());
+            }
+        });
+    if layer.channels.len() > 1 {
+        ui.separator();
+        ui.selectable_value(&mut settings.isolate, None, "All");
+        for (i, name) in layer.get_component_names(&channel_names).iter().enumerate() {
+            ui.selectable_value(&mut settings.isolate, Some(i), name)
+                .on_hover_text(&channel_names[layer.channels[i]]);
+        }
+    }
+}
+
+/// Exposure, tone curve and zoom controls of the render view.
+fn show_display_toolbar(ui: &mut egui::Ui, history: &mut RenderHistory, show_histogram: &mut bool) {
+    let settings = &mut history.view_settings;
+    ui.add(
+        egui::DragValue::new(&mut settings.exposure)
+            .speed(0.05)
+            .range(-20.0..=20.0)
+            .prefix("EV "),
+    )
+    .on_hover_text("Exposure in stops");
+    ui.add(
+        egui::DragValue::new(&mut settings.offset)
+            .speed(0.01)
+            .prefix("Offset "),
+    );
+    egui::ComboBox::from_id_salt("render_view_tone_mapping")
+        .selected_text(settings.tone_mapping.get_label())
+        .show_ui(ui, |ui| {
+            for tone_mapping in ToneMapping::all() {
+                ui.selectable_value(
+                    &mut settings.tone_mapping,
+                    *tone_mapping,
+                    tone_mapping.get_label(),
+                );
+            }
+        });
+    ui.selectable_value(&mut settings.srgb, true, "sRGB");
+    ui.selectable_value(&mut settings.srgb, false, "Gamma");
+    if !settings.srgb {
+        ui.add(
+            egui::DragValue::new(&mut settings.gamma)
+                .speed(0.01)
+                .range(0.1..=5.0),
         );
-        {
-            let tex_manager = ui.ctx().tex_manager().clone();
-            let mut tex_manager = tex_manager.write();
-            if history.texture_id.is_none() {
-                let image_size = [render_size.x as usize, render_size.y as usize];
-                let color_image = egui::ColorImage::filled(image_size, egui::Color32::BLACK);
-                let image_data = egui::ImageData::Color(Arc::new(color_image));
-                let options = egui::TextureOptions::LINEAR;
-                history.texture_id =
-                    Some(tex_manager.alloc("render_image".to_string(), image_data, options));
+    }
+    if ui.button("↺").on_hover_text("Reset the display").clicked() {
+        settings.reset_display();
+    }
+    ui.separator();
+    let zoom = &mut history.view_zoom;
+    ui.selectable_value(&mut zoom.fit, FitMode::Fit, "Fit")
+        .on_hover_text("Show the whole image (double click)");
+    ui.selectable_value(&mut zoom.fit, FitMode::Fill, "Fill")
+        .on_hover_text("Fill the view with the image");
+    if ui
+        .selectable_label(zoom.fit == FitMode::Manual && zoom.zoom == 1.0, "1:1")
+        .on_hover_text("One image pixel per screen point")
+        .clicked()
+    {
+        zoom.set_actual_size();
+    }
+    if zoom.fit == FitMode::Manual {
+        ui.label(format!("{:.0}%", zoom.zoom * 100.0));
+    }
+    ui.separator();
+    ui.toggle_value(show_histogram, "Histogram");
+}
+
+fn show_histogram(ui: &egui::Ui, rect: egui::Rect, histogram: &ImageHistogram, exposure: f32) {
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, egui::Color32::from_black_alpha(160));
+    let max_count = histogram.get_max_count().max(1) as f32;
+    let n = histogram.bins.len();
+    let bar_width = rect.width() / n as f32;
+    for (i, count) in histogram.bins.iter().enumerate() {
+        if *count == 0 {
+            continue;
+        }
+        // Square root so that small bins stay visible
+        let h = (*count as f32 / max_count).sqrt() * rect.height();
+        let x = rect.min.x + i as f32 * bar_width;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(x, rect.max.y - h),
+            egui::pos2(x + bar_width, rect.max.y),
+        );
+        painter.rect_filled(bar, 0.0, egui::Color32::from_gray(200));
+    }
+    // Values that the exposure maps to white
+    let x = rect.min.x + histogram.get_position(-exposure) * rect.width();
+    painter.line_segment(
+        [egui::pos2(x, rect.min.y), egui::pos2(x, rect.max.y)],
+        egui::Stroke::new(1.0, egui::Color32::YELLOW),
+    );
+    painter.text(
+        rect.left_top() + egui::vec2(4.0, 2.0),
+        egui::Align2::LEFT_TOP,
+        format!("{:+.0} .. {:+.0} EV", histogram.min, histogram.max),
+        egui::FontId::monospace(10.0),
+        egui::Color32::from_gray(160),
+    );
+}
+
+/// Values of every channel of one pixel.
+fn show_pixel_probe(ui: &mut egui::Ui, image: &ImageData, x: usize, y: usize) {
+    let n_channels = image.channel_names.len();
+    let index = (y * image.width + x) * n_channels;
+    ui.label(format!("Pixel {}, {}", x, y));
+    egui::Grid::new("render_view_pixel_probe")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            for (c, name) in image.channel_names.iter().enumerate() {
+                ui.label(name);
+                ui.monospace(format!("{:.6}", image.data[index + c]));
+                ui.end_row();
             }
+        });
+}
 
-            if let Some(texture_id) = history.texture_id {
-                if !image.tiles.is_empty() {
-                    image.tiles.clear();
-                    let image_delta = create_image_delta(&image);
-                    tex_manager.set(texture_id, image_delta);
-                }
+/// Uploads the image of a history when it or its view settings changed.
+pub fn update_texture(ctx: &egui::Context, history: &mut RenderHistory) -> Option<egui::TextureId> {
+    let image = history.get_image_data()?;
+    let mut image = image.lock().unwrap();
+    let tex_manager = ctx.tex_manager().clone();
+    let mut tex_manager = tex_manager.write();
+    if history.texture_id.is_none() {
+        let image_size = [image.width, image.height];
+        let color_image = egui::ColorImage::filled(image_size, egui::Color32::BLACK);
+        let image_data = egui::ImageData::Color(Arc::new(color_image));
+        history.texture_id =
+            Some(tex_manager.alloc("render_image".to_string(), image_data, TEXTURE_OPTIONS));
+    }
+    let texture_id = history.texture_id?;
+    let settings_changed = history.texture_settings.as_ref() != Some(&history.view_settings);
+    if !image.tiles.is_empty() || settings_changed {
+        image.tiles.clear();
+        let (image_delta, histogram) = create_image_delta(&image, &history.view_settings);
+        tex_manager.set(texture_id, image_delta);
+        history.texture_settings = Some(history.view_settings.clone());
+        history.histogram = histogram;
+    }
+    return Some(texture_id);
+}
 
-                ui.painter().image(
-                    texture_id,
-                    scaled_rect,
-                    egui::Rect::from_min_max(egui::Pos2::ZERO, egui::Pos2::new(1.0, 1.0)),
-                    egui::Color32::WHITE,
-                );
+/// Drag pans, the wheel zooms around the pointer and a double click fits the image.
+pub fn handle_zoom_input(
+    ui: &egui::Ui,
+    response: &egui::Response,
+    zoom: &mut RenderViewZoom,
+    view_rect: egui::Rect,
+    image_size: egui::Vec2,
+) {
+    if response.dragged() {
+        zoom.pan_by(view_rect, image_size, response.drag_delta());
+    }
+    if let Some(pos) = response.hover_pos() {
+        let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+        if scroll != 0.0 {
+            let factor = (scroll * 0.005).exp();
+            zoom.zoom_at(view_rect, image_size, pos, factor);
+        }
+    }
+    if response.double_clicked() {
+        zoom.fit = FitMode::Fit;
+    }
+}
+
+/// Image pixel under `pos`, if it is on the image.
+pub fn get_pixel_at(
+    image_rect: egui::Rect,
+    image_size: egui::Vec2,
+    pos: egui::Pos2,
+) -> Option<(usize, usize)> {
+    if !image_rect.contains(pos) || image_size.x < 1.0 || image_size.y < 1.0 {
+        return None;
+    }
+    let uv = (pos - image_rect.min) / image_rect.size();
+    let x = ((uv.x * image_size.x) as usize).min(image_size.x as usize - 1);
+    let y = ((uv.y * image_size.y) as usize).min(image_size.y as usize - 1);
+    return Some((x, y));
+}
+
+pub const FULL_UV: egui::Rect = egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0));
+
+/// Image rect position in [0, 1] image coordinates.
+fn get_image_uv(image_rect: egui::Rect, pos: egui::Pos2) -> [f32; 2] {
+    let uv = (pos - image_rect.min) / image_rect.size();
+    [uv.x.clamp(0.0, 1.0), uv.y.clamp(0.0, 1.0)]
+}
+
+fn get_crop_rect(image_rect: egui::Rect, crop_window: [f32; 4]) -> egui::Rect {
+    let [x0, x1, y0, y1] = crop_window;
+    let size = image_rect.size();
+    egui::Rect::from_min_max(
+        image_rect.min + egui::vec2(x0 * size.x, y0 * size.y),
+        image_rect.min + egui::vec2(x1 * size.x, y1 * size.y),
+    )
+}
+
+pub struct RenderView {
+    show_histogram: bool,
+    region_start: Option<[f32; 2]>, // Where the region drag started, in image coordinates
+    crop_window: Option<[f32; 4]>,  // Selected region
+}
+
+impl RenderView {
+    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+        Self {
+            show_histogram: false,
+            region_start: None,
+            crop_window: None,
+        }
+    }
+
+    /// Shift and drag selects a region, a plain drag pans.
+    fn handle_region_input(
+        &mut self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        image_rect: egui::Rect,
+    ) -> bool {
+        let shift = ui.input(|i| i.modifiers.shift);
+        if response.drag_started() && shift {
+            if let Some(pos) = ui.input(|i| i.pointer.press_origin()) {
+                self.region_start = Some(get_image_uv(image_rect, pos));
             }
         }
+        let Some(start) = self.region_start else {
+            if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.crop_window = None;
+            }
+            return false;
+        };
+        if let Some(pos) = response.interact_pointer_pos() {
+            self.crop_window = get_crop_window(start, get_image_uv(image_rect, pos));
+        }
+        if !response.dragged() {
+            self.region_start = None;
+        }
+        return true;
+    }
 
-        ui.painter().rect_stroke(
+    fn show_image(&mut self, ui: &mut egui::Ui, history: &mut RenderHistory) {
+        let view_rect = ui.available_rect_before_wrap();
+        let response = ui.allocate_rect(view_rect, egui::Sense::click_and_drag());
+        let texture_id = update_texture(ui.ctx(), history);
+        let Some(image) = history.get_image_data() else {
+            return;
+        };
+        let image = image.lock().unwrap();
+        let render_size = egui::vec2(image.width as f32, image.height as f32);
+        let scaled_rect = history.view_zoom.get_image_rect(view_rect, render_size);
+        if !self.handle_region_input(ui, &response, scaled_rect) {
+            handle_zoom_input(
+                ui,
+                &response,
+                &mut history.view_zoom,
+                view_rect,
+                render_size,
+            );
+        }
+        let scaled_rect = history.view_zoom.get_image_rect(view_rect, render_size);
+        let painter = ui.painter_at(view_rect);
+        if let Some(texture_id) = texture_id {
+            painter.image(texture_id, scaled_rect, FULL_UV, egui::Color32::WHITE);
+        }
+
+        painter.rect_stroke(
             scaled_rect,
             0.0,
             egui::Stroke::new(1.0, egui::Color32::WHITE),
-            egui::StrokeKind::Inside,
+            egui::StrokeKind::Outside,
         );
-    }
-}
 
-pub struct RenderView {}
+        if let Some(crop_window) = self.crop_window {
+            let crop_rect = get_crop_rect(scaled_rect, crop_window);
+            painter.rect_filled(crop_rect, 0.0, egui::Color32::from_white_alpha(16));
+            painter.rect_stroke(
+                crop_rect,
+                0.0,
+                egui::Stroke::new(1.0, egui::Color32::YELLOW),
+                egui::StrokeKind::Middle,
+            );
+        }
 
-impl RenderView {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Self {}
+        if self.show_histogram {
+            if let Some(histogram) = history.histogram.as_ref() {
+                let size = egui::vec2(240.0, 80.0);
+                let rect = egui::Rect::from_min_size(
+                    view_rect.right_bottom() - size - egui::vec2(8.0, 8.0),
+                    size,
+                );
+                show_histogram(ui, rect, histogram, history.view_settings.exposure);
+            }
+        }
+
+        if let Some(pos) = response.hover_pos() {
+            if let Some((x, y)) = get_pixel_at(scaled_rect, render_size, pos) {
+                if !response.dragged() {
+                    response.on_hover_ui_at_pointer(|ui| show_pixel_probe(ui, &image, x, y));
+                }
+            }
+        }
+    }
+
+    /// Region selection buttons; returns the crop window to render when asked.
+    fn show_region_toolbar(&mut self, ui: &mut egui::Ui, can_render: bool) -> Option<[f32; 4]> {
+        let Some(crop_window) = self.crop_window else {
+            ui.label("Shift+drag to select a region")
+                .on_hover_text("Render only part of the image");
+            return None;
+        };
+        let mut render = None;
+        if ui
+            .add_enabled(can_render, egui::Button::new("▶ Region"))
+            .on_hover_text("Render the selected region over this image")
+            .clicked()
+        {
+            render = Some(crop_window);
+        }
+        if ui
+            .button("✖")
+            .on_hover_text("Clear the region (Esc)")
+            .clicked()
+        {
+            self.crop_window = None;
+        }
+        return render;
     }
 
-    pub fn show(&mut self, ui: &mut egui::Ui, history: &mut RenderHistory) {
-        show_render_view(ui, history);
+    /// Shows the image of a history; returns a crop window when a region render is asked for.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        history: &mut RenderHistory,
+        can_render: bool,
+    ) -> Option<[f32; 4]> {
+        history.reload_image(ui.ctx());
+        let mut region = None;
+        if history.get_image_data().is_some() {
+            ui.horizontal(|ui| show_channel_toolbar(ui, history));
+            ui.horizontal(|ui| {
+                show_display_toolbar(ui, history, &mut self.show_histogram);
+                ui.separator();
+                region = self.show_region_toolbar(ui, can_render);
+            });
+        }
+        self.show_image(ui, history);
+        return region;
     }
 }
@@ -0,0 +1,188 @@
+use super::image_channels::ChannelMode;
+use crate::conversion::color_space::gamma_correct;
+use crate::render::viewport_settings::ToneMapping;
+
+use eframe::egui;
+
+/// How a render history shows its image; each history keeps its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderViewSettings {
+    pub layer: String, // Empty for the first layer
+    pub isolate: Option<usize>,
+    pub mode: ChannelMode,
+    pub exposure: f32, // In stops
+    pub offset: f32,   // Added after the exposure
+    pub tone_mapping: ToneMapping,
+    pub srgb: bool, // sRGB transfer curve instead of the gamma
+    pub gamma: f32,
+}
+
+impl Default for RenderViewSettings {
+    fn default() -> Self {
+        Self {
+            layer: String::new(),
+            isolate: None,
+            mode: ChannelMode::default(),
+            exposure: 0.0,
+            offset: 0.0,
+            tone_mapping: ToneMapping::LinearClamp,
+            srgb: true,
+            gamma: 2.2,
+        }
+    }
+}
+
+// Narkowicz's fit of the ACES reference rendering transform, as in tone_mapping.wgsl
+fn aces_filmic(x: f32) -> f32 {
+    let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+    (x * (a * x + b)) / (x * (c * x + d) + e)
+}
+
+impl RenderViewSettings {
+    pub fn reset_display(&mut self) {
+        let default = Self::default();
+        self.exposure = default.exposure;
+        self.offset = default.offset;
+        self.tone_mapping = default.tone_mapping;
+        self.srgb = default.srgb;
+        self.gamma = default.gamma;
+    }
+
+    /// Linear radiance to display values in [0, 1].
+    pub fn to_display(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let scale = f32::powf(2.0, self.exposure);
+        rgb.map(|v| {
+            let v = (v * scale + self.offset).max(0.0);
+            let v = match self.tone_mapping {
+                ToneMapping::LinearClamp => v,
+                ToneMapping::Reinhard => v / (1.0 + v),
+                ToneMapping::AcesFilmic => aces_filmic(v),
+            };
+            let v = v.clamp(0.0, 1.0);
+            if self.srgb {
+                gamma_correct(v)
+            } else {
+                v.powf(1.0 / self.gamma.max(0.01))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FitMode {
+    #[default]
+    Fit, // Whole image visible
+    Fill,   // View covered by the image
+    Manual, // Zoom and pan set by the user
+}
+
+/// Zoom and pan of the render view, kept apart from the settings the texture depends on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderViewZoom {
+    pub fit: FitMode,
+    pub zoom: f32,       // Screen points per image pixel in the manual mode
+    pub pan: egui::Vec2, // Image center relative to the view center
+}
+
+impl Default for RenderViewZoom {
+    fn default() -> Self {
+        Self {
+            fit: FitMode::Fit,
+            zoom: 1.0,
+            pan: egui::Vec2::ZERO,
+        }
+    }
+}
+
+const MIN_ZOOM: f32 = 1.0 / 64.0;
+const MAX_ZOOM: f32 = 64.0;
+
+impl RenderViewZoom {
+    pub fn get_scale(&self, view_size: egui::Vec2, image_size: egui::Vec2) -> f32 {
+        let scale_x = view_size.x / image_size.x.max(1.0);
+        let scale_y = view_size.y / image_size.y.max(1.0);
+        match self.fit {
+            FitMode::Fit => scale_x.min(scale_y),
+            FitMode::Fill => scale_x.max(scale_y),
+            FitMode::Manual => self.zoom,
+        }
+    }
+
+    pub fn get_image_rect(&self, view_rect: egui::Rect, image_size: egui::Vec2) -> egui::Rect {
+        let scale = self.get_scale(view_rect.size(), image_size);
+        let pan = match self.fit {
+            FitMode::Manual => self.pan,
+            _ => egui::Vec2::ZERO,
+        };
+        egui::Rect::from_center_size(view_rect.center() + pan, image_size * scale)
+    }
+
+    fn switch_to_manual(&mut self, view_rect: egui::Rect, image_size: egui::Vec2) {
+        if self.fit != FitMode::Manual {
+            self.zoom = self.get_scale(view_rect.size(), image_size);
+            self.pan = egui::Vec2::ZERO;
+            self.fit = FitMode::Manual;
+        }
+    }
+
+    /// Zooms by `factor` keeping the image point under `pos` in place.
+    pub fn zoom_at(
+        &mut self,
+        view_rect: egui::Rect,
+        image_size: egui::Vec2,
+        pos: egui::Pos2,
+        factor: f32,
+    ) {
+        self.switch_to_manual(view_rect, image_size);
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let offset = pos - view_rect.center() - self.pan;
+        self.pan += offset - offset * (zoom / self.zoom);
+        self.zoom = zoom;
+    }
+
+    pub fn pan_by(&mut self, view_rect: egui::Rect, image_size: egui::Vec2, delta: egui::Vec2) {
+        self.switch_to_manual(view_rect, image_size);
+        self.pan += delta;
+    }
+
+    pub fn set_actual_size(&mut self) {
+        self.fit = FitMode::Manual;
+        self.zoom = 1.0;
+        self.pan = egui::Vec2::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_display() {
+        let mut settings = RenderViewSettings::default();
+        settings.srgb = false;
+        settings.gamma = 1.0;
+        assert_eq!(settings.to_display([0.25, 0.5, 2.0]), [0.25, 0.5, 1.0]);
+        settings.exposure = 1.0;
+        assert_eq!(settings.to_display([0.25, 0.5, 2.0]), [0.5, 1.0, 1.0]);
+        settings.offset = -0.5;
+        settings.tone_mapping = ToneMapping::Reinhard;
+        assert_eq!(settings.to_display([0.25, 0.5, 1.0]), [0.0, 1.0 / 3.0, 0.6]);
+    }
+
+    #[test]
+    fn test_zoom_at_keeps_point() {
+        let view_rect = egui::Rect::from_min_size(egui::pos2(0.0, 0.0), egui::vec2(200.0, 100.0));
+        let image_size = egui::vec2(400.0, 100.0);
+        let mut zoom = RenderViewZoom::default();
+        assert_eq!(zoom.get_scale(view_rect.size(), image_size), 0.5);
+        let pos = egui::pos2(150.0, 40.0);
+        let before = zoom.get_image_rect(view_rect, image_size);
+        let uv = (pos - before.min) / before.size();
+        zoom.zoom_at(view_rect, image_size, pos, 4.0);
+        assert_eq!(zoom.fit, FitMode::Manual);
+        assert_eq!(zoom.zoom, 2.0);
+        let after = zoom.get_image_rect(view_rect, image_size);
+        let moved = after.min + uv * after.size();
+        assert!((moved - pos).length() < 1e-3);
+    }
+}
@@ -1,7 +1,14 @@
 use super::fps_counter::FpsCounter;
+use super::gizmo_controller::GizmoController;
+use super::gizmo_controller::ViewProjection;
+use super::gizmo_controller::get_parent_matrix;
+use super::gizmo_controller::get_world_matrix;
+use super::viewport_camera::ViewportCamera;
+use super::viewport_camera::get_selection_bounds;
+use crate::conversion::camera_shape::screen_window::get_crop_window;
+use crate::conversion::camera_shape::screen_window::is_full_crop_window;
 use crate::model::base::Matrix4x4;
 use crate::model::base::Property;
-use crate::model::base::Quaternion;
 use crate::model::base::Vector3;
 use crate::model::scene::CameraComponent;
 use crate::model::scene::CameraProperties;
@@ -9,79 +16,216 @@ use crate::model::scene::CoordinateSystemComponent;
 use crate::model::scene::FilmComponent;
 use crate::model::scene::Node;
 use crate::model::scene::TransformComponent;
+use crate::render::DebugRenderer;
 use crate::render::LightingRenderer;
 use crate::render::RenderMode;
+use crate::render::SceneOverlays;
 use crate::render::SolidRenderer;
+use crate::render::ViewportSettings;
 use crate::render::WireRenderer;
+use crate::render::transform_gizmo::GizmoMode;
+use crate::render::transform_gizmo::GizmoSpace;
 
 use std::sync::Arc;
 use std::sync::RwLock;
 
 use eframe::egui;
 use egui::Vec2;
+use uuid::Uuid;
 
-pub fn react_response(response: &egui::Response, root_node: &Arc<RwLock<Node>>) {
-    if response.dragged_by(egui::PointerButton::Primary) {
-        if let Some(camera_node) = Node::find_node_by_component::<CameraComponent>(&root_node) {
-            let mut camera_node = camera_node.write().unwrap();
-            if let Some(component) = camera_node.get_component_mut::<TransformComponent>() {
-                let rotation_y = response.drag_motion().x * 0.01;
-                let rotation_x = response.drag_motion().y * 0.01;
-                {
-                    let (t, r, s) = component.get_local_trs();
-                    let m = r.to_matrix();
-                    let mut upper = m.transform_vector(&Vector3::new(0.0, 1.0, 0.0)).normalize();
-                    let mut right = m.transform_vector(&Vector3::new(1.0, 0.0, 0.0)).normalize();
-
-                    {
-                        let root_node = root_node.read().unwrap();
-                        if let Some(cs) = root_node.get_component::<CoordinateSystemComponent>() {
-                            upper = cs.get_up_vector();
-                            let forward = Vector3::cross(&right, &upper).normalize(); //xy->z
-                            right = Vector3::cross(&upper, &forward).normalize(); //yz->x
-                        }
-                    }
-                    //let upper = Vector3::new(0.0, 1.0, 0.0);
-                    //let right = Vector3::new(1.0, 0.0, 0.0);
-
-                    let rotation_y = if s.x < 0.0 { -rotation_y } else { rotation_y };
-                    let rotation_x = if s.y < 0.0 { -rotation_x } else { rotation_x };
+const ORBIT_SPEED: f32 = 0.01; // Radians per pixel
+const DOLLY_SPEED: f32 = 0.002; // Per scrolled point
+const FLY_SPEED: f32 = 0.5; // Pivot distances per second
 
-                    let rot_y = Quaternion::from_angle_axis(rotation_y, &upper);
-                    let rot_x = Quaternion::from_angle_axis(rotation_x, &right);
-                    let new_rotation = rot_x * rot_y * r;
-                    component.set_local_trs(t, new_rotation, s);
-                }
-            }
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SceneViewCommand {
+    FrameSelected,
+    SetRenderCameraFromView,
 }
 
 pub struct SceneView {
     wireframe: Option<WireRenderer>,
     solid: Option<SolidRenderer>,
     shaded: Option<LightingRenderer>,
+    debug: Option<DebugRenderer>,
+    camera: ViewportCamera,
+    camera_scene: Option<Uuid>, // Root node the editor camera was placed in
+    view_through_camera: bool,
+    commands: Vec<SceneViewCommand>,
+    gizmo: GizmoController,
     fps_counter: FpsCounter,
 }
 
+fn get_up_vector(root_node: &Arc<RwLock<Node>>) -> Vector3 {
+    let root_node = root_node.read().unwrap();
+    if let Some(cs) = root_node.get_component::<CoordinateSystemComponent>() {
+        return cs.get_up_vector();
+    }
+    return Vector3::new(0.0, 1.0, 0.0);
+}
+
+/// Moves the render camera to the editor camera, and sets its fov when given (degrees).
+fn set_render_camera(root_node: &Arc<RwLock<Node>>, camera: &ViewportCamera, fov: Option<f32>) {
+    if let Some(camera_node) = Node::find_node_by_component::<CameraComponent>(root_node) {
+        // The local matrix is relative to the parent, which may be transformed
+        let world_to_parent = get_parent_matrix(&camera_node)
+            .and_then(|m| m.inverse())
+            .unwrap_or(Matrix4x4::identity());
+        let mut camera_node = camera_node.write().unwrap();
+        if let Some(component) = camera_node.get_component_mut::<TransformComponent>() {
+            component.set_local_matrix(world_to_parent * camera.get_camera_to_world());
+        }
+        if let Some(fov) = fov {
+            if let Some(component) = camera_node.get_component_mut::<CameraComponent>() {
+                component.props.add_floats("float fov", &[fov]);
+                component
+                    .props
+                    .add_string("string edition", &Uuid::new_v4().to_string());
+            }
+        }
+    }
+}
+
 impl SceneView {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         let wireframe = WireRenderer::new(cc);
         let solid = SolidRenderer::new(cc);
         let shaded = LightingRenderer::new(cc);
+        let debug = DebugRenderer::new(cc);
         Self {
             wireframe,
             solid,
             shaded,
+            debug,
+            camera: ViewportCamera::default(),
+            camera_scene: None,
+            view_through_camera: false,
+            commands: Vec::new(),
+            gizmo: GizmoController::new(),
             fps_counter: FpsCounter::new(),
         }
     }
 
+    pub fn is_view_through_camera(&self) -> bool {
+        self.view_through_camera
+    }
+
+    /// Views through the pbrt camera; navigation then moves the render camera.
+    pub fn set_view_through_camera(&mut self, view_through_camera: bool) {
+        self.view_through_camera = view_through_camera;
+    }
+
+    pub fn frame_selected(&mut self) {
+        self.commands.push(SceneViewCommand::FrameSelected);
+    }
+
+    pub fn set_render_camera_from_view(&mut self) {
+        self.commands
+            .push(SceneViewCommand::SetRenderCameraFromView);
+    }
+
+    pub fn get_gizmo_mode(&self) -> GizmoMode {
+        self.gizmo.mode
+    }
+
+    pub fn set_gizmo_mode(&mut self, mode: GizmoMode) {
+        self.gizmo.mode = mode;
+    }
+
+    pub fn get_gizmo_space(&self) -> GizmoSpace {
+        self.gizmo.space
+    }
+
+    pub fn set_gizmo_space(&mut self, space: GizmoSpace) {
+        self.gizmo.space = space;
+    }
+
+    // View and projection of the render camera, or of the editor camera
+    fn get_view_matrices(
+        &self,
+        render_w2c: &Matrix4x4,
+        render_fov: f32,
+        aspect: f32,
+        clip_range: (f32, f32),
+    ) -> (Matrix4x4, Matrix4x4) {
+        let (w2c, fov, (znear, zfar)) = if self.view_through_camera {
+            (*render_w2c, render_fov, clip_range)
+        } else {
+            (
+                self.camera.get_world_to_camera(),
+                self.camera.fov,
+                self.camera.get_clip_range(),
+            )
+        };
+        return (w2c, Matrix4x4::perspective(fov, aspect, znear, zfar));
+    }
+
+    // Orbit, pan, dolly and fly from the pointer and keys, returns whether the camera moved
+    fn react_response(
+        &mut self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        rect: egui::Rect,
+    ) -> bool {
+        let mut moved = false;
+        let camera = &mut self.camera;
+        let drag = response.drag_motion();
+        let shift = ui.input(|i| i.modifiers.shift);
+        if response.dragged_by(egui::PointerButton::Middle)
+            || (shift && response.dragged_by(egui::PointerButton::Primary))
+        {
+            camera.pan(drag.x, drag.y, rect.height());
+            moved = true;
+        } else if response.dragged_by(egui::PointerButton::Primary) {
+            camera.orbit(drag.x * ORBIT_SPEED, drag.y * ORBIT_SPEED);
+            moved = true;
+        } else if response.dragged_by(egui::PointerButton::Secondary) {
+            camera.look(drag.x * ORBIT_SPEED, drag.y * ORBIT_SPEED);
+            // WASD to fly while looking around, Q and E to go down and up
+            let (motion, dt) = ui.input(|i| {
+                let axis = |plus: egui::Key, minus: egui::Key| {
+                    (i.key_down(plus) as i32 - i.key_down(minus) as i32) as f32
+                };
+                let motion = Vector3::new(
+                    axis(egui::Key::D, egui::Key::A),
+                    axis(egui::Key::E, egui::Key::Q),
+                    axis(egui::Key::W, egui::Key::S),
+                );
+                (motion, i.stable_dt)
+            });
+            if motion.length_squared() > 0.0 {
+                let speed = camera.distance * FLY_SPEED * if shift { 4.0 } else { 1.0 };
+                camera.fly(&(motion.normalize() * (speed * dt)));
+                ui.ctx().request_repaint();
+            }
+            moved = true;
+        }
+        if response.hovered() {
+            let (scroll, pointer) = ui.input(|i| (i.smooth_scroll_delta.y, i.pointer.hover_pos()));
+            if scroll != 0.0 {
+                let pointer = pointer.unwrap_or(rect.center());
+                let ndc = [
+                    2.0 * (pointer.x - rect.min.x) / rect.width() - 1.0,
+                    1.0 - 2.0 * (pointer.y - rect.min.y) / rect.height(),
+                ];
+                let factor = f32::exp(-scroll * DOLLY_SPEED);
+                camera.dolly(factor, ndc, rect.width() / rect.height());
+                moved = true;
+            }
+            if ui.input(|i| i.key_pressed(egui::Key::F)) {
+                self.commands.push(SceneViewCommand::FrameSelected);
+            }
+        }
+        return moved;
+    }
+
     pub fn show(
         &mut self,
         ui: &mut egui::Ui,
         node: &Arc<RwLock<Node>>,
+        selected: Option<&Arc<RwLock<Node>>>,
         render_mode: RenderMode,
+        settings: &ViewportSettings,
         is_playing: bool,
     ) {
         let available_rect = ui.available_rect_before_wrap();
@@ -92,14 +236,18 @@ impl SceneView {
         let mut zfar = 10000.0f32;
         let mut fov = 90.0f32.to_radians();
         let mut w2c = Matrix4x4::identity();
+        let mut camera_to_world = None;
         let mut render_size = Vec2::new(1280.0, 720.0);
+        let mut crop_window = [0.0, 1.0, 0.0, 1.0];
+        let mut lens = None; // Lens radius and focal distance
         {
             let root_node = node.clone();
             if let Some(camera_node) = Node::find_node_by_component::<CameraComponent>(&root_node) {
+                let local_to_world = get_world_matrix(&camera_node);
                 let camera_node = camera_node.read().unwrap();
-                if let Some(t) = camera_node.get_component::<TransformComponent>() {
-                    let local_to_world = t.get_local_matrix();
+                if camera_node.get_component::<TransformComponent>().is_some() {
                     w2c = local_to_world.inverse().unwrap();
+                    camera_to_world = Some(local_to_world);
                 }
                 if let Some(camera) = camera_node.get_component::<CameraComponent>() {
                     if let Some(prop) = camera.props.get("fov") {
@@ -109,6 +257,12 @@ impl SceneView {
                             }
                         }
                     }
+                    let lens_radius = camera.props.find_one_float("lensradius").unwrap_or(0.0);
+                    if lens_radius > 0.0 {
+                        let focal_distance =
+                            camera.props.find_one_float("focaldistance").unwrap_or(1e6);
+                        lens = Some((lens_radius, focal_distance));
+                    }
                     if let Some(prop) = camera.props.get("znear") {
                         if let Property::Floats(f) = prop {
                             if f.len() > 0 && f[0] > 0.0 {
@@ -134,6 +288,7 @@ impl SceneView {
                         .find_one_int("integer yresolution")
                         .unwrap_or(720);
                     render_size = Vec2::new(width as f32, height as f32);
+                    crop_window = get_crop_window(Some(&film.props));
                 }
             }
         }
@@ -156,15 +311,79 @@ impl SceneView {
             let k = scaled_size.y / (fov / 2.0).tan(); //tan = y / x
             2.0 * f32::atan2(available_size.y, k)
         };
-        let fov = vertical_fov;
+        // pbrt's fov spans the shorter side of the film gate
+        let film_fov_scale = scaled_size.x.min(scaled_size.y) / available_size.y;
 
         let (rect, response) = ui.allocate_exact_size(available_size, egui::Sense::drag());
+        let aspect = rect.width() / rect.height();
+
+        let up = get_up_vector(node);
+        let scene_id = node.read().unwrap().get_id();
+        if self.camera_scene != Some(scene_id) {
+            // Start the editor camera at the render camera of a new scene
+            let mut camera = ViewportCamera::default();
+            camera.up = up;
+            if let Some(m) = camera_to_world.as_ref() {
+                let eye = m.transform_point(&Vector3::zero());
+                let forward = m.transform_vector(&Vector3::new(0.0, 0.0, 1.0)).normalize();
+                let distance = get_selection_bounds(node, None)
+                    .map(|(center, _)| Vector3::dot(&(center - eye), &forward))
+                    .filter(|d| *d > 0.0)
+                    .unwrap_or(10.0);
+                camera.set_from_camera_to_world(m, &up, vertical_fov, distance);
+            }
+            self.camera = camera;
+            self.camera_scene = Some(scene_id);
+        } else if self.view_through_camera {
+            if let Some(m) = camera_to_world.as_ref() {
+                let distance = self.camera.distance;
+                self.camera
+                    .set_from_camera_to_world(m, &up, vertical_fov, distance);
+            }
+        }
+
+        let clip_range = (znear, zfar);
         if is_playing {
-            react_response(&response, node);
+            // The gizmo takes drags starting on its handles before the camera
+            let (view_w2c, view_c2c) =
+                self.get_view_matrices(&w2c, vertical_fov, aspect, clip_range);
+            let view = ViewProjection::new(&view_w2c, &view_c2c, rect);
+            let on_gizmo = self
+                .gizmo
+                .update(ui, &response, selected, &view, &settings.gizmo);
+            let moved = !on_gizmo && self.react_response(ui, &response, rect);
+            if moved && self.view_through_camera {
+                set_render_camera(node, &self.camera, None);
+            }
+            for command in std::mem::take(&mut self.commands) {
+                match command {
+                    SceneViewCommand::FrameSelected => {
+                        if let Some((center, radius)) = get_selection_bounds(node, selected) {
+                            self.camera.frame(&center, radius, aspect);
+                            if self.view_through_camera {
+                                set_render_camera(node, &self.camera, None);
+                            }
+                        }
+                    }
+                    SceneViewCommand::SetRenderCameraFromView => {
+                        let fov = 2.0 * f32::atan(f32::tan(self.camera.fov * 0.5) * film_fov_scale);
+                        set_render_camera(node, &self.camera, Some(fov.to_degrees()));
+                    }
+                }
+            }
+        } else {
+            self.commands.clear();
         }
 
-        let aspect = rect.width() / rect.height();
-        let c2c = Matrix4x4::perspective(fov, aspect, znear, zfar);
+        let (w2c, c2c) = self.get_view_matrices(&w2c, vertical_fov, aspect, clip_range);
+        let mut overlays = SceneOverlays {
+            show_cameras: !self.view_through_camera,
+            ..Default::default()
+        };
+        if is_playing {
+            let view = ViewProjection::new(&w2c, &c2c, rect);
+            overlays.transform_gizmo = self.gizmo.get_gizmo(selected, &view);
+        }
 
         ui.painter().rect_filled(rect, 0.0, egui::Color32::BLACK);
 
@@ -172,29 +391,59 @@ impl SceneView {
         match render_mode {
             RenderMode::Wire => {
                 if let Some(renderer) = &mut self.wireframe {
-                    renderer.render(ui, rect, node, &w2c, &c2c);
+                    renderer.render(ui, rect, node, &w2c, &c2c, &overlays);
                 }
             }
             RenderMode::Solid => {
                 if let Some(renderer) = &mut self.solid {
-                    renderer.render(ui, rect, node, &w2c, &c2c);
+                    renderer.render(ui, rect, node, &w2c, &c2c, &overlays);
                 }
             }
             RenderMode::Lighting => {
                 if let Some(renderer) = &mut self.shaded {
-                    renderer.render(ui, rect, node, &w2c, &c2c);
+                    renderer.render(ui, rect, node, &w2c, &c2c, settings, &overlays);
                 }
             }
-            _ => {
-                ui.label("Unsupported render mode");
+            RenderMode::Debug(mode) => {
+                if let Some(renderer) = &mut self.debug {
+                    renderer.render(ui, rect, node, &w2c, &c2c, mode, &overlays);
+                }
             }
         }
-        ui.painter().rect_stroke(
-            scaled_rect,
-            0.0,
-            egui::Stroke::new(1.0, egui::Color32::WHITE),
-            egui::StrokeKind::Inside,
-        );
+        if self.view_through_camera {
+            ui.painter().rect_stroke(
+                scaled_rect,
+                0.0,
+                egui::Stroke::new(1.0, egui::Color32::WHITE),
+                egui::StrokeKind::Inside,
+            );
+            if !is_full_crop_window(&crop_window) {
+                let [x0, x1, y0, y1] = crop_window;
+                let crop_rect = egui::Rect::from_min_max(
+                    scaled_rect.lerp_inside(egui::vec2(x0, y0)),
+                    scaled_rect.lerp_inside(egui::vec2(x1, y1)),
+                );
+                ui.painter().rect_stroke(
+                    crop_rect,
+                    0.0,
+                    egui::Stroke::new(1.0, egui::Color32::from_rgb(255, 140, 25)),
+                    egui::StrokeKind::Inside,
+                );
+            }
+            let mut gate_text = format!("{} x {}", render_size.x, render_size.y);
+            if let Some((lens_radius, focal_distance)) = lens {
+                gate_text += &format!("  lens {:.3}  focus {:.2}", lens_radius, focal_distance);
+            }
+            ui.painter().text(
+                scaled_rect.left_bottom() + egui::vec2(5.0, -5.0),
+                egui::Align2::LEFT_BOTTOM,
+                gate_text,
+                egui::FontId::monospace(12.0),
+                egui::Color32::WHITE,
+            );
+        }
+
+        self.gizmo.show_overlay(ui);
 
         let show_fps = true; //TODO: add option to show/hide fps
         if show_fps {
@@ -0,0 +1,318 @@
+use crate::conversion::mesh_data::create_mesh_data;
+use crate::model::base::Matrix4x4;
+use crate::model::base::Vector3;
+use crate::model::scene::Node;
+use crate::model::scene::ShapeComponent;
+use crate::render::SceneItemType;
+use crate::render::get_scene_items;
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use uuid::Uuid;
+
+const MIN_DISTANCE: f32 = 1e-3;
+const MAX_PITCH_DOT: f32 = 0.995; // Keeps the view off the poles of the up axis
+const FRAME_MARGIN: f32 = 1.1;
+
+// Rodrigues' rotation of v around a unit axis
+fn rotate_vector(v: &Vector3, axis: &Vector3, angle: f32) -> Vector3 {
+    let (s, c) = angle.sin_cos();
+    let k = Vector3::dot(axis, v) * (1.0 - c);
+    return *v * c + Vector3::cross(axis, v) * s + *axis * k;
+}
+
+/// Editor camera of the scene view, kept apart from the pbrt camera of the scene.
+///
+/// The camera orbits around `pivot` at `distance`, looking along `forward`.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewportCamera {
+    pub pivot: Vector3,
+    pub distance: f32,
+    pub forward: Vector3,
+    pub up: Vector3,    // World up from the coordinate system
+    pub fov: f32,       // Vertical field of view in radians
+    pub mirror_x: bool, // Follows a `Scale -1 1 1` on the render camera
+}
+
+impl Default for ViewportCamera {
+    fn default() -> Self {
+        let eye = Vector3::new(15.0, 8.0, -15.0);
+        ViewportCamera {
+            pivot: Vector3::zero(),
+            distance: eye.length(),
+            forward: (-eye).normalize(),
+            up: Vector3::new(0.0, 1.0, 0.0),
+            fov: 35.0f32.to_radians(),
+            mirror_x: false,
+        }
+    }
+}
+
+impl ViewportCamera {
+    /// Takes the position and direction of a camera, keeping the pivot `distance` ahead.
+    pub fn set_from_camera_to_world(
+        &mut self,
+        camera_to_world: &Matrix4x4,
+        up: &Vector3,
+        fov: f32,
+        distance: f32,
+    ) {
+        let eye = camera_to_world.transform_point(&Vector3::zero());
+        let forward = camera_to_world
+            .transform_vector(&Vector3::new(0.0, 0.0, 1.0))
+            .normalize();
+        let right = camera_to_world.transform_vector(&Vector3::new(1.0, 0.0, 0.0));
+        let up = up.normalize();
+        let forward = if Vector3::dot(&forward, &up).abs() > MAX_PITCH_DOT {
+            // Looking straight along the up axis, tilt slightly towards the camera's up
+            let camera_up = camera_to_world.transform_vector(&Vector3::new(0.0, 1.0, 0.0));
+            (forward + camera_up.normalize() * 0.1).normalize()
+        } else {
+            forward
+        };
+        self.up = up;
+        self.forward = forward;
+        self.distance = distance.max(MIN_DISTANCE);
+        self.pivot = eye + forward * self.distance;
+        self.fov = fov;
+        // Handedness of the camera frame against the unmirrored one
+        let unmirrored = Vector3::cross(&up, &forward);
+        self.mirror_x = Vector3::dot(&unmirrored, &right) < 0.0;
+    }
+
+    pub fn get_eye(&self) -> Vector3 {
+        return self.pivot - self.forward * self.distance;
+    }
+
+    /// Direction of the screen's right edge in world space.
+    pub fn get_right(&self) -> Vector3 {
+        let right = Vector3::cross(&self.up, &self.forward).normalize();
+        return if self.mirror_x { -right } else { right };
+    }
+
+    /// Direction of the screen's top edge in world space.
+    pub fn get_camera_up(&self) -> Vector3 {
+        let right = Vector3::cross(&self.up, &self.forward).normalize();
+        return Vector3::cross(&self.forward, &right).normalize();
+    }
+
+    pub fn get_camera_to_world(&self) -> Matrix4x4 {
+        let eye = self.get_eye();
+        let m = Matrix4x4::camera_to_world(
+            eye.x,
+            eye.y,
+            eye.z,
+            self.pivot.x,
+            self.pivot.y,
+            self.pivot.z,
+            self.up.x,
+            self.up.y,
+            self.up.z,
+        );
+        if self.mirror_x {
+            return m * Matrix4x4::scale(-1.0, 1.0, 1.0);
+        }
+        return m;
+    }
+
+    pub fn get_world_to_camera(&self) -> Matrix4x4 {
+        return self
+            .get_camera_to_world()
+            .inverse()
+            .unwrap_or(Matrix4x4::identity());
+    }
+
+    pub fn get_clip_range(&self) -> (f32, f32) {
+        let znear = (self.distance * 0.01).clamp(MIN_DISTANCE, 1.0);
+        let zfar = f32::max(10000.0, self.distance * 100.0);
+        return (znear, zfar);
+    }
+
+    fn rotate_forward(&mut self, yaw: f32, pitch: f32) {
+        let yaw = if self.mirror_x { -yaw } else { yaw };
+        let forward = rotate_vector(&self.forward, &self.up, yaw).normalize();
+        let right = Vector3::cross(&self.up, &forward).normalize();
+        let pitched = rotate_vector(&forward, &right, pitch).normalize();
+        if Vector3::dot(&pitched, &self.up).abs() < MAX_PITCH_DOT {
+            self.forward = pitched;
+        } else {
+            self.forward = forward;
+        }
+    }
+
+    /// Turns around the pivot, angles in radians of screen-space motion.
+    pub fn orbit(&mut self, yaw: f32, pitch: f32) {
+        self.rotate_forward(yaw, pitch);
+    }
+
+    /// Turns the view around the eye, as in fly mode.
+    pub fn look(&mut self, yaw: f32, pitch: f32) {
+        let eye = self.get_eye();
+        self.rotate_forward(yaw, pitch);
+        self.pivot = eye + self.forward * self.distance;
+    }
+
+    /// Moves the pivot so the scene follows a drag of `dx`, `dy` pixels.
+    pub fn pan(&mut self, dx: f32, dy: f32, viewport_height: f32) {
+        let scale = 2.0 * self.distance * f32::tan(self.fov * 0.5) / viewport_height.max(1.0);
+        self.pivot =
+            self.pivot - self.get_right() * (dx * scale) + self.get_camera_up() * (dy * scale);
+    }
+
+    /// Scales the distance to the pivot by `factor`, keeping the point under
+    /// the cursor fixed; `ndc` is the cursor position in -1..1 with y up.
+    pub fn dolly(&mut self, factor: f32, ndc: [f32; 2], aspect: f32) {
+        let factor = factor.max(MIN_DISTANCE / self.distance);
+        let t = f32::tan(self.fov * 0.5);
+        let dir = self.get_right() * (ndc[0] * t * aspect)
+            + self.get_camera_up() * (ndc[1] * t)
+            + self.forward;
+        // Point under the cursor on the plane of the pivot
+        let target = self.get_eye() + dir * self.distance;
+        self.pivot = target + (self.pivot - target) * factor;
+        self.distance *= factor;
+    }
+
+    /// Moves eye and pivot along the view axes: x right, y world up, z forward.
+    pub fn fly(&mut self, motion: &Vector3) {
+        let offset = self.get_right() * motion.x + self.up * motion.y + self.forward * motion.z;
+        self.pivot = self.pivot + offset;
+    }
+
+    /// Fits a bounding sphere in the view.
+    pub fn frame(&mut self, center: &Vector3, radius: f32, aspect: f32) {
+        let half_fov = f32::min(self.fov * 0.5, f32::atan(f32::tan(self.fov * 0.5) * aspect));
+        self.pivot = *center;
+        self.distance = f32::max(radius * FRAME_MARGIN / half_fov.sin(), MIN_DISTANCE);
+    }
+}
+
+fn collect_node_ids(node: &Arc<RwLock<Node>>, ids: &mut HashSet<Uuid>) {
+    let node = node.read().unwrap();
+    ids.insert(node.get_id());
+    for child in node.children.iter() {
+        collect_node_ids(child, ids);
+    }
+}
+
+/// Bounding sphere of the shapes under `selected`, or of the whole scene.
+///
+/// Nodes without shapes, such as point lights, count as their position.
+pub fn get_selection_bounds(
+    root_node: &Arc<RwLock<Node>>,
+    selected: Option<&Arc<RwLock<Node>>>,
+) -> Option<(Vector3, f32)> {
+    let mut ids = HashSet::new();
+    collect_node_ids(selected.unwrap_or(root_node), &mut ids);
+    let selected_id = selected.map(|node| node.read().unwrap().get_id());
+    let mut min = Vector3::new(f32::MAX, f32::MAX, f32::MAX);
+    let mut max = Vector3::new(f32::MIN, f32::MIN, f32::MIN);
+    let mut add_point = |p: Vector3| {
+        min = Vector3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+        max = Vector3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+    };
+    for item in get_scene_items(root_node).iter() {
+        let node = item.node.read().unwrap();
+        if !ids.contains(&node.get_id()) {
+            continue;
+        }
+        // The render camera only counts when it is the selection itself
+        if item.category == SceneItemType::Camera && selected_id != Some(node.get_id()) {
+            continue;
+        }
+        let mesh_data = node
+            .get_component::<ShapeComponent>()
+            .and_then(|component| {
+                let shape = component.get_shape();
+                let shape = shape.read().unwrap();
+                create_mesh_data(&shape)
+            });
+        match mesh_data {
+            Some(mesh_data) if !mesh_data.positions.is_empty() => {
+                for p in mesh_data.positions.chunks_exact(3) {
+                    add_point(item.matrix.transform_point(&Vector3::new(p[0], p[1], p[2])));
+                }
+            }
+            _ => {
+                add_point(item.matrix.transform_point(&Vector3::zero()));
+            }
+        }
+    }
+    if min.x > max.x {
+        return None;
+    }
+    let center = (min + max) * 0.5;
+    let radius = f32::max((max - min).length() * 0.5, 0.1);
+    return Some((center, radius));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: &Vector3, b: &Vector3) {
+        assert!((*a - *b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_orbit_keeps_pivot_and_distance() {
+        let mut camera = ViewportCamera::default();
+        let pivot = camera.pivot;
+        let distance = (camera.get_eye() - camera.pivot).length();
+        camera.orbit(0.7, -0.3);
+        assert_near(&camera.pivot, &pivot);
+        assert!(((camera.get_eye() - camera.pivot).length() - distance).abs() < 1e-4);
+        // Never flips over the up axis
+        camera.orbit(0.0, 10.0);
+        assert!(Vector3::dot(&camera.forward, &camera.up).abs() < MAX_PITCH_DOT);
+    }
+
+    #[test]
+    fn test_look_keeps_eye() {
+        let mut camera = ViewportCamera::default();
+        let eye = camera.get_eye();
+        camera.look(-0.4, 0.2);
+        assert_near(&camera.get_eye(), &eye);
+    }
+
+    #[test]
+    fn test_dolly_keeps_cursor_point() {
+        let mut camera = ViewportCamera::default();
+        let t = f32::tan(camera.fov * 0.5);
+        let target = camera.get_eye()
+            + (camera.get_right() * (0.5 * t * 1.5)
+                + camera.get_camera_up() * (-0.25 * t)
+                + camera.forward)
+                * camera.distance;
+        camera.dolly(0.5, [0.5, -0.25], 1.5);
+        let w2c = camera.get_world_to_camera();
+        let p = w2c.transform_point(&target);
+        assert!((p.x / (p.z * t * 1.5) - 0.5).abs() < 1e-3);
+        assert!((p.y / (p.z * t) + 0.25).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_camera_to_world_round_trip() {
+        let mut camera = ViewportCamera::default();
+        camera.mirror_x = true;
+        let m = camera.get_camera_to_world();
+        let mut other = ViewportCamera::default();
+        other.set_from_camera_to_world(&m, &camera.up, camera.fov, camera.distance);
+        assert!(other.mirror_x);
+        assert_near(&other.get_eye(), &camera.get_eye());
+        assert_near(&other.forward, &camera.forward);
+        assert_near(&other.get_right(), &camera.get_right());
+    }
+
+    #[test]
+    fn test_frame_fits_sphere() {
+        let mut camera = ViewportCamera::default();
+        let center = Vector3::new(1.0, 2.0, 3.0);
+        camera.frame(&center, 2.0, 2.0);
+        assert_near(&camera.pivot, &center);
+        let half_fov = camera.fov * 0.5;
+        assert!(camera.distance * half_fov.sin() >= 2.0);
+    }
+}
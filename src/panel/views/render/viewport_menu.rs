@@ -0,0 +1,205 @@
+use crate::model::scene::LightComponent;
+use crate::model::scene::Node;
+use crate::render::ViewportSettings;
+use crate::render::viewport_settings::MAX_SHADOW_CASCADES;
+use crate::render::viewport_settings::MAX_SHADOWED_LIGHTS;
+use crate::render::viewport_settings::SHADOW_MAP_SIZES;
+use crate::render::viewport_settings::ToneMapping;
+
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use eframe::egui;
+
+const SHADOW_LIGHT_TYPES: [&str; 3] = ["distant", "spot", "point"];
+
+fn show_shadow_settings(
+    ui: &mut egui::Ui,
+    settings: &mut ViewportSettings,
+    root_node: &Arc<RwLock<Node>>,
+) {
+    let shadows = &mut settings.shadows;
+    ui.checkbox(&mut shadows.enabled, "Shadows");
+    ui.add_enabled_ui(shadows.enabled, |ui| {
+        egui::Grid::new("shadow_settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Shadowed Lights");
+                ui.add(egui::Slider::new(
+                    &mut shadows.max_shadowed_lights,
+                    0..=MAX_SHADOWED_LIGHTS,
+                ))
+                .on_hover_text("Budget of lights rendering shadow maps");
+                ui.end_row();
+
+                ui.label("Resolution");
+                egui::ComboBox::from_id_salt("shadow_map_size")
+                    .selected_text(format!("{}", shadows.map_size))
+                    .show_ui(ui, |ui| {
+                        for size in SHADOW_MAP_SIZES {
+                            ui.selectable_value(&mut shadows.map_size, size, format!("{}", size));
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Cascades");
+                ui.add(egui::Slider::new(
+                    &mut shadows.cascade_count,
+                    1..=MAX_SHADOW_CASCADES,
+                ));
+                ui.end_row();
+
+                ui.label("PCF Radius");
+                ui.add(egui::Slider::new(&mut shadows.pcf_radius, 0..=3));
+                ui.end_row();
+
+                ui.label("Depth Bias");
+                ui.add(
+                    egui::DragValue::new(&mut shadows.depth_bias)
+                        .speed(0.0001)
+                        .range(0.0..=0.01),
+                );
+                ui.end_row();
+
+                ui.label("Normal Bias");
+                ui.add(
+                    egui::DragValue::new(&mut shadows.normal_bias)
+                        .speed(0.05)
+                        .range(0.0..=10.0),
+                );
+                ui.end_row();
+            });
+
+        ui.separator();
+        ui.label("Shadow Casting Lights");
+        for node in Node::find_nodes_by_component::<LightComponent>(root_node) {
+            let node = node.read().unwrap();
+            let Some(component) = node.get_component::<LightComponent>() else {
+                continue;
+            };
+            let light = component.get_light();
+            let light = light.read().unwrap();
+            let light_type = light.get_type();
+            if !SHADOW_LIGHT_TYPES.contains(&light_type.as_str()) {
+                continue;
+            }
+            let id = light.get_id();
+            let mut enabled = shadows.is_light_enabled(id);
+            let label = format!("{} ({})", node.get_name(), light_type);
+            if ui.checkbox(&mut enabled, label).changed() {
+                shadows.set_light_enabled(id, enabled);
+            }
+        }
+    });
+}
+
+fn show_post_process_settings(ui: &mut egui::Ui, settings: &mut ViewportSettings) {
+    let post_process = &mut settings.post_process;
+    ui.checkbox(&mut post_process.ssao.enabled, "Ambient Occlusion");
+    ui.add_enabled_ui(post_process.ssao.enabled, |ui| {
+        egui::Grid::new("ssao_settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Radius");
+                ui.add(
+                    egui::DragValue::new(&mut post_process.ssao.radius)
+                        .speed(0.01)
+                        .range(0.01..=100.0),
+                )
+                .on_hover_text("Sampling radius in world units");
+                ui.end_row();
+
+                ui.label("Intensity");
+                ui.add(egui::Slider::new(
+                    &mut post_process.ssao.intensity,
+                    0.0..=2.0,
+                ));
+                ui.end_row();
+
+                ui.label("Samples");
+                ui.add(egui::Slider::new(
+                    &mut post_process.ssao.sample_count,
+                    4..=64,
+                ));
+                ui.end_row();
+            });
+    });
+
+    ui.separator();
+    egui::Grid::new("tone_mapping_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Exposure");
+            ui.add(
+                egui::Slider::new(&mut post_process.exposure, -10.0..=10.0)
+                    .step_by(0.1)
+                    .suffix(" EV"),
+            );
+            ui.end_row();
+
+            ui.label("Tone Mapping");
+            egui::ComboBox::from_id_salt("tone_mapping")
+                .selected_text(post_process.tone_mapping.get_label())
+                .show_ui(ui, |ui| {
+                    for tone_mapping in ToneMapping::all() {
+                        ui.selectable_value(
+                            &mut post_process.tone_mapping,
+                            *tone_mapping,
+                            tone_mapping.get_label(),
+                        );
+                    }
+                });
+            ui.end_row();
+        });
+    ui.checkbox(&mut post_process.false_color, "False Color")
+        .on_hover_text("Luminance from -4 (blue) to +4 (red) stops around middle gray");
+}
+
+fn show_gizmo_settings(ui: &mut egui::Ui, settings: &mut ViewportSettings) {
+    let gizmo = &mut settings.gizmo;
+    ui.checkbox(&mut gizmo.snap, "Snap")
+        .on_hover_text("Hold Ctrl while dragging to toggle");
+    egui::Grid::new("gizmo_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Move Step");
+            ui.add(
+                egui::DragValue::new(&mut gizmo.translate_step)
+                    .speed(0.01)
+                    .range(0.001..=1000.0),
+            );
+            ui.end_row();
+
+            ui.label("Rotate Step");
+            ui.add(
+                egui::DragValue::new(&mut gizmo.rotate_step)
+                    .speed(0.5)
+                    .range(0.1..=180.0)
+                    .suffix("°"),
+            );
+            ui.end_row();
+
+            ui.label("Scale Step");
+            ui.add(
+                egui::DragValue::new(&mut gizmo.scale_step)
+                    .speed(0.01)
+                    .range(0.001..=10.0),
+            );
+            ui.end_row();
+        });
+}
+
+/// Menu of the scene view options, shown in the render mode bar.
+pub fn show_viewport_menu(
+    ui: &mut egui::Ui,
+    settings: &mut ViewportSettings,
+    root_node: &Arc<RwLock<Node>>,
+) {
+    ui.menu_button("Viewport", |ui| {
+        show_shadow_settings(ui, settings, root_node);
+        ui.separator();
+        show_post_process_settings(ui, settings);
+        ui.separator();
+        show_gizmo_settings(ui, settings);
+    });
+}
@@ -8,8 +8,12 @@ pub type PreprocessorResult<T> = Result<T, PreprocessorError>;
 /// Errors that can occur during preprocessing
 #[derive(Debug, Clone, PartialEq)]
 pub enum PreprocessorError {
-    /// Parse error with line number and description
-    ParseError { line: usize, message: String },
+    /// Parse error with the file, line number and description
+    ParseError {
+        file: String,
+        line: usize,
+        message: String,
+    },
 
     /// File not found for include directive
     FileNotFound { path: String, line: usize },
@@ -25,13 +29,24 @@ pub enum PreprocessorError {
 
     /// Invalid macro usage
     InvalidMacro { message: String, line: usize },
+
+    /// `#error` directive reached in an active region
+    ErrorDirective {
+        message: String,
+        file: String,
+        line: usize,
+    },
 }
 
 impl fmt::Display for PreprocessorError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            PreprocessorError::ParseError { line, message } => {
-                write!(f, "Parse error at line {}: {}", line, message)
+            PreprocessorError::ParseError {
+                file,
+                line,
+                message,
+            } => {
+                write!(f, "Parse error at {}:{}: {}", file, line, message)
             }
             PreprocessorError::FileNotFound { path, line } => {
                 write!(f, "File not found at line {}: {}", line, path)
@@ -53,6 +68,13 @@ impl fmt::Display for PreprocessorError {
             PreprocessorError::InvalidMacro { message, line } => {
                 write!(f, "Invalid macro at line {}: {}", line, message)
             }
+            PreprocessorError::ErrorDirective {
+                message,
+                file,
+                line,
+            } => {
+                write!(f, "#error at {}:{}: {}", file, line, message)
+            }
         }
     }
 }
@@ -0,0 +1,250 @@
+//! Constant-expression evaluator for `#if` and `#elif`
+//!
+//! The processor resolves `defined(X)` and expands macros before the expression gets here,
+//! so only integer literals, operators and parentheses are left to evaluate.
+
+use nom::{
+    IResult,
+    branch::alt,
+    bytes::complete::{tag, tag_no_case, take_while1},
+    character::complete::{char, digit1, hex_digit1, multispace0, one_of},
+    combinator::{all_consuming, map, map_res, not, opt, peek},
+    multi::many0,
+    sequence::{delimited, pair, preceded, terminated, tuple},
+};
+
+/// Parsed form of a constant expression
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(i64),
+    Unary(char, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+/// Wrap a parser to skip surrounding whitespace
+fn ws<'a, O, F>(inner: F) -> impl FnMut(&'a str) -> IResult<&'a str, O>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, O>,
+{
+    delimited(multispace0, inner, multispace0)
+}
+
+/// Parse an integer literal: decimal or hexadecimal with optional u/l suffixes
+fn number(input: &str) -> IResult<&str, Expr> {
+    let hex = map_res(preceded(tag_no_case("0x"), hex_digit1), |s: &str| {
+        i64::from_str_radix(s, 16)
+    });
+    let dec = map_res(digit1, |s: &str| s.parse::<i64>());
+    let (input, value) = ws(terminated(
+        alt((hex, dec)),
+        opt(take_while1(|c: char| "uUlL".contains(c))),
+    ))(input)?;
+    Ok((input, Expr::Number(value)))
+}
+
+fn primary(input: &str) -> IResult<&str, Expr> {
+    alt((number, delimited(ws(char('(')), conditional, ws(char(')')))))(input)
+}
+
+fn unary(input: &str) -> IResult<&str, Expr> {
+    alt((
+        map(pair(ws(one_of("!-+~")), unary), |(op, expr)| {
+            Expr::Unary(op, Box::new(expr))
+        }),
+        primary,
+    ))(input)
+}
+
+/// Fold a left-associative chain of binary operators
+fn fold_binary(first: Expr, rest: Vec<(&'static str, Expr)>) -> Expr {
+    rest.into_iter().fold(first, |lhs, (op, rhs)| {
+        Expr::Binary(op, Box::new(lhs), Box::new(rhs))
+    })
+}
+
+/// Match an operator token and return it with a static lifetime
+fn op<'a>(token: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'static str> {
+    map(ws(tag(token)), move |_| token)
+}
+
+/// Match a single-character operator that is not the start of a longer one
+fn op_not<'a>(
+    token: &'static str,
+    next: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, &'static str> {
+    map(
+        ws(terminated(tag(token), not(peek(one_of(next))))),
+        move |_| token,
+    )
+}
+
+fn multiplicative(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = unary(input)?;
+    let (input, rest) = many0(pair(alt((op("*"), op("/"), op("%"))), unary))(input)?;
+    Ok((input, fold_binary(first, rest)))
+}
+
+fn additive(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = multiplicative(input)?;
+    let (input, rest) = many0(pair(alt((op("+"), op("-"))), multiplicative))(input)?;
+    Ok((input, fold_binary(first, rest)))
+}
+
+fn shift(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = additive(input)?;
+    let (input, rest) = many0(pair(alt((op("<<"), op(">>"))), additive))(input)?;
+    Ok((input, fold_binary(first, rest)))
+}
+
+fn relational(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = shift(input)?;
+    let (input, rest) = many0(pair(
+        alt((op("<="), op(">="), op_not("<", "<"), op_not(">", ">"))),
+        shift,
+    ))(input)?;
+    Ok((input, fold_binary(first, rest)))
+}
+
+fn equality(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = relational(input)?;
+    let (input, rest) = many0(pair(alt((op("=="), op("!="))), relational))(input)?;
+    Ok((input, fold_binary(first, rest)))
+}
+
+fn bit_and(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = equality(input)?;
+    let (input, rest) = many0(pair(op_not("&", "&"), equality))(input)?;
+    Ok((input, fold_binary(first, rest)))
+}
+
+fn bit_xor(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = bit_and(input)?;
+    let (input, rest) = many0(pair(op("^"), bit_and))(input)?;
+    Ok((input, fold_binary(first, rest)))
+}
+
+fn bit_or(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = bit_xor(input)?;
+    let (input, rest) = many0(pair(op_not("|", "|"), bit_xor))(input)?;
+    Ok((input, fold_binary(first, rest)))
+}
+
+fn logical_and(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = bit_or(input)?;
+    let (input, rest) = many0(pair(op("&&"), bit_or))(input)?;
+    Ok((input, fold_binary(first, rest)))
+}
+
+fn logical_or(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = logical_and(input)?;
+    let (input, rest) = many0(pair(op("||"), logical_and))(input)?;
+    Ok((input, fold_binary(first, rest)))
+}
+
+/// `cond ? a : b`, right-associative and lowest precedence
+fn conditional(input: &str) -> IResult<&str, Expr> {
+    let (input, cond) = logical_or(input)?;
+    let (input, branches) = opt(tuple((
+        ws(char('?')),
+        conditional,
+        ws(char(':')),
+        conditional,
+    )))(input)?;
+    match branches {
+        Some((_, a, _, b)) => Ok((
+            input,
+            Expr::Conditional(Box::new(cond), Box::new(a), Box::new(b)),
+        )),
+        None => Ok((input, cond)),
+    }
+}
+
+fn eval(expr: &Expr) -> Result<i64, String> {
+    match expr {
+        Expr::Number(v) => Ok(*v),
+        Expr::Unary(op, e) => {
+            let v = eval(e)?;
+            Ok(match op {
+                '!' => (v == 0) as i64,
+                '-' => v.wrapping_neg(),
+                '~' => !v,
+                _ => v,
+            })
+        }
+        Expr::Conditional(c, a, b) => {
+            if eval(c)? != 0 {
+                eval(a)
+            } else {
+                eval(b)
+            }
+        }
+        // short-circuit so `defined(X) && 1 / X` does not fail when X is 0
+        Expr::Binary("&&", a, b) => Ok((eval(a)? != 0 && eval(b)? != 0) as i64),
+        Expr::Binary("||", a, b) => Ok((eval(a)? != 0 || eval(b)? != 0) as i64),
+        Expr::Binary(op, a, b) => {
+            let a = eval(a)?;
+            let b = eval(b)?;
+            match *op {
+                "*" => Ok(a.wrapping_mul(b)),
+                "/" | "%" if b == 0 => Err("Division by zero".to_string()),
+                "/" => Ok(a.wrapping_div(b)),
+                "%" => Ok(a.wrapping_rem(b)),
+                "+" => Ok(a.wrapping_add(b)),
+                "-" => Ok(a.wrapping_sub(b)),
+                "<<" => Ok(a.wrapping_shl(b as u32)),
+                ">>" => Ok(a.wrapping_shr(b as u32)),
+                "<" => Ok((a < b) as i64),
+                ">" => Ok((a > b) as i64),
+                "<=" => Ok((a <= b) as i64),
+                ">=" => Ok((a >= b) as i64),
+                "==" => Ok((a == b) as i64),
+                "!=" => Ok((a != b) as i64),
+                "&" => Ok(a & b),
+                "^" => Ok(a ^ b),
+                "|" => Ok(a | b),
+                _ => Err(format!("Unknown operator {}", op)),
+            }
+        }
+    }
+}
+
+/// Evaluate an integer constant expression
+pub fn evaluate(input: &str) -> Result<i64, String> {
+    let (_, expr) = all_consuming(ws(conditional))(input)
+        .map_err(|_| format!("Invalid constant expression: {}", input.trim()))?;
+    eval(&expr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_arithmetic() {
+        assert_eq!(evaluate("1 + 2 * 3"), Ok(7));
+        assert_eq!(evaluate("(1 + 2) * 3"), Ok(9));
+        assert_eq!(evaluate("10 / 3 % 2"), Ok(1));
+        assert_eq!(evaluate("-4 + ~0"), Ok(-5));
+        assert_eq!(evaluate("0x10 >> 2 << 1"), Ok(8));
+        assert_eq!(evaluate("100u"), Ok(100));
+    }
+
+    #[test]
+    fn test_evaluate_logical() {
+        assert_eq!(evaluate("1 < 2 && 2 <= 2"), Ok(1));
+        assert_eq!(evaluate("1 > 2 || 3 != 3"), Ok(0));
+        assert_eq!(evaluate("!0 == 1"), Ok(1));
+        assert_eq!(evaluate("6 & 3 | 8 ^ 1"), Ok(11));
+        assert_eq!(evaluate("0 ? 1 : 2 ? 3 : 4"), Ok(3));
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        assert!(evaluate("1 / 0").is_err());
+        assert!(evaluate("1 +").is_err());
+        assert!(evaluate("3.5 > 1").is_err());
+        // the right side of && is not evaluated
+        assert_eq!(evaluate("0 && 1 / 0"), Ok(0));
+    }
+}
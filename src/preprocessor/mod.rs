@@ -2,15 +2,19 @@
 //!
 //! A C-like preprocessor that handles:
 //! - `#define` directives for constant and macro definitions
-//! - `#ifdef` and `#ifndef` for conditional compilation
-//! - `#include` for file inclusion with circular dependency detection
+//! - `#undef` to remove a definition
+//! - `#ifdef`, `#ifndef`, `#if` and `#elif` with `#else` for conditional compilation
+//! - `#include` for file inclusion with circular dependency detection and `#pragma once`
+//! - `#error` to stop on unsupported configurations
+//! - `#line` and a source map from output lines back to the original files
 
 mod error;
+mod expression;
 mod parser;
 mod processor;
 
 pub use error::{PreprocessorError, PreprocessorResult};
-pub use processor::Preprocessor;
+pub use processor::{Preprocessor, SourceLocation};
 
 #[cfg(test)]
 mod tests;
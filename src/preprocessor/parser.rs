@@ -4,10 +4,10 @@ use nom::{
     IResult,
     branch::alt,
     bytes::complete::{tag, take_until, take_while},
-    character::complete::{alpha1, alphanumeric1, char, space0, space1},
-    combinator::{map, opt, recognize},
+    character::complete::{alpha1, alphanumeric1, char, digit1, satisfy, space0, space1},
+    combinator::{map, map_res, not, opt, peek, recognize},
     multi::{many0, separated_list0},
-    sequence::{delimited, tuple},
+    sequence::{delimited, preceded, tuple},
 };
 
 /// Represents a preprocessor directive
@@ -29,9 +29,33 @@ pub enum Directive {
     /// #ifndef NAME
     IfNDef { name: String },
 
+    /// #if EXPR
+    If { expr: String },
+
+    /// #elif EXPR
+    ElIf { expr: String },
+
+    /// #else
+    Else,
+
     /// #endif
     EndIf,
 
+    /// #undef NAME
+    Undef { name: String },
+
+    /// #pragma once
+    PragmaOnce,
+
+    /// #pragma anything-else (ignored)
+    Pragma { text: String },
+
+    /// #error message
+    Error { message: String },
+
+    /// #line NUMBER or #line NUMBER "file"
+    Line { line: usize, file: Option<String> },
+
     /// #include "path" or #include <path>
     Include { path: String },
 }
@@ -145,6 +169,104 @@ fn parse_endif(input: &str) -> IResult<&str, Directive> {
     Ok((input, Directive::EndIf))
 }
 
+/// Strip a trailing `//` comment from a directive argument
+fn strip_comment(text: &str) -> &str {
+    match text.find("//") {
+        Some(pos) => &text[..pos],
+        None => text,
+    }
+}
+
+/// Parse the expression of #if or #elif, not matching #ifdef/#ifndef
+fn parse_condition<'a>(keyword: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, String> {
+    move |input: &'a str| {
+        let (input, _) = tag(keyword)(input)?;
+        let (input, _) = not(peek(satisfy(|c: char| c.is_alphanumeric() || c == '_')))(input)?;
+        let (input, expr) = until_eol(input)?;
+        Ok((input, strip_comment(expr).trim().to_string()))
+    }
+}
+
+/// Parse #if directive
+fn parse_if(input: &str) -> IResult<&str, Directive> {
+    map(parse_condition("#if"), |expr| Directive::If { expr })(input)
+}
+
+/// Parse #elif directive
+fn parse_elif(input: &str) -> IResult<&str, Directive> {
+    map(parse_condition("#elif"), |expr| Directive::ElIf { expr })(input)
+}
+
+/// Parse #else directive
+fn parse_else(input: &str) -> IResult<&str, Directive> {
+    let (input, _) = tag("#else")(input)?;
+    let (input, _) = not(peek(satisfy(|c: char| c.is_alphanumeric() || c == '_')))(input)?;
+
+    Ok((input, Directive::Else))
+}
+
+/// Parse #undef directive
+fn parse_undef(input: &str) -> IResult<&str, Directive> {
+    let (input, _) = tag("#undef")(input)?;
+    let (input, _) = space1(input)?;
+    let (input, name) = identifier(input)?;
+
+    Ok((
+        input,
+        Directive::Undef {
+            name: name.to_string(),
+        },
+    ))
+}
+
+/// Parse #pragma directive
+fn parse_pragma(input: &str) -> IResult<&str, Directive> {
+    let (input, _) = tag("#pragma")(input)?;
+    let (input, _) = space1(input)?;
+    let (input, text) = until_eol(input)?;
+    let text = strip_comment(text).trim();
+    if text == "once" {
+        return Ok((input, Directive::PragmaOnce));
+    }
+
+    Ok((
+        input,
+        Directive::Pragma {
+            text: text.to_string(),
+        },
+    ))
+}
+
+/// Parse #error directive
+fn parse_error(input: &str) -> IResult<&str, Directive> {
+    let (input, _) = tag("#error")(input)?;
+    let (input, _) = ws(input)?;
+    let (input, message) = until_eol(input)?;
+
+    Ok((
+        input,
+        Directive::Error {
+            message: message.trim().to_string(),
+        },
+    ))
+}
+
+/// Parse #line directive
+fn parse_line(input: &str) -> IResult<&str, Directive> {
+    let (input, _) = tag("#line")(input)?;
+    let (input, _) = space1(input)?;
+    let (input, line) = map_res(digit1, |s: &str| s.parse::<usize>())(input)?;
+    let (input, file) = opt(preceded(space1, parse_include_quoted))(input)?;
+
+    Ok((
+        input,
+        Directive::Line {
+            line,
+            file: file.map(|f| f.to_string()),
+        },
+    ))
+}
+
 /// Parse #include directive with quoted path
 fn parse_include_quoted(input: &str) -> IResult<&str, &str> {
     delimited(char('"'), take_until("\""), char('"'))(input)
@@ -174,9 +296,16 @@ pub fn parse_directive(input: &str) -> IResult<&str, Directive> {
     alt((
         parse_include,
         parse_define,
+        parse_undef,
         parse_ifdef,
         parse_ifndef,
+        parse_if,
+        parse_elif,
+        parse_else,
         parse_endif,
+        parse_pragma,
+        parse_error,
+        parse_line,
     ))(input)
 }
 
@@ -252,6 +381,62 @@ mod tests {
         assert_eq!(directive, Directive::EndIf);
     }
 
+    #[test]
+    fn test_parse_if_elif_else() {
+        let (_, directive) = parse_directive("#if defined(A) && B > 1 // comment").unwrap();
+        assert_eq!(
+            directive,
+            Directive::If {
+                expr: "defined(A) && B > 1".to_string(),
+            }
+        );
+        let (_, directive) = parse_directive("#elif (B)").unwrap();
+        assert_eq!(
+            directive,
+            Directive::ElIf {
+                expr: "(B)".to_string(),
+            }
+        );
+        let (_, directive) = parse_directive("#else").unwrap();
+        assert_eq!(directive, Directive::Else);
+        // #ifdef is not mistaken for #if
+        let (_, directive) = parse_directive("#ifdef DEBUG").unwrap();
+        assert_eq!(
+            directive,
+            Directive::IfDef {
+                name: "DEBUG".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_undef_pragma_error_line() {
+        let (_, directive) = parse_directive("#undef DEBUG").unwrap();
+        assert_eq!(
+            directive,
+            Directive::Undef {
+                name: "DEBUG".to_string(),
+            }
+        );
+        let (_, directive) = parse_directive("#pragma once").unwrap();
+        assert_eq!(directive, Directive::PragmaOnce);
+        let (_, directive) = parse_directive("#error Unsupported material").unwrap();
+        assert_eq!(
+            directive,
+            Directive::Error {
+                message: "Unsupported material".to_string(),
+            }
+        );
+        let (_, directive) = parse_directive(r#"#line 10 "common.wgsl""#).unwrap();
+        assert_eq!(
+            directive,
+            Directive::Line {
+                line: 10,
+                file: Some("common.wgsl".to_string()),
+            }
+        );
+    }
+
     #[test]
     fn test_parse_include_quoted() {
         let input = r#"#include "shader.wgsl""#;
@@ -1,11 +1,49 @@
 //! Preprocessor implementation
 
 use super::error::{PreprocessorError, PreprocessorResult};
+use super::expression::evaluate;
 use super::parser::{Directive, parse_directive};
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Location of an output line in the original sources
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// File the line came from, `<source>` for text passed to `process`
+    pub file: String,
+
+    /// 1-based line number in that file (as adjusted by `#line`)
+    pub line: usize,
+}
+
+/// State of one `#if`/`#ifdef`/`#ifndef` ... `#endif` block
+struct Conditional {
+    /// Whether the enclosing block emits lines
+    parent_active: bool,
+
+    /// Whether the current branch emits lines
+    active: bool,
+
+    /// Whether any branch of this block has been taken
+    taken: bool,
+
+    /// Whether `#else` has been seen
+    seen_else: bool,
+}
+
+impl Conditional {
+    fn new(parent_active: bool, condition: bool) -> Self {
+        let active = parent_active && condition;
+        Self {
+            parent_active,
+            active,
+            taken: active,
+            seen_else: false,
+        }
+    }
+}
+
 /// A preprocessor that handles C-like directives
 pub struct Preprocessor {
     /// Defined symbols (from #define)
@@ -19,27 +57,23 @@ pub struct Preprocessor {
 
     /// Set of currently included files (for circular dependency detection)
     include_stack: HashSet<PathBuf>,
+
+    /// Files marked with `#pragma once` that were already included
+    once_files: HashSet<PathBuf>,
+
+    /// Original location of each line of the last output
+    source_map: Vec<SourceLocation>,
 }
 
 impl Preprocessor {
     /// Create a new preprocessor
     pub fn new() -> Self {
-        Self {
-            defines: HashMap::new(),
-            macros: HashMap::new(),
-            base_paths: vec![PathBuf::from(".")],
-            include_stack: HashSet::new(),
-        }
+        Self::with_base_path(".")
     }
 
     /// Create a preprocessor with a specific base path for includes
     pub fn with_base_path<P: AsRef<Path>>(base_path: P) -> Self {
-        Self {
-            defines: HashMap::new(),
-            macros: HashMap::new(),
-            base_paths: vec![base_path.as_ref().to_path_buf()],
-            include_stack: HashSet::new(),
-        }
+        Self::with_base_paths([base_path])
     }
 
     /// Create a preprocessor with multiple base paths for includes
@@ -52,6 +86,8 @@ impl Preprocessor {
                 .map(|p| p.as_ref().to_path_buf())
                 .collect(),
             include_stack: HashSet::new(),
+            once_files: HashSet::new(),
+            source_map: Vec::new(),
         }
     }
 
@@ -65,92 +101,211 @@ impl Preprocessor {
         self.defines.insert(name.into(), value.into());
     }
 
+    /// Remove a symbol or macro
+    pub fn undefine(&mut self, name: &str) {
+        self.defines.remove(name);
+        self.macros.remove(name);
+    }
+
     /// Check if a symbol is defined
     pub fn is_defined(&self, name: &str) -> bool {
         self.defines.contains_key(name) || self.macros.contains_key(name)
     }
 
+    /// Original locations of the lines of the last output, one entry per line
+    pub fn source_map(&self) -> &[SourceLocation] {
+        &self.source_map
+    }
+
+    /// Original location of a 1-based line of the last output
+    pub fn get_source_location(&self, output_line: usize) -> Option<&SourceLocation> {
+        output_line
+            .checked_sub(1)
+            .and_then(|index| self.source_map.get(index))
+    }
+
     /// Process source code
     pub fn process(&mut self, source: &str) -> PreprocessorResult<String> {
-        let mut output = String::new();
-        let mut lines = source.lines().enumerate();
-        let mut conditional_stack: Vec<bool> = Vec::new();
-        let mut skip_depth = 0usize;
+        self.source_map.clear();
+        self.once_files.clear();
+        self.process_source(source, None)
+    }
+
+    /// Process a file, recording its path in the source map
+    pub fn process_file<P: AsRef<Path>>(&mut self, path: P) -> PreprocessorResult<String> {
+        let path = path.as_ref().to_path_buf();
+        let source = fs::read_to_string(&path).map_err(|e| PreprocessorError::IoError {
+            path: path.to_string_lossy().to_string(),
+            message: e.to_string(),
+        })?;
+        self.source_map.clear();
+        self.once_files.clear();
+        self.include_stack.insert(path.clone());
+        let result = self.process_source(&source, Some(&path));
+        self.include_stack.remove(&path);
+        result
+    }
 
-        while let Some((line_num, line)) = lines.next() {
+    /// Process the text of one file, appending to the source map
+    fn process_source(&mut self, source: &str, path: Option<&Path>) -> PreprocessorResult<String> {
+        let mut output = String::new();
+        let mut conditional_stack: Vec<Conditional> = Vec::new();
+        let mut file = path
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| "<source>".to_string());
+        let source_name = file.clone(); // Errors name the file itself, not the one set by #line
+        let mut logical_line = 0usize;
+
+        for (line_num, line) in source.lines().enumerate() {
             let line_number = line_num + 1;
+            logical_line += 1;
             let trimmed = line.trim_start();
+            let active = conditional_stack.last().is_none_or(|c| c.active);
 
             // Check if this is a preprocessor directive
             if trimmed.starts_with('#') {
-                match parse_directive(trimmed) {
-                    Ok((_, directive)) => match directive {
-                        Directive::Define { name, value } => {
-                            if skip_depth == 0 {
-                                self.defines.insert(name, value);
-                            }
-                        }
-                        Directive::DefineMacro { name, params, body } => {
-                            if skip_depth == 0 {
-                                self.macros.insert(name, (params, body));
-                            }
+                let directive = match parse_directive(trimmed) {
+                    Ok((_, directive)) => directive,
+                    // Skipped regions may contain anything
+                    Err(_) if !active => continue,
+                    Err(_) => {
+                        return Err(PreprocessorError::ParseError {
+                            file: source_name.clone(),
+                            line: line_number,
+                            message: format!("Failed to parse directive: {}", trimmed),
+                        });
+                    }
+                };
+                match directive {
+                    Directive::IfDef { name } => {
+                        let condition = active && self.is_defined(&name);
+                        conditional_stack.push(Conditional::new(active, condition));
+                    }
+                    Directive::IfNDef { name } => {
+                        let condition = active && !self.is_defined(&name);
+                        conditional_stack.push(Conditional::new(active, condition));
+                    }
+                    Directive::If { expr } => {
+                        let condition =
+                            active && self.evaluate_condition(&expr, &source_name, line_number)?;
+                        conditional_stack.push(Conditional::new(active, condition));
+                    }
+                    Directive::ElIf { expr } => {
+                        let Some(conditional) = conditional_stack.last_mut() else {
+                            return Err(PreprocessorError::ParseError {
+                                file: source_name.clone(),
+                                line: line_number,
+                                message: "Unexpected #elif without matching #if".to_string(),
+                            });
+                        };
+                        if conditional.seen_else {
+                            return Err(PreprocessorError::ParseError {
+                                file: source_name.clone(),
+                                line: line_number,
+                                message: "Unexpected #elif after #else".to_string(),
+                            });
                         }
-                        Directive::IfDef { name } => {
-                            let is_defined = self.is_defined(&name);
-                            conditional_stack.push(is_defined);
-                            if !is_defined || skip_depth > 0 {
-                                skip_depth += 1;
-                            }
+                        if conditional.parent_active && !conditional.taken {
+                            let condition =
+                                self.evaluate_condition(&expr, &source_name, line_number)?;
+                            conditional.active = condition;
+                            conditional.taken = condition;
+                        } else {
+                            conditional.active = false;
                         }
-                        Directive::IfNDef { name } => {
-                            let is_not_defined = !self.is_defined(&name);
-                            conditional_stack.push(is_not_defined);
-                            if !is_not_defined || skip_depth > 0 {
-                                skip_depth += 1;
-                            }
+                    }
+                    Directive::Else => {
+                        let Some(conditional) = conditional_stack.last_mut() else {
+                            return Err(PreprocessorError::ParseError {
+                                file: source_name.clone(),
+                                line: line_number,
+                                message: "Unexpected #else without matching #if".to_string(),
+                            });
+                        };
+                        if conditional.seen_else {
+                            return Err(PreprocessorError::ParseError {
+                                file: source_name.clone(),
+                                line: line_number,
+                                message: "Unexpected #else after #else".to_string(),
+                            });
                         }
-                        Directive::EndIf => {
-                            if conditional_stack.is_empty() {
-                                return Err(PreprocessorError::ParseError {
-                                    line: line_number,
-                                    message: "Unexpected #endif without matching #ifdef or #ifndef"
+                        conditional.active = conditional.parent_active && !conditional.taken;
+                        conditional.taken = true;
+                        conditional.seen_else = true;
+                    }
+                    Directive::EndIf => {
+                        if conditional_stack.pop().is_none() {
+                            return Err(PreprocessorError::ParseError {
+                                file: source_name.clone(),
+                                line: line_number,
+                                message:
+                                    "Unexpected #endif without matching #if, #ifdef or #ifndef"
                                         .to_string(),
-                                });
-                            }
-                            let condition = conditional_stack.pop().unwrap();
-                            if !condition || skip_depth > 1 {
-                                skip_depth = skip_depth.saturating_sub(1);
-                            }
+                            });
                         }
-                        Directive::Include { path } => {
-                            if skip_depth == 0 {
-                                let included_content = self.process_include(&path, line_number)?;
-                                output.push_str(&included_content);
-                                output.push('\n');
-                            }
+                    }
+                    _ if !active => {}
+                    Directive::Define { name, value } => {
+                        self.defines.insert(name, value);
+                    }
+                    Directive::DefineMacro { name, params, body } => {
+                        self.macros.insert(name, (params, body));
+                    }
+                    Directive::Undef { name } => {
+                        self.undefine(&name);
+                    }
+                    Directive::Include { path } => {
+                        let included_content = self.process_include(&path, line_number)?;
+                        output.push_str(&included_content);
+                        output.push('\n');
+                        self.source_map.push(SourceLocation {
+                            file: file.clone(),
+                            line: logical_line,
+                        });
+                    }
+                    Directive::PragmaOnce => {
+                        if let Some(path) = path {
+                            self.once_files.insert(path.to_path_buf());
                         }
-                    },
-                    Err(_) => {
-                        return Err(PreprocessorError::ParseError {
+                    }
+                    Directive::Pragma { .. } => {}
+                    Directive::Error { message } => {
+                        return Err(PreprocessorError::ErrorDirective {
+                            message,
+                            file: source_name.clone(),
                             line: line_number,
-                            message: format!("Failed to parse directive: {}", trimmed),
                         });
                     }
+                    Directive::Line {
+                        line,
+                        file: new_file,
+                    } => {
+                        // The line after #line N is line N
+                        logical_line = line.saturating_sub(1);
+                        if let Some(new_file) = new_file {
+                            file = new_file;
+                        }
+                    }
                 }
-            } else if skip_depth == 0 {
+            } else if active {
                 // Process line for macro/define substitutions
                 let processed_line = self.substitute_defines(line)?;
                 output.push_str(&processed_line);
                 output.push('\n');
+                self.source_map.push(SourceLocation {
+                    file: file.clone(),
+                    line: logical_line,
+                });
             }
         }
 
         // Check for unclosed conditionals
         if !conditional_stack.is_empty() {
             return Err(PreprocessorError::ParseError {
+                file: source_name,
                 line: 0,
                 message: format!(
-                    "{} unclosed conditional(s) (#ifdef/#ifndef without #endif)",
+                    "{} unclosed conditional(s) (#if/#ifdef/#ifndef without #endif)",
                     conditional_stack.len()
                 ),
             });
@@ -159,6 +314,97 @@ impl Preprocessor {
         Ok(output)
     }
 
+    /// Evaluate the expression of an #if or #elif directive
+    fn evaluate_condition(&self, expr: &str, file: &str, line: usize) -> PreprocessorResult<bool> {
+        // defined() has to be resolved before the symbols inside it are expanded
+        let resolved = self.resolve_defined(expr);
+        let expanded = self.substitute_defines(&resolved)?;
+        // Like C, identifiers left after expansion evaluate to 0
+        let mut integers = String::new();
+        let mut current_word = String::new();
+        for ch in expanded.chars().chain(std::iter::once(' ')) {
+            if ch.is_alphanumeric() || ch == '_' {
+                current_word.push(ch);
+                continue;
+            }
+            if !current_word.is_empty() {
+                if current_word.starts_with(|c: char| c.is_ascii_digit()) {
+                    integers.push_str(&current_word);
+                } else if current_word == "true" {
+                    integers.push('1');
+                } else {
+                    integers.push('0');
+                }
+                current_word.clear();
+            }
+            integers.push(ch);
+        }
+        evaluate(&integers)
+            .map(|value| value != 0)
+            .map_err(|message| PreprocessorError::ParseError {
+                file: file.to_string(),
+                line,
+                message,
+            })
+    }
+
+    /// Replace `defined(NAME)` and `defined NAME` with 1 or 0
+    fn resolve_defined(&self, expr: &str) -> String {
+        let chars: Vec<char> = expr.chars().collect();
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let mut result = String::new();
+        let mut i = 0;
+        while i < chars.len() {
+            if !is_word(chars[i]) {
+                result.push(chars[i]);
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < chars.len() && is_word(chars[i]) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if word != "defined" {
+                result.push_str(&word);
+                continue;
+            }
+            let mut j = i;
+            while j < chars.len() && chars[j].is_whitespace() {
+                j += 1;
+            }
+            let parenthesized = j < chars.len() && chars[j] == '(';
+            if parenthesized {
+                j += 1;
+                while j < chars.len() && chars[j].is_whitespace() {
+                    j += 1;
+                }
+            }
+            let name_start = j;
+            while j < chars.len() && is_word(chars[j]) {
+                j += 1;
+            }
+            let name: String = chars[name_start..j].iter().collect();
+            if parenthesized {
+                while j < chars.len() && chars[j].is_whitespace() {
+                    j += 1;
+                }
+                if j >= chars.len() || chars[j] != ')' || name.is_empty() {
+                    // Leave it for the evaluator to report
+                    result.push_str(&word);
+                    continue;
+                }
+                j += 1;
+            } else if name.is_empty() {
+                result.push_str(&word);
+                continue;
+            }
+            result.push(if self.is_defined(&name) { '1' } else { '0' });
+            i = j;
+        }
+        result
+    }
+
     /// Process an include directive
     fn process_include(&mut self, path: &str, _line: usize) -> PreprocessorResult<String> {
         // Try to resolve the include path from all base paths
@@ -180,6 +426,11 @@ impl Preprocessor {
                 });
             }
 
+            // Files with #pragma once are only included the first time
+            if self.once_files.contains(&candidate_path) {
+                return Ok(String::new());
+            }
+
             // Try to read the file
             match fs::read_to_string(&candidate_path) {
                 Ok(content) => {
@@ -217,7 +468,7 @@ impl Preprocessor {
         self.include_stack.insert(resolved_path.clone());
 
         // Process the included file
-        let processed = self.process(&content)?;
+        let processed = self.process_source(&content, Some(&resolved_path))?;
 
         // Remove from include stack
         self.include_stack.remove(&resolved_path);
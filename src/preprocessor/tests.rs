@@ -236,6 +236,31 @@ fn test_file_not_found() {
     }
 }
 
+#[test]
+fn test_directive_error_in_include() {
+    let temp_dir = TempDir::new().unwrap();
+    let include_path = temp_dir.path().join("broken.wgsl");
+    fs::write(&include_path, "let a = 1;\n#if 1 +\n#endif\n").unwrap();
+
+    let mut preprocessor = Preprocessor::with_base_path(temp_dir.path());
+    let source = "let b = 2;\n#include \"broken.wgsl\"\n";
+    let error = preprocessor.process(source).unwrap_err();
+    assert!(error.to_string().contains("broken.wgsl:2"));
+    match error {
+        PreprocessorError::ParseError { file, line, .. } => {
+            assert_eq!(file, include_path.to_string_lossy());
+            assert_eq!(line, 2);
+        }
+        _ => panic!("Expected ParseError in the included file"),
+    }
+
+    let mut preprocessor = Preprocessor::new();
+    match preprocessor.process("#if 1 +\n#endif\n") {
+        Err(PreprocessorError::ParseError { file, .. }) => assert_eq!(file, "<source>"),
+        _ => panic!("Expected ParseError for invalid expression"),
+    }
+}
+
 #[test]
 fn test_unclosed_ifdef() {
     let mut preprocessor = Preprocessor::new();
@@ -523,3 +548,229 @@ fn test_circular_dependency_multi_path() {
         _ => panic!("Expected CircularDependency error"),
     }
 }
+
+#[test]
+fn test_if_expression() {
+    let mut preprocessor = Preprocessor::new();
+    preprocessor.define("LEVEL", "3");
+    let source = r#"
+#if LEVEL >= 2 && defined(LEVEL)
+let high = true;
+#endif
+#if LEVEL * 2 == 5 || defined MISSING
+let never = true;
+#endif
+"#;
+
+    let result = preprocessor.process(source).unwrap();
+    assert!(result.contains("let high = true;"));
+    assert!(!result.contains("let never = true;"));
+}
+
+#[test]
+fn test_if_undefined_symbol_is_zero() {
+    let mut preprocessor = Preprocessor::new();
+    let source = r#"
+#if MISSING
+let missing = true;
+#elif !MISSING
+let not_missing = true;
+#endif
+"#;
+
+    let result = preprocessor.process(source).unwrap();
+    assert!(!result.contains("let missing = true;"));
+    assert!(result.contains("let not_missing = true;"));
+}
+
+#[test]
+fn test_elif_else_chain() {
+    let source = r#"
+#if MODE == 1
+let mode = "one";
+#elif MODE == 2
+let mode = "two";
+#elif MODE == 2 || MODE == 3
+let mode = "two or three";
+#else
+let mode = "other";
+#endif
+"#;
+
+    for (mode, expected) in [
+        ("1", "\"one\""),
+        ("2", "\"two\""),
+        ("3", "\"two or three\""),
+        ("4", "\"other\""),
+    ] {
+        let mut preprocessor = Preprocessor::new();
+        preprocessor.define("MODE", mode);
+        let result = preprocessor.process(source).unwrap();
+        let modes: Vec<&str> = result.lines().filter(|l| l.contains("let mode")).collect();
+        assert_eq!(modes.len(), 1);
+        assert!(modes[0].contains(expected));
+    }
+}
+
+#[test]
+fn test_nested_else_in_skipped_block() {
+    let mut preprocessor = Preprocessor::new();
+    let source = r#"
+#ifdef OUTER
+#ifdef INNER
+let inner = true;
+#unknown directive is ignored here
+#else
+let not_inner = true;
+#endif
+#else
+let not_outer = true;
+#endif
+"#;
+
+    let result = preprocessor.process(source).unwrap();
+    assert!(!result.contains("let inner = true;"));
+    assert!(!result.contains("let not_inner = true;"));
+    assert!(result.contains("let not_outer = true;"));
+}
+
+#[test]
+fn test_else_errors() {
+    let mut preprocessor = Preprocessor::new();
+    let source = r#"
+#ifdef A
+#else
+#else
+#endif
+"#;
+    match preprocessor.process(source) {
+        Err(PreprocessorError::ParseError { line, message, .. }) => {
+            assert_eq!(line, 4);
+            assert!(message.contains("#else after #else"));
+        }
+        _ => panic!("Expected ParseError for duplicate #else"),
+    }
+
+    let mut preprocessor = Preprocessor::new();
+    match preprocessor.process("#elif 1\n") {
+        Err(PreprocessorError::ParseError { message, .. }) => {
+            assert!(message.contains("Unexpected #elif"));
+        }
+        _ => panic!("Expected ParseError for unexpected elif"),
+    }
+
+    let mut preprocessor = Preprocessor::new();
+    match preprocessor.process("#if 1 +\n#endif\n") {
+        Err(PreprocessorError::ParseError { line, .. }) => assert_eq!(line, 1),
+        _ => panic!("Expected ParseError for invalid expression"),
+    }
+}
+
+#[test]
+fn test_undef() {
+    let mut preprocessor = Preprocessor::new();
+    let source = r#"
+#define VALUE 1
+#define TWICE(x) (x * 2)
+let a = VALUE;
+#undef VALUE
+#undef TWICE
+let b = VALUE;
+let c = TWICE(3);
+#ifdef VALUE
+let defined = true;
+#endif
+"#;
+
+    let result = preprocessor.process(source).unwrap();
+    assert!(result.contains("let a = 1;"));
+    assert!(result.contains("let b = VALUE;"));
+    assert!(result.contains("let c = TWICE(3);"));
+    assert!(!result.contains("let defined = true;"));
+}
+
+#[test]
+fn test_error_directive() {
+    let mut preprocessor = Preprocessor::new();
+    let source = r#"
+#ifndef SUPPORTED
+#error Unsupported configuration
+#endif
+"#;
+    match preprocessor.process(source) {
+        Err(PreprocessorError::ErrorDirective { message, line, .. }) => {
+            assert_eq!(message, "Unsupported configuration");
+            assert_eq!(line, 3);
+        }
+        _ => panic!("Expected ErrorDirective"),
+    }
+
+    // Skipped #error directives are ignored
+    let mut preprocessor = Preprocessor::new();
+    preprocessor.define("SUPPORTED", "1");
+    assert!(preprocessor.process(source).is_ok());
+}
+
+#[test]
+fn test_pragma_once() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("common.wgsl"),
+        "#pragma once\nconst COMMON: f32 = 1.0;",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("other.wgsl"),
+        "#include \"common.wgsl\"\nconst OTHER: f32 = 2.0;",
+    )
+    .unwrap();
+
+    let mut preprocessor = Preprocessor::with_base_path(temp_dir.path());
+    let source = r#"
+#pragma optimize(on)
+#include "common.wgsl"
+#include "other.wgsl"
+#include "common.wgsl"
+"#;
+
+    let result = preprocessor.process(source).unwrap();
+    assert_eq!(result.matches("const COMMON").count(), 1);
+    assert!(result.contains("const OTHER: f32 = 2.0;"));
+
+    // The next run starts over
+    let result = preprocessor.process(source).unwrap();
+    assert_eq!(result.matches("const COMMON").count(), 1);
+}
+
+#[test]
+fn test_source_map() {
+    let temp_dir = TempDir::new().unwrap();
+    let include_path = temp_dir.path().join("lib.wgsl");
+    fs::write(&include_path, "// lib\n#define X 1\nfn lib() {}").unwrap();
+    let main_path = temp_dir.path().join("main.wgsl");
+    fs::write(
+        &main_path,
+        "#include \"lib.wgsl\"\n#ifdef X\nfn main() {}\n#endif\n#line 100 \"generated.wgsl\"\nfn generated() {}",
+    )
+    .unwrap();
+
+    let mut preprocessor = Preprocessor::with_base_path(temp_dir.path());
+    let result = preprocessor.process_file(&main_path).unwrap();
+    let lines: Vec<&str> = result.lines().collect();
+    assert_eq!(lines.len(), preprocessor.source_map().len());
+
+    let location_of = |text: &str| {
+        let index = lines.iter().position(|l| l.contains(text)).unwrap();
+        preprocessor.get_source_location(index + 1).unwrap().clone()
+    };
+    let lib = location_of("fn lib()");
+    assert_eq!(lib.file, include_path.to_string_lossy());
+    assert_eq!(lib.line, 3);
+    let main = location_of("fn main()");
+    assert_eq!(main.file, main_path.to_string_lossy());
+    assert_eq!(main.line, 3);
+    let generated = location_of("fn generated()");
+    assert_eq!(generated.file, "generated.wgsl");
+    assert_eq!(generated.line, 100);
+    assert!(preprocessor.get_source_location(0).is_none());
+}
@@ -1,10 +1,16 @@
 //pub mod gl;
 pub mod render_mode;
 pub mod scene_item;
+pub mod scene_overlays;
+pub mod transform_gizmo;
+pub mod viewport_settings;
 pub mod wgpu;
 
 pub use render_mode::*;
 pub use scene_item::*;
+pub use scene_overlays::SceneOverlays;
+pub use viewport_settings::ViewportSettings;
+pub use wgpu::debug_renderer::DebugRenderer;
 pub use wgpu::lighting_renderer::LightingRenderer;
 pub use wgpu::solid_renderer::SolidRenderer;
 pub use wgpu::wire_renderer::WireRenderer;
@@ -3,4 +3,71 @@ pub enum RenderMode {
     Wire,
     Solid,
     Lighting,
+    Debug(DebugMode),
+}
+
+impl RenderMode {
+    /// Whether the render items carry the textures of the materials.
+    pub fn uses_textures(&self) -> bool {
+        match self {
+            RenderMode::Lighting => true,
+            RenderMode::Debug(DebugMode::Albedo) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Visualisations of the scene data, shown without lighting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugMode {
+    ShadingNormals,
+    GeometricNormals,
+    Tangents,
+    UvChecker,
+    MaterialId,
+    Albedo,
+    Overdraw,
+    Backfaces,
+}
+
+impl DebugMode {
+    pub fn all() -> &'static [DebugMode] {
+        &[
+            DebugMode::ShadingNormals,
+            DebugMode::GeometricNormals,
+            DebugMode::Tangents,
+            DebugMode::UvChecker,
+            DebugMode::MaterialId,
+            DebugMode::Albedo,
+            DebugMode::Overdraw,
+            DebugMode::Backfaces,
+        ]
+    }
+
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            DebugMode::ShadingNormals => "Shading Normals",
+            DebugMode::GeometricNormals => "Geometric Normals",
+            DebugMode::Tangents => "Tangents (dpdu)",
+            DebugMode::UvChecker => "UV Checker",
+            DebugMode::MaterialId => "Material ID",
+            DebugMode::Albedo => "Albedo",
+            DebugMode::Overdraw => "Overdraw",
+            DebugMode::Backfaces => "Backfaces",
+        }
+    }
+
+    /// Index of the mode in the debug shader.
+    pub fn get_index(&self) -> u32 {
+        match self {
+            DebugMode::ShadingNormals => 0,
+            DebugMode::GeometricNormals => 1,
+            DebugMode::Tangents => 2,
+            DebugMode::UvChecker => 3,
+            DebugMode::MaterialId => 4,
+            DebugMode::Albedo => 5,
+            DebugMode::Overdraw => 6,
+            DebugMode::Backfaces => 7,
+        }
+    }
 }
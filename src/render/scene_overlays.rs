@@ -0,0 +1,17 @@
+use super::transform_gizmo::TransformGizmo;
+
+/// Editor helpers drawn with the scene in every render mode.
+#[derive(Debug, Clone)]
+pub struct SceneOverlays {
+    pub transform_gizmo: Option<TransformGizmo>,
+    pub show_cameras: bool, // Off while looking through the render camera
+}
+
+impl Default for SceneOverlays {
+    fn default() -> Self {
+        Self {
+            transform_gizmo: None,
+            show_cameras: true,
+        }
+    }
+}
@@ -0,0 +1,393 @@
+use crate::model::base::Matrix4x4;
+use crate::model::base::Vector3;
+
+const ARROW_LENGTH: f32 = 1.0;
+const ARROW_HEAD: f32 = 0.15;
+const HANDLE_START: f32 = 0.2; // Axis handles leave the centre free
+const PLANE_MIN: f32 = 0.3;
+const PLANE_MAX: f32 = 0.5;
+const RING_SEGMENTS: usize = 64;
+const BOX_SIZE: f32 = 0.05;
+const UNIFORM_BOX_SIZE: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GizmoMode {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+impl GizmoMode {
+    pub fn all() -> &'static [GizmoMode] {
+        &[GizmoMode::Translate, GizmoMode::Rotate, GizmoMode::Scale]
+    }
+
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            GizmoMode::Translate => "Translate",
+            GizmoMode::Rotate => "Rotate",
+            GizmoMode::Scale => "Scale",
+        }
+    }
+
+    pub fn get_index(&self) -> usize {
+        match self {
+            GizmoMode::Translate => 0,
+            GizmoMode::Rotate => 1,
+            GizmoMode::Scale => 2,
+        }
+    }
+}
+
+/// Axes the gizmo follows; scaling always happens along the local axes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GizmoSpace {
+    Local,
+    World,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GizmoHandle {
+    X,
+    Y,
+    Z,
+    XY,
+    YZ,
+    ZX,
+    Uniform,
+}
+
+impl GizmoHandle {
+    pub fn get_index(&self) -> usize {
+        match self {
+            GizmoHandle::X => 0,
+            GizmoHandle::Y => 1,
+            GizmoHandle::Z => 2,
+            GizmoHandle::XY => 3,
+            GizmoHandle::YZ => 4,
+            GizmoHandle::ZX => 5,
+            GizmoHandle::Uniform => 6,
+        }
+    }
+
+    /// Axis of an axis handle, or the normal of a plane handle.
+    pub fn get_axis_index(&self) -> Option<usize> {
+        match self {
+            GizmoHandle::X | GizmoHandle::YZ => Some(0),
+            GizmoHandle::Y | GizmoHandle::ZX => Some(1),
+            GizmoHandle::Z | GizmoHandle::XY => Some(2),
+            GizmoHandle::Uniform => None,
+        }
+    }
+
+    pub fn is_plane(&self) -> bool {
+        matches!(self, GizmoHandle::XY | GizmoHandle::YZ | GizmoHandle::ZX)
+    }
+
+    pub fn get_color(&self, highlighted: bool) -> [f32; 4] {
+        if highlighted {
+            return [1.0, 0.85, 0.1, 1.0];
+        }
+        match self {
+            GizmoHandle::X => [0.9, 0.2, 0.2, 1.0],
+            GizmoHandle::Y => [0.2, 0.85, 0.2, 1.0],
+            GizmoHandle::Z => [0.25, 0.4, 1.0, 1.0],
+            GizmoHandle::XY => [0.4, 0.5, 1.0, 1.0],
+            GizmoHandle::YZ => [1.0, 0.4, 0.4, 1.0],
+            GizmoHandle::ZX => [0.4, 1.0, 0.4, 1.0],
+            GizmoHandle::Uniform => [0.85, 0.85, 0.85, 1.0],
+        }
+    }
+}
+
+pub fn get_gizmo_handles(mode: GizmoMode) -> &'static [GizmoHandle] {
+    match mode {
+        GizmoMode::Translate => &[
+            GizmoHandle::X,
+            GizmoHandle::Y,
+            GizmoHandle::Z,
+            GizmoHandle::XY,
+            GizmoHandle::YZ,
+            GizmoHandle::ZX,
+        ],
+        GizmoMode::Rotate => &[GizmoHandle::X, GizmoHandle::Y, GizmoHandle::Z],
+        GizmoMode::Scale => &[
+            GizmoHandle::X,
+            GizmoHandle::Y,
+            GizmoHandle::Z,
+            GizmoHandle::Uniform,
+        ],
+    }
+}
+
+fn get_unit_axis(i: usize) -> [f32; 3] {
+    let mut a = [0.0; 3];
+    a[i] = 1.0;
+    return a;
+}
+
+fn scale_add(base: [f32; 3], a: [f32; 3], s: f32) -> [f32; 3] {
+    return [base[0] + a[0] * s, base[1] + a[1] * s, base[2] + a[2] * s];
+}
+
+fn get_box_lines(center: [f32; 3], half: f32) -> Vec<Vec<[f32; 3]>> {
+    let corner = |i: usize| {
+        [
+            center[0] + if i & 1 == 0 { -half } else { half },
+            center[1] + if i & 2 == 0 { -half } else { half },
+            center[2] + if i & 4 == 0 { -half } else { half },
+        ]
+    };
+    let mut lines = Vec::new();
+    for i in 0..8 {
+        for bit in [1, 2, 4] {
+            if i & bit == 0 {
+                lines.push(vec![corner(i), corner(i | bit)]);
+            }
+        }
+    }
+    return lines;
+}
+
+/// Polylines of a handle in gizmo space, where the arrows are one unit long.
+pub fn get_gizmo_handle_lines(mode: GizmoMode, handle: GizmoHandle) -> Vec<Vec<[f32; 3]>> {
+    let mut lines = Vec::new();
+    match (mode, handle) {
+        (GizmoMode::Translate, GizmoHandle::X | GizmoHandle::Y | GizmoHandle::Z) => {
+            let i = handle.get_index();
+            let a = get_unit_axis(i);
+            let u = get_unit_axis((i + 1) % 3);
+            let v = get_unit_axis((i + 2) % 3);
+            let origin = [0.0; 3];
+            lines.push(vec![
+                scale_add(origin, a, HANDLE_START),
+                scale_add(origin, a, ARROW_LENGTH),
+            ]);
+            // Arrow head as a cone of lines
+            let tip = scale_add(origin, a, ARROW_LENGTH + ARROW_HEAD);
+            let mut ring = Vec::new();
+            for k in 0..=8 {
+                let t = k as f32 / 8.0 * std::f32::consts::TAU;
+                let p = scale_add(origin, a, ARROW_LENGTH);
+                let p = scale_add(p, u, t.cos() * ARROW_HEAD * 0.3);
+                let p = scale_add(p, v, t.sin() * ARROW_HEAD * 0.3);
+                if k < 8 {
+                    lines.push(vec![p, tip]);
+                }
+                ring.push(p);
+            }
+            lines.push(ring);
+        }
+        (GizmoMode::Translate, GizmoHandle::XY | GizmoHandle::YZ | GizmoHandle::ZX) => {
+            let n = handle.get_axis_index().unwrap();
+            let u = get_unit_axis((n + 1) % 3);
+            let v = get_unit_axis((n + 2) % 3);
+            let p = |s: f32, t: f32| scale_add(scale_add([0.0; 3], u, s), v, t);
+            lines.push(vec![
+                p(PLANE_MIN, PLANE_MIN),
+                p(PLANE_MAX, PLANE_MIN),
+                p(PLANE_MAX, PLANE_MAX),
+                p(PLANE_MIN, PLANE_MAX),
+                p(PLANE_MIN, PLANE_MIN),
+            ]);
+        }
+        (GizmoMode::Rotate, GizmoHandle::X | GizmoHandle::Y | GizmoHandle::Z) => {
+            let i = handle.get_index();
+            let u = get_unit_axis((i + 1) % 3);
+            let v = get_unit_axis((i + 2) % 3);
+            let mut ring = Vec::with_capacity(RING_SEGMENTS + 1);
+            for k in 0..=RING_SEGMENTS {
+                let t = k as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+                ring.push(scale_add(scale_add([0.0; 3], u, t.cos()), v, t.sin()));
+            }
+            lines.push(ring);
+        }
+        (GizmoMode::Scale, GizmoHandle::X | GizmoHandle::Y | GizmoHandle::Z) => {
+            let a = get_unit_axis(handle.get_index());
+            lines.push(vec![
+                scale_add([0.0; 3], a, HANDLE_START),
+                scale_add([0.0; 3], a, ARROW_LENGTH),
+            ]);
+            lines.extend(get_box_lines(
+                scale_add([0.0; 3], a, ARROW_LENGTH),
+                BOX_SIZE,
+            ));
+        }
+        (GizmoMode::Scale, GizmoHandle::Uniform) => {
+            lines.extend(get_box_lines([0.0; 3], UNIFORM_BOX_SIZE));
+        }
+        _ => {}
+    }
+    return lines;
+}
+
+/// Manipulator drawn at the selected node.
+#[derive(Debug, Clone)]
+pub struct TransformGizmo {
+    pub mode: GizmoMode,
+    pub origin: Vector3,
+    pub orientation: Matrix4x4, // Rotation of the gizmo axes
+    pub size: f32,              // World length of the arrows
+    pub highlighted: Option<GizmoHandle>,
+}
+
+fn get_segment_distance(p: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32 {
+    let ab = [b[0] - a[0], b[1] - a[1]];
+    let ap = [p[0] - a[0], p[1] - a[1]];
+    let len2 = ab[0] * ab[0] + ab[1] * ab[1];
+    let t = if len2 > 0.0 {
+        ((ap[0] * ab[0] + ap[1] * ab[1]) / len2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let d = [ap[0] - ab[0] * t, ap[1] - ab[1] * t];
+    return (d[0] * d[0] + d[1] * d[1]).sqrt();
+}
+
+fn is_inside_polygon(p: [f32; 2], polygon: &[[f32; 2]]) -> bool {
+    let mut inside = false;
+    let n = polygon.len();
+    for i in 0..n {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % n];
+        if (a[1] > p[1]) != (b[1] > p[1]) {
+            let x = a[0] + (p[1] - a[1]) / (b[1] - a[1]) * (b[0] - a[0]);
+            if p[0] < x {
+                inside = !inside;
+            }
+        }
+    }
+    return inside;
+}
+
+impl TransformGizmo {
+    pub fn get_local_to_world(&self) -> Matrix4x4 {
+        let o = self.origin;
+        return Matrix4x4::translate(o.x, o.y, o.z)
+            * self.orientation
+            * Matrix4x4::scale(self.size, self.size, self.size);
+    }
+
+    /// World direction of a gizmo axis.
+    pub fn get_axis(&self, i: usize) -> Vector3 {
+        let a = get_unit_axis(i);
+        return self
+            .orientation
+            .transform_vector(&Vector3::new(a[0], a[1], a[2]))
+            .normalize();
+    }
+
+    /// Handle under a screen position, `project` maps world points to the screen.
+    ///
+    /// Lines are picked within `tolerance` pixels and plane handles over their area.
+    pub fn hit_test(
+        &self,
+        position: [f32; 2],
+        tolerance: f32,
+        project: &dyn Fn(&Vector3) -> Option<[f32; 2]>,
+    ) -> Option<GizmoHandle> {
+        let m = self.get_local_to_world();
+        let mut best: Option<(GizmoHandle, f32)> = None;
+        for handle in get_gizmo_handles(self.mode).iter() {
+            let lines = get_gizmo_handle_lines(self.mode, *handle);
+            let mut distance = f32::MAX;
+            for line in lines.iter() {
+                let points = line
+                    .iter()
+                    .map(|p| project(&m.transform_point(&Vector3::new(p[0], p[1], p[2]))))
+                    .collect::<Option<Vec<_>>>();
+                let Some(points) = points else {
+                    continue;
+                };
+                if handle.is_plane() || *handle == GizmoHandle::Uniform {
+                    if points.len() > 2 && is_inside_polygon(position, &points) {
+                        distance = 0.0;
+                    }
+                }
+                for w in points.windows(2) {
+                    distance = distance.min(get_segment_distance(position, w[0], w[1]));
+                }
+            }
+            if *handle == GizmoHandle::Uniform {
+                // The box is hard to hit from its edges, take the area around the centre
+                if let Some(center) = project(&self.origin) {
+                    let d = [position[0] - center[0], position[1] - center[1]];
+                    let d = (d[0] * d[0] + d[1] * d[1]).sqrt();
+                    distance = distance.min((d - tolerance).max(0.0));
+                }
+            }
+            if distance <= tolerance && best.is_none_or(|(_, d)| distance < d) {
+                best = Some((*handle, distance));
+            }
+        }
+        return best.map(|(handle, _)| handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Orthographic view down -z, one world unit to 100 pixels
+    fn project(p: &Vector3) -> Option<[f32; 2]> {
+        Some([p.x * 100.0, -p.y * 100.0])
+    }
+
+    fn gizmo(mode: GizmoMode) -> TransformGizmo {
+        TransformGizmo {
+            mode,
+            origin: Vector3::zero(),
+            orientation: Matrix4x4::identity(),
+            size: 1.0,
+            highlighted: None,
+        }
+    }
+
+    #[test]
+    fn test_hit_test_translate() {
+        let g = gizmo(GizmoMode::Translate);
+        assert_eq!(g.hit_test([80.0, 2.0], 5.0, &project), Some(GizmoHandle::X));
+        assert_eq!(
+            g.hit_test([1.0, -70.0], 5.0, &project),
+            Some(GizmoHandle::Y)
+        );
+        // Inside the XY square
+        assert_eq!(
+            g.hit_test([40.0, -40.0], 5.0, &project),
+            Some(GizmoHandle::XY)
+        );
+        assert_eq!(g.hit_test([-80.0, 80.0], 5.0, &project), None);
+    }
+
+    #[test]
+    fn test_hit_test_rotate_and_scale() {
+        let g = gizmo(GizmoMode::Rotate);
+        // The Z ring is the unit circle on screen
+        assert_eq!(
+            g.hit_test([70.7, -70.7], 5.0, &project),
+            Some(GizmoHandle::Z)
+        );
+        let g = gizmo(GizmoMode::Scale);
+        assert_eq!(
+            g.hit_test([2.0, 2.0], 5.0, &project),
+            Some(GizmoHandle::Uniform)
+        );
+        assert_eq!(
+            g.hit_test([100.0, 0.0], 5.0, &project),
+            Some(GizmoHandle::X)
+        );
+    }
+
+    #[test]
+    fn test_handle_lines() {
+        for mode in GizmoMode::all() {
+            for handle in get_gizmo_handles(*mode) {
+                assert!(!get_gizmo_handle_lines(*mode, *handle).is_empty());
+            }
+        }
+        assert_eq!(
+            get_gizmo_handle_lines(GizmoMode::Scale, GizmoHandle::Uniform).len(),
+            12
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const MAX_SHADOWED_LIGHTS: usize = 8; // Upper bound of the shadowed lights budget
+pub const MAX_SHADOW_CASCADES: u32 = 4; // Cascades per directional light
+pub const SHADOW_MAP_SIZES: [u32; 4] = [512, 1024, 2048, 4096];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShadowSettings {
+    pub enabled: bool,
+    pub max_shadowed_lights: usize, // Budget of lights rendering shadow maps
+    pub map_size: u32,              // Resolution of one shadow map layer
+    pub cascade_count: u32,         // Cascades of directional lights
+    pub pcf_radius: u32,            // PCF kernel is (2 * radius + 1)^2 taps
+    pub depth_bias: f32,            // Bias in shadow map depth
+    pub normal_bias: f32,           // Offset along the normal in shadow map texels
+    #[serde(skip)]
+    pub disabled_lights: HashSet<Uuid>, // Lights whose shadows are turned off
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_shadowed_lights: 4,
+            map_size: 1024,
+            cascade_count: 3,
+            pcf_radius: 1,
+            depth_bias: 0.0005,
+            normal_bias: 1.5,
+            disabled_lights: HashSet::new(),
+        }
+    }
+}
+
+impl ShadowSettings {
+    pub fn is_light_enabled(&self, id: Uuid) -> bool {
+        return !self.disabled_lights.contains(&id);
+    }
+
+    pub fn set_light_enabled(&mut self, id: Uuid, enabled: bool) {
+        if enabled {
+            self.disabled_lights.remove(&id);
+        } else {
+            self.disabled_lights.insert(id);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SsaoSettings {
+    pub enabled: bool,
+    pub radius: f32,       // Sampling radius in world units
+    pub intensity: f32,    // Strength of the occlusion
+    pub sample_count: u32, // Samples per pixel
+}
+
+impl Default for SsaoSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false, // Opt-in, the lighting view keeps its plain look by default
+            radius: 0.5,
+            intensity: 1.0,
+            sample_count: 16,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ToneMapping {
+    #[default]
+    LinearClamp,
+    Reinhard,
+    AcesFilmic,
+}
+
+impl ToneMapping {
+    pub fn all() -> &'static [ToneMapping] {
+        &[
+            ToneMapping::LinearClamp,
+            ToneMapping::Reinhard,
+            ToneMapping::AcesFilmic,
+        ]
+    }
+
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            ToneMapping::LinearClamp => "Linear (Clamp)",
+            ToneMapping::Reinhard => "Reinhard",
+            ToneMapping::AcesFilmic => "ACES Filmic",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostProcessSettings {
+    pub ssao: SsaoSettings,
+    pub exposure: f32, // In stops, 0 keeps the rendered radiance
+    pub tone_mapping: ToneMapping,
+    pub false_color: bool, // Shows the luminance in stops around middle gray
+}
+
+impl Default for PostProcessSettings {
+    fn default() -> Self {
+        Self {
+            ssao: SsaoSettings::default(),
+            exposure: 0.0,
+            tone_mapping: ToneMapping::default(),
+            false_color: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GizmoSettings {
+    pub snap: bool,          // Ctrl while dragging inverts this
+    pub translate_step: f32, // In world units
+    pub rotate_step: f32,    // In degrees
+    pub scale_step: f32,     // Scale factor increment
+}
+
+impl Default for GizmoSettings {
+    fn default() -> Self {
+        Self {
+            snap: false,
+            translate_step: 1.0,
+            rotate_step: 15.0,
+            scale_step: 0.1,
+        }
+    }
+}
+
+/// Options of the scene view that do not belong to the pbrt scene.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewportSettings {
+    pub shadows: ShadowSettings,
+    pub post_process: PostProcessSettings,
+    pub gizmo: GizmoSettings,
+}
@@ -0,0 +1,693 @@
+use super::mesh::RenderVertex;
+use super::render_item::RenderItem;
+use crate::render::render_mode::DebugMode;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use eframe::wgpu;
+use eframe::wgpu::util::DeviceExt;
+use uuid::Uuid;
+use wgpu::util::align_to;
+
+use bytemuck::{Pod, Zeroable};
+
+const MIN_LOCAL_BUFFER_NUM: usize = 64;
+const OVERDRAW_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
+const NO_MATERIAL_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod, Zeroable)]
+struct GlobalUniforms {
+    world_to_camera: [[f32; 4]; 4], // 4 * 4 * 4 = 64
+    camera_to_clip: [[f32; 4]; 4],  // 4 * 4 * 4 = 64
+    camera_position: [f32; 4],
+    mode: u32,
+    _pad1: u32,
+    _pad2: u32,
+    _pad3: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct LocalUniforms {
+    local_to_world: [[f32; 4]; 4], // 4 * 4 * 4 = 64
+    world_to_local: [[f32; 4]; 4], // 4 * 4 * 4 = 64
+    base_color: [f32; 4],          // Albedo, or the colour of the material id
+    has_texture: u32,
+    one_sided: u32,
+    _pad1: u32,
+    _pad2: u32,
+}
+
+// Texture id and edition of the albedo texture bound to an item
+type TextureKey = (Uuid, String);
+
+/// Stable colour of a material id, spread over the hue circle.
+pub fn get_id_color(id: &Uuid) -> [f32; 4] {
+    // FNV-1a over the bytes of the id
+    let mut hash: u32 = 0x811c9dc5;
+    for b in id.as_bytes() {
+        hash ^= *b as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    let h = (hash & 0xffff) as f32 / 65536.0;
+    let s = 0.5 + 0.4 * ((hash >> 16) & 0xff) as f32 / 255.0;
+    let v = 0.7 + 0.3 * ((hash >> 24) & 0xff) as f32 / 255.0;
+    let rgb = hsv_to_rgb(h, s, v);
+    return [rgb[0], rgb[1], rgb[2], 1.0];
+}
+
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [f32; 3] {
+    let h = (h.fract() + 1.0).fract() * 6.0;
+    let i = h.floor();
+    let f = h - i;
+    let p = v * (1.0 - s);
+    let q = v * (1.0 - s * f);
+    let t = v * (1.0 - s * (1.0 - f));
+    match i as i32 {
+        0 => [v, t, p],
+        1 => [q, v, p],
+        2 => [p, v, t],
+        3 => [p, q, v],
+        4 => [t, p, v],
+        _ => [v, p, q],
+    }
+}
+
+fn create_local_uniform_buffer(device: &wgpu::Device, num_items: usize) -> wgpu::Buffer {
+    let local_uniform_size = std::mem::size_of::<LocalUniforms>() as wgpu::BufferAddress;
+    let uniform_alignment = {
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as wgpu::BufferAddress;
+        align_to(local_uniform_size, alignment)
+    };
+    let required_size = uniform_alignment * num_items.max(1) as wgpu::BufferAddress;
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Debug Item Matrices Buffer"),
+        size: required_size,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+        mapped_at_creation: false,
+    });
+    return buffer;
+}
+
+fn create_local_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    return device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Debug Local Bind Group"),
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer,
+                offset: 0,
+                size: wgpu::BufferSize::new(size_of::<LocalUniforms>() as _),
+            }),
+        }],
+    });
+}
+
+fn create_texture_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    return device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Debug Albedo Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    });
+}
+
+fn create_overdraw_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    return device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Overdraw Texture"),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: OVERDRAW_TEXTURE_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[OVERDRAW_TEXTURE_FORMAT],
+    });
+}
+
+#[derive(Debug, Clone)]
+pub struct DebugMeshRenderer {
+    // Meshes drawn with the debug shader, or counted into the overdraw
+    // texture and resolved to a heat map
+    pipeline: wgpu::RenderPipeline,
+    overdraw_pipeline: wgpu::RenderPipeline,
+    resolve_pipeline: wgpu::RenderPipeline,
+    global_bind_group: wgpu::BindGroup,
+    global_uniform_buffer: wgpu::Buffer,
+    local_bind_group_layout: wgpu::BindGroupLayout,
+    local_bind_group: wgpu::BindGroup,
+    local_uniform_buffer: wgpu::Buffer,
+    local_uniform_alignment: wgpu::BufferAddress,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    default_texture_bind_group: wgpu::BindGroup,
+    texture_bind_groups: HashMap<TextureKey, wgpu::BindGroup>,
+    resolve_bind_group_layout: wgpu::BindGroupLayout,
+    resolve_bind_group: Option<wgpu::BindGroup>,
+    overdraw_texture: Option<wgpu::Texture>,
+    render_items: Vec<Arc<RenderItem>>,
+    texture_keys: Vec<Option<TextureKey>>,
+    mode: DebugMode,
+}
+
+impl DebugMeshRenderer {
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_items: &[Arc<RenderItem>],
+        world_to_camera: &glam::Mat4,
+        camera_to_clip: &glam::Mat4,
+        mode: DebugMode,
+        size: [u32; 2],
+    ) {
+        self.mode = mode;
+        let render_items = render_items
+            .iter()
+            .filter(|item| matches!(item.as_ref(), RenderItem::Mesh(_)))
+            .cloned()
+            .collect::<Vec<_>>();
+        let num_items = render_items.len();
+
+        let local_uniform_alignment = self.local_uniform_alignment;
+        if self.local_uniform_buffer.size()
+            < (num_items as wgpu::BufferAddress * local_uniform_alignment)
+        {
+            let new_buffer = create_local_uniform_buffer(device, num_items);
+            self.local_bind_group =
+                create_local_bind_group(device, &self.local_bind_group_layout, &new_buffer);
+            self.local_uniform_buffer = new_buffer;
+        }
+
+        let mut texture_bind_groups = HashMap::new();
+        let mut texture_keys = Vec::with_capacity(num_items);
+        for (i, item) in render_items.iter().enumerate() {
+            let mut uniform = LocalUniforms {
+                local_to_world: item.get_matrix().to_cols_array_2d(),
+                world_to_local: item.get_matrix().inverse().to_cols_array_2d(),
+                base_color: NO_MATERIAL_COLOR,
+                has_texture: 0,
+                one_sided: 0,
+                _pad1: 0,
+                _pad2: 0,
+            };
+            let mut texture_key = None;
+            if let RenderItem::Mesh(mesh_item) = item.as_ref() {
+                if let Some(surface) = mesh_item.debug_surface.as_ref() {
+                    uniform.one_sided = surface.one_sided as u32;
+                    match mode {
+                        DebugMode::MaterialId => {
+                            if let Some(id) = surface.material_id.as_ref() {
+                                uniform.base_color = get_id_color(id);
+                            }
+                        }
+                        _ => {
+                            uniform.base_color = surface.albedo;
+                        }
+                    }
+                    if let Some(texture) = surface.albedo_texture.as_ref() {
+                        let key = (texture.get_id(), texture.edition.clone());
+                        let bind_group =
+                            self.texture_bind_groups.remove(&key).unwrap_or_else(|| {
+                                create_texture_bind_group(
+                                    device,
+                                    &self.texture_bind_group_layout,
+                                    &texture.view,
+                                    &texture.sampler,
+                                )
+                            });
+                        texture_bind_groups.insert(key.clone(), bind_group);
+                        uniform.has_texture = 1;
+                        texture_key = Some(key);
+                    }
+                }
+            }
+            texture_keys.push(texture_key);
+            let offset = i as wgpu::BufferAddress * local_uniform_alignment;
+            queue.write_buffer(
+                &self.local_uniform_buffer,
+                offset,
+                bytemuck::bytes_of(&uniform),
+            );
+        }
+        // Bind groups of textures no longer shown are dropped here
+        self.texture_bind_groups = texture_bind_groups;
+        self.texture_keys = texture_keys;
+        self.render_items = render_items;
+
+        {
+            let camera_to_world = world_to_camera.inverse();
+            let camera_position = camera_to_world.w_axis;
+            let global_uniforms = GlobalUniforms {
+                world_to_camera: world_to_camera.to_cols_array_2d(),
+                camera_to_clip: camera_to_clip.to_cols_array_2d(),
+                camera_position: camera_position.to_array(),
+                mode: mode.get_index(),
+                ..Default::default()
+            };
+            queue.write_buffer(
+                &self.global_uniform_buffer,
+                0,
+                bytemuck::bytes_of(&global_uniforms),
+            );
+        }
+
+        if mode == DebugMode::Overdraw {
+            let [width, height] = [size[0].max(1), size[1].max(1)];
+            let resize = match self.overdraw_texture.as_ref() {
+                Some(texture) => texture.width() != width || texture.height() != height,
+                None => true,
+            };
+            if resize {
+                let texture = create_overdraw_texture(device, width, height);
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                self.resolve_bind_group =
+                    Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some("Overdraw Resolve Bind Group"),
+                        layout: &self.resolve_bind_group_layout,
+                        entries: &[wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&view),
+                        }],
+                    }));
+                self.overdraw_texture = Some(texture);
+            }
+        }
+    }
+
+    fn draw_items(&self, render_pass: &mut wgpu::RenderPass) {
+        let local_uniform_alignment = self.local_uniform_alignment;
+        render_pass.set_bind_group(0, &self.global_bind_group, &[]);
+        for (i, item) in self.render_items.iter().enumerate() {
+            if let RenderItem::Mesh(mesh_item) = item.as_ref() {
+                let offset =
+                    (i as wgpu::BufferAddress * local_uniform_alignment) as wgpu::DynamicOffset;
+                render_pass.set_bind_group(1, &self.local_bind_group, &[offset]);
+                let texture_bind_group = self.texture_keys[i]
+                    .as_ref()
+                    .and_then(|key| self.texture_bind_groups.get(key))
+                    .unwrap_or(&self.default_texture_bind_group);
+                render_pass.set_bind_group(2, texture_bind_group, &[]);
+                render_pass.set_vertex_buffer(0, mesh_item.mesh.vertex_buffer.slice(..));
+                render_pass.set_index_buffer(
+                    mesh_item.mesh.index_buffer.slice(..),
+                    wgpu::IndexFormat::Uint32,
+                );
+                render_pass.draw_indexed(0..mesh_item.mesh.index_count, 0, 0..1);
+            }
+        }
+    }
+
+    /// Counts the surfaces covering each pixel for the overdraw mode.
+    pub fn paint_overdraw(&self, encoder: &mut wgpu::CommandEncoder) {
+        if self.mode != DebugMode::Overdraw {
+            return;
+        }
+        let Some(texture) = self.overdraw_texture.as_ref() else {
+            return;
+        };
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Overdraw Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        rpass.set_pipeline(&self.overdraw_pipeline);
+        self.draw_items(&mut rpass);
+    }
+
+    pub fn paint(&self, render_pass: &mut wgpu::RenderPass<'static>) {
+        if self.mode == DebugMode::Overdraw {
+            if let Some(bind_group) = self.resolve_bind_group.as_ref() {
+                render_pass.set_pipeline(&self.resolve_pipeline);
+                render_pass.set_bind_group(0, bind_group, &[]);
+                render_pass.draw(0..6, 0..1); // Draw a full-screen quad
+            }
+            return;
+        }
+        if self.render_items.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        self.draw_items(render_pass);
+    }
+}
+
+impl DebugMeshRenderer {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target_format: wgpu::TextureFormat,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Debug Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                include_str!("../../../assets/shaders/render_debug_mesh.wgsl").into(),
+            ),
+        });
+        let resolve_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Overdraw Resolve Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                include_str!("../../../assets/shaders/overdraw_resolve.wgsl").into(),
+            ),
+        });
+
+        let vertex_buffer_layout = [wgpu::VertexBufferLayout {
+            array_stride: size_of::<RenderVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x3,
+                    offset: std::mem::size_of::<f32>() as u64 * 3,
+                    shader_location: 1,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x3,
+                    offset: std::mem::size_of::<f32>() as u64 * 6,
+                    shader_location: 2,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x3,
+                    offset: std::mem::size_of::<f32>() as u64 * 9,
+                    shader_location: 3,
+                },
+            ],
+        }];
+
+        let global_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Debug Global Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<GlobalUniforms>() as _),
+                    },
+                    count: None,
+                }],
+            });
+
+        let local_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Debug Local Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<LocalUniforms>() as _),
+                    },
+                    count: None,
+                }],
+            });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Debug Albedo Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let resolve_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Overdraw Resolve Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug Pipeline Layout"),
+            bind_group_layouts: &[
+                &global_bind_group_layout,
+                &local_bind_group_layout,
+                &texture_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        // No culling, the backface mode shows both sides
+        let primitive = wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            ..Default::default()
+        };
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Debug Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &vertex_buffer_layout,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: target_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive,
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        // Every fragment adds one to the count, hidden surfaces included
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let overdraw_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Overdraw Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &vertex_buffer_layout,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: OVERDRAW_TEXTURE_FORMAT,
+                    blend: Some(wgpu::BlendState {
+                        color: additive,
+                        alpha: additive,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive,
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let resolve_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Overdraw Resolve Pipeline Layout"),
+                bind_group_layouts: &[&resolve_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        // Drawn into the egui render pass like the other views
+        let resolve_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Overdraw Resolve Pipeline"),
+            layout: Some(&resolve_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &resolve_shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &resolve_shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(target_format.into())],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let global_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer for Debug"),
+            contents: bytemuck::bytes_of(&GlobalUniforms::default()),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+        });
+        let global_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Debug Global Bind Group"),
+            layout: &global_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: global_uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let local_uniform_size = size_of::<LocalUniforms>() as wgpu::BufferAddress;
+        let local_uniform_alignment = {
+            let alignment =
+                device.limits().min_uniform_buffer_offset_alignment as wgpu::BufferAddress;
+            align_to(local_uniform_size, alignment)
+        };
+        let local_uniform_buffer = create_local_uniform_buffer(device, MIN_LOCAL_BUFFER_NUM);
+        let local_bind_group =
+            create_local_bind_group(device, &local_bind_group_layout, &local_uniform_buffer);
+
+        // A white texel for the items without an albedo texture
+        let default_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Debug Default Texture"),
+                size: wgpu::Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &[255, 255, 255, 255],
+        );
+        let default_view = default_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let default_sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+        let default_texture_bind_group = create_texture_bind_group(
+            device,
+            &texture_bind_group_layout,
+            &default_view,
+            &default_sampler,
+        );
+
+        return DebugMeshRenderer {
+            pipeline,
+            overdraw_pipeline,
+            resolve_pipeline,
+            global_bind_group,
+            global_uniform_buffer,
+            local_bind_group_layout,
+            local_bind_group,
+            local_uniform_buffer,
+            local_uniform_alignment,
+            texture_bind_group_layout,
+            default_texture_bind_group,
+            texture_bind_groups: HashMap::new(),
+            resolve_bind_group_layout,
+            resolve_bind_group: None,
+            overdraw_texture: None,
+            render_items: Vec::new(),
+            texture_keys: Vec::new(),
+            mode: DebugMode::ShadingNormals,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_id_color() {
+        let a = Uuid::from_u128(1);
+        let b = Uuid::from_u128(2);
+        assert_eq!(get_id_color(&a), get_id_color(&a));
+        assert_ne!(get_id_color(&a), get_id_color(&b));
+        for c in get_id_color(&a).iter().chain(get_id_color(&b).iter()) {
+            assert!((0.0..=1.0).contains(c));
+        }
+    }
+}
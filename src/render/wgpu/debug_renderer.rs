@@ -0,0 +1,137 @@
+use super::debug_mesh_renderer::DebugMeshRenderer;
+use super::lines_renderer::LinesRenderer;
+use super::render_item::get_render_items;
+use crate::model::base::Matrix4x4;
+use crate::model::scene::Node;
+use crate::render::render_mode::DebugMode;
+use crate::render::render_mode::RenderMode;
+use crate::render::scene_overlays::SceneOverlays;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use eframe::egui;
+use eframe::egui_wgpu;
+use eframe::wgpu;
+
+pub struct DebugRenderer {
+    mesh_renderer: Arc<RwLock<DebugMeshRenderer>>,
+    lines_renderer: Arc<RwLock<LinesRenderer>>,
+}
+
+#[derive(Debug, Clone)]
+struct PerFrameCallback {
+    rect: [f32; 4],
+    mesh_renderer: Arc<RwLock<DebugMeshRenderer>>,
+    lines_renderer: Arc<RwLock<LinesRenderer>>,
+    node: Arc<RwLock<Node>>,
+    world_to_camera: glam::Mat4,
+    camera_to_clip: glam::Mat4,
+    overlays: SceneOverlays,
+    mode: DebugMode,
+}
+
+unsafe impl Send for PerFrameCallback {}
+unsafe impl Sync for PerFrameCallback {}
+
+impl egui_wgpu::CallbackTrait for PerFrameCallback {
+    fn prepare(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        screen_descriptor: &egui_wgpu::ScreenDescriptor,
+        encoder: &mut wgpu::CommandEncoder,
+        _resources: &mut egui_wgpu::CallbackResources,
+    ) -> Vec<wgpu::CommandBuffer> {
+        let render_items = get_render_items(
+            device,
+            queue,
+            &self.node,
+            RenderMode::Debug(self.mode),
+            &self.overlays,
+        );
+        let pixels_per_point = screen_descriptor.pixels_per_point;
+        let width = ((self.rect[2] - self.rect[0]) * pixels_per_point) as u32;
+        let height = ((self.rect[3] - self.rect[1]) * pixels_per_point) as u32;
+        {
+            let mut renderer = self.mesh_renderer.write().unwrap();
+            renderer.prepare(
+                device,
+                queue,
+                &render_items,
+                &self.world_to_camera,
+                &self.camera_to_clip,
+                self.mode,
+                [width, height],
+            );
+            renderer.paint_overdraw(encoder);
+        }
+        {
+            let mut renderer = self.lines_renderer.write().unwrap();
+            renderer.prepare(
+                device,
+                queue,
+                &render_items,
+                &self.world_to_camera,
+                &self.camera_to_clip,
+            );
+        }
+        return vec![];
+    }
+
+    fn paint(
+        &self,
+        _info: egui::PaintCallbackInfo,
+        render_pass: &mut wgpu::RenderPass<'static>,
+        _resources: &egui_wgpu::CallbackResources,
+    ) {
+        {
+            let renderer = self.mesh_renderer.read().unwrap();
+            renderer.paint(render_pass);
+        }
+        {
+            let renderer = self.lines_renderer.read().unwrap();
+            renderer.paint(render_pass);
+        }
+    }
+}
+
+impl DebugRenderer {
+    pub fn new<'a>(cc: &'a eframe::CreationContext<'a>) -> Option<Self> {
+        let render_state = cc.wgpu_render_state.as_ref()?;
+        let device = &render_state.device;
+        let queue = &render_state.queue;
+        let mesh_renderer = DebugMeshRenderer::new(device, queue, render_state.target_format);
+        let lines_renderer = LinesRenderer::new(device, queue, render_state.target_format);
+        return Some(DebugRenderer {
+            mesh_renderer: Arc::new(RwLock::new(mesh_renderer)),
+            lines_renderer: Arc::new(RwLock::new(lines_renderer)),
+        });
+    }
+
+    pub fn render(
+        &mut self,
+        ui: &mut egui::Ui,
+        rect: egui::Rect,
+        node: &Arc<RwLock<Node>>,
+        w2c: &Matrix4x4,
+        c2c: &Matrix4x4,
+        mode: DebugMode,
+        overlays: &SceneOverlays,
+    ) {
+        let c2c = *c2c;
+        let c2c = Matrix4x4::OPENGL_TO_WGPU_CLIP * c2c; // Convert to WGPU clip space
+        ui.painter().add(egui_wgpu::Callback::new_paint_callback(
+            rect,
+            PerFrameCallback {
+                rect: [rect.min.x, rect.min.y, rect.max.x, rect.max.y],
+                mesh_renderer: self.mesh_renderer.clone(),
+                lines_renderer: self.lines_renderer.clone(),
+                node: node.clone(),
+                world_to_camera: glam::Mat4::from(w2c),
+                camera_to_clip: glam::Mat4::from(c2c),
+                overlays: overlays.clone(),
+                mode,
+            },
+        ));
+    }
+}
@@ -0,0 +1,479 @@
+use super::texture::RenderTexture;
+use crate::conversion::texture_node::DynaImage;
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::RwLock;
+use std::thread;
+
+use eframe::wgpu;
+use uuid::Uuid;
+
+const SPECULAR_WIDTH: u32 = 512;
+const SPECULAR_MIP_COUNT: u32 = 6; // roughness 0.0, 0.2, ..., 1.0
+const SPECULAR_SAMPLE_COUNT: u32 = 32;
+const IRRADIANCE_WIDTH: u32 = 32;
+const SH_SOURCE_WIDTH: u32 = 64;
+
+/// Environment map of an infinite light, prefiltered for split-sum image based lighting.
+/// Mip level `i` of `specular` is convolved with GGX of roughness `i / (mip_count - 1)`,
+/// `irradiance` holds the cosine-convolved radiance divided by pi.
+#[derive(Debug, Clone)]
+pub struct RenderEnvironment {
+    pub id: Uuid,
+    pub edition: String,
+    pub specular: RenderTexture,
+    pub irradiance: RenderTexture,
+    pub mip_count: u32,
+}
+
+impl RenderEnvironment {
+    pub fn get_id(&self) -> Uuid {
+        self.id
+    }
+}
+
+type PrefilteredImages = (Vec<image::Rgba32FImage>, image::Rgba32FImage);
+
+/// An environment being prefiltered on a thread, so loading a map does not stall the viewport.
+#[derive(Debug, Clone)]
+pub struct PendingEnvironment {
+    pub id: Uuid,
+    pub edition: String,
+    result: Arc<Mutex<Option<PrefilteredImages>>>,
+}
+
+impl PendingEnvironment {
+    pub fn spawn(id: Uuid, edition: &str, image: Arc<RwLock<DynaImage>>) -> Self {
+        let result = Arc::new(Mutex::new(None));
+        {
+            let result = result.clone();
+            thread::spawn(move || {
+                let images = prefilter_environment(&image.read().unwrap());
+                *result.lock().unwrap() = Some(images);
+            });
+        }
+        return Self {
+            id,
+            edition: edition.to_string(),
+            result,
+        };
+    }
+
+    /// The prefiltered images once the thread is done.
+    pub fn take_result(&self) -> Option<PrefilteredImages> {
+        return self.result.lock().unwrap().take();
+    }
+}
+
+// Lat-long image, rows run from +z (theta = 0) to -z (theta = pi).
+#[derive(Debug, Clone)]
+struct LatLongImage {
+    width: u32,
+    height: u32,
+    data: Vec<[f32; 3]>,
+}
+
+impl LatLongImage {
+    fn from_image(image: &image::Rgb32FImage) -> Self {
+        let (width, height) = image.dimensions();
+        let data = image.pixels().map(|p| p.0).collect();
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    fn resize(&self, width: u32, height: u32) -> Self {
+        let mut image = image::Rgb32FImage::new(self.width, self.height);
+        for (i, pixel) in image.pixels_mut().enumerate() {
+            pixel.0 = self.data[i];
+        }
+        let image =
+            image::imageops::resize(&image, width, height, image::imageops::FilterType::Triangle);
+        return Self::from_image(&image);
+    }
+
+    fn get_direction(&self, x: u32, y: u32) -> glam::Vec3 {
+        let u = (x as f32 + 0.5) / self.width as f32;
+        let v = (y as f32 + 0.5) / self.height as f32;
+        return spherical_direction(u, v);
+    }
+
+    fn get_pixel(&self, x: i32, y: i32) -> glam::Vec3 {
+        let x = x.rem_euclid(self.width as i32) as u32;
+        let y = y.clamp(0, self.height as i32 - 1) as u32;
+        return glam::Vec3::from(self.data[(y * self.width + x) as usize]);
+    }
+
+    fn sample(&self, direction: glam::Vec3) -> glam::Vec3 {
+        let (u, v) = spherical_uv(direction);
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+        let (x0, y0) = (x0 as i32, y0 as i32);
+        let c00 = self.get_pixel(x0, y0);
+        let c10 = self.get_pixel(x0 + 1, y0);
+        let c01 = self.get_pixel(x0, y0 + 1);
+        let c11 = self.get_pixel(x0 + 1, y0 + 1);
+        let c0 = c00.lerp(c10, fx);
+        let c1 = c01.lerp(c11, fx);
+        return c0.lerp(c1, fy);
+    }
+
+    fn to_rgba32f(&self) -> image::Rgba32FImage {
+        let mut image = image::Rgba32FImage::new(self.width, self.height);
+        for (i, pixel) in image.pixels_mut().enumerate() {
+            let c = self.data[i];
+            pixel.0 = [c[0], c[1], c[2], 1.0];
+        }
+        return image;
+    }
+}
+
+// Same mapping as `spherical_texture_lookup` in the surface shaders.
+fn spherical_direction(u: f32, v: f32) -> glam::Vec3 {
+    let phi = 2.0 * PI * u;
+    let theta = PI * v;
+    return glam::vec3(
+        theta.sin() * phi.cos(),
+        theta.sin() * phi.sin(),
+        theta.cos(),
+    );
+}
+
+fn spherical_uv(direction: glam::Vec3) -> (f32, f32) {
+    let phi = direction.y.atan2(direction.x);
+    let theta = direction.z.clamp(-1.0, 1.0).acos();
+    return (phi / (2.0 * PI), theta / PI);
+}
+
+fn radical_inverse(mut bits: u32) -> f32 {
+    bits = bits.reverse_bits();
+    return bits as f32 * (1.0 / 4294967296.0);
+}
+
+fn orthonormal_basis(n: glam::Vec3) -> (glam::Vec3, glam::Vec3) {
+    let up = if n.z.abs() < 0.999 {
+        glam::Vec3::Z
+    } else {
+        glam::Vec3::X
+    };
+    let t = up.cross(n).normalize();
+    let b = n.cross(t);
+    return (t, b);
+}
+
+fn ggx_d(n_dot_h: f32, alpha: f32) -> f32 {
+    let a2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+// GGX convolution assuming n = v = r, sampled from a box filtered pyramid to keep the sample count low.
+fn prefilter_specular(
+    pyramid: &[LatLongImage],
+    width: u32,
+    height: u32,
+    roughness: f32,
+) -> LatLongImage {
+    let base = &pyramid[0];
+    if roughness <= 0.0 {
+        return base.resize(width, height);
+    }
+    let alpha = (roughness * roughness).max(1e-3);
+    let texel_solid_angle = 4.0 * PI / (base.width * base.height) as f32;
+    let mut samples = Vec::with_capacity(SPECULAR_SAMPLE_COUNT as usize);
+    for i in 0..SPECULAR_SAMPLE_COUNT {
+        let xi = (i as f32 / SPECULAR_SAMPLE_COUNT as f32, radical_inverse(i));
+        let phi = 2.0 * PI * xi.0;
+        let cos_theta = ((1.0 - xi.1) / (1.0 + (alpha * alpha - 1.0) * xi.1)).sqrt();
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let h = glam::vec3(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+        let pdf = ggx_d(cos_theta, alpha) / 4.0;
+        let sample_solid_angle = 1.0 / (SPECULAR_SAMPLE_COUNT as f32 * pdf + 1e-4);
+        let lod = 0.5 * (sample_solid_angle / texel_solid_angle).log2();
+        let level = (lod.max(0.0).round() as usize).min(pyramid.len() - 1);
+        samples.push((h, level));
+    }
+
+    let mut data = vec![[0.0f32; 3]; (width * height) as usize];
+    let rows_per_thread = get_rows_per_thread(height);
+    std::thread::scope(|scope| {
+        for (chunk_index, chunk) in data
+            .chunks_mut((rows_per_thread * width) as usize)
+            .enumerate()
+        {
+            let samples = &samples;
+            scope.spawn(move || {
+                let y_start = chunk_index as u32 * rows_per_thread;
+                for (i, pixel) in chunk.iter_mut().enumerate() {
+                    let x = i as u32 % width;
+                    let y = y_start + i as u32 / width;
+                    let u = (x as f32 + 0.5) / width as f32;
+                    let v = (y as f32 + 0.5) / height as f32;
+                    let n = spherical_direction(u, v);
+                    let (t, b) = orthonormal_basis(n);
+                    let mut sum = glam::Vec3::ZERO;
+                    let mut weight = 0.0;
+                    for (h, level) in samples.iter() {
+                        let h = t * h.x + b * h.y + n * h.z;
+                        let l = 2.0 * n.dot(h) * h - n;
+                        let n_dot_l = n.dot(l);
+                        if n_dot_l > 0.0 {
+                            sum += pyramid[*level].sample(l) * n_dot_l;
+                            weight += n_dot_l;
+                        }
+                    }
+                    let c = if weight > 0.0 {
+                        sum / weight
+                    } else {
+                        glam::Vec3::ZERO
+                    };
+                    *pixel = c.to_array();
+                }
+            });
+        }
+    });
+    return LatLongImage {
+        width,
+        height,
+        data,
+    };
+}
+
+fn get_rows_per_thread(height: u32) -> u32 {
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get() as u32)
+        .unwrap_or(1);
+    return height.div_ceil(threads).max(1);
+}
+
+fn sh_basis(d: glam::Vec3) -> [f32; 9] {
+    [
+        0.282095,
+        0.488603 * d.y,
+        0.488603 * d.z,
+        0.488603 * d.x,
+        1.092548 * d.x * d.y,
+        1.092548 * d.y * d.z,
+        0.315392 * (3.0 * d.z * d.z - 1.0),
+        1.092548 * d.x * d.z,
+        0.546274 * (d.x * d.x - d.y * d.y),
+    ]
+}
+
+// Irradiance through order 2 spherical harmonics (Ramamoorthi and Hanrahan).
+fn prefilter_irradiance(source: &LatLongImage, width: u32, height: u32) -> LatLongImage {
+    let mut coeffs = [glam::Vec3::ZERO; 9];
+    let d_phi = 2.0 * PI / source.width as f32;
+    let d_theta = PI / source.height as f32;
+    for y in 0..source.height {
+        for x in 0..source.width {
+            let d = source.get_direction(x, y);
+            let sin_theta = (1.0 - d.z * d.z).max(0.0).sqrt();
+            let solid_angle = d_phi * d_theta * sin_theta;
+            let c = source.get_pixel(x as i32, y as i32);
+            let basis = sh_basis(d);
+            for i in 0..9 {
+                coeffs[i] += c * basis[i] * solid_angle;
+            }
+        }
+    }
+    let band = [
+        PI,
+        2.0 * PI / 3.0,
+        2.0 * PI / 3.0,
+        2.0 * PI / 3.0,
+        PI / 4.0,
+        PI / 4.0,
+        PI / 4.0,
+        PI / 4.0,
+        PI / 4.0,
+    ];
+    let mut data = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let u = (x as f32 + 0.5) / width as f32;
+            let v = (y as f32 + 0.5) / height as f32;
+            let basis = sh_basis(spherical_direction(u, v));
+            let mut e = glam::Vec3::ZERO;
+            for i in 0..9 {
+                e += coeffs[i] * band[i] * basis[i];
+            }
+            data.push((e / PI).max(glam::Vec3::ZERO).to_array());
+        }
+    }
+    return LatLongImage {
+        width,
+        height,
+        data,
+    };
+}
+
+/// Prefiltered lat-long images: the specular mip chain and the irradiance map.
+pub fn prefilter_environment(image: &DynaImage) -> PrefilteredImages {
+    let source = LatLongImage::from_image(&image.to_rgb32f());
+    let width = SPECULAR_WIDTH;
+    let height = SPECULAR_WIDTH / 2;
+    let mut pyramid = vec![source.resize(width, height)];
+    while pyramid.last().unwrap().height > 4 {
+        let last = pyramid.last().unwrap();
+        pyramid.push(last.resize(last.width / 2, last.height / 2));
+    }
+
+    let mut specular = Vec::with_capacity(SPECULAR_MIP_COUNT as usize);
+    for level in 0..SPECULAR_MIP_COUNT {
+        let roughness = level as f32 / (SPECULAR_MIP_COUNT - 1) as f32;
+        let level_width = (width >> level).max(1);
+        let level_height = (height >> level).max(1);
+        let image = prefilter_specular(&pyramid, level_width, level_height, roughness);
+        specular.push(image.to_rgba32f());
+    }
+
+    let sh_source = source.resize(SH_SOURCE_WIDTH, SH_SOURCE_WIDTH / 2);
+    let irradiance = prefilter_irradiance(&sh_source, IRRADIANCE_WIDTH, IRRADIANCE_WIDTH / 2);
+    return (specular, irradiance.to_rgba32f());
+}
+
+fn create_texture_with_mips(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    label: &str,
+    mips: &[image::Rgba32FImage],
+) -> wgpu::Texture {
+    let (width, height) = mips[0].dimensions();
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: mips.len() as u32,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba32Float,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    for (level, image) in mips.iter().enumerate() {
+        let (width, height) = image.dimensions();
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: level as u32,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(image.as_raw()),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * 4 * width),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+    return texture;
+}
+
+fn create_environment_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    id: Uuid,
+    edition: &str,
+    label: &str,
+    mips: &[image::Rgba32FImage],
+) -> RenderTexture {
+    let texture = create_texture_with_mips(device, queue, label, mips);
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Environment Sampler"),
+        address_mode_u: wgpu::AddressMode::Repeat,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        min_filter: wgpu::FilterMode::Linear,
+        mag_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+    return RenderTexture {
+        id,
+        edition: edition.to_string(),
+        texture,
+        view,
+        sampler,
+        scale: [1.0, 1.0],
+        delta: [0.0, 0.0],
+    };
+}
+
+pub fn create_render_environment(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    id: Uuid,
+    edition: &str,
+    images: PrefilteredImages,
+) -> RenderEnvironment {
+    let (specular, irradiance) = images;
+    let mip_count = specular.len() as u32;
+    let specular = create_environment_texture(
+        device,
+        queue,
+        id,
+        edition,
+        "Environment Specular",
+        &specular,
+    );
+    let irradiance = create_environment_texture(
+        device,
+        queue,
+        id,
+        edition,
+        "Environment Irradiance",
+        &[irradiance],
+    );
+    return RenderEnvironment {
+        id,
+        edition: edition.to_string(),
+        specular,
+        irradiance,
+        mip_count,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefilter_environment_001() {
+        // a constant environment stays constant after every convolution
+        let image = image::Rgb32FImage::from_pixel(64, 32, image::Rgb([0.5, 0.25, 1.0]));
+        let (specular, irradiance) = prefilter_environment(&DynaImage::ImageRgb32F(image));
+        assert_eq!(specular.len(), SPECULAR_MIP_COUNT as usize);
+        assert_eq!(
+            specular[1].dimensions(),
+            (SPECULAR_WIDTH / 2, SPECULAR_WIDTH / 4)
+        );
+        let expected = [0.5, 0.25, 1.0];
+        for image in specular.iter().chain(std::iter::once(&irradiance)) {
+            let (w, h) = image.dimensions();
+            for (x, y) in [(0, 0), (w / 3, h / 2), (w - 1, h - 1)] {
+                let p = image.get_pixel(x, y).0;
+                for i in 0..3 {
+                    assert!((p[i] - expected[i]).abs() < 0.02, "{:?}", p);
+                }
+            }
+        }
+    }
+}
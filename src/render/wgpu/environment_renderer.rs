@@ -0,0 +1,185 @@
+use super::light::RenderLight;
+use super::render_item::RenderItem;
+
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use eframe::wgpu;
+use eframe::wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod, Zeroable)]
+struct EnvironmentUniforms {
+    clip_to_world: [[f32; 4]; 4],  // 4 * 4 * 4 = 64
+    world_to_light: [[f32; 4]; 4], // 4 * 4 * 4 = 64
+    intensity: [f32; 4],           // 4 * 4 = 16
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvironmentRenderer {
+    // Draws the environment map of the first infinite light as the viewport background
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: Option<wgpu::BindGroup>,
+}
+
+impl EnvironmentRenderer {
+    pub fn new(
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        target_format: wgpu::TextureFormat,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Environment Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                include_str!("../../../assets/shaders/render_environment.wgsl").into(),
+            ),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            size_of::<EnvironmentUniforms>() as _
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("Environment Bind Group Layout"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Environment Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Environment Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(target_format.into())],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            // Drawn first without touching depth, meshes cover it
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer for Environment"),
+            contents: bytemuck::bytes_of(&EnvironmentUniforms::default()),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+        });
+
+        EnvironmentRenderer {
+            pipeline,
+            bind_group_layout,
+            uniform_buffer,
+            bind_group: None,
+        }
+    }
+
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_items: &[Arc<RenderItem>],
+        world_to_camera: &glam::Mat4,
+        camera_to_clip: &glam::Mat4,
+    ) {
+        self.bind_group = None;
+        for item in render_items.iter() {
+            if let RenderItem::Light(light_item) = item.as_ref() {
+                if let RenderLight::Infinite(light) = light_item.light.as_ref() {
+                    let Some(environment) = light.environment.as_ref() else {
+                        continue;
+                    };
+                    let clip_to_world = (*camera_to_clip * *world_to_camera).inverse();
+                    let intensity = light.intensity;
+                    let uniforms = EnvironmentUniforms {
+                        clip_to_world: clip_to_world.to_cols_array_2d(),
+                        world_to_light: light_item.matrix.inverse().to_cols_array_2d(),
+                        intensity: [intensity[0], intensity[1], intensity[2], 1.0],
+                    };
+                    queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+                    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        layout: &self.bind_group_layout,
+                        entries: &[
+                            wgpu::BindGroupEntry {
+                                binding: 0,
+                                resource: self.uniform_buffer.as_entire_binding(),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 1,
+                                resource: wgpu::BindingResource::TextureView(
+                                    &environment.specular.view,
+                                ),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 2,
+                                resource: wgpu::BindingResource::Sampler(
+                                    &environment.specular.sampler,
+                                ),
+                            },
+                        ],
+                        label: Some("Environment Bind Group"),
+                    });
+                    self.bind_group = Some(bind_group);
+                    return;
+                }
+            }
+        }
+    }
+
+    pub fn paint(&self, render_pass: &mut wgpu::RenderPass) {
+        if let Some(bind_group) = self.bind_group.as_ref() {
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw(0..6, 0..1); // Draw a full-screen quad
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::texture::RenderTexture;
+use super::environment::RenderEnvironment;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -57,8 +57,8 @@ pub struct RectsRenderLight {
 pub struct InfiniteRenderLight {
     pub id: Uuid,
     pub edition: String,
-    pub intensity: [f32; 3],                 // RGB intensity
-    pub texture: Option<Arc<RenderTexture>>, // Environment map texture
+    pub intensity: [f32; 3],                         // RGB intensity
+    pub environment: Option<Arc<RenderEnvironment>>, // Prefiltered environment map
 }
 
 #[derive(Debug, Clone)]
@@ -5,7 +5,10 @@ use super::material::RenderPass;
 use super::mesh::RenderVertex;
 use super::render_item::RenderItem;
 use super::shader::RenderShader;
+use super::shadow_map::ShadowAssignment;
+use super::shadow_map::ShadowMapRenderer;
 use super::texture::RenderTexture;
+use crate::render::viewport_settings::ShadowSettings;
 use crate::render::wgpu::light::RenderLight;
 use crate::render::wgpu::material::RenderMaterial;
 use std::collections::HashMap;
@@ -66,6 +69,7 @@ struct LightUniforms {
 struct DirectionalLight {
     direction: [f32; 4], // Direction of the light // 4 * 4 = 16
     intensity: [f32; 4], // Intensity of the light // 4 * 4 = 16
+    shadow: [i32; 4],    // First shadow map layer, cascade count // 4 * 4 = 16
 }
 
 #[repr(C)]
@@ -75,7 +79,8 @@ struct SphereLight {
     intensity: [f32; 4], // Intensity of the light // 4 * 4 = 16
     radius: f32,         // Radius of the light // 1 * 4 = 4
     range: f32,          // Range of the light // 1 * 4 = 4
-    _pad1: [f32; 2],     // Range of the light // 4 * 4 = 8
+    shadow_index: i32,   // First of the six shadow map layers // 1 * 4 = 4
+    _pad1: u32,
 }
 
 #[repr(C)]
@@ -91,7 +96,7 @@ struct DiskLight {
     u_axis: [f32; 4],     // U axis for rectangle // 4 * 4 = 16
     v_axis: [f32; 4],     // V axis for rectangle // 4 * 4 = 16
     twosided: u32,        // Whether the rectangle emits light on both sides
-    _pad1: u32,
+    shadow_index: i32,    // Shadow map layer of spot lights
     _pad2: u32,
     _pad3: u32,
 }
@@ -192,13 +197,15 @@ pub struct LightingMeshRenderer {
     disk_light_buffer: wgpu::Buffer,
     rect_light_buffer: wgpu::Buffer,
     infinite_light_buffer: wgpu::Buffer,
+    // Shadow maps of the punctual lights
+    shadow_renderer: ShadowMapRenderer,
 
     // Mesh items to render
     mesh_items: Vec<Arc<RenderItem>>,
     // Textures used in the materials
     textures: HashMap<Uuid, Arc<RenderTexture>>,
-    // Material entries
-    pipelines: HashMap<Uuid, Arc<RwLock<PipelineEntry>>>,
+    // Material entries, one per shader and render category
+    pipelines: HashMap<(Uuid, RenderCategory), Arc<RwLock<PipelineEntry>>>,
 }
 
 fn create_local_uniform_buffer(device: &wgpu::Device, num_items: usize) -> wgpu::Buffer {
@@ -234,16 +241,40 @@ impl LightingMeshRenderer {
         render_items: &[Arc<RenderItem>],
         world_to_camera: &glam::Mat4,
         camera_to_clip: &glam::Mat4,
+        shadow_settings: &ShadowSettings,
     ) {
         self.prepare_global(device, queue, world_to_camera, camera_to_clip); //group(0)
         {
             let (mesh_items, light_items) = Self::split_items(render_items);
             self.prepare_locals(device, queue, &mesh_items); //group(1)
             self.prepare_materials(device, queue, &mesh_items); //group(2)
-            self.prepare_lights(device, queue, &light_items); //group(3)
+            let shadows = self.shadow_renderer.prepare(
+                device,
+                queue,
+                &light_items,
+                &mesh_items,
+                world_to_camera,
+                camera_to_clip,
+                shadow_settings,
+            );
+            self.prepare_lights(device, queue, &light_items, &shadows); //group(3)
         }
     }
 
+    /// Renders the shadow maps; call before the pass that paints the meshes.
+    pub fn paint_shadows(&self, encoder: &mut wgpu::CommandEncoder) {
+        let local_uniform_alignment = align_to(
+            std::mem::size_of::<LocalUniforms>() as wgpu::BufferAddress,
+            self.min_uniform_buffer_offset_alignment,
+        );
+        self.shadow_renderer.paint(
+            encoder,
+            &self.mesh_items,
+            &self.local_bind_group,
+            local_uniform_alignment,
+        );
+    }
+
     pub fn paint(&self, render_pass: &mut wgpu::RenderPass) {
         if !self.mesh_items.is_empty() {
             self.render(render_pass, &self.mesh_items);
@@ -471,14 +502,15 @@ impl LightingMeshRenderer {
                 }
                 entry.material_bind_groups.clear();
             }
-            let mut tmp_pipelines: HashMap<Uuid, TmpPipelineEntry> = HashMap::new();
+            let mut tmp_pipelines: HashMap<(Uuid, RenderCategory), TmpPipelineEntry> =
+                HashMap::new();
             for (mesh_index, item) in mesh_items.iter().enumerate() {
                 if let Some(material) = item.get_material() {
                     for pass in material.passes.iter() {
                         let shader = pass.shader.clone();
                         let shader_id = shader.id;
                         let entry = tmp_pipelines
-                            .entry(shader_id)
+                            .entry((shader_id, pass.render_category))
                             .or_insert(TmpPipelineEntry::new(&shader));
                         entry.mesh_indices.push(mesh_index);
                         let pass_id = pass.id;
@@ -496,7 +528,7 @@ impl LightingMeshRenderer {
                 }
             }
 
-            for (shader_id, tmp_entry) in tmp_pipelines.iter() {
+            for (pipeline_key, tmp_entry) in tmp_pipelines.iter() {
                 let mesh_indices = &tmp_entry.mesh_indices;
                 let material_indices = &tmp_entry.material_indices;
                 let num_materials = tmp_entry.material_indices_map.len();
@@ -505,16 +537,15 @@ impl LightingMeshRenderer {
                 }
                 let (_, pass) = tmp_entry.material_indices_map.values().next().unwrap();
 
-                if !self.pipelines.contains_key(&shader_id) {
+                if !self.pipelines.contains_key(pipeline_key) {
                     let shader = tmp_entry.shader.clone();
-                    let shader_id = shader.id;
                     let pipeline = self.create_pipeline(device, queue, &shader.shader, pass);
                     let pipeline = Arc::new(RwLock::new(pipeline));
-                    self.pipelines.insert(shader_id, pipeline);
+                    self.pipelines.insert(*pipeline_key, pipeline);
                 }
                 let entry = self
                     .pipelines
-                    .get_mut(&shader_id)
+                    .get_mut(pipeline_key)
                     .expect("Pipeline for basic material not found");
                 let mut entry = entry.write().unwrap();
                 entry.mesh_indices = mesh_indices.clone();
@@ -551,6 +582,7 @@ impl LightingMeshRenderer {
         _queue: &wgpu::Queue,
         light_texture_view: &wgpu::TextureView,
         light_sampler: &wgpu::Sampler,
+        irradiance_texture_view: &wgpu::TextureView,
     ) -> wgpu::BindGroup {
         let default_ltc_texture = self
             .textures
@@ -598,6 +630,31 @@ impl LightingMeshRenderer {
                 binding: 9,
                 resource: wgpu::BindingResource::Sampler(&default_ltc_texture.sampler),
             },
+            wgpu::BindGroupEntry {
+                binding: 10,
+                resource: wgpu::BindingResource::TextureView(irradiance_texture_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 11,
+                resource: self
+                    .shadow_renderer
+                    .get_uniform_buffer()
+                    .as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 12,
+                resource: self.shadow_renderer.get_view_buffer().as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 13,
+                resource: wgpu::BindingResource::TextureView(
+                    self.shadow_renderer.get_texture_view(),
+                ),
+            },
+            wgpu::BindGroupEntry {
+                binding: 14,
+                resource: wgpu::BindingResource::Sampler(self.shadow_renderer.get_sampler()),
+            },
         ];
         let light_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Lighting Light Bind Group"),
@@ -612,13 +669,20 @@ impl LightingMeshRenderer {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         render_items: &[Arc<RenderItem>],
+        shadows: &HashMap<usize, ShadowAssignment>,
     ) {
+        let get_shadow_index = |index: usize| {
+            shadows
+                .get(&index)
+                .map(|shadow| shadow.first_layer)
+                .unwrap_or(-1)
+        };
         let mut light_uniforms = LightUniforms::default();
         let mut light_textures = Vec::new();
         // Point lights
         {
             let mut light_buffer = Vec::new();
-            for item in render_items.iter() {
+            for (index, item) in render_items.iter().enumerate() {
                 if let RenderItem::Light(light_item) = item.as_ref() {
                     if let RenderItem::Light(item) = item.as_ref() {
                         if let RenderLight::Sphere(light) = item.light.as_ref() {
@@ -639,6 +703,7 @@ impl LightingMeshRenderer {
                                 position: [position.x, position.y, position.z, 1.0],
                                 intensity: [intensity[0], intensity[1], intensity[2], 1.0],
                                 radius: radius,
+                                shadow_index: get_shadow_index(index),
                                 ..Default::default()
                             };
                             light_buffer.push(light);
@@ -659,7 +724,7 @@ impl LightingMeshRenderer {
         // Spot lights
         {
             let mut light_buffer = Vec::new();
-            for item in render_items.iter() {
+            for (index, item) in render_items.iter().enumerate() {
                 if let RenderItem::Light(light_item) = item.as_ref() {
                     if let RenderLight::Disk(light) = light_item.light.as_ref() {
                         if light_buffer.len() >= MAX_DISK_LIGHT_NUM {
@@ -694,6 +759,7 @@ impl LightingMeshRenderer {
                             u_axis: [u_axis.x, u_axis.y, u_axis.z, 0.0],
                             v_axis: [v_axis.x, v_axis.y, v_axis.z, 0.0],
                             twosided: if light.twosided { 1 } else { 0 },
+                            shadow_index: get_shadow_index(index),
                             ..Default::default()
                         };
                         light_buffer.push(light);
@@ -776,17 +842,19 @@ impl LightingMeshRenderer {
                             break;
                         }
                         let mut texture_index = -1;
-                        if let Some(texture) = &light.texture {
+                        let mut mip_count = 0;
+                        if let Some(environment) = &light.environment {
                             // New texture, add it to the list
                             texture_index = light_textures.len() as i32;
-                            light_textures.push(texture.clone());
+                            mip_count = environment.mip_count as i32;
+                            light_textures.push(environment.clone());
                         }
 
                         let inv_matrix = light_item.matrix.inverse();
                         let intensity = light.intensity;
                         let light = InfiniteLight {
                             intensity: [intensity[0], intensity[1], intensity[2], 1.0],
-                            indices: [texture_index, 0, 0, 0], // Indices for the light texture
+                            indices: [texture_index, mip_count, 0, 0], // texture index, specular mip count
                             inv_matrix: inv_matrix.to_cols_array_2d(),
                         };
                         light_buffer.push(light);
@@ -806,7 +874,7 @@ impl LightingMeshRenderer {
         // Directional lights
         {
             let mut light_buffer = Vec::new();
-            for item in render_items.iter() {
+            for (index, item) in render_items.iter().enumerate() {
                 if let RenderItem::Light(light_item) = item.as_ref() {
                     if let RenderLight::Directional(light) = light_item.light.as_ref() {
                         if light_buffer.len() >= MAX_DIRECTIONAL_LIGHT_NUM {
@@ -820,9 +888,14 @@ impl LightingMeshRenderer {
                             direction[2],
                         ));
                         let intensity = light.intensity;
+                        let shadow = shadows
+                            .get(&index)
+                            .map(|shadow| [shadow.first_layer, shadow.layer_count, 0, 0])
+                            .unwrap_or([-1, 0, 0, 0]);
                         let light = DirectionalLight {
                             direction: [direction[0], direction[1], direction[2], 0.0],
                             intensity: [intensity[0], intensity[1], intensity[2], 1.0],
+                            shadow,
                         };
                         light_buffer.push(light);
                     }
@@ -849,14 +922,16 @@ impl LightingMeshRenderer {
                     queue,
                     &light_texture.view,
                     &light_texture.sampler,
+                    &light_texture.view,
                 );
             } else {
-                let light_texture = &light_textures[0];
+                let environment = &light_textures[0];
                 self.light_bind_group = self.create_light_bind_group(
                     device,
                     queue,
-                    &light_texture.view,
-                    &light_texture.sampler,
+                    &environment.specular.view,
+                    &environment.specular.sampler,
+                    &environment.irradiance.view,
                 );
             }
         }
@@ -976,7 +1051,9 @@ impl LightingMeshRenderer {
         let mut blendstate = wgpu::BlendState::REPLACE;
         if render_category == RenderCategory::Transparent {
             blendstate = wgpu::BlendState::ALPHA_BLENDING;
-        } else if render_category == RenderCategory::TransparentSpecular {
+        } else if render_category == RenderCategory::TransparentSpecular
+            || render_category == RenderCategory::OpaqueAdditive
+        {
             blendstate = wgpu::BlendState {
                 color: wgpu::BlendComponent {
                     src_factor: wgpu::BlendFactor::One,
@@ -990,6 +1067,7 @@ impl LightingMeshRenderer {
         let mut depth_write_enabled = true;
         if render_category == RenderCategory::Transparent
             || render_category == RenderCategory::TransparentSpecular
+            || render_category == RenderCategory::OpaqueAdditive
         {
             depth_write_enabled = false;
         }
@@ -1196,6 +1274,54 @@ impl LightingMeshRenderer {
                         ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 10,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 11,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 12,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage {
+                                read_only: true,
+                            },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 13,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Depth,
+                            view_dimension: wgpu::TextureViewDimension::D2Array,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 14,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                        count: None,
+                    },
                 ],
             });
 
@@ -1301,6 +1427,8 @@ impl LightingMeshRenderer {
         let default_ltc_texture = create_default_ltc_texture(device, queue);
         let default_ltc_texture = Arc::new(default_ltc_texture);
 
+        let shadow_renderer = ShadowMapRenderer::new(device, &local_bind_group_layout);
+
         let light_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Lighting Light Bind Group"),
             layout: &light_bind_group_layout,
@@ -1345,6 +1473,28 @@ impl LightingMeshRenderer {
                     binding: 9,
                     resource: wgpu::BindingResource::Sampler(&default_ltc_texture.sampler),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 10,
+                    resource: wgpu::BindingResource::TextureView(&default_light_texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 11,
+                    resource: shadow_renderer.get_uniform_buffer().as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 12,
+                    resource: shadow_renderer.get_view_buffer().as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 13,
+                    resource: wgpu::BindingResource::TextureView(
+                        shadow_renderer.get_texture_view(),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 14,
+                    resource: wgpu::BindingResource::Sampler(shadow_renderer.get_sampler()),
+                },
             ],
         });
 
@@ -1374,6 +1524,7 @@ impl LightingMeshRenderer {
             disk_light_buffer,
             rect_light_buffer,
             infinite_light_buffer,
+            shadow_renderer,
             mesh_items,
             textures,
             pipelines: materials,
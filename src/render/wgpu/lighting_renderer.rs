@@ -1,10 +1,13 @@
+use super::environment_renderer::EnvironmentRenderer;
 use super::lighting_mesh_renderer::LightingMeshRenderer;
-use super::linear_to_srgb_renderer::LinearToSrgbRenderer;
 use super::lines_renderer::LinesRenderer;
+use super::post_process_renderer::PostProcessRenderer;
 use super::render_item::get_render_items;
 use crate::model::base::Matrix4x4;
 use crate::model::scene::Node;
 use crate::render::render_mode::RenderMode;
+use crate::render::scene_overlays::SceneOverlays;
+use crate::render::viewport_settings::ViewportSettings;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::RwLock;
@@ -25,22 +28,26 @@ type FrameBufferMap = HashMap<FrameBufferType, (wgpu::Texture, wgpu::Texture)>;
 
 pub struct LightingRenderer {
     // surface textures
+    environment_renderer: Arc<RwLock<EnvironmentRenderer>>,
     mesh_renderer: Arc<RwLock<LightingMeshRenderer>>,
     lines_renderer: Arc<RwLock<LinesRenderer>>,
-    copy_texture_renderer: Arc<RwLock<LinearToSrgbRenderer>>,
+    post_process_renderer: Arc<RwLock<PostProcessRenderer>>,
     frame_buffers: Arc<RwLock<FrameBufferMap>>,
 }
 
 #[derive(Debug, Clone)]
 struct PerFrameCallback {
     rect: [f32; 4],
+    environment_renderer: Arc<RwLock<EnvironmentRenderer>>,
     mesh_renderer: Arc<RwLock<LightingMeshRenderer>>,
     lines_renderer: Arc<RwLock<LinesRenderer>>,
-    copy_texture_renderer: Arc<RwLock<LinearToSrgbRenderer>>,
+    post_process_renderer: Arc<RwLock<PostProcessRenderer>>,
     frame_buffers: Arc<RwLock<FrameBufferMap>>,
     node: Arc<RwLock<Node>>,
     world_to_camera: glam::Mat4,
     camera_to_clip: glam::Mat4,
+    overlays: SceneOverlays,
+    settings: ViewportSettings,
 }
 
 unsafe impl Send for PerFrameCallback {}
@@ -114,7 +121,13 @@ impl egui_wgpu::CallbackTrait for PerFrameCallback {
     ) -> Vec<wgpu::CommandBuffer> {
         //let rect = self.rect.clone();
         //println!("PerFrameCallback::prepare: rect: {:?}", rect);
-        let render_items = get_render_items(device, queue, &self.node, RenderMode::Lighting);
+        let render_items = get_render_items(
+            device,
+            queue,
+            &self.node,
+            RenderMode::Lighting,
+            &self.overlays,
+        );
         let commands = vec![];
         // Prepare the frame buffers
         self.prepare_frame_buffers(device, &screen_descriptor, &self.rect);
@@ -123,6 +136,17 @@ impl egui_wgpu::CallbackTrait for PerFrameCallback {
             if let Some((color_texture, depth_texture)) =
                 frame_buffers.get(&FrameBufferType::FinalRender)
             {
+                {
+                    // Prepare the background from the infinite light
+                    let mut renderer = self.environment_renderer.write().unwrap();
+                    renderer.prepare(
+                        device,
+                        queue,
+                        &render_items,
+                        &self.world_to_camera,
+                        &self.camera_to_clip,
+                    );
+                }
                 {
                     // Prepare the mesh renderer with the render items
                     let mut renderer = self.mesh_renderer.write().unwrap();
@@ -132,7 +156,9 @@ impl egui_wgpu::CallbackTrait for PerFrameCallback {
                         &render_items,
                         &self.world_to_camera,
                         &self.camera_to_clip,
+                        &self.settings.shadows,
                     );
+                    renderer.paint_shadows(encoder);
                 }
                 {
                     let mut renderer = self.lines_renderer.write().unwrap();
@@ -145,9 +171,16 @@ impl egui_wgpu::CallbackTrait for PerFrameCallback {
                     );
                 }
                 {
-                    // Prepare the copy renderer
-                    let mut renderer = self.copy_texture_renderer.write().unwrap();
-                    renderer.prepare(device, color_texture);
+                    // Prepare the post-process chain reading the frame buffers
+                    let mut renderer = self.post_process_renderer.write().unwrap();
+                    renderer.prepare(
+                        device,
+                        queue,
+                        color_texture,
+                        depth_texture,
+                        &self.camera_to_clip,
+                        &self.settings.post_process,
+                    );
                 }
 
                 let color_texture_view =
@@ -176,6 +209,10 @@ impl egui_wgpu::CallbackTrait for PerFrameCallback {
                     occlusion_query_set: None,
                 });
 
+                {
+                    let renderer = self.environment_renderer.read().unwrap();
+                    renderer.paint(&mut rpass);
+                }
                 {
                     let renderer = self.mesh_renderer.read().unwrap();
                     renderer.paint(&mut rpass);
@@ -184,6 +221,11 @@ impl egui_wgpu::CallbackTrait for PerFrameCallback {
                     let renderer = self.lines_renderer.read().unwrap();
                     renderer.paint(&mut rpass);
                 }
+                drop(rpass);
+                {
+                    let renderer = self.post_process_renderer.read().unwrap();
+                    renderer.paint_ssao(encoder);
+                }
             }
         }
 
@@ -196,9 +238,9 @@ impl egui_wgpu::CallbackTrait for PerFrameCallback {
         render_pass: &mut wgpu::RenderPass<'static>,
         _resources: &egui_wgpu::CallbackResources,
     ) {
-        // Set the render pass to copy the final render texture to the screen
-        let copy_renderer = self.copy_texture_renderer.read().unwrap();
-        copy_renderer.paint(render_pass);
+        // Resolve the final render texture to the screen
+        let post_process_renderer = self.post_process_renderer.read().unwrap();
+        post_process_renderer.paint(render_pass);
     }
 }
 
@@ -207,15 +249,17 @@ impl LightingRenderer {
         let render_state = cc.wgpu_render_state.as_ref()?;
         let device = &render_state.device;
         let queue = &render_state.queue;
+        let environment_renderer = EnvironmentRenderer::new(device, queue, INTERNAL_TEXTURE_FORMAT);
         let mesh_renderer = LightingMeshRenderer::new(device, queue, INTERNAL_TEXTURE_FORMAT);
         let lines_renderer = LinesRenderer::new(device, queue, INTERNAL_TEXTURE_FORMAT);
-        let copy_texture_renderer =
-            LinearToSrgbRenderer::new(device, queue, render_state.target_format);
+        let post_process_renderer =
+            PostProcessRenderer::new(device, queue, render_state.target_format);
         // Create the lighting renderer with the mesh and lines renderers
         return Some(LightingRenderer {
+            environment_renderer: Arc::new(RwLock::new(environment_renderer)),
             mesh_renderer: Arc::new(RwLock::new(mesh_renderer)),
             lines_renderer: Arc::new(RwLock::new(lines_renderer)),
-            copy_texture_renderer: Arc::new(RwLock::new(copy_texture_renderer)),
+            post_process_renderer: Arc::new(RwLock::new(post_process_renderer)),
             frame_buffers: Arc::new(RwLock::new(HashMap::new())),
         });
     }
@@ -227,6 +271,8 @@ impl LightingRenderer {
         node: &Arc<RwLock<Node>>,
         w2c: &Matrix4x4,
         c2c: &Matrix4x4,
+        settings: &ViewportSettings,
+        overlays: &SceneOverlays,
     ) {
         let c2c = *c2c;
         let c2c = Matrix4x4::OPENGL_TO_WGPU_CLIP * c2c; // Convert to WGPU clip space
@@ -234,13 +280,16 @@ impl LightingRenderer {
             rect,
             PerFrameCallback {
                 rect: [rect.min.x, rect.min.y, rect.max.x, rect.max.y],
+                environment_renderer: self.environment_renderer.clone(),
                 mesh_renderer: self.mesh_renderer.clone(),
                 lines_renderer: self.lines_renderer.clone(),
-                copy_texture_renderer: self.copy_texture_renderer.clone(),
+                post_process_renderer: self.post_process_renderer.clone(),
                 frame_buffers: self.frame_buffers.clone(),
                 node: node.clone(),
                 world_to_camera: glam::Mat4::from(w2c),
                 camera_to_clip: glam::Mat4::from(c2c),
+                overlays: overlays.clone(),
+                settings: settings.clone(),
             },
         ));
     }
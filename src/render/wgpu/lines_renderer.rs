@@ -1,3 +1,4 @@
+use crate::render::wgpu::material::RenderCategory;
 use crate::render::wgpu::material::RenderUniformValue;
 
 use super::lines::RenderLinesVertex;
@@ -37,6 +38,7 @@ struct MaterialUniforms {
 #[derive(Debug, Clone)]
 pub struct LinesRenderer {
     pipeline: wgpu::RenderPipeline,
+    overlay_pipeline: wgpu::RenderPipeline, // Ignores the depth buffer
     #[allow(dead_code)]
     global_bind_group_layout: wgpu::BindGroupLayout,
     global_bind_group: wgpu::BindGroup,
@@ -87,6 +89,18 @@ fn create_material_uniform_buffer(device: &wgpu::Device, num_items: usize) -> wg
     return buffer;
 }
 
+fn is_overlay(item: &RenderItem) -> bool {
+    if let RenderItem::Lines(line_item) = item {
+        if let Some(material) = line_item.material.as_ref() {
+            return material
+                .passes
+                .iter()
+                .any(|pass| pass.render_category == RenderCategory::Overlay);
+        }
+    }
+    return false;
+}
+
 impl LinesRenderer {
     pub fn prepare(
         &mut self,
@@ -194,25 +208,34 @@ impl LinesRenderer {
 
     pub fn paint(&self, render_pass: &mut wgpu::RenderPass) {
         if !self.render_items.is_empty() {
-            let local_uniform_alignment = self.local_uniform_alignment;
-            let material_uniform_alignment = self.material_uniform_alignment;
-            render_pass.set_pipeline(&self.pipeline); //
             render_pass.set_bind_group(0, &self.global_bind_group, &[]);
-            for (i, item) in self.render_items.iter().enumerate() {
-                let i = i as wgpu::DynamicOffset;
-                if let RenderItem::Lines(line_item) = item.as_ref() {
-                    let local_uniform_offset = i * local_uniform_alignment as wgpu::DynamicOffset;
-                    let material_uniform_offset =
-                        i * material_uniform_alignment as wgpu::DynamicOffset;
-                    render_pass.set_bind_group(1, &self.local_bind_group, &[local_uniform_offset]);
-                    render_pass.set_bind_group(
-                        2,
-                        &self.material_bind_group,
-                        &[material_uniform_offset],
-                    );
-                    render_pass.set_vertex_buffer(0, line_item.lines.vertex_buffer.slice(..));
-                    render_pass.draw(0..line_item.lines.vertex_count, 0..1);
-                }
+            // Overlays are drawn last so they stay on top of the scene lines
+            for (pipeline, overlay) in [(&self.pipeline, false), (&self.overlay_pipeline, true)] {
+                render_pass.set_pipeline(pipeline);
+                self.paint_items(render_pass, overlay);
+            }
+        }
+    }
+
+    fn paint_items(&self, render_pass: &mut wgpu::RenderPass, overlay: bool) {
+        let local_uniform_alignment = self.local_uniform_alignment;
+        let material_uniform_alignment = self.material_uniform_alignment;
+        for (i, item) in self.render_items.iter().enumerate() {
+            if is_overlay(item) != overlay {
+                continue;
+            }
+            let i = i as wgpu::DynamicOffset;
+            if let RenderItem::Lines(line_item) = item.as_ref() {
+                let local_uniform_offset = i * local_uniform_alignment as wgpu::DynamicOffset;
+                let material_uniform_offset = i * material_uniform_alignment as wgpu::DynamicOffset;
+                render_pass.set_bind_group(1, &self.local_bind_group, &[local_uniform_offset]);
+                render_pass.set_bind_group(
+                    2,
+                    &self.material_bind_group,
+                    &[material_uniform_offset],
+                );
+                render_pass.set_vertex_buffer(0, line_item.lines.vertex_buffer.slice(..));
+                render_pass.draw(0..line_item.lines.vertex_count, 0..1);
             }
         }
     }
@@ -331,6 +354,34 @@ impl LinesRenderer {
             cache: None,
         });
 
+        let overlay_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Lines Overlay Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &vertex_buffer_layout,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(target_format.into())],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: primitive,
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
         let global_unifroms = GlobalUniforms {
             world_to_camera: glam::Mat4::IDENTITY.to_cols_array_2d(), // Identity matrix for now
             camera_to_clip: glam::Mat4::IDENTITY.to_cols_array_2d(),  // Identity matrix for now
@@ -398,6 +449,7 @@ impl LinesRenderer {
 
         return LinesRenderer {
             pipeline,
+            overlay_pipeline,
             global_bind_group_layout,
             global_bind_group,
             global_uniform_buffer,
@@ -9,10 +9,12 @@ use uuid::Uuid;
 pub enum RenderCategory {
     #[default]
     Opaque = 1000, //use for opaque surfaces
-    Emissive = 1500,            //use for light diffuse no lighting
-    Masked = 2500,              //use for masked surfaces
-    Transparent = 3000,         //use for transparent surfaces
+    OpaqueAdditive = 1100, //use for layers added onto opaque surfaces (mix)
+    Emissive = 1500,       //use for light diffuse no lighting
+    Masked = 2500,         //use for masked surfaces
+    Transparent = 3000,    //use for transparent surfaces
     TransparentSpecular = 3500, //use for transparent emissive surfaces
+    Overlay = 4000,        //use for gizmos drawn over the scene
 }
 
 #[derive(Debug, Clone)]
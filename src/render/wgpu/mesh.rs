@@ -14,6 +14,7 @@ pub struct RenderMesh {
     pub index_buffer: wgpu::Buffer,
     pub vertex_count: u32,
     pub index_count: u32,
+    pub bounds: [[f32; 3]; 2], // Local bounding box, min and max
 }
 
 #[repr(C)]
@@ -51,6 +52,21 @@ fn get_vertices(mesh: &MeshData) -> Vec<RenderVertex> {
     vertices
 }
 
+fn get_bounds(mesh: &MeshData) -> [[f32; 3]; 2] {
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    for p in mesh.positions.chunks_exact(3) {
+        for i in 0..3 {
+            min[i] = f32::min(min[i], p[i]);
+            max[i] = f32::max(max[i], p[i]);
+        }
+    }
+    if min[0] > max[0] {
+        return [[0.0; 3], [0.0; 3]];
+    }
+    return [min, max];
+}
+
 fn get_indices(mesh: &MeshData) -> Vec<u32> {
     mesh.indices.iter().map(|&i| i as u32).collect()
 }
@@ -92,6 +108,7 @@ impl RenderMesh {
                 contents: bytemuck::cast_slice(&index_data),
                 usage: wgpu::BufferUsages::INDEX,
             });
+            let bounds = get_bounds(&mesh_data);
             let mesh = RenderMesh {
                 id: mesh_id,
                 edition,
@@ -99,6 +116,7 @@ impl RenderMesh {
                 index_buffer,
                 vertex_count: vertex_count as u32,
                 index_count: index_count as u32,
+                bounds,
             };
             return Some(mesh);
         }
@@ -1,4 +1,8 @@
 pub mod copy_shaders;
+pub mod debug_mesh_renderer;
+pub mod debug_renderer;
+pub mod environment;
+pub mod environment_renderer;
 pub mod light;
 pub mod lighting_mesh_renderer;
 pub mod lighting_renderer;
@@ -9,12 +13,15 @@ pub mod ltc;
 pub mod material;
 pub mod matrix;
 pub mod mesh;
+pub mod post_process_renderer;
 pub mod render_gizmo_item;
 pub mod render_item;
 pub mod render_light_item;
 pub mod render_mesh_item;
 pub mod render_resource;
 pub mod shader;
+pub mod shader_permutation;
+pub mod shadow_map;
 pub mod solid_mesh_renderer;
 pub mod solid_renderer;
 pub mod texture;
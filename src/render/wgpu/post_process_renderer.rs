@@ -0,0 +1,368 @@
+use crate::render::viewport_settings::PostProcessSettings;
+use crate::render::viewport_settings::ToneMapping;
+
+use bytemuck::{Pod, Zeroable};
+use eframe::wgpu;
+use eframe::wgpu::util::DeviceExt;
+
+const AO_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod, Zeroable)]
+struct SsaoUniforms {
+    camera_to_clip: [[f32; 4]; 4], // 4 * 4 * 4 = 64
+    clip_to_camera: [[f32; 4]; 4], // 4 * 4 * 4 = 64
+    radius: f32,
+    intensity: f32,
+    sample_count: u32,
+    _pad1: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod, Zeroable)]
+struct ToneMappingUniforms {
+    exposure: f32,
+    tone_mapping: u32,
+    false_color: u32,
+    ssao: u32,
+}
+
+fn get_tone_mapping_index(tone_mapping: ToneMapping) -> u32 {
+    match tone_mapping {
+        ToneMapping::LinearClamp => 0,
+        ToneMapping::Reinhard => 1,
+        ToneMapping::AcesFilmic => 2,
+    }
+}
+
+fn create_fullscreen_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    shader: &wgpu::ShaderModule,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    target_format: wgpu::TextureFormat,
+    depth_stencil: Option<wgpu::DepthStencilState>,
+) -> wgpu::RenderPipeline {
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(&format!("{} Pipeline Layout", label)),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    });
+    return device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(&format!("{} Pipeline", label)),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            compilation_options: Default::default(),
+            targets: &[Some(target_format.into())],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            ..Default::default()
+        },
+        depth_stencil,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    });
+}
+
+fn create_ao_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    return device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Ambient Occlusion Texture"),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: AO_TEXTURE_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[AO_TEXTURE_FORMAT],
+    });
+}
+
+#[derive(Debug, Clone)]
+pub struct PostProcessRenderer {
+    // Resolves the linear render of the lighting view to the screen:
+    // SSAO from the depth buffer, then exposure, tone mapping and sRGB encoding
+    ssao_pipeline: wgpu::RenderPipeline,
+    ssao_bind_group_layout: wgpu::BindGroupLayout,
+    ssao_uniform_buffer: wgpu::Buffer,
+    ssao_bind_group: Option<wgpu::BindGroup>,
+    ao_texture: Option<wgpu::Texture>,
+    ssao_enabled: bool,
+    tone_mapping_pipeline: wgpu::RenderPipeline,
+    tone_mapping_bind_group_layout: wgpu::BindGroupLayout,
+    tone_mapping_uniform_buffer: wgpu::Buffer,
+    tone_mapping_bind_group: Option<wgpu::BindGroup>,
+}
+
+impl PostProcessRenderer {
+    pub fn new(
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        target_format: wgpu::TextureFormat,
+    ) -> Self {
+        let ssao_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("SSAO Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                include_str!("../../../assets/shaders/ssao.wgsl").into(),
+            ),
+        });
+        let tone_mapping_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Tone Mapping Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                include_str!("../../../assets/shaders/tone_mapping.wgsl").into(),
+            ),
+        });
+
+        let ssao_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(size_of::<SsaoUniforms>() as _),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Depth,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some("SSAO Bind Group Layout"),
+            });
+
+        let tone_mapping_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(
+                                size_of::<ToneMappingUniforms>() as _,
+                            ),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some("Tone Mapping Bind Group Layout"),
+            });
+
+        let ssao_pipeline = create_fullscreen_pipeline(
+            device,
+            "SSAO",
+            &ssao_shader,
+            &ssao_bind_group_layout,
+            AO_TEXTURE_FORMAT,
+            None,
+        );
+        // Drawn into the egui render pass like the other views
+        let tone_mapping_pipeline = create_fullscreen_pipeline(
+            device,
+            "Tone Mapping",
+            &tone_mapping_shader,
+            &tone_mapping_bind_group_layout,
+            target_format,
+            Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+        );
+
+        let ssao_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer for SSAO"),
+            contents: bytemuck::bytes_of(&SsaoUniforms::default()),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+        });
+        let tone_mapping_uniform_buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Uniform Buffer for Tone Mapping"),
+                contents: bytemuck::bytes_of(&ToneMappingUniforms::default()),
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+            });
+
+        PostProcessRenderer {
+            ssao_pipeline,
+            ssao_bind_group_layout,
+            ssao_uniform_buffer,
+            ssao_bind_group: None,
+            ao_texture: None,
+            ssao_enabled: false,
+            tone_mapping_pipeline,
+            tone_mapping_bind_group_layout,
+            tone_mapping_uniform_buffer,
+            tone_mapping_bind_group: None,
+        }
+    }
+
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        color_texture: &wgpu::Texture,
+        depth_texture: &wgpu::Texture,
+        camera_to_clip: &glam::Mat4,
+        settings: &PostProcessSettings,
+    ) {
+        let (width, height) = (color_texture.width(), color_texture.height());
+        let resized = self
+            .ao_texture
+            .as_ref()
+            .map(|texture| texture.width() != width || texture.height() != height)
+            .unwrap_or(true);
+        if resized {
+            self.ao_texture = Some(create_ao_texture(device, width, height));
+        }
+        let ao_texture = self.ao_texture.as_ref().unwrap();
+        let ao_texture_view = ao_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        self.ssao_enabled = settings.ssao.enabled;
+        let ssao_uniforms = SsaoUniforms {
+            camera_to_clip: camera_to_clip.to_cols_array_2d(),
+            clip_to_camera: camera_to_clip.inverse().to_cols_array_2d(),
+            radius: settings.ssao.radius,
+            intensity: settings.ssao.intensity,
+            sample_count: settings.ssao.sample_count,
+            _pad1: 0,
+        };
+        queue.write_buffer(
+            &self.ssao_uniform_buffer,
+            0,
+            bytemuck::bytes_of(&ssao_uniforms),
+        );
+        let tone_mapping_uniforms = ToneMappingUniforms {
+            exposure: settings.exposure.exp2(),
+            tone_mapping: get_tone_mapping_index(settings.tone_mapping),
+            false_color: settings.false_color as u32,
+            ssao: settings.ssao.enabled as u32,
+        };
+        queue.write_buffer(
+            &self.tone_mapping_uniform_buffer,
+            0,
+            bytemuck::bytes_of(&tone_mapping_uniforms),
+        );
+
+        let depth_texture_view = depth_texture.create_view(&wgpu::TextureViewDescriptor {
+            aspect: wgpu::TextureAspect::DepthOnly,
+            ..Default::default()
+        });
+        self.ssao_bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.ssao_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.ssao_uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&depth_texture_view),
+                },
+            ],
+            label: Some("SSAO Bind Group"),
+        }));
+        let color_texture_view = color_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.tone_mapping_bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.tone_mapping_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.tone_mapping_uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&color_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&ao_texture_view),
+                },
+            ],
+            label: Some("Tone Mapping Bind Group"),
+        }));
+    }
+
+    /// Renders the ambient occlusion once the depth buffer of the frame is complete.
+    pub fn paint_ssao(&self, encoder: &mut wgpu::CommandEncoder) {
+        if !self.ssao_enabled {
+            return;
+        }
+        let (Some(ao_texture), Some(bind_group)) =
+            (self.ao_texture.as_ref(), self.ssao_bind_group.as_ref())
+        else {
+            return;
+        };
+        let ao_texture_view = ao_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("SSAO Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &ao_texture_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        rpass.set_pipeline(&self.ssao_pipeline);
+        rpass.set_bind_group(0, bind_group, &[]);
+        rpass.draw(0..6, 0..1); // Draw a full-screen quad
+    }
+
+    pub fn paint(&self, render_pass: &mut wgpu::RenderPass) {
+        if let Some(bind_group) = self.tone_mapping_bind_group.as_ref() {
+            render_pass.set_pipeline(&self.tone_mapping_pipeline);
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw(0..6, 0..1); // Draw a full-screen quad
+        }
+    }
+}
@@ -7,10 +7,17 @@ use super::render_item::RenderItem;
 use super::render_item::create_render_pass;
 use super::render_item::get_shader_type;
 use super::render_resource::RenderResourceManager;
+use crate::conversion::camera_shape::create_camera_shape;
+use crate::model::scene::CameraComponent;
 use crate::model::scene::CoordinateSystemComponent;
+use crate::model::scene::FilmComponent;
 use crate::model::scene::Node;
 use crate::model::scene::ResourceManager;
 use crate::render::render_mode::RenderMode;
+use crate::render::scene_item::SceneItem;
+use crate::render::transform_gizmo::TransformGizmo;
+use crate::render::transform_gizmo::get_gizmo_handle_lines;
+use crate::render::transform_gizmo::get_gizmo_handles;
 
 use std::sync::Arc;
 use std::sync::RwLock;
@@ -25,6 +32,7 @@ fn get_lines_material(
     edition: &str,
     render_resource_manager: &mut RenderResourceManager,
     base_color: &[f32; 4],
+    render_category: RenderCategory,
 ) -> Option<Arc<RenderMaterial>> {
     if let Some(mat) = render_resource_manager.get_material(id) {
         if mat.edition == edition {
@@ -43,7 +51,7 @@ fn get_lines_material(
         device,
         queue,
         "lines",
-        RenderCategory::Opaque,
+        render_category,
         &uniform_values,
         render_resource_manager,
     )];
@@ -114,8 +122,15 @@ pub fn get_render_axis_gizmo_items(
                 _ => continue,
             };
             let matrix = glam::Mat4::IDENTITY; // World axes are at the origin
-            let material =
-                get_lines_material(device, queue, id, &edition, render_resource_manager, &color);
+            let material = get_lines_material(
+                device,
+                queue,
+                id,
+                &edition,
+                render_resource_manager,
+                &color,
+                RenderCategory::Opaque,
+            );
             let render_item = LinesRenderItem {
                 lines,
                 material,
@@ -222,8 +237,15 @@ pub fn get_render_grid_gizmo_items(
     }
     if let Some(lines) = render_lines {
         let color = [0.5, 0.5, 0.5, 1.0]; // Gray color for the grid
-        let material =
-            get_lines_material(device, queue, id, &edition, render_resource_manager, &color);
+        let material = get_lines_material(
+            device,
+            queue,
+            id,
+            &edition,
+            render_resource_manager,
+            &color,
+            RenderCategory::Opaque,
+        );
         let matrix = glam::Mat4::IDENTITY; // Grid is at the origin
         let render_item = LinesRenderItem {
             lines,
@@ -234,3 +256,139 @@ pub fn get_render_grid_gizmo_items(
     }
     return render_items;
 }
+
+pub fn get_render_transform_gizmo_items(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    gizmo: &TransformGizmo,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Vec<Arc<RenderItem>> {
+    const BASE_ID: u128 = 0x00000000_1000_0000_0000_000000000100; // Transform gizmo handles
+    let edition = "transform_gizmo".to_string();
+    let matrix = glam::Mat4::from(&gizmo.get_local_to_world());
+    let mut render_items = Vec::new();
+    for handle in get_gizmo_handles(gizmo.mode).iter() {
+        let index = (gizmo.mode.get_index() * 8 + handle.get_index()) as u128;
+        let id = Uuid::from_u128(BASE_ID + index);
+        let mut render_lines = None;
+        if let Some(lines) = render_resource_manager.get_lines(id) {
+            render_lines = Some(lines.clone());
+        } else {
+            let lines = get_gizmo_handle_lines(gizmo.mode, *handle);
+            if let Some(lines) = RenderLines::from_lines(device, queue, id, &edition, &lines) {
+                let lines = Arc::new(lines);
+                render_resource_manager.add_lines(&lines);
+                render_lines = Some(lines);
+            }
+        }
+        if let Some(lines) = render_lines {
+            // Highlighted handles use their own material
+            let highlighted = gizmo.highlighted == Some(*handle);
+            let material_index = handle.get_index() as u128 * 2 + highlighted as u128;
+            let material_id = Uuid::from_u128(BASE_ID + 0x80 + material_index);
+            let color = handle.get_color(highlighted);
+            let material = get_lines_material(
+                device,
+                queue,
+                material_id,
+                &edition,
+                render_resource_manager,
+                &color,
+                RenderCategory::Overlay,
+            );
+            let render_item = LinesRenderItem {
+                lines,
+                material,
+                matrix,
+            };
+            render_items.push(Arc::new(RenderItem::Lines(render_item)));
+        }
+    }
+    return render_items;
+}
+
+fn get_camera_edition(node: &Arc<RwLock<Node>>) -> Option<String> {
+    let node = node.read().unwrap();
+    let camera = node.get_component::<CameraComponent>()?;
+    let film = node.get_component::<FilmComponent>().map(|f| &f.props);
+    return Some(format!("{:?}{:?}", camera.props, film));
+}
+
+pub fn get_render_camera_gizmo_items(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    item: &SceneItem,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Vec<Arc<RenderItem>> {
+    const MATERIAL_IDS: [Uuid; 2] = [
+        Uuid::from_u128(0x00000000_1000_0000_0000_000000000005), // Frustum
+        Uuid::from_u128(0x00000000_1000_0000_0000_000000000006), // Focal plane
+    ];
+    const COLORS: [[f32; 4]; 2] = [
+        [0.6, 0.8, 1.0, 1.0],  // Light blue for the frustum
+        [1.0, 0.55, 0.1, 1.0], // Orange for the focal plane
+    ];
+    let mut render_items = Vec::new();
+    let Some(edition) = get_camera_edition(&item.node) else {
+        return render_items;
+    };
+    let node_id = item.node.read().unwrap().get_id();
+    let ids = [node_id, Uuid::from_u128(node_id.as_u128() ^ 1)];
+    let mut shape = None;
+    for i in 0..2 {
+        let id = ids[i];
+        let mut render_lines = None;
+        if let Some(lines) = render_resource_manager.get_lines(id) {
+            if lines.edition == edition {
+                render_lines = Some(lines.clone());
+            }
+        }
+        if render_lines.is_none() {
+            if shape.is_none() {
+                shape = create_camera_shape(&item.node);
+            }
+            let Some(shape) = shape.as_ref() else {
+                return render_items;
+            };
+            let lines = if i == 0 {
+                &shape.lines
+            } else {
+                &shape.focal_lines
+            };
+            if lines.is_empty() {
+                continue;
+            }
+            let lines = lines
+                .iter()
+                .map(|line| {
+                    line.iter()
+                        .map(|point| [point.x, point.y, point.z])
+                        .collect::<Vec<[f32; 3]>>()
+                })
+                .collect::<Vec<Vec<[f32; 3]>>>();
+            if let Some(lines) = RenderLines::from_lines(device, queue, id, &edition, &lines) {
+                let lines = Arc::new(lines);
+                render_resource_manager.add_lines(&lines);
+                render_lines = Some(lines);
+            }
+        }
+        if let Some(lines) = render_lines {
+            let material = get_lines_material(
+                device,
+                queue,
+                MATERIAL_IDS[i],
+                "camera",
+                render_resource_manager,
+                &COLORS[i],
+                RenderCategory::Opaque,
+            );
+            let render_item = LinesRenderItem {
+                lines,
+                material,
+                matrix: glam::Mat4::from(&item.matrix),
+            };
+            render_items.push(Arc::new(RenderItem::Lines(render_item)));
+        }
+    }
+    return render_items;
+}
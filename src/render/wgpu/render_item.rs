@@ -6,18 +6,26 @@ use super::material::RenderPass;
 use super::material::RenderUniformValue;
 use super::mesh::RenderMesh;
 use super::render_gizmo_item::get_render_axis_gizmo_items;
+use super::render_gizmo_item::get_render_camera_gizmo_items;
 use super::render_gizmo_item::get_render_grid_gizmo_items;
+use super::render_gizmo_item::get_render_transform_gizmo_items;
 use super::render_light_item::get_render_light_gizmo_item;
 use super::render_light_item::get_render_light_items;
 use super::render_mesh_item::get_render_mesh_item;
 use super::render_resource::RenderResourceComponent;
 use super::render_resource::RenderResourceManager;
 use super::shader::RenderShader;
+use super::shader_permutation::ShaderPermutationKey;
+use super::shader_permutation::preprocess_shader;
+use super::shader_permutation::preprocess_shader_source;
+use super::shader_permutation::validate_shader_source;
 use super::texture::RenderTexture;
-use crate::conversion::spectrum::Spectrum;
+use crate::conversion::color_space::ColorSpace;
+use crate::conversion::spectrum::property_to_rgb;
 use crate::conversion::texture_node::DynaImage;
 use crate::conversion::texture_node::TexturePurpose;
 use crate::conversion::texture_node::create_image_variants;
+use crate::conversion::texture_node::create_normal_map_variant;
 use crate::conversion::texture_node::create_texture_nodes;
 use crate::model::base::Property;
 use crate::model::base::PropertyMap;
@@ -26,11 +34,16 @@ use crate::model::scene::ResourceCacheComponent;
 use crate::model::scene::ResourceCacheManager;
 use crate::model::scene::ResourceComponent;
 use crate::model::scene::ResourceManager;
+use crate::model::scene::SceneComponent;
+use crate::model::scene::Texture;
 use crate::render::render_mode::RenderMode;
 use crate::render::scene_item::*;
+use crate::render::scene_overlays::SceneOverlays;
 use crate::render::wgpu::shader;
 //use crate::render::wgpu::texture;
 
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::RwLock;
 use std::vec;
@@ -41,10 +54,20 @@ use uuid::Uuid;
 
 use eframe::wgpu;
 
+/// Surface data the debug modes show in place of the shaded material.
+#[derive(Debug, Clone)]
+pub struct DebugSurface {
+    pub material_id: Option<Uuid>, // Material, or area light, of the mesh
+    pub albedo: [f32; 4],
+    pub albedo_texture: Option<Arc<RenderTexture>>,
+    pub one_sided: bool, // Shapes and area lights with twosided false
+}
+
 #[derive(Debug, Clone)]
 pub struct MeshRenderItem {
     pub mesh: Arc<RenderMesh>,
     pub material: Option<Arc<RenderMaterial>>,
+    pub debug_surface: Option<DebugSurface>,
     pub matrix: glam::Mat4,
 }
 
@@ -120,54 +143,31 @@ pub fn get_string(props: &PropertyMap, key: &str) -> Option<String> {
     return None;
 }
 
+/// Resolves a color-like property to linear sRGB for the viewport.
+/// `color_space` is the scene working color space RGB values are given in.
 pub fn get_color(
     props: &PropertyMap,
     key: &str,
     resource_manager: &ResourceManager,
+    color_space: ColorSpace,
 ) -> Option<[f32; 4]> {
     if let Some((key_type, _key_name, value)) = props.entry(key) {
-        if key_type == "blackbody" {
-            if let Property::Floats(v) = value {
-                if v.len() >= 2 {
-                    let s = Spectrum::from_blackbody(&v);
-                    let rgb = s.to_rgb();
-                    return Some([rgb[0], rgb[1], rgb[2], 1.0]);
-                }
-            }
-        } else if key_type == "spectrum" {
+        let mut fullpath = None;
+        if key_type == "spectrum" {
             if let Property::Strings(v) = value {
-                if v.len() >= 1 {
-                    let name = v[0].clone();
-                    if let Some(resource) = resource_manager.find_spectrum_by_filename(&name) {
-                        let resource = resource.read().unwrap();
-                        if let Some(fullpath) = resource.get_fullpath() {
-                            if let Ok(spectrum) = Spectrum::load_from_file(&fullpath) {
-                                let rgb = spectrum.to_rgb();
-                                return Some([rgb[0], rgb[1], rgb[2], 1.0]);
-                            }
-                        }
+                if let Some(name) = v.first() {
+                    if let Some(resource) = resource_manager.find_spectrum_by_filename(name) {
+                        fullpath = resource.read().unwrap().get_fullpath();
                     }
-                    log::warn!(
-                        "Spectrum resource not found for key: {} with value: {}",
-                        key,
-                        name
-                    );
-                }
-            }
-        } else if key_type == "float" {
-            if let Property::Floats(v) = value {
-                if v.len() >= 1 {
-                    // Assuming the first three values are RGB
-                    return Some([v[0], v[0], v[0], 1.0]);
-                }
-            }
-        } else {
-            if let Property::Floats(v) = value {
-                if v.len() >= 3 {
-                    return Some([v[0], v[1], v[2], 1.0]);
                 }
             }
         }
+        if let Some(rgb) = property_to_rgb(key_type, value, fullpath.as_deref(), color_space) {
+            return Some([rgb[0], rgb[1], rgb[2], 1.0]);
+        }
+        if key_type == "spectrum" {
+            log::warn!("Spectrum could not be resolved for key: {}", key);
+        }
     }
     return None;
 }
@@ -203,6 +203,19 @@ pub fn get_texture(
     return None;
 }
 
+/// The normal map converted from the float texture bound to `key`.
+pub fn get_normal_map(
+    props: &PropertyMap,
+    key: &str,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &RenderResourceManager,
+) -> Option<Arc<RenderTexture>> {
+    let name = get_string(props, key)?;
+    let texture = resource_manager.find_texture_by_name(&name)?;
+    let texture_id = texture.read().unwrap().get_id();
+    return render_resource_manager.get_normal_map(texture_id).cloned();
+}
+
 pub fn get_shader_type(
     shader_type: &str,
     uniform_values: &Vec<(String, RenderUniformValue)>,
@@ -253,7 +266,7 @@ fn create_uniform_value_bytes(
             }
             RenderUniformValue::Vec4(v) => {
                 if remain != 0 {
-                    for _ in 0..remain {
+                    for _ in 0..remain / 4 {
                         bytes.extend_from_slice(bytemuck::bytes_of(&0.0f32));
                         type_variables.push(("f32".to_string(), format!("_pad{}", padding_count))); //
                         padding_count += 1;
@@ -282,7 +295,7 @@ fn create_uniform_value_bytes(
             }
             RenderUniformValue::Mat4(v) => {
                 if remain != 0 {
-                    for _ in 0..remain {
+                    for _ in 0..remain / 4 {
                         bytes.extend_from_slice(bytemuck::bytes_of(&0.0f32));
                         type_variables.push(("f32".to_string(), format!("_pad{}", padding_count))); //
                         padding_count += 1;
@@ -293,6 +306,14 @@ fn create_uniform_value_bytes(
                 type_variables.push(("mat4x4<f32>".to_string(), name.clone())); //
             }
             RenderUniformValue::Texture(v) => {
+                if remain != 0 {
+                    for _ in 0..remain / 4 {
+                        bytes.extend_from_slice(bytemuck::bytes_of(&0.0f32));
+                        type_variables.push(("f32".to_string(), format!("_pad{}", padding_count))); //
+                        padding_count += 1;
+                    }
+                    remain = 0;
+                }
                 let scale_offset: [f32; 4] = [v.scale[0], v.scale[1], v.delta[0], v.delta[1]];
                 bytes.extend_from_slice(bytemuck::bytes_of(&scale_offset));
                 type_variables.push(("vec4<f32>".to_string(), format!("{}_uv_factor", name))); //
@@ -300,7 +321,7 @@ fn create_uniform_value_bytes(
         }
     }
     if remain != 0 {
-        for _ in 0..remain {
+        for _ in 0..remain / 4 {
             bytes.extend_from_slice(bytemuck::bytes_of(&0.0f32));
             type_variables.push(("f32".to_string(), format!("_pad{}", padding_count))); //
             padding_count += 1;
@@ -311,38 +332,33 @@ fn create_uniform_value_bytes(
     return (type_variables, bytes);
 }
 
-fn get_shader_id_from_type(shader_type: &str) -> Uuid {
-    return Uuid::new_v3(&Uuid::NAMESPACE_OID, shader_type.as_bytes());
+fn get_shader_dir() -> PathBuf {
+    return dirs::cache_dir()
+        .unwrap()
+        .join("pbrt_ui")
+        .join("shaders")
+        .join("surface");
 }
 
-fn get_shader_source(shader_type: &str) -> String {
-    let cache_dir = dirs::cache_dir().unwrap().join("pbrt_ui").join("shaders");
-    if shader_type.starts_with("arealight") {
-        let source = cache_dir.join("surface").join("arealight_diffuse.wgsl");
-        if source.exists() {
-            if let Ok(code) = std::fs::read_to_string(source) {
-                return code;
-            }
-        }
-    } else {
-        let source = cache_dir
-            .join("surface")
-            .join(format!("{}.wgsl", shader_type));
-        if source.exists() {
-            if let Ok(code) = std::fs::read_to_string(source) {
-                return code;
+fn get_shader_module(device: &wgpu::Device, key: &ShaderPermutationKey) -> wgpu::ShaderModule {
+    let shader_dir = get_shader_dir();
+    let fallback = ShaderPermutationKey::new("basic", &[], &[]);
+    let source = match preprocess_shader(key, &shader_dir) {
+        Ok((source, source_map)) => match validate_shader_source(&source, &source_map) {
+            Ok(()) => source,
+            Err(e) => {
+                log::error!("Failed to compile shader {}: {}", key, e);
+                preprocess_shader_source(&fallback, &shader_dir).unwrap()
             }
+        },
+        Err(e) => {
+            log::error!("Failed to preprocess shader {}: {}", key, e);
+            preprocess_shader_source(&fallback, &shader_dir).unwrap()
         }
-    }
-    let source = cache_dir.join("surface").join("basic_material.wgsl");
-    let code = std::fs::read_to_string(source).unwrap();
-    return code;
-}
-
-fn get_shader_module(device: &wgpu::Device, shader_type: &str) -> wgpu::ShaderModule {
-    let source = get_shader_source(shader_type);
+    };
+    let label = key.to_string();
     let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("Shader"),
+        label: Some(&label),
         source: wgpu::ShaderSource::Wgsl(source.into()),
     });
     return shader;
@@ -353,20 +369,20 @@ pub fn create_render_shader(
     _queue: &wgpu::Queue,
     shader_type: &str,
     uniform_values: &[(String, RenderUniformValue)],
+    defines: &[(&str, &str)],
     render_resource_manager: &mut RenderResourceManager,
 ) -> Arc<RenderShader> {
-    let shader_type = get_shader_type(shader_type, &uniform_values.to_vec());
-    let shader_id = get_shader_id_from_type(&shader_type);
-    if let Some(shader) = render_resource_manager.get_shader(shader_id) {
+    let key = ShaderPermutationKey::new(shader_type, uniform_values, defines);
+    if let Some(shader) = render_resource_manager.get_shader_permutation(&key) {
         return shader.clone();
     }
-    let shader_module = get_shader_module(device, &shader_type);
+    let shader_module = get_shader_module(device, &key);
     let render_shader = RenderShader {
-        id: shader_id,
+        id: key.get_id(),
         shader: Arc::new(shader_module),
     };
     let render_shader = Arc::new(render_shader);
-    render_resource_manager.add_shader(&render_shader);
+    render_resource_manager.add_shader_permutation(&key, &render_shader);
     return render_shader;
 }
 
@@ -377,12 +393,34 @@ pub fn create_render_pass(
     render_category: RenderCategory,
     uniform_values: &[(String, RenderUniformValue)],
     render_resource_manager: &mut RenderResourceManager,
+) -> Arc<RenderPass> {
+    return create_render_pass_with_defines(
+        device,
+        queue,
+        shader_type,
+        render_category,
+        uniform_values,
+        &[],
+        render_resource_manager,
+    );
+}
+
+/// Like `create_render_pass`, with extra preprocessor symbols for the shader permutation.
+pub fn create_render_pass_with_defines(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    shader_type: &str,
+    render_category: RenderCategory,
+    uniform_values: &[(String, RenderUniformValue)],
+    defines: &[(&str, &str)],
+    render_resource_manager: &mut RenderResourceManager,
 ) -> Arc<RenderPass> {
     let shader = create_render_shader(
         device,
         queue,
         shader_type,
         uniform_values,
+        defines,
         render_resource_manager,
     );
     let (_uniform_values_types, uniform_values_bytes) = create_uniform_value_bytes(uniform_values);
@@ -435,6 +473,14 @@ fn get_resource_cache_manager(node: &Arc<RwLock<Node>>) -> Arc<RwLock<ResourceCa
     return component.get_resource_cache_manager();
 }
 
+fn get_color_space(node: &Arc<RwLock<Node>>) -> ColorSpace {
+    let node = node.read().unwrap();
+    if let Some(scene) = node.get_component::<SceneComponent>() {
+        return scene.get_color_space();
+    }
+    return ColorSpace::default();
+}
+
 fn get_image_data(image: &DynaImage) -> image::Rgba32FImage {
     return image.to_rgba32f();
 }
@@ -485,6 +531,44 @@ fn convert_address_mode(wrap: &str) -> wgpu::AddressMode {
     }
 }
 
+fn create_render_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &Texture,
+    image: &DynaImage,
+) -> RenderTexture {
+    let wrap = get_string(texture.as_property_map(), "wrap").unwrap_or("repeat".to_string());
+    let swrap = get_string(texture.as_property_map(), "swrap").unwrap_or(wrap.clone());
+    let twrap = get_string(texture.as_property_map(), "twrap").unwrap_or(wrap.clone());
+    let address_mode_u = convert_address_mode(&swrap);
+    let address_mode_v = convert_address_mode(&twrap);
+    let uscale = get_float(texture.as_property_map(), "uscale").unwrap_or(1.0);
+    let vscale = get_float(texture.as_property_map(), "vscale").unwrap_or(1.0);
+    let udelta = get_float(texture.as_property_map(), "udelta").unwrap_or(0.0);
+    let vdelta = get_float(texture.as_property_map(), "vdelta").unwrap_or(0.0);
+
+    let image_data = get_image_data(image);
+    let gpu_texture = get_texture_from_image(device, queue, &image_data);
+    let view = gpu_texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Render Texture Sampler"),
+        address_mode_u: address_mode_u,
+        address_mode_v: address_mode_v,
+        min_filter: wgpu::FilterMode::Linear,
+        mag_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+    return RenderTexture {
+        id: texture.get_id(),
+        edition: texture.get_edition(),
+        texture: gpu_texture,
+        view,
+        sampler,
+        scale: [uscale, vscale],
+        delta: [udelta, vdelta],
+    };
+}
+
 fn create_render_textures(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
@@ -503,41 +587,11 @@ fn create_render_textures(
                 continue;
             }
         }
-        let wrap = get_string(texture.as_property_map(), "wrap").unwrap_or("repeat".to_string());
-        let swrap = get_string(texture.as_property_map(), "swrap").unwrap_or(wrap.clone());
-        let twrap = get_string(texture.as_property_map(), "twrap").unwrap_or(wrap.clone());
-        let address_mode_u = convert_address_mode(&swrap);
-        let address_mode_v = convert_address_mode(&twrap);
-        let uscale = get_float(texture.as_property_map(), "uscale").unwrap_or(1.0);
-        let vscale = get_float(texture.as_property_map(), "vscale").unwrap_or(1.0);
-        let udelta = get_float(texture.as_property_map(), "udelta").unwrap_or(0.0);
-        let vdelta = get_float(texture.as_property_map(), "vdelta").unwrap_or(0.0);
-
         if let Some(texture_node) = resource_cache_manager.textures.get(&texture_id) {
             let texture_node = texture_node.read().unwrap();
             if let Some(image) = texture_node.image_variants.get(&purpose) {
                 let image = image.read().unwrap();
-                let image_data = get_image_data(&image);
-                let texture = get_texture_from_image(device, queue, &image_data);
-                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-                let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-                    label: Some("Render Texture Sampler"),
-                    address_mode_u: address_mode_u,
-                    address_mode_v: address_mode_v,
-                    min_filter: wgpu::FilterMode::Linear,
-                    mag_filter: wgpu::FilterMode::Linear,
-                    ..Default::default()
-                });
-
-                let render_texture = RenderTexture {
-                    id: texture_id,
-                    edition: texture_edition.clone(),
-                    texture,
-                    view,
-                    sampler,
-                    scale: [uscale, vscale],
-                    delta: [udelta, vdelta],
-                };
+                let render_texture = create_render_texture(device, queue, &texture, &image);
                 let render_texture = Arc::new(render_texture);
                 render_resource_manager.add_texture(&render_texture);
             }
@@ -545,11 +599,53 @@ fn create_render_textures(
     }
 }
 
+/// Creates normal maps for the textures the materials use as bump maps.
+fn create_render_normal_maps(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    resource_manager: &ResourceManager,
+    resource_cache_manager: &ResourceCacheManager,
+    render_resource_manager: &mut RenderResourceManager,
+) {
+    let mut bumpmap_names = HashSet::new();
+    for (_id, material) in resource_manager.materials.iter() {
+        let material = material.read().unwrap();
+        if let Some(name) = get_string(&material.props, "bumpmap") {
+            bumpmap_names.insert(name);
+        }
+    }
+    for name in bumpmap_names.iter() {
+        let Some(texture) = resource_manager.find_texture_by_name(name) else {
+            continue;
+        };
+        let texture = texture.read().unwrap();
+        let texture_id = texture.get_id();
+        if let Some(normal_map) = render_resource_manager.get_normal_map(texture_id) {
+            if texture.get_edition() == normal_map.edition {
+                continue;
+            }
+        }
+        if let Some(texture_node) = resource_cache_manager.textures.get(&texture_id) {
+            if let Some(image) = create_normal_map_variant(
+                texture_node,
+                resource_manager,
+                resource_cache_manager.color_space,
+            ) {
+                let image = image.read().unwrap();
+                let normal_map = create_render_texture(device, queue, &texture, &image);
+                let normal_map = Arc::new(normal_map);
+                render_resource_manager.add_normal_map(&normal_map);
+            }
+        }
+    }
+}
+
 pub fn get_render_items(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     node: &Arc<RwLock<Node>>,
     mode: RenderMode,
+    overlays: &SceneOverlays,
 ) -> Vec<Arc<RenderItem>> {
     let scene_items = get_scene_items(node);
     let mut render_items = Vec::new();
@@ -560,7 +656,10 @@ pub fn get_render_items(
     let render_resource_manager = get_render_resource_manager(node);
     let mut render_resource_manager = render_resource_manager.write().unwrap();
 
-    if mode == RenderMode::Lighting {
+    let color_space = get_color_space(node);
+    render_resource_manager.set_color_space(color_space);
+    if mode.uses_textures() {
+        resource_cache_manager.set_color_space(color_space);
         create_texture_nodes(&resource_manager, &mut resource_cache_manager);
         create_image_variants(
             &resource_manager,
@@ -575,6 +674,13 @@ pub fn get_render_items(
             &mut render_resource_manager,
             TexturePurpose::Render,
         );
+        create_render_normal_maps(
+            device,
+            queue,
+            &resource_manager,
+            &resource_cache_manager,
+            &mut render_resource_manager,
+        );
     }
 
     for item in scene_items.iter() {
@@ -617,8 +723,16 @@ pub fn get_render_items(
                     render_items.push(Arc::new(render_item));
                 }
             }
-            // Handle other categories like Light, Camera, etc.
-            _ => {}
+            SceneItemType::Camera => {
+                if overlays.show_cameras {
+                    render_items.extend(get_render_camera_gizmo_items(
+                        device,
+                        queue,
+                        item,
+                        &mut render_resource_manager,
+                    ));
+                }
+            }
         }
     }
     //additional render items based on the mode
@@ -645,7 +759,39 @@ pub fn get_render_items(
                 &mut render_resource_manager,
             ));
         }
+        if let Some(gizmo) = overlays.transform_gizmo.as_ref() {
+            render_items.extend(get_render_transform_gizmo_items(
+                device,
+                queue,
+                gizmo,
+                &mut render_resource_manager,
+            ));
+        }
     }
 
     return render_items;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_vec4_after_float_is_aligned() {
+        let uniform_values = vec![
+            ("roughness".to_string(), RenderUniformValue::Float(0.5)),
+            (
+                "kd".to_string(),
+                RenderUniformValue::Vec4([1.0, 0.5, 0.25, 1.0]),
+            ),
+            ("eta".to_string(), RenderUniformValue::Float(1.5)),
+        ];
+        let (type_variables, bytes) = create_uniform_value_bytes(&uniform_values);
+        assert_eq!(bytes.len(), 48);
+        assert_eq!(type_variables.len(), 1 + 3 + 1 + 1 + 3);
+        assert_eq!(type_variables[4].1, "kd");
+        let floats: &[f32] = bytemuck::cast_slice(&bytes);
+        assert_eq!(floats[4..8], [1.0, 0.5, 0.25, 1.0]);
+        assert_eq!(floats[8], 1.5);
+    }
+}
@@ -1,3 +1,6 @@
+use super::environment::PendingEnvironment;
+use super::environment::RenderEnvironment;
+use super::environment::create_render_environment;
 use super::light::DirectionalRenderLight;
 use super::light::DiskRenderLight;
 use super::light::InfiniteRenderLight;
@@ -14,16 +17,15 @@ use super::render_item::RenderLightItem;
 use super::render_item::create_render_pass;
 use super::render_item::get_color;
 use super::render_resource::RenderResourceManager;
-use super::texture::RenderTexture;
 use crate::conversion::light_shape::create_light_shape;
 use crate::conversion::mesh_data::create_mesh_data;
 use crate::conversion::plane_data::create_plane_meshes_from_mesh;
 use crate::conversion::plane_data::create_plane_outline_from_plane_mesh;
 use crate::conversion::plane_data::create_plane_rect_from_plane_outline;
-use crate::conversion::texture_node::DynaImage;
 use crate::conversion::texture_node::TexturePurpose;
 use crate::conversion::texture_node::create_image_variant;
 use crate::model::base::Matrix4x4;
+use crate::model::base::PropertyMap;
 use crate::model::base::Vector3;
 use crate::model::scene::Light;
 use crate::model::scene::LightComponent;
@@ -53,6 +55,17 @@ fn coordinate_system(v1: &Vector3) -> (Vector3, Vector3) {
     return (v2, v3);
 }
 
+/// A color of the light in linear sRGB, white when it is not set or cannot be resolved.
+fn get_light_color(
+    props: &PropertyMap,
+    key: &str,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &RenderResourceManager,
+) -> [f32; 4] {
+    let color_space = render_resource_manager.color_space;
+    return get_color(props, key, resource_manager, color_space).unwrap_or([1.0, 1.0, 1.0, 1.0]);
+}
+
 fn get_light_type(node: &Arc<RwLock<Node>>) -> Option<String> {
     let node = node.read().unwrap();
     if let Some(component) = node.get_component::<LightComponent>() {
@@ -119,8 +132,8 @@ fn get_directional_light_item(
         let dir = to - from;
         let direction = [dir.x, dir.y, dir.z];
 
-        let l = get_color(&props, "L", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
-        let scale = get_color(&props, "scale", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
+        let l = get_light_color(&props, "L", resource_manager, render_resource_manager);
+        let scale = get_light_color(&props, "scale", resource_manager, render_resource_manager);
 
         let intensity = [l[0] * scale[0], l[1] * scale[1], l[2] * scale[2]];
         let render_light = DirectionalRenderLight {
@@ -186,8 +199,8 @@ fn get_point_light_item(
         let translation = Matrix4x4::translate(from[0], from[1], from[2]);
         let mat = translation * item.matrix;
 
-        let l = get_color(&props, "I", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
-        let scale = get_color(&props, "scale", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
+        let l = get_light_color(&props, "I", resource_manager, render_resource_manager);
+        let scale = get_light_color(&props, "scale", resource_manager, render_resource_manager);
 
         let p = 4.0; //std::f32::consts::PI;//1.0 / (4.0 * std::f32::consts::PI); // Point light power normalization
         let intensity = [
@@ -277,8 +290,8 @@ fn get_spot_light_item(
         let inner_angle = f32::to_radians((coneangle - conedelta).max(0.0));
         let outer_angle = f32::to_radians(coneangle);
 
-        let l = get_color(&props, "I", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
-        let scale = get_color(&props, "scale", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
+        let l = get_light_color(&props, "I", resource_manager, render_resource_manager);
+        let scale = get_light_color(&props, "scale", resource_manager, render_resource_manager);
 
         let p = 1.0; // / std::f32::consts::PI; // Point light power normalization
         let intensity = [
@@ -366,8 +379,8 @@ fn get_sphere_light_item(
     };
 
     let props = light.as_property_map();
-    let l = get_color(&props, "L", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
-    let scale = get_color(&props, "scale", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
+    let l = get_light_color(&props, "L", resource_manager, render_resource_manager);
+    let scale = get_light_color(&props, "scale", resource_manager, render_resource_manager);
 
     let intensity = [
         area * l[0] * scale[0],
@@ -435,8 +448,8 @@ fn get_disk_light_item(
     //let area = 1.0;////radius * radius; // Assuming a disk for area calculation
 
     let props = light.as_property_map();
-    let l = get_color(&props, "L", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
-    let scale = get_color(&props, "scale", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
+    let l = get_light_color(&props, "L", resource_manager, render_resource_manager);
+    let scale = get_light_color(&props, "scale", resource_manager, render_resource_manager);
 
     let coneangle = props.find_one_float("coneangle").unwrap_or(90.0);
     let conedelta = props.find_one_float("conedeltaangle").unwrap_or(90.0);
@@ -520,9 +533,8 @@ fn get_rects_light_item(
         }
         if !rects.is_empty() {
             let props = light.as_property_map();
-            let l = get_color(&props, "L", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
-            let scale =
-                get_color(&props, "scale", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
+            let l = get_light_color(&props, "L", resource_manager, render_resource_manager);
+            let scale = get_light_color(&props, "scale", resource_manager, render_resource_manager);
 
             let twosided = props.find_one_bool("twosided").unwrap_or(false);
 
@@ -660,99 +672,45 @@ fn get_area_light_item(
     return None;
 }
 
-fn get_image_data(image: &DynaImage) -> image::Rgba32FImage {
-    return image.to_rgba32f();
-}
-
-fn get_texture_from_image(
-    device: &wgpu::Device,
-    queue: &wgpu::Queue,
-    image: &image::Rgba32FImage,
-) -> wgpu::Texture {
-    let dimensions = image.dimensions();
-    let size = wgpu::Extent3d {
-        width: dimensions.0,
-        height: dimensions.1,
-        depth_or_array_layers: 1,
-    };
-    let texture = device.create_texture(&wgpu::TextureDescriptor {
-        label: Some("Render Texture"),
-        size: size,
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Rgba32Float,
-        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-        view_formats: &[],
-    });
-    let image_raw = image.as_raw();
-    queue.write_texture(
-        texture.as_image_copy(),
-        bytemuck::cast_slice(image_raw),
-        wgpu::TexelCopyBufferLayout {
-            offset: 0,
-            bytes_per_row: Some(4 * 4 * dimensions.0),
-            rows_per_image: None,
-        },
-        size,
-    );
-    return texture;
-}
-
-fn get_render_texture(
+fn get_render_environment(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     resource_manager: &ResourceManager,
     resource_cache_manager: &mut ResourceCacheManager,
     render_resource_manager: &mut RenderResourceManager,
     mapname: &str,
-) -> Option<Arc<RenderTexture>> {
-    //println!("Searching for texture: {}", mapname);
-    if let Some(texture) = resource_manager.find_texture_by_filename(mapname) {
-        let texture = texture.read().unwrap();
-        let texture_id = texture.get_id();
-        let texture_edition = texture.get_edition();
-        // println!("Found texture: {} (ID: {})", mapname, texture.get_id());
-        if let Some(render_texture) = render_resource_manager.get_texture(texture_id) {
-            if render_texture.edition == texture_edition {
-                return Some(render_texture.clone());
-            }
+) -> Option<Arc<RenderEnvironment>> {
+    let texture = resource_manager.find_texture_by_filename(mapname)?;
+    let texture = texture.read().unwrap();
+    let texture_id = texture.get_id();
+    let texture_edition = texture.get_edition();
+    if let Some(environment) = render_resource_manager.get_environment(texture_id) {
+        if environment.edition == texture_edition {
+            return Some(environment.clone());
         }
-        if let Some(texture_node) = resource_cache_manager.textures.get(&texture_id) {
-            // println!("Loading texture: {} (ID: {})", mapname, texture_id);
-            if let Some(image) =
-                create_image_variant(texture_node, resource_manager, TexturePurpose::Render)
-            {
-                // println!("Texture image created: {} (ID: {})", mapname, texture_id);
-                let image = image.read().unwrap();
-                let image_data = get_image_data(&image);
-                let texture = get_texture_from_image(device, queue, &image_data);
-                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-                let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-                    label: Some("Render Texture Sampler"),
-                    address_mode_u: wgpu::AddressMode::Repeat,
-                    address_mode_v: wgpu::AddressMode::ClampToEdge,
-                    min_filter: wgpu::FilterMode::Linear,
-                    mag_filter: wgpu::FilterMode::Linear,
-                    ..Default::default()
-                });
-                let render_texture = RenderTexture {
-                    id: texture_id,
-                    edition: texture_edition.clone(),
-                    texture,
-                    view,
-                    sampler,
-                    scale: [1.0, 1.0],
-                    delta: [0.0, 0.0],
-                };
-                let render_texture = Arc::new(render_texture);
-                render_resource_manager.add_texture(&render_texture);
-                // println!("Loaded texture: {} (ID: {})", mapname, texture_id);
-                return Some(render_texture);
-            }
+    }
+    // Prefiltering takes a while, the light stays constant until it is done
+    if let Some(pending) = render_resource_manager.get_pending_environment(texture_id) {
+        if pending.edition == texture_edition {
+            let images = pending.take_result()?;
+            let environment =
+                create_render_environment(device, queue, texture_id, &texture_edition, images);
+            let environment = Arc::new(environment);
+            render_resource_manager.remove_pending_environment(texture_id);
+            render_resource_manager.add_environment(&environment);
+            return Some(environment);
         }
     }
-    return None; // Texture not found
+    let texture_node = resource_cache_manager.textures.get(&texture_id)?;
+    let image = create_image_variant(
+        texture_node,
+        resource_manager,
+        TexturePurpose::Render,
+        resource_cache_manager.color_space,
+    )?;
+    let pending = PendingEnvironment::spawn(texture_id, &texture_edition, image);
+    render_resource_manager.add_pending_environment(pending);
+    return None;
 }
 
 fn get_rotation_matrix(matrix: &Matrix4x4) -> Matrix4x4 {
@@ -799,8 +757,8 @@ fn get_infinite_light_item(
 
         let props = light.as_property_map();
 
-        let l = get_color(&props, "L", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
-        let scale = get_color(&props, "scale", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
+        let l = get_light_color(&props, "L", resource_manager, render_resource_manager);
+        let scale = get_light_color(&props, "scale", resource_manager, render_resource_manager);
 
         let p = 1.0; // / std::f32::consts::PI; // Point light power normalization
         let intensity = [
@@ -809,36 +767,41 @@ fn get_infinite_light_item(
             p * l[2] * scale[2],
         ];
 
-        let mapname = props.find_one_string("mapname").unwrap_or("".to_string());
-        if mapname.is_empty() {
-            return None; // No texture map specified for infinite light
-        }
-
         // Use only rotation part of the matrix for infinite light
         let light_matrix = get_rotation_matrix(&item.matrix);
 
-        if let Some(texture) = get_render_texture(
-            device,
-            queue,
-            resource_manager,
-            resource_cache_manager,
-            render_resource_manager,
-            &mapname,
-        ) {
-            let render_light = InfiniteRenderLight {
-                id,
-                edition: edition.clone(),
-                intensity,
-                texture: Some(texture.clone()),
-            };
-            let render_light = Arc::new(RenderLight::Infinite(render_light));
+        // Without a map the light is a constant environment
+        let mapname = props.find_one_string("mapname").unwrap_or("".to_string());
+        let environment = if mapname.is_empty() {
+            None
+        } else {
+            get_render_environment(
+                device,
+                queue,
+                resource_manager,
+                resource_cache_manager,
+                render_resource_manager,
+                &mapname,
+            )
+        };
+
+        // Not cached while the map is missing so it is picked up once loaded
+        let is_complete = mapname.is_empty() || environment.is_some();
+        let render_light = InfiniteRenderLight {
+            id,
+            edition: edition.clone(),
+            intensity,
+            environment,
+        };
+        let render_light = Arc::new(RenderLight::Infinite(render_light));
+        if is_complete {
             render_resource_manager.add_light(&render_light);
-            let render_item = RenderLightItem {
-                light: render_light.clone(),
-                matrix: glam::Mat4::from(light_matrix),
-            };
-            return Some(RenderItem::Light(render_item));
         }
+        let render_item = RenderLightItem {
+            light: render_light.clone(),
+            matrix: glam::Mat4::from(light_matrix),
+        };
+        return Some(RenderItem::Light(render_item));
     }
     return None; // Placeholder for light retrieval logic
 }
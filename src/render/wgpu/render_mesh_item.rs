@@ -3,14 +3,19 @@ use super::material::RenderMaterial;
 use super::material::RenderPass;
 use super::material::RenderUniformValue;
 use super::mesh::RenderMesh;
+use super::render_item::DebugSurface;
 use super::render_item::MeshRenderItem;
 use super::render_item::RenderItem;
 use super::render_item::create_render_pass;
+use super::render_item::create_render_pass_with_defines;
 use super::render_item::get_bool;
 use super::render_item::get_color;
 use super::render_item::get_float;
+use super::render_item::get_normal_map;
+use super::render_item::get_string;
 use super::render_item::get_texture;
 use super::render_resource::RenderResourceManager;
+use super::texture::RenderTexture;
 use crate::model::scene::Light;
 use crate::model::scene::Material;
 use crate::model::scene::MaterialComponent;
@@ -92,6 +97,173 @@ fn roughness_to_alpha(roughness: f32) -> f32 {
         + 0.000640711 * x * x * x * x;
 }
 
+// RGB fit of pbrt's default copper spectra
+const COPPER_ETA: [f32; 4] = [0.200438, 0.924033, 1.10221, 1.0];
+const COPPER_K: [f32; 4] = [3.91295, 2.45285, 2.14219, 1.0];
+
+/// Weight of a sub-material inside a mix material.
+/// The base layer is scaled by `1 - amount`, the overlay by `amount` and added on top.
+struct MixLayer {
+    amount: RenderUniformValue,
+    overlay: bool,
+}
+
+/// Inputs shared by all passes of a material: its mix weight and the normal map of its bumpmap.
+struct SurfaceLayer<'a> {
+    mix: Option<&'a MixLayer>,
+    normal_map: Option<Arc<RenderTexture>>,
+}
+
+/// A color parameter as a uniform: the resolved color, or the texture bound to it.
+fn get_color_uniform(
+    material: &Material,
+    key: &str,
+    default: [f32; 4],
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> RenderUniformValue {
+    if let Some(color) = get_color(
+        &material.props,
+        key,
+        resource_manager,
+        render_resource_manager.color_space,
+    ) {
+        return RenderUniformValue::Vec4(color);
+    }
+    if let Some(texture) = get_texture(
+        &material.props,
+        key,
+        resource_manager,
+        render_resource_manager,
+    ) {
+        return RenderUniformValue::Texture(texture);
+    }
+    return RenderUniformValue::Vec4(default);
+}
+
+/// A float parameter as a uniform: the value, or the texture bound to it.
+fn get_float_uniform(
+    material: &Material,
+    key: &str,
+    default: f32,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> RenderUniformValue {
+    if let Some(texture) = get_texture(
+        &material.props,
+        key,
+        resource_manager,
+        render_resource_manager,
+    ) {
+        return RenderUniformValue::Texture(texture);
+    }
+    let value = get_float(&material.props, key).unwrap_or(default);
+    return RenderUniformValue::Float(value);
+}
+
+/// The roughness parameter as a uniform. A textured roughness is remapped in the shader,
+/// so the returned define tells the permutation to do so.
+fn get_roughness_uniform(
+    material: &Material,
+    key: &str,
+    default: f32,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> (RenderUniformValue, Option<(&'static str, &'static str)>) {
+    let remaproughness = get_bool(&material.props, "remaproughness").unwrap_or(true);
+    match get_float_uniform(
+        material,
+        key,
+        default,
+        resource_manager,
+        render_resource_manager,
+    ) {
+        RenderUniformValue::Float(roughness) if remaproughness => (
+            RenderUniformValue::Float(roughness_to_alpha(roughness)),
+            None,
+        ),
+        RenderUniformValue::Texture(texture) if remaproughness => (
+            RenderUniformValue::Texture(texture),
+            Some(("REMAP_ROUGHNESS", "1")),
+        ),
+        value => (value, None),
+    }
+}
+
+/// `uroughness`/`vroughness` fall back to `roughness` like pbrt does for metal and uber.
+fn get_anisotropic_roughness_key(material: &Material, key: &'static str) -> &'static str {
+    if material.props.entry(key).is_some() {
+        return key;
+    }
+    return "roughness";
+}
+
+fn is_black(value: &RenderUniformValue) -> bool {
+    match value {
+        RenderUniformValue::Vec4(v) => v[0] <= 0.0 && v[1] <= 0.0 && v[2] <= 0.0,
+        _ => false,
+    }
+}
+
+fn get_rgb(
+    material: &Material,
+    key: &str,
+    default: [f32; 3],
+    resource_manager: &ResourceManager,
+    render_resource_manager: &RenderResourceManager,
+) -> [f32; 3] {
+    get_color(
+        &material.props,
+        key,
+        resource_manager,
+        render_resource_manager.color_space,
+    )
+    .map(|c| [c[0], c[1], c[2]])
+    .unwrap_or(default)
+}
+
+/// Creates a pass of a surface shader family, bump mapped and weighted as a mix layer as `layer` says.
+fn create_surface_pass(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    family: &str,
+    render_category: RenderCategory,
+    mut uniform_values: Vec<(String, RenderUniformValue)>,
+    defines: &[(&str, &str)],
+    layer: &SurfaceLayer,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Arc<RenderPass> {
+    let mut defines = defines.to_vec();
+    let mut render_category = render_category;
+    if let Some(normal_map) = layer.normal_map.as_ref() {
+        // defines BUMPMAP_TEXTURE, the shaders perturb the shading normal with it
+        uniform_values.push((
+            "bumpmap".to_string(),
+            RenderUniformValue::Texture(normal_map.clone()),
+        ));
+    }
+    if let Some(layer) = layer.mix {
+        // the amount comes last so the sub-material's texture bindings stay in place
+        uniform_values.push(("amount".to_string(), layer.amount.clone()));
+        defines.push(("MIX_AMOUNT", "1"));
+        if layer.overlay {
+            defines.push(("MIX_OVERLAY", "1"));
+            if render_category == RenderCategory::Opaque {
+                render_category = RenderCategory::OpaqueAdditive;
+            }
+        }
+    }
+    return create_render_pass_with_defines(
+        device,
+        queue,
+        family,
+        render_category,
+        &uniform_values,
+        &defines,
+        render_resource_manager,
+    );
+}
+
 fn create_basic_render_passes(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
@@ -100,7 +272,13 @@ fn create_basic_render_passes(
     render_resource_manager: &mut RenderResourceManager,
 ) -> Vec<Arc<RenderPass>> {
     let diffuse_color = if let Some(key) = get_base_diffuse_key(material) {
-        get_color(&material.props, &key, resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0])
+        get_color(
+            &material.props,
+            &key,
+            resource_manager,
+            render_resource_manager.color_space,
+        )
+        .unwrap_or([1.0, 1.0, 1.0, 1.0])
     } else {
         [1.0, 1.0, 1.0, 1.0]
     };
@@ -130,179 +308,993 @@ fn create_matte_render_passes(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     material: &Material,
+    layer: &SurfaceLayer,
     resource_manager: &ResourceManager,
     render_resource_manager: &mut RenderResourceManager,
 ) -> Vec<Arc<RenderPass>> {
     let keys = ["Kd"];
     let mut uniform_values = vec![];
     for key in keys {
-        if let Some(color) = get_color(&material.props, key, resource_manager) {
-            uniform_values.push((key.to_lowercase(), RenderUniformValue::Vec4(color)));
-        } else if let Some(texture) = get_texture(
-            &material.props,
+        let value = get_color_uniform(
+            material,
             key,
+            [1.0; 4],
             resource_manager,
             render_resource_manager,
-        ) {
-            let texture = render_resource_manager
-                .get_texture(texture.get_id())
-                .unwrap();
-            uniform_values.push((
-                key.to_lowercase(),
-                RenderUniformValue::Texture(texture.clone()),
-            ));
-        } else {
-            uniform_values.push((
-                key.to_lowercase(),
-                RenderUniformValue::Vec4([1.0, 1.0, 1.0, 1.0]),
-            ));
+        );
+        uniform_values.push((key.to_lowercase(), value));
+    }
+    let render_pass = create_surface_pass(
+        device,
+        queue,
+        "lambertian_none",
+        RenderCategory::Opaque,
+        uniform_values,
+        &[],
+        layer,
+        render_resource_manager,
+    );
+    return vec![render_pass];
+}
+
+fn create_plastic_render_passes(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    material: &Material,
+    layer: &SurfaceLayer,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Vec<Arc<RenderPass>> {
+    let keys = ["Kd", "Ks"];
+    let mut uniform_values = vec![];
+    for key in keys {
+        let value = get_color_uniform(
+            material,
+            key,
+            [1.0; 4],
+            resource_manager,
+            render_resource_manager,
+        );
+        uniform_values.push((key.to_lowercase(), value));
+    }
+    let (roughness, define) = get_roughness_uniform(
+        material,
+        "roughness",
+        0.1,
+        resource_manager,
+        render_resource_manager,
+    );
+    uniform_values.push(("roughness".to_string(), roughness));
+    let defines: Vec<(&str, &str)> = define.into_iter().collect();
+    //println!("{}: Plastic Shader Type: {}", material.get_name(),shader_type);
+    let render_pass = create_surface_pass(
+        device,
+        queue,
+        "lambertian_ggx",
+        RenderCategory::Opaque,
+        uniform_values,
+        &defines,
+        layer,
+        render_resource_manager,
+    );
+    return vec![render_pass];
+}
+
+fn create_uber_render_passes(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    material: &Material,
+    layer: &SurfaceLayer,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Vec<Arc<RenderPass>> {
+    let kd = get_color_uniform(
+        material,
+        "Kd",
+        [0.25, 0.25, 0.25, 1.0],
+        resource_manager,
+        render_resource_manager,
+    );
+    let ks = get_color_uniform(
+        material,
+        "Ks",
+        [0.25, 0.25, 0.25, 1.0],
+        resource_manager,
+        render_resource_manager,
+    );
+    let kr = get_color_uniform(
+        material,
+        "Kr",
+        [0.0, 0.0, 0.0, 1.0],
+        resource_manager,
+        render_resource_manager,
+    );
+    let kt = get_color_uniform(
+        material,
+        "Kt",
+        [0.0, 0.0, 0.0, 1.0],
+        resource_manager,
+        render_resource_manager,
+    );
+    let roughness_key = get_anisotropic_roughness_key(material, "uroughness");
+    let (roughness, define) = get_roughness_uniform(
+        material,
+        roughness_key,
+        0.1,
+        resource_manager,
+        render_resource_manager,
+    );
+    let defines: Vec<(&str, &str)> = define.into_iter().collect();
+
+    let mut passes = vec![];
+    {
+        let uniform_values = vec![
+            ("kd".to_string(), kd),
+            ("ks".to_string(), ks),
+            ("roughness".to_string(), roughness),
+        ];
+        let render_pass = create_surface_pass(
+            device,
+            queue,
+            "lambertian_ggx",
+            RenderCategory::Opaque,
+            uniform_values,
+            &defines,
+            layer,
+            render_resource_manager,
+        );
+        passes.push(render_pass);
+    }
+    // perfect specular reflection and transmission are added on top of the glossy base
+    if !is_black(&kr) {
+        let uniform_values = vec![
+            ("kr".to_string(), kr),
+            ("roughness".to_string(), RenderUniformValue::Float(0.0)),
+        ];
+        let render_pass = create_surface_pass(
+            device,
+            queue,
+            "none_ggx",
+            RenderCategory::TransparentSpecular,
+            uniform_values,
+            &[],
+            layer,
+            render_resource_manager,
+        );
+        passes.push(render_pass);
+    }
+    if !is_black(&kt) {
+        let uniform_values = vec![("kt".to_string(), kt)];
+        let render_pass = create_surface_pass(
+            device,
+            queue,
+            "transmission_none",
+            RenderCategory::TransparentSpecular,
+            uniform_values,
+            &[],
+            layer,
+            render_resource_manager,
+        );
+        passes.push(render_pass);
+    }
+    return passes;
+}
+
+fn create_substrate_render_passes(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    material: &Material,
+    layer: &SurfaceLayer,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Vec<Arc<RenderPass>> {
+    let mut uniform_values = vec![];
+    for key in ["Kd", "Ks"] {
+        let value = get_color_uniform(
+            material,
+            key,
+            [0.5, 0.5, 0.5, 1.0],
+            resource_manager,
+            render_resource_manager,
+        );
+        uniform_values.push((key.to_lowercase(), value));
+    }
+    let mut defines = vec![];
+    for key in ["uroughness", "vroughness"] {
+        let (roughness, define) = get_roughness_uniform(
+            material,
+            key,
+            0.1,
+            resource_manager,
+            render_resource_manager,
+        );
+        uniform_values.push((key.to_string(), roughness));
+        defines.extend(define);
+    }
+    defines.dedup();
+    let render_pass = create_surface_pass(
+        device,
+        queue,
+        "fresnel_blend",
+        RenderCategory::Opaque,
+        uniform_values,
+        &defines,
+        layer,
+        render_resource_manager,
+    );
+    return vec![render_pass];
+}
+
+fn create_metal_render_passes(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    material: &Material,
+    layer: &SurfaceLayer,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Vec<Arc<RenderPass>> {
+    let eta = get_color_uniform(
+        material,
+        "eta",
+        COPPER_ETA,
+        resource_manager,
+        render_resource_manager,
+    );
+    let k = get_color_uniform(
+        material,
+        "k",
+        COPPER_K,
+        resource_manager,
+        render_resource_manager,
+    );
+    let mut uniform_values = vec![("eta".to_string(), eta), ("k".to_string(), k)];
+    let mut defines = vec![];
+    for key in ["uroughness", "vroughness"] {
+        let roughness_key = get_anisotropic_roughness_key(material, key);
+        let (roughness, define) = get_roughness_uniform(
+            material,
+            roughness_key,
+            0.01,
+            resource_manager,
+            render_resource_manager,
+        );
+        uniform_values.push((key.to_string(), roughness));
+        defines.extend(define);
+    }
+    defines.dedup();
+    let render_pass = create_surface_pass(
+        device,
+        queue,
+        "conductor_ggx",
+        RenderCategory::Opaque,
+        uniform_values,
+        &defines,
+        layer,
+        render_resource_manager,
+    );
+    return vec![render_pass];
+}
+
+fn create_mirror_render_passes(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    material: &Material,
+    layer: &SurfaceLayer,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Vec<Arc<RenderPass>> {
+    let kr = get_color_uniform(
+        material,
+        "Kr",
+        [0.9, 0.9, 0.9, 1.0],
+        resource_manager,
+        render_resource_manager,
+    );
+    let uniform_values = vec![
+        ("kr".to_string(), kr),
+        ("roughness".to_string(), RenderUniformValue::Float(0.0)),
+    ];
+    let render_pass = create_surface_pass(
+        device,
+        queue,
+        "none_ggx",
+        RenderCategory::Opaque,
+        uniform_values,
+        &[],
+        layer,
+        render_resource_manager,
+    );
+    return vec![render_pass];
+}
+
+fn create_glass_render_passes(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    material: &Material,
+    layer: &SurfaceLayer,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Vec<Arc<RenderPass>> {
+    let transmission = get_color_uniform(
+        material,
+        "Kt",
+        [1.0; 4],
+        resource_manager,
+        render_resource_manager,
+    );
+    let reflection = get_color_uniform(
+        material,
+        "Kr",
+        [1.0; 4],
+        resource_manager,
+        render_resource_manager,
+    );
+    //use uroughness
+    let (roughness, define) = get_roughness_uniform(
+        material,
+        "uroughness",
+        0.1,
+        resource_manager,
+        render_resource_manager,
+    );
+    let defines: Vec<(&str, &str)> = define.into_iter().collect();
+
+    let mut passes = vec![];
+    {
+        let uniform_values = vec![("kt".to_string(), transmission)];
+        let render_pass = create_surface_pass(
+            device,
+            queue,
+            "transmission_none",
+            RenderCategory::Transparent,
+            uniform_values,
+            &[],
+            layer,
+            render_resource_manager,
+        );
+        passes.push(render_pass);
+    }
+    {
+        let uniform_values = vec![
+            ("kr".to_string(), reflection),
+            ("roughness".to_string(), roughness),
+        ];
+        let render_pass = create_surface_pass(
+            device,
+            queue,
+            "none_ggx",
+            RenderCategory::TransparentSpecular,
+            uniform_values,
+            &defines,
+            layer,
+            render_resource_manager,
+        );
+        passes.push(render_pass);
+    }
+    return passes;
+}
+
+/// Uniforms of the wrap-lighting family, in the order of its MaterialUniforms.
+fn create_wrap_render_pass(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    kd: RenderUniformValue,
+    kr: RenderUniformValue,
+    scatter: [f32; 3],
+    roughness: (RenderUniformValue, Option<(&'static str, &'static str)>),
+    wrap: f32,
+    eta: f32,
+    layer: &SurfaceLayer,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Arc<RenderPass> {
+    let (roughness, define) = roughness;
+    let defines: Vec<(&str, &str)> = define.into_iter().collect();
+    let uniform_values = vec![
+        ("kd".to_string(), kd),
+        ("kr".to_string(), kr),
+        (
+            "scatter".to_string(),
+            RenderUniformValue::Vec4([scatter[0], scatter[1], scatter[2], 1.0]),
+        ),
+        ("roughness".to_string(), roughness),
+        (
+            "wrap".to_string(),
+            RenderUniformValue::Float(wrap.clamp(0.0, 1.0)),
+        ),
+        ("eta".to_string(), RenderUniformValue::Float(eta)),
+    ];
+    return create_surface_pass(
+        device,
+        queue,
+        "wrap_ggx",
+        RenderCategory::Opaque,
+        uniform_values,
+        &defines,
+        layer,
+        render_resource_manager,
+    );
+}
+
+/// How far light wraps around the terminator for a mean free path in scene units.
+fn get_wrap_from_mfp(mfp: f32) -> f32 {
+    let mfp = f32::max(mfp, 0.0);
+    return mfp / (1.0 + mfp);
+}
+
+fn create_subsurface_render_passes(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    material: &Material,
+    layer: &SurfaceLayer,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Vec<Arc<RenderPass>> {
+    if let Some(name) = get_string(&material.props, "name") {
+        log::debug!("Measured medium '{}' is shown with sigma_a/sigma_s", name);
+    }
+    let sigma_a = get_rgb(
+        material,
+        "sigma_a",
+        [0.0011, 0.0024, 0.014],
+        resource_manager,
+        render_resource_manager,
+    );
+    let sigma_s = get_rgb(
+        material,
+        "sigma_s",
+        [2.55, 3.21, 3.77],
+        resource_manager,
+        render_resource_manager,
+    );
+    let scale = get_float(&material.props, "scale").unwrap_or(1.0);
+    let mut albedo = [0.0; 3];
+    let mut mfp = 0.0;
+    for i in 0..3 {
+        let sigma_t = sigma_a[i] + sigma_s[i];
+        if sigma_t > 0.0 {
+            albedo[i] = sigma_s[i] / sigma_t;
+            mfp += 1.0 / (sigma_t * scale.max(1e-6)) / 3.0;
         }
     }
-    let render_pass = create_render_pass(
+    let kd = RenderUniformValue::Vec4([albedo[0], albedo[1], albedo[2], 1.0]);
+    let kr = get_color_uniform(
+        material,
+        "Kr",
+        [1.0; 4],
+        resource_manager,
+        render_resource_manager,
+    );
+    let roughness = get_roughness_uniform(
+        material,
+        "uroughness",
+        0.0,
+        resource_manager,
+        render_resource_manager,
+    );
+    let eta = get_float(&material.props, "eta").unwrap_or(1.33);
+    let scatter = albedo.map(|a| a * a);
+    let render_pass = create_wrap_render_pass(
+        device,
+        queue,
+        kd,
+        kr,
+        scatter,
+        roughness,
+        get_wrap_from_mfp(mfp),
+        eta,
+        layer,
+        render_resource_manager,
+    );
+    return vec![render_pass];
+}
+
+fn create_kdsubsurface_render_passes(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    material: &Material,
+    layer: &SurfaceLayer,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Vec<Arc<RenderPass>> {
+    let kd = get_color_uniform(
+        material,
+        "Kd",
+        [0.5, 0.5, 0.5, 1.0],
+        resource_manager,
+        render_resource_manager,
+    );
+    let kr = get_color_uniform(
+        material,
+        "Kr",
+        [1.0; 4],
+        resource_manager,
+        render_resource_manager,
+    );
+    let mfp = get_rgb(
+        material,
+        "mfp",
+        [1.0, 1.0, 1.0],
+        resource_manager,
+        render_resource_manager,
+    );
+    let scale = get_float(&material.props, "scale").unwrap_or(1.0);
+    let mfp = (mfp[0] + mfp[1] + mfp[2]) / 3.0 / scale.max(1e-6);
+    let scatter = match &kd {
+        RenderUniformValue::Vec4(c) => [c[0] * c[0], c[1] * c[1], c[2] * c[2]],
+        _ => [1.0, 1.0, 1.0],
+    };
+    let roughness = get_roughness_uniform(
+        material,
+        "uroughness",
+        0.0,
+        resource_manager,
+        render_resource_manager,
+    );
+    let eta = get_float(&material.props, "eta").unwrap_or(1.33);
+    let render_pass = create_wrap_render_pass(
+        device,
+        queue,
+        kd,
+        kr,
+        scatter,
+        roughness,
+        get_wrap_from_mfp(mfp),
+        eta,
+        layer,
+        render_resource_manager,
+    );
+    return vec![render_pass];
+}
+
+fn create_translucent_render_passes(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    material: &Material,
+    layer: &SurfaceLayer,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Vec<Arc<RenderPass>> {
+    let reflect = get_rgb(
+        material,
+        "reflect",
+        [0.5, 0.5, 0.5],
+        resource_manager,
+        render_resource_manager,
+    );
+    let transmit = get_rgb(
+        material,
+        "transmit",
+        [0.5, 0.5, 0.5],
+        resource_manager,
+        render_resource_manager,
+    );
+    let scale_by_reflect = |value: RenderUniformValue| match value {
+        RenderUniformValue::Vec4(c) => {
+            RenderUniformValue::Vec4([c[0] * reflect[0], c[1] * reflect[1], c[2] * reflect[2], 1.0])
+        }
+        value => value,
+    };
+    let kd = scale_by_reflect(get_color_uniform(
+        material,
+        "Kd",
+        [0.25, 0.25, 0.25, 1.0],
+        resource_manager,
+        render_resource_manager,
+    ));
+    let kr = scale_by_reflect(get_color_uniform(
+        material,
+        "Ks",
+        [0.25, 0.25, 0.25, 1.0],
+        resource_manager,
+        render_resource_manager,
+    ));
+    // light transmitted through the sheet shows up past the terminator
+    let max_transmit = transmit[0].max(transmit[1]).max(transmit[2]);
+    let scatter = if max_transmit > 0.0 {
+        transmit.map(|t| t / max_transmit)
+    } else {
+        [1.0, 1.0, 1.0]
+    };
+    let roughness = get_roughness_uniform(
+        material,
+        "roughness",
+        0.1,
+        resource_manager,
+        render_resource_manager,
+    );
+    let render_pass = create_wrap_render_pass(
+        device,
+        queue,
+        kd,
+        kr,
+        scatter,
+        roughness,
+        max_transmit,
+        1.5,
+        layer,
+        render_resource_manager,
+    );
+    return vec![render_pass];
+}
+
+/// Hair color from the absorption coefficient, inverting pbrt's SigmaAFromReflectance.
+fn get_hair_color_from_sigma_a(sigma_a: [f32; 3], beta_n: f32) -> [f32; 3] {
+    let b = beta_n;
+    let denominator = 5.969 - 0.215 * b + 2.532 * b.powi(2) - 10.73 * b.powi(3)
+        + 5.574 * b.powi(4)
+        + 0.245 * b.powi(5);
+    return sigma_a.map(|s| f32::exp(-f32::sqrt(s.max(0.0)) * denominator));
+}
+
+fn create_hair_render_passes(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    material: &Material,
+    layer: &SurfaceLayer,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Vec<Arc<RenderPass>> {
+    let beta_n = get_float(&material.props, "beta_n").unwrap_or(0.3);
+    let get = |key: &str| {
+        get_color(
+            &material.props,
+            key,
+            resource_manager,
+            render_resource_manager.color_space,
+        )
+        .map(|c| [c[0], c[1], c[2]])
+    };
+    // same precedence as pbrt: sigma_a, then color, then melanin concentrations
+    let color = if let Some(sigma_a) = get("sigma_a") {
+        get_hair_color_from_sigma_a(sigma_a, beta_n)
+    } else if let Some(color) = get("color") {
+        color
+    } else {
+        let eumelanin = get_float(&material.props, "eumelanin").unwrap_or(1.3);
+        let pheomelanin = get_float(&material.props, "pheomelanin").unwrap_or(0.0);
+        let sigma_a = [
+            eumelanin * 0.419 + pheomelanin * 0.187,
+            eumelanin * 0.697 + pheomelanin * 0.4,
+            eumelanin * 1.37 + pheomelanin * 1.05,
+        ];
+        get_hair_color_from_sigma_a(sigma_a, beta_n)
+    };
+    let kd = RenderUniformValue::Vec4([color[0], color[1], color[2], 1.0]);
+    let kr = RenderUniformValue::Vec4([1.0, 1.0, 1.0, 1.0]);
+    let beta_m = get_float(&material.props, "beta_m").unwrap_or(0.3);
+    let eta = get_float(&material.props, "eta").unwrap_or(1.55);
+    let render_pass = create_wrap_render_pass(
+        device,
+        queue,
+        kd,
+        kr,
+        color,
+        (RenderUniformValue::Float(beta_m), None),
+        0.5,
+        eta,
+        layer,
+        render_resource_manager,
+    );
+    return vec![render_pass];
+}
+
+fn create_fourier_render_passes(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    material: &Material,
+    layer: &SurfaceLayer,
+    render_resource_manager: &mut RenderResourceManager,
+) -> Vec<Arc<RenderPass>> {
+    // the measured BSDF is not evaluated in the viewport, show a neutral glossy surface
+    if let Some(bsdffile) = get_string(&material.props, "bsdffile") {
+        log::debug!(
+            "Fourier BSDF '{}' is shown as a neutral glossy surface",
+            bsdffile
+        );
+    }
+    let uniform_values = vec![
+        (
+            "kd".to_string(),
+            RenderUniformValue::Vec4([0.5, 0.5, 0.5, 1.0]),
+        ),
+        (
+            "ks".to_string(),
+            RenderUniformValue::Vec4([0.5, 0.5, 0.5, 1.0]),
+        ),
+        (
+            "roughness".to_string(),
+            RenderUniformValue::Float(roughness_to_alpha(0.1)),
+        ),
+    ];
+    let render_pass = create_surface_pass(
         device,
         queue,
-        "lambertian_none",
+        "lambertian_ggx",
         RenderCategory::Opaque,
-        &uniform_values,
+        uniform_values,
+        &[],
+        layer,
         render_resource_manager,
     );
     return vec![render_pass];
 }
 
-fn create_plastic_render_passes(
+fn create_disney_render_passes(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     material: &Material,
+    layer: &SurfaceLayer,
     resource_manager: &ResourceManager,
     render_resource_manager: &mut RenderResourceManager,
 ) -> Vec<Arc<RenderPass>> {
-    let keys = ["Kd", "Ks"];
-    let mut uniform_values = vec![];
-    for key in keys {
-        if let Some(color) = get_color(&material.props, key, resource_manager) {
-            uniform_values.push((key.to_lowercase(), RenderUniformValue::Vec4(color)));
-        } else if let Some(texture) = get_texture(
-            &material.props,
-            key,
-            resource_manager,
-            render_resource_manager,
-        ) {
-            let texture = render_resource_manager
-                .get_texture(texture.get_id())
-                .unwrap();
-            uniform_values.push((
-                key.to_lowercase(),
-                RenderUniformValue::Texture(texture.clone()),
-            ));
-        } else {
-            uniform_values.push((
-                key.to_lowercase(),
-                RenderUniformValue::Vec4([1.0, 1.0, 1.0, 1.0]),
-            ));
-        }
-    }
-    let mut roughness = get_float(&material.props, "roughness").unwrap_or(0.1);
-    let remaproughness = get_bool(&material.props, "remaproughness").unwrap_or(true);
-    if remaproughness {
-        roughness = roughness_to_alpha(roughness);
-    }
-    uniform_values.push((
-        "roughness".to_string(),
-        RenderUniformValue::Float(roughness),
-    ));
-    //println!("{}: Plastic Shader Type: {}", material.get_name(),shader_type);
-    let render_pass = create_render_pass(
+    let float = |key: &str, default: f32| get_float(&material.props, key).unwrap_or(default);
+    let color = get_color_uniform(
+        material,
+        "color",
+        [0.5, 0.5, 0.5, 1.0],
+        resource_manager,
+        render_resource_manager,
+    );
+    let params1 = [
+        float("eta", 1.5),
+        float("speculartint", 0.0),
+        float("anisotropic", 0.0),
+        float("sheen", 0.0),
+    ];
+    let params2 = [
+        float("sheentint", 0.5),
+        float("clearcoat", 0.0),
+        float("clearcoatgloss", 1.0),
+        float("spectrans", 0.0),
+    ];
+    let metallic = get_float_uniform(
+        material,
+        "metallic",
+        0.0,
+        resource_manager,
+        render_resource_manager,
+    );
+    let roughness = get_float_uniform(
+        material,
+        "roughness",
+        0.5,
+        resource_manager,
+        render_resource_manager,
+    );
+    let uniform_values = vec![
+        ("color".to_string(), color),
+        ("params1".to_string(), RenderUniformValue::Vec4(params1)),
+        ("params2".to_string(), RenderUniformValue::Vec4(params2)),
+        ("metallic".to_string(), metallic),
+        ("roughness".to_string(), roughness),
+    ];
+    let render_pass = create_surface_pass(
         device,
         queue,
-        "lambertian_ggx",
+        "disney",
         RenderCategory::Opaque,
-        &uniform_values,
+        uniform_values,
+        &[],
+        layer,
         render_resource_manager,
     );
     return vec![render_pass];
 }
 
-fn create_uber_render_passes(
-    device: &wgpu::Device,
-    queue: &wgpu::Queue,
+fn find_sub_material(
     material: &Material,
+    key: &str,
     resource_manager: &ResourceManager,
-    render_resource_manager: &mut RenderResourceManager,
-) -> Vec<Arc<RenderPass>> {
-    return create_plastic_render_passes(
-        device,
-        queue,
-        material,
-        resource_manager,
-        render_resource_manager,
-    );
+) -> Option<Arc<RwLock<Material>>> {
+    let name = get_string(&material.props, key)?;
+    let sub_material = resource_manager.find_material_by_name(&name);
+    if sub_material.is_none() {
+        log::warn!(
+            "Mix material '{}' refers to unknown material '{}'",
+            material.get_name(),
+            name
+        );
+    }
+    return sub_material;
 }
 
-fn create_substrate_render_passes(
+fn create_mix_render_passes(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     material: &Material,
     resource_manager: &ResourceManager,
     render_resource_manager: &mut RenderResourceManager,
 ) -> Vec<Arc<RenderPass>> {
-    return create_plastic_render_passes(
-        device,
-        queue,
+    let amount = get_color_uniform(
         material,
+        "amount",
+        [0.5, 0.5, 0.5, 1.0],
         resource_manager,
         render_resource_manager,
     );
+    // pbrt scales namedmaterial1 by amount and namedmaterial2 by 1 - amount
+    let layers = [("namedmaterial2", false), ("namedmaterial1", true)];
+    let mut passes = vec![];
+    for (key, overlay) in layers {
+        let Some(sub_material) = find_sub_material(material, key, resource_manager) else {
+            continue;
+        };
+        let sub_material = sub_material.read().unwrap();
+        let layer = MixLayer {
+            amount: amount.clone(),
+            overlay,
+        };
+        let new_passes = create_material_passes(
+            device,
+            queue,
+            &sub_material,
+            Some(&layer),
+            resource_manager,
+            render_resource_manager,
+        );
+        passes.extend(new_passes);
+    }
+    if passes.is_empty() {
+        return create_basic_render_passes(
+            device,
+            queue,
+            material,
+            resource_manager,
+            render_resource_manager,
+        );
+    }
+    return passes;
 }
 
-fn create_glass_render_passes(
+fn create_material_passes(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     material: &Material,
+    mix: Option<&MixLayer>,
     resource_manager: &ResourceManager,
     render_resource_manager: &mut RenderResourceManager,
 ) -> Vec<Arc<RenderPass>> {
-    let diffuse_color =
-        get_color(&material.props, "Kt", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
-    let specular_color =
-        get_color(&material.props, "Kr", resource_manager).unwrap_or([1.0, 1.0, 1.0, 1.0]);
-    let mut roughness = get_float(&material.props, "uroughness").unwrap_or(0.1); //use uroughness
-    let remaproughness = get_bool(&material.props, "remaproughness").unwrap_or(true);
-    if remaproughness {
-        roughness = roughness_to_alpha(roughness);
-    }
-
-    let mut passes = vec![];
-    {
-        let uniform_values = vec![("kt".to_string(), RenderUniformValue::Vec4(diffuse_color))];
-        let render_pass = create_render_pass(
+    let normal_map = get_normal_map(
+        &material.props,
+        "bumpmap",
+        resource_manager,
+        render_resource_manager,
+    );
+    let layer = &SurfaceLayer { mix, normal_map };
+    let material_type = material.get_type();
+    match material_type.as_str() {
+        "matte" => create_matte_render_passes(
             device,
             queue,
-            "transmission_none",
-            RenderCategory::Transparent,
-            &uniform_values,
+            material,
+            layer,
+            resource_manager,
             render_resource_manager,
-        );
-        passes.push(render_pass);
-    }
-    {
-        let uniform_values = vec![
-            ("kr".to_string(), RenderUniformValue::Vec4(specular_color)),
-            (
-                "roughness".to_string(),
-                RenderUniformValue::Float(roughness),
-            ),
-        ];
-        let render_pass = create_render_pass(
+        ),
+        "plastic" => create_plastic_render_passes(
             device,
             queue,
-            "none_ggx",
-            RenderCategory::TransparentSpecular,
-            &uniform_values,
+            material,
+            layer,
+            resource_manager,
             render_resource_manager,
-        );
-        passes.push(render_pass);
+        ),
+        "uber" => create_uber_render_passes(
+            device,
+            queue,
+            material,
+            layer,
+            resource_manager,
+            render_resource_manager,
+        ),
+        "substrate" => create_substrate_render_passes(
+            device,
+            queue,
+            material,
+            layer,
+            resource_manager,
+            render_resource_manager,
+        ),
+        "metal" => create_metal_render_passes(
+            device,
+            queue,
+            material,
+            layer,
+            resource_manager,
+            render_resource_manager,
+        ),
+        "mirror" => create_mirror_render_passes(
+            device,
+            queue,
+            material,
+            layer,
+            resource_manager,
+            render_resource_manager,
+        ),
+        "glass" => create_glass_render_passes(
+            device,
+            queue,
+            material,
+            layer,
+            resource_manager,
+            render_resource_manager,
+        ),
+        "subsurface" => create_subsurface_render_passes(
+            device,
+            queue,
+            material,
+            layer,
+            resource_manager,
+            render_resource_manager,
+        ),
+        "kdsubsurface" => create_kdsubsurface_render_passes(
+            device,
+            queue,
+            material,
+            layer,
+            resource_manager,
+            render_resource_manager,
+        ),
+        "translucent" => create_translucent_render_passes(
+            device,
+            queue,
+            material,
+            layer,
+            resource_manager,
+            render_resource_manager,
+        ),
+        "hair" => create_hair_render_passes(
+            device,
+            queue,
+            material,
+            layer,
+            resource_manager,
+            render_resource_manager,
+        ),
+        "fourier" => {
+            create_fourier_render_passes(device, queue, material, layer, render_resource_manager)
+        }
+        "disney" => create_disney_render_passes(
+            device,
+            queue,
+            material,
+            layer,
+            resource_manager,
+            render_resource_manager,
+        ),
+        // a mix inside a mix would need the product of both amounts
+        "mix" if mix.is_none() => create_mix_render_passes(
+            device,
+            queue,
+            material,
+            resource_manager,
+            render_resource_manager,
+        ),
+        _ => create_basic_render_passes(
+            device,
+            queue,
+            material,
+            resource_manager,
+            render_resource_manager,
+        ),
     }
-    return passes;
+}
+
+/// The edition a cached render material is checked against.
+/// A mix material also changes when one of its sub-materials does.
+fn get_material_edition(material: &Material, resource_manager: &ResourceManager) -> String {
+    let mut edition = material.get_edition();
+    if material.get_type() == "mix" {
+        for key in ["namedmaterial1", "namedmaterial2"] {
+            if let Some(name) = get_string(&material.props, key) {
+                if let Some(sub_material) = resource_manager.find_material_by_name(&name) {
+                    edition.push(':');
+                    edition.push_str(&sub_material.read().unwrap().get_edition());
+                }
+            }
+        }
+    }
+    return edition;
 }
 
 fn create_render_material_from_material(
@@ -314,71 +1306,15 @@ fn create_render_material_from_material(
 ) -> RenderMaterial {
     let material_type = material.get_type();
     let id = material.get_id();
-    let edition = material.get_edition();
-    let mut passes = vec![];
-    match material_type.as_str() {
-        "matte" => {
-            let new_passes = create_matte_render_passes(
-                device,
-                queue,
-                material,
-                resource_manager,
-                render_resource_manager,
-            );
-            passes.extend(new_passes);
-        }
-        "plastic" => {
-            let new_passes = create_plastic_render_passes(
-                device,
-                queue,
-                material,
-                resource_manager,
-                render_resource_manager,
-            );
-            passes.extend(new_passes);
-        }
-        "uber" => {
-            let new_passes = create_uber_render_passes(
-                device,
-                queue,
-                material,
-                resource_manager,
-                render_resource_manager,
-            );
-            passes.extend(new_passes);
-        }
-        "substrate" => {
-            let new_passes = create_substrate_render_passes(
-                device,
-                queue,
-                material,
-                resource_manager,
-                render_resource_manager,
-            );
-            passes.extend(new_passes);
-        }
-
-        "glass" => {
-            let new_passes = create_glass_render_passes(
-                device,
-                queue,
-                material,
-                resource_manager,
-                render_resource_manager,
-            );
-            passes.extend(new_passes);
-        }
-        _ => {
-            let new_passes = create_basic_render_passes(
-                device,
-                queue,
-                material,
-                resource_manager,
-                render_resource_manager,
-            );
-            passes.extend(new_passes);
-        }
-    }
+    let edition = get_material_edition(material, resource_manager);
+    let passes = create_material_passes(
+        device,
+        queue,
+        material,
+        None,
+        resource_manager,
+        render_resource_manager,
+    );
     let render_material = RenderMaterial {
         id,
         edition,
@@ -399,7 +1335,12 @@ fn create_render_material_from_light(
     {
         let keys = ["L", "scale"];
         for key in keys {
-            if let Some(color) = get_color(light.as_property_map(), key, resource_manager) {
+            if let Some(color) = get_color(
+                light.as_property_map(),
+                key,
+                resource_manager,
+                render_resource_manager.color_space,
+            ) {
                 uniform_values.push((key.to_string(), RenderUniformValue::Vec4(color)));
             } else {
                 uniform_values.push((
@@ -469,7 +1410,7 @@ pub fn get_render_material(
         let material = material.read().unwrap();
         let material_id = material.get_id();
         if let Some(mat) = render_resource_manager.get_material(material_id) {
-            if mat.edition == material.get_edition() {
+            if mat.edition == get_material_edition(&material, resource_manager) {
                 return Some(mat.clone());
             }
         }
@@ -494,6 +1435,49 @@ pub fn get_render_material(
     return None;
 }
 
+fn get_debug_surface(
+    node: &Arc<RwLock<Node>>,
+    resource_manager: &ResourceManager,
+    render_resource_manager: &mut RenderResourceManager,
+) -> DebugSurface {
+    let node = node.read().unwrap();
+    let mut surface = DebugSurface {
+        material_id: None,
+        albedo: [1.0, 1.0, 1.0, 1.0],
+        albedo_texture: None,
+        one_sided: false,
+    };
+    if let Some(component) = node.get_component::<ShapeComponent>() {
+        let shape = component.get_shape();
+        let shape = shape.read().unwrap();
+        surface.one_sided = !get_bool(shape.as_property_map(), "twosided").unwrap_or(true);
+    }
+    if let Some(light) = node.get_component::<LightComponent>() {
+        let light = light.get_light();
+        let light = light.read().unwrap();
+        surface.material_id = Some(light.get_id());
+        surface.one_sided |= !get_bool(light.as_property_map(), "twosided").unwrap_or(false);
+    } else if let Some(component) = node.get_component::<MaterialComponent>() {
+        let material = component.get_material();
+        let material = material.read().unwrap();
+        surface.material_id = Some(material.get_id());
+        if let Some(key) = get_base_diffuse_key(&material) {
+            match get_color_uniform(
+                &material,
+                &key,
+                [1.0, 1.0, 1.0, 1.0],
+                resource_manager,
+                render_resource_manager,
+            ) {
+                RenderUniformValue::Texture(texture) => surface.albedo_texture = Some(texture),
+                RenderUniformValue::Vec4(color) => surface.albedo = color,
+                _ => {}
+            }
+        }
+    }
+    return surface;
+}
+
 pub fn get_render_mesh_item(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
@@ -515,9 +1499,19 @@ pub fn get_render_mesh_item(
         } else {
             None
         };
+        let debug_surface = if let RenderMode::Debug(_) = mode {
+            Some(get_debug_surface(
+                &item.node,
+                resource_manager,
+                render_resource_manager,
+            ))
+        } else {
+            None
+        };
         let render_item = MeshRenderItem {
             mesh,
             material,
+            debug_surface,
             matrix,
         };
         return Some(RenderItem::Mesh(render_item));
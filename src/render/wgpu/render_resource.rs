@@ -1,9 +1,13 @@
+use super::environment::PendingEnvironment;
+use super::environment::RenderEnvironment;
 use super::light::RenderLight;
 use super::lines::RenderLines;
 use super::material::RenderMaterial;
 use super::mesh::RenderMesh;
 use super::shader::RenderShader;
+use super::shader_permutation::ShaderPermutationKey;
 use super::texture::RenderTexture;
+use crate::conversion::color_space::ColorSpace;
 use crate::model::scene::Component;
 
 use std::collections::HashMap;
@@ -18,8 +22,13 @@ pub struct RenderResourceManager {
     pub lights: HashMap<Uuid, Arc<RenderLight>>, // Assuming lights are also stored as RenderLines
     pub lines: HashMap<Uuid, Arc<RenderLines>>,
     pub shaders: HashMap<Uuid, Arc<RenderShader>>,
+    pub shader_permutations: HashMap<ShaderPermutationKey, Arc<RenderShader>>,
     pub materials: HashMap<Uuid, Arc<RenderMaterial>>,
     pub textures: HashMap<Uuid, Arc<RenderTexture>>,
+    pub normal_maps: HashMap<Uuid, Arc<RenderTexture>>, // key is the bump texture id
+    pub environments: HashMap<Uuid, Arc<RenderEnvironment>>, // key is the texture id
+    pub pending_environments: HashMap<Uuid, PendingEnvironment>, // key is the texture id
+    pub color_space: ColorSpace, // working color space the colors were resolved in
 }
 
 impl RenderResourceManager {
@@ -29,10 +38,29 @@ impl RenderResourceManager {
             lights: HashMap::new(),
             lines: HashMap::new(),
             shaders: HashMap::new(),
+            shader_permutations: HashMap::new(),
             materials: HashMap::new(),
             textures: HashMap::new(),
+            normal_maps: HashMap::new(),
+            environments: HashMap::new(),
+            pending_environments: HashMap::new(),
+            color_space: ColorSpace::default(),
         }
     }
+
+    /// Sets the scene working color space. Resources holding resolved colors are dropped when it changes.
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        if self.color_space == color_space {
+            return;
+        }
+        self.color_space = color_space;
+        self.lights.clear();
+        self.materials.clear();
+        self.textures.clear();
+        self.normal_maps.clear();
+        self.environments.clear();
+        self.pending_environments.clear();
+    }
     pub fn add_mesh(&mut self, mesh: &Arc<RenderMesh>) {
         let id = mesh.get_id();
         self.meshes.insert(id, mesh.clone());
@@ -85,6 +113,18 @@ impl RenderResourceManager {
         self.shaders.remove(&id);
     }
 
+    pub fn add_shader_permutation(
+        &mut self,
+        key: &ShaderPermutationKey,
+        shader: &Arc<RenderShader>,
+    ) {
+        self.shader_permutations.insert(key.clone(), shader.clone());
+    }
+
+    pub fn get_shader_permutation(&self, key: &ShaderPermutationKey) -> Option<&Arc<RenderShader>> {
+        self.shader_permutations.get(key)
+    }
+
     pub fn add_material(&mut self, material: &Arc<RenderMaterial>) {
         let id = material.get_id();
         self.materials.insert(id, material.clone());
@@ -110,6 +150,36 @@ impl RenderResourceManager {
     pub fn remove_texture(&mut self, id: Uuid) {
         self.textures.remove(&id);
     }
+
+    pub fn add_normal_map(&mut self, normal_map: &Arc<RenderTexture>) {
+        let id = normal_map.get_id();
+        self.normal_maps.insert(id, normal_map.clone());
+    }
+
+    pub fn get_normal_map(&self, id: Uuid) -> Option<&Arc<RenderTexture>> {
+        self.normal_maps.get(&id)
+    }
+
+    pub fn add_environment(&mut self, environment: &Arc<RenderEnvironment>) {
+        let id = environment.get_id();
+        self.environments.insert(id, environment.clone());
+    }
+
+    pub fn get_environment(&self, id: Uuid) -> Option<&Arc<RenderEnvironment>> {
+        self.environments.get(&id)
+    }
+
+    pub fn add_pending_environment(&mut self, pending: PendingEnvironment) {
+        self.pending_environments.insert(pending.id, pending);
+    }
+
+    pub fn get_pending_environment(&self, id: Uuid) -> Option<&PendingEnvironment> {
+        self.pending_environments.get(&id)
+    }
+
+    pub fn remove_pending_environment(&mut self, id: Uuid) {
+        self.pending_environments.remove(&id);
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,203 @@
+use super::material::RenderUniformValue;
+use crate::preprocessor::Preprocessor;
+use crate::preprocessor::PreprocessorError;
+use crate::preprocessor::PreprocessorResult;
+use crate::preprocessor::SourceLocation;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use eframe::wgpu::naga;
+use uuid::Uuid;
+
+/// Identifies one compiled variant of a surface shader family.
+/// `family` names the uber-source (`lambertian_ggx`, `none_ggx`, ...) and
+/// `defines` are the symbols it is run through the preprocessor with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShaderPermutationKey {
+    pub family: String,
+    pub defines: BTreeMap<String, String>,
+}
+
+impl ShaderPermutationKey {
+    /// Builds the key for a render pass. Every texture uniform `xx` defines
+    /// `XX_TEXTURE` with the bindings the material bind group will put it at.
+    pub fn new(
+        family: &str,
+        uniform_values: &[(String, RenderUniformValue)],
+        defines: &[(&str, &str)],
+    ) -> Self {
+        let mut map = BTreeMap::new();
+        // same layout as LightingMeshRenderer::create_material_bind_group
+        let material_binding_offset = 1;
+        let mut texture_index = 0;
+        for (name, value) in uniform_values.iter() {
+            if let RenderUniformValue::Texture(_) = value {
+                let name = name.to_uppercase();
+                let binding = material_binding_offset + 2 * texture_index;
+                map.insert(format!("{}_TEXTURE", name), "1".to_string());
+                map.insert(format!("{}_TEXTURE_BINDING", name), binding.to_string());
+                map.insert(
+                    format!("{}_SAMPLER_BINDING", name),
+                    (binding + 1).to_string(),
+                );
+                texture_index += 1;
+            }
+        }
+        for (name, value) in defines.iter() {
+            map.insert(name.to_string(), value.to_string());
+        }
+        Self {
+            family: family.to_string(),
+            defines: map,
+        }
+    }
+
+    pub fn get_id(&self) -> Uuid {
+        Uuid::new_v3(&Uuid::NAMESPACE_OID, self.to_string().as_bytes())
+    }
+
+    /// File name of the uber-source in the `surface` shader directory.
+    pub fn get_source_name(&self) -> String {
+        match self.family.as_str() {
+            "arealight" => "arealight_diffuse.wgsl".to_string(),
+            "basic" => "basic_material.wgsl".to_string(),
+            family => format!("{}.wgsl", family),
+        }
+    }
+}
+
+impl fmt::Display for ShaderPermutationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let defines: Vec<String> = self
+            .defines
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        write!(f, "{}[{}]", self.family, defines.join(","))
+    }
+}
+
+/// Runs the family's uber-source through the preprocessor.
+/// Families without a source of their own fall back to `basic_material.wgsl`.
+pub fn preprocess_shader_source(
+    key: &ShaderPermutationKey,
+    shader_dir: &Path,
+) -> PreprocessorResult<String> {
+    preprocess_shader(key, shader_dir).map(|(source, _)| source)
+}
+
+/// Same as `preprocess_shader_source`, also returning where each output line came from.
+pub fn preprocess_shader(
+    key: &ShaderPermutationKey,
+    shader_dir: &Path,
+) -> PreprocessorResult<(String, Vec<SourceLocation>)> {
+    let mut path = shader_dir.join(key.get_source_name());
+    if !path.exists() {
+        path = shader_dir.join("basic_material.wgsl");
+    }
+    if !path.exists() {
+        return Err(PreprocessorError::IoError {
+            path: path.to_string_lossy().to_string(),
+            message: "Shader source not found".to_string(),
+        });
+    }
+    let mut preprocessor = Preprocessor::with_base_path(shader_dir);
+    for (name, value) in key.defines.iter() {
+        preprocessor.define(name, value);
+    }
+    let source = preprocessor.process_file(&path)?;
+    Ok((source, preprocessor.source_map().to_vec()))
+}
+
+/// Compiles the preprocessed source with naga before it reaches the device,
+/// so an error can be reported at its line in the original shader files.
+pub fn validate_shader_source(source: &str, source_map: &[SourceLocation]) -> Result<(), String> {
+    let locate = |line: Option<u32>| -> String {
+        line.and_then(|line| source_map.get((line as usize).checked_sub(1)?))
+            .map(|location| format!("{}:{}", location.file, location.line))
+            .unwrap_or_else(|| "<unknown>".to_string())
+    };
+    let module = naga::front::wgsl::parse_str(source).map_err(|e| {
+        let line = e.location(source).map(|l| l.line_number);
+        format!("{}: {}", locate(line), e.message())
+    })?;
+    let mut validator = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    );
+    validator.validate(&module).map_err(|e| {
+        let line = e.location(source).map(|l| l.line_number);
+        format!("{}: {}", locate(line), e.as_inner())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn get_shader_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("assets")
+            .join("shaders")
+            .join("surface")
+    }
+
+    #[test]
+    fn test_preprocess_shader_source_001() {
+        // a textured kd gets its bindings, the untextured ks stays a uniform
+        let key = ShaderPermutationKey {
+            family: "lambertian_ggx".to_string(),
+            defines: BTreeMap::from([
+                ("KD_TEXTURE".to_string(), "1".to_string()),
+                ("KD_TEXTURE_BINDING".to_string(), "1".to_string()),
+                ("KD_SAMPLER_BINDING".to_string(), "2".to_string()),
+            ]),
+        };
+        let source = preprocess_shader_source(&key, &get_shader_dir()).unwrap();
+        assert!(source.contains("@binding(1)\nvar kd_texture"));
+        assert!(source.contains("@binding(2)\nvar kd_sampler"));
+        assert!(!source.contains("ks_texture"));
+        assert!(source.contains("return material_uniforms.ks.rgb;"));
+        assert!(
+            !source
+                .lines()
+                .any(|line| line.trim_start().starts_with('#'))
+        );
+    }
+
+    #[test]
+    fn test_preprocess_shader_source_002() {
+        // diffuse-only families drop the specular terms
+        let key = ShaderPermutationKey::new("lambertian_none", &[], &[]);
+        let source = preprocess_shader_source(&key, &get_shader_dir()).unwrap();
+        assert!(source.contains("irradiance_texture, light_sampler"));
+        assert!(!source.contains("env_brdf_approx(roughness, NdotV)"));
+    }
+
+    #[test]
+    fn test_validate_shader_source() {
+        let key = ShaderPermutationKey::new("none_ggx", &[], &[]);
+        let (source, source_map) = preprocess_shader(&key, &get_shader_dir()).unwrap();
+        assert_eq!(source.lines().count(), source_map.len());
+        assert!(validate_shader_source(&source, &source_map).is_ok());
+
+        // an error in an included file is reported against that file
+        let index = source
+            .lines()
+            .position(|line| line.contains("fn lambertian_reflection"))
+            .unwrap();
+        let broken: Vec<&str> = source
+            .lines()
+            .enumerate()
+            .map(|(i, line)| if i == index { "fn broken( {" } else { line })
+            .collect();
+        let message = validate_shader_source(&broken.join("\n"), &source_map).unwrap_err();
+        let location = &source_map[index];
+        assert!(location.file.ends_with("surface_common.wgsl"));
+        assert!(message.starts_with(&format!("{}:{}", location.file, location.line)));
+    }
+}
@@ -0,0 +1,821 @@
+use super::light::RenderLight;
+use super::material::RenderCategory;
+use super::mesh::RenderVertex;
+use super::render_item::RenderItem;
+use crate::render::viewport_settings::MAX_SHADOW_CASCADES;
+use crate::render::viewport_settings::ShadowSettings;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use eframe::wgpu;
+use wgpu::util::align_to;
+
+use bytemuck::{Pod, Zeroable};
+
+pub const MAX_SHADOW_LAYERS: usize = 48; // Enough for 8 point lights with 6 faces each
+const SHADOW_DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+const SHADOW_TEXEL_BYTES: u64 = 4; // Of SHADOW_DEPTH_FORMAT
+const CASCADE_SPLIT_LAMBDA: f32 = 0.75; // Blend of logarithmic and uniform cascade splits
+const MAX_SPOT_FOV: f32 = 170.0; // Degrees, wider spot lights are clipped
+const SHADOW_MEMORY_BUDGET: u64 = 256 << 20; // Bytes of the whole depth texture array
+const MIN_SHADOW_MAP_SIZE: u32 = 256;
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod, Zeroable)]
+pub struct ShadowUniforms {
+    cascade_splits: [f32; 4], // View depth where each cascade ends
+    params: [f32; 4],         // texel size, pcf radius, depth bias, normal bias
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod, Zeroable)]
+pub struct ShadowView {
+    world_to_clip: [[f32; 4]; 4], // 4 * 4 * 4 = 64
+    params: [f32; 4], // world size of a texel (at distance 1 for perspective views), perspective flag
+}
+
+/// Layers of the shadow map array assigned to one light.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowAssignment {
+    pub first_layer: i32,
+    pub layer_count: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ShadowCaster {
+    Directional(glam::Vec3),
+    Spot(glam::Vec3, glam::Vec3, f32),
+    Point(glam::Vec3),
+}
+
+impl ShadowCaster {
+    fn get_layer_count(&self, cascade_count: usize) -> usize {
+        match self {
+            ShadowCaster::Directional(_) => cascade_count,
+            ShadowCaster::Spot(..) => 1,
+            ShadowCaster::Point(_) => 6,
+        }
+    }
+}
+
+/// Bounding sphere of the scene geometry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneBounds {
+    pub center: glam::Vec3,
+    pub radius: f32,
+}
+
+pub fn get_scene_bounds(mesh_items: &[Arc<RenderItem>]) -> Option<SceneBounds> {
+    let mut min = glam::Vec3::splat(f32::MAX);
+    let mut max = glam::Vec3::splat(f32::MIN);
+    for item in mesh_items.iter() {
+        if let RenderItem::Mesh(mesh_item) = item.as_ref() {
+            let [bmin, bmax] = mesh_item.mesh.bounds;
+            for i in 0..8 {
+                let corner = glam::vec3(
+                    if i & 1 == 0 { bmin[0] } else { bmax[0] },
+                    if i & 2 == 0 { bmin[1] } else { bmax[1] },
+                    if i & 4 == 0 { bmin[2] } else { bmax[2] },
+                );
+                let p = mesh_item.matrix.transform_point3(corner);
+                min = min.min(p);
+                max = max.max(p);
+            }
+        }
+    }
+    if min.x > max.x {
+        return None;
+    }
+    let center = (min + max) * 0.5;
+    let radius = f32::max((max - min).length() * 0.5, 1e-3);
+    return Some(SceneBounds { center, radius });
+}
+
+/// View depths where the cascades end, blending logarithmic and uniform splits.
+pub fn get_cascade_splits(near: f32, far: f32, count: usize, lambda: f32) -> Vec<f32> {
+    let mut splits = Vec::with_capacity(count);
+    for i in 1..=count {
+        let t = i as f32 / count as f32;
+        let log_split = near * f32::powf(far / near, t);
+        let uniform_split = near + (far - near) * t;
+        splits.push(lambda * log_split + (1.0 - lambda) * uniform_split);
+    }
+    return splits;
+}
+
+fn get_up_vector(direction: glam::Vec3) -> glam::Vec3 {
+    if direction.normalize().y.abs() < 0.999 {
+        return glam::Vec3::Y;
+    }
+    return glam::Vec3::X;
+}
+
+fn create_shadow_view(world_to_clip: glam::Mat4, texel: f32, perspective: bool) -> ShadowView {
+    ShadowView {
+        world_to_clip: world_to_clip.to_cols_array_2d(),
+        params: [texel, if perspective { 1.0 } else { 0.0 }, 0.0, 0.0],
+    }
+}
+
+/// Distance from the camera to the near plane and far plane.
+fn get_camera_depth_range(camera_to_clip: &glam::Mat4) -> (f32, f32) {
+    let clip_to_camera = camera_to_clip.inverse();
+    let near = clip_to_camera.project_point3(glam::vec3(0.0, 0.0, 0.0));
+    let far = clip_to_camera.project_point3(glam::vec3(0.0, 0.0, 1.0));
+    return (-near.z, -far.z);
+}
+
+/// One orthographic view per cascade, fitted to the bounding sphere of the frustum slice.
+pub fn get_directional_views(
+    direction: glam::Vec3,
+    world_to_camera: &glam::Mat4,
+    camera_to_clip: &glam::Mat4,
+    scene: &SceneBounds,
+    cascade_count: usize,
+    map_size: u32,
+) -> (Vec<ShadowView>, Vec<f32>) {
+    let direction = direction.normalize();
+    let up = get_up_vector(direction);
+    let camera_to_world = world_to_camera.inverse();
+    let clip_to_camera = camera_to_clip.inverse();
+    let camera_position = camera_to_world.transform_point3(glam::Vec3::ZERO);
+
+    // the cascades only need to reach the far side of the scene
+    let (near, far) = get_camera_depth_range(camera_to_clip);
+    let scene_far = camera_position.distance(scene.center) + scene.radius;
+    let far = f32::max(f32::min(far, scene_far), near * 2.0);
+    let splits = get_cascade_splits(near, far, cascade_count, CASCADE_SPLIT_LAMBDA);
+
+    // corner rays of the frustum at view depth 1
+    let mut rays = [glam::Vec3::ZERO; 4];
+    for (i, (x, y)) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+        .iter()
+        .enumerate()
+    {
+        let p = clip_to_camera.project_point3(glam::vec3(*x, *y, 1.0));
+        rays[i] = p / -p.z;
+    }
+
+    let light_rotation = glam::Mat4::look_to_rh(glam::Vec3::ZERO, direction, up);
+    let mut views = Vec::with_capacity(cascade_count);
+    let mut slice_near = near;
+    for slice_far in splits.iter() {
+        let mut corners = Vec::with_capacity(8);
+        for depth in [slice_near, *slice_far] {
+            for ray in rays.iter() {
+                corners.push(camera_to_world.transform_point3(*ray * depth));
+            }
+        }
+        let center = corners.iter().fold(glam::Vec3::ZERO, |a, c| a + *c) / 8.0;
+        let radius = corners
+            .iter()
+            .fold(0.0f32, |r, c| f32::max(r, c.distance(center)));
+        // a fixed size and texel aligned center keep the shadow edges from swimming
+        let radius = f32::ceil(radius * 16.0) / 16.0;
+        let texel = 2.0 * radius / map_size as f32;
+        let mut light_center = light_rotation.transform_point3(center);
+        light_center.x = f32::floor(light_center.x / texel) * texel;
+        light_center.y = f32::floor(light_center.y / texel) * texel;
+        let center = light_rotation.inverse().transform_point3(light_center);
+
+        // pull the eye back so that every caster of the scene is in front of it
+        let back = scene.radius + center.distance(scene.center);
+        let eye = center - direction * back;
+        let view = glam::Mat4::look_to_rh(eye, direction, up);
+        let projection =
+            glam::Mat4::orthographic_rh(-radius, radius, -radius, radius, 0.0, back + radius);
+        views.push(create_shadow_view(projection * view, texel, false));
+        slice_near = *slice_far;
+    }
+    return (views, splits);
+}
+
+fn get_perspective_range(position: glam::Vec3, scene: &SceneBounds) -> (f32, f32) {
+    let far = f32::max(position.distance(scene.center) + scene.radius, 1e-2);
+    let near = f32::max(far * 1e-4, 1e-3);
+    return (near, far);
+}
+
+pub fn get_spot_view(
+    position: glam::Vec3,
+    direction: glam::Vec3,
+    outer_angle: f32,
+    scene: &SceneBounds,
+    map_size: u32,
+) -> ShadowView {
+    let direction = direction.normalize();
+    let fov = f32::min(2.0 * outer_angle, MAX_SPOT_FOV.to_radians());
+    let (near, far) = get_perspective_range(position, scene);
+    let view = glam::Mat4::look_to_rh(position, direction, get_up_vector(direction));
+    let projection = glam::Mat4::perspective_rh(fov, 1.0, near, far);
+    let texel = 2.0 * f32::tan(fov * 0.5) / map_size as f32;
+    return create_shadow_view(projection * view, texel, true);
+}
+
+/// The six cube faces in the order +X, -X, +Y, -Y, +Z, -Z, as selected in shadows.wgsl.
+pub fn get_point_views(
+    position: glam::Vec3,
+    scene: &SceneBounds,
+    map_size: u32,
+) -> Vec<ShadowView> {
+    let faces = [
+        (glam::Vec3::X, glam::Vec3::NEG_Y),
+        (glam::Vec3::NEG_X, glam::Vec3::NEG_Y),
+        (glam::Vec3::Y, glam::Vec3::Z),
+        (glam::Vec3::NEG_Y, glam::Vec3::NEG_Z),
+        (glam::Vec3::Z, glam::Vec3::NEG_Y),
+        (glam::Vec3::NEG_Z, glam::Vec3::NEG_Y),
+    ];
+    let (near, far) = get_perspective_range(position, scene);
+    let projection = glam::Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, near, far);
+    let texel = 2.0 / map_size as f32;
+    return faces
+        .iter()
+        .map(|(direction, up)| {
+            let view = glam::Mat4::look_to_rh(position, *direction, *up);
+            create_shadow_view(projection * view, texel, true)
+        })
+        .collect();
+}
+
+/// Picks the lights that get shadow maps: directional lights first, then the point and
+/// spot lights closest to the camera, until the budget runs out.
+fn select_shadow_casters(
+    light_items: &[Arc<RenderItem>],
+    camera_position: glam::Vec3,
+    cascade_count: usize,
+    settings: &ShadowSettings,
+) -> Vec<(usize, ShadowCaster)> {
+    let mut directional = Vec::new();
+    let mut local = Vec::new();
+    for (index, item) in light_items.iter().enumerate() {
+        if let RenderItem::Light(light_item) = item.as_ref() {
+            let light = light_item.light.as_ref();
+            if !settings.is_light_enabled(light.get_id()) {
+                continue;
+            }
+            let matrix = light_item.matrix; //local_to_world
+            match light {
+                RenderLight::Directional(light) => {
+                    let direction = matrix.transform_vector3(glam::Vec3::from(light.direction));
+                    directional.push((index, ShadowCaster::Directional(direction)));
+                }
+                RenderLight::Sphere(light) if light.radius <= 0.0 => {
+                    let position = matrix.transform_point3(glam::Vec3::from(light.position));
+                    local.push((index, ShadowCaster::Point(position)));
+                }
+                RenderLight::Disk(light) if light.radius <= 0.0 => {
+                    let position = matrix.transform_point3(glam::Vec3::from(light.position));
+                    let direction = matrix.transform_vector3(glam::Vec3::from(light.direction));
+                    local.push((
+                        index,
+                        ShadowCaster::Spot(position, direction, light.outer_angle),
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+    let get_distance = |caster: &ShadowCaster| match caster {
+        ShadowCaster::Spot(position, _, _) | ShadowCaster::Point(position) => {
+            position.distance(camera_position)
+        }
+        ShadowCaster::Directional(_) => 0.0,
+    };
+    local.sort_by(|a, b| get_distance(&a.1).total_cmp(&get_distance(&b.1)));
+
+    let mut casters = Vec::new();
+    let mut num_layers = 0;
+    for (index, caster) in directional.into_iter().chain(local.into_iter()) {
+        if casters.len() >= settings.max_shadowed_lights {
+            break;
+        }
+        let layer_count = caster.get_layer_count(cascade_count);
+        if num_layers + layer_count > MAX_SHADOW_LAYERS {
+            continue;
+        }
+        num_layers += layer_count;
+        casters.push((index, caster));
+    }
+    return casters;
+}
+
+/// The largest map size up to `map_size` at which `num_layers` layers fit in the memory budget.
+fn get_budget_map_size(map_size: u32, num_layers: usize) -> u32 {
+    let mut map_size = map_size;
+    while map_size > MIN_SHADOW_MAP_SIZE
+        && num_layers as u64 * map_size as u64 * map_size as u64 * SHADOW_TEXEL_BYTES
+            > SHADOW_MEMORY_BUDGET
+    {
+        map_size /= 2;
+    }
+    return map_size.max(1);
+}
+
+/// False when the bounds of a mesh are entirely outside of one clip plane of the view.
+fn is_in_view(
+    world_to_clip: &glam::Mat4,
+    local_to_world: &glam::Mat4,
+    bounds: &[[f32; 3]; 2],
+) -> bool {
+    let local_to_clip = *world_to_clip * *local_to_world;
+    let [bmin, bmax] = bounds;
+    let mut corners = [glam::Vec4::ZERO; 8];
+    for (i, corner) in corners.iter_mut().enumerate() {
+        let p = glam::vec4(
+            if i & 1 == 0 { bmin[0] } else { bmax[0] },
+            if i & 2 == 0 { bmin[1] } else { bmax[1] },
+            if i & 4 == 0 { bmin[2] } else { bmax[2] },
+            1.0,
+        );
+        *corner = local_to_clip * p;
+    }
+    let planes: [fn(&glam::Vec4) -> bool; 6] = [
+        |c| c.x < -c.w,
+        |c| c.x > c.w,
+        |c| c.y < -c.w,
+        |c| c.y > c.w,
+        |c| c.z < 0.0,
+        |c| c.z > c.w,
+    ];
+    return !planes
+        .iter()
+        .any(|is_outside| corners.iter().all(|c| is_outside(c)));
+}
+
+fn casts_shadow(item: &RenderItem) -> bool {
+    if let Some(material) = item.get_material() {
+        return material
+            .passes
+            .iter()
+            .any(|pass| pass.render_category == RenderCategory::Opaque);
+    }
+    return true;
+}
+
+fn create_shadow_texture(
+    device: &wgpu::Device,
+    map_size: u32,
+    num_layers: usize,
+) -> (wgpu::Texture, wgpu::TextureView, Vec<wgpu::TextureView>) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Shadow Map Texture"),
+        size: wgpu::Extent3d {
+            width: map_size,
+            height: map_size,
+            depth_or_array_layers: num_layers as u32,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: SHADOW_DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor {
+        label: Some("Shadow Map Array View"),
+        dimension: Some(wgpu::TextureViewDimension::D2Array),
+        ..Default::default()
+    });
+    let layer_views = (0..num_layers)
+        .map(|layer| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("Shadow Map Layer View"),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_array_layer: layer as u32,
+                array_layer_count: Some(1),
+                ..Default::default()
+            })
+        })
+        .collect();
+    return (texture, view, layer_views);
+}
+
+/// Renders depth maps of the shadowed lights for `LightingMeshRenderer`.
+/// All maps are layers of one depth texture array; a point light uses six layers as cube faces.
+#[derive(Debug, Clone)]
+pub struct ShadowMapRenderer {
+    pipeline: wgpu::RenderPipeline,
+    view_alignment: wgpu::BufferAddress,
+    view_bind_group: wgpu::BindGroup,
+    view_uniform_buffer: wgpu::Buffer,
+    view_buffer: wgpu::Buffer,
+    uniform_buffer: wgpu::Buffer,
+    #[allow(dead_code)]
+    texture: wgpu::Texture,
+    texture_view: wgpu::TextureView,
+    layer_views: Vec<wgpu::TextureView>,
+    sampler: wgpu::Sampler,
+    map_size: u32,
+    num_layers: usize,                    // Layers to render this frame
+    layer_views_to_clip: Vec<glam::Mat4>, // World to clip of each layer, to skip meshes outside of it
+}
+
+impl ShadowMapRenderer {
+    pub fn new(device: &wgpu::Device, local_bind_group_layout: &wgpu::BindGroupLayout) -> Self {
+        let view_alignment = align_to(
+            size_of::<ShadowView>() as wgpu::BufferAddress,
+            device.limits().min_uniform_buffer_offset_alignment as wgpu::BufferAddress,
+        );
+        let view_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Shadow View Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<ShadowView>() as _),
+                    },
+                    count: None,
+                }],
+            });
+        let view_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadow View Uniform Buffer"),
+            size: view_alignment * MAX_SHADOW_LAYERS as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        });
+        let view_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow View Bind Group"),
+            layout: &view_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &view_uniform_buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(size_of::<ShadowView>() as _),
+                }),
+            }],
+        });
+        let view_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer for Shadow Views"),
+            size: (MAX_SHADOW_LAYERS * size_of::<ShadowView>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Uniform Buffer for Shadows"),
+            size: size_of::<ShadowUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow Map Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                include_str!("../../../assets/shaders/render_shadow.wgsl").into(),
+            ),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Map Pipeline Layout"),
+            bind_group_layouts: &[
+                &view_bind_group_layout, //group(0)
+                local_bind_group_layout, //group(1)
+            ],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Map Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: size_of::<RenderVertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &[wgpu::VertexAttribute {
+                        format: wgpu::VertexFormat::Float32x3,
+                        offset: 0,
+                        shader_location: 0,
+                    }],
+                }],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: None,
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: SHADOW_DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Map Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+        let map_size = 1;
+        let (texture, texture_view, layer_views) = create_shadow_texture(device, map_size, 1);
+        return Self {
+            pipeline,
+            view_alignment,
+            view_bind_group,
+            view_uniform_buffer,
+            view_buffer,
+            uniform_buffer,
+            texture,
+            texture_view,
+            layer_views,
+            sampler,
+            map_size,
+            num_layers: 0,
+            layer_views_to_clip: Vec::new(),
+        };
+    }
+
+    pub fn get_uniform_buffer(&self) -> &wgpu::Buffer {
+        &self.uniform_buffer
+    }
+
+    pub fn get_view_buffer(&self) -> &wgpu::Buffer {
+        &self.view_buffer
+    }
+
+    pub fn get_texture_view(&self) -> &wgpu::TextureView {
+        &self.texture_view
+    }
+
+    pub fn get_sampler(&self) -> &wgpu::Sampler {
+        &self.sampler
+    }
+
+    /// Computes the light views and returns the layers of every shadowed light,
+    /// keyed by the index in `light_items`.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        light_items: &[Arc<RenderItem>],
+        mesh_items: &[Arc<RenderItem>],
+        world_to_camera: &glam::Mat4,
+        camera_to_clip: &glam::Mat4,
+        settings: &ShadowSettings,
+    ) -> HashMap<usize, ShadowAssignment> {
+        self.num_layers = 0;
+        self.layer_views_to_clip.clear();
+        let mut assignments = HashMap::new();
+        let scene = get_scene_bounds(mesh_items);
+        if !settings.enabled || scene.is_none() {
+            return assignments;
+        }
+        let scene = scene.unwrap();
+        let cascade_count = settings.cascade_count.clamp(1, MAX_SHADOW_CASCADES) as usize;
+        let camera_position = world_to_camera.inverse().transform_point3(glam::Vec3::ZERO);
+        let casters = select_shadow_casters(light_items, camera_position, cascade_count, settings);
+        // Many point lights at a large size would not fit in memory, the layers get smaller instead
+        let num_layers = casters
+            .iter()
+            .map(|(_, caster)| caster.get_layer_count(cascade_count))
+            .sum();
+        let map_size = settings
+            .map_size
+            .clamp(1, device.limits().max_texture_dimension_2d);
+        let map_size = get_budget_map_size(map_size, num_layers);
+
+        let mut views = Vec::new();
+        let mut cascade_splits = [f32::MAX; 4];
+        for (index, caster) in casters {
+            let first_layer = views.len() as i32;
+            match caster {
+                ShadowCaster::Directional(direction) => {
+                    let (cascades, splits) = get_directional_views(
+                        direction,
+                        world_to_camera,
+                        camera_to_clip,
+                        &scene,
+                        cascade_count,
+                        map_size,
+                    );
+                    for (i, split) in splits.iter().enumerate() {
+                        cascade_splits[i] = *split;
+                    }
+                    views.extend(cascades);
+                }
+                ShadowCaster::Spot(position, direction, outer_angle) => {
+                    views.push(get_spot_view(
+                        position,
+                        direction,
+                        outer_angle,
+                        &scene,
+                        map_size,
+                    ));
+                }
+                ShadowCaster::Point(position) => {
+                    views.extend(get_point_views(position, &scene, map_size));
+                }
+            }
+            let layer_count = views.len() as i32 - first_layer;
+            assignments.insert(
+                index,
+                ShadowAssignment {
+                    first_layer,
+                    layer_count,
+                },
+            );
+        }
+        if views.is_empty() {
+            return assignments;
+        }
+
+        if self.map_size != map_size || self.layer_views.len() < views.len() {
+            let (texture, texture_view, layer_views) =
+                create_shadow_texture(device, map_size, views.len());
+            self.texture = texture;
+            self.texture_view = texture_view;
+            self.layer_views = layer_views;
+            self.map_size = map_size;
+        }
+
+        for (i, view) in views.iter().enumerate() {
+            let offset = i as wgpu::BufferAddress * self.view_alignment;
+            queue.write_buffer(&self.view_uniform_buffer, offset, bytemuck::bytes_of(view));
+        }
+        queue.write_buffer(&self.view_buffer, 0, bytemuck::cast_slice(&views));
+        let uniforms = ShadowUniforms {
+            cascade_splits,
+            params: [
+                1.0 / map_size as f32,
+                settings.pcf_radius as f32,
+                settings.depth_bias,
+                settings.normal_bias,
+            ],
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+        self.num_layers = views.len();
+        self.layer_views_to_clip = views
+            .iter()
+            .map(|view| glam::Mat4::from_cols_array_2d(&view.world_to_clip))
+            .collect();
+        return assignments;
+    }
+
+    /// Renders the shadow casters into every layer prepared this frame, skipping the ones outside of it.
+    /// `local_bind_group` holds the matrices of `mesh_items` in the same order.
+    pub fn paint(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        mesh_items: &[Arc<RenderItem>],
+        local_bind_group: &wgpu::BindGroup,
+        local_uniform_alignment: wgpu::BufferAddress,
+    ) {
+        for layer in 0..self.num_layers {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shadow Map Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.layer_views[layer],
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            rpass.set_pipeline(&self.pipeline);
+            let view_offset =
+                layer as wgpu::DynamicOffset * self.view_alignment as wgpu::DynamicOffset;
+            rpass.set_bind_group(0, &self.view_bind_group, &[view_offset]);
+            let world_to_clip = &self.layer_views_to_clip[layer];
+            for (item_index, item) in mesh_items.iter().enumerate() {
+                if let RenderItem::Mesh(mesh_item) = item.as_ref() {
+                    if !casts_shadow(item) {
+                        continue;
+                    }
+                    if !is_in_view(world_to_clip, &mesh_item.matrix, &mesh_item.mesh.bounds) {
+                        continue;
+                    }
+                    let local_uniform_offset = item_index as wgpu::DynamicOffset
+                        * local_uniform_alignment as wgpu::DynamicOffset;
+                    rpass.set_bind_group(1, local_bind_group, &[local_uniform_offset]);
+                    rpass.set_vertex_buffer(0, mesh_item.mesh.vertex_buffer.slice(..));
+                    rpass.set_index_buffer(
+                        mesh_item.mesh.index_buffer.slice(..),
+                        wgpu::IndexFormat::Uint32,
+                    );
+                    rpass.draw_indexed(0..mesh_item.mesh.index_count, 0, 0..1);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(view: &ShadowView, p: glam::Vec3) -> glam::Vec3 {
+        glam::Mat4::from_cols_array_2d(&view.world_to_clip).project_point3(p)
+    }
+
+    fn inside(ndc: glam::Vec3) -> bool {
+        ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0 && ndc.z >= 0.0 && ndc.z <= 1.0
+    }
+
+    #[test]
+    fn test_budget_map_size() {
+        // eight point lights do not get 4096 maps, a single directional light keeps its size
+        let map_size = get_budget_map_size(4096, 48);
+        assert!(map_size < 4096);
+        assert!(48 * map_size as u64 * map_size as u64 * 4 <= SHADOW_MEMORY_BUDGET);
+        assert_eq!(get_budget_map_size(1024, 4), 1024);
+        assert_eq!(get_budget_map_size(4096, 1000), MIN_SHADOW_MAP_SIZE);
+    }
+
+    #[test]
+    fn test_is_in_view() {
+        let scene = SceneBounds {
+            center: glam::Vec3::ZERO,
+            radius: 10.0,
+        };
+        let view = get_spot_view(glam::Vec3::ZERO, glam::Vec3::NEG_Z, 0.5, &scene, 512);
+        let world_to_clip = glam::Mat4::from_cols_array_2d(&view.world_to_clip);
+        let bounds = [[-0.5, -0.5, -0.5], [0.5, 0.5, 0.5]];
+        let in_front = glam::Mat4::from_translation(glam::vec3(0.0, 0.0, -5.0));
+        let behind = glam::Mat4::from_translation(glam::vec3(0.0, 0.0, 5.0));
+        let aside = glam::Mat4::from_translation(glam::vec3(8.0, 0.0, -2.0));
+        assert!(is_in_view(&world_to_clip, &in_front, &bounds));
+        assert!(!is_in_view(&world_to_clip, &behind, &bounds));
+        assert!(!is_in_view(&world_to_clip, &aside, &bounds));
+    }
+
+    #[test]
+    fn test_cascade_splits() {
+        let splits = get_cascade_splits(0.1, 100.0, 3, CASCADE_SPLIT_LAMBDA);
+        assert_eq!(splits.len(), 3);
+        assert!(splits[0] > 0.1);
+        assert!(splits[0] < splits[1] && splits[1] < splits[2]);
+        assert!((splits[2] - 100.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_point_views_cover_faces() {
+        let scene = SceneBounds {
+            center: glam::Vec3::ZERO,
+            radius: 10.0,
+        };
+        let views = get_point_views(glam::Vec3::ZERO, &scene, 512);
+        let axes = [
+            glam::Vec3::X,
+            glam::Vec3::NEG_X,
+            glam::Vec3::Y,
+            glam::Vec3::NEG_Y,
+            glam::Vec3::Z,
+            glam::Vec3::NEG_Z,
+        ];
+        for (face, axis) in axes.iter().enumerate() {
+            let ndc = project(&views[face], *axis * 2.0 + glam::vec3(0.3, 0.2, 0.1));
+            assert!(inside(ndc), "face {} {:?}", face, ndc);
+            let ndc = project(&views[face], -*axis * 2.0);
+            assert!(!inside(ndc));
+        }
+    }
+
+    #[test]
+    fn test_directional_views_cover_scene() {
+        let scene = SceneBounds {
+            center: glam::Vec3::ZERO,
+            radius: 5.0,
+        };
+        let world_to_camera =
+            glam::Mat4::look_at_rh(glam::vec3(0.0, 2.0, 10.0), glam::Vec3::ZERO, glam::Vec3::Y);
+        let camera_to_clip = glam::Mat4::perspective_rh(1.0, 1.5, 0.1, 1000.0);
+        let (views, splits) = get_directional_views(
+            glam::vec3(0.3, -1.0, 0.2),
+            &world_to_camera,
+            &camera_to_clip,
+            &scene,
+            3,
+            1024,
+        );
+        assert_eq!(views.len(), 3);
+        // the cascades end at the far side of the scene instead of the far plane
+        assert!(splits[2] < 20.0);
+        // a point in the middle of the view is inside the cascade its depth selects
+        let p = glam::vec3(0.0, 0.0, 0.0);
+        let depth = -world_to_camera.transform_point3(p).z;
+        let cascade = splits.iter().position(|s| depth <= *s).unwrap();
+        assert!(inside(project(&views[cascade], p)));
+    }
+}
@@ -4,6 +4,7 @@ use super::wire_mesh_renderer::WireMeshRenderer;
 use crate::model::base::Matrix4x4;
 use crate::model::scene::Node;
 use crate::render::render_mode::RenderMode;
+use crate::render::scene_overlays::SceneOverlays;
 use crate::render::wgpu::render_item::RenderItem;
 use std::sync::Arc;
 use std::sync::RwLock;
@@ -24,6 +25,7 @@ struct PerFrameCallback {
     node: Arc<RwLock<Node>>,
     world_to_camera: glam::Mat4,
     camera_to_clip: glam::Mat4,
+    overlays: SceneOverlays,
 }
 
 unsafe impl Send for PerFrameCallback {}
@@ -38,7 +40,8 @@ impl egui_wgpu::CallbackTrait for PerFrameCallback {
         encoder: &mut wgpu::CommandEncoder,
         resources: &mut egui_wgpu::CallbackResources,
     ) -> Vec<wgpu::CommandBuffer> {
-        let render_items = get_render_items(device, queue, &self.node, RenderMode::Wire);
+        let render_items =
+            get_render_items(device, queue, &self.node, RenderMode::Wire, &self.overlays);
         let num_items = render_items.len();
         if num_items == 0 {
             return vec![];
@@ -135,6 +138,7 @@ impl WireRenderer {
         node: &Arc<RwLock<Node>>,
         w2c: &Matrix4x4,
         c2c: &Matrix4x4,
+        overlays: &SceneOverlays,
     ) {
         let c2c = *c2c;
         let c2c = Matrix4x4::OPENGL_TO_WGPU_CLIP * c2c; // Convert to WGPU clip space
@@ -146,6 +150,7 @@ impl WireRenderer {
                 node: node.clone(),
                 world_to_camera: glam::Mat4::from(w2c),
                 camera_to_clip: glam::Mat4::from(c2c),
+                overlays: overlays.clone(),
             },
         ));
     }